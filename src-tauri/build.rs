@@ -1,3 +1,6 @@
 fn main() {
+    #[cfg(feature = "grpc")]
+    tonic_build::compile_protos("proto/analyzer.proto").expect("Failed to compile proto/analyzer.proto");
+
     tauri_build::build()
 }
@@ -0,0 +1,242 @@
+// A/B Testing Module - Persisted provider comparisons with human ratings
+// Every analyze_ab_test run is stored so win rates and latency deltas can be tracked over time
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+// Ratings that don't name a provider
+pub const RATING_TIE: &str = "tie";
+pub const RATING_NEITHER: &str = "neither";
+
+// One provider's side of a comparison
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ProviderOutcome {
+    pub provider: String,
+    pub success: bool,
+    pub latency_ms: u64,
+    pub result: Option<serde_json::Value>,
+    pub error: Option<String>,
+}
+
+// A stored comparison and its (optional) human judgment
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AbTestRecord {
+    pub test_id: String,
+    pub timestamp: String,
+    pub prompt: String,
+    pub total_time_ms: u64,
+    pub outcomes: Vec<ProviderOutcome>,
+    pub winner: Option<String>,
+    pub notes: Option<String>,
+    pub rated_at: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ProviderAbStats {
+    pub provider: String,
+    pub tests: usize,
+    pub successes: usize,
+    pub success_rate: f64,
+    pub mean_latency_ms: f64,
+    // Average of (this provider's latency - the other providers' latency) in the same test
+    pub mean_latency_delta_ms: f64,
+    pub wins: usize,
+    pub win_rate: f64,  // Wins over rated tests this provider took part in
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct DailyProviderStats {
+    pub date: String,
+    pub provider: String,
+    pub tests: usize,
+    pub wins: usize,
+    pub mean_latency_ms: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AbStats {
+    pub total_tests: usize,
+    pub rated_tests: usize,
+    pub ties: usize,
+    pub neither: usize,
+    pub providers: Vec<ProviderAbStats>,
+    pub daily: Vec<DailyProviderStats>,
+}
+
+// A winner must be one of the compared providers, "tie" or "neither"
+pub fn validate_winner(winner: &str, record: &AbTestRecord) -> Result<(), String> {
+    if winner == RATING_TIE || winner == RATING_NEITHER {
+        return Ok(());
+    }
+    if record.outcomes.iter().any(|o| o.provider == winner) {
+        return Ok(());
+    }
+
+    let providers: Vec<&str> = record.outcomes.iter().map(|o| o.provider.as_str()).collect();
+    Err(format!(
+        "Invalid winner '{}' - expected one of {:?}, '{}' or '{}'",
+        winner, providers, RATING_TIE, RATING_NEITHER
+    ))
+}
+
+#[derive(Default)]
+struct Accumulator {
+    tests: usize,
+    successes: usize,
+    latency_sum: f64,
+    delta_sum: f64,
+    delta_count: usize,
+    rated: usize,
+    wins: usize,
+}
+
+fn mean(sum: f64, count: usize) -> f64 {
+    if count == 0 { 0.0 } else { sum / count as f64 }
+}
+
+pub fn compute_stats(records: &[AbTestRecord]) -> AbStats {
+    let mut per_provider: BTreeMap<String, Accumulator> = BTreeMap::new();
+    let mut per_day: BTreeMap<(String, String), (usize, usize, f64, usize)> = BTreeMap::new();
+    let mut rated_tests = 0;
+    let mut ties = 0;
+    let mut neither = 0;
+
+    for record in records {
+        let date = record.timestamp.get(..10).unwrap_or("").to_string();
+
+        match record.winner.as_deref() {
+            Some(RATING_TIE) => ties += 1,
+            Some(RATING_NEITHER) => neither += 1,
+            _ => {}
+        }
+        if record.winner.is_some() {
+            rated_tests += 1;
+        }
+
+        let successful: Vec<&ProviderOutcome> = record.outcomes.iter().filter(|o| o.success).collect();
+
+        for outcome in &record.outcomes {
+            let won = record.winner.as_deref() == Some(outcome.provider.as_str());
+            let stats = per_provider.entry(outcome.provider.clone()).or_default();
+            stats.tests += 1;
+            if record.winner.is_some() {
+                stats.rated += 1;
+            }
+            if won {
+                stats.wins += 1;
+            }
+
+            let day = per_day.entry((date.clone(), outcome.provider.clone())).or_insert((0, 0, 0.0, 0));
+            day.0 += 1;
+            if won {
+                day.1 += 1;
+            }
+
+            if !outcome.success {
+                continue;
+            }
+            stats.successes += 1;
+            stats.latency_sum += outcome.latency_ms as f64;
+            day.2 += outcome.latency_ms as f64;
+            day.3 += 1;
+
+            // Paired delta against the other providers that succeeded on the same frame
+            let others: Vec<f64> = successful
+                .iter()
+                .filter(|o| o.provider != outcome.provider)
+                .map(|o| o.latency_ms as f64)
+                .collect();
+            if !others.is_empty() {
+                let others_mean = others.iter().sum::<f64>() / others.len() as f64;
+                stats.delta_sum += outcome.latency_ms as f64 - others_mean;
+                stats.delta_count += 1;
+            }
+        }
+    }
+
+    AbStats {
+        total_tests: records.len(),
+        rated_tests,
+        ties,
+        neither,
+        providers: per_provider
+            .into_iter()
+            .map(|(provider, s)| ProviderAbStats {
+                provider,
+                tests: s.tests,
+                successes: s.successes,
+                success_rate: mean(s.successes as f64, s.tests),
+                mean_latency_ms: mean(s.latency_sum, s.successes),
+                mean_latency_delta_ms: mean(s.delta_sum, s.delta_count),
+                wins: s.wins,
+                win_rate: mean(s.wins as f64, s.rated),
+            })
+            .collect(),
+        daily: per_day
+            .into_iter()
+            .map(|((date, provider), (tests, wins, latency_sum, latency_count))| DailyProviderStats {
+                date,
+                provider,
+                tests,
+                wins,
+                mean_latency_ms: mean(latency_sum, latency_count),
+            })
+            .collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn outcome(provider: &str, latency_ms: u64, success: bool) -> ProviderOutcome {
+        ProviderOutcome {
+            provider: provider.to_string(),
+            success,
+            latency_ms,
+            result: None,
+            error: None,
+        }
+    }
+
+    fn record(winner: Option<&str>, llava_ms: u64, moondream_ms: u64) -> AbTestRecord {
+        AbTestRecord {
+            test_id: uuid::Uuid::new_v4().to_string(),
+            timestamp: "2025-03-12T10:00:00.000Z".to_string(),
+            prompt: "describe".to_string(),
+            total_time_ms: llava_ms.max(moondream_ms),
+            outcomes: vec![outcome("llava", llava_ms, true), outcome("moondream", moondream_ms, true)],
+            winner: winner.map(|w| w.to_string()),
+            notes: None,
+            rated_at: None,
+        }
+    }
+
+    #[test]
+    fn test_win_rates_and_latency_deltas() {
+        let records = vec![
+            record(Some("moondream"), 3000, 1000),
+            record(Some("llava"), 2000, 1000),
+            record(Some("moondream"), 4000, 1000),
+            record(None, 3000, 1000),
+        ];
+
+        let stats = compute_stats(&records);
+        let moondream = stats.providers.iter().find(|p| p.provider == "moondream").unwrap();
+        let llava = stats.providers.iter().find(|p| p.provider == "llava").unwrap();
+
+        assert_eq!(stats.rated_tests, 3);
+        assert_eq!(moondream.wins, 2);
+        assert!((moondream.win_rate - 2.0 / 3.0).abs() < 1e-9);
+        assert_eq!(llava.mean_latency_delta_ms, 2000.0);
+        assert_eq!(moondream.mean_latency_delta_ms, -2000.0);
+    }
+
+    #[test]
+    fn test_winner_validation() {
+        let record = record(None, 1000, 1000);
+        assert!(validate_winner("llava", &record).is_ok());
+        assert!(validate_winner(RATING_TIE, &record).is_ok());
+        assert!(validate_winner("gpt-4o", &record).is_err());
+    }
+}
@@ -0,0 +1,187 @@
+// Alerting Module - What alert channels (speech, desktop, chime, push) share
+// Channels follow the store's live events on their own and only hear about alerts whose incident still needs attention
+
+use crate::event_store::StoredEvent;
+use crate::event_stream::{priority_rank, PRIORITIES};
+use chrono::NaiveTime;
+use serde::{Deserialize, Serialize};
+use tokio::process::Command;
+use tokio::sync::broadcast::{self, error::RecvError};
+
+// An alert as channels see it
+#[derive(Debug, Clone, PartialEq)]
+pub struct AlertNotice {
+    pub id: String,
+    pub camera_id: Option<String>,
+    pub description: String,
+    pub kind: String,
+    pub priority: String,
+    pub rule: Option<String>,  // Set for alerts from user rules
+    pub box_count: usize,  // Boxes that made a rule fire
+    pub incident_id: Option<String>,
+}
+
+impl AlertNotice {
+    // None for other events, and for alerts whose incident was acknowledged or snoozed
+    pub fn from_event(event: &StoredEvent) -> Option<Self> {
+        if event.event_type != "alert" {
+            return None;
+        }
+        let payload = event.payload.clone().unwrap_or_default();
+        if payload["notify"] == serde_json::Value::Bool(false) {
+            return None;
+        }
+        Some(AlertNotice {
+            id: event.id.clone(),
+            camera_id: event.camera_id.clone(),
+            description: event.description.clone().unwrap_or_default(),
+            kind: payload["kind"].as_str().unwrap_or("alert").to_string(),
+            priority: payload["priority"].as_str().filter(|p| priority_rank(p).is_some()).unwrap_or("medium").to_string(),
+            rule: payload["rule"].as_str().map(str::to_string),
+            box_count: payload["boxes"].as_array().map_or(0, |boxes| boxes.len()),
+            incident_id: payload["incident_id"].as_str().map(str::to_string),
+        })
+    }
+
+    pub fn at_least(&self, min_priority: &str) -> bool {
+        match (priority_rank(&self.priority), priority_rank(min_priority)) {
+            (Some(rank), Some(min)) => rank <= min,
+            _ => true,
+        }
+    }
+
+    // "High alert on front door"
+    pub fn title(&self) -> String {
+        let mut priority = self.priority.clone();
+        if let Some(first) = priority.get_mut(0..1) {
+            first.make_ascii_uppercase();
+        }
+        match &self.camera_id {
+            Some(camera) => format!("{} alert on {}", priority, camera),
+            None => format!("{} alert", priority),
+        }
+    }
+
+    // Fill {rule}, {camera}, {priority}, {kind}, {count} and {description} in a message template
+    pub fn render(&self, template: &str) -> String {
+        template
+            .replace("{rule}", self.rule.as_deref().unwrap_or(&self.kind))
+            .replace("{camera}", &self.camera_id.as_deref().unwrap_or("the camera").replace(['-', '_'], " "))
+            .replace("{priority}", &self.priority)
+            .replace("{kind}", &self.kind)
+            .replace("{count}", &self.box_count.to_string())
+            .replace("{description}", &self.description)
+    }
+}
+
+pub fn validate_priority(priority: &str) -> Result<(), String> {
+    if priority_rank(priority).is_none() {
+        return Err(format!("Unknown priority '{}' (use {})", priority, PRIORITIES.join(", ")));
+    }
+    Ok(())
+}
+
+// Whether a channel with a priority floor and quiet hours passes an alert on at this local time
+pub fn passes(notice: &AlertNotice, min_priority: &str, quiet_hours: Option<&QuietHours>, time: NaiveTime) -> bool {
+    notice.at_least(min_priority) && !quiet_hours.is_some_and(|q| q.contains(time))
+}
+
+// Next alert a channel should act on; None once the store has shut down
+pub async fn next_notice(receiver: &mut broadcast::Receiver<StoredEvent>, channel: &str) -> Option<AlertNotice> {
+    loop {
+        match receiver.recv().await {
+            Ok(event) => {
+                if let Some(notice) = AlertNotice::from_event(&event) {
+                    return Some(notice);
+                }
+            }
+            Err(RecvError::Lagged(skipped)) => eprintln!("{} skipped {} events", channel, skipped),
+            Err(RecvError::Closed) => return None,
+        }
+    }
+}
+
+// Run host commands in turn until one succeeds, e.g. the speech engines a Linux desktop may have
+pub async fn run_first(commands: Vec<Command>, what: &str) -> Result<(), String> {
+    let mut last_error = format!("No {} for this platform", what);
+    for mut command in commands {
+        match command.status().await {
+            Ok(status) if status.success() => return Ok(()),
+            Ok(status) => last_error = format!("{} exited with {}", what, status),
+            Err(e) => last_error = format!("No {} found: {}", what, e),
+        }
+    }
+    Err(last_error)
+}
+
+fn parse_time(time: &str) -> Result<NaiveTime, String> {
+    NaiveTime::parse_from_str(time, "%H:%M").map_err(|_| format!("Invalid quiet hours time '{}' (use HH:MM)", time))
+}
+
+// Local times a channel stays silent, e.g. 22:00-07:00 across midnight
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct QuietHours {
+    pub start: String,
+    pub end: String,
+}
+
+impl QuietHours {
+    pub fn validate(&self) -> Result<(), String> {
+        if parse_time(&self.start)? == parse_time(&self.end)? {
+            return Err("Quiet hours need different start and end times".to_string());
+        }
+        Ok(())
+    }
+
+    pub fn contains(&self, time: NaiveTime) -> bool {
+        match (parse_time(&self.start), parse_time(&self.end)) {
+            (Ok(start), Ok(end)) if start <= end => time >= start && time < end,
+            (Ok(start), Ok(end)) => time >= start || time < end,
+            _ => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn alert(payload: serde_json::Value) -> StoredEvent {
+        StoredEvent {
+            id: "a1".to_string(),
+            timestamp: "2026-01-01T00:00:00.000Z".to_string(),
+            camera_id: Some("register-2".to_string()),
+            event_type: "alert".to_string(),
+            person_count: None,
+            object_counts: None,
+            provider: Some("rules".to_string()),
+            prompt: None,
+            description: Some("Rule 'Queue' triggered on register-2".to_string()),
+            payload: Some(payload),
+        }
+    }
+
+    #[test]
+    fn test_notices_skip_handled_incidents() {
+        let notice = AlertNotice::from_event(&alert(serde_json::json!({
+            "kind": "rule", "priority": "high", "rule": "Queue", "boxes": [{}, {}, {}], "notify": true
+        })))
+        .unwrap();
+        assert_eq!(notice.render("{count} people queueing at {camera}"), "3 people queueing at register 2");
+        assert!(notice.at_least("medium") && !notice.at_least("critical"));
+
+        assert!(AlertNotice::from_event(&alert(serde_json::json!({ "kind": "rule", "notify": false }))).is_none());
+        let detection = StoredEvent { event_type: "detection".to_string(), ..alert(serde_json::json!({})) };
+        assert!(AlertNotice::from_event(&detection).is_none());
+    }
+
+    #[test]
+    fn test_quiet_hours() {
+        let at = |h, m| NaiveTime::from_hms_opt(h, m, 0).unwrap();
+        let night = QuietHours { start: "22:00".to_string(), end: "07:00".to_string() };
+        assert!(night.contains(at(23, 15)) && night.contains(at(6, 59)));
+        assert!(!night.contains(at(7, 0)) && !night.contains(at(12, 0)));
+        assert!(QuietHours { start: "8:00".to_string(), end: "8:00".to_string() }.validate().is_err());
+        assert!(QuietHours { start: "25:00".to_string(), end: "07:00".to_string() }.validate().is_err());
+    }
+}
@@ -0,0 +1,259 @@
+// ANPR Module - Automatic number plate recognition on live frames
+// Plate (or vehicle) boxes are cropped and read by an OCR-capable vision provider; reads are stored and checked against allow/block lists
+
+use crate::bus::BusEvent;
+use crate::event_store::{self, format_timestamp, PlateRead};
+use crate::frame_processor::{self, RegionOfInterest};
+use crate::yolo_detector::{BoundingBox, DetectionData};
+use crate::{pipeline, settings, verification, AppState};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::time::{Duration, Instant};
+
+// Detector classes read directly; vehicles are read whole when the model has no plate class
+const PLATE_CLASSES: [&str; 2] = ["license_plate", "license plate"];
+const VEHICLE_CLASSES: [&str; 4] = ["car", "truck", "bus", "motorcycle"];
+
+const PROMPT: &str = "Read the vehicle license plate in this image. Reply with the plate characters only \
+(letters and digits, no spaces), or NONE if no plate is readable.";
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct AnprSettings {
+    pub enabled: bool,
+    pub provider: String,        // Vision provider used as the OCR model
+    pub interval_secs: u64,      // Minimum time between reads of the same camera
+    pub dedupe_secs: u64,        // The same plate on the same camera is stored once per window
+    pub allowlist: Vec<String>,
+    pub blocklist: Vec<String>,
+    pub alert_unlisted: bool,    // Alert on plates missing from a non-empty allowlist
+}
+
+impl Default for AnprSettings {
+    fn default() -> Self {
+        AnprSettings {
+            enabled: false,
+            provider: "llava".to_string(),
+            interval_secs: 5,
+            dedupe_secs: 60,
+            allowlist: Vec::new(),
+            blocklist: Vec::new(),
+            alert_unlisted: false,
+        }
+    }
+}
+
+impl AnprSettings {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.interval_secs == 0 {
+            return Err("interval_secs must be at least 1".to_string());
+        }
+        if let Some(entry) = self.allowlist.iter().chain(&self.blocklist).find(|p| normalize_plate(p).is_none()) {
+            return Err(format!("'{}' is not a valid plate", entry));
+        }
+        Ok(())
+    }
+
+    pub fn list_status(&self, plate: &str) -> ListStatus {
+        let listed = |list: &[String]| list.iter().any(|p| normalize_plate(p).as_deref() == Some(plate));
+        if listed(&self.blocklist) {
+            ListStatus::Blocked
+        } else if listed(&self.allowlist) {
+            ListStatus::Allowed
+        } else {
+            ListStatus::Unlisted
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ListStatus {
+    Allowed,
+    Blocked,
+    Unlisted,
+}
+
+impl ListStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ListStatus::Allowed => "allowed",
+            ListStatus::Blocked => "blocked",
+            ListStatus::Unlisted => "unlisted",
+        }
+    }
+}
+
+// Uppercase letters and digits only, so "ab-12 cd" and "AB12CD" are the same plate
+pub fn normalize_plate(text: &str) -> Option<String> {
+    let plate: String = text.chars().filter(|c| c.is_ascii_alphanumeric()).map(|c| c.to_ascii_uppercase()).collect();
+    if plate == "NONE" || !(2..=10).contains(&plate.len()) {
+        return None;
+    }
+    Some(plate)
+}
+
+// Per-camera pacing plus recently stored plates
+#[derive(Default)]
+pub struct AnprMonitor {
+    last_check: HashMap<String, Instant>,
+    running: HashSet<String>,
+    recent: HashMap<(String, String), Instant>,
+}
+
+async fn read_plate(
+    state: &AppState,
+    config: &AnprSettings,
+    camera_id: Option<&str>,
+    frame_base64: &str,
+    bbox: &BoundingBox,
+) -> Result<(), String> {
+    let roi = RegionOfInterest {
+        x1: bbox.x1,
+        y1: bbox.y1,
+        x2: bbox.x2,
+        y2: bbox.y2,
+        padding: Some(0.05),
+    };
+    let crop = frame_processor::crop_to_roi(frame_base64, &roi)?;
+    let start = Instant::now();
+    let result = pipeline::describe_frame(&state.moondream, &state.providers, &config.provider, PROMPT, crop).await;
+    state.bus.publish(BusEvent::analysis(&config.provider, start.elapsed(), result.is_ok()));
+    let (answer, _) = result?.unwrap_or_default();
+    let plate = match answer.lines().next().and_then(normalize_plate) {
+        Some(plate) => plate,
+        None => return Ok(()),
+    };
+
+    let camera = camera_id.unwrap_or("").to_string();
+    {
+        let mut monitor = state.anpr.lock().await;
+        let now = Instant::now();
+        let window = Duration::from_secs(config.dedupe_secs);
+        monitor.recent.retain(|_, at| now.duration_since(*at) < window);
+        if monitor.recent.insert((camera, plate.clone()), now).is_some() {
+            return Ok(());
+        }
+    }
+
+    let status = config.list_status(&plate);
+    let priority = match status {
+        ListStatus::Blocked => Some("high"),
+        ListStatus::Unlisted if config.alert_unlisted && !config.allowlist.is_empty() => Some("medium"),
+        _ => None,
+    };
+
+    let alert = match priority {
+        Some(priority) => {
+            let label = if status == ListStatus::Blocked { "Blocklisted" } else { "Unlisted" };
+            let description = match camera_id {
+                Some(camera) => format!("{} plate {} on {}", label, plate, camera),
+                None => format!("{} plate {}", label, plate),
+            };
+            let mut payload = serde_json::json!({
+                "kind": "anpr",
+                "priority": priority,
+                "plate": plate,
+                "list": status,
+                "bbox": bbox,
+            });
+            // Confirmed only by a second read of the same plate
+            let second_look = verification::verify(state, camera_id, &config.provider, PROMPT, frame_base64, Some(&roi), priority, |text, _| {
+                text.lines().next().and_then(normalize_plate).as_deref() == Some(plate.as_str())
+            })
+            .await;
+            verification::attach(&mut payload, second_look);
+            Some((description, payload))
+        }
+        None => None,
+    };
+
+    let alert_id = match alert {
+        Some((description, payload)) => {
+            let alert_id = event_store::record_alert(&state.events, camera_id, &config.provider, &description, payload, frame_base64).await?;
+            println!("🚗 {}", description);
+            Some(alert_id)
+        }
+        None => None,
+    };
+
+    state.events.lock().await.record_plate_read(&PlateRead {
+        read_at: format_timestamp(chrono::Utc::now()),
+        camera_id: camera_id.map(|c| c.to_string()),
+        plate,
+        list: status.as_str().to_string(),
+        alert_id,
+    })
+}
+
+async fn read_plates(state: AppState, config: AnprSettings, camera_id: Option<String>, frame: String, boxes: Vec<BoundingBox>) {
+    for bbox in &boxes {
+        if let Err(e) = read_plate(&state, &config, camera_id.as_deref(), &frame, bbox).await {
+            eprintln!("Plate read failed: {}", e);
+        }
+    }
+    state.anpr.lock().await.running.remove(camera_id.as_deref().unwrap_or(""));
+}
+
+// Plate boxes when the detector has them, otherwise whole vehicles
+fn plate_candidates(detection: &DetectionData) -> Vec<BoundingBox> {
+    let of = |classes: &[&str]| -> Vec<BoundingBox> {
+        detection.boxes.iter().filter(|b| classes.contains(&b.class_name.as_str())).cloned().collect()
+    };
+    let plates = of(&PLATE_CLASSES);
+    if plates.is_empty() { of(&VEHICLE_CLASSES) } else { plates }
+}
+
+// Start a background read of this frame's plates when the camera is due
+pub async fn watch(state: &AppState, camera_id: Option<&str>, frame_base64: &str, detection: &DetectionData) {
+    let config = settings::current().anpr;
+    if !config.enabled {
+        return;
+    }
+    let boxes = plate_candidates(detection);
+    if boxes.is_empty() {
+        return;
+    }
+
+    let camera = camera_id.unwrap_or("").to_string();
+    {
+        let mut monitor = state.anpr.lock().await;
+        let due = monitor
+            .last_check
+            .get(&camera)
+            .map(|at| at.elapsed() >= Duration::from_secs(config.interval_secs))
+            .unwrap_or(true);
+        if !due || monitor.running.contains(&camera) {
+            return;
+        }
+        monitor.last_check.insert(camera.clone(), Instant::now());
+        monitor.running.insert(camera);
+    }
+
+    let state = state.clone();
+    let camera_id = camera_id.map(|c| c.to_string());
+    tokio::spawn(read_plates(state, config, camera_id, frame_base64.to_string(), boxes));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_plate_normalization_and_lists() {
+        assert_eq!(normalize_plate("ab-12 cd").as_deref(), Some("AB12CD"));
+        assert_eq!(normalize_plate("NONE"), None);
+        assert_eq!(normalize_plate("The plate is not readable in this image"), None);
+
+        let config = AnprSettings {
+            allowlist: vec!["AB 12 CD".to_string()],
+            blocklist: vec!["xyz-987".to_string()],
+            ..Default::default()
+        };
+        assert!(config.validate().is_ok());
+        assert_eq!(config.list_status("AB12CD"), ListStatus::Allowed);
+        assert_eq!(config.list_status("XYZ987"), ListStatus::Blocked);
+        assert_eq!(config.list_status("QQ11"), ListStatus::Unlisted);
+        assert!(AnprSettings { blocklist: vec!["-".to_string()], ..config }.validate().is_err());
+    }
+}
@@ -0,0 +1,114 @@
+// API Keys Module - Named keys for the REST, WebSocket and gRPC servers, each with a role
+// Viewers read events and rules, operators also submit frames and triggers, admins can change configuration
+
+use crate::api_server::key_matches;
+use crate::event_store::format_timestamp;
+use crate::settings::{self, AppSettings};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum Role {
+    Viewer,
+    Operator,
+    Admin,
+}
+
+impl Role {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Role::Viewer => "viewer",
+            Role::Operator => "operator",
+            Role::Admin => "admin",
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ApiKey {
+    pub id: String,
+    pub name: String,  // e.g. "Lobby dashboard"
+    pub role: Role,
+    pub key_hash: String,  // SHA-256 of the key; the key itself is only shown once
+    pub prefix: String,  // First characters, to tell keys apart in the list
+    pub created_at: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CreatedApiKey {
+    pub key: String,
+    pub info: ApiKey,
+}
+
+fn hash(key: &str) -> String {
+    Sha256::digest(key.as_bytes()).iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+// The app's own key (settings.api_key) is always admin
+pub fn role_for(given: &str, settings: &AppSettings) -> Option<Role> {
+    if settings.api_key.as_deref().is_some_and(|key| key_matches(given, key)) {
+        return Some(Role::Admin);
+    }
+    let given = hash(given);
+    settings.api_keys.iter().find(|k| key_matches(&given, &k.key_hash)).map(|k| k.role)
+}
+
+// Checked against the saved settings on every request, so a revoked key stops working straight away
+pub fn allows(given: Option<&str>, required: Role) -> bool {
+    given.and_then(|g| role_for(g, &settings::current())).is_some_and(|role| role >= required)
+}
+
+pub fn create(name: &str, role: Role) -> Result<CreatedApiKey, String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("API key name is required".to_string());
+    }
+    if settings::current().api_keys.iter().any(|k| k.name == name) {
+        return Err(format!("An API key named '{}' already exists", name));
+    }
+    let key = format!("lva_{}", uuid::Uuid::new_v4().simple());
+    let info = ApiKey {
+        id: uuid::Uuid::new_v4().to_string(),
+        name: name.to_string(),
+        role,
+        key_hash: hash(&key),
+        prefix: key[..8].to_string(),
+        created_at: format_timestamp(chrono::Utc::now()),
+    };
+    settings::update(|s| s.api_keys.push(info.clone()))?;
+    Ok(CreatedApiKey { key, info })
+}
+
+pub fn revoke(id: &str) -> Result<bool, String> {
+    let before = settings::current().api_keys.len();
+    let saved = settings::update(|s| s.api_keys.retain(|k| k.id != id))?;
+    Ok(saved.api_keys.len() < before)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_roles_resolve_from_keys() {
+        let viewer = "lva_dashboard";
+        let mut settings = AppSettings { api_key: Some("app-key".to_string()), ..Default::default() };
+        settings.api_keys.push(ApiKey {
+            id: "1".to_string(),
+            name: "Lobby dashboard".to_string(),
+            role: Role::Viewer,
+            key_hash: hash(viewer),
+            prefix: viewer[..8].to_string(),
+            created_at: String::new(),
+        });
+
+        assert_eq!(role_for("app-key", &settings), Some(Role::Admin));
+        assert_eq!(role_for(viewer, &settings), Some(Role::Viewer));
+        assert_eq!(role_for("lva_unknown", &settings), None);
+        assert!(Role::Viewer < Role::Operator && Role::Operator < Role::Admin);
+
+        // Only hashes are saved
+        assert!(!serde_json::to_string(&settings.api_keys).unwrap().contains(viewer));
+    }
+}
@@ -0,0 +1,307 @@
+// API Server Module - Optional REST API so other applications on the LAN can use the analyzer
+// /health is open; everything else, including /trigger and the /ws stream, needs an API key (Bearer token or X-API-Key) with a high enough role
+
+use crate::api_keys::{self, Role};
+use crate::audit::{self, AuditAction, AuditEntry};
+use crate::bus::BusEvent;
+use crate::event_store::EventFilter;
+use crate::event_stream::{self, StreamFilter};
+use crate::trigger::{self, TriggerRequest, TriggerResult};
+use crate::rules::{self, Rule};
+use crate::{dewarp, enhance, frame_processor, local_only, pause, pipeline, settings, slo, AppState};
+use axum::extract::ws::WebSocketUpgrade;
+use axum::extract::{DefaultBodyLimit, Path, Query, Request, State};
+use axum::http::{HeaderMap, StatusCode};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post, put};
+use axum::{Json, Router};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tokio::net::TcpListener;
+
+pub const DEFAULT_API_PORT: u16 = 8787;
+
+// Base64 frames from 4K cameras run to a few MB
+const MAX_BODY_BYTES: usize = 25 * 1024 * 1024;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ApiServerInfo {
+    pub url: String,
+    pub api_key: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct DetectBody {
+    frame_base64: String,
+    camera_id: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct AnalyzeBody {
+    frame_base64: String,
+    prompt: Option<String>,
+    provider: Option<String>,
+    camera_id: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct StreamQuery {
+    topics: Option<String>,
+    camera: Option<String>,
+    priority: Option<String>,
+    api_key: Option<String>,  // Browsers can't set headers on a WebSocket handshake
+}
+
+struct ApiError(StatusCode, String);
+
+impl ApiError {
+    fn bad_request(message: String) -> Self {
+        ApiError(StatusCode::BAD_REQUEST, message)
+    }
+
+    fn unavailable(message: String) -> Self {
+        ApiError(StatusCode::SERVICE_UNAVAILABLE, message)
+    }
+}
+
+impl From<String> for ApiError {
+    fn from(message: String) -> Self {
+        ApiError(StatusCode::INTERNAL_SERVER_ERROR, message)
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        (self.0, Json(serde_json::json!({ "error": self.1 }))).into_response()
+    }
+}
+
+// The stored key, generating and persisting one on first use
+pub fn api_key() -> Result<String, String> {
+    if let Some(key) = settings::current().api_key {
+        return Ok(key);
+    }
+    let key = uuid::Uuid::new_v4().simple().to_string();
+    settings::update(|s| s.api_key = Some(key.clone()))?;
+    Ok(key)
+}
+
+fn presented_key(headers: &HeaderMap) -> Option<&str> {
+    let bearer = headers
+        .get("authorization")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "));
+    bearer.or_else(|| headers.get("x-api-key").and_then(|v| v.to_str().ok()))
+}
+
+// Compare without stopping at the first differing byte
+pub fn key_matches(given: &str, key: &str) -> bool {
+    given.len() == key.len() && given.bytes().zip(key.bytes()).fold(0u8, |acc, (a, b)| acc | (a ^ b)) == 0
+}
+
+pub fn authorized(headers: &HeaderMap, required: Role) -> bool {
+    api_keys::allows(presented_key(headers), required)
+}
+
+fn unauthorized(required: Role) -> ApiError {
+    ApiError(StatusCode::UNAUTHORIZED, format!("Missing or invalid API key (needs {} role)", required.as_str()))
+}
+
+async fn require_role(State(required): State<Role>, request: Request, next: Next) -> Response {
+    if authorized(request.headers(), required) {
+        next.run(request).await
+    } else {
+        unauthorized(required).into_response()
+    }
+}
+
+async fn health(State(state): State<AppState>) -> Json<serde_json::Value> {
+    let backend = state.backend_ready.lock().await.clone();
+    Json(serde_json::json!({
+        "status": "ok",
+        "version": env!("CARGO_PKG_VERSION"),
+        "backend": backend
+    }))
+}
+
+async fn detect(
+    State(state): State<AppState>,
+    Json(body): Json<DetectBody>,
+) -> Result<Json<crate::yolo_detector::DetectionData>, ApiError> {
+    pause::check().map_err(ApiError::unavailable)?;
+    let frame = frame_processor::prepare_frame(body.frame_base64, None)
+        .and_then(|frame| dewarp::for_camera(frame, body.camera_id.as_deref()))
+        .map_err(ApiError::bad_request)?;
+    let detect_frame = enhance::for_detection(&frame, body.camera_id.as_deref()).map_err(ApiError::bad_request)?;
+    state.bus.publish(BusEvent::FrameCaptured);
+    let start_time = std::time::Instant::now();
+    let mut detection = state.yolo.detect(&detect_frame).await.inspect_err(|_| {
+        state.metrics.record_error("detection");
+    })?;
+    let latency = start_time.elapsed();
+    crate::run_live_analytics(&state, body.camera_id.as_deref(), &frame, &mut detection).await;
+
+    state.bus.publish(BusEvent::DetectionReady {
+        camera_id: body.camera_id,
+        detection: Arc::new(detection.clone()),
+        latency,
+        live: true,
+        snapshot: None,
+    });
+    Ok(Json(detection))
+}
+
+async fn analyze(
+    State(state): State<AppState>,
+    Json(body): Json<AnalyzeBody>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    pause::check().map_err(ApiError::unavailable)?;
+    let frame = frame_processor::prepare_frame(body.frame_base64, None).map_err(ApiError::bad_request)?;
+    let provider = body.provider.unwrap_or_else(slo::default_provider);
+    let prompt = body
+        .prompt
+        .unwrap_or_else(|| crate::benchmark::DEFAULT_BENCHMARK_PROMPT.to_string());
+    pipeline::ensure_provider_ready(&state.providers, &provider)
+        .await
+        .map_err(ApiError::unavailable)?;
+
+    let _in_flight = state.metrics.track_in_flight();
+    let start_time = std::time::Instant::now();
+    let result = pipeline::describe_frame(&state.moondream, &state.providers, &provider, &prompt, frame).await;
+    state.bus.publish(BusEvent::analysis(&provider, start_time.elapsed(), result.is_ok()));
+    let (description, analysis) = result?.unwrap_or_default();
+
+    let event_id = crate::store_camera_analysis(
+        &state,
+        body.camera_id.as_deref(),
+        &provider,
+        &prompt,
+        &description,
+        Some(analysis.clone()),
+    )
+    .await;
+    Ok(Json(serde_json::json!({
+        "event_id": event_id,
+        "provider": provider,
+        "description": description,
+        "result": analysis,
+        "processing_time_ms": start_time.elapsed().as_millis() as u64
+    })))
+}
+
+async fn events(
+    State(state): State<AppState>,
+    Query(filter): Query<EventFilter>,
+) -> Result<Json<Vec<crate::event_store::StoredEvent>>, ApiError> {
+    let events = state.events.lock().await.list_events(&filter)?;
+    audit::record(AuditEntry::new(AuditAction::Access, format!("{} events", events.len()), "GET /events"));
+    Ok(Json(events))
+}
+
+// Capture and analyze for an external system, e.g. a door sensor or POS webhook
+async fn trigger_analysis(
+    State(state): State<AppState>,
+    Json(body): Json<TriggerRequest>,
+) -> Result<Json<TriggerResult>, ApiError> {
+    body.validate().map_err(ApiError::bad_request)?;
+    Ok(Json(trigger::run(&state, body).await?))
+}
+
+async fn trigger_events(
+    State(state): State<AppState>,
+    Path(external_id): Path<String>,
+) -> Result<Json<Vec<crate::event_store::StoredEvent>>, ApiError> {
+    let events = state.events.lock().await.trigger_events(&external_id)?;
+    audit::record(AuditEntry::new(AuditAction::Access, format!("{} events", events.len()), format!("GET /triggers/{}", external_id)));
+    Ok(Json(events))
+}
+
+async fn get_rules() -> Json<Vec<Rule>> {
+    Json(settings::current().rules)
+}
+
+// Same checks as the set_rules command
+async fn put_rules(State(state): State<AppState>, Json(body): Json<Vec<Rule>>) -> Result<Json<Vec<Rule>>, ApiError> {
+    rules::validate(&body, &settings::current().zones).map_err(ApiError::bad_request)?;
+    settings::update(|s| s.rules = body.clone())?;
+    state.rules.lock().await.reset_timers();
+    Ok(Json(body))
+}
+
+// Live events; `?topics=alert&camera=front-door&priority=critical` narrows what this connection receives
+async fn stream(
+    State(state): State<AppState>,
+    Query(query): Query<StreamQuery>,
+    headers: HeaderMap,
+    upgrade: WebSocketUpgrade,
+) -> Result<Response, ApiError> {
+    if !api_keys::allows(query.api_key.as_deref(), Role::Viewer) && !authorized(&headers, Role::Viewer) {
+        return Err(unauthorized(Role::Viewer));
+    }
+
+    let filter = StreamFilter::from_query(query.topics.as_deref(), query.camera.as_deref(), query.priority.as_deref())
+        .map_err(ApiError::bad_request)?;
+    let events = state.events.lock().await.subscribe();
+    Ok(upgrade.on_upgrade(move |socket| event_stream::run_session(socket, events, filter)))
+}
+
+pub fn router(state: AppState) -> Router {
+    let viewer = Router::new()
+        .route("/events", get(events))
+        .route("/triggers/:external_id", get(trigger_events))
+        .route("/rules", get(get_rules))
+        .route_layer(axum::middleware::from_fn_with_state(Role::Viewer, require_role));
+    let operator = Router::new()
+        .route("/detect", post(detect))
+        .route("/analyze", post(analyze))
+        .route("/trigger", post(trigger_analysis))
+        .route_layer(axum::middleware::from_fn_with_state(Role::Operator, require_role));
+    let admin = Router::new()
+        .route("/rules", put(put_rules))
+        .route_layer(axum::middleware::from_fn_with_state(Role::Admin, require_role));
+
+    Router::new()
+        .route("/health", get(health))
+        .route("/ws", get(stream))
+        .merge(viewer)
+        .merge(operator)
+        .merge(admin)
+        .layer(DefaultBodyLimit::max(MAX_BODY_BYTES))
+        .with_state(state)
+}
+
+// Listen on the LAN, or only on localhost while local-only mode is on
+pub async fn bind(port: u16) -> Result<TcpListener, String> {
+    let host = if local_only::enabled() { "127.0.0.1" } else { "0.0.0.0" };
+    let address = format!("{}:{}", host, port);
+    TcpListener::bind(&address)
+        .await
+        .map_err(|e| format!("Failed to bind API server on {}: {}", address, e))
+}
+
+pub async fn serve(state: AppState, listener: TcpListener) {
+    if let Err(e) = axum::serve(listener, router(state)).await {
+        eprintln!("API server stopped: {}", e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_api_key_headers() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(presented_key(&headers), None);
+
+        headers.insert("authorization", "Bearer secret".parse().unwrap());
+        assert_eq!(presented_key(&headers), Some("secret"));
+        assert!(key_matches("secret", "secret"));
+        assert!(!key_matches("secret", "secret2"));
+
+        let mut headers = HeaderMap::new();
+        headers.insert("x-api-key", "secret".parse().unwrap());
+        assert_eq!(presented_key(&headers), Some("secret"));
+    }
+}
@@ -0,0 +1,335 @@
+// App Status Module - One snapshot of every subsystem, plus a `status-changed` event whenever one of them changes
+// Camera and provider health come from the bus; subsystems report their errors through record_error
+
+use crate::bus::BusEvent;
+use crate::downloads::{self, DownloadStatus};
+use crate::event_store::format_timestamp;
+use crate::intake::PipelineStats;
+use crate::ollama_manager::{OllamaManager, OllamaStatus};
+use crate::offline::{self, OfflineStatus};
+use crate::pause::{self, AnalysisState};
+use crate::vision_provider::ProviderInfo;
+use crate::warmup::BackendReady;
+use crate::{ollama_runtime, semantic_search, settings, storage, AppState};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter};
+use tokio::sync::broadcast;
+
+pub const STATUS_EVENT: &str = "status-changed";
+const WATCH_INTERVAL: Duration = Duration::from_secs(2);
+
+// A camera that hasn't sent a frame for this long is reported inactive
+const CAMERA_ACTIVE_WINDOW: Duration = Duration::from_secs(60);
+// Walking the data directory is slow, so its size is refreshed at most this often
+const DATA_SIZE_TTL: Duration = Duration::from_secs(60);
+// Frames without a camera id come from the app's own camera
+const DEFAULT_CAMERA: &str = "default";
+
+static HEALTH: Mutex<BTreeMap<String, SubsystemHealth>> = Mutex::new(BTreeMap::new());
+static CAMERAS: Mutex<BTreeMap<String, CameraSeen>> = Mutex::new(BTreeMap::new());
+static DATA_SIZE: Mutex<Option<(Instant, u64)>> = Mutex::new(None);
+// Numbers every status-changed event; an AppStatus carries the latest so the UI can drop older events
+static SEQUENCE: AtomicU64 = AtomicU64::new(0);
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct SubsystemHealth {
+    pub last_ok_at: Option<String>,
+    pub last_error_at: Option<String>,
+    pub last_error: Option<String>,
+}
+
+struct CameraSeen {
+    seen: Instant,
+    last_frame_at: String,
+    frames: u64,
+    person_count: u32,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CameraState {
+    pub camera_id: String,
+    pub active: bool,
+    pub last_frame_at: String,
+    pub frames: u64,
+    pub person_count: u32,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ProviderHealth {
+    #[serde(flatten)]
+    pub info: ProviderInfo,
+    pub health: SubsystemHealth,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SelectedModels {
+    pub vision_model: String,
+    pub embedding_model: String,
+    pub ollama_version: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct DiskStatus {
+    pub free_mb: Option<u64>,
+    pub data_mb: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AppStatus {
+    pub sequence: u64,
+    pub collected_at: String,
+    pub ollama: OllamaStatus,
+    pub backend: Option<BackendReady>,
+    pub models: SelectedModels,
+    pub moondream: serde_json::Value,
+    pub providers: Vec<ProviderHealth>,
+    pub cameras: Vec<CameraState>,
+    pub monitoring: AnalysisState,
+    pub pipeline: PipelineStats,
+    pub disk: DiskStatus,
+    pub offline: OfflineStatus,
+    pub downloads: DownloadStatus,
+    pub subsystems: BTreeMap<String, SubsystemHealth>,  // Last success and error of everything that reported one
+}
+
+fn now() -> String {
+    format_timestamp(chrono::Utc::now())
+}
+
+fn update(subsystem: &str, edit: impl FnOnce(&mut SubsystemHealth)) {
+    edit(HEALTH.lock().unwrap_or_else(|e| e.into_inner()).entry(subsystem.to_string()).or_default());
+}
+
+pub fn record_ok(subsystem: &str) {
+    update(subsystem, |h| h.last_ok_at = Some(now()));
+}
+
+pub fn record_error(subsystem: &str, error: &str) {
+    update(subsystem, |h| {
+        h.last_error_at = Some(now());
+        h.last_error = Some(error.to_string());
+    });
+}
+
+fn health(subsystem: &str) -> SubsystemHealth {
+    HEALTH.lock().unwrap_or_else(|e| e.into_inner()).get(subsystem).cloned().unwrap_or_default()
+}
+
+fn cameras(now: Instant) -> Vec<CameraState> {
+    CAMERAS
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .iter()
+        .map(|(id, seen)| CameraState {
+            camera_id: id.clone(),
+            active: now.duration_since(seen.seen) < CAMERA_ACTIVE_WINDOW,
+            last_frame_at: seen.last_frame_at.clone(),
+            frames: seen.frames,
+            person_count: seen.person_count,
+        })
+        .collect()
+}
+
+// Runs for the life of the app, like the store and metrics subscribers
+pub async fn follow_bus(mut receiver: broadcast::Receiver<BusEvent>) {
+    loop {
+        match receiver.recv().await {
+            Ok(BusEvent::DetectionReady { camera_id, detection, live: true, .. }) => {
+                let id = camera_id.unwrap_or_else(|| DEFAULT_CAMERA.to_string());
+                let mut cameras = CAMERAS.lock().unwrap_or_else(|e| e.into_inner());
+                let seen = cameras.entry(id).or_insert(CameraSeen { seen: Instant::now(), last_frame_at: String::new(), frames: 0, person_count: 0 });
+                seen.seen = Instant::now();
+                seen.last_frame_at = now();
+                seen.frames += 1;
+                seen.person_count = detection.person_count;
+            }
+            // The error text, if any, comes from record_error at the call site
+            Ok(BusEvent::AnalysisReady { provider, success, .. }) => {
+                if success {
+                    record_ok(&provider);
+                } else {
+                    update(&provider, |h| h.last_error_at = Some(now()));
+                }
+            }
+            Ok(_) => {}
+            Err(broadcast::error::RecvError::Lagged(_)) => continue,
+            Err(broadcast::error::RecvError::Closed) => break,
+        }
+    }
+}
+
+async fn data_mb() -> u64 {
+    if let Some((at, size)) = *DATA_SIZE.lock().unwrap_or_else(|e| e.into_inner()) {
+        if at.elapsed() < DATA_SIZE_TTL {
+            return size;
+        }
+    }
+    let size = tokio::task::spawn_blocking(|| storage::dir_size(&storage::data_dir()) / (1024 * 1024)).await.unwrap_or(0);
+    *DATA_SIZE.lock().unwrap_or_else(|e| e.into_inner()) = Some((Instant::now(), size));
+    size
+}
+
+pub async fn collect(state: &AppState) -> AppStatus {
+    let settings = settings::current();
+    let providers = state
+        .providers
+        .read()
+        .await
+        .list()
+        .into_iter()
+        .map(|info| ProviderHealth { health: health(&info.name), info })
+        .collect();
+    // Not through the Ollama manager's lock, which a model pull holds for minutes
+    let ollama_dir = storage::data_dir().join("ollama");
+
+    AppStatus {
+        sequence: SEQUENCE.load(Ordering::Relaxed),
+        collected_at: now(),
+        ollama: OllamaManager::check_status().await,
+        backend: state.backend_ready.lock().await.clone(),
+        models: SelectedModels {
+            vision_model: settings.vision_model.clone(),
+            embedding_model: semantic_search::EMBEDDING_MODEL.to_string(),
+            ollama_version: ollama_runtime::installed(&ollama_dir).map(|info| info.version),
+        },
+        moondream: state.moondream.check_status().await.unwrap_or_else(|e| serde_json::json!({ "status": "error", "error": e })),
+        providers,
+        cameras: cameras(Instant::now()),
+        monitoring: pause::state(),
+        pipeline: state.intake.stats(Instant::now()),
+        disk: DiskStatus {
+            free_mb: storage::free_space_mb(&storage::data_dir()).await,
+            data_mb: data_mb().await,
+        },
+        offline: offline::status(),
+        downloads: downloads::status(),
+        subsystems: HEALTH.lock().unwrap_or_else(|e| e.into_inner()).clone(),
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct StatusChange {
+    pub sequence: u64,
+    pub subsystem: String,  // "ollama", "backend", "camera:<id>", a provider name, ...
+    pub status: String,
+    pub detail: Option<String>,
+    pub at: String,
+}
+
+// Subsystem -> (status, detail), cheap enough to take every WATCH_INTERVAL
+type States = BTreeMap<String, (String, Option<String>)>;
+
+async fn states(state: &AppState) -> States {
+    let mut states = States::new();
+    let ollama = OllamaManager::check_status().await;
+    let ollama_state = match (ollama.running, ollama.model_ready) {
+        (false, _) => "down",
+        (true, false) => "model_missing",
+        (true, true) => "ready",
+    };
+    states.insert("ollama".to_string(), (ollama_state.to_string(), ollama.error.or(Some(ollama.model))));
+    if let Some(backend) = state.backend_ready.lock().await.as_ref() {
+        states.insert("backend".to_string(), (if backend.ready { "ready" } else { "failed" }.to_string(), backend.error.clone()));
+    }
+    for camera in cameras(Instant::now()) {
+        states.insert(format!("camera:{}", camera.camera_id), (if camera.active { "active" } else { "disconnected" }.to_string(), None));
+    }
+    // Timestamps share one format, so they compare as strings; the live checks above win over reported errors
+    for (name, health) in HEALTH.lock().unwrap_or_else(|e| e.into_inner()).iter() {
+        let failing = health.last_error_at.is_some() && health.last_error_at > health.last_ok_at;
+        states.entry(name.clone()).or_insert((if failing { "error" } else { "ok" }.to_string(), health.last_error.clone().filter(|_| failing)));
+    }
+    let monitoring = pause::state();
+    states.insert("monitoring".to_string(), (if monitoring.paused { "paused" } else { "running" }.to_string(), monitoring.reason));
+    states.insert("network".to_string(), (if offline::is_offline() { "offline" } else { "online" }.to_string(), None));
+    states.insert("downloads".to_string(), (if downloads::paused() { "paused" } else { "running" }.to_string(), None));
+    states
+}
+
+// Subsystems that appeared or whose status or detail changed
+fn diff(old: &States, new: &States) -> Vec<(String, String, Option<String>)> {
+    new.iter()
+        .filter(|(name, current)| old.get(*name) != Some(*current))
+        .map(|(name, (status, detail))| (name.clone(), status.clone(), detail.clone()))
+        .collect()
+}
+
+// Background task: compare subsystem states every WATCH_INTERVAL and emit what changed
+pub async fn watch(app: AppHandle, state: AppState) {
+    let mut previous = states(&state).await;
+    loop {
+        tokio::time::sleep(WATCH_INTERVAL).await;
+        let current = states(&state).await;
+        for (subsystem, status, detail) in diff(&previous, &current) {
+            let change = StatusChange {
+                sequence: SEQUENCE.fetch_add(1, Ordering::Relaxed) + 1,
+                subsystem,
+                status,
+                detail,
+                at: now(),
+            };
+            if let Err(e) = app.emit(STATUS_EVENT, &change) {
+                eprintln!("Failed to emit status change: {}", e);
+            }
+        }
+        previous = current;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bus::EventBus;
+    use crate::yolo_detector::DetectionData;
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn test_bus_updates_cameras_and_health() {
+        let (bus, _) = EventBus::new();
+        let following = tokio::spawn(follow_bus(bus.subscribe()));
+        let detection: DetectionData = serde_json::from_value(serde_json::json!({
+            "person_count": 3, "object_counts": {"person": 3}, "crowd_density": 0.1, "motion_intensity": 0, "zone_occupancy": 0
+        }))
+        .unwrap();
+        bus.publish(BusEvent::DetectionReady {
+            camera_id: Some("status-test-door".to_string()),
+            detection: Arc::new(detection),
+            latency: Duration::from_millis(20),
+            live: true,
+            snapshot: None,
+        });
+        bus.publish(BusEvent::analysis("status-test-cloud", Duration::from_secs(1), false));
+        drop(bus);
+        following.await.unwrap();
+        record_error("status-test-cloud", "HTTP 429");
+
+        let camera = cameras(Instant::now()).into_iter().find(|c| c.camera_id == "status-test-door").unwrap();
+        assert!(camera.active);
+        assert_eq!((camera.frames, camera.person_count), (1, 3));
+        assert!(!cameras(Instant::now() + CAMERA_ACTIVE_WINDOW).iter().any(|c| c.camera_id == "status-test-door" && c.active));
+
+        let cloud = health("status-test-cloud");
+        assert_eq!(cloud.last_error.as_deref(), Some("HTTP 429"));
+        assert!(cloud.last_ok_at.is_none());
+    }
+
+    #[test]
+    fn test_diff_reports_changed_subsystems() {
+        let entry = |status: &str| (status.to_string(), None);
+        let old = States::from([("ollama".to_string(), entry("model_missing")), ("camera:door".to_string(), entry("active"))]);
+        let mut new = old.clone();
+        assert!(diff(&old, &new).is_empty());
+
+        new.insert("ollama".to_string(), entry("ready"));
+        new.insert("camera:door".to_string(), entry("disconnected"));
+        new.insert("openai".to_string(), ("error".to_string(), Some("HTTP 429".to_string())));
+        let changes = diff(&old, &new);
+        assert_eq!(changes.len(), 3);
+        assert!(changes.contains(&("ollama".to_string(), "ready".to_string(), None)));
+        assert!(changes.contains(&("openai".to_string(), "error".to_string(), Some("HTTP 429".to_string()))));
+    }
+}
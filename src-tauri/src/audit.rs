@@ -0,0 +1,166 @@
+// Audit Module - Append-only record of exports, uploads, API reads and data sent to external services
+// Entries are queued wherever the transfer happens and written to the event database by one background task
+
+use crate::event_store::{format_timestamp, EventStore};
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+const FLUSH_INTERVAL: Duration = Duration::from_secs(5);
+// Without a running writer (e.g. the CLI) the queue would otherwise grow forever
+const MAX_PENDING: usize = 10_000;
+
+static PENDING: Mutex<Vec<AuditEntry>> = Mutex::new(Vec::new());
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditAction {
+    Export,  // Written to a file or handed to the UI
+    Upload,  // Object storage
+    Transmission,  // Frames or events sent to a remote service
+    Access,  // Read through the REST API
+}
+
+impl AuditAction {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AuditAction::Export => "export",
+            AuditAction::Upload => "upload",
+            AuditAction::Transmission => "transmission",
+            AuditAction::Access => "access",
+        }
+    }
+
+    pub fn parse(action: &str) -> Result<Self, String> {
+        match action {
+            "export" => Ok(AuditAction::Export),
+            "upload" => Ok(AuditAction::Upload),
+            "transmission" => Ok(AuditAction::Transmission),
+            "access" => Ok(AuditAction::Access),
+            other => Err(format!("Unknown audit action: {}", other)),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct AuditEntry {
+    pub seq: Option<i64>,  // Set once stored
+    pub timestamp: String,
+    pub action: AuditAction,
+    pub data: String,  // What left, e.g. "1 frame + prompt (212 chars)"
+    pub destination: String,  // URL, object key or file path
+    pub provider: Option<String>,
+    pub bytes: Option<u64>,
+    pub error: Option<String>,
+}
+
+impl AuditEntry {
+    pub fn new(action: AuditAction, data: impl Into<String>, destination: impl Into<String>) -> Self {
+        AuditEntry {
+            seq: None,
+            timestamp: format_timestamp(chrono::Utc::now()),
+            action,
+            data: data.into(),
+            destination: destination.into(),
+            provider: None,
+            bytes: None,
+            error: None,
+        }
+    }
+
+    pub fn provider(mut self, provider: &str) -> Self {
+        self.provider = Some(provider.to_string());
+        self
+    }
+
+    pub fn bytes(mut self, bytes: u64) -> Self {
+        self.bytes = Some(bytes);
+        self
+    }
+
+    // Failed attempts are kept too; the data may have left before the error
+    pub fn outcome<T>(mut self, result: &Result<T, String>) -> Self {
+        self.error = result.as_ref().err().cloned();
+        self
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct AuditFilter {
+    pub from: Option<String>,
+    pub to: Option<String>,
+    pub action: Option<String>,
+    pub provider: Option<String>,
+    pub limit: Option<usize>,
+}
+
+// Decoded size of base64 payloads
+pub fn base64_bytes(images: &[String]) -> u64 {
+    images.iter().map(|i| i.len() as u64 * 3 / 4).sum()
+}
+
+pub fn record(entry: AuditEntry) {
+    let mut pending = PENDING.lock().unwrap_or_else(|e| e.into_inner());
+    if pending.len() >= MAX_PENDING {
+        eprintln!("Audit: Queue full, dropping entry for {}", entry.destination);
+        return;
+    }
+    pending.push(entry);
+}
+
+// Queued entries go back in front if the write fails
+pub fn flush(store: &EventStore) -> Result<usize, String> {
+    let entries = std::mem::take(&mut *PENDING.lock().unwrap_or_else(|e| e.into_inner()));
+    if entries.is_empty() {
+        return Ok(0);
+    }
+    match store.append_audit(&entries) {
+        Ok(()) => Ok(entries.len()),
+        Err(e) => {
+            let mut pending = PENDING.lock().unwrap_or_else(|e| e.into_inner());
+            let newer = std::mem::replace(&mut *pending, entries);
+            pending.extend(newer);
+            Err(e)
+        }
+    }
+}
+
+pub async fn run(events: Arc<tokio::sync::Mutex<EventStore>>) {
+    let mut ticker = tokio::time::interval(FLUSH_INTERVAL);
+    loop {
+        ticker.tick().await;
+        if let Err(e) = flush(&*events.lock().await) {
+            eprintln!("Audit: {}", e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_entries_are_stored_and_append_only() {
+        let store = EventStore::open_in_memory().unwrap();
+        let sent: Result<(), String> = Ok(());
+        let failed: Result<(), String> = Err("timeout".to_string());
+        let openai = AuditEntry::new(AuditAction::Transmission, "1 frame + prompt", "https://api.openai.com/v1");
+        record(openai.provider("audit-test").bytes(900).outcome(&sent));
+        record(AuditEntry::new(AuditAction::Upload, "events.parquet", "s3://audit-test").provider("audit-test").outcome(&failed));
+        assert!(flush(&store).unwrap() >= 2);
+
+        let filter = AuditFilter { provider: Some("audit-test".to_string()), ..Default::default() };
+        let entries = store.audit_log(&filter).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert!(entries.iter().all(|e| e.seq.is_some()));
+        let uploads = store.audit_log(&AuditFilter { action: Some("upload".to_string()), ..filter }).unwrap();
+        assert_eq!((uploads.len(), uploads[0].error.as_deref()), (1, Some("timeout")));
+
+        // The table itself refuses edits
+        let mut conn = rusqlite::Connection::open_in_memory().unwrap();
+        crate::migrations::migrate(&mut conn, None).unwrap();
+        conn.execute("INSERT INTO audit_log (timestamp, action, data, destination) VALUES ('t', 'export', 'd', 'x')", []).unwrap();
+        assert!(conn.execute("DELETE FROM audit_log", []).is_err());
+        assert!(conn.execute("UPDATE audit_log SET data = 'nothing'", []).is_err());
+    }
+}
@@ -0,0 +1,252 @@
+// Backup Module - One-file backup and restore of the app's data folder
+// The event database, settings (zones included), provider endpoints and optionally snapshots, for moving machines or recovering from corruption
+
+use crate::encryption;
+use crate::event_store::format_timestamp;
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use zip::write::FileOptions;
+use zip::{CompressionMethod, ZipArchive, ZipWriter};
+
+// Bumped when a backup can no longer be restored by older versions
+pub const BACKUP_FORMAT: u32 = 1;
+
+const MANIFEST: &str = "manifest.json";
+pub const DATABASE: &str = "events.db";
+const SNAPSHOTS: &str = "snapshots";
+
+// Restored as-is next to the database; missing ones are skipped
+const CONFIG_FILES: [&str; 3] = ["settings.json", PROVIDERS, "object_sync.json"];
+
+// Backed up without its API keys; a restore keeps the keys already on the machine
+const PROVIDERS: &str = "providers.json";
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct BackupManifest {
+    pub format: u32,
+    pub created_at: String,
+    pub app_version: String,
+    pub files: Vec<String>,  // Config files, besides the database
+    pub snapshots: usize,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct BackupSummary {
+    pub path: String,
+    pub size_bytes: u64,
+    pub manifest: BackupManifest,
+}
+
+fn read(source: &Path) -> Result<Vec<u8>, String> {
+    let mut contents = Vec::new();
+    File::open(source)
+        .and_then(|mut f| f.read_to_end(&mut contents))
+        .map_err(|e| format!("Failed to read {}: {}", source.display(), e))?;
+    Ok(contents)
+}
+
+// Provider name -> settings, as ProviderRegistry saves them
+fn provider_settings(contents: &[u8]) -> Result<serde_json::Map<String, serde_json::Value>, String> {
+    serde_json::from_slice(contents).map_err(|e| format!("Invalid {}: {}", PROVIDERS, e))
+}
+
+fn without_api_keys(contents: &[u8]) -> Result<Vec<u8>, String> {
+    let mut providers = provider_settings(contents)?;
+    for settings in providers.values_mut().filter_map(|s| s.as_object_mut()) {
+        settings.remove("api_key");
+    }
+    serde_json::to_vec_pretty(&providers).map_err(|e| e.to_string())
+}
+
+// Restored providers with the API keys configured on this machine for providers of the same name
+fn with_local_api_keys(contents: &[u8], local: &Path) -> Result<Vec<u8>, String> {
+    let mut providers = provider_settings(contents)?;
+    let local = if local.is_file() { provider_settings(&read(local)?)? } else { Default::default() };
+    for (name, settings) in providers.iter_mut() {
+        if let (Some(settings), Some(key)) = (settings.as_object_mut(), local.get(name).map(|l| &l["api_key"]).filter(|k| !k.is_null())) {
+            settings.insert("api_key".to_string(), key.clone());
+        }
+    }
+    serde_json::to_vec_pretty(&providers).map_err(|e| e.to_string())
+}
+
+fn add_file(zip: &mut ZipWriter<File>, name: &str, source: &Path, method: CompressionMethod) -> Result<(), String> {
+    let contents = if name == PROVIDERS { without_api_keys(&read(source)?)? } else { read(source)? };
+    zip.start_file(name, FileOptions::default().compression_method(method))
+        .and_then(|_| zip.write_all(&contents).map_err(Into::into))
+        .map_err(|e| format!("Failed to write {} to backup: {}", name, e))
+}
+
+// `database` is a copy taken with EventStore::backup_to, never the live file
+pub fn write_archive(path: &Path, database: &Path, root: &Path, snapshots: Option<&Path>) -> Result<BackupSummary, String> {
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    // Written aside first so a failed backup never replaces a good one
+    let partial = path.with_extension("partial");
+    let file = File::create(&partial).map_err(|e| format!("Failed to create {}: {}", partial.display(), e))?;
+    let mut zip = ZipWriter::new(file);
+
+    add_file(&mut zip, DATABASE, database, CompressionMethod::Deflated)?;
+    let mut files = Vec::new();
+    for name in CONFIG_FILES {
+        let source = root.join(name);
+        if source.is_file() {
+            add_file(&mut zip, name, &source, CompressionMethod::Deflated)?;
+            files.push(name.to_string());
+        }
+    }
+    let mut snapshot_count = 0;
+    if let Some(dir) = snapshots {
+        for entry in std::fs::read_dir(dir).into_iter().flatten().flatten() {
+            let source = entry.path();
+            if source.is_file() {
+                // Already-compressed images
+                let name = format!("{}/{}", SNAPSHOTS, entry.file_name().to_string_lossy());
+                add_file(&mut zip, &name, &source, CompressionMethod::Stored)?;
+                snapshot_count += 1;
+            }
+        }
+    }
+
+    let manifest = BackupManifest {
+        format: BACKUP_FORMAT,
+        created_at: format_timestamp(chrono::Utc::now()),
+        app_version: env!("CARGO_PKG_VERSION").to_string(),
+        files,
+        snapshots: snapshot_count,
+    };
+    let json = serde_json::to_vec_pretty(&manifest).map_err(|e| e.to_string())?;
+    zip.start_file(MANIFEST, FileOptions::default())
+        .and_then(|_| zip.write_all(&json).map_err(Into::into))
+        .and_then(|_| zip.finish())
+        .map_err(|e| format!("Failed to finish backup: {}", e))?;
+    std::fs::rename(&partial, path).map_err(|e| format!("Failed to save {}: {}", path.display(), e))?;
+
+    Ok(BackupSummary {
+        path: path.display().to_string(),
+        size_bytes: std::fs::metadata(path).map(|m| m.len()).unwrap_or(0),
+        manifest,
+    })
+}
+
+// Unpack into `staging`; nothing in the data folder is touched yet
+pub fn extract(path: &Path, staging: &Path) -> Result<BackupManifest, String> {
+    let file = File::open(path).map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+    let mut archive = ZipArchive::new(file).map_err(|e| format!("Not a backup archive: {}", e))?;
+
+    let manifest: BackupManifest = {
+        let entry = archive.by_name(MANIFEST).map_err(|_| "Backup has no manifest".to_string())?;
+        serde_json::from_reader(entry).map_err(|e| format!("Invalid backup manifest: {}", e))?
+    };
+    if manifest.format > BACKUP_FORMAT {
+        return Err(format!("Backup format {} is newer than this version supports ({})", manifest.format, BACKUP_FORMAT));
+    }
+
+    for i in 0..archive.len() {
+        let mut entry = archive.by_index(i).map_err(|e| e.to_string())?;
+        // Entries that would land outside the staging folder are refused
+        let name = entry
+            .enclosed_name()
+            .map(Path::to_path_buf)
+            .ok_or_else(|| format!("Unsafe path in backup: {}", entry.name()))?;
+        if entry.is_dir() || name == Path::new(MANIFEST) {
+            continue;
+        }
+        let target = staging.join(&name);
+        if let Some(parent) = target.parent() {
+            std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+        }
+        let mut out = File::create(&target).map_err(|e| e.to_string())?;
+        std::io::copy(&mut entry, &mut out).map_err(|e| format!("Failed to extract {}: {}", name.display(), e))?;
+    }
+
+    if !staging.join(DATABASE).is_file() {
+        return Err("Backup has no event database".to_string());
+    }
+    Ok(manifest)
+}
+
+// Config files and snapshots from the staging folder; the database is restored through the open store instead
+pub fn install(staging: &Path, root: &Path, snapshots: &Path) -> Result<Vec<PathBuf>, String> {
+    let mut installed = Vec::new();
+    for name in CONFIG_FILES {
+        let source = staging.join(name);
+        if source.is_file() {
+            let target = root.join(name);
+            if name == PROVIDERS {
+                let contents = with_local_api_keys(&read(&source)?, &target)?;
+                encryption::write_private(&target, &contents).map_err(|e| format!("Failed to restore {}: {}", name, e))?;
+            } else {
+                std::fs::copy(&source, &target).map_err(|e| format!("Failed to restore {}: {}", name, e))?;
+            }
+            installed.push(target);
+        }
+    }
+    let staged_snapshots = staging.join(SNAPSHOTS);
+    if staged_snapshots.is_dir() {
+        std::fs::create_dir_all(snapshots).map_err(|e| e.to_string())?;
+        for entry in std::fs::read_dir(&staged_snapshots).map_err(|e| e.to_string())?.flatten() {
+            let target = snapshots.join(entry.file_name());
+            std::fs::copy(entry.path(), &target).map_err(|e| format!("Failed to restore snapshot: {}", e))?;
+            installed.push(target);
+        }
+    }
+    Ok(installed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bus::Alert;
+    use crate::event_store::{EventFilter, EventStore};
+
+    #[test]
+    fn test_backup_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().join("data");
+        std::fs::create_dir_all(root.join("snapshots")).unwrap();
+        std::fs::write(root.join("settings.json"), "{\"vision_model\": \"llava:13b\"}").unwrap();
+        let providers = serde_json::json!({
+            "gpt4o": { "kind": "openai", "api_key": "sk-old-machine" },
+            "vllm": { "kind": "openai", "base_url": "http://gpu:8000/v1" }
+        });
+        std::fs::write(root.join(PROVIDERS), providers.to_string()).unwrap();
+        std::fs::write(root.join("snapshots").join("a.jpg"), [0xff, 0xd8, 0xff]).unwrap();
+
+        let store = EventStore::open_in_memory().unwrap();
+        store.store_alert(&Alert::new(Some("door"), "ppe", "No helmet", serde_json::json!({}), None)).unwrap();
+        let database = dir.path().join("copy.db");
+        store.backup_to(&database).unwrap();
+
+        let archive = dir.path().join("backups").join("lva.zip");
+        let summary = write_archive(&archive, &database, &root, Some(&root.join("snapshots"))).unwrap();
+        assert_eq!(summary.manifest.files, vec!["settings.json".to_string(), PROVIDERS.to_string()]);
+        assert_eq!(summary.manifest.snapshots, 1);
+        assert!(!archive.with_extension("partial").exists());
+
+        // Restore onto a fresh machine
+        let staging = dir.path().join("staging");
+        let manifest = extract(&archive, &staging).unwrap();
+        assert_eq!(manifest, summary.manifest);
+        assert!(!std::fs::read_to_string(staging.join(PROVIDERS)).unwrap().contains("sk-old-machine"));
+        let target = dir.path().join("new");
+        std::fs::create_dir_all(&target).unwrap();
+        std::fs::write(target.join(PROVIDERS), serde_json::json!({ "gpt4o": { "kind": "openai", "api_key": "sk-new-machine" } }).to_string()).unwrap();
+        assert_eq!(install(&staging, &target, &target.join("snapshots")).unwrap().len(), 3);
+        assert!(std::fs::read_to_string(target.join("settings.json")).unwrap().contains("llava:13b"));
+        let restored: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(target.join(PROVIDERS)).unwrap()).unwrap();
+        assert_eq!(restored["gpt4o"]["api_key"], "sk-new-machine");
+        assert!(restored["vllm"].get("api_key").is_none());
+
+        let mut restored = EventStore::open_in_memory().unwrap();
+        restored.restore_from(&staging.join(DATABASE)).unwrap();
+        assert_eq!(restored.list_events(&EventFilter::default()).unwrap().len(), 1);
+
+        std::fs::write(dir.path().join("junk.db"), b"not a database").unwrap();
+        assert!(restored.restore_from(&dir.path().join("junk.db")).is_err());
+        assert!(extract(&dir.path().join("junk.db"), &staging).is_err());
+    }
+}
@@ -0,0 +1,113 @@
+// Barcode Module - Read barcodes and QR codes off shelf labels, packaging and signage
+// Decoding is done by rxing (a Rust port of ZXing), which covers EAN/UPC, Code 128, Code 39, ITF, QR, Data Matrix and PDF417
+
+use crate::{frame_processor, privacy};
+use image::GrayImage;
+use rxing::{DecodeHintType, DecodeHintValue, DecodingHintDictionary, RXingResult};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ScannedCode {
+    pub format: String,  // e.g. "ean_13", "upc_a", "code_128" or "qr_code"
+    pub value: String,
+    // Pixel bounds of the points the decoder located it by
+    pub x1: f32,
+    pub y1: f32,
+    pub x2: f32,
+    pub y2: f32,
+}
+
+fn scanned(result: &RXingResult) -> ScannedCode {
+    let points = result.getPoints();
+    let (mut x1, mut y1, mut x2, mut y2) = (f32::MAX, f32::MAX, 0.0f32, 0.0f32);
+    for point in points {
+        x1 = x1.min(point.x);
+        y1 = y1.min(point.y);
+        x2 = x2.max(point.x);
+        y2 = y2.max(point.y);
+    }
+    if points.is_empty() {
+        (x1, y1) = (0.0, 0.0);
+    }
+    ScannedCode {
+        // QR_CODE -> "qr_code"; an EAN-13 with a leading zero comes back as UPC_A
+        format: format!("{:?}", result.getBarcodeFormat()).to_lowercase(),
+        value: result.getText().to_string(),
+        x1,
+        y1,
+        x2,
+        y2,
+    }
+}
+
+pub fn scan_image(image: &GrayImage) -> Vec<ScannedCode> {
+    let (width, height) = image.dimensions();
+    // Try harder also reads 1D labels turned on their side
+    let mut hints = DecodingHintDictionary::new();
+    hints.insert(DecodeHintType::TRY_HARDER, DecodeHintValue::TryHarder(true));
+    // Nothing in the frame comes back as an error too
+    let results = rxing::helpers::detect_multiple_in_luma_with_hints(image.as_raw().clone(), width, height, &mut hints)
+        .unwrap_or_default();
+
+    // The same code found twice (e.g. read in both directions) is reported once, covering both places
+    let mut codes: BTreeMap<(String, String), ScannedCode> = BTreeMap::new();
+    for code in results.iter().map(scanned) {
+        let entry = codes.entry((code.format.clone(), code.value.clone())).or_insert(code.clone());
+        entry.x1 = entry.x1.min(code.x1);
+        entry.y1 = entry.y1.min(code.y1);
+        entry.x2 = entry.x2.max(code.x2);
+        entry.y2 = entry.y2.max(code.y2);
+    }
+    codes.into_values().collect()
+}
+
+pub fn scan_frame(frame_base64: &str) -> Result<Vec<ScannedCode>, String> {
+    let image = frame_processor::decode_frame(&privacy::mask_frame(frame_base64.to_string())?)?;
+    Ok(scan_image(&image.to_luma8()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::Luma;
+    use rxing::{BarcodeFormat, MultiFormatWriter, Writer};
+
+    // A white frame with one code drawn at (left, top)
+    fn frame_with(format: BarcodeFormat, contents: &str, size: (i32, i32), (left, top): (u32, u32)) -> GrayImage {
+        let matrix = MultiFormatWriter.encode(contents, &format, size.0, size.1).unwrap();
+        let mut image = GrayImage::from_pixel(480, 360, Luma([235]));
+        for y in 0..matrix.getHeight() {
+            for x in 0..matrix.getWidth() {
+                if matrix.get(x, y) {
+                    image.put_pixel(left + x, top + y, Luma([25]));
+                }
+            }
+        }
+        image
+    }
+
+    fn read(image: &GrayImage) -> Vec<(String, String)> {
+        scan_image(image).into_iter().map(|c| (c.format, c.value)).collect()
+    }
+
+    #[test]
+    fn test_reads_qr_codes() {
+        let image = frame_with(BarcodeFormat::QR_CODE, "https://example.com/p/4006381333931", (200, 200), (150, 80));
+        let codes = scan_image(&image);
+        assert_eq!(codes.len(), 1);
+        assert_eq!((codes[0].format.as_str(), codes[0].value.as_str()), ("qr_code", "https://example.com/p/4006381333931"));
+        assert!(codes[0].x1 >= 150.0 && codes[0].x2 <= 350.0 && codes[0].y1 >= 80.0 && codes[0].y2 <= 280.0);
+    }
+
+    #[test]
+    fn test_reads_retail_barcodes() {
+        let ean = frame_with(BarcodeFormat::EAN_13, "4006381333931", (300, 100), (90, 130));
+        assert_eq!(read(&ean), vec![("ean_13".to_string(), "4006381333931".to_string())]);
+        let upc = frame_with(BarcodeFormat::EAN_13, "0036000291452", (300, 100), (90, 130));
+        assert_eq!(read(&upc), vec![("upc_a".to_string(), "036000291452".to_string())]);
+        let shelf = frame_with(BarcodeFormat::CODE_128, "SHELF-A12", (300, 100), (90, 130));
+        assert_eq!(read(&shelf), vec![("code_128".to_string(), "SHELF-A12".to_string())]);
+        assert!(scan_image(&GrayImage::from_pixel(480, 360, Luma([235]))).is_empty());
+    }
+}
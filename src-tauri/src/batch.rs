@@ -0,0 +1,142 @@
+// Batch Module - Offline analysis of image folders
+// Files run through detection + VLM analysis on a bounded worker pool with progress events
+
+use crate::yolo_detector::DetectionData;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+pub const DEFAULT_CONCURRENCY: usize = 2;
+pub const MAX_CONCURRENCY: usize = 8;
+
+// Tauri event names
+pub const PROGRESS_EVENT: &str = "batch-progress";
+pub const COMPLETE_EVENT: &str = "batch-complete";
+
+// Result for one file in the batch
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct BatchItemResult {
+    pub path: String,
+    pub success: bool,
+    pub detection: Option<DetectionData>,
+    pub description: Option<String>,
+    pub analysis: Option<serde_json::Value>,
+    pub event_id: Option<String>,
+    pub error: Option<String>,
+    pub processing_time_ms: u64,
+}
+
+impl BatchItemResult {
+    pub fn new(path: String) -> Self {
+        BatchItemResult {
+            path,
+            success: false,
+            detection: None,
+            description: None,
+            analysis: None,
+            event_id: None,
+            error: None,
+            processing_time_ms: 0,
+        }
+    }
+}
+
+// Emitted after each file finishes
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct BatchProgress {
+    pub batch_id: String,
+    pub completed: usize,
+    pub total: usize,
+    pub item: BatchItemResult,
+}
+
+// Aggregate summary returned (and emitted) when the batch is done
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct BatchSummary {
+    pub batch_id: String,
+    pub provider: String,
+    pub total: usize,
+    pub succeeded: usize,
+    pub failed: usize,
+    pub total_people: u32,
+    pub max_people: u32,
+    pub object_totals: HashMap<String, u32>,
+    pub average_processing_ms: f64,
+    pub elapsed_ms: u64,
+    pub items: Vec<BatchItemResult>,
+}
+
+pub fn summarize(batch_id: &str, provider: &str, items: Vec<BatchItemResult>, elapsed_ms: u64) -> BatchSummary {
+    let mut object_totals: HashMap<String, u32> = HashMap::new();
+    let mut total_people = 0;
+    let mut max_people = 0;
+
+    for detection in items.iter().filter_map(|i| i.detection.as_ref()) {
+        total_people += detection.person_count;
+        max_people = max_people.max(detection.person_count);
+        for (class_name, count) in &detection.object_counts {
+            *object_totals.entry(class_name.clone()).or_insert(0) += count;
+        }
+    }
+
+    let succeeded = items.iter().filter(|i| i.success).count();
+    let average_processing_ms = if items.is_empty() {
+        0.0
+    } else {
+        items.iter().map(|i| i.processing_time_ms).sum::<u64>() as f64 / items.len() as f64
+    };
+
+    BatchSummary {
+        batch_id: batch_id.to_string(),
+        provider: provider.to_string(),
+        total: items.len(),
+        succeeded,
+        failed: items.len() - succeeded,
+        total_people,
+        max_people,
+        object_totals,
+        average_processing_ms,
+        elapsed_ms,
+        items,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(path: &str, people: Option<u32>) -> BatchItemResult {
+        BatchItemResult {
+            path: path.to_string(),
+            success: people.is_some(),
+            detection: people.map(|count| DetectionData {
+                person_count: count,
+                object_counts: HashMap::from([("person".to_string(), count)]),
+                crowd_density: 0.0,
+                motion_intensity: 0.0,
+                zone_occupancy: 0.0,
+                boxes: Vec::new(),
+                smoothed_person_count: None,
+                visitors: None,
+                staff_count: None,
+                annotations: Default::default(),
+                escalate: None,
+            }),
+            description: None,
+            analysis: None,
+            event_id: None,
+            error: people.map_or(Some("unreadable".to_string()), |_| None),
+            processing_time_ms: 100,
+        }
+    }
+
+    #[test]
+    fn test_summary_aggregates_detections() {
+        let summary = summarize("b1", "none", vec![item("a.jpg", Some(2)), item("b.jpg", Some(3)), item("c.jpg", None)], 500);
+
+        assert_eq!(summary.succeeded, 2);
+        assert_eq!(summary.failed, 1);
+        assert_eq!(summary.total_people, 5);
+        assert_eq!(summary.max_people, 3);
+        assert_eq!(summary.object_totals["person"], 5);
+    }
+}
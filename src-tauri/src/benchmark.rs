@@ -0,0 +1,141 @@
+// Benchmark Module - Latency and throughput comparison across providers
+// Extends the one-off A/B test into repeatable capacity-planning runs
+
+use serde::{Deserialize, Serialize};
+
+// Providers that can be benchmarked
+pub const SUPPORTED_PROVIDERS: [&str; 3] = ["yolo", "llava", "moondream"];
+
+pub const DEFAULT_BENCHMARK_PROMPT: &str =
+    "Describe what you see in this image in 2-3 sentences. Focus on the main subjects and activities.";
+
+// Outcome of a single benchmark call
+#[derive(Debug, Clone)]
+pub struct Sample {
+    pub latency_ms: u64,
+    pub success: bool,
+    pub error: Option<String>,
+}
+
+// Latency distribution over successful calls
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct LatencyStats {
+    pub min_ms: u64,
+    pub mean_ms: f64,
+    pub p50_ms: u64,
+    pub p90_ms: u64,
+    pub p95_ms: u64,
+    pub p99_ms: u64,
+    pub max_ms: u64,
+}
+
+// Aggregated results for one provider
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ProviderBenchmark {
+    pub provider: String,
+    pub runs: usize,
+    pub successes: usize,
+    pub failures: usize,
+    pub failure_rate: f64,
+    pub latency: LatencyStats,
+    pub throughput_per_sec: f64,
+    pub total_time_ms: u64,
+    pub errors: Vec<String>,  // Distinct error messages (first few)
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct BenchmarkReport {
+    pub benchmark_id: String,
+    pub timestamp: String,
+    pub frame_count: usize,
+    pub iterations: usize,
+    pub providers: Vec<ProviderBenchmark>,
+}
+
+// Nearest-rank percentile over sorted values
+pub fn percentile(sorted: &[u64], pct: f64) -> u64 {
+    if sorted.is_empty() {
+        return 0;
+    }
+    let rank = ((pct / 100.0) * sorted.len() as f64).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
+
+pub fn latency_stats(latencies: &[u64]) -> LatencyStats {
+    if latencies.is_empty() {
+        return LatencyStats::default();
+    }
+
+    let mut sorted = latencies.to_vec();
+    sorted.sort_unstable();
+
+    LatencyStats {
+        min_ms: sorted[0],
+        mean_ms: sorted.iter().sum::<u64>() as f64 / sorted.len() as f64,
+        p50_ms: percentile(&sorted, 50.0),
+        p90_ms: percentile(&sorted, 90.0),
+        p95_ms: percentile(&sorted, 95.0),
+        p99_ms: percentile(&sorted, 99.0),
+        max_ms: sorted[sorted.len() - 1],
+    }
+}
+
+// Summarize a provider's samples; throughput counts successful calls over wall time
+pub fn summarize(provider: &str, samples: &[Sample], total_time_ms: u64) -> ProviderBenchmark {
+    let latencies: Vec<u64> = samples.iter().filter(|s| s.success).map(|s| s.latency_ms).collect();
+    let successes = latencies.len();
+    let failures = samples.len() - successes;
+
+    let mut errors: Vec<String> = Vec::new();
+    for error in samples.iter().filter_map(|s| s.error.as_ref()) {
+        if errors.len() < 5 && !errors.contains(error) {
+            errors.push(error.clone());
+        }
+    }
+
+    ProviderBenchmark {
+        provider: provider.to_string(),
+        runs: samples.len(),
+        successes,
+        failures,
+        failure_rate: if samples.is_empty() { 0.0 } else { failures as f64 / samples.len() as f64 },
+        latency: latency_stats(&latencies),
+        throughput_per_sec: if total_time_ms == 0 { 0.0 } else { successes as f64 / (total_time_ms as f64 / 1000.0) },
+        total_time_ms,
+        errors,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(latency_ms: u64, success: bool) -> Sample {
+        Sample {
+            latency_ms,
+            success,
+            error: if success { None } else { Some("timeout".to_string()) },
+        }
+    }
+
+    #[test]
+    fn test_percentiles() {
+        let values: Vec<u64> = (1..=100).collect();
+        assert_eq!(percentile(&values, 50.0), 50);
+        assert_eq!(percentile(&values, 95.0), 95);
+        assert_eq!(percentile(&values, 100.0), 100);
+        assert_eq!(percentile(&[7], 99.0), 7);
+    }
+
+    #[test]
+    fn test_summary_ignores_failed_latencies() {
+        let samples = vec![sample(100, true), sample(300, true), sample(30000, false), sample(30000, false)];
+        let summary = summarize("llava", &samples, 2000);
+
+        assert_eq!(summary.successes, 2);
+        assert_eq!(summary.failure_rate, 0.5);
+        assert_eq!(summary.latency.max_ms, 300);
+        assert_eq!(summary.throughput_per_sec, 1.0);
+        assert_eq!(summary.errors, vec!["timeout".to_string()]);
+    }
+}
@@ -0,0 +1,6 @@
+// Headless entry point: detect, analyze and monitor from the terminal without the GUI
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    std::process::exit(live_vision_analyzer_lib::cli::run(args))
+}
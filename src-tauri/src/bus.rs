@@ -0,0 +1,148 @@
+// Bus Module - Typed in-process events between backend subsystems
+// Detection and analysis paths publish what happened; storage and metrics subscribe on their own instead of being called inline.
+// A subscriber that falls behind skips events, so alerts also go to the store on a channel of their own that never drops them
+
+use crate::yolo_detector::DetectionData;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{broadcast, mpsc};
+
+// Generous so a slow subscriber (the store on a busy disk) rarely lags
+const CAPACITY: usize = 1024;
+
+// Alerts waiting for the store; when it is this far behind, publishers wait instead of dropping any
+const ALERT_CAPACITY: usize = 256;
+
+// An alert to be stored under an id chosen by the publisher, so it can be referenced right away
+#[derive(Debug, Clone)]
+pub struct Alert {
+    pub id: String,
+    pub camera_id: Option<String>,
+    pub provider: String,
+    pub description: String,
+    pub payload: serde_json::Value,
+    pub snapshot: Option<String>,  // Evidence frame, saved before the alert is stored
+}
+
+impl Alert {
+    pub fn new(camera_id: Option<&str>, provider: &str, description: &str, payload: serde_json::Value, snapshot: Option<&str>) -> Self {
+        Alert {
+            id: uuid::Uuid::new_v4().to_string(),
+            camera_id: camera_id.map(|c| c.to_string()),
+            provider: provider.to_string(),
+            description: description.to_string(),
+            payload,
+            snapshot: snapshot.map(|s| s.to_string()),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum BusEvent {
+    // A live frame arrived for detection
+    FrameCaptured,
+    // Detection finished; live detections are stored, with `snapshot` kept when there are boxes
+    DetectionReady {
+        camera_id: Option<String>,
+        detection: Arc<DetectionData>,
+        latency: Duration,
+        live: bool,
+        snapshot: Option<Arc<str>>,
+    },
+    // A VLM call returned, successfully or not
+    AnalysisReady { provider: String, latency: Duration, success: bool },
+    // Also delivered to the store on the alert channel; subscribers here may miss it
+    AlertFired(Arc<Alert>),
+}
+
+impl BusEvent {
+    pub fn analysis(provider: &str, latency: Duration, success: bool) -> Self {
+        BusEvent::AnalysisReady { provider: provider.to_string(), latency, success }
+    }
+}
+
+#[derive(Clone)]
+pub struct EventBus {
+    sender: broadcast::Sender<BusEvent>,
+    alerts: mpsc::Sender<Arc<Alert>>,
+}
+
+impl EventBus {
+    // The bus and the receiving end of its alert channel, for event_store::file_alerts
+    pub fn new() -> (Self, mpsc::Receiver<Arc<Alert>>) {
+        let (alerts, receiver) = mpsc::channel(ALERT_CAPACITY);
+        (EventBus { sender: broadcast::channel(CAPACITY).0, alerts }, receiver)
+    }
+
+    // Nobody listening is fine: the event is simply dropped
+    pub fn publish(&self, event: BusEvent) {
+        let _ = self.sender.send(event);
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<BusEvent> {
+        self.sender.subscribe()
+    }
+
+    // Hand an alert to the store and publish it; returns the id it will be stored under
+    pub async fn alert(
+        &self,
+        camera_id: Option<&str>,
+        provider: &str,
+        description: &str,
+        payload: serde_json::Value,
+        snapshot: Option<&str>,
+    ) -> String {
+        let alert = Arc::new(Alert::new(camera_id, provider, description, payload, snapshot));
+        if self.alerts.send(alert.clone()).await.is_err() {
+            eprintln!("No alert store is running; alert {} was not stored", alert.id);
+        }
+        self.publish(BusEvent::AlertFired(alert.clone()));
+        alert.id.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::event_store::EventStore;
+    use crate::metrics::Metrics;
+    use tokio::sync::Mutex;
+
+    #[tokio::test]
+    async fn test_subscribers_store_and_count_independently() {
+        let (bus, alerts) = EventBus::new();
+        let events = Arc::new(Mutex::new(EventStore::open_in_memory().unwrap()));
+        let metrics = Arc::new(Metrics::new());
+        let storage = tokio::spawn(crate::event_store::follow_bus(events.clone(), bus.subscribe()));
+        let filing = tokio::spawn(crate::event_store::file_alerts(events.clone(), alerts));
+        let counting = tokio::spawn(crate::metrics::follow_bus(metrics.clone(), bus.subscribe()));
+
+        let detection: DetectionData = serde_json::from_value(serde_json::json!({
+            "person_count": 2, "object_counts": {"person": 2}, "crowd_density": 0.1, "motion_intensity": 0, "zone_occupancy": 0
+        }))
+        .unwrap();
+        bus.publish(BusEvent::FrameCaptured);
+        bus.publish(BusEvent::DetectionReady {
+            camera_id: Some("door".to_string()),
+            detection: Arc::new(detection),
+            latency: Duration::from_millis(20),
+            live: true,
+            snapshot: None,
+        });
+        bus.publish(BusEvent::analysis("llava", Duration::from_secs(2), true));
+        let alert_id = bus.alert(Some("door"), "rules", "Door left open", serde_json::json!({"priority": "high"}), None).await;
+
+        // Closing the bus ends every subscriber once it has caught up
+        drop(bus);
+        storage.await.unwrap();
+        filing.await.unwrap();
+        counting.await.unwrap();
+
+        let stored = events.lock().await.get_event(&alert_id).unwrap().unwrap();
+        assert_eq!(stored.description.as_deref(), Some("Door left open"));
+        let text = metrics.render();
+        assert!(text.contains("lva_frames_captured_total 1"));
+        assert!(text.contains("lva_frames_processed_total 1"));
+        assert!(text.contains("provider=\"llava\""));
+    }
+}
@@ -0,0 +1,146 @@
+// Chime Module - Audible alert sounds on the host, louder for more urgent alerts
+// Plays the platform's own system sounds unless a sound file is set for the priority; critical alerts ring three times
+
+use crate::alerting::{self, QuietHours};
+use crate::event_store::EventStore;
+use crate::event_stream::PRIORITIES;
+use crate::settings;
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::sync::Arc;
+use tokio::process::Command;
+use tokio::sync::Mutex;
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct ChimeSettings {
+    pub enabled: bool,
+    pub min_priority: String,
+    pub quiet_hours: Option<QuietHours>,
+    pub sounds: BTreeMap<String, String>,  // Priority -> sound file replacing the system sound
+}
+
+impl Default for ChimeSettings {
+    fn default() -> Self {
+        ChimeSettings {
+            enabled: false,
+            min_priority: "high".to_string(),
+            quiet_hours: None,
+            sounds: BTreeMap::new(),
+        }
+    }
+}
+
+impl ChimeSettings {
+    pub fn validate(&self) -> Result<(), String> {
+        alerting::validate_priority(&self.min_priority)?;
+        if let Some(quiet_hours) = &self.quiet_hours {
+            quiet_hours.validate()?;
+        }
+        for (priority, file) in &self.sounds {
+            alerting::validate_priority(priority)?;
+            if !Path::new(file).is_file() {
+                return Err(format!("Sound file for {} alerts not found: {}", priority, file));
+            }
+        }
+        Ok(())
+    }
+}
+
+// System sound names per priority, most urgent first like PRIORITIES
+const MACOS_SOUNDS: [&str; 4] = ["Sosumi", "Basso", "Glass", "Tink"];
+const FREEDESKTOP_SOUNDS: [&str; 4] = ["alarm-clock-elapsed", "dialog-warning", "message-new-instant", "message"];
+const WINDOWS_SOUNDS: [&str; 4] = ["Hand", "Exclamation", "Asterisk", "Beep"];
+
+fn sound_index(priority: &str) -> usize {
+    PRIORITIES.iter().position(|p| *p == priority).unwrap_or(2)
+}
+
+pub fn repeats(priority: &str) -> usize {
+    if priority == "critical" {
+        3
+    } else {
+        1
+    }
+}
+
+fn sound_commands(priority: &str, file: Option<&str>) -> Vec<Command> {
+    let index = sound_index(priority);
+    if cfg!(target_os = "macos") {
+        let mut afplay = Command::new("afplay");
+        afplay.arg(file.map(str::to_string).unwrap_or_else(|| format!("/System/Library/Sounds/{}.aiff", MACOS_SOUNDS[index])));
+        vec![afplay]
+    } else if cfg!(target_os = "windows") {
+        let mut powershell = Command::new("powershell");
+        match file {
+            // The path goes through the environment so nothing in it is read as PowerShell
+            Some(file) => powershell
+                .args(["-NoProfile", "-Command", "(New-Object System.Media.SoundPlayer $env:LVA_SOUND).PlaySync()"])
+                .env("LVA_SOUND", file),
+            None => powershell.args([
+                "-NoProfile",
+                "-Command",
+                &format!("[System.Media.SystemSounds]::{}.Play(); Start-Sleep -Milliseconds 800", WINDOWS_SOUNDS[index]),
+            ]),
+        };
+        vec![powershell]
+    } else {
+        let name = FREEDESKTOP_SOUNDS[index];
+        let mut paplay = Command::new("paplay");
+        paplay.arg(file.map(str::to_string).unwrap_or_else(|| format!("/usr/share/sounds/freedesktop/stereo/{}.oga", name)));
+        match file {
+            Some(file) => {
+                let mut aplay = Command::new("aplay");
+                aplay.arg(file);
+                vec![paplay, aplay]
+            }
+            None => {
+                let mut canberra = Command::new("canberra-gtk-play");
+                canberra.args(["-i", name]);
+                vec![canberra, paplay]
+            }
+        }
+    }
+}
+
+// Play the sound for a priority, repeated for critical alerts
+pub async fn play(priority: &str, config: &ChimeSettings) -> Result<(), String> {
+    let file = config.sounds.get(priority).map(String::as_str);
+    for _ in 0..repeats(priority) {
+        alerting::run_first(sound_commands(priority, file), "sound player").await?;
+    }
+    Ok(())
+}
+
+// Background task: chime for alerts at or above the configured priority
+pub async fn run(events: Arc<Mutex<EventStore>>) {
+    let mut receiver = events.lock().await.subscribe();
+    while let Some(notice) = alerting::next_notice(&mut receiver, "Alert chimes").await {
+        let config = settings::current().chime;
+        if config.enabled && alerting::passes(&notice, &config.min_priority, config.quiet_hours.as_ref(), Local::now().time()) {
+            if let Err(e) = play(&notice.priority, &config).await {
+                eprintln!("Failed to play alert chime: {}", e);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sounds_follow_priority() {
+        assert_eq!(MACOS_SOUNDS[sound_index("critical")], "Sosumi");
+        assert_eq!(FREEDESKTOP_SOUNDS[sound_index("low")], "message");
+        assert_eq!(WINDOWS_SOUNDS[sound_index("unknown")], "Asterisk");
+        assert_eq!((repeats("critical"), repeats("high")), (3, 1));
+
+        assert!(ChimeSettings::default().validate().is_ok());
+        let missing = ChimeSettings { sounds: BTreeMap::from([("high".to_string(), "/no/such/bell.wav".to_string())]), ..Default::default() };
+        assert!(missing.validate().is_err());
+        assert!(ChimeSettings { min_priority: "urgent".to_string(), ..Default::default() }.validate().is_err());
+    }
+}
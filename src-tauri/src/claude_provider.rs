@@ -0,0 +1,180 @@
+// Claude Provider Module - Anthropic Messages API with image content blocks
+// Used for escalated analyses and A/B comparisons when an Anthropic key is configured
+
+use crate::network;
+use crate::vision_provider::{ProviderSettings, TokenUsage, VisionProvider, VisionRequest, VisionResponse};
+use async_trait::async_trait;
+
+pub const DEFAULT_BASE_URL: &str = "https://api.anthropic.com/v1";
+pub const DEFAULT_MODEL: &str = "claude-sonnet-4-5";
+const API_VERSION: &str = "2023-06-01";
+const DEFAULT_MAX_TOKENS: u32 = 300;
+
+pub struct ClaudeProvider {
+    name: String,
+    api_key: String,
+    base_url: String,
+    model: String,
+}
+
+impl ClaudeProvider {
+    pub fn from_settings(name: &str, settings: &ProviderSettings) -> Result<Self, String> {
+        let api_key = settings
+            .api_key
+            .clone()
+            .or_else(|| std::env::var("ANTHROPIC_API_KEY").ok())
+            .ok_or("Claude provider needs an API key (settings or ANTHROPIC_API_KEY)")?;
+
+        Ok(ClaudeProvider {
+            name: name.to_string(),
+            api_key,
+            base_url: settings
+                .base_url
+                .clone()
+                .unwrap_or_else(|| DEFAULT_BASE_URL.to_string())
+                .trim_end_matches('/')
+                .to_string(),
+            model: settings.model.clone().unwrap_or_else(|| DEFAULT_MODEL.to_string()),
+        })
+    }
+
+    // Images go before the question, as recommended for vision prompts
+    pub fn build_payload(&self, request: &VisionRequest) -> serde_json::Value {
+        let mut content: Vec<serde_json::Value> = request
+            .images
+            .iter()
+            .map(|image| {
+                serde_json::json!({
+                    "type": "image",
+                    "source": { "type": "base64", "media_type": "image/jpeg", "data": image }
+                })
+            })
+            .collect();
+        content.push(serde_json::json!({ "type": "text", "text": request.prompt }));
+
+        serde_json::json!({
+            "model": request.model_or(&self.model),
+            "max_tokens": request.max_tokens.unwrap_or(DEFAULT_MAX_TOKENS),
+            "temperature": 0.3,
+            "messages": [{ "role": "user", "content": content }]
+        })
+    }
+}
+
+// Join the text blocks of a Messages API response and read token usage
+pub fn parse_message(body: &serde_json::Value) -> Result<(String, Option<TokenUsage>), String> {
+    if body["type"] == "error" {
+        return Err(format!(
+            "Claude API error: {}",
+            body["error"]["message"].as_str().unwrap_or("unknown error")
+        ));
+    }
+
+    let blocks = body["content"].as_array().ok_or("Claude response has no content")?;
+    let text = blocks
+        .iter()
+        .filter(|block| block["type"] == "text")
+        .filter_map(|block| block["text"].as_str())
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    let usage = body["usage"]["input_tokens"].as_u64().map(|input| TokenUsage {
+        input_tokens: input as u32,
+        output_tokens: body["usage"]["output_tokens"].as_u64().unwrap_or(0) as u32,
+        ..Default::default()
+    });
+
+    Ok((text, usage))
+}
+
+#[async_trait]
+impl VisionProvider for ClaudeProvider {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn model(&self) -> &str {
+        &self.model
+    }
+
+    fn endpoint(&self) -> Option<&str> {
+        Some(&self.base_url)
+    }
+
+    async fn analyze(&self, request: &VisionRequest) -> Result<VisionResponse, String> {
+        crate::local_only::check_url(&self.base_url)?;
+        crate::offline::check_url(&self.base_url)?;
+
+        let client = network::client_builder()
+            .timeout(std::time::Duration::from_millis(request.timeout_ms))
+            .build()
+            .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
+
+        let response = client
+            .post(format!("{}/messages", self.base_url))
+            .header("x-api-key", &self.api_key)
+            .header("anthropic-version", API_VERSION)
+            .json(&self.build_payload(request))
+            .send()
+            .await
+            .map_err(|e| format!("Failed to reach Claude API: {}", e))?;
+
+        let status = response.status();
+        let body: serde_json::Value = response
+            .json()
+            .await
+            .map_err(|e| format!("Failed to parse response: {}", e))?;
+        if !status.is_success() && body["type"] != "error" {
+            return Err(format!("Analysis failed: {}", status));
+        }
+
+        let (text, usage) = parse_message(&body)?;
+        Ok(VisionResponse {
+            provider: self.name.clone(),
+            model: request.model_or(&self.model).to_string(),
+            text,
+            usage,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_payload_and_message_parsing() {
+        let settings = ProviderSettings {
+            kind: "claude".to_string(),
+            api_key: Some("sk-ant-test".to_string()),
+            base_url: None,
+            model: None,
+            options: None,
+        };
+        let provider = ClaudeProvider::from_settings("claude", &settings).unwrap();
+        let request = VisionRequest {
+            prompt: "What is happening?".to_string(),
+            images: vec!["AAAA".to_string()],
+            timeout_ms: 1000,
+            max_tokens: Some(100),
+            ..Default::default()
+        };
+
+        let payload = provider.build_payload(&request);
+        assert_eq!(payload["model"], DEFAULT_MODEL);
+        assert_eq!(payload["messages"][0]["content"][0]["source"]["data"], "AAAA");
+        assert_eq!(payload["messages"][0]["content"][1]["text"], "What is happening?");
+
+        let body = serde_json::json!({
+            "type": "message",
+            "content": [{ "type": "text", "text": "A queue at the counter." }],
+            "usage": { "input_tokens": 900, "output_tokens": 8 }
+        });
+        let (text, usage) = parse_message(&body).unwrap();
+        assert_eq!(text, "A queue at the counter.");
+        assert_eq!(usage.unwrap().output_tokens, 8);
+
+        let error = serde_json::json!({ "type": "error", "error": { "message": "overloaded" } });
+        assert!(parse_message(&error).unwrap_err().contains("overloaded"));
+    }
+}
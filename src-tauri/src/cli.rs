@@ -0,0 +1,307 @@
+// CLI Module - Headless front end behind the lva-cli binary
+// detect, analyze and monitor write one JSON object per line to stdout or --output
+
+use crate::benchmark;
+use crate::frame_processor;
+use crate::pipeline::{self, Backends};
+use crate::settings::{self, AppSettings};
+use crate::smoothing::CountSmoother;
+use crate::video_source::{self, VideoAnalysisOptions};
+use std::io::Write;
+use std::path::Path;
+
+const USAGE: &str = "Usage:
+  lva-cli detect <image>...
+  lva-cli analyze <image>... [--provider llava] [--prompt TEXT]
+  lva-cli monitor --rtsp URL [--interval SECS] [--provider llava] [--prompt TEXT]
+                  [--min-people N] [--gap SECS] [--max-frames N]
+
+Options:
+  -o, --output PATH   Append JSONL to PATH instead of stdout";
+
+#[derive(Debug, Default, PartialEq)]
+struct Args {
+    command: String,
+    inputs: Vec<String>,
+    provider: Option<String>,
+    prompt: Option<String>,
+    output: Option<String>,
+    rtsp: Option<String>,
+    interval_secs: Option<f64>,
+    min_people: Option<u32>,
+    gap_secs: Option<f64>,
+    max_frames: Option<usize>,
+}
+
+fn parse_args(args: &[String]) -> Result<Args, String> {
+    let mut iter = args.iter();
+    let mut parsed = Args {
+        command: iter.next().cloned().ok_or("Missing command")?,
+        ..Default::default()
+    };
+
+    while let Some(arg) = iter.next() {
+        let mut value = || iter.next().cloned().ok_or_else(|| format!("{} needs a value", arg));
+        let number = |v: String| v.parse::<f64>().map_err(|_| format!("{} expects a number, got '{}'", arg, v));
+        match arg.as_str() {
+            "--provider" => parsed.provider = Some(value()?),
+            "--prompt" => parsed.prompt = Some(value()?),
+            "-o" | "--output" => parsed.output = Some(value()?),
+            "--rtsp" => parsed.rtsp = Some(value()?),
+            "--interval" => parsed.interval_secs = Some(number(value()?)?),
+            "--gap" => parsed.gap_secs = Some(number(value()?)?),
+            "--min-people" => parsed.min_people = Some(number(value()?)? as u32),
+            "--max-frames" => parsed.max_frames = Some(number(value()?)? as usize),
+            flag if flag.starts_with('-') => return Err(format!("Unknown option: {}", flag)),
+            input => parsed.inputs.push(input.to_string()),
+        }
+    }
+
+    Ok(parsed)
+}
+
+// JSONL sink; each record is flushed so `tail -f` and pipes see it immediately
+struct Output(Box<dyn Write>);
+
+impl Output {
+    fn open(path: Option<&str>) -> Result<Self, String> {
+        match path {
+            Some(path) => {
+                let file = std::fs::OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(path)
+                    .map_err(|e| format!("Failed to open {}: {}", path, e))?;
+                Ok(Output(Box::new(file)))
+            }
+            None => Ok(Output(Box::new(std::io::stdout()))),
+        }
+    }
+
+    fn write(&mut self, record: serde_json::Value) -> Result<(), String> {
+        writeln!(self.0, "{}", record).and_then(|_| self.0.flush()).map_err(|e| e.to_string())
+    }
+}
+
+fn now() -> String {
+    chrono::Utc::now().to_rfc3339()
+}
+
+// Returns the number of inputs that failed
+async fn detect(backends: &Backends, args: &Args, out: &mut Output) -> Result<usize, String> {
+    let mut failures = 0;
+    for input in &args.inputs {
+        let result = match frame_processor::load_frame_file(Path::new(input)) {
+            Ok(frame) => backends.yolo.detect(&frame).await,
+            Err(e) => Err(e),
+        };
+        let record = match result {
+            Ok(detection) => serde_json::json!({ "image": input, "timestamp": now(), "detection": detection }),
+            Err(e) => {
+                failures += 1;
+                serde_json::json!({ "image": input, "timestamp": now(), "error": e })
+            }
+        };
+        out.write(record)?;
+    }
+    Ok(failures)
+}
+
+async fn analyze(backends: &Backends, args: &Args, out: &mut Output) -> Result<usize, String> {
+    let provider = args.provider.clone().unwrap_or_else(|| "llava".to_string());
+    let prompt = args.prompt.clone().unwrap_or_else(|| benchmark::DEFAULT_BENCHMARK_PROMPT.to_string());
+    pipeline::ensure_provider_ready(&backends.providers, &provider).await?;
+
+    let mut failures = 0;
+    for input in &args.inputs {
+        let result = match frame_processor::load_frame_file(Path::new(input)) {
+            Ok(frame) => pipeline::describe_frame(&backends.moondream, &backends.providers, &provider, &prompt, frame).await,
+            Err(e) => Err(e),
+        };
+        let mut record = serde_json::json!({ "image": input, "timestamp": now(), "provider": provider });
+        match result {
+            Ok(Some((description, analysis))) => {
+                record["description"] = serde_json::json!(description);
+                record["result"] = analysis;
+            }
+            Ok(None) => {}
+            Err(e) => {
+                failures += 1;
+                record["error"] = serde_json::json!(e);
+            }
+        }
+        out.write(record)?;
+    }
+    Ok(failures)
+}
+
+// Poll a live stream, log person-count changes and escalate them to the VLM
+async fn monitor(backends: &Backends, args: &Args, out: &mut Output) -> Result<usize, String> {
+    let url = args.rtsp.clone().ok_or("monitor needs --rtsp URL")?;
+    let options = VideoAnalysisOptions {
+        provider: args.provider.clone(),
+        prompt: args.prompt.clone(),
+        min_people: args.min_people,
+        min_analysis_gap_secs: args.gap_secs,
+        ..Default::default()
+    };
+    let provider = options.provider.clone().unwrap_or_else(|| "llava".to_string());
+    let prompt = options
+        .prompt
+        .clone()
+        .unwrap_or_else(|| benchmark::DEFAULT_BENCHMARK_PROMPT.to_string());
+    pipeline::ensure_provider_ready(&backends.providers, &provider).await?;
+
+    let interval = std::time::Duration::from_secs_f64(args.interval_secs.unwrap_or(2.0).max(0.1));
+    let start_time = std::time::Instant::now();
+    let mut previous_count: Option<u32> = None;
+    let mut last_analysis_secs: Option<f64> = None;
+    let mut smoother = CountSmoother::new(settings::current().smoothing);
+    let mut frames = 0;
+    let mut failures = 0;
+    eprintln!("Monitoring {} every {:?} (provider: {})", url, interval, provider);
+
+    while args.max_frames.map(|max| frames < max).unwrap_or(true) {
+        frames += 1;
+        let offset_secs = start_time.elapsed().as_secs_f64();
+        let frame = match video_source::grab_stream_frame(&url)
+            .await
+            .and_then(|frame| frame_processor::prepare_frame(frame, None))
+        {
+            Ok(frame) => frame,
+            Err(e) => {
+                // Cameras drop out; keep polling
+                failures += 1;
+                eprintln!("{}", e);
+                tokio::time::sleep(interval).await;
+                continue;
+            }
+        };
+
+        let mut detection = match backends.yolo.detect(&frame).await {
+            Ok(detection) => detection,
+            Err(e) => {
+                failures += 1;
+                eprintln!("Detection failed: {}", e);
+                tokio::time::sleep(interval).await;
+                continue;
+            }
+        };
+
+        smoother.apply(&mut detection);
+        if previous_count != Some(detection.stable_person_count()) {
+            out.write(serde_json::json!({
+                "timestamp": now(),
+                "source": url,
+                "event_type": "detection",
+                "detection": detection
+            }))?;
+        }
+
+        if video_source::should_escalate(&options, &detection, previous_count, last_analysis_secs, offset_secs) {
+            last_analysis_secs = Some(offset_secs);
+            let mut record = serde_json::json!({
+                "timestamp": now(),
+                "source": url,
+                "event_type": "analysis",
+                "provider": provider,
+                "person_count": detection.stable_person_count()
+            });
+            match pipeline::describe_frame(&backends.moondream, &backends.providers, &provider, &prompt, frame).await {
+                Ok(Some((description, analysis))) => {
+                    record["description"] = serde_json::json!(description);
+                    record["result"] = analysis;
+                }
+                Ok(None) => {}
+                Err(e) => {
+                    failures += 1;
+                    record["error"] = serde_json::json!(e);
+                }
+            }
+            out.write(record)?;
+        }
+        previous_count = Some(detection.stable_person_count());
+
+        tokio::time::sleep(interval).await;
+    }
+    Ok(failures)
+}
+
+async fn run_command(args: Args) -> Result<usize, String> {
+    if args.command != "monitor" && args.inputs.is_empty() {
+        return Err(format!("{} needs at least one image", args.command));
+    }
+    if !matches!(args.command.as_str(), "detect" | "analyze" | "monitor") {
+        return Err(format!("Unknown command: {}", args.command));
+    }
+
+    let mut out = Output::open(args.output.as_deref())?;
+    let backends = pipeline::init_backends().await;
+    match args.command.as_str() {
+        "detect" => detect(&backends, &args, &mut out).await,
+        "analyze" => analyze(&backends, &args, &mut out).await,
+        _ => monitor(&backends, &args, &mut out).await,
+    }
+}
+
+// Entry point for lva-cli; returns the process exit code
+pub fn run(args: Vec<String>) -> i32 {
+    if args.is_empty() || matches!(args[0].as_str(), "-h" | "--help" | "help") {
+        eprintln!("{}", USAGE);
+        return if args.is_empty() { 2 } else { 0 };
+    }
+    let args = match parse_args(&args) {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("{}\n\n{}", e, USAGE);
+            return 2;
+        }
+    };
+
+    settings::init(AppSettings::load(&AppSettings::default_path()));
+    let runtime = match tokio::runtime::Runtime::new() {
+        Ok(runtime) => runtime,
+        Err(e) => {
+            eprintln!("Failed to start runtime: {}", e);
+            return 1;
+        }
+    };
+
+    match runtime.block_on(run_command(args)) {
+        Ok(0) => 0,
+        Ok(failures) => {
+            eprintln!("{} input(s) failed", failures);
+            1
+        }
+        Err(e) => {
+            eprintln!("{}", e);
+            1
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(line: &str) -> Vec<String> {
+        line.split_whitespace().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_parse_args() {
+        let parsed = parse_args(&args("monitor --rtsp rtsp://cam/stream --interval 0.5 --min-people 3 -o out.jsonl")).unwrap();
+        assert_eq!(parsed.command, "monitor");
+        assert_eq!(parsed.rtsp.as_deref(), Some("rtsp://cam/stream"));
+        assert_eq!(parsed.interval_secs, Some(0.5));
+        assert_eq!(parsed.min_people, Some(3));
+        assert_eq!(parsed.output.as_deref(), Some("out.jsonl"));
+
+        let parsed = parse_args(&args("detect a.jpg b.png")).unwrap();
+        assert_eq!(parsed.inputs, vec!["a.jpg", "b.png"]);
+
+        assert!(parse_args(&args("detect --interval")).is_err());
+        assert!(parse_args(&args("monitor --interval soon")).is_err());
+        assert!(parse_args(&args("detect --verbose")).is_err());
+    }
+}
@@ -0,0 +1,262 @@
+// Config Module - Export/import of the whole configuration and named configuration profiles
+// A profile ("Retail daytime", "Security night") bundles zones, shifts, rules and model choices that can be switched in one go
+
+use crate::anpr::AnprSettings;
+use crate::enhance::LowLightSettings;
+use crate::event_store::format_timestamp;
+use crate::fire::FireSettings;
+use crate::pacing::PacingSettings;
+use crate::plugins;
+use crate::ppe::PpeSettings;
+use crate::privacy::PrivacyMask;
+use crate::rules::{self, Rule};
+use crate::scene_prompts::{self, PromptHistory};
+use crate::scripting::Script;
+use crate::settings::{self, AppSettings};
+use crate::staff::StaffSettings;
+use crate::zones::{self, Zone};
+use serde::{Deserialize, Serialize};
+
+// Bumped when an export can no longer be read by older versions
+pub const CONFIG_FORMAT: u32 = 1;
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct ConfigProfile {
+    pub vision_model: String,
+    pub active_profile: String,  // Inference profile
+    pub default_provider: String,
+    pub rules: Vec<Rule>,
+    pub scripts: Vec<Script>,
+    pub ppe: PpeSettings,  // PPE items and shifts
+    pub fire: FireSettings,
+    pub anpr: AnprSettings,
+    pub scene_prompts: PromptHistory,  // Per-store tuning of the retail scene prompts
+    pub staff: StaffSettings,
+    pub privacy_masks: Vec<PrivacyMask>,
+    pub zones: Vec<Zone>,
+    pub low_light: LowLightSettings,
+    pub pacing: PacingSettings,
+}
+
+impl Default for ConfigProfile {
+    fn default() -> Self {
+        ConfigProfile::capture(&AppSettings::default())
+    }
+}
+
+impl ConfigProfile {
+    pub fn capture(settings: &AppSettings) -> Self {
+        ConfigProfile {
+            vision_model: settings.vision_model.clone(),
+            active_profile: settings.active_profile.clone(),
+            default_provider: settings.default_provider.clone(),
+            rules: settings.rules.clone(),
+            scripts: settings.scripts.clone(),
+            ppe: settings.ppe.clone(),
+            fire: settings.fire.clone(),
+            anpr: settings.anpr.clone(),
+            scene_prompts: settings.scene_prompts.clone(),
+            staff: settings.staff.clone(),
+            privacy_masks: settings.privacy_masks.clone(),
+            zones: settings.zones.clone(),
+            low_light: settings.low_light.clone(),
+            pacing: settings.pacing.clone(),
+        }
+    }
+
+    pub fn apply_to(&self, settings: &mut AppSettings) {
+        let profile = self.clone();
+        settings.vision_model = profile.vision_model;
+        settings.active_profile = profile.active_profile;
+        settings.default_provider = profile.default_provider;
+        settings.rules = profile.rules;
+        settings.scripts = profile.scripts;
+        settings.ppe = profile.ppe;
+        settings.fire = profile.fire;
+        settings.anpr = profile.anpr;
+        settings.scene_prompts = profile.scene_prompts;
+        settings.staff = profile.staff;
+        settings.privacy_masks = profile.privacy_masks;
+        settings.zones = profile.zones;
+        settings.low_light = profile.low_light;
+        settings.pacing = profile.pacing;
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ConfigExport {
+    pub format: u32,
+    pub exported_at: String,
+    pub settings: AppSettings,
+}
+
+// Credentials and the storage key stay on this machine; import puts the local ones back
+pub fn export(settings: &AppSettings) -> ConfigExport {
+    let mut settings = settings.clone();
+    settings.api_key = None;
+    settings.api_keys.clear();
+    settings.encryption = Default::default();
+    settings.object_storage.secret_access_key.clear();
+    settings.mqtt.password = None;
+    settings.network.proxy_password = None;
+    for target in &mut settings.push.targets {
+        target.access_token = None;
+    }
+    for instance in &mut settings.remote_instances {
+        instance.api_key.clear();
+    }
+    ConfigExport {
+        format: CONFIG_FORMAT,
+        exported_at: format_timestamp(chrono::Utc::now()),
+        settings,
+    }
+}
+
+fn restore_secrets(imported: &mut AppSettings, current: &AppSettings) {
+    if imported.api_key.is_none() {
+        imported.api_key = current.api_key.clone();
+    }
+    if imported.api_keys.is_empty() {
+        imported.api_keys = current.api_keys.clone();
+    }
+    // Only a backup's own settings.json carries the key material for its database
+    if imported.encryption.salt.is_empty() {
+        imported.encryption = current.encryption.clone();
+    }
+    let storage = &mut imported.object_storage;
+    if storage.secret_access_key.is_empty() && storage.access_key_id == current.object_storage.access_key_id {
+        storage.secret_access_key = current.object_storage.secret_access_key.clone();
+    }
+    if imported.mqtt.password.is_none() && imported.mqtt.username == current.mqtt.username {
+        imported.mqtt.password = current.mqtt.password.clone();
+    }
+    if imported.network.proxy_password.is_none() && imported.network.proxy_username == current.network.proxy_username {
+        imported.network.proxy_password = current.network.proxy_password.clone();
+    }
+    for target in imported.push.targets.iter_mut().filter(|t| t.access_token.is_none()) {
+        if let Some(local) = current.push.targets.iter().find(|t| t.name == target.name && t.topic == target.topic) {
+            target.access_token = local.access_token.clone();
+        }
+    }
+    for instance in imported.remote_instances.iter_mut().filter(|i| i.api_key.is_empty()) {
+        if let Some(local) = current.remote_instances.iter().find(|r| r.name == instance.name) {
+            instance.api_key = local.api_key.clone();
+        }
+    }
+}
+
+fn validate_profile(profile: &ConfigProfile, settings: &AppSettings) -> Result<(), String> {
+    settings::validate_vision_model(&profile.vision_model)?;
+    settings.profile(Some(&profile.active_profile))?;
+    rules::validate(&profile.rules, &profile.zones)?;
+    profile.ppe.validate()?;
+    profile.fire.validate()?;
+    profile.anpr.validate()?;
+    scene_prompts::validate(&profile.scene_prompts)?;
+    profile.staff.validate()?;
+    profile.low_light.validate()?;
+    profile.pacing.validate()?;
+    zones::validate(&profile.zones)?;
+    profile.privacy_masks.iter().try_for_each(PrivacyMask::validate)
+}
+
+// Everything the individual set_* commands would check; scripts need the script host and are checked by the caller
+pub fn validate(settings: &AppSettings) -> Result<(), String> {
+    validate_profile(&ConfigProfile::capture(settings), settings)?;
+    for (name, profile) in &settings.config_profiles {
+        validate_profile(profile, settings).map_err(|e| format!("Configuration profile '{}': {}", name, e))?;
+    }
+    if let Some(active) = settings.active_config.as_deref().filter(|a| !settings.config_profiles.contains_key(*a)) {
+        return Err(format!("Unknown configuration profile: {}", active));
+    }
+    settings.profiles.values().try_for_each(|p| p.validate())?;
+    settings.smoothing.validate()?;
+    settings.reid.validate()?;
+    settings.fisheye.values().try_for_each(|c| c.validate())?;
+    settings.floor_calibrations.values().try_for_each(|c| c.homography().map(|_| ()))?;
+    plugins::validate(&settings.plugins)?;
+    settings.remote_instances.iter().try_for_each(|i| i.validate())?;
+    if settings.object_storage.enabled {
+        settings.object_storage.validate()?;
+    }
+    if settings.mqtt.enabled {
+        settings.mqtt.validate()?;
+    }
+    settings.retention.validate()?;
+    settings.incidents.validate()?;
+    settings.tts.validate()?;
+    settings.desktop_notifications.validate()?;
+    settings.chime.validate()?;
+    if settings.push.enabled {
+        settings.push.validate()?;
+    }
+    if settings.verification.enabled {
+        settings.verification.validate()?;
+    }
+    settings.suppression.validate()?;
+    settings.preview.validate()?;
+    settings.network.validate()?;
+    settings.power.validate(&settings.profiles)?;
+    settings.slo.validate(&settings.profiles)
+}
+
+// An export file, or a bare settings.json
+pub fn import(json: &str, current: &AppSettings) -> Result<AppSettings, String> {
+    let mut value: serde_json::Value = serde_json::from_str(json).map_err(|e| format!("Invalid configuration: {}", e))?;
+    let format = value["format"].as_u64().unwrap_or(0);
+    let mut imported: AppSettings = match value.get_mut("settings") {
+        Some(settings) => {
+            if format > CONFIG_FORMAT as u64 {
+                return Err(format!("Configuration format {} is newer than this version supports ({})", format, CONFIG_FORMAT));
+            }
+            // Exports from before rule and PPE areas were zones
+            zones::adopt_legacy_areas(settings);
+            serde_json::from_value(settings.clone())
+        }
+        None => {
+            zones::adopt_legacy_areas(&mut value);
+            serde_json::from_value(value)
+        }
+    }
+    .map_err(|e| format!("Invalid configuration: {}", e))?;
+    restore_secrets(&mut imported, current);
+    validate(&imported)?;
+    Ok(imported)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_export_import_and_profiles() {
+        let mut current = AppSettings { api_key: Some("local-key".to_string()), ..Default::default() };
+        current.mqtt.username = Some("frigate".to_string());
+        current.mqtt.password = Some("hunter2".to_string());
+
+        let mut night = ConfigProfile::capture(&current);
+        night.vision_model = "llava:13b".to_string();
+        night.active_profile = "quality".to_string();
+        current.config_profiles.insert("Security night".to_string(), night.clone());
+
+        let exported = serde_json::to_string(&export(&current)).unwrap();
+        assert!(!exported.contains("local-key") && !exported.contains("hunter2"));
+
+        let imported = import(&exported, &current).unwrap();
+        assert_eq!(imported.api_key.as_deref(), Some("local-key"));
+        assert_eq!(imported.mqtt.password.as_deref(), Some("hunter2"));
+        assert_eq!(imported.config_profiles["Security night"], night);
+
+        let mut switched = imported.clone();
+        night.apply_to(&mut switched);
+        assert_eq!((switched.vision_model.as_str(), switched.active_profile.as_str()), ("llava:13b", "quality"));
+
+        // A bare settings file works too, but a broken profile is rejected
+        night.active_profile = "turbo".to_string();
+        current.config_profiles.insert("broken".to_string(), night);
+        let bare = serde_json::to_string(&current).unwrap();
+        assert!(import(&bare, &current).unwrap_err().contains("broken"));
+        assert!(import("{\"format\": 99, \"settings\": {}}", &current).is_err());
+    }
+}
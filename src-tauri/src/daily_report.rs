@@ -0,0 +1,417 @@
+// Daily Report Module - Aggregates a day's events into a Markdown/HTML summary
+// Runs on a schedule after midnight and on demand through generate_report
+
+use crate::event_store::{format_timestamp, EventStore, StoredEvent};
+use crate::history_query::local_at;
+use crate::ollama_manager::OllamaManager;
+use chrono::{DateTime, Duration, Local, NaiveDate, NaiveTime, Timelike};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+// How many VLM descriptions make it into the report
+const MAX_NOTABLE_DESCRIPTIONS: usize = 8;
+
+// Minutes after midnight when the scheduled report for the previous day runs
+const SCHEDULE_DELAY_MINUTES: i64 = 5;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct NotableDescription {
+    pub timestamp: String,
+    pub provider: Option<String>,
+    pub description: String,
+}
+
+// Aggregated metrics for one calendar day (local time)
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct DailyStats {
+    pub date: String,
+    pub footfall: u32,
+    pub returning_visitors: u32,  // Re-identified people who came back; 0 without re-identification
+    pub peak_occupancy: u32,
+    pub peak_time: Option<String>,
+    pub average_occupancy: f32,
+    pub detection_samples: usize,
+    pub analysis_count: usize,
+    pub alert_count: usize,
+    pub hourly_footfall: Vec<u32>,  // 24 buckets, local hours
+    pub notable_descriptions: Vec<NotableDescription>,
+}
+
+// Generated report returned by the generate_report command
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct DailyReport {
+    pub date: String,
+    pub format: String,
+    pub path: String,
+    pub stats: DailyStats,
+    pub narrative: Option<String>,
+    pub content: String,
+}
+
+pub fn reports_dir() -> PathBuf {
+    let home_dir = std::env::var("HOME").unwrap_or_else(|_| "/tmp".to_string());
+    PathBuf::from(home_dir).join(".live-vision-analyzer").join("reports")
+}
+
+// Local day boundaries as stored-timestamp strings
+pub fn day_bounds(date: NaiveDate) -> (String, String) {
+    let from = local_at(date, NaiveTime::MIN);
+    let to = local_at(date + Duration::days(1), NaiveTime::MIN);
+    (format_timestamp(from), format_timestamp(to))
+}
+
+fn local_hour(timestamp: &str) -> Option<usize> {
+    DateTime::parse_from_rfc3339(timestamp)
+        .ok()
+        .map(|t| t.with_timezone(&Local).hour() as usize)
+}
+
+// Compute the day's metrics from its events (expected in chronological order)
+pub fn compute_stats(date: NaiveDate, events: &[StoredEvent]) -> DailyStats {
+    let mut footfall = 0;
+    let mut returning_visitors = 0;
+    let mut hourly_footfall = vec![0u32; 24];
+    let mut peak_occupancy = 0;
+    let mut peak_time = None;
+    let mut occupancy_sum = 0u64;
+    let mut detection_samples = 0;
+    let mut previous_count = 0;
+    let mut analyses = Vec::new();
+    let mut alert_count = 0;
+
+    for event in events {
+        match event.event_type.as_str() {
+            "detection" => {
+                // Staff tagged by the classifier are not part of occupancy or footfall
+                let staff = event.payload.as_ref().and_then(|p| p["staff_count"].as_u64()).unwrap_or(0) as u32;
+                let count = event.person_count.unwrap_or(0).saturating_sub(staff);
+                detection_samples += 1;
+                occupancy_sum += count as u64;
+
+                // Re-identified arrivals when available, otherwise increases in the person count
+                let visitors = event.payload.as_ref().and_then(|p| p.get("visitors")).filter(|v| !v.is_null());
+                let arrivals = match visitors {
+                    Some(visitors) => {
+                        returning_visitors += visitors["returning"].as_u64().unwrap_or(0) as u32;
+                        let new = visitors["new"].as_u64().unwrap_or(0) as u32;
+                        new.saturating_sub(visitors["new_staff"].as_u64().unwrap_or(0) as u32)
+                    }
+                    None => count.saturating_sub(previous_count),
+                };
+                if arrivals > 0 {
+                    footfall += arrivals;
+                    if let Some(hour) = local_hour(&event.timestamp) {
+                        hourly_footfall[hour] += arrivals;
+                    }
+                }
+                previous_count = count;
+
+                if count > peak_occupancy {
+                    peak_occupancy = count;
+                    peak_time = Some(event.timestamp.clone());
+                }
+            }
+            "analysis" => analyses.push(event),
+            "alert" => alert_count += 1,
+            _ => {}
+        }
+    }
+
+    // Prefer the longest descriptions - they tend to carry the most detail
+    let mut notable: Vec<&StoredEvent> = analyses
+        .iter()
+        .copied()
+        .filter(|e| e.description.as_deref().map(|d| !d.trim().is_empty()).unwrap_or(false))
+        .collect();
+    notable.sort_by_key(|e| std::cmp::Reverse(e.description.as_ref().map(|d| d.len()).unwrap_or(0)));
+    notable.truncate(MAX_NOTABLE_DESCRIPTIONS);
+    notable.sort_by(|a, b| a.timestamp.cmp(&b.timestamp));
+
+    DailyStats {
+        date: date.format("%Y-%m-%d").to_string(),
+        footfall,
+        returning_visitors,
+        peak_occupancy,
+        peak_time,
+        average_occupancy: if detection_samples > 0 {
+            occupancy_sum as f32 / detection_samples as f32
+        } else {
+            0.0
+        },
+        detection_samples,
+        analysis_count: analyses.len(),
+        alert_count,
+        hourly_footfall,
+        notable_descriptions: notable
+            .into_iter()
+            .map(|e| NotableDescription {
+                timestamp: e.timestamp.clone(),
+                provider: e.provider.clone(),
+                description: e.description.clone().unwrap_or_default(),
+            })
+            .collect(),
+    }
+}
+
+fn format_local_time(timestamp: &str) -> String {
+    DateTime::parse_from_rfc3339(timestamp)
+        .map(|t| t.with_timezone(&Local).format("%H:%M").to_string())
+        .unwrap_or_else(|_| timestamp.to_string())
+}
+
+pub fn render_markdown(stats: &DailyStats, narrative: Option<&str>) -> String {
+    let mut md = format!("# Daily Report - {}\n\n", stats.date);
+
+    if let Some(narrative) = narrative {
+        md.push_str(&format!("## Summary\n\n{}\n\n", narrative.trim()));
+    }
+
+    md.push_str("## Key Metrics\n\n| Metric | Value |\n|---|---|\n");
+    md.push_str(&format!("| Estimated footfall | {} |\n", stats.footfall));
+    if stats.returning_visitors > 0 {
+        md.push_str(&format!("| Returning visitors | {} |\n", stats.returning_visitors));
+    }
+    md.push_str(&format!(
+        "| Peak occupancy | {}{} |\n",
+        stats.peak_occupancy,
+        stats.peak_time.as_deref().map(|t| format!(" at {}", format_local_time(t))).unwrap_or_default()
+    ));
+    md.push_str(&format!("| Average occupancy | {:.1} |\n", stats.average_occupancy));
+    md.push_str(&format!("| VLM analyses | {} |\n", stats.analysis_count));
+    md.push_str(&format!("| Alerts | {} |\n\n", stats.alert_count));
+
+    md.push_str("## Footfall by Hour\n\n| Hour | Arrivals |\n|---|---|\n");
+    for (hour, count) in stats.hourly_footfall.iter().enumerate().filter(|(_, c)| **c > 0) {
+        md.push_str(&format!("| {:02}:00 | {} |\n", hour, count));
+    }
+    md.push('\n');
+
+    if !stats.notable_descriptions.is_empty() {
+        md.push_str("## Notable Observations\n\n");
+        for note in &stats.notable_descriptions {
+            md.push_str(&format!(
+                "- **{}** ({}): {}\n",
+                format_local_time(&note.timestamp),
+                note.provider.as_deref().unwrap_or("unknown"),
+                note.description.replace('\n', " ")
+            ));
+        }
+    }
+
+    md
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
+pub fn render_html(stats: &DailyStats, narrative: Option<&str>) -> String {
+    let mut body = format!("<h1>Daily Report - {}</h1>\n", stats.date);
+
+    if let Some(narrative) = narrative {
+        body.push_str(&format!("<h2>Summary</h2>\n<p>{}</p>\n", escape_html(narrative.trim())));
+    }
+
+    body.push_str("<h2>Key Metrics</h2>\n<table>\n");
+    let peak = format!(
+        "{}{}",
+        stats.peak_occupancy,
+        stats.peak_time.as_deref().map(|t| format!(" at {}", format_local_time(t))).unwrap_or_default()
+    );
+    let mut rows = vec![("Estimated footfall", stats.footfall.to_string())];
+    if stats.returning_visitors > 0 {
+        rows.push(("Returning visitors", stats.returning_visitors.to_string()));
+    }
+    rows.extend([
+        ("Peak occupancy", peak),
+        ("Average occupancy", format!("{:.1}", stats.average_occupancy)),
+        ("VLM analyses", stats.analysis_count.to_string()),
+        ("Alerts", stats.alert_count.to_string()),
+    ]);
+    for (label, value) in rows {
+        body.push_str(&format!("<tr><th>{}</th><td>{}</td></tr>\n", label, value));
+    }
+    body.push_str("</table>\n");
+
+    body.push_str("<h2>Footfall by Hour</h2>\n<table>\n<tr><th>Hour</th><th>Arrivals</th></tr>\n");
+    for (hour, count) in stats.hourly_footfall.iter().enumerate().filter(|(_, c)| **c > 0) {
+        body.push_str(&format!("<tr><td>{:02}:00</td><td>{}</td></tr>\n", hour, count));
+    }
+    body.push_str("</table>\n");
+
+    if !stats.notable_descriptions.is_empty() {
+        body.push_str("<h2>Notable Observations</h2>\n<ul>\n");
+        for note in &stats.notable_descriptions {
+            body.push_str(&format!(
+                "<li><strong>{}</strong> ({}): {}</li>\n",
+                format_local_time(&note.timestamp),
+                escape_html(note.provider.as_deref().unwrap_or("unknown")),
+                escape_html(&note.description)
+            ));
+        }
+        body.push_str("</ul>\n");
+    }
+
+    format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>Daily Report - {}</title>\n\
+         <style>body{{font-family:sans-serif;max-width:800px;margin:2em auto}}table{{border-collapse:collapse}}\
+         th,td{{border:1px solid #ccc;padding:4px 10px;text-align:left}}</style>\n</head>\n<body>\n{}</body>\n</html>\n",
+        stats.date, body
+    )
+}
+
+// Ask the local LLM for a short narrative summary of the day's numbers
+async fn generate_narrative(stats: &DailyStats) -> Option<String> {
+    let status = OllamaManager::check_status().await;
+    if !status.running || !status.model_ready {
+        println!("DailyReport: Ollama not ready, skipping narrative");
+        return None;
+    }
+
+    let prompt = format!(
+        "You are writing the daily operations report for a store manager. Using only the data below, \
+         write a concise 3-5 sentence summary covering traffic, busiest period, alerts and anything notable. \
+         Do not invent numbers.\n\n{}",
+        serde_json::to_string_pretty(stats).unwrap_or_default()
+    );
+
+    match OllamaManager::generate(&prompt, Vec::new(), 60000, None).await {
+        Ok(result) => result["response"].as_str().map(|s| s.trim().to_string()).filter(|s| !s.is_empty()),
+        Err(e) => {
+            eprintln!("DailyReport: Failed to generate narrative: {}", e);
+            None
+        }
+    }
+}
+
+// Build the report for a date and save it to the reports directory
+pub async fn generate_report(
+    events: &Arc<Mutex<EventStore>>,
+    date: NaiveDate,
+    format: &str,
+    use_llm: bool,
+) -> Result<DailyReport, String> {
+    let (from, to) = day_bounds(date);
+    let day_events = events.lock().await.events_between(&from, &to)?;
+    let stats = compute_stats(date, &day_events);
+
+    let narrative = if use_llm { generate_narrative(&stats).await } else { None };
+
+    let (content, extension) = match format {
+        "html" => (render_html(&stats, narrative.as_deref()), "html"),
+        "markdown" | "md" => (render_markdown(&stats, narrative.as_deref()), "md"),
+        other => return Err(format!("Unknown report format: {}", other)),
+    };
+
+    let dir = reports_dir();
+    std::fs::create_dir_all(&dir).map_err(|e| format!("Failed to create reports directory: {}", e))?;
+    let path = dir.join(format!("report-{}.{}", stats.date, extension));
+    std::fs::write(&path, &content).map_err(|e| format!("Failed to write report: {}", e))?;
+
+    println!("DailyReport: Saved {} report to {}", stats.date, path.display());
+
+    Ok(DailyReport {
+        date: stats.date.clone(),
+        format: extension.to_string(),
+        path: path.display().to_string(),
+        stats,
+        narrative,
+        content,
+    })
+}
+
+// Background task: generate yesterday's report shortly after every local midnight
+pub async fn run_scheduler(events: Arc<Mutex<EventStore>>) {
+    loop {
+        let now = Local::now();
+        let next_run = local_at(now.date_naive() + Duration::days(1), NaiveTime::MIN)
+            + Duration::minutes(SCHEDULE_DELAY_MINUTES);
+        let wait = (next_run - now.with_timezone(&chrono::Utc)).to_std().unwrap_or_default();
+
+        tokio::time::sleep(wait).await;
+
+        let yesterday = Local::now().date_naive() - Duration::days(1);
+        if let Err(e) = generate_report(&events, yesterday, "html", true).await {
+            eprintln!("DailyReport: Scheduled report failed: {}", e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(event_type: &str, hour: u32, person_count: Option<u32>, description: Option<&str>) -> StoredEvent {
+        let date = NaiveDate::from_ymd_opt(2025, 3, 12).unwrap();
+        StoredEvent {
+            id: uuid::Uuid::new_v4().to_string(),
+            timestamp: format_timestamp(local_at(date, NaiveTime::from_hms_opt(hour, 0, 0).unwrap())),
+            camera_id: None,
+            event_type: event_type.to_string(),
+            person_count,
+            object_counts: None,
+            provider: Some("llava".to_string()),
+            prompt: None,
+            description: description.map(|d| d.to_string()),
+            payload: None,
+        }
+    }
+
+    #[test]
+    fn test_compute_stats() {
+        let date = NaiveDate::from_ymd_opt(2025, 3, 12).unwrap();
+        let events = vec![
+            event("detection", 9, Some(2), None),
+            event("detection", 10, Some(5), None),
+            event("analysis", 10, None, Some("Queue forming at the checkout")),
+            event("detection", 11, Some(1), None),
+            event("alert", 11, None, None),
+        ];
+
+        let stats = compute_stats(date, &events);
+        assert_eq!(stats.footfall, 5);
+        assert_eq!(stats.hourly_footfall[9], 2);
+        assert_eq!(stats.hourly_footfall[10], 3);
+        assert_eq!(stats.peak_occupancy, 5);
+        assert_eq!(stats.analysis_count, 1);
+        assert_eq!(stats.alert_count, 1);
+
+        let markdown = render_markdown(&stats, Some("A steady day."));
+        assert!(markdown.contains("| Estimated footfall | 5 |"));
+        assert!(markdown.contains("Queue forming at the checkout"));
+    }
+
+    #[test]
+    fn test_footfall_uses_reidentified_arrivals() {
+        let date = NaiveDate::from_ymd_opt(2025, 3, 12).unwrap();
+        let with_visitors = |hour, count, new, returning| StoredEvent {
+            payload: Some(serde_json::json!({ "visitors": { "new": new, "returning": returning, "unique": 0 } })),
+            ..event("detection", hour, Some(count), None)
+        };
+        // The same two people leave and come back: the count rises twice, but only two people visited
+        let events = vec![
+            with_visitors(9, 2, 2, 0),
+            with_visitors(9, 0, 0, 0),
+            with_visitors(10, 2, 0, 2),
+        ];
+
+        let stats = compute_stats(date, &events);
+        assert_eq!(stats.footfall, 2);
+        assert_eq!(stats.returning_visitors, 2);
+        assert!(render_markdown(&stats, None).contains("| Returning visitors | 2 |"));
+
+        // An employee arriving with a customer is left out of footfall and occupancy
+        let staffed = StoredEvent {
+            payload: Some(serde_json::json!({
+                "staff_count": 1,
+                "visitors": { "new": 2, "returning": 0, "unique": 2, "new_staff": 1 }
+            })),
+            ..event("detection", 11, Some(2), None)
+        };
+        let stats = compute_stats(date, &[staffed]);
+        assert_eq!(stats.footfall, 1);
+        assert_eq!(stats.peak_occupancy, 1);
+    }
+}
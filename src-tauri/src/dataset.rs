@@ -0,0 +1,245 @@
+// Dataset Module - Archived detection snapshots packaged for detector fine-tuning
+// Writes COCO (annotations.json) or YOLO (labels/*.txt + data.yaml) layouts from stored boxes
+
+use crate::encryption;
+use crate::yolo_detector::BoundingBox;
+use base64::{engine::general_purpose, Engine as _};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DatasetFormat {
+    Coco,
+    Yolo,
+}
+
+impl DatasetFormat {
+    pub fn parse(format: &str) -> Result<Self, String> {
+        match format.to_lowercase().as_str() {
+            "coco" => Ok(DatasetFormat::Coco),
+            "yolo" => Ok(DatasetFormat::Yolo),
+            other => Err(format!("Unsupported dataset format '{}' (use coco or yolo)", other)),
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            DatasetFormat::Coco => "coco",
+            DatasetFormat::Yolo => "yolo",
+        }
+    }
+}
+
+// One archived frame and the boxes that label it
+#[derive(Debug, Clone)]
+pub struct DatasetSample {
+    pub event_id: String,
+    pub image_path: PathBuf,
+    pub boxes: Vec<BoundingBox>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct DatasetSummary {
+    pub path: String,
+    pub format: String,
+    pub images: usize,
+    pub annotations: usize,
+    pub categories: Vec<String>,
+}
+
+pub fn snapshots_dir() -> PathBuf {
+    crate::storage::data_dir().join("snapshots")
+}
+
+pub fn snapshot_path(event_id: &str) -> PathBuf {
+    snapshots_dir().join(format!("{}.jpg", event_id))
+}
+
+// Keep the frame behind a recorded detection so it can be labelled later
+pub fn save_snapshot(event_id: &str, frame_base64: &str) -> Result<PathBuf, String> {
+    let bytes = general_purpose::STANDARD
+        .decode(frame_base64)
+        .map_err(|e| format!("Failed to decode snapshot: {}", e))?;
+    // Re-encode so every snapshot is a real JPEG whatever the camera sent
+    let image = image::load_from_memory(&bytes).map_err(|e| format!("Failed to decode snapshot: {}", e))?;
+    let path = snapshot_path(event_id);
+    std::fs::create_dir_all(snapshots_dir()).map_err(|e| format!("Failed to create snapshots directory: {}", e))?;
+    let mut jpeg = std::io::Cursor::new(Vec::new());
+    image
+        .to_rgb8()
+        .write_to(&mut jpeg, image::ImageFormat::Jpeg)
+        .map_err(|e| format!("Failed to encode snapshot: {}", e))?;
+    let stored = encryption::seal_bytes(&jpeg.into_inner())?;
+    std::fs::write(&path, stored).map_err(|e| format!("Failed to save snapshot: {}", e))?;
+    Ok(path)
+}
+
+// Clamp a box to the image; None when nothing is left
+fn clamp_box(b: &BoundingBox, width: u32, height: u32) -> Option<(f32, f32, f32, f32)> {
+    let x1 = b.x1.clamp(0.0, width as f32);
+    let y1 = b.y1.clamp(0.0, height as f32);
+    let x2 = b.x2.clamp(0.0, width as f32);
+    let y2 = b.y2.clamp(0.0, height as f32);
+    (x2 > x1 && y2 > y1).then_some((x1, y1, x2 - x1, y2 - y1))
+}
+
+pub fn export_dataset(samples: &[DatasetSample], format: DatasetFormat, out_dir: &Path) -> Result<DatasetSummary, String> {
+    let images_dir = out_dir.join("images");
+    std::fs::create_dir_all(&images_dir).map_err(|e| format!("Failed to create dataset directory: {}", e))?;
+
+    // Class ids in first-seen order; COCO ids start at 1, YOLO at 0
+    let mut categories: Vec<String> = Vec::new();
+    for b in samples.iter().flat_map(|s| &s.boxes) {
+        if !categories.contains(&b.class_name) {
+            categories.push(b.class_name.clone());
+        }
+    }
+    let class_id = |name: &str| categories.iter().position(|c| c == name).unwrap_or(0);
+
+    let mut coco_images = Vec::new();
+    let mut coco_annotations = Vec::new();
+    let mut annotation_count = 0;
+
+    for (index, sample) in samples.iter().enumerate() {
+        // Exported datasets are for training elsewhere, so images are written decrypted
+        let jpeg = encryption::read_file(&sample.image_path)?;
+        let (width, height) = image::load_from_memory(&jpeg)
+            .map(|i| (i.width(), i.height()))
+            .map_err(|e| format!("Failed to read {}: {}", sample.image_path.display(), e))?;
+        let file_name = format!("{}.jpg", sample.event_id);
+        std::fs::write(images_dir.join(&file_name), jpeg)
+            .map_err(|e| format!("Failed to copy {}: {}", sample.image_path.display(), e))?;
+
+        let boxes: Vec<(usize, (f32, f32, f32, f32))> = sample
+            .boxes
+            .iter()
+            .filter_map(|b| clamp_box(b, width, height).map(|rect| (class_id(&b.class_name), rect)))
+            .collect();
+        annotation_count += boxes.len();
+
+        match format {
+            DatasetFormat::Coco => {
+                coco_images.push(serde_json::json!({
+                    "id": index + 1,
+                    "file_name": file_name,
+                    "width": width,
+                    "height": height
+                }));
+                for (class, (x, y, w, h)) in boxes {
+                    coco_annotations.push(serde_json::json!({
+                        "id": coco_annotations.len() + 1,
+                        "image_id": index + 1,
+                        "category_id": class + 1,
+                        "bbox": [x, y, w, h],
+                        "area": w * h,
+                        "iscrowd": 0
+                    }));
+                }
+            }
+            DatasetFormat::Yolo => {
+                let labels_dir = out_dir.join("labels");
+                std::fs::create_dir_all(&labels_dir).map_err(|e| format!("Failed to create labels directory: {}", e))?;
+                let lines: Vec<String> = boxes
+                    .iter()
+                    .map(|(class, (x, y, w, h))| {
+                        format!(
+                            "{} {:.6} {:.6} {:.6} {:.6}",
+                            class,
+                            (x + w / 2.0) / width as f32,
+                            (y + h / 2.0) / height as f32,
+                            w / width as f32,
+                            h / height as f32
+                        )
+                    })
+                    .collect();
+                std::fs::write(labels_dir.join(format!("{}.txt", sample.event_id)), lines.join("\n"))
+                    .map_err(|e| format!("Failed to write labels: {}", e))?;
+            }
+        }
+    }
+
+    match format {
+        DatasetFormat::Coco => {
+            let coco = serde_json::json!({
+                "info": {
+                    "description": "Live Vision Analyzer detections",
+                    "date_created": chrono::Utc::now().to_rfc3339()
+                },
+                "images": coco_images,
+                "annotations": coco_annotations,
+                "categories": categories
+                    .iter()
+                    .enumerate()
+                    .map(|(i, name)| serde_json::json!({ "id": i + 1, "name": name }))
+                    .collect::<Vec<_>>()
+            });
+            let contents = serde_json::to_string_pretty(&coco).map_err(|e| e.to_string())?;
+            std::fs::write(out_dir.join("annotations.json"), contents)
+                .map_err(|e| format!("Failed to write annotations: {}", e))?;
+        }
+        DatasetFormat::Yolo => {
+            let names: BTreeMap<usize, &String> = categories.iter().enumerate().collect();
+            let mut yaml = format!("path: {}\ntrain: images\nval: images\nnames:\n", out_dir.display());
+            for (id, name) in names {
+                yaml.push_str(&format!("  {}: {}\n", id, name));
+            }
+            std::fs::write(out_dir.join("data.yaml"), yaml).map_err(|e| format!("Failed to write data.yaml: {}", e))?;
+        }
+    }
+
+    Ok(DatasetSummary {
+        path: out_dir.to_string_lossy().to_string(),
+        format: format.name().to_string(),
+        images: samples.len(),
+        annotations: annotation_count,
+        categories,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bbox(class_name: &str, x1: f32, y1: f32, x2: f32, y2: f32) -> BoundingBox {
+        BoundingBox::new(class_name, x1, y1, x2, y2, 0.9)
+    }
+
+    fn sample(dir: &Path) -> DatasetSample {
+        let image_path = dir.join("frame.jpg");
+        image::RgbImage::new(200, 100).save(&image_path).unwrap();
+        DatasetSample {
+            event_id: "e1".to_string(),
+            image_path,
+            boxes: vec![bbox("person", 50.0, 0.0, 100.0, 100.0), bbox("backpack", 150.0, 50.0, 250.0, 90.0)],
+        }
+    }
+
+    #[test]
+    fn test_coco_export() {
+        let dir = tempfile::tempdir().unwrap();
+        let out = dir.path().join("coco");
+        let summary = export_dataset(&[sample(dir.path())], DatasetFormat::Coco, &out).unwrap();
+        assert_eq!(summary.annotations, 2);
+        assert_eq!(summary.categories, vec!["person", "backpack"]);
+
+        let coco: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(out.join("annotations.json")).unwrap()).unwrap();
+        assert_eq!(coco["images"][0]["width"], 200);
+        assert_eq!(coco["annotations"][0]["bbox"], serde_json::json!([50.0, 0.0, 50.0, 100.0]));
+        // The second box is clipped to the image edge
+        assert_eq!(coco["annotations"][1]["bbox"][2], 50.0);
+        assert!(out.join("images").join("e1.jpg").exists());
+    }
+
+    #[test]
+    fn test_yolo_export() {
+        let dir = tempfile::tempdir().unwrap();
+        let out = dir.path().join("yolo");
+        export_dataset(&[sample(dir.path())], DatasetFormat::Yolo, &out).unwrap();
+
+        let labels = std::fs::read_to_string(out.join("labels").join("e1.txt")).unwrap();
+        assert_eq!(labels.lines().next().unwrap(), "0 0.375000 0.500000 0.250000 1.000000");
+        assert!(std::fs::read_to_string(out.join("data.yaml")).unwrap().contains("1: backpack"));
+    }
+}
@@ -0,0 +1,88 @@
+// Desktop Notify Module - Native desktop notifications for alerts
+// Shown through the OS notification center, so operators see alerts while the app window is hidden or behind others
+
+use crate::alerting::{self, AlertNotice, QuietHours};
+use crate::event_store::EventStore;
+use crate::settings;
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tauri::AppHandle;
+use tauri_plugin_notification::NotificationExt;
+use tokio::sync::Mutex;
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct DesktopNotificationSettings {
+    pub enabled: bool,
+    pub min_priority: String,
+    pub quiet_hours: Option<QuietHours>,
+}
+
+impl Default for DesktopNotificationSettings {
+    fn default() -> Self {
+        DesktopNotificationSettings {
+            enabled: false,
+            min_priority: "medium".to_string(),
+            quiet_hours: None,
+        }
+    }
+}
+
+impl DesktopNotificationSettings {
+    pub fn validate(&self) -> Result<(), String> {
+        alerting::validate_priority(&self.min_priority)?;
+        match &self.quiet_hours {
+            Some(quiet_hours) => quiet_hours.validate(),
+            None => Ok(()),
+        }
+    }
+}
+
+pub fn show(app: &AppHandle, title: &str, body: &str) -> Result<(), String> {
+    app.notification()
+        .builder()
+        .title(title)
+        .body(body)
+        .show()
+        .map_err(|e| format!("Failed to show notification: {}", e))
+}
+
+// Background task: a notification for every alert at or above the configured priority
+pub async fn run(app: AppHandle, events: Arc<Mutex<EventStore>>) {
+    let mut receiver = events.lock().await.subscribe();
+    while let Some(notice) = alerting::next_notice(&mut receiver, "Desktop notifications").await {
+        let config = settings::current().desktop_notifications;
+        if config.enabled && alerting::passes(&notice, &config.min_priority, config.quiet_hours.as_ref(), Local::now().time()) {
+            if let Err(e) = show(&app, &notice.title(), &notice.description) {
+                eprintln!("{}", e);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_titles_and_priority_floor() {
+        let notice = AlertNotice {
+            id: "a1".to_string(),
+            camera_id: Some("front door".to_string()),
+            description: "Door left open".to_string(),
+            kind: "rule".to_string(),
+            priority: "high".to_string(),
+            rule: Some("Door".to_string()),
+            box_count: 0,
+            incident_id: None,
+        };
+        assert_eq!(notice.title(), "High alert on front door");
+        let config = DesktopNotificationSettings::default();
+        let noon = chrono::NaiveTime::from_hms_opt(12, 0, 0).unwrap();
+        assert!(alerting::passes(&notice, &config.min_priority, None, noon));
+        assert!(!alerting::passes(&AlertNotice { priority: "low".to_string(), ..notice.clone() }, &config.min_priority, None, noon));
+        let night = QuietHours { start: "11:00".to_string(), end: "13:00".to_string() };
+        assert!(!alerting::passes(&notice, &config.min_priority, Some(&night), noon));
+    }
+}
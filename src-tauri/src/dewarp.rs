@@ -0,0 +1,232 @@
+// Dewarp Module - Flatten ceiling fisheye frames before detection
+// A per-camera calibration unrolls the image circle into a panorama or four perspective views, so zones and boxes are drawn on an undistorted picture
+
+use crate::frame_processor;
+use crate::settings;
+use image::{DynamicImage, Rgb, RgbImage};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::f32::consts::PI;
+use std::sync::{Arc, Mutex};
+
+pub const PROJECTIONS: [&str; 2] = ["panorama", "quad"];
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct FisheyeCalibration {
+    pub center_x: f32,       // Image circle centre as a fraction of frame width
+    pub center_y: f32,       // ...and of frame height
+    pub radius: f32,         // Image circle radius as a fraction of half the shorter frame side
+    pub fov_degrees: f32,    // Field of view across the image circle
+    pub projection: String,  // "panorama" (360° strip) or "quad" (four 90° views)
+    pub tilt_degrees: f32,   // Quad views: how far each view looks away from straight down
+    pub output_width: u32,
+}
+
+impl Default for FisheyeCalibration {
+    fn default() -> Self {
+        FisheyeCalibration {
+            center_x: 0.5,
+            center_y: 0.5,
+            radius: 1.0,
+            fov_degrees: 180.0,
+            projection: "panorama".to_string(),
+            tilt_degrees: 55.0,
+            output_width: 1280,
+        }
+    }
+}
+
+impl FisheyeCalibration {
+    pub fn validate(&self) -> Result<(), String> {
+        if !PROJECTIONS.contains(&self.projection.as_str()) {
+            return Err(format!("Unknown projection '{}' (use {})", self.projection, PROJECTIONS.join(", ")));
+        }
+        if !(0.0..=1.0).contains(&self.center_x) || !(0.0..=1.0).contains(&self.center_y) {
+            return Err("center_x and center_y must be between 0 and 1".to_string());
+        }
+        if !(self.radius > 0.0 && self.radius <= 1.5) {
+            return Err("radius must be greater than 0 and at most 1.5".to_string());
+        }
+        if !(90.0..=240.0).contains(&self.fov_degrees) {
+            return Err("fov_degrees must be between 90 and 240".to_string());
+        }
+        if !(0.0..=85.0).contains(&self.tilt_degrees) {
+            return Err("tilt_degrees must be between 0 and 85".to_string());
+        }
+        if !(64..=4096).contains(&self.output_width) {
+            return Err("output_width must be between 64 and 4096".to_string());
+        }
+        Ok(())
+    }
+
+    fn output_size(&self) -> (u32, u32) {
+        match self.projection.as_str() {
+            // 360° across by half the field of view down
+            "panorama" => (self.output_width, (self.output_width as f32 * self.fov_degrees / 720.0).round() as u32),
+            // 2x2 grid of 4:3 views
+            _ => (self.output_width, self.output_width * 3 / 4),
+        }
+    }
+}
+
+// Source pixel for every output pixel, row-major
+struct Remap {
+    calibration: FisheyeCalibration,
+    input: (u32, u32),
+    output: (u32, u32),
+    sources: Vec<(f32, f32)>,
+}
+
+// Building a map costs a few trig calls per pixel, so it is kept until the calibration or frame size changes
+static REMAPS: Mutex<BTreeMap<String, Arc<Remap>>> = Mutex::new(BTreeMap::new());
+
+fn build_remap(calibration: &FisheyeCalibration, width: u32, height: u32) -> Remap {
+    let (out_w, out_h) = calibration.output_size();
+    let (cx, cy) = (calibration.center_x * width as f32, calibration.center_y * height as f32);
+    let radius = calibration.radius * width.min(height) as f32 / 2.0;
+    let half_fov = calibration.fov_degrees.to_radians() / 2.0;
+
+    // Equidistant lens: distance from the centre grows linearly with the angle off the optical axis
+    let project = |off_axis: f32, azimuth: f32| {
+        let r = radius * off_axis / half_fov;
+        (cx + r * azimuth.cos(), cy + r * azimuth.sin())
+    };
+
+    let mut sources = Vec::with_capacity((out_w * out_h) as usize);
+    if calibration.projection == "panorama" {
+        for y in 0..out_h {
+            // Heads point away from the centre under a ceiling camera, so the rim is the top row
+            let off_axis = half_fov * (1.0 - (y as f32 + 0.5) / out_h as f32);
+            for x in 0..out_w {
+                sources.push(project(off_axis, 2.0 * PI * (x as f32 + 0.5) / out_w as f32));
+            }
+        }
+    } else {
+        let (tile_w, tile_h) = (out_w / 2, out_h / 2);
+        let focal = (tile_w as f32 / 2.0) / (PI / 4.0).tan();  // 90° horizontal view
+        let tilt = calibration.tilt_degrees.to_radians();
+        for y in 0..out_h {
+            for x in 0..out_w {
+                // Views clockwise from the top of the fisheye image
+                let view = match (x < tile_w, y < tile_h) {
+                    (true, true) => 0,
+                    (false, true) => 1,
+                    (false, false) => 2,
+                    (true, false) => 3,
+                };
+                let azimuth = -PI / 2.0 + view as f32 * PI / 2.0;
+                let (u, v) = ((x % tile_w) as f32 + 0.5 - tile_w as f32 / 2.0, (y % tile_h) as f32 + 0.5 - tile_h as f32 / 2.0);
+
+                // Virtual camera axes in fisheye coordinates (x right, y down, z along the lens)
+                let forward = [tilt.sin() * azimuth.cos(), tilt.sin() * azimuth.sin(), tilt.cos()];
+                let down = [-tilt.cos() * azimuth.cos(), -tilt.cos() * azimuth.sin(), tilt.sin()];
+                let right = [-azimuth.sin(), azimuth.cos(), 0.0];
+                let ray: Vec<f32> = (0..3).map(|i| u * right[i] + v * down[i] + focal * forward[i]).collect();
+                let norm = ray.iter().map(|c| c * c).sum::<f32>().sqrt();
+                sources.push(project((ray[2] / norm).clamp(-1.0, 1.0).acos(), ray[1].atan2(ray[0])));
+            }
+        }
+    }
+
+    Remap {
+        calibration: calibration.clone(),
+        input: (width, height),
+        output: (out_w, out_h),
+        sources,
+    }
+}
+
+// Bilinear sample; outside the frame is black
+fn sample(image: &RgbImage, x: f32, y: f32) -> Rgb<u8> {
+    let (w, h) = (image.width() as i64, image.height() as i64);
+    let (x0, y0) = ((x - 0.5).floor(), (y - 0.5).floor());
+    let (ax, ay) = (x - 0.5 - x0, y - 0.5 - y0);
+    let pixel = |px: i64, py: i64| {
+        if px < 0 || py < 0 || px >= w || py >= h {
+            [0.0; 3]
+        } else {
+            image.get_pixel(px as u32, py as u32).0.map(|c| c as f32)
+        }
+    };
+    let (x0, y0) = (x0 as i64, y0 as i64);
+    let (p00, p10, p01, p11) = (pixel(x0, y0), pixel(x0 + 1, y0), pixel(x0, y0 + 1), pixel(x0 + 1, y0 + 1));
+    Rgb(std::array::from_fn(|i| {
+        let top = p00[i] * (1.0 - ax) + p10[i] * ax;
+        let bottom = p01[i] * (1.0 - ax) + p11[i] * ax;
+        (top * (1.0 - ay) + bottom * ay).round() as u8
+    }))
+}
+
+fn apply(remap: &Remap, image: &RgbImage) -> RgbImage {
+    let (out_w, out_h) = remap.output;
+    RgbImage::from_fn(out_w, out_h, |x, y| {
+        let (sx, sy) = remap.sources[(y * out_w + x) as usize];
+        sample(image, sx, sy)
+    })
+}
+
+pub fn dewarp(camera_id: &str, calibration: &FisheyeCalibration, image: &RgbImage) -> RgbImage {
+    let dims = image.dimensions();
+    let cached = REMAPS
+        .lock()
+        .ok()
+        .and_then(|maps| maps.get(camera_id).cloned())
+        .filter(|remap| remap.calibration == *calibration && remap.input == dims);
+    let remap = cached.unwrap_or_else(|| {
+        let remap = Arc::new(build_remap(calibration, dims.0, dims.1));
+        if let Ok(mut maps) = REMAPS.lock() {
+            maps.insert(camera_id.to_string(), remap.clone());
+        }
+        remap
+    });
+    apply(&remap, image)
+}
+
+// The frame in this camera's flattened projection; unchanged for cameras without a calibration
+pub fn for_camera(frame_base64: String, camera_id: Option<&str>) -> Result<String, String> {
+    let camera = camera_id.unwrap_or("");
+    let Some(calibration) = settings::current().fisheye.get(camera).cloned() else {
+        return Ok(frame_base64);
+    };
+    let image = frame_processor::decode_frame(&frame_base64)?.to_rgb8();
+    frame_processor::encode_frame(&DynamicImage::ImageRgb8(dewarp(camera, &calibration, &image)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_projections_unroll_the_image_circle() {
+        // Fisheye quadrants in four colours: top red, right green, bottom blue, left white
+        let fisheye = RgbImage::from_fn(200, 200, |x, y| {
+            let (dx, dy) = (x as f32 - 100.0, y as f32 - 100.0);
+            match (dy.abs() > dx.abs(), dy < 0.0, dx > 0.0) {
+                (true, true, _) => Rgb([255, 0, 0]),
+                (true, false, _) => Rgb([0, 0, 255]),
+                (false, _, true) => Rgb([0, 255, 0]),
+                (false, _, false) => Rgb([255, 255, 255]),
+            }
+        });
+
+        let panorama = FisheyeCalibration { output_width: 400, ..Default::default() };
+        assert!(panorama.validate().is_ok());
+        let strip = dewarp("test-panorama", &panorama, &fisheye);
+        assert_eq!(strip.dimensions(), (400, 100));
+        // Azimuth runs clockwise from the right of the circle
+        assert_eq!(strip.get_pixel(10, 50).0, [0, 255, 0]);
+        assert_eq!(strip.get_pixel(100, 50).0, [0, 0, 255]);
+        assert_eq!(strip.get_pixel(200, 50).0, [255, 255, 255]);
+        assert_eq!(strip.get_pixel(300, 50).0, [255, 0, 0]);
+
+        let quad = FisheyeCalibration { projection: "quad".to_string(), output_width: 160, ..Default::default() };
+        let views = dewarp("test-quad", &quad, &fisheye);
+        assert_eq!(views.dimensions(), (160, 120));
+        assert_eq!(views.get_pixel(40, 30).0, [255, 0, 0]);
+        assert_eq!(views.get_pixel(120, 30).0, [0, 255, 0]);
+        assert_eq!(views.get_pixel(120, 90).0, [0, 0, 255]);
+        assert_eq!(views.get_pixel(40, 90).0, [255, 255, 255]);
+        assert!(FisheyeCalibration { projection: "cube".to_string(), ..Default::default() }.validate().is_err());
+    }
+}
@@ -0,0 +1,176 @@
+// Diagnostics Module - Self-test behind the setup checklist
+// Each check passes, warns or fails with a hint the UI can show next to it
+
+use crate::event_store::format_timestamp;
+use crate::moondream_manager::MoondreamManager;
+use crate::ollama_manager::{OllamaManager, OllamaStatus};
+use crate::{settings, storage};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+// Below this much free space the event store and snapshots can't keep growing
+const MIN_FREE_MB: u64 = 1024;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum CheckStatus {
+    Pass,
+    Warn,
+    Fail,
+    Skipped,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct DiagnosticCheck {
+    pub id: String,
+    pub label: String,
+    pub status: CheckStatus,
+    pub detail: String,
+    pub hint: Option<String>,
+}
+
+impl DiagnosticCheck {
+    fn new(id: &str, label: &str, status: CheckStatus, detail: impl Into<String>) -> Self {
+        DiagnosticCheck {
+            id: id.to_string(),
+            label: label.to_string(),
+            status,
+            detail: detail.into(),
+            hint: None,
+        }
+    }
+
+    fn hint(mut self, hint: impl Into<String>) -> Self {
+        self.hint = Some(hint.into());
+        self
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct DiagnosticsReport {
+    pub ran_at: String,
+    pub ready: bool,  // Nothing failed
+    pub checks: Vec<DiagnosticCheck>,
+}
+
+// Linux video devices; elsewhere the webview's permission prompt is the only way to find out
+fn camera_check(dev: &Path) -> DiagnosticCheck {
+    const LABEL: &str = "Camera access";
+    if !cfg!(target_os = "linux") {
+        return DiagnosticCheck::new("camera", LABEL, CheckStatus::Skipped, "Camera permission is requested when monitoring starts");
+    }
+    let devices: Vec<_> = std::fs::read_dir(dev)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter(|entry| entry.file_name().to_string_lossy().starts_with("video"))
+        .map(|entry| entry.path())
+        .collect();
+    if devices.is_empty() {
+        return DiagnosticCheck::new("camera", LABEL, CheckStatus::Warn, "No video devices found")
+            .hint("Connect a webcam, or use a network stream instead");
+    }
+    let readable = devices.iter().filter(|path| std::fs::File::open(path).is_ok()).count();
+    if readable == 0 {
+        return DiagnosticCheck::new("camera", LABEL, CheckStatus::Fail, format!("{} video devices, none readable", devices.len()))
+            .hint("Add your user to the 'video' group and log in again");
+    }
+    DiagnosticCheck::new("camera", LABEL, CheckStatus::Pass, format!("{} of {} video devices readable", readable, devices.len()))
+}
+
+fn ollama_checks(status: &OllamaStatus) -> [DiagnosticCheck; 2] {
+    let server = if status.running {
+        DiagnosticCheck::new("ollama", "Ollama server", CheckStatus::Pass, "Responding on 127.0.0.1:11434")
+    } else {
+        DiagnosticCheck::new("ollama", "Ollama server", CheckStatus::Fail, status.error.clone().unwrap_or_else(|| "Not responding".to_string()))
+            .hint("Start Ollama from the setup screen")
+    };
+    let model = match (status.running, status.model_ready) {
+        (false, _) => DiagnosticCheck::new("model", "Vision model", CheckStatus::Skipped, "Needs the Ollama server"),
+        (true, true) => DiagnosticCheck::new("model", "Vision model", CheckStatus::Pass, format!("{} is installed", status.model)),
+        (true, false) => DiagnosticCheck::new("model", "Vision model", CheckStatus::Fail, format!("{} is not installed", status.model))
+            .hint(format!("Download {} from the setup screen", status.model)),
+    };
+    [server, model]
+}
+
+fn moondream_check(key: Result<Option<bool>, String>) -> DiagnosticCheck {
+    const LABEL: &str = "Moondream API key";
+    match key {
+        Ok(Some(true)) => DiagnosticCheck::new("moondream", LABEL, CheckStatus::Pass, "Key accepted"),
+        Ok(Some(false)) => DiagnosticCheck::new("moondream", LABEL, CheckStatus::Fail, "Key rejected by the Moondream API")
+            .hint("Check MOONDREAM_API_KEY"),
+        Ok(None) => DiagnosticCheck::new("moondream", LABEL, CheckStatus::Skipped, "No key configured; Moondream is optional"),
+        Err(e) => DiagnosticCheck::new("moondream", LABEL, CheckStatus::Warn, e),
+    }
+}
+
+// The model download needs room on top of the floor when it isn't installed yet
+fn disk_check(free_mb: Option<u64>, pending_download_mb: u64) -> DiagnosticCheck {
+    const LABEL: &str = "Disk space";
+    let Some(free_mb) = free_mb else {
+        return DiagnosticCheck::new("disk", LABEL, CheckStatus::Warn, "Free space could not be determined");
+    };
+    let detail = format!("{} MB free", free_mb);
+    if free_mb < MIN_FREE_MB {
+        DiagnosticCheck::new("disk", LABEL, CheckStatus::Fail, detail).hint(format!("Free up at least {} MB", MIN_FREE_MB - free_mb))
+    } else if free_mb < MIN_FREE_MB + pending_download_mb {
+        DiagnosticCheck::new("disk", LABEL, CheckStatus::Warn, detail)
+            .hint(format!("The vision model download needs about {} MB", pending_download_mb))
+    } else {
+        DiagnosticCheck::new("disk", LABEL, CheckStatus::Pass, detail)
+    }
+}
+
+fn write_check(dir: &Path) -> DiagnosticCheck {
+    const LABEL: &str = "Data folder";
+    let probe = dir.join(format!(".write-test-{}", uuid::Uuid::new_v4()));
+    let result = std::fs::create_dir_all(dir).and_then(|_| std::fs::write(&probe, b"ok"));
+    let _ = std::fs::remove_file(&probe);
+    match result {
+        Ok(()) => DiagnosticCheck::new("storage", LABEL, CheckStatus::Pass, format!("{} is writable", dir.display())),
+        Err(e) => DiagnosticCheck::new("storage", LABEL, CheckStatus::Fail, format!("Can't write to {}: {}", dir.display(), e))
+            .hint("Check the folder's owner and permissions"),
+    }
+}
+
+pub async fn run(moondream: &MoondreamManager) -> DiagnosticsReport {
+    let data_dir = storage::data_dir();
+    let ollama = OllamaManager::check_status().await;
+    let pending_download_mb = if ollama.model_ready { 0 } else { storage::estimated_model_mb(&settings::current().vision_model) };
+
+    let mut checks = vec![camera_check(Path::new("/dev"))];
+    checks.extend(ollama_checks(&ollama));
+    checks.push(moondream_check(moondream.verify_api_key().await));
+    checks.push(disk_check(storage::free_space_mb(&data_dir).await, pending_download_mb));
+    checks.push(write_check(&data_dir));
+
+    DiagnosticsReport {
+        ran_at: format_timestamp(chrono::Utc::now()),
+        ready: checks.iter().all(|c| c.status != CheckStatus::Fail),
+        checks,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_disk_and_write_checks() {
+        assert_eq!(disk_check(Some(500), 0).status, CheckStatus::Fail);
+        assert_eq!(disk_check(Some(4000), 5000).status, CheckStatus::Warn);
+        assert_eq!(disk_check(Some(8000), 5000).status, CheckStatus::Pass);
+        assert_eq!(disk_check(None, 0).status, CheckStatus::Warn);
+
+        let dir = tempfile::tempdir().unwrap();
+        let check = write_check(&dir.path().join("nested"));
+        assert_eq!(check.status, CheckStatus::Pass);
+        assert_eq!(std::fs::read_dir(dir.path().join("nested")).unwrap().count(), 0);
+
+        let stopped = OllamaStatus { running: false, model_ready: false, model: "llava:7b".to_string(), error: None };
+        let [server, model] = ollama_checks(&stopped);
+        assert_eq!((server.status, model.status), (CheckStatus::Fail, CheckStatus::Skipped));
+        assert_eq!(moondream_check(Ok(Some(false))).status, CheckStatus::Fail);
+    }
+}
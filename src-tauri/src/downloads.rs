@@ -0,0 +1,163 @@
+// Downloads Module - Rate limit and pause/resume for the Ollama binary download and model pulls
+// Ollama fetches model weights itself, so a pull is throttled by cancelling it when it runs ahead and resuming it later
+
+use crate::settings;
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+const PAUSE_POLL: Duration = Duration::from_millis(250);
+
+static PAUSED: AtomicBool = AtomicBool::new(false);
+static CANCELLED: AtomicBool = AtomicBool::new(false);
+static NEXT_ID: AtomicU64 = AtomicU64::new(1);
+static ACTIVE: Mutex<Vec<ActiveDownload>> = Mutex::new(Vec::new());
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ActiveDownload {
+    pub id: u64,
+    pub name: String,  // e.g. "Ollama 0.4.7" or "Pulling llava:7b"
+    pub downloaded_bytes: u64,
+    pub total_bytes: Option<u64>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct DownloadStatus {
+    pub paused: bool,
+    pub limit_kbps: Option<u32>,
+    pub active: Vec<ActiveDownload>,
+}
+
+pub fn status() -> DownloadStatus {
+    DownloadStatus {
+        paused: paused(),
+        limit_kbps: settings::current().network.download_limit_kbps,
+        active: ACTIVE.lock().unwrap_or_else(|e| e.into_inner()).clone(),
+    }
+}
+
+pub fn paused() -> bool {
+    PAUSED.load(Ordering::Relaxed)
+}
+
+pub fn set_paused(paused: bool) {
+    if PAUSED.swap(paused, Ordering::Relaxed) != paused {
+        println!("{} Downloads {}", if paused { "⏸️" } else { "▶️" }, if paused { "paused" } else { "resumed" });
+    }
+}
+
+// At exit, so a paused pull doesn't hold up shutdown
+pub fn cancel_all() {
+    CANCELLED.store(true, Ordering::Relaxed);
+}
+
+// Bytes per second, read on every check so a new limit applies to running downloads
+fn limit() -> Option<u64> {
+    settings::current().network.download_limit_kbps.map(|kbps| kbps as u64 * 1024)
+}
+
+// How long to wait so `bytes` over `elapsed` stays under `limit` bytes per second
+fn delay(bytes: u64, elapsed: Duration, limit: Option<u64>) -> Duration {
+    match limit {
+        Some(limit) if limit > 0 => Duration::from_secs_f64(bytes as f64 / limit as f64).saturating_sub(elapsed),
+        _ => Duration::ZERO,
+    }
+}
+
+// One download in progress; listed in status() until dropped
+pub struct Transfer {
+    id: u64,
+    downloaded: u64,
+    // The rate is measured from here, reset after a pause so the pause doesn't count as headroom
+    window_start: Instant,
+    window_bytes: u64,
+}
+
+impl Transfer {
+    pub fn start(name: &str, total: Option<u64>) -> Self {
+        let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+        ACTIVE.lock().unwrap_or_else(|e| e.into_inner()).push(ActiveDownload {
+            id,
+            name: name.to_string(),
+            downloaded_bytes: 0,
+            total_bytes: total,
+        });
+        Self { id, downloaded: 0, window_start: Instant::now(), window_bytes: 0 }
+    }
+
+    fn update(&self, edit: impl FnOnce(&mut ActiveDownload)) {
+        if let Some(entry) = ACTIVE.lock().unwrap_or_else(|e| e.into_inner()).iter_mut().find(|d| d.id == self.id) {
+            edit(entry);
+        }
+    }
+
+    pub fn set_total(&self, total: u64) {
+        self.update(|d| d.total_bytes = Some(total));
+    }
+
+    // Total downloaded so far, for pulls where Ollama reports progress
+    pub fn record(&mut self, downloaded: u64) {
+        self.window_bytes += downloaded.saturating_sub(self.downloaded);
+        self.downloaded = downloaded;
+        self.update(|d| d.downloaded_bytes = downloaded);
+    }
+
+    // How far ahead of the rate limit this download is
+    pub fn ahead(&self) -> Duration {
+        delay(self.window_bytes, self.window_start.elapsed(), limit())
+    }
+
+    // Waits out a pause and any time the download is ahead of the limit
+    pub async fn pace(&mut self) -> Result<(), String> {
+        if paused() {
+            while paused() && !CANCELLED.load(Ordering::Relaxed) {
+                tokio::time::sleep(PAUSE_POLL).await;
+            }
+            self.window_start = Instant::now();
+            self.window_bytes = 0;
+        }
+        if CANCELLED.load(Ordering::Relaxed) {
+            return Err("Download cancelled".to_string());
+        }
+        tokio::time::sleep(self.ahead()).await;
+        Ok(())
+    }
+
+    // For downloads streamed through this process, after each chunk
+    pub async fn advance(&mut self, bytes: usize) -> Result<(), String> {
+        self.record(self.downloaded + bytes as u64);
+        self.pace().await
+    }
+}
+
+impl Drop for Transfer {
+    fn drop(&mut self) {
+        ACTIVE.lock().unwrap_or_else(|e| e.into_inner()).retain(|d| d.id != self.id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rate_limit_delay() {
+        // 1 MiB at 512 KiB/s takes 2 s
+        let limit = Some(512 * 1024);
+        assert_eq!(delay(1024 * 1024, Duration::ZERO, limit), Duration::from_secs(2));
+        assert_eq!(delay(1024 * 1024, Duration::from_millis(1500), limit), Duration::from_millis(500));
+        assert_eq!(delay(1024 * 1024, Duration::from_secs(3), limit), Duration::ZERO);
+        assert_eq!(delay(1024 * 1024, Duration::ZERO, None), Duration::ZERO);
+
+        let mut transfer = Transfer::start("Pulling llava", Some(4096));
+        transfer.record(1024);
+        transfer.record(3072);
+        let listed = status().active.into_iter().find(|d| d.id == transfer.id).unwrap();
+        assert_eq!((listed.downloaded_bytes, listed.total_bytes), (3072, Some(4096)));
+        assert_eq!(transfer.window_bytes, 3072);
+        let id = transfer.id;
+        drop(transfer);
+        assert!(!status().active.iter().any(|d| d.id == id));
+    }
+}
@@ -0,0 +1,340 @@
+// Encryption Module - Optional at-rest encryption of event descriptions, payloads and snapshots
+// AES-256-CTR with an HMAC-SHA256 tag, keyed from a passphrase through PBKDF2; the key is only ever held in memory
+
+use aes::cipher::{generic_array::GenericArray, BlockEncrypt, KeyInit};
+use aes::Aes256;
+use base64::{engine::general_purpose, Engine as _};
+use hmac::{Hmac, Mac};
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use std::io::Write;
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::sync::RwLock;
+
+// Marks sealed text columns and snapshot files, so plaintext written before encryption was enabled still reads
+const TEXT_PREFIX: &str = "enc1:";
+const FILE_MAGIC: &[u8] = b"LVA-ENC1";
+const NONCE_LEN: usize = 16;
+const TAG_LEN: usize = 32;
+const PBKDF2_ROUNDS: u32 = 210_000;
+// Sealed at setup; opening it proves a passphrase is the right one
+const VERIFIER: &str = "live-vision-analyzer";
+pub const PASSPHRASE_ENV: &str = "LVA_DB_PASSPHRASE";
+const KEYCHAIN_SERVICE: &str = "live-vision-analyzer";
+const KEYCHAIN_ACCOUNT: &str = "event-store";
+
+// Shown for sealed fields while storage is locked
+pub const LOCKED: &str = "[locked]";
+
+static KEY: RwLock<Option<Key>> = RwLock::new(None);
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+#[serde(default)]
+pub struct EncryptionSettings {
+    pub enabled: bool,
+    pub salt: String,  // base64
+    pub verifier: String,  // VERIFIER sealed with the key
+    pub use_keychain: bool,  // Passphrase kept in the OS keychain and unlocked at startup
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct EncryptionStatus {
+    pub enabled: bool,
+    pub unlocked: bool,
+    pub use_keychain: bool,
+}
+
+#[derive(Clone)]
+struct Key {
+    cipher: [u8; 32],
+    mac: [u8; 32],
+}
+
+fn derive(passphrase: &str, salt: &[u8]) -> Key {
+    let mut material = [0u8; 64];
+    pbkdf2::pbkdf2::<Hmac<Sha256>>(passphrase.as_bytes(), salt, PBKDF2_ROUNDS, &mut material);
+    let (mut cipher, mut mac) = ([0u8; 32], [0u8; 32]);
+    cipher.copy_from_slice(&material[..32]);
+    mac.copy_from_slice(&material[32..]);
+    Key { cipher, mac }
+}
+
+// CTR keystream with the nonce as the initial 128-bit counter
+fn apply_keystream(key: &Key, nonce: &[u8], data: &mut [u8]) {
+    let cipher = Aes256::new(GenericArray::from_slice(&key.cipher));
+    let mut counter = u128::from_be_bytes(nonce.try_into().unwrap_or([0; NONCE_LEN]));
+    for chunk in data.chunks_mut(16) {
+        let mut block = GenericArray::clone_from_slice(&counter.to_be_bytes());
+        cipher.encrypt_block(&mut block);
+        chunk.iter_mut().zip(block.iter()).for_each(|(byte, k)| *byte ^= k);
+        counter = counter.wrapping_add(1);
+    }
+}
+
+fn tag(key: &Key, sealed: &[u8]) -> Hmac<Sha256> {
+    let mut mac = <Hmac<Sha256> as Mac>::new_from_slice(&key.mac).expect("HMAC accepts any key length");
+    mac.update(sealed);
+    mac
+}
+
+// nonce | ciphertext | tag
+fn seal_with(key: &Key, plaintext: &[u8]) -> Vec<u8> {
+    let mut sealed = vec![0u8; NONCE_LEN];
+    rand::thread_rng().fill_bytes(&mut sealed);
+    sealed.extend_from_slice(plaintext);
+    let (nonce, body) = sealed.split_at_mut(NONCE_LEN);
+    apply_keystream(key, nonce, body);
+    let tag = tag(key, &sealed).finalize().into_bytes();
+    sealed.extend_from_slice(&tag);
+    sealed
+}
+
+fn open_with(key: &Key, sealed: &[u8]) -> Result<Vec<u8>, String> {
+    if sealed.len() < NONCE_LEN + TAG_LEN {
+        return Err("Encrypted data is truncated".to_string());
+    }
+    let (body, expected) = sealed.split_at(sealed.len() - TAG_LEN);
+    tag(key, body)
+        .verify_slice(expected)
+        .map_err(|_| "Encrypted data failed its integrity check (wrong passphrase?)".to_string())?;
+    let mut plaintext = body[NONCE_LEN..].to_vec();
+    apply_keystream(key, &body[..NONCE_LEN], &mut plaintext);
+    Ok(plaintext)
+}
+
+fn key() -> Option<Key> {
+    KEY.read().ok().and_then(|k| k.clone())
+}
+
+pub fn enabled() -> bool {
+    crate::settings::current().encryption.enabled
+}
+
+pub fn status() -> EncryptionStatus {
+    let settings = crate::settings::current().encryption;
+    EncryptionStatus { enabled: settings.enabled, unlocked: key().is_some(), use_keychain: settings.use_keychain }
+}
+
+fn locked_error() -> String {
+    "Encrypted storage is locked; unlock it with the passphrase first".to_string()
+}
+
+// New salt and verifier for `passphrase`; the key is kept so existing data can be sealed straight away
+pub fn setup(passphrase: &str, use_keychain: bool) -> Result<EncryptionSettings, String> {
+    if passphrase.chars().count() < 8 {
+        return Err("Passphrase must be at least 8 characters".to_string());
+    }
+    let mut salt = [0u8; 16];
+    rand::thread_rng().fill_bytes(&mut salt);
+    let key = derive(passphrase, &salt);
+    let settings = EncryptionSettings {
+        enabled: true,
+        salt: general_purpose::STANDARD.encode(salt),
+        verifier: general_purpose::STANDARD.encode(seal_with(&key, VERIFIER.as_bytes())),
+        use_keychain,
+    };
+    if use_keychain {
+        keychain_store(passphrase)?;
+    }
+    *KEY.write().map_err(|e| e.to_string())? = Some(key);
+    Ok(settings)
+}
+
+pub fn unlock(passphrase: &str, settings: &EncryptionSettings) -> Result<(), String> {
+    let salt = general_purpose::STANDARD.decode(&settings.salt).map_err(|e| format!("Invalid encryption salt: {}", e))?;
+    let key = derive(passphrase, &salt);
+    let verifier = general_purpose::STANDARD.decode(&settings.verifier).map_err(|e| format!("Invalid encryption verifier: {}", e))?;
+    match open_with(&key, &verifier) {
+        Ok(plaintext) if plaintext == VERIFIER.as_bytes() => {}
+        _ => return Err("Wrong passphrase".to_string()),
+    }
+    *KEY.write().map_err(|e| e.to_string())? = Some(key);
+    Ok(())
+}
+
+pub fn lock() {
+    if let Ok(mut key) = KEY.write() {
+        *key = None;
+    }
+}
+
+// The environment wins over the keychain, for headless installs
+pub fn unlock_at_startup(settings: &EncryptionSettings) {
+    if !settings.enabled {
+        return;
+    }
+    let passphrase = std::env::var(PASSPHRASE_ENV).ok().or_else(|| settings.use_keychain.then(keychain_load).flatten());
+    match passphrase.map(|p| unlock(&p, settings)) {
+        Some(Ok(())) => println!("🔐 Encrypted storage unlocked"),
+        Some(Err(e)) => eprintln!("Failed to unlock encrypted storage: {}", e),
+        None => println!("🔒 Encrypted storage is locked until the passphrase is entered"),
+    }
+}
+
+// Text column value to store; plain while encryption is off
+pub fn seal_text(text: &str) -> Result<String, String> {
+    if !enabled() {
+        return Ok(text.to_string());
+    }
+    force_seal_text(text)
+}
+
+// Sealed with the unlocked key before the enabled flag is saved, for converting existing rows
+pub fn force_seal_text(text: &str) -> Result<String, String> {
+    if text.starts_with(TEXT_PREFIX) {
+        return Ok(text.to_string());
+    }
+    let key = key().ok_or_else(locked_error)?;
+    Ok(format!("{}{}", TEXT_PREFIX, general_purpose::STANDARD.encode(seal_with(&key, text.as_bytes()))))
+}
+
+// Stored text column value as plaintext; LOCKED when it can't be opened
+pub fn open_text(stored: String) -> String {
+    let Some(encoded) = stored.strip_prefix(TEXT_PREFIX) else {
+        return stored;
+    };
+    let plaintext = key().and_then(|key| {
+        let sealed = general_purpose::STANDARD.decode(encoded).ok()?;
+        open_with(&key, &sealed).ok()
+    });
+    plaintext.and_then(|p| String::from_utf8(p).ok()).unwrap_or_else(|| LOCKED.to_string())
+}
+
+pub fn is_sealed_text(stored: &str) -> bool {
+    stored.starts_with(TEXT_PREFIX)
+}
+
+pub fn seal_bytes(data: &[u8]) -> Result<Vec<u8>, String> {
+    if !enabled() || data.starts_with(FILE_MAGIC) {
+        return Ok(data.to_vec());
+    }
+    let key = key().ok_or_else(locked_error)?;
+    Ok([FILE_MAGIC, &seal_with(&key, data)].concat())
+}
+
+pub fn open_bytes(stored: Vec<u8>) -> Result<Vec<u8>, String> {
+    let Some(sealed) = stored.strip_prefix(FILE_MAGIC) else {
+        return Ok(stored);
+    };
+    open_with(&key().ok_or_else(locked_error)?, sealed)
+}
+
+// Snapshot file contents whether or not it was written sealed
+pub fn read_file(path: &Path) -> Result<Vec<u8>, String> {
+    let stored = std::fs::read(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    open_bytes(stored)
+}
+
+// Files holding credentials are readable by their owner only
+pub fn write_private(path: &Path, contents: &[u8]) -> Result<(), String> {
+    let failed = |e: std::io::Error| format!("Failed to write {}: {}", path.display(), e);
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let mut file = options.open(path).map_err(failed)?;
+    // The mode only applies to new files; older ones were written world-readable
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        file.set_permissions(std::fs::Permissions::from_mode(0o600)).map_err(failed)?;
+    }
+    file.write_all(contents).map_err(failed)
+}
+
+// Seal (or open) every snapshot in place when encryption is switched on (or off)
+pub fn convert_files(dir: &Path, seal: bool) -> Result<usize, String> {
+    let mut converted = 0;
+    for entry in std::fs::read_dir(dir).into_iter().flatten().flatten() {
+        let path = entry.path();
+        let stored = std::fs::read(&path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        if stored.starts_with(FILE_MAGIC) == seal {
+            continue;
+        }
+        let key = key().ok_or_else(locked_error)?;
+        let contents = if seal { [FILE_MAGIC, &seal_with(&key, &stored)].concat() } else { open_bytes(stored)? };
+        let staging = path.with_extension("tmp");
+        std::fs::write(&staging, contents)
+            .and_then(|_| std::fs::rename(&staging, &path))
+            .map_err(|e| format!("Failed to rewrite {}: {}", path.display(), e))?;
+        converted += 1;
+    }
+    Ok(converted)
+}
+
+// macOS Keychain through `security`, the Secret Service through `secret-tool` elsewhere; the passphrase goes over stdin
+fn keychain_store(passphrase: &str) -> Result<(), String> {
+    let (program, args, input) = if cfg!(target_os = "macos") {
+        let quoted = passphrase.replace('\\', "\\\\").replace('"', "\\\"");
+        let command = format!("add-generic-password -U -s {} -a {} -w \"{}\"\n", KEYCHAIN_SERVICE, KEYCHAIN_ACCOUNT, quoted);
+        ("security", vec!["-i"], command)
+    } else {
+        let args = vec!["store", "--label=Live Vision Analyzer", "service", KEYCHAIN_SERVICE, "account", KEYCHAIN_ACCOUNT];
+        ("secret-tool", args, passphrase.to_string())
+    };
+    let mut child = Command::new(program)
+        .args(&args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .spawn()
+        .map_err(|e| format!("OS keychain is not available ({}): {}", program, e))?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(input.as_bytes()).map_err(|e| e.to_string())?;
+    }
+    let status = child.wait().map_err(|e| e.to_string())?;
+    if !status.success() {
+        return Err(format!("{} could not store the passphrase", program));
+    }
+    Ok(())
+}
+
+fn keychain_load() -> Option<String> {
+    let output = if cfg!(target_os = "macos") {
+        Command::new("security").args(["find-generic-password", "-s", KEYCHAIN_SERVICE, "-a", KEYCHAIN_ACCOUNT, "-w"]).output()
+    } else {
+        Command::new("secret-tool").args(["lookup", "service", KEYCHAIN_SERVICE, "account", KEYCHAIN_ACCOUNT]).output()
+    };
+    let output = output.ok().filter(|o| o.status.success())?;
+    let passphrase = String::from_utf8(output.stdout).ok()?.trim_end_matches('\n').to_string();
+    (!passphrase.is_empty()).then_some(passphrase)
+}
+
+pub fn keychain_forget() {
+    let _ = if cfg!(target_os = "macos") {
+        Command::new("security").args(["delete-generic-password", "-s", KEYCHAIN_SERVICE, "-a", KEYCHAIN_ACCOUNT]).output()
+    } else {
+        Command::new("secret-tool").args(["clear", "service", KEYCHAIN_SERVICE, "account", KEYCHAIN_ACCOUNT]).output()
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_seal_and_open() {
+        let key = derive("correct horse", b"salt-salt-salt-1");
+        let text = "A courier in a red jacket at the back door, carrying two parcels";
+        let sealed = seal_with(&key, text.as_bytes());
+        assert_eq!(sealed.len(), NONCE_LEN + text.len() + TAG_LEN);
+        assert!(!sealed.windows(7).any(|w| w == b"courier"));
+        assert_eq!(open_with(&key, &sealed).unwrap(), text.as_bytes());
+        // Fresh nonce every time
+        assert_ne!(seal_with(&key, text.as_bytes()), sealed);
+
+        let mut tampered = sealed.clone();
+        tampered[NONCE_LEN + 3] ^= 1;
+        assert!(open_with(&key, &tampered).is_err());
+        assert!(open_with(&derive("wrong horse", b"salt-salt-salt-1"), &sealed).is_err());
+
+        // Plaintext from before encryption passes through; sealed text without the key reads as locked
+        assert_eq!(open_text("plain".to_string()), "plain");
+        let stored = format!("{}{}", TEXT_PREFIX, general_purpose::STANDARD.encode(&sealed));
+        assert_eq!(open_text(stored), LOCKED);
+    }
+}
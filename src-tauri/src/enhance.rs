@@ -0,0 +1,184 @@
+// Enhance Module - Low-light enhancement of frames before detection
+// Gamma lifts the shadows and CLAHE (contrast-limited adaptive histogram equalisation) restores local contrast on the luma
+
+use crate::frame_processor;
+use crate::settings;
+use image::{DynamicImage, RgbImage};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+const BINS: usize = 256;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum LowLightMode {
+    #[default]
+    Off,
+    Auto,    // Only frames darker than dark_threshold
+    Always,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct LowLightSettings {
+    pub cameras: BTreeMap<String, LowLightMode>,  // Camera id ("" for frames sent without one); unlisted cameras are off
+    pub dark_threshold: f32,  // Mean brightness (0-1) below which "auto" enhances
+    pub clip_limit: f32,      // Histogram bin cap as a multiple of the average bin; lower keeps noise down
+    pub tiles: u32,           // CLAHE grid size per axis
+}
+
+impl Default for LowLightSettings {
+    fn default() -> Self {
+        LowLightSettings {
+            cameras: BTreeMap::new(),
+            dark_threshold: 0.25,
+            clip_limit: 3.0,
+            tiles: 8,
+        }
+    }
+}
+
+impl LowLightSettings {
+    pub fn validate(&self) -> Result<(), String> {
+        if !(self.dark_threshold > 0.0 && self.dark_threshold < 1.0) {
+            return Err("dark_threshold must be between 0 and 1".to_string());
+        }
+        if self.clip_limit < 1.0 {
+            return Err("clip_limit must be at least 1".to_string());
+        }
+        if !(1..=32).contains(&self.tiles) {
+            return Err("tiles must be between 1 and 32".to_string());
+        }
+        Ok(())
+    }
+
+    pub fn mode(&self, camera_id: Option<&str>) -> LowLightMode {
+        self.cameras.get(camera_id.unwrap_or("")).copied().unwrap_or_default()
+    }
+}
+
+fn luma(r: u8, g: u8, b: u8) -> f32 {
+    0.299 * r as f32 + 0.587 * g as f32 + 0.114 * b as f32
+}
+
+pub fn mean_brightness(image: &RgbImage) -> f32 {
+    let total: f32 = image.pixels().map(|p| luma(p.0[0], p.0[1], p.0[2])).sum();
+    total / (image.width() * image.height()).max(1) as f32 / 255.0
+}
+
+// Lookup table mapping one tile's levels through its clipped, equalised histogram
+fn tile_mapping(levels: impl Iterator<Item = u8>, clip_limit: f32) -> [u8; BINS] {
+    let mut histogram = [0f32; BINS];
+    let mut count = 0f32;
+    for level in levels {
+        histogram[level as usize] += 1.0;
+        count += 1.0;
+    }
+    // Clip tall bins and spread the excess evenly so flat regions don't turn into noise
+    let cap = (clip_limit * count / BINS as f32).max(1.0);
+    let excess: f32 = histogram.iter().map(|h| (h - cap).max(0.0)).sum();
+    histogram.iter_mut().for_each(|h| *h = h.min(cap) + excess / BINS as f32);
+
+    let mut mapping = [0u8; BINS];
+    let mut cumulative = 0.0;
+    for (level, h) in histogram.iter().enumerate() {
+        cumulative += h;
+        mapping[level] = (cumulative / count.max(1.0) * 255.0).round().min(255.0) as u8;
+    }
+    mapping
+}
+
+// Gamma chosen to bring the mean brightness up towards mid-grey, then CLAHE on the luma; colours keep their ratios
+pub fn enhance(image: &RgbImage, clip_limit: f32, tiles: u32) -> RgbImage {
+    let (width, height) = image.dimensions();
+    let mean = mean_brightness(image).clamp(0.01, 0.99);
+    let gamma = (0.5f32.ln() / mean.ln()).clamp(0.3, 1.0);
+    let gamma_table: Vec<f32> = (0..BINS).map(|v| 255.0 * (v as f32 / 255.0).powf(gamma)).collect();
+
+    let lifted: Vec<u8> = image
+        .pixels()
+        .map(|p| gamma_table[luma(p.0[0], p.0[1], p.0[2]).round() as usize].round() as u8)
+        .collect();
+
+    let tiles_x = tiles.min(width).max(1);
+    let tiles_y = tiles.min(height).max(1);
+    let tile_w = width.div_ceil(tiles_x);
+    let tile_h = height.div_ceil(tiles_y);
+    let mut mappings = Vec::with_capacity((tiles_x * tiles_y) as usize);
+    for ty in 0..tiles_y {
+        for tx in 0..tiles_x {
+            let (x0, y0) = (tx * tile_w, ty * tile_h);
+            let (x1, y1) = ((x0 + tile_w).min(width), (y0 + tile_h).min(height));
+            let levels = (y0..y1).flat_map(|y| (x0..x1).map(move |x| (x, y))).map(|(x, y)| lifted[(y * width + x) as usize]);
+            mappings.push(tile_mapping(levels, clip_limit));
+        }
+    }
+
+    // Bilinear blend of the four nearest tile mappings hides the tile seams
+    let mut output = RgbImage::new(width, height);
+    for (x, y, pixel) in output.enumerate_pixels_mut() {
+        let fx = ((x as f32 + 0.5) / tile_w as f32 - 0.5).clamp(0.0, (tiles_x - 1) as f32);
+        let fy = ((y as f32 + 0.5) / tile_h as f32 - 0.5).clamp(0.0, (tiles_y - 1) as f32);
+        let (tx0, ty0) = (fx.floor() as u32, fy.floor() as u32);
+        let (tx1, ty1) = ((tx0 + 1).min(tiles_x - 1), (ty0 + 1).min(tiles_y - 1));
+        let (ax, ay) = (fx - tx0 as f32, fy - ty0 as f32);
+        let level = lifted[(y * width + x) as usize] as usize;
+        let map = |tx: u32, ty: u32| mappings[(ty * tiles_x + tx) as usize][level] as f32;
+        let top = map(tx0, ty0) * (1.0 - ax) + map(tx1, ty0) * ax;
+        let bottom = map(tx0, ty1) * (1.0 - ax) + map(tx1, ty1) * ax;
+        let target = top * (1.0 - ay) + bottom * ay;
+
+        let source = image.get_pixel(x, y).0;
+        let original = luma(source[0], source[1], source[2]).max(1.0);
+        let scale = target / original;
+        *pixel = image::Rgb(source.map(|c| (c as f32 * scale).round().min(255.0) as u8));
+    }
+    output
+}
+
+// The frame the detector should see for this camera; unchanged unless enhancement applies
+pub fn for_detection(frame_base64: &str, camera_id: Option<&str>) -> Result<String, String> {
+    let config = settings::current().low_light;
+    let mode = config.mode(camera_id);
+    if mode == LowLightMode::Off {
+        return Ok(frame_base64.to_string());
+    }
+    let image = frame_processor::decode_frame(frame_base64)?.to_rgb8();
+    if mode == LowLightMode::Auto && mean_brightness(&image) >= config.dark_threshold {
+        return Ok(frame_base64.to_string());
+    }
+    frame_processor::encode_frame(&DynamicImage::ImageRgb8(enhance(&image, config.clip_limit, config.tiles)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::Rgb;
+
+    #[test]
+    fn test_enhancement_brightens_dark_frames() {
+        // A dim shop floor: dark shelving with a slightly brighter person shape
+        let night = RgbImage::from_fn(64, 48, |x, y| {
+            if (24..36).contains(&x) && (10..44).contains(&y) { Rgb([34, 30, 28]) } else { Rgb([14, 14, 18]) }
+        });
+        let enhanced = enhance(&night, 3.0, 4);
+        assert!(mean_brightness(&night) < 0.1);
+        assert!(mean_brightness(&enhanced) > 0.25);
+
+        // The person stands out more after enhancement
+        let contrast = |image: &RgbImage| {
+            let p = image.get_pixel(30, 30).0;
+            let b = image.get_pixel(10, 30).0;
+            luma(p[0], p[1], p[2]) - luma(b[0], b[1], b[2])
+        };
+        assert!(contrast(&enhanced) > contrast(&night) * 1.5);
+
+        let config = LowLightSettings {
+            cameras: BTreeMap::from([("parking".to_string(), LowLightMode::Auto)]),
+            ..Default::default()
+        };
+        assert_eq!(config.mode(Some("parking")), LowLightMode::Auto);
+        assert_eq!(config.mode(None), LowLightMode::Off);
+        assert!(config.validate().is_ok());
+    }
+}
@@ -0,0 +1,239 @@
+// Evaluation Module - Accuracy against a folder of labeled images
+// Scores detector boxes (precision/recall by IoU) and retail JSON fields against ground truth
+
+use crate::yolo_detector::BoundingBox;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+// File inside the evaluation folder that holds the labels
+pub const GROUND_TRUTH_FILE: &str = "ground_truth.json";
+
+pub const DEFAULT_IOU_THRESHOLD: f32 = 0.5;
+
+// Labels for one image; either part may be absent
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct GroundTruth {
+    pub image: String,
+    pub boxes: Option<Vec<BoundingBox>>,
+    pub scene_type: Option<String>,  // Retail prompt to run: queue, inventory or safety
+    pub expected: Option<serde_json::Map<String, serde_json::Value>>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct DetectionScore {
+    pub class_name: String,
+    pub true_positives: u32,
+    pub false_positives: u32,
+    pub false_negatives: u32,
+    pub precision: f64,
+    pub recall: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct FieldScore {
+    pub field: String,
+    pub correct: u32,
+    pub total: u32,
+    pub accuracy: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct EvaluationReport {
+    pub evaluation_id: String,
+    pub timestamp: String,
+    pub images: usize,
+    pub iou_threshold: f32,
+    pub detection: Vec<DetectionScore>,
+    pub detection_overall: DetectionScore,
+    pub fields: Vec<FieldScore>,
+    pub field_accuracy: f64,
+    pub errors: Vec<String>,
+}
+
+pub fn load_ground_truth(dir: &Path) -> Result<Vec<GroundTruth>, String> {
+    let path = dir.join(GROUND_TRUTH_FILE);
+    let contents =
+        std::fs::read_to_string(&path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    serde_json::from_str(&contents).map_err(|e| format!("Invalid {}: {}", GROUND_TRUTH_FILE, e))
+}
+
+pub fn iou(a: &BoundingBox, b: &BoundingBox) -> f32 {
+    let width = (a.x2.min(b.x2) - a.x1.max(b.x1)).max(0.0);
+    let height = (a.y2.min(b.y2) - a.y1.max(b.y1)).max(0.0);
+    let intersection = width * height;
+    let union = (a.x2 - a.x1) * (a.y2 - a.y1) + (b.x2 - b.x1) * (b.y2 - b.y1) - intersection;
+    if union <= 0.0 {
+        0.0
+    } else {
+        intersection / union
+    }
+}
+
+fn ratio(part: u32, whole: u32) -> f64 {
+    if whole == 0 {
+        0.0
+    } else {
+        part as f64 / whole as f64
+    }
+}
+
+// Numbers within 10% (at least 1) count as correct; strings compare case-insensitively
+pub fn field_matches(expected: &serde_json::Value, actual: &serde_json::Value) -> bool {
+    use serde_json::Value;
+    match (expected, actual) {
+        (Value::Number(e), Value::Number(a)) => {
+            let (e, a) = (e.as_f64().unwrap_or(0.0), a.as_f64().unwrap_or(0.0));
+            (e - a).abs() <= (e.abs() * 0.1).max(1.0)
+        }
+        (Value::String(e), Value::String(a)) => e.trim().eq_ignore_ascii_case(a.trim()),
+        (Value::Array(e), Value::Array(a)) => {
+            e.len() == a.len() && e.iter().all(|item| a.iter().any(|other| field_matches(item, other)))
+        }
+        _ => expected == actual,
+    }
+}
+
+// Running totals across all evaluated images
+#[derive(Default)]
+pub struct Tally {
+    detection: BTreeMap<String, DetectionScore>,
+    fields: BTreeMap<String, FieldScore>,
+}
+
+impl Tally {
+    // Greedy matching by confidence: each label can be claimed once, by a box of the same class
+    pub fn add_detections(&mut self, truth: &[BoundingBox], predicted: &[BoundingBox], iou_threshold: f32) {
+        let mut predicted: Vec<&BoundingBox> = predicted.iter().collect();
+        predicted.sort_by(|a, b| b.confidence.total_cmp(&a.confidence));
+        let mut claimed = vec![false; truth.len()];
+
+        for prediction in predicted {
+            let best = truth
+                .iter()
+                .enumerate()
+                .filter(|(i, label)| !claimed[*i] && label.class_name == prediction.class_name)
+                .map(|(i, label)| (i, iou(label, prediction)))
+                .filter(|(_, overlap)| *overlap >= iou_threshold)
+                .max_by(|a, b| a.1.total_cmp(&b.1));
+            let score = self.class_score(&prediction.class_name);
+            match best {
+                Some((i, _)) => {
+                    claimed[i] = true;
+                    score.true_positives += 1;
+                }
+                None => score.false_positives += 1,
+            }
+        }
+
+        for (label, _) in truth.iter().zip(&claimed).filter(|(_, claimed)| !**claimed) {
+            self.class_score(&label.class_name).false_negatives += 1;
+        }
+    }
+
+    // The free-text description is not scored
+    pub fn add_fields(&mut self, expected: &serde_json::Map<String, serde_json::Value>, actual: Option<&serde_json::Value>) {
+        for (field, value) in expected.iter().filter(|(field, _)| *field != "description") {
+            let score = self.fields.entry(field.clone()).or_insert_with(|| FieldScore {
+                field: field.clone(),
+                ..Default::default()
+            });
+            score.total += 1;
+            if actual.and_then(|a| a.get(field)).map(|a| field_matches(value, a)).unwrap_or(false) {
+                score.correct += 1;
+            }
+        }
+    }
+
+    fn class_score(&mut self, class_name: &str) -> &mut DetectionScore {
+        self.detection.entry(class_name.to_string()).or_insert_with(|| DetectionScore {
+            class_name: class_name.to_string(),
+            ..Default::default()
+        })
+    }
+
+    pub fn report(self, images: usize, iou_threshold: f32, errors: Vec<String>) -> EvaluationReport {
+        let finish = |mut score: DetectionScore| {
+            score.precision = ratio(score.true_positives, score.true_positives + score.false_positives);
+            score.recall = ratio(score.true_positives, score.true_positives + score.false_negatives);
+            score
+        };
+
+        let mut overall = DetectionScore { class_name: "all".to_string(), ..Default::default() };
+        for score in self.detection.values() {
+            overall.true_positives += score.true_positives;
+            overall.false_positives += score.false_positives;
+            overall.false_negatives += score.false_negatives;
+        }
+
+        let fields: Vec<FieldScore> = self
+            .fields
+            .into_values()
+            .map(|mut score| {
+                score.accuracy = ratio(score.correct, score.total);
+                score
+            })
+            .collect();
+        let field_accuracy = ratio(fields.iter().map(|f| f.correct).sum(), fields.iter().map(|f| f.total).sum());
+
+        EvaluationReport {
+            evaluation_id: uuid::Uuid::new_v4().to_string(),
+            timestamp: chrono::Utc::now().to_rfc3339(),
+            images,
+            iou_threshold,
+            detection: self.detection.into_values().map(finish).collect(),
+            detection_overall: finish(overall),
+            fields,
+            field_accuracy,
+            errors,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bbox(class_name: &str, x1: f32, confidence: f32) -> BoundingBox {
+        BoundingBox::new(class_name, x1, 0.0, x1 + 100.0, 100.0, confidence)
+    }
+
+    #[test]
+    fn test_detection_precision_recall() {
+        let mut tally = Tally::default();
+        let truth = vec![bbox("person", 0.0, 0.0), bbox("person", 300.0, 0.0), bbox("cart", 600.0, 0.0)];
+        // One good person, one duplicate of it, one box far from any label
+        let predicted = vec![bbox("person", 10.0, 0.9), bbox("person", 5.0, 0.5), bbox("cart", 900.0, 0.8)];
+        tally.add_detections(&truth, &predicted, DEFAULT_IOU_THRESHOLD);
+
+        let report = tally.report(1, DEFAULT_IOU_THRESHOLD, Vec::new());
+        let person = report.detection.iter().find(|s| s.class_name == "person").unwrap();
+        assert_eq!((person.true_positives, person.false_positives, person.false_negatives), (1, 1, 1));
+        assert_eq!(person.precision, 0.5);
+        assert_eq!(report.detection_overall.true_positives, 1);
+        assert_eq!(report.detection_overall.false_negatives, 2);
+    }
+
+    #[test]
+    fn test_field_accuracy() {
+        let expected = serde_json::json!({
+            "people_count": 10,
+            "staff_needed": true,
+            "crowd_density": "High",
+            "customer_mood": ["calm", "impatient"],
+            "description": "ignored"
+        });
+        let actual = serde_json::json!({
+            "people_count": 11,
+            "staff_needed": false,
+            "crowd_density": "high",
+            "customer_mood": ["impatient", "calm"]
+        });
+
+        let mut tally = Tally::default();
+        tally.add_fields(expected.as_object().unwrap(), Some(&actual));
+        let report = tally.report(1, DEFAULT_IOU_THRESHOLD, Vec::new());
+        assert_eq!(report.fields.len(), 4);
+        assert_eq!(report.field_accuracy, 0.75);
+    }
+}
@@ -0,0 +1,1975 @@
+// Event Store Module - SQLite persistence for detections and VLM analyses
+// Gives the app a queryable history of what the cameras saw
+
+use crate::ab_testing::{AbTestRecord, ProviderOutcome};
+use crate::audit::{AuditAction, AuditEntry, AuditFilter};
+use crate::bus::{Alert, BusEvent};
+use crate::dataset;
+use crate::encryption;
+use crate::incidents::{self, Incident, IncidentActivity, IncidentFilter, IncidentMetrics, IncidentState};
+use crate::migrations::{self, AppliedMigration};
+use crate::pause::{Pause, PauseInterval};
+use crate::preview;
+use crate::semantic_search::{blob_to_vector, vector_to_blob};
+use crate::settings;
+use crate::suppression;
+use crate::verification;
+use crate::vision_provider::TokenUsage;
+use crate::yolo_detector::{BoundingBox, DetectionData};
+use chrono::{DateTime, SecondsFormat, Utc};
+use rusqlite::{params, Connection, DatabaseName, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::sync::broadcast::{self, error::RecvError};
+use tokio::sync::mpsc;
+use tokio::sync::Mutex;
+
+// Unchanged detection snapshots are still recorded at this interval
+const DETECTION_HEARTBEAT_SECS: i64 = 60;
+
+// Hard cap on rows returned by ad-hoc history queries
+pub const MAX_QUERY_ROWS: usize = 500;
+
+// Accepted values for EventFeedback::verdict
+pub const FEEDBACK_VERDICTS: [&str; 3] = ["correct", "partial", "incorrect"];
+
+// Human-readable schema description (used when asking the LLM to write SQL)
+pub const SCHEMA_DESCRIPTION: &str = "Table events(
+  id TEXT,               -- unique event id
+  timestamp TEXT,        -- UTC RFC3339 with milliseconds, e.g. 2025-01-31T18:05:00.000Z
+  camera_id TEXT,        -- camera that produced the event (may be NULL)
+  event_type TEXT,       -- 'detection' (YOLO snapshot), 'analysis' (VLM result), 'alert' (e.g. a PPE violation or blocklisted plate) or 'pause' (analysis paused by an operator; description is the reason, payload has paused_at / resumed_at / duration_secs)
+  person_count INTEGER,  -- people visible in the frame, temporally smoothed (detection events)
+  object_counts TEXT,    -- JSON object of class name -> count (detection events)
+  provider TEXT,         -- 'llava' or 'moondream' (analysis events)
+  prompt TEXT,           -- prompt sent to the VLM (analysis events)
+  description TEXT,      -- natural language VLM output (analysis events)
+  payload TEXT           -- raw JSON result; detection payloads have visitors.new / visitors.returning with re-identification on and staff_count with staff classification on
+  anonymized_at TEXT     -- when the retention job checked the event; identifying text was replaced with '[anonymized]'
+)";
+
+// A stored detection or analysis record
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct StoredEvent {
+    pub id: String,
+    pub timestamp: String,
+    pub camera_id: Option<String>,
+    pub event_type: String,
+    pub person_count: Option<u32>,
+    pub object_counts: Option<serde_json::Value>,
+    pub provider: Option<String>,
+    pub prompt: Option<String>,
+    pub description: Option<String>,
+    pub payload: Option<serde_json::Value>,
+}
+
+// Filters for listing events
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct EventFilter {
+    pub from: Option<String>,
+    pub to: Option<String>,
+    pub event_type: Option<String>,
+    pub camera_id: Option<String>,
+    pub instance: Option<String>,  // Events pulled from this remote instance (hub mode)
+    pub limit: Option<usize>,
+}
+
+// Result of a free-form read-only query
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct QueryRows {
+    pub columns: Vec<String>,
+    pub rows: Vec<serde_json::Value>,
+    pub truncated: bool,
+}
+
+// A human rating of an event, optionally with the right answer
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct EventFeedback {
+    pub event_id: String,
+    pub verdict: String,
+    pub corrected_boxes: Option<Vec<BoundingBox>>,
+    pub corrected_description: Option<String>,
+    pub submitted_at: String,
+}
+
+// Verdict counts per provider, for comparing accuracy
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct FeedbackStats {
+    pub provider: String,
+    pub correct: u32,
+    pub partial: u32,
+    pub incorrect: u32,
+    pub corrected: u32,
+}
+
+// Links an analysis to the external event (door sensor, POS transaction, ...) that requested it
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ExternalTrigger {
+    pub event_id: String,
+    pub source: String,
+    pub external_id: String,
+    pub received_at: String,
+}
+
+// Averages over the analyses one provider and model ran, for performance dashboards
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ModelPerformance {
+    pub provider: String,
+    pub model: String,
+    pub analyses: u32,
+    pub avg_input_tokens: f64,
+    pub avg_output_tokens: f64,
+    pub avg_tokens_per_second: Option<f64>,
+    pub avg_load_ms: Option<f64>,
+    pub avg_total_ms: Option<f64>,
+}
+
+// One person checked for protective equipment; missing is empty when they were compliant
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PpeCheck {
+    pub checked_at: String,
+    pub camera_id: Option<String>,
+    pub zone: Option<String>,
+    pub shift: Option<String>,
+    pub track_id: Option<u64>,
+    pub missing: Vec<String>,
+    pub alert_id: Option<String>,
+}
+
+// Compliance rate for one zone and shift
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PpeCompliance {
+    pub zone: Option<String>,
+    pub shift: Option<String>,
+    pub checks: u32,
+    pub compliant: u32,
+    pub rate: f32,
+}
+
+// A number plate read by ANPR; list is 'allowed', 'blocked' or 'unlisted'
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PlateRead {
+    pub read_at: String,
+    pub camera_id: Option<String>,
+    pub plate: String,
+    pub list: String,
+    pub alert_id: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TableRows {
+    pub table: String,
+    pub rows: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct DbInfo {
+    pub path: Option<String>,
+    pub size_bytes: Option<u64>,
+    pub schema_version: u32,
+    pub latest_version: u32,  // What this build migrates to
+    pub migrations: Vec<AppliedMigration>,
+    pub tables: Vec<TableRows>,
+}
+
+const INCIDENT_COLUMNS: &str = "id, camera_id, kind, subject, priority, description, state, opened_at, updated_at, \
+    acknowledged_at, acknowledged_by, resolved_at, resolution, alert_count, last_alert_id, assigned_to, snoozed_until";
+
+// Backlog a slow live subscriber may fall behind by before it starts missing events
+const LIVE_CHANNEL_CAPACITY: usize = 256;
+
+pub struct EventStore {
+    conn: Connection,
+    last_detection: Option<(u32, DateTime<Utc>)>,
+    live: broadcast::Sender<StoredEvent>,
+}
+
+// Description and payload as written to the database, sealed while encryption is on
+fn sealed_columns(event: &StoredEvent) -> Result<(Option<String>, Option<String>), String> {
+    let description = event.description.as_deref().map(encryption::seal_text).transpose()?;
+    let payload = event.payload.as_ref().map(|p| encryption::seal_text(&p.to_string())).transpose()?;
+    Ok((description, payload))
+}
+
+// Timestamps are stored in one fixed format so string comparison orders them correctly
+pub fn format_timestamp(time: DateTime<Utc>) -> String {
+    time.to_rfc3339_opts(SecondsFormat::Millis, true)
+}
+
+impl EventStore {
+    // Default database location next to the embedded Ollama data
+    pub fn default_path() -> PathBuf {
+        let home_dir = std::env::var("HOME").unwrap_or_else(|_| "/tmp".to_string());
+        PathBuf::from(home_dir).join(".live-vision-analyzer").join("events.db")
+    }
+
+    pub fn open(path: &Path) -> Result<Self, String> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+        }
+
+        let conn = Connection::open(path)
+            .map_err(|e| format!("Failed to open event store: {}", e))?;
+
+        println!("EventStore: Opened {}", path.display());
+        Self::init(conn, Some(path))
+    }
+
+    pub fn open_in_memory() -> Result<Self, String> {
+        let conn = Connection::open_in_memory()
+            .map_err(|e| format!("Failed to open event store: {}", e))?;
+
+        Self::init(conn, None)
+    }
+
+    fn init(mut conn: Connection, path: Option<&Path>) -> Result<Self, String> {
+        conn.execute_batch("PRAGMA foreign_keys = ON")
+            .map_err(|e| format!("Failed to initialize event store: {}", e))?;
+        migrations::migrate(&mut conn, path)?;
+
+        Ok(EventStore {
+            conn,
+            last_detection: None,
+            live: broadcast::channel(LIVE_CHANNEL_CAPACITY).0,
+        })
+    }
+
+    // Consistent copy of the database, taken while events keep being written
+    pub fn backup_to(&self, path: &Path) -> Result<(), String> {
+        self.conn
+            .backup(DatabaseName::Main, path, None)
+            .map_err(|e| format!("Failed to back up event store: {}", e))
+    }
+
+    // Replace every table with a backed-up copy; live subscribers stay connected
+    pub fn restore_from(&mut self, path: &Path) -> Result<(), String> {
+        let source = Connection::open_with_flags(path, rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY)
+            .map_err(|e| format!("Failed to open backup database: {}", e))?;
+        let integrity: String = source
+            .query_row("PRAGMA integrity_check", [], |row| row.get(0))
+            .map_err(|e| format!("Backup database is unreadable: {}", e))?;
+        if integrity != "ok" {
+            return Err(format!("Backup database is corrupt: {}", integrity));
+        }
+        let has_events: bool = source
+            .query_row("SELECT EXISTS(SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = 'events')", [], |row| row.get(0))
+            .map_err(|e| e.to_string())?;
+        if !has_events {
+            return Err("Backup database has no events table".to_string());
+        }
+        migrations::check_supported(migrations::schema_version(&source)?, migrations::latest_version())?;
+        drop(source);
+
+        self.conn
+            .restore(DatabaseName::Main, path, None::<fn(rusqlite::backup::Progress)>)
+            .map_err(|e| format!("Failed to restore event store: {}", e))?;
+        // Backups from older versions are brought up to date like any old database
+        migrations::migrate(&mut self.conn, None)?;
+        self.last_detection = None;
+        Ok(())
+    }
+
+    pub fn db_info(&self) -> Result<DbInfo, String> {
+        let names: Vec<String> = {
+            let mut stmt = self
+                .conn
+                .prepare("SELECT name FROM sqlite_master WHERE type = 'table' AND name NOT LIKE 'sqlite_%' ORDER BY name")
+                .map_err(|e| e.to_string())?;
+            let rows = stmt.query_map([], |row| row.get(0)).map_err(|e| e.to_string())?;
+            rows.collect::<Result<_, _>>().map_err(|e| e.to_string())?
+        };
+        let tables = names
+            .into_iter()
+            .map(|table| {
+                let sql = format!("SELECT COUNT(*) FROM \"{}\"", table.replace('"', "\"\""));
+                let rows: i64 = self.conn.query_row(&sql, [], |row| row.get(0)).map_err(|e| e.to_string())?;
+                Ok(TableRows { table, rows: rows as u64 })
+            })
+            .collect::<Result<_, String>>()?;
+        let path = self.conn.path().filter(|p| !p.is_empty()).map(str::to_string);
+
+        Ok(DbInfo {
+            size_bytes: path.as_ref().and_then(|p| std::fs::metadata(p).ok()).map(|m| m.len()),
+            path,
+            schema_version: migrations::schema_version(&self.conn)?,
+            latest_version: migrations::latest_version(),
+            migrations: migrations::applied(&self.conn)?,
+            tables,
+        })
+    }
+
+    pub fn insert_event(&self, event: &StoredEvent) -> Result<(), String> {
+        self.write_event(event)?;
+        // No subscribers is the normal case, not an error
+        let _ = self.live.send(event.clone());
+        Ok(())
+    }
+
+    // Insert without telling live subscribers yet
+    fn write_event(&self, event: &StoredEvent) -> Result<(), String> {
+        let (description, payload) = sealed_columns(event)?;
+        self.conn
+            .execute(
+                "INSERT INTO events (id, timestamp, camera_id, event_type, person_count, object_counts, provider, prompt, description, payload)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+                params![
+                    event.id,
+                    event.timestamp,
+                    event.camera_id,
+                    event.event_type,
+                    event.person_count,
+                    event.object_counts.as_ref().map(|v| v.to_string()),
+                    event.provider,
+                    event.prompt,
+                    description,
+                    payload,
+                ],
+            )
+            .map_err(|e| format!("Failed to store event: {}", e))?;
+        Ok(())
+    }
+
+    // Store an event pulled from a remote instance; false if it was already here
+    pub fn store_remote_event(&self, instance: &str, event: &StoredEvent) -> Result<bool, String> {
+        let (description, payload) = sealed_columns(event)?;
+        let inserted = self
+            .conn
+            .execute(
+                "INSERT OR IGNORE INTO events (id, timestamp, camera_id, event_type, person_count, object_counts, provider, prompt, description, payload)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+                params![
+                    event.id,
+                    event.timestamp,
+                    event.camera_id,
+                    event.event_type,
+                    event.person_count,
+                    event.object_counts.as_ref().map(|v| v.to_string()),
+                    event.provider,
+                    event.prompt,
+                    description,
+                    payload,
+                ],
+            )
+            .map_err(|e| format!("Failed to store remote event: {}", e))?;
+        if inserted == 0 {
+            return Ok(false);
+        }
+
+        self.conn
+            .execute(
+                "INSERT OR REPLACE INTO event_origins (event_id, instance, received_at) VALUES (?1, ?2, ?3)",
+                params![event.id, instance, format_timestamp(Utc::now())],
+            )
+            .map_err(|e| format!("Failed to store event origin: {}", e))?;
+        let _ = self.live.send(event.clone());
+        Ok(true)
+    }
+
+    // Newest event already pulled from an instance, where catch-up resumes
+    pub fn last_remote_timestamp(&self, instance: &str) -> Result<Option<String>, String> {
+        self.conn
+            .query_row(
+                "SELECT MAX(e.timestamp) FROM event_origins o JOIN events e ON e.id = o.event_id WHERE o.instance = ?1",
+                params![instance],
+                |row| row.get(0),
+            )
+            .map_err(|e| format!("Failed to query remote events: {}", e))
+    }
+
+    // Every event stored from now on
+    pub fn subscribe(&self) -> broadcast::Receiver<StoredEvent> {
+        self.live.subscribe()
+    }
+
+    // Store a YOLO snapshot when the (smoothed) person count changes, someone new arrives, or as a periodic heartbeat
+    pub fn record_detection(&mut self, camera_id: Option<&str>, data: &DetectionData) -> Result<Option<String>, String> {
+        let now = Utc::now();
+        let person_count = data.stable_person_count();
+        let arrivals = data.visitors.map(|v| v.new + v.returning).unwrap_or(0);
+        if let Some((last_count, last_time)) = self.last_detection {
+            let unchanged = last_count == person_count && arrivals == 0;
+            if unchanged && (now - last_time).num_seconds() < DETECTION_HEARTBEAT_SECS {
+                return Ok(None);
+            }
+        }
+
+        let event = StoredEvent {
+            id: uuid::Uuid::new_v4().to_string(),
+            timestamp: format_timestamp(now),
+            camera_id: camera_id.map(|c| c.to_string()),
+            event_type: "detection".to_string(),
+            person_count: Some(person_count),
+            object_counts: serde_json::to_value(&data.object_counts).ok(),
+            provider: Some("yolo".to_string()),
+            prompt: None,
+            description: None,
+            payload: serde_json::to_value(data).ok(),
+        };
+
+        self.insert_event(&event)?;
+        self.last_detection = Some((person_count, now));
+
+        Ok(Some(event.id))
+    }
+
+    // Store a VLM analysis result
+    pub fn record_analysis(
+        &self,
+        camera_id: Option<&str>,
+        provider: &str,
+        prompt: &str,
+        description: &str,
+        payload: Option<serde_json::Value>,
+    ) -> Result<String, String> {
+        let event = StoredEvent {
+            id: uuid::Uuid::new_v4().to_string(),
+            timestamp: format_timestamp(Utc::now()),
+            camera_id: camera_id.map(|c| c.to_string()),
+            event_type: "analysis".to_string(),
+            person_count: None,
+            object_counts: None,
+            provider: Some(provider.to_string()),
+            prompt: Some(prompt.to_string()),
+            description: Some(description.to_string()),
+            payload,
+        };
+
+        self.insert_event(&event)?;
+        Ok(event.id)
+    }
+
+    // Store an alert published on the bus, under the id its publisher chose, and file it under its incident.
+    // Live subscribers get it with payload.incident_id and payload.notify, false once someone acknowledged or snoozed the incident
+    pub fn store_alert(&self, alert: &Alert) -> Result<(), String> {
+        let now = Utc::now();
+        let held_back = held_back(&alert.payload);
+        let incident = if held_back {
+            None
+        } else {
+            self.track_incident(alert, now)
+                .inspect_err(|e| eprintln!("Failed to update incident for alert {}: {}", alert.id, e))
+                .ok()
+        };
+        let mut payload = alert.payload.clone();
+        if let (Some(incident), Some(fields)) = (&incident, payload.as_object_mut()) {
+            fields.insert("incident_id".to_string(), serde_json::json!(incident.id));
+            fields.insert("notify".to_string(), serde_json::json!(incidents::should_notify(incident, now)));
+        }
+        if let Some(fields) = payload.as_object_mut().filter(|_| held_back) {
+            fields.insert("notify".to_string(), serde_json::json!(false));
+        }
+
+        let event = StoredEvent {
+            id: alert.id.clone(),
+            timestamp: format_timestamp(now),
+            camera_id: alert.camera_id.clone(),
+            event_type: "alert".to_string(),
+            person_count: None,
+            object_counts: None,
+            provider: Some(alert.provider.clone()),
+            prompt: None,
+            description: Some(alert.description.clone()),
+            payload: Some(payload),
+        };
+        self.write_event(&event)?;
+        if let Some(incident) = &incident {
+            if let Err(e) = self.link_incident_alert(&incident.id, &alert.id) {
+                eprintln!("Failed to link alert {} to its incident: {}", alert.id, e);
+            }
+        }
+        let _ = self.live.send(event);
+        Ok(())
+    }
+
+    // Start of an analysis pause; the payload gets its end once analysis resumes
+    pub fn record_pause(&self, pause: &Pause) -> Result<(), String> {
+        let paused_at = format_timestamp(pause.paused_at);
+        self.insert_event(&StoredEvent {
+            id: pause.event_id.clone(),
+            timestamp: paused_at.clone(),
+            camera_id: None,
+            event_type: "pause".to_string(),
+            person_count: None,
+            object_counts: None,
+            provider: None,
+            prompt: None,
+            description: Some(pause.reason.clone()),
+            payload: Some(serde_json::json!({ "reason": pause.reason, "paused_at": paused_at, "resumed_at": null })),
+        })
+    }
+
+    pub fn end_pause(&self, interval: &PauseInterval) -> Result<(), String> {
+        let payload = serde_json::to_value(interval).map_err(|e| e.to_string())?;
+        self.conn
+            .execute(
+                "UPDATE events SET payload = ?1 WHERE id = ?2 AND event_type = 'pause'",
+                params![encryption::seal_text(&payload.to_string())?, interval.event_id],
+            )
+            .map_err(|e| format!("Failed to record pause end: {}", e))?;
+        Ok(())
+    }
+
+    pub fn get_event(&self, id: &str) -> Result<Option<StoredEvent>, String> {
+        self.conn
+            .query_row(
+                "SELECT id, timestamp, camera_id, event_type, person_count, object_counts, provider, prompt, description, payload
+                 FROM events WHERE id = ?1",
+                params![id],
+                Self::row_to_event,
+            )
+            .optional()
+            .map_err(|e| format!("Failed to load event: {}", e))
+    }
+
+    pub fn append_audit(&self, entries: &[AuditEntry]) -> Result<(), String> {
+        let tx = self.conn.unchecked_transaction().map_err(|e| e.to_string())?;
+        for entry in entries {
+            tx.execute(
+                "INSERT INTO audit_log (timestamp, action, data, destination, provider, bytes, error)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+                params![
+                    entry.timestamp,
+                    entry.action.as_str(),
+                    entry.data,
+                    entry.destination,
+                    entry.provider,
+                    entry.bytes.map(|b| b as i64),
+                    entry.error,
+                ],
+            )
+            .map_err(|e| format!("Failed to write audit log: {}", e))?;
+        }
+        tx.commit().map_err(|e| format!("Failed to write audit log: {}", e))
+    }
+
+    // Newest first
+    pub fn audit_log(&self, filter: &AuditFilter) -> Result<Vec<AuditEntry>, String> {
+        if let Some(action) = &filter.action {
+            AuditAction::parse(action)?;
+        }
+        let mut stmt = self
+            .conn
+            .prepare(
+                "SELECT seq, timestamp, action, data, destination, provider, bytes, error FROM audit_log
+                 WHERE (?1 IS NULL OR timestamp >= ?1) AND (?2 IS NULL OR timestamp < ?2)
+                   AND (?3 IS NULL OR action = ?3) AND (?4 IS NULL OR provider = ?4)
+                 ORDER BY seq DESC LIMIT ?5",
+            )
+            .map_err(|e| e.to_string())?;
+        let limit = filter.limit.unwrap_or(100).min(MAX_QUERY_ROWS) as i64;
+        let rows = stmt
+            .query_map(params![filter.from, filter.to, filter.action, filter.provider, limit], |row| {
+                let action: String = row.get(2)?;
+                Ok(AuditEntry {
+                    seq: row.get(0)?,
+                    timestamp: row.get(1)?,
+                    action: AuditAction::parse(&action).unwrap_or(AuditAction::Transmission),
+                    data: row.get(3)?,
+                    destination: row.get(4)?,
+                    provider: row.get(5)?,
+                    bytes: row.get::<_, Option<i64>>(6)?.map(|b| b as u64),
+                    error: row.get(7)?,
+                })
+            })
+            .map_err(|e| e.to_string())?;
+        rows.collect::<Result<_, _>>().map_err(|e| format!("Failed to read audit log: {}", e))
+    }
+
+    // Seal every plaintext description and payload, or open every sealed one, when encryption is switched
+    pub fn convert_encryption(&self, seal: bool) -> Result<usize, String> {
+        let tx = self.conn.unchecked_transaction().map_err(|e| e.to_string())?;
+        let rows: Vec<(String, Option<String>, Option<String>)> = {
+            let mut stmt = tx
+                .prepare("SELECT id, description, payload FROM events WHERE description IS NOT NULL OR payload IS NOT NULL")
+                .map_err(|e| e.to_string())?;
+            let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?))).map_err(|e| e.to_string())?;
+            rows.collect::<Result<_, _>>().map_err(|e| e.to_string())?
+        };
+        let convert = |value: Option<String>| -> Result<Option<String>, String> {
+            match value {
+                Some(v) if seal && !encryption::is_sealed_text(&v) => encryption::force_seal_text(&v).map(Some),
+                Some(v) if !seal && encryption::is_sealed_text(&v) => match encryption::open_text(v) {
+                    opened if opened == encryption::LOCKED => Err("Stored text could not be decrypted with this key".to_string()),
+                    opened => Ok(Some(opened)),
+                },
+                _ => Ok(None),
+            }
+        };
+
+        let mut converted = 0;
+        for (id, description, payload) in rows {
+            let (description, payload) = (convert(description)?, convert(payload)?);
+            if description.is_none() && payload.is_none() {
+                continue;
+            }
+            tx.execute(
+                "UPDATE events SET description = COALESCE(?2, description), payload = COALESCE(?3, payload) WHERE id = ?1",
+                params![id, description, payload],
+            )
+            .map_err(|e| format!("Failed to convert event: {}", e))?;
+            converted += 1;
+        }
+        tx.commit().map_err(|e| e.to_string())?;
+        Ok(converted)
+    }
+
+    pub fn event_timestamp(&self, id: &str) -> Result<Option<String>, String> {
+        self.conn
+            .query_row("SELECT timestamp FROM events WHERE id = ?1", params![id], |row| row.get(0))
+            .optional()
+            .map_err(|e| format!("Failed to load event: {}", e))
+    }
+
+    // Check every event older than `before` once; `scrub` returns cleaned text, or None when there was nothing to remove
+    pub fn anonymize_before<F>(&self, before: &str, now: &str, scrub: F) -> Result<usize, String>
+    where
+        F: Fn(Option<&str>, Option<serde_json::Value>) -> Option<(Option<String>, Option<serde_json::Value>)>,
+    {
+        let tx = self.conn.unchecked_transaction().map_err(|e| e.to_string())?;
+        let candidates: Vec<(String, Option<String>, Option<String>)> = {
+            let mut stmt = tx
+                .prepare("SELECT id, description, payload FROM events WHERE anonymized_at IS NULL AND timestamp < ?1")
+                .map_err(|e| e.to_string())?;
+            let rows = stmt
+                .query_map(params![before], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))
+                .map_err(|e| e.to_string())?;
+            rows.collect::<Result<_, _>>().map_err(|e| e.to_string())?
+        };
+
+        let mut scrubbed = 0;
+        for (id, description, payload) in candidates {
+            let description = description.map(encryption::open_text);
+            let payload = payload.map(encryption::open_text).and_then(|p| serde_json::from_str(&p).ok());
+            // Sealed text can't be checked until storage is unlocked
+            if description.as_deref() == Some(encryption::LOCKED) {
+                continue;
+            }
+            let result = match scrub(description.as_deref(), payload) {
+                Some((description, payload)) => {
+                    scrubbed += 1;
+                    let description = description.as_deref().map(encryption::seal_text).transpose()?;
+                    let payload = payload.map(|p| encryption::seal_text(&p.to_string())).transpose()?;
+                    tx.execute("DELETE FROM event_embeddings WHERE event_id = ?1", params![id]).and_then(|_| {
+                        tx.execute(
+                            "UPDATE events SET description = ?2, payload = ?3, anonymized_at = ?4 WHERE id = ?1",
+                            params![id, description, payload, now],
+                        )
+                    })
+                }
+                None => tx.execute("UPDATE events SET anonymized_at = ?2 WHERE id = ?1", params![id, now]),
+            };
+            result.map_err(|e| format!("Failed to anonymize event: {}", e))?;
+        }
+        tx.commit().map_err(|e| e.to_string())?;
+        Ok(scrubbed)
+    }
+
+    pub fn list_events(&self, filter: &EventFilter) -> Result<Vec<StoredEvent>, String> {
+        let mut sql = String::from(
+            "SELECT id, timestamp, camera_id, event_type, person_count, object_counts, provider, prompt, description, payload
+             FROM events WHERE 1 = 1",
+        );
+        let mut values: Vec<String> = Vec::new();
+
+        if let Some(from) = &filter.from {
+            values.push(from.clone());
+            sql.push_str(&format!(" AND timestamp >= ?{}", values.len()));
+        }
+        if let Some(to) = &filter.to {
+            values.push(to.clone());
+            sql.push_str(&format!(" AND timestamp < ?{}", values.len()));
+        }
+        if let Some(event_type) = &filter.event_type {
+            values.push(event_type.clone());
+            sql.push_str(&format!(" AND event_type = ?{}", values.len()));
+        }
+        if let Some(camera_id) = &filter.camera_id {
+            values.push(camera_id.clone());
+            sql.push_str(&format!(" AND camera_id = ?{}", values.len()));
+        }
+        if let Some(instance) = &filter.instance {
+            values.push(instance.clone());
+            sql.push_str(&format!(" AND id IN (SELECT event_id FROM event_origins WHERE instance = ?{})", values.len()));
+        }
+
+        let limit = filter.limit.unwrap_or(100).min(MAX_QUERY_ROWS);
+        sql.push_str(&format!(" ORDER BY timestamp DESC LIMIT {}", limit));
+
+        let mut stmt = self.conn.prepare(&sql).map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map(rusqlite::params_from_iter(values.iter()), Self::row_to_event)
+            .map_err(|e| format!("Failed to query events: {}", e))?;
+
+        rows.collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("Failed to read events: {}", e))
+    }
+
+    // Save (or replace) the embedding vector for an event description
+    pub fn store_embedding(&self, event_id: &str, model: &str, vector: &[f32]) -> Result<(), String> {
+        self.conn
+            .execute(
+                "INSERT OR REPLACE INTO event_embeddings (event_id, model, dims, vector) VALUES (?1, ?2, ?3, ?4)",
+                params![event_id, model, vector.len() as i64, vector_to_blob(vector)],
+            )
+            .map_err(|e| format!("Failed to store embedding: {}", e))?;
+
+        Ok(())
+    }
+
+    // All embeddings produced by a given model
+    pub fn load_embeddings(&self, model: &str) -> Result<Vec<(String, Vec<f32>)>, String> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT event_id, vector FROM event_embeddings WHERE model = ?1")
+            .map_err(|e| e.to_string())?;
+
+        let rows = stmt
+            .query_map(params![model], |row| {
+                let blob: Vec<u8> = row.get(1)?;
+                Ok((row.get::<_, String>(0)?, blob_to_vector(&blob)))
+            })
+            .map_err(|e| format!("Failed to load embeddings: {}", e))?;
+
+        rows.collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("Failed to read embeddings: {}", e))
+    }
+
+    // Analyses with a description but no embedding for the given model yet
+    pub fn events_missing_embeddings(&self, model: &str, limit: usize) -> Result<Vec<(String, String)>, String> {
+        let mut stmt = self
+            .conn
+            .prepare(
+                "SELECT e.id, e.description FROM events e
+                 LEFT JOIN event_embeddings m ON m.event_id = e.id AND m.model = ?1
+                 WHERE e.description IS NOT NULL AND e.description != '' AND m.event_id IS NULL
+                 ORDER BY e.timestamp DESC LIMIT ?2",
+            )
+            .map_err(|e| e.to_string())?;
+
+        let rows = stmt
+            .query_map(params![model, limit as i64], |row| Ok((row.get(0)?, encryption::open_text(row.get(1)?))))
+            .map_err(|e| format!("Failed to query events: {}", e))?;
+
+        let events = rows.collect::<Result<Vec<_>, _>>().map_err(|e| format!("Failed to read events: {}", e))?;
+        Ok(events.into_iter().filter(|(_, description)| description != encryption::LOCKED).collect())
+    }
+
+    // Every event in a time range, oldest first (used for aggregation)
+    pub fn events_between(&self, from: &str, to: &str) -> Result<Vec<StoredEvent>, String> {
+        let mut stmt = self
+            .conn
+            .prepare(
+                "SELECT id, timestamp, camera_id, event_type, person_count, object_counts, provider, prompt, description, payload
+                 FROM events WHERE timestamp >= ?1 AND timestamp < ?2 ORDER BY timestamp ASC",
+            )
+            .map_err(|e| e.to_string())?;
+
+        let rows = stmt
+            .query_map(params![from, to], Self::row_to_event)
+            .map_err(|e| format!("Failed to query events: {}", e))?;
+
+        rows.collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("Failed to read events: {}", e))
+    }
+
+    pub fn save_ab_test(&mut self, record: &AbTestRecord) -> Result<(), String> {
+        let tx = self.conn.transaction().map_err(|e| e.to_string())?;
+
+        tx.execute(
+            "INSERT INTO ab_tests (id, timestamp, prompt, total_time_ms, winner, notes, rated_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            params![
+                record.test_id,
+                record.timestamp,
+                record.prompt,
+                record.total_time_ms as i64,
+                record.winner,
+                record.notes,
+                record.rated_at,
+            ],
+        )
+        .map_err(|e| format!("Failed to store A/B test: {}", e))?;
+
+        for outcome in &record.outcomes {
+            tx.execute(
+                "INSERT INTO ab_test_results (test_id, provider, success, latency_ms, result, error)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                params![
+                    record.test_id,
+                    outcome.provider,
+                    outcome.success,
+                    outcome.latency_ms as i64,
+                    outcome.result.as_ref().map(|v| v.to_string()),
+                    outcome.error,
+                ],
+            )
+            .map_err(|e| format!("Failed to store A/B test result: {}", e))?;
+        }
+
+        tx.commit().map_err(|e| format!("Failed to store A/B test: {}", e))
+    }
+
+    // Record the human judgment for a comparison; returns false if the test doesn't exist
+    pub fn rate_ab_test(&self, test_id: &str, winner: &str, notes: Option<&str>) -> Result<bool, String> {
+        let updated = self
+            .conn
+            .execute(
+                "UPDATE ab_tests SET winner = ?2, notes = ?3, rated_at = ?4 WHERE id = ?1",
+                params![test_id, winner, notes, format_timestamp(Utc::now())],
+            )
+            .map_err(|e| format!("Failed to rate A/B test: {}", e))?;
+
+        Ok(updated > 0)
+    }
+
+    pub fn get_ab_test(&self, test_id: &str) -> Result<Option<AbTestRecord>, String> {
+        let mut records = self.load_ab_tests_where("t.id = ?1", &[test_id])?;
+        Ok(records.pop())
+    }
+
+    // Comparisons in a time range, oldest first
+    pub fn list_ab_tests(&self, from: Option<&str>, to: Option<&str>) -> Result<Vec<AbTestRecord>, String> {
+        let from = from.unwrap_or("");
+        let to = to.unwrap_or("9999");
+        self.load_ab_tests_where("t.timestamp >= ?1 AND t.timestamp < ?2", &[from, to])
+    }
+
+    fn load_ab_tests_where(&self, condition: &str, values: &[&str]) -> Result<Vec<AbTestRecord>, String> {
+        let sql = format!(
+            "SELECT t.id, t.timestamp, t.prompt, t.total_time_ms, t.winner, t.notes, t.rated_at,
+                    r.provider, r.success, r.latency_ms, r.result, r.error
+             FROM ab_tests t LEFT JOIN ab_test_results r ON r.test_id = t.id
+             WHERE {} ORDER BY t.timestamp ASC, t.id, r.provider",
+            condition
+        );
+
+        let mut stmt = self.conn.prepare(&sql).map_err(|e| e.to_string())?;
+        let mut rows = stmt
+            .query(rusqlite::params_from_iter(values.iter()))
+            .map_err(|e| format!("Failed to query A/B tests: {}", e))?;
+
+        let mut records: Vec<AbTestRecord> = Vec::new();
+        while let Some(row) = rows.next().map_err(|e| e.to_string())? {
+            let test_id: String = row.get(0).map_err(|e| e.to_string())?;
+            if records.last().map(|r| r.test_id != test_id).unwrap_or(true) {
+                records.push(AbTestRecord {
+                    test_id,
+                    timestamp: row.get(1).map_err(|e| e.to_string())?,
+                    prompt: row.get(2).map_err(|e| e.to_string())?,
+                    total_time_ms: row.get::<_, i64>(3).map_err(|e| e.to_string())? as u64,
+                    outcomes: Vec::new(),
+                    winner: row.get(4).map_err(|e| e.to_string())?,
+                    notes: row.get(5).map_err(|e| e.to_string())?,
+                    rated_at: row.get(6).map_err(|e| e.to_string())?,
+                });
+            }
+
+            let provider: Option<String> = row.get(7).map_err(|e| e.to_string())?;
+            if let (Some(provider), Some(record)) = (provider, records.last_mut()) {
+                let result: Option<String> = row.get(10).map_err(|e| e.to_string())?;
+                record.outcomes.push(ProviderOutcome {
+                    provider,
+                    success: row.get(8).map_err(|e| e.to_string())?,
+                    latency_ms: row.get::<_, i64>(9).map_err(|e| e.to_string())? as u64,
+                    result: result.and_then(|s| serde_json::from_str(&s).ok()),
+                    error: row.get(11).map_err(|e| e.to_string())?,
+                });
+            }
+        }
+
+        Ok(records)
+    }
+
+    // Store (or replace) feedback for an event; returns false if the event doesn't exist
+    pub fn submit_feedback(&self, feedback: &EventFeedback) -> Result<bool, String> {
+        if !FEEDBACK_VERDICTS.contains(&feedback.verdict.as_str()) {
+            return Err(format!(
+                "Unknown verdict '{}' (use {})",
+                feedback.verdict,
+                FEEDBACK_VERDICTS.join(", ")
+            ));
+        }
+        if self.get_event(&feedback.event_id)?.is_none() {
+            return Ok(false);
+        }
+
+        let corrected_boxes = feedback
+            .corrected_boxes
+            .as_ref()
+            .map(serde_json::to_string)
+            .transpose()
+            .map_err(|e| e.to_string())?;
+        self.conn
+            .execute(
+                "INSERT OR REPLACE INTO event_feedback (event_id, verdict, corrected_boxes, corrected_description, submitted_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5)",
+                params![
+                    feedback.event_id,
+                    feedback.verdict,
+                    corrected_boxes,
+                    feedback.corrected_description,
+                    feedback.submitted_at,
+                ],
+            )
+            .map_err(|e| format!("Failed to store feedback: {}", e))?;
+
+        Ok(true)
+    }
+
+    pub fn get_feedback(&self, event_id: &str) -> Result<Option<EventFeedback>, String> {
+        self.conn
+            .query_row(
+                "SELECT event_id, verdict, corrected_boxes, corrected_description, submitted_at
+                 FROM event_feedback WHERE event_id = ?1",
+                params![event_id],
+                |row| {
+                    let corrected_boxes: Option<String> = row.get(2)?;
+                    Ok(EventFeedback {
+                        event_id: row.get(0)?,
+                        verdict: row.get(1)?,
+                        corrected_boxes: corrected_boxes.and_then(|s| serde_json::from_str(&s).ok()),
+                        corrected_description: row.get(3)?,
+                        submitted_at: row.get(4)?,
+                    })
+                },
+            )
+            .optional()
+            .map_err(|e| format!("Failed to load feedback: {}", e))
+    }
+
+    pub fn feedback_stats(&self) -> Result<Vec<FeedbackStats>, String> {
+        let mut stmt = self
+            .conn
+            .prepare(
+                "SELECT COALESCE(e.provider, 'unknown'), f.verdict,
+                        f.corrected_boxes IS NOT NULL OR f.corrected_description IS NOT NULL
+                 FROM event_feedback f JOIN events e ON e.id = f.event_id
+                 ORDER BY 1",
+            )
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?, row.get::<_, bool>(2)?)))
+            .map_err(|e| format!("Failed to query feedback: {}", e))?;
+
+        let mut stats: Vec<FeedbackStats> = Vec::new();
+        for row in rows {
+            let (provider, verdict, corrected) = row.map_err(|e| format!("Failed to read feedback: {}", e))?;
+            if stats.last().map(|s| s.provider != provider).unwrap_or(true) {
+                stats.push(FeedbackStats { provider, ..Default::default() });
+            }
+            let Some(entry) = stats.last_mut() else { continue };
+            match verdict.as_str() {
+                "correct" => entry.correct += 1,
+                "partial" => entry.partial += 1,
+                _ => entry.incorrect += 1,
+            }
+            if corrected {
+                entry.corrected += 1;
+            }
+        }
+
+        Ok(stats)
+    }
+
+    // Alerts marked incorrect since a time, each with when it was marked; alert suppression learns from them
+    pub fn dismissed_alerts(&self, since: &str) -> Result<Vec<(StoredEvent, String)>, String> {
+        let mut stmt = self
+            .conn
+            .prepare(
+                "SELECT e.id, e.timestamp, e.camera_id, e.event_type, e.person_count, e.object_counts, e.provider, e.prompt,
+                        e.description, e.payload, f.submitted_at
+                 FROM event_feedback f JOIN events e ON e.id = f.event_id
+                 WHERE e.event_type = 'alert' AND f.verdict = 'incorrect' AND f.submitted_at >= ?1
+                 ORDER BY f.submitted_at ASC",
+            )
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map(params![since], |row| Ok((Self::row_to_event(row)?, row.get(10)?)))
+            .map_err(|e| format!("Failed to query dismissed alerts: {}", e))?;
+
+        rows.collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("Failed to read dismissed alerts: {}", e))
+    }
+
+    pub fn link_trigger(&self, trigger: &ExternalTrigger) -> Result<(), String> {
+        self.conn
+            .execute(
+                "INSERT OR REPLACE INTO external_triggers (event_id, source, external_id, received_at)
+                 VALUES (?1, ?2, ?3, ?4)",
+                params![trigger.event_id, trigger.source, trigger.external_id, trigger.received_at],
+            )
+            .map_err(|e| format!("Failed to store trigger: {}", e))?;
+        Ok(())
+    }
+
+    pub fn record_ppe_check(&self, check: &PpeCheck) -> Result<(), String> {
+        self.conn
+            .execute(
+                "INSERT INTO ppe_checks (checked_at, camera_id, zone, shift, track_id, missing, alert_id)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+                params![
+                    check.checked_at,
+                    check.camera_id,
+                    check.zone,
+                    check.shift,
+                    check.track_id.map(|id| id as i64),
+                    check.missing.join(","),
+                    check.alert_id,
+                ],
+            )
+            .map_err(|e| format!("Failed to store PPE check: {}", e))?;
+        Ok(())
+    }
+
+    // Compliance per zone and shift for checks in [from, to)
+    pub fn ppe_compliance(&self, from: Option<&str>, to: Option<&str>) -> Result<Vec<PpeCompliance>, String> {
+        let mut stmt = self
+            .conn
+            .prepare(
+                "SELECT zone, shift, COUNT(*), SUM(missing = '')
+                 FROM ppe_checks WHERE checked_at >= ?1 AND checked_at < ?2
+                 GROUP BY zone, shift ORDER BY zone, shift",
+            )
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map(params![from.unwrap_or(""), to.unwrap_or("9999")], |row| {
+                let checks: u32 = row.get(2)?;
+                let compliant: u32 = row.get(3)?;
+                Ok(PpeCompliance {
+                    zone: row.get(0)?,
+                    shift: row.get(1)?,
+                    checks,
+                    compliant,
+                    rate: compliant as f32 / checks.max(1) as f32,
+                })
+            })
+            .map_err(|e| format!("Failed to query PPE compliance: {}", e))?;
+        rows.collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("Failed to read PPE compliance: {}", e))
+    }
+
+    // Add the alert to the unresolved incident about the same thing, or open one; the alert is linked once stored
+    pub fn track_incident(&self, alert: &Alert, now: DateTime<Utc>) -> Result<Incident, String> {
+        let kind = incidents::alert_kind(&alert.payload);
+        let subject = incidents::subject(&alert.payload);
+        let priority = alert.payload["priority"].as_str().map(str::to_string);
+        let description = encryption::seal_text(&alert.description)?;
+        let timestamp = format_timestamp(now);
+
+        let existing: Option<String> = self
+            .conn
+            .query_row(
+                "SELECT id FROM incidents WHERE kind = ?1 AND camera_id IS ?2 AND subject IS ?3 AND state != 'resolved'
+                 ORDER BY opened_at DESC LIMIT 1",
+                params![kind, alert.camera_id, subject],
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(|e| format!("Failed to look up incident: {}", e))?;
+
+        let id = match existing {
+            Some(id) => {
+                self.conn
+                    .execute(
+                        "UPDATE incidents SET description = ?2, priority = COALESCE(?3, priority), updated_at = ?4,
+                         alert_count = alert_count + 1, last_alert_id = ?5 WHERE id = ?1",
+                        params![id, description, priority, timestamp, alert.id],
+                    )
+                    .map_err(|e| format!("Failed to update incident: {}", e))?;
+                id
+            }
+            None => {
+                let id = uuid::Uuid::new_v4().to_string();
+                self.conn
+                    .execute(
+                        "INSERT INTO incidents (id, camera_id, kind, subject, priority, description, state, opened_at, updated_at, alert_count, last_alert_id)
+                         VALUES (?1, ?2, ?3, ?4, ?5, ?6, 'open', ?7, ?7, 1, ?8)",
+                        params![id, alert.camera_id, kind, subject, priority, description, timestamp, alert.id],
+                    )
+                    .map_err(|e| format!("Failed to open incident: {}", e))?;
+                id
+            }
+        };
+        self.get_incident(&id)?.ok_or_else(|| format!("Incident {} disappeared", id))
+    }
+
+    fn link_incident_alert(&self, incident_id: &str, alert_id: &str) -> Result<(), String> {
+        self.conn
+            .execute(
+                "INSERT OR IGNORE INTO incident_alerts (incident_id, alert_id) VALUES (?1, ?2)",
+                params![incident_id, alert_id],
+            )
+            .map_err(|e| format!("Failed to link alert to incident: {}", e))?;
+        Ok(())
+    }
+
+    pub fn get_incident(&self, id: &str) -> Result<Option<Incident>, String> {
+        self.conn
+            .query_row(
+                &format!("SELECT {} FROM incidents WHERE id = ?1", INCIDENT_COLUMNS),
+                params![id],
+                Self::row_to_incident,
+            )
+            .optional()
+            .map_err(|e| format!("Failed to load incident: {}", e))
+    }
+
+    pub fn list_incidents(&self, filter: &IncidentFilter) -> Result<Vec<Incident>, String> {
+        let mut sql = format!("SELECT {} FROM incidents WHERE 1 = 1", INCIDENT_COLUMNS);
+        let mut values: Vec<String> = Vec::new();
+
+        if let Some(state) = &filter.state {
+            values.push(IncidentState::parse(state)?.as_str().to_string());
+            sql.push_str(&format!(" AND state = ?{}", values.len()));
+        }
+        if let Some(camera_id) = &filter.camera_id {
+            values.push(camera_id.clone());
+            sql.push_str(&format!(" AND camera_id = ?{}", values.len()));
+        }
+        if let Some(kind) = &filter.kind {
+            values.push(kind.clone());
+            sql.push_str(&format!(" AND kind = ?{}", values.len()));
+        }
+
+        let limit = filter.limit.unwrap_or(100).min(MAX_QUERY_ROWS);
+        sql.push_str(&format!(" ORDER BY opened_at DESC LIMIT {}", limit));
+
+        let mut stmt = self.conn.prepare(&sql).map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map(rusqlite::params_from_iter(values.iter()), Self::row_to_incident)
+            .map_err(|e| format!("Failed to query incidents: {}", e))?;
+        rows.collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("Failed to read incidents: {}", e))
+    }
+
+    // Ids of the alerts filed under an incident, oldest first
+    pub fn incident_alert_ids(&self, id: &str) -> Result<Vec<String>, String> {
+        let mut stmt = self
+            .conn
+            .prepare(
+                "SELECT a.alert_id FROM incident_alerts a JOIN events e ON e.id = a.alert_id
+                 WHERE a.incident_id = ?1 ORDER BY e.timestamp",
+            )
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map(params![id], |row| row.get(0))
+            .map_err(|e| format!("Failed to query incident alerts: {}", e))?;
+        rows.collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("Failed to read incident alerts: {}", e))
+    }
+
+    // Someone is on it; acknowledging twice keeps the first acknowledgment
+    pub fn acknowledge_incident(&self, id: &str, by: Option<&str>, now: DateTime<Utc>) -> Result<Incident, String> {
+        let incident = self.get_incident(id)?.ok_or_else(|| format!("Unknown incident: {}", id))?;
+        match incident.state {
+            IncidentState::Resolved => Err("Incident is already resolved".to_string()),
+            IncidentState::Acknowledged => Ok(incident),
+            IncidentState::Open => {
+                self.conn
+                    .execute(
+                        "UPDATE incidents SET state = 'acknowledged', acknowledged_at = ?2, acknowledged_by = ?3 WHERE id = ?1",
+                        params![id, format_timestamp(now), by],
+                    )
+                    .map_err(|e| format!("Failed to acknowledge incident: {}", e))?;
+                self.log_incident_activity(id, by, "acknowledged", None, now)?;
+                self.get_incident(id)?.ok_or_else(|| format!("Unknown incident: {}", id))
+            }
+        }
+    }
+
+    // None when the incident was already resolved; `by` is None when the app resolved it itself
+    pub fn resolve_incident(&self, id: &str, resolution: &str, by: Option<&str>, now: DateTime<Utc>) -> Result<Option<Incident>, String> {
+        let changed = self
+            .conn
+            .execute(
+                "UPDATE incidents SET state = 'resolved', resolved_at = ?2, resolution = ?3 WHERE id = ?1 AND state != 'resolved'",
+                params![id, format_timestamp(now), resolution],
+            )
+            .map_err(|e| format!("Failed to resolve incident: {}", e))?;
+        if changed == 0 {
+            return Ok(None);
+        }
+        self.log_incident_activity(id, by, "resolved", Some(resolution), now)?;
+        self.get_incident(id)
+    }
+
+    fn resolve_all(&self, ids: Vec<String>, resolution: &str, now: DateTime<Utc>) -> Result<Vec<Incident>, String> {
+        let mut resolved = Vec::new();
+        for id in ids {
+            resolved.extend(self.resolve_incident(&id, resolution, None, now)?);
+        }
+        Ok(resolved)
+    }
+
+    // The incident an alert was filed under
+    pub fn incident_for_alert(&self, alert_id: &str) -> Result<String, String> {
+        self.conn
+            .query_row(
+                "SELECT incident_id FROM incident_alerts WHERE alert_id = ?1",
+                params![alert_id],
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(|e| format!("Failed to look up incident: {}", e))?
+            .ok_or_else(|| format!("Alert {} is not part of an incident", alert_id))
+    }
+
+    // Silence alert channels for an incident until `until`; it stays open and keeps collecting alerts
+    pub fn snooze_incident(&self, id: &str, until: DateTime<Utc>, by: Option<&str>, now: DateTime<Utc>) -> Result<Incident, String> {
+        let incident = self.get_incident(id)?.ok_or_else(|| format!("Unknown incident: {}", id))?;
+        if incident.state == IncidentState::Resolved {
+            return Err("Incident is already resolved".to_string());
+        }
+        let until = format_timestamp(until);
+        self.conn
+            .execute("UPDATE incidents SET snoozed_until = ?2 WHERE id = ?1", params![id, until])
+            .map_err(|e| format!("Failed to snooze incident: {}", e))?;
+        self.log_incident_activity(id, by, "snoozed", Some(&format!("until {}", until)), now)?;
+        self.get_incident(id)?.ok_or_else(|| format!("Unknown incident: {}", id))
+    }
+
+    // None takes the incident off whoever had it
+    pub fn assign_incident(&self, id: &str, assignee: Option<&str>, by: Option<&str>, now: DateTime<Utc>) -> Result<Incident, String> {
+        let changed = self
+            .conn
+            .execute("UPDATE incidents SET assigned_to = ?2 WHERE id = ?1", params![id, assignee])
+            .map_err(|e| format!("Failed to assign incident: {}", e))?;
+        if changed == 0 {
+            return Err(format!("Unknown incident: {}", id));
+        }
+        self.log_incident_activity(id, by, if assignee.is_some() { "assigned" } else { "unassigned" }, assignee, now)?;
+        self.get_incident(id)?.ok_or_else(|| format!("Unknown incident: {}", id))
+    }
+
+    // A free-text note for the next operator; allowed after resolution too
+    pub fn annotate_incident(&self, id: &str, note: &str, by: Option<&str>, now: DateTime<Utc>) -> Result<Incident, String> {
+        let incident = self.get_incident(id)?.ok_or_else(|| format!("Unknown incident: {}", id))?;
+        if note.trim().is_empty() {
+            return Err("Note cannot be empty".to_string());
+        }
+        self.log_incident_activity(id, by, "note", Some(note.trim()), now)?;
+        Ok(incident)
+    }
+
+    fn log_incident_activity(&self, id: &str, actor: Option<&str>, action: &str, detail: Option<&str>, now: DateTime<Utc>) -> Result<(), String> {
+        let detail = detail.map(encryption::seal_text).transpose()?;
+        self.conn
+            .execute(
+                "INSERT INTO incident_activity (incident_id, timestamp, actor, action, detail) VALUES (?1, ?2, ?3, ?4, ?5)",
+                params![id, format_timestamp(now), actor, action, detail],
+            )
+            .map_err(|e| format!("Failed to record incident activity: {}", e))?;
+        Ok(())
+    }
+
+    // What operators did to an incident, oldest first
+    pub fn incident_activity(&self, id: &str) -> Result<Vec<IncidentActivity>, String> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT timestamp, actor, action, detail FROM incident_activity WHERE incident_id = ?1 ORDER BY seq")
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map(params![id], |row| {
+                let detail: Option<String> = row.get(3)?;
+                Ok(IncidentActivity {
+                    timestamp: row.get(0)?,
+                    actor: row.get(1)?,
+                    action: row.get(2)?,
+                    detail: detail.map(encryption::open_text),
+                })
+            })
+            .map_err(|e| format!("Failed to query incident activity: {}", e))?;
+        rows.collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("Failed to read incident activity: {}", e))
+    }
+
+    // A later look found the hazard gone, e.g. the spill was cleaned up
+    pub fn resolve_cleared_incidents(&self, kind: &str, camera_id: Option<&str>, now: DateTime<Utc>) -> Result<Vec<Incident>, String> {
+        let ids = self.unresolved_incident_ids("kind = ?1 AND camera_id IS ?2", params![kind, camera_id])?;
+        self.resolve_all(ids, "cleared", now)
+    }
+
+    // Incidents whose last alert is older than the cutoff
+    pub fn resolve_quiet_incidents(&self, cutoff: &str, now: DateTime<Utc>) -> Result<Vec<Incident>, String> {
+        let ids = self.unresolved_incident_ids("updated_at < ?1", params![cutoff])?;
+        self.resolve_all(ids, "quiet", now)
+    }
+
+    fn unresolved_incident_ids(&self, condition: &str, values: &[&dyn rusqlite::ToSql]) -> Result<Vec<String>, String> {
+        let sql = format!("SELECT id FROM incidents WHERE state != 'resolved' AND {}", condition);
+        let mut stmt = self.conn.prepare(&sql).map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map(values, |row| row.get(0))
+            .map_err(|e| format!("Failed to query incidents: {}", e))?;
+        rows.collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("Failed to read incidents: {}", e))
+    }
+
+    // Counts and response times per kind for incidents opened in [from, to)
+    pub fn incident_metrics(&self, from: Option<&str>, to: Option<&str>) -> Result<Vec<IncidentMetrics>, String> {
+        let mut stmt = self
+            .conn
+            .prepare(
+                "SELECT kind, COUNT(*), SUM(state = 'resolved'), SUM(state != 'resolved'),
+                        AVG((julianday(acknowledged_at) - julianday(opened_at)) * 86400),
+                        AVG((julianday(resolved_at) - julianday(opened_at)) * 86400),
+                        MAX((julianday(resolved_at) - julianday(opened_at)) * 86400)
+                 FROM incidents WHERE opened_at >= ?1 AND opened_at < ?2
+                 GROUP BY kind ORDER BY kind",
+            )
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map(params![from.unwrap_or(""), to.unwrap_or("9999")], |row| {
+                Ok(IncidentMetrics {
+                    kind: row.get(0)?,
+                    opened: row.get(1)?,
+                    resolved: row.get(2)?,
+                    still_open: row.get(3)?,
+                    avg_secs_to_acknowledge: row.get(4)?,
+                    avg_duration_secs: row.get(5)?,
+                    max_duration_secs: row.get(6)?,
+                })
+            })
+            .map_err(|e| format!("Failed to query incident metrics: {}", e))?;
+        rows.collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("Failed to read incident metrics: {}", e))
+    }
+
+    fn row_to_incident(row: &rusqlite::Row) -> rusqlite::Result<Incident> {
+        let state: String = row.get(6)?;
+        let opened_at: String = row.get(7)?;
+        let resolved_at: Option<String> = row.get(11)?;
+        Ok(Incident {
+            id: row.get(0)?,
+            camera_id: row.get(1)?,
+            kind: row.get(2)?,
+            subject: row.get(3)?,
+            priority: row.get(4)?,
+            description: encryption::open_text(row.get(5)?),
+            state: IncidentState::parse(&state).unwrap_or(IncidentState::Open),
+            duration_secs: incidents::duration_secs(&opened_at, resolved_at.as_deref(), Utc::now()),
+            opened_at,
+            updated_at: row.get(8)?,
+            acknowledged_at: row.get(9)?,
+            acknowledged_by: row.get(10)?,
+            resolved_at,
+            resolution: row.get(12)?,
+            alert_count: row.get(13)?,
+            last_alert_id: row.get(14)?,
+            assigned_to: row.get(15)?,
+            snoozed_until: row.get(16)?,
+        })
+    }
+
+    pub fn record_usage(&self, event_id: &str, provider: &str, model: &str, usage: &TokenUsage) -> Result<(), String> {
+        self.conn
+            .execute(
+                "INSERT OR REPLACE INTO analysis_usage
+                 (event_id, recorded_at, provider, model, input_tokens, output_tokens, tokens_per_second, load_ms, prompt_eval_ms, eval_ms, total_ms)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+                params![
+                    event_id,
+                    format_timestamp(Utc::now()),
+                    provider,
+                    model,
+                    usage.input_tokens,
+                    usage.output_tokens,
+                    usage.tokens_per_second,
+                    usage.load_ms,
+                    usage.prompt_eval_ms,
+                    usage.eval_ms,
+                    usage.total_ms
+                ],
+            )
+            .map_err(|e| format!("Failed to store token usage: {}", e))?;
+        Ok(())
+    }
+
+    // Per provider and model for analyses recorded in [from, to); AVG skips the timings cloud APIs don't report
+    pub fn model_performance(&self, from: Option<&str>, to: Option<&str>) -> Result<Vec<ModelPerformance>, String> {
+        let mut stmt = self
+            .conn
+            .prepare(
+                "SELECT provider, model, COUNT(*), AVG(input_tokens), AVG(output_tokens), AVG(tokens_per_second), AVG(load_ms), AVG(total_ms)
+                 FROM analysis_usage WHERE recorded_at >= ?1 AND recorded_at < ?2
+                 GROUP BY provider, model ORDER BY provider, model",
+            )
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map(params![from.unwrap_or(""), to.unwrap_or("9999")], |row| {
+                Ok(ModelPerformance {
+                    provider: row.get(0)?,
+                    model: row.get(1)?,
+                    analyses: row.get(2)?,
+                    avg_input_tokens: row.get(3)?,
+                    avg_output_tokens: row.get(4)?,
+                    avg_tokens_per_second: row.get(5)?,
+                    avg_load_ms: row.get(6)?,
+                    avg_total_ms: row.get(7)?,
+                })
+            })
+            .map_err(|e| format!("Failed to query model performance: {}", e))?;
+        rows.collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("Failed to read model performance: {}", e))
+    }
+
+    pub fn record_plate_read(&self, read: &PlateRead) -> Result<(), String> {
+        self.conn
+            .execute(
+                "INSERT INTO plate_reads (read_at, camera_id, plate, list, alert_id) VALUES (?1, ?2, ?3, ?4, ?5)",
+                params![read.read_at, read.camera_id, read.plate, read.list, read.alert_id],
+            )
+            .map_err(|e| format!("Failed to store plate read: {}", e))?;
+        Ok(())
+    }
+
+    // Plate reads in [from, to), newest first, optionally for one plate
+    pub fn plate_reads(
+        &self,
+        plate: Option<&str>,
+        from: Option<&str>,
+        to: Option<&str>,
+        limit: Option<usize>,
+    ) -> Result<Vec<PlateRead>, String> {
+        let limit = limit.unwrap_or(100).min(MAX_QUERY_ROWS);
+        let mut stmt = self
+            .conn
+            .prepare(&format!(
+                "SELECT read_at, camera_id, plate, list, alert_id FROM plate_reads
+                 WHERE (?1 IS NULL OR plate = ?1) AND read_at >= ?2 AND read_at < ?3
+                 ORDER BY read_at DESC LIMIT {}",
+                limit
+            ))
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map(params![plate, from.unwrap_or(""), to.unwrap_or("9999")], |row| {
+                Ok(PlateRead {
+                    read_at: row.get(0)?,
+                    camera_id: row.get(1)?,
+                    plate: row.get(2)?,
+                    list: row.get(3)?,
+                    alert_id: row.get(4)?,
+                })
+            })
+            .map_err(|e| format!("Failed to query plate reads: {}", e))?;
+        rows.collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("Failed to read plate reads: {}", e))
+    }
+
+    // Events recorded for an external event id, oldest first
+    pub fn trigger_events(&self, external_id: &str) -> Result<Vec<StoredEvent>, String> {
+        let mut stmt = self
+            .conn
+            .prepare(
+                "SELECT e.id, e.timestamp, e.camera_id, e.event_type, e.person_count, e.object_counts, e.provider, e.prompt, e.description, e.payload
+                 FROM external_triggers t JOIN events e ON e.id = t.event_id
+                 WHERE t.external_id = ?1
+                 ORDER BY e.timestamp",
+            )
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map(params![external_id], Self::row_to_event)
+            .map_err(|e| format!("Failed to query triggers: {}", e))?;
+        rows.collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("Failed to read trigger events: {}", e))
+    }
+
+    // Run a SELECT statement with writes disabled, returning rows as JSON objects
+    pub fn query_read_only(&self, sql: &str) -> Result<QueryRows, String> {
+        self.conn
+            .execute_batch("PRAGMA query_only = ON")
+            .map_err(|e| e.to_string())?;
+
+        let result = self.query_rows(sql);
+
+        self.conn
+            .execute_batch("PRAGMA query_only = OFF")
+            .map_err(|e| e.to_string())?;
+
+        result
+    }
+
+    fn query_rows(&self, sql: &str) -> Result<QueryRows, String> {
+        let mut stmt = self
+            .conn
+            .prepare(sql)
+            .map_err(|e| format!("Invalid query: {}", e))?;
+
+        let columns: Vec<String> = stmt.column_names().iter().map(|c| c.to_string()).collect();
+        let mut rows = stmt.query([]).map_err(|e| format!("Query failed: {}", e))?;
+
+        let mut output = Vec::new();
+        let mut truncated = false;
+        while let Some(row) = rows.next().map_err(|e| format!("Query failed: {}", e))? {
+            if output.len() >= MAX_QUERY_ROWS {
+                truncated = true;
+                break;
+            }
+
+            let mut object = serde_json::Map::new();
+            for (index, column) in columns.iter().enumerate() {
+                let value = match row.get_ref(index).map_err(|e| e.to_string())? {
+                    rusqlite::types::ValueRef::Null => serde_json::Value::Null,
+                    rusqlite::types::ValueRef::Integer(i) => serde_json::json!(i),
+                    rusqlite::types::ValueRef::Real(f) => serde_json::json!(f),
+                    rusqlite::types::ValueRef::Text(t) => serde_json::json!(encryption::open_text(String::from_utf8_lossy(t).into_owned())),
+                    rusqlite::types::ValueRef::Blob(b) => serde_json::json!(format!("<{} bytes>", b.len())),
+                };
+                object.insert(column.clone(), value);
+            }
+            output.push(serde_json::Value::Object(object));
+        }
+
+        Ok(QueryRows {
+            columns,
+            rows: output,
+            truncated,
+        })
+    }
+
+    fn row_to_event(row: &rusqlite::Row) -> rusqlite::Result<StoredEvent> {
+        let object_counts: Option<String> = row.get(5)?;
+        let payload: Option<String> = row.get(9)?;
+
+        Ok(StoredEvent {
+            id: row.get(0)?,
+            timestamp: row.get(1)?,
+            camera_id: row.get(2)?,
+            event_type: row.get(3)?,
+            person_count: row.get(4)?,
+            object_counts: object_counts.and_then(|s| serde_json::from_str(&s).ok()),
+            provider: row.get(6)?,
+            prompt: row.get(7)?,
+            description: row.get::<_, Option<String>>(8)?.map(encryption::open_text),
+            payload: payload.map(encryption::open_text).and_then(|s| serde_json::from_str(&s).ok()),
+        })
+    }
+}
+
+// An alert its second look didn't back, or one operators keep dismissing, is kept for review outside any incident
+fn held_back(payload: &serde_json::Value) -> bool {
+    verification::rejected(payload) || suppression::suppressed(payload)
+}
+
+// Mark an alert against learned dismissals, save its evidence and, unless it is held back, its preview, then store it.
+// Both are on disk before anyone is notified; the preview is encoded on a blocking thread, outside the store lock
+pub async fn file_alert(events: &Mutex<EventStore>, alert: Arc<Alert>) -> Result<(), String> {
+    let alert = suppression::mark(&settings::current().suppression, alert, chrono::Local::now());
+    if let Some(frame) = &alert.snapshot {
+        if let Err(e) = dataset::save_snapshot(&alert.id, frame) {
+            eprintln!("Failed to save alert evidence: {}", e);
+        }
+        if !held_back(&alert.payload) {
+            let (camera_id, id, frame) = (alert.camera_id.clone(), alert.id.clone(), frame.clone());
+            let saved = tokio::task::spawn_blocking(move || preview::save(camera_id.as_deref(), &id, &frame)).await;
+            if let Err(e) = saved.map_err(|e| e.to_string()).and_then(|saved| saved) {
+                eprintln!("Failed to save alert preview: {}", e);
+            }
+        }
+    }
+    events.lock().await.store_alert(&alert)
+}
+
+// Store an alert raised by an analytic outside the bus (e.g. a PPE violation), with the frame as its evidence
+pub async fn record_alert(
+    events: &Mutex<EventStore>,
+    camera_id: Option<&str>,
+    provider: &str,
+    description: &str,
+    payload: serde_json::Value,
+    frame_base64: &str,
+) -> Result<String, String> {
+    let alert = Arc::new(Alert::new(camera_id, provider, description, payload, Some(frame_base64)));
+    file_alert(events, alert.clone()).await?;
+    Ok(alert.id.clone())
+}
+
+// Storage side of the bus: live detections with their snapshot
+pub async fn follow_bus(events: Arc<Mutex<EventStore>>, mut bus: broadcast::Receiver<BusEvent>) {
+    loop {
+        let event = match bus.recv().await {
+            Ok(event) => event,
+            Err(RecvError::Lagged(skipped)) => {
+                eprintln!("Event store missed {} bus events", skipped);
+                continue;
+            }
+            Err(RecvError::Closed) => break,
+        };
+        match event {
+            BusEvent::DetectionReady { camera_id, detection, live: true, snapshot, .. } => {
+                // The snapshot is written under the store lock so subscribers to stored events can wait for it
+                let mut store = events.lock().await;
+                match store.record_detection(camera_id.as_deref(), &detection) {
+                    Ok(Some(event_id)) if !detection.boxes.is_empty() => {
+                        if let Some(frame) = snapshot {
+                            if let Err(e) = dataset::save_snapshot(&event_id, &frame) {
+                                eprintln!("Failed to save detection snapshot: {}", e);
+                            }
+                        }
+                    }
+                    Ok(_) => {}
+                    Err(e) => eprintln!("Failed to store detection: {}", e),
+                }
+            }
+            // Alerts arrive on the bus's alert channel instead, see file_alerts
+            _ => {}
+        }
+    }
+}
+
+// Storage side of the alert channel: alerts filed one at a time in the order they were raised, none skipped.
+// The preview encodes on a blocking thread while this waits
+pub async fn file_alerts(events: Arc<Mutex<EventStore>>, mut alerts: mpsc::Receiver<Arc<Alert>>) {
+    while let Some(alert) = alerts.recv().await {
+        if let Err(e) = file_alert(&events, alert).await {
+            eprintln!("Failed to store alert: {}", e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn detection(person_count: u32) -> DetectionData {
+        DetectionData {
+            person_count,
+            object_counts: HashMap::from([("person".to_string(), person_count)]),
+            crowd_density: 0.1,
+            motion_intensity: 0.1,
+            zone_occupancy: 0.1,
+            boxes: Vec::new(),
+            smoothed_person_count: None,
+            visitors: None,
+            staff_count: None,
+            annotations: Default::default(),
+            escalate: None,
+        }
+    }
+
+    #[test]
+    fn test_detections_are_recorded_on_change() {
+        let mut store = EventStore::open_in_memory().unwrap();
+
+        assert!(store.record_detection(None, &detection(1)).unwrap().is_some());
+        // Same count within the heartbeat window is skipped
+        assert!(store.record_detection(None, &detection(1)).unwrap().is_none());
+        assert!(store.record_detection(None, &detection(2)).unwrap().is_some());
+
+        let events = store.list_events(&EventFilter::default()).unwrap();
+        assert_eq!(events.len(), 2);
+    }
+
+    #[test]
+    fn test_read_only_query_blocks_writes() {
+        let store = EventStore::open_in_memory().unwrap();
+        let id = store.record_analysis(None, "llava", "describe", "A person at the counter", None).unwrap();
+
+        let rows = store.query_read_only("SELECT COUNT(*) AS total FROM events").unwrap();
+        assert_eq!(rows.rows[0]["total"], 1);
+
+        assert!(store.query_read_only("DELETE FROM events").is_err());
+        assert!(store.get_event(&id).unwrap().is_some());
+    }
+
+    #[test]
+    fn test_ab_test_round_trip() {
+        let mut store = EventStore::open_in_memory().unwrap();
+        let record = AbTestRecord {
+            test_id: "t1".to_string(),
+            timestamp: format_timestamp(Utc::now()),
+            prompt: "describe".to_string(),
+            total_time_ms: 1200,
+            outcomes: vec![
+                ProviderOutcome { provider: "llava".to_string(), success: true, latency_ms: 1200, result: None, error: None },
+                ProviderOutcome { provider: "moondream".to_string(), success: false, latency_ms: 300, result: None, error: Some("401".to_string()) },
+            ],
+            winner: None,
+            notes: None,
+            rated_at: None,
+        };
+        store.save_ab_test(&record).unwrap();
+
+        assert!(store.rate_ab_test("t1", "llava", Some("more detail")).unwrap());
+        assert!(!store.rate_ab_test("missing", "llava", None).unwrap());
+
+        let loaded = store.get_ab_test("t1").unwrap().unwrap();
+        assert_eq!(loaded.outcomes.len(), 2);
+        assert_eq!(loaded.winner.as_deref(), Some("llava"));
+        assert_eq!(store.list_ab_tests(None, None).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_feedback_round_trip() {
+        let mut store = EventStore::open_in_memory().unwrap();
+        let id = store.record_detection(None, &detection(1)).unwrap().unwrap();
+        let feedback = EventFeedback {
+            event_id: id.clone(),
+            verdict: "partial".to_string(),
+            corrected_boxes: Some(vec![BoundingBox {
+                x1: 10.0,
+                y1: 10.0,
+                x2: 50.0,
+                y2: 90.0,
+                confidence: 1.0,
+                class_name: "person".to_string(),
+                track_id: None,
+                role: None,
+                floor: None,
+                motion: None,
+            }]),
+            corrected_description: None,
+            submitted_at: format_timestamp(Utc::now()),
+        };
+
+        assert!(store.submit_feedback(&feedback).unwrap());
+        assert!(!store.submit_feedback(&EventFeedback { event_id: "missing".to_string(), ..feedback.clone() }).unwrap());
+        assert!(store.submit_feedback(&EventFeedback { verdict: "great".to_string(), ..feedback.clone() }).is_err());
+
+        let loaded = store.get_feedback(&id).unwrap().unwrap();
+        assert_eq!(loaded.corrected_boxes.unwrap()[0].x2, 50.0);
+
+        let stats = store.feedback_stats().unwrap();
+        assert_eq!(stats[0].provider, "yolo");
+        assert_eq!((stats[0].partial, stats[0].corrected), (1, 1));
+
+        // Only alerts marked incorrect count as dismissed
+        let alert = Alert::new(Some("dock"), "rules", "Person at dock", serde_json::json!({ "kind": "rule" }), None);
+        store.store_alert(&alert).unwrap();
+        let alert_id = alert.id;
+        let since = format_timestamp(Utc::now() - chrono::Duration::days(1));
+        assert!(store.dismissed_alerts(&since).unwrap().is_empty());
+        store.submit_feedback(&EventFeedback { event_id: alert_id.clone(), verdict: "incorrect".to_string(), ..feedback }).unwrap();
+        let dismissed = store.dismissed_alerts(&since).unwrap();
+        assert_eq!((dismissed.len(), dismissed[0].0.id.as_str()), (1, alert_id.as_str()));
+    }
+
+    #[test]
+    fn test_remote_events() {
+        let store = EventStore::open_in_memory().unwrap();
+        let mut receiver = store.subscribe();
+        let event = StoredEvent {
+            id: "remote-1".to_string(),
+            timestamp: "2025-01-15T10:00:00.000Z".to_string(),
+            camera_id: Some("entrance".to_string()),
+            event_type: "analysis".to_string(),
+            person_count: None,
+            object_counts: None,
+            provider: Some("llava".to_string()),
+            prompt: None,
+            description: Some("Two shoppers at the counter".to_string()),
+            payload: None,
+        };
+        assert!(store.store_remote_event("store-12", &event).unwrap());
+        assert!(!store.store_remote_event("store-12", &event).unwrap());
+        assert_eq!(receiver.try_recv().unwrap().id, "remote-1");
+        store.record_analysis(None, "llava", "prompt", "local", None).unwrap();
+
+        let filter = EventFilter { instance: Some("store-12".to_string()), ..Default::default() };
+        assert_eq!(store.list_events(&filter).unwrap().len(), 1);
+        assert_eq!(store.list_events(&EventFilter::default()).unwrap().len(), 2);
+        assert_eq!(store.last_remote_timestamp("store-12").unwrap().as_deref(), Some("2025-01-15T10:00:00.000Z"));
+        assert_eq!(store.last_remote_timestamp("store-7").unwrap(), None);
+    }
+
+    #[test]
+    fn test_trigger_link() {
+        let store = EventStore::open_in_memory().unwrap();
+        let id = store.record_analysis(Some("entrance"), "llava", "Who is at the door?", "A courier", None).unwrap();
+        store
+            .link_trigger(&ExternalTrigger {
+                event_id: id.clone(),
+                source: "door-sensor".to_string(),
+                external_id: "door-7781".to_string(),
+                received_at: format_timestamp(Utc::now()),
+            })
+            .unwrap();
+
+        let events = store.trigger_events("door-7781").unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].id, id);
+        assert!(store.trigger_events("door-0000").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_embeddings_backfill() {
+        let store = EventStore::open_in_memory().unwrap();
+        let first = store.record_analysis(None, "llava", "describe", "Someone carrying a ladder", None).unwrap();
+        store.record_analysis(None, "llava", "describe", "An empty aisle", None).unwrap();
+
+        store.store_embedding(&first, "test-model", &[0.1, 0.2]).unwrap();
+
+        assert_eq!(store.events_missing_embeddings("test-model", 10).unwrap().len(), 1);
+        assert_eq!(store.load_embeddings("test-model").unwrap()[0].1, vec![0.1, 0.2]);
+    }
+
+    #[test]
+    fn test_ppe_compliance() {
+        let store = EventStore::open_in_memory().unwrap();
+        let alert = Alert::new(Some("dock"), "llava", "PPE violation: missing helmet", serde_json::json!({ "kind": "ppe" }), None);
+        store.store_alert(&alert).unwrap();
+        let alert_id = alert.id;
+        let check = |zone: &str, missing: &[&str], alert_id: Option<String>| PpeCheck {
+            checked_at: format_timestamp(Utc::now()),
+            camera_id: Some("dock".to_string()),
+            zone: Some(zone.to_string()),
+            shift: Some("morning".to_string()),
+            track_id: None,
+            missing: missing.iter().map(|m| m.to_string()).collect(),
+            alert_id,
+        };
+        store.record_ppe_check(&check("loading bay", &[], None)).unwrap();
+        store.record_ppe_check(&check("loading bay", &["helmet"], Some(alert_id))).unwrap();
+        store.record_ppe_check(&check("loading bay", &[], None)).unwrap();
+        store.record_ppe_check(&check("warehouse", &[], None)).unwrap();
+
+        let stats = store.ppe_compliance(None, None).unwrap();
+        assert_eq!(stats.len(), 2);
+        assert_eq!((stats[0].checks, stats[0].compliant), (3, 2));
+        assert!((stats[0].rate - 2.0 / 3.0).abs() < 1e-6);
+        assert_eq!(stats[1].rate, 1.0);
+    }
+
+    #[test]
+    fn test_model_performance() {
+        let store = EventStore::open_in_memory().unwrap();
+        let usage = |output_tokens, tokens_per_second| TokenUsage { input_tokens: 600, output_tokens, tokens_per_second, ..Default::default() };
+        for (model, output_tokens, speed) in [("llava:7b", 40, Some(20.0)), ("llava:7b", 60, Some(30.0)), ("gpt-4o-mini", 50, None)] {
+            let id = store.record_analysis(None, "test", "Describe", "Two people", None).unwrap();
+            store.record_usage(&id, "test", model, &usage(output_tokens, speed)).unwrap();
+        }
+
+        let stats = store.model_performance(None, None).unwrap();
+        assert_eq!(stats.len(), 2);
+        let llava = stats.iter().find(|s| s.model == "llava:7b").unwrap();
+        assert_eq!((llava.analyses, llava.avg_output_tokens, llava.avg_tokens_per_second), (2, 50.0, Some(25.0)));
+        assert_eq!(stats.iter().find(|s| s.model == "gpt-4o-mini").unwrap().avg_tokens_per_second, None);
+    }
+
+    #[test]
+    fn test_incident_lifecycle() {
+        let store = EventStore::open_in_memory().unwrap();
+        let start = Utc::now();
+        let alert = |id: &str, hazard: &str| Alert {
+            id: id.to_string(),
+            camera_id: Some("aisle-3".to_string()),
+            provider: "moondream".to_string(),
+            description: format!("Safety hazard: {}", hazard),
+            payload: serde_json::json!({ "kind": "safety", "priority": "medium", "hazard_type": hazard }),
+            snapshot: None,
+        };
+
+        // The same spill on later frames stays one incident; a different hazard opens another
+        let file = |alert: Alert, at: i64| {
+            store
+                .write_event(&StoredEvent {
+                    id: alert.id.clone(),
+                    timestamp: format_timestamp(start + chrono::Duration::seconds(at)),
+                    camera_id: alert.camera_id.clone(),
+                    event_type: "alert".to_string(),
+                    person_count: None,
+                    object_counts: None,
+                    provider: Some(alert.provider.clone()),
+                    prompt: None,
+                    description: Some(alert.description.clone()),
+                    payload: Some(alert.payload.clone()),
+                })
+                .unwrap();
+            let incident = store.track_incident(&alert, start + chrono::Duration::seconds(at)).unwrap();
+            store.link_incident_alert(&incident.id, &alert.id).unwrap();
+        };
+        for (i, id) in ["a1", "a2", "a3"].iter().enumerate() {
+            file(alert(id, "spill"), i as i64 * 10);
+        }
+        file(alert("b1", "obstruction"), 100);
+        let spills = store.list_incidents(&IncidentFilter { kind: Some("safety".to_string()), ..Default::default() }).unwrap();
+        assert_eq!(spills.len(), 2);
+        let spill = spills.iter().find(|i| i.subject.as_deref() == Some("spill")).unwrap();
+        assert_eq!((spill.alert_count, spill.state, spill.last_alert_id.as_deref()), (3, IncidentState::Open, Some("a3")));
+        assert_eq!(store.incident_alert_ids(&spill.id).unwrap().len(), 3);
+
+        let acknowledged = store.acknowledge_incident(&spill.id, Some("sam"), start + chrono::Duration::seconds(60)).unwrap();
+        assert_eq!((acknowledged.state, acknowledged.acknowledged_by.as_deref()), (IncidentState::Acknowledged, Some("sam")));
+
+        // Quiet since the cutoff resolves the spill only; the obstruction was reported just now
+        let quiet = store.resolve_quiet_incidents(&format_timestamp(start + chrono::Duration::seconds(25)), start + chrono::Duration::seconds(120)).unwrap();
+        assert_eq!(quiet.len(), 1);
+        assert_eq!((quiet[0].resolution.as_deref(), quiet[0].duration_secs), (Some("quiet"), 120));
+        assert!(store.acknowledge_incident(&spill.id, None, Utc::now()).is_err());
+
+        // A clean look at the aisle clears what is left
+        let cleared = store.resolve_cleared_incidents("safety", Some("aisle-3"), Utc::now()).unwrap();
+        assert_eq!(cleared.len(), 1);
+        let metrics = store.incident_metrics(None, None).unwrap();
+        assert_eq!((metrics[0].opened, metrics[0].resolved, metrics[0].still_open), (2, 2, 0));
+        assert!((metrics[0].avg_secs_to_acknowledge.unwrap() - 60.0).abs() < 0.01);
+
+        // A repeat spill after resolution is a new incident
+        store.store_alert(&alert("a4", "spill")).unwrap();
+        assert_eq!(store.list_incidents(&IncidentFilter { state: Some("open".to_string()), ..Default::default() }).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_alert_workflow_stops_notifications() {
+        let store = EventStore::open_in_memory().unwrap();
+        let mut live = store.subscribe();
+        let alert = |id: &str| Alert {
+            id: id.to_string(),
+            camera_id: Some("entrance".to_string()),
+            provider: "rules".to_string(),
+            description: "Crowd at the entrance".to_string(),
+            payload: serde_json::json!({ "kind": "crowd", "priority": "high", "rule": "entrance-crowd" }),
+            snapshot: None,
+        };
+        let notify = |live: &mut broadcast::Receiver<StoredEvent>| live.try_recv().unwrap().payload.unwrap()["notify"].clone();
+
+        // Not backed by its second look: stored, but no incident and nothing to announce
+        let mut unconfirmed = alert("c0");
+        unconfirmed.payload["verification"] = serde_json::json!({ "confirmed": false });
+        store.store_alert(&unconfirmed).unwrap();
+        assert_eq!(notify(&mut live), serde_json::json!(false));
+        assert!(store.incident_for_alert("c0").is_err());
+
+        store.store_alert(&alert("c1")).unwrap();
+        assert_eq!(notify(&mut live), serde_json::json!(true));
+        let incident_id = store.incident_for_alert("c1").unwrap();
+        assert!(store.incident_for_alert("missing").is_err());
+
+        // Snoozed: still collecting alerts, but channels stay quiet until it runs out
+        let now = Utc::now();
+        store.snooze_incident(&incident_id, now + chrono::Duration::minutes(10), Some("ana"), now).unwrap();
+        store.store_alert(&alert("c2")).unwrap();
+        assert_eq!(notify(&mut live), serde_json::json!(false));
+        store.snooze_incident(&incident_id, now - chrono::Duration::minutes(1), Some("ana"), now).unwrap();
+        store.store_alert(&alert("c3")).unwrap();
+        assert_eq!(notify(&mut live), serde_json::json!(true));
+
+        let assigned = store.assign_incident(&incident_id, Some("ben"), Some("ana"), now).unwrap();
+        assert_eq!(assigned.assigned_to.as_deref(), Some("ben"));
+        store.acknowledge_incident(&incident_id, Some("ben"), now).unwrap();
+        store.store_alert(&alert("c4")).unwrap();
+        assert_eq!(notify(&mut live), serde_json::json!(false));
+
+        store.annotate_incident(&incident_id, "Opened the second door", Some("ben"), now).unwrap();
+        assert!(store.annotate_incident(&incident_id, "  ", Some("ben"), now).is_err());
+        store.resolve_incident(&incident_id, "crowd dispersed", Some("ben"), now).unwrap();
+        let actions: Vec<(String, Option<String>)> = store
+            .incident_activity(&incident_id)
+            .unwrap()
+            .into_iter()
+            .map(|a| (a.action, a.actor))
+            .collect();
+        let by = |actor: &str| Some(actor.to_string());
+        assert_eq!(actions, vec![
+            ("snoozed".to_string(), by("ana")),
+            ("snoozed".to_string(), by("ana")),
+            ("assigned".to_string(), by("ana")),
+            ("acknowledged".to_string(), by("ben")),
+            ("note".to_string(), by("ben")),
+            ("resolved".to_string(), by("ben")),
+        ]);
+        assert!(store.snooze_incident(&incident_id, now, None, now).is_err());
+    }
+}
@@ -0,0 +1,168 @@
+// Event Stream Module - Live detection/analysis events over WebSocket for dashboards and NVRs
+// Each connection filters by topic (event type) and camera, set in the URL or changed later by message
+
+use crate::event_store::StoredEvent;
+use axum::extract::ws::{Message, WebSocket};
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast::{self, error::RecvError};
+
+// Event types a client can subscribe to
+pub const TOPICS: [&str; 3] = ["detection", "analysis", "alert"];
+
+// Alert priorities (payload.priority), most urgent first
+pub const PRIORITIES: [&str; 4] = ["critical", "high", "medium", "low"];
+
+pub fn priority_rank(priority: &str) -> Option<usize> {
+    PRIORITIES.iter().position(|p| *p == priority)
+}
+
+// Empty topics means everything
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct StreamFilter {
+    #[serde(default)]
+    pub topics: Vec<String>,
+    pub camera_id: Option<String>,
+    #[serde(default)]
+    pub min_priority: Option<String>,  // Only events at least this urgent, e.g. "critical" for a fire channel
+}
+
+impl StreamFilter {
+    // From `?topics=alert,analysis&camera=front-door&priority=high`
+    pub fn from_query(topics: Option<&str>, camera: Option<&str>, priority: Option<&str>) -> Result<Self, String> {
+        let filter = StreamFilter {
+            topics: topics
+                .unwrap_or("")
+                .split(',')
+                .map(|t| t.trim().to_lowercase())
+                .filter(|t| !t.is_empty())
+                .collect(),
+            camera_id: camera.filter(|c| !c.is_empty()).map(|c| c.to_string()),
+            min_priority: priority.filter(|p| !p.is_empty()).map(|p| p.to_lowercase()),
+        };
+        filter.validate()?;
+        Ok(filter)
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if let Some(unknown) = self.topics.iter().find(|t| !TOPICS.contains(&t.as_str())) {
+            return Err(format!("Unknown topic '{}' (use {})", unknown, TOPICS.join(", ")));
+        }
+        match self.min_priority.as_deref() {
+            Some(priority) if priority_rank(priority).is_none() => {
+                Err(format!("Unknown priority '{}' (use {})", priority, PRIORITIES.join(", ")))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    pub fn matches(&self, event: &StoredEvent) -> bool {
+        let topic_ok = self.topics.is_empty() || self.topics.contains(&event.event_type);
+        let camera_ok = self.camera_id.is_none() || self.camera_id == event.camera_id;
+        // Events without a priority never pass a priority filter
+        let priority_ok = match self.min_priority.as_deref().and_then(priority_rank) {
+            Some(min) => event
+                .payload
+                .as_ref()
+                .and_then(|p| p["priority"].as_str())
+                .and_then(priority_rank)
+                .map(|rank| rank <= min)
+                .unwrap_or(false),
+            None => true,
+        };
+        topic_ok && camera_ok && priority_ok
+    }
+}
+
+fn control(kind: &str, detail: serde_json::Value) -> Message {
+    Message::Text(serde_json::json!({ "type": kind, "detail": detail }).to_string())
+}
+
+// Forward matching events until the client disconnects
+pub async fn run_session(mut socket: WebSocket, mut events: broadcast::Receiver<StoredEvent>, mut filter: StreamFilter) {
+    if socket.send(control("subscribed", serde_json::json!(filter))).await.is_err() {
+        return;
+    }
+
+    loop {
+        tokio::select! {
+            received = events.recv() => match received {
+                Ok(event) if filter.matches(&event) => {
+                    let message = serde_json::json!({ "type": "event", "event": event }).to_string();
+                    if socket.send(Message::Text(message)).await.is_err() {
+                        break;
+                    }
+                }
+                Ok(_) => {}
+                Err(RecvError::Lagged(skipped)) => {
+                    if socket.send(control("lagged", serde_json::json!(skipped))).await.is_err() {
+                        break;
+                    }
+                }
+                Err(RecvError::Closed) => break,
+            },
+            incoming = socket.recv() => match incoming {
+                // A text message replaces the connection's filter
+                Some(Ok(Message::Text(text))) => {
+                    let reply = match serde_json::from_str::<StreamFilter>(&text)
+                        .map_err(|e| e.to_string())
+                        .and_then(|new_filter| new_filter.validate().map(|_| new_filter))
+                    {
+                        Ok(new_filter) => {
+                            filter = new_filter;
+                            control("subscribed", serde_json::json!(filter))
+                        }
+                        Err(e) => control("error", serde_json::json!(e)),
+                    };
+                    if socket.send(reply).await.is_err() {
+                        break;
+                    }
+                }
+                Some(Ok(Message::Close(_))) | None | Some(Err(_)) => break,
+                Some(Ok(_)) => {}
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(event_type: &str, camera_id: Option<&str>) -> StoredEvent {
+        StoredEvent {
+            id: "e1".to_string(),
+            timestamp: "2025-01-15T10:00:00.000Z".to_string(),
+            camera_id: camera_id.map(|c| c.to_string()),
+            event_type: event_type.to_string(),
+            person_count: None,
+            object_counts: None,
+            provider: None,
+            prompt: None,
+            description: None,
+            payload: None,
+        }
+    }
+
+    #[test]
+    fn test_stream_filters() {
+        let everything = StreamFilter::from_query(None, None, None).unwrap();
+        assert!(everything.matches(&event("detection", None)));
+
+        let alerts = StreamFilter::from_query(Some("Alert"), Some("front-door"), None).unwrap();
+        assert!(alerts.matches(&event("alert", Some("front-door"))));
+        assert!(!alerts.matches(&event("alert", Some("back-door"))));
+        assert!(!alerts.matches(&event("analysis", Some("front-door"))));
+
+        assert!(StreamFilter::from_query(Some("alert,motion"), None, None).is_err());
+
+        let critical = StreamFilter::from_query(Some("alert"), None, Some("critical")).unwrap();
+        let with_priority = |priority: &str| StoredEvent {
+            payload: Some(serde_json::json!({ "priority": priority })),
+            ..event("alert", None)
+        };
+        assert!(critical.matches(&with_priority("critical")));
+        assert!(!critical.matches(&with_priority("high")));
+        assert!(!critical.matches(&event("alert", None)));
+        assert!(StreamFilter::from_query(None, None, Some("urgent")).is_err());
+    }
+}
@@ -0,0 +1,241 @@
+// Export Module - Dumps stored detections and analyses to CSV, JSON Lines or Parquet
+// Every format carries a schema version so notebooks can detect layout changes
+
+use crate::event_store::StoredEvent;
+use arrow_array::{ArrayRef, RecordBatch, StringArray, UInt32Array};
+use arrow_schema::{DataType, Field, Schema};
+use parquet::arrow::ArrowWriter;
+use parquet::file::properties::WriterProperties;
+use parquet::format::KeyValue;
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::path::Path;
+use std::sync::Arc;
+
+// Bump when columns are added, removed or change meaning
+pub const SCHEMA_VERSION: u32 = 1;
+
+const COLUMNS: [&str; 11] = [
+    "schema_version",
+    "id",
+    "timestamp",
+    "camera_id",
+    "event_type",
+    "person_count",
+    "object_counts",
+    "provider",
+    "prompt",
+    "description",
+    "payload",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ExportFormat {
+    Csv,
+    Jsonl,
+    Parquet,
+}
+
+impl ExportFormat {
+    pub fn parse(format: &str) -> Result<Self, String> {
+        match format.to_lowercase().as_str() {
+            "csv" => Ok(ExportFormat::Csv),
+            "jsonl" | "ndjson" | "json-lines" => Ok(ExportFormat::Jsonl),
+            "parquet" => Ok(ExportFormat::Parquet),
+            other => Err(format!("Unsupported export format '{}' (use csv, jsonl or parquet)", other)),
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "csv",
+            ExportFormat::Jsonl => "jsonl",
+            ExportFormat::Parquet => "parquet",
+        }
+    }
+}
+
+// Inclusive start, exclusive end; either side may be open
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct ExportRange {
+    pub from: Option<String>,
+    pub to: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ExportSummary {
+    pub path: String,
+    pub format: String,
+    pub rows: usize,
+    pub schema_version: u32,
+}
+
+fn json_text(value: &Option<serde_json::Value>) -> Option<String> {
+    value.as_ref().map(|v| v.to_string())
+}
+
+// Quote fields containing separators, quotes or line breaks (RFC 4180)
+pub fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+fn write_csv(events: &[StoredEvent], out: &mut impl Write) -> Result<(), String> {
+    writeln!(out, "{}", COLUMNS.join(",")).map_err(|e| e.to_string())?;
+    for event in events {
+        let fields = [
+            SCHEMA_VERSION.to_string(),
+            event.id.clone(),
+            event.timestamp.clone(),
+            event.camera_id.clone().unwrap_or_default(),
+            event.event_type.clone(),
+            event.person_count.map(|c| c.to_string()).unwrap_or_default(),
+            json_text(&event.object_counts).unwrap_or_default(),
+            event.provider.clone().unwrap_or_default(),
+            event.prompt.clone().unwrap_or_default(),
+            event.description.clone().unwrap_or_default(),
+            json_text(&event.payload).unwrap_or_default(),
+        ];
+        let line: Vec<String> = fields.iter().map(|f| csv_field(f)).collect();
+        writeln!(out, "{}", line.join(",")).map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+fn write_jsonl(events: &[StoredEvent], out: &mut impl Write) -> Result<(), String> {
+    for event in events {
+        let mut record = serde_json::to_value(event).map_err(|e| e.to_string())?;
+        record["schema_version"] = serde_json::json!(SCHEMA_VERSION);
+        writeln!(out, "{}", record).map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+fn write_parquet(events: &[StoredEvent], file: std::fs::File) -> Result<(), String> {
+    let text = |name: &str, nullable: bool| Field::new(name, DataType::Utf8, nullable);
+    let schema = Arc::new(Schema::new(vec![
+        Field::new("schema_version", DataType::UInt32, false),
+        text("id", false),
+        text("timestamp", false),
+        text("camera_id", true),
+        text("event_type", false),
+        Field::new("person_count", DataType::UInt32, true),
+        text("object_counts", true),
+        text("provider", true),
+        text("prompt", true),
+        text("description", true),
+        text("payload", true),
+    ]));
+
+    let strings = |f: &dyn Fn(&StoredEvent) -> Option<String>| -> ArrayRef {
+        Arc::new(events.iter().map(f).collect::<StringArray>())
+    };
+    let columns: Vec<ArrayRef> = vec![
+        Arc::new(UInt32Array::from(vec![SCHEMA_VERSION; events.len()])),
+        strings(&|e| Some(e.id.clone())),
+        strings(&|e| Some(e.timestamp.clone())),
+        strings(&|e| e.camera_id.clone()),
+        strings(&|e| Some(e.event_type.clone())),
+        Arc::new(events.iter().map(|e| e.person_count).collect::<UInt32Array>()),
+        strings(&|e| json_text(&e.object_counts)),
+        strings(&|e| e.provider.clone()),
+        strings(&|e| e.prompt.clone()),
+        strings(&|e| e.description.clone()),
+        strings(&|e| json_text(&e.payload)),
+    ];
+    let batch = RecordBatch::try_new(schema.clone(), columns).map_err(|e| format!("Failed to build record batch: {}", e))?;
+
+    // The version also goes into the file metadata for tools that read it
+    let properties = WriterProperties::builder()
+        .set_key_value_metadata(Some(vec![KeyValue::new(
+            "live_vision_analyzer.schema_version".to_string(),
+            SCHEMA_VERSION.to_string(),
+        )]))
+        .build();
+    let mut writer = ArrowWriter::try_new(file, schema, Some(properties))
+        .map_err(|e| format!("Failed to create Parquet writer: {}", e))?;
+    writer.write(&batch).map_err(|e| format!("Failed to write Parquet: {}", e))?;
+    writer.close().map_err(|e| format!("Failed to finish Parquet file: {}", e))?;
+    Ok(())
+}
+
+pub fn export(events: &[StoredEvent], format: ExportFormat, path: &Path) -> Result<ExportSummary, String> {
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        std::fs::create_dir_all(parent).map_err(|e| format!("Failed to create export directory: {}", e))?;
+    }
+    let file = std::fs::File::create(path).map_err(|e| format!("Failed to create {}: {}", path.display(), e))?;
+
+    match format {
+        ExportFormat::Csv => write_csv(events, &mut std::io::BufWriter::new(file))?,
+        ExportFormat::Jsonl => write_jsonl(events, &mut std::io::BufWriter::new(file))?,
+        ExportFormat::Parquet => write_parquet(events, file)?,
+    }
+
+    Ok(ExportSummary {
+        path: path.to_string_lossy().to_string(),
+        format: format.name().to_string(),
+        rows: events.len(),
+        schema_version: SCHEMA_VERSION,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(id: &str, description: &str) -> StoredEvent {
+        StoredEvent {
+            id: id.to_string(),
+            timestamp: "2025-01-15T10:00:00Z".to_string(),
+            camera_id: None,
+            event_type: "analysis".to_string(),
+            person_count: Some(2),
+            object_counts: None,
+            provider: Some("llava".to_string()),
+            prompt: Some("Describe".to_string()),
+            description: Some(description.to_string()),
+            payload: Some(serde_json::json!({ "people_count": 2 })),
+        }
+    }
+
+    #[test]
+    fn test_csv_and_jsonl_export() {
+        assert_eq!(csv_field("plain"), "plain");
+        assert_eq!(csv_field("a, \"b\""), "\"a, \"\"b\"\"\"");
+
+        let dir = tempfile::tempdir().unwrap();
+        let events = vec![event("e1", "Two people, one \"cart\""), event("e2", "Empty")];
+
+        let csv_path = dir.path().join("events.csv");
+        export(&events, ExportFormat::Csv, &csv_path).unwrap();
+        let csv = std::fs::read_to_string(&csv_path).unwrap();
+        assert!(csv.starts_with("schema_version,id,"));
+        assert!(csv.contains("\"Two people, one \"\"cart\"\"\""));
+
+        let jsonl_path = dir.path().join("events.jsonl");
+        let summary = export(&events, ExportFormat::Jsonl, &jsonl_path).unwrap();
+        assert_eq!(summary.rows, 2);
+        let first: serde_json::Value =
+            serde_json::from_str(std::fs::read_to_string(&jsonl_path).unwrap().lines().next().unwrap()).unwrap();
+        assert_eq!(first["schema_version"], SCHEMA_VERSION);
+        assert_eq!(first["payload"]["people_count"], 2);
+    }
+
+    #[test]
+    fn test_parquet_export() {
+        use parquet::file::reader::{FileReader, SerializedFileReader};
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("events.parquet");
+        export(&[event("e1", "One person")], ExportFormat::Parquet, &path).unwrap();
+
+        let reader = SerializedFileReader::new(std::fs::File::open(&path).unwrap()).unwrap();
+        let metadata = reader.metadata().file_metadata();
+        assert_eq!(metadata.num_rows(), 1);
+        assert_eq!(metadata.schema_descr().num_columns(), 11);
+        assert!(ExportFormat::parse("xlsx").is_err());
+    }
+}
@@ -0,0 +1,268 @@
+// Fire Module - Always-on fire and smoke watch on live frames
+// A cheap tile classifier flags candidates; the VLM "safety" prompt must confirm before a critical alert goes out
+
+use crate::moondream_manager::retail_prompt;
+use crate::bus::BusEvent;
+use crate::{frame_processor, pipeline, settings, verification, AppState};
+use image::imageops::FilterType;
+use image::{DynamicImage, Rgb};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::time::{Duration, Instant};
+
+// Frames are scanned on a small copy split into a fixed grid
+const SCAN_WIDTH: u32 = 160;
+const GRID_COLS: usize = 8;
+const GRID_ROWS: usize = 6;
+
+// Weight of the current frame in each tile's grey baseline
+const BASELINE_UPDATE: f32 = 0.05;
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct FireSettings {
+    pub enabled: bool,
+    pub provider: String,        // VLM that confirms candidates
+    pub fire_threshold: f32,     // Share of flame-coloured pixels that makes a tile a candidate
+    pub smoke_threshold: f32,    // Rise in grey pixels over the tile's baseline that makes a candidate
+    pub confirm_frames: u32,     // Consecutive candidate frames before the VLM is asked
+    pub cooldown_secs: u64,      // Minimum time between confirmations per camera
+}
+
+impl Default for FireSettings {
+    fn default() -> Self {
+        FireSettings {
+            enabled: true,
+            provider: "llava".to_string(),
+            fire_threshold: 0.25,
+            smoke_threshold: 0.35,
+            confirm_frames: 3,
+            cooldown_secs: 120,
+        }
+    }
+}
+
+impl FireSettings {
+    pub fn validate(&self) -> Result<(), String> {
+        for (name, value) in [("fire_threshold", self.fire_threshold), ("smoke_threshold", self.smoke_threshold)] {
+            if !(value > 0.0 && value <= 1.0) {
+                return Err(format!("{} must be greater than 0 and at most 1", name));
+            }
+        }
+        if self.confirm_frames == 0 {
+            return Err("confirm_frames must be at least 1".to_string());
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct HotTile {
+    pub row: usize,
+    pub col: usize,
+    pub fire: f32,
+    pub smoke: f32,
+}
+
+// Bright, saturated red-orange-yellow
+fn is_flame(pixel: &Rgb<u8>) -> bool {
+    let [r, g, b] = pixel.0.map(|c| c as i32);
+    r > 180 && r >= g && g > b && r - b > 90
+}
+
+// Light, colourless haze
+fn is_smoke(pixel: &Rgb<u8>) -> bool {
+    let [r, g, b] = pixel.0.map(|c| c as i32);
+    let (max, min) = (r.max(g).max(b), r.min(g).min(b));
+    max - min < 20 && (110..=230).contains(&max)
+}
+
+// Flame and grey shares per tile, row-major
+fn tile_scores(image: &DynamicImage) -> Vec<(f32, f32)> {
+    let scale = (image.width() as f32 / SCAN_WIDTH as f32).max(1.0);
+    let small = image
+        .resize_exact(
+            ((image.width() as f32 / scale) as u32).max(GRID_COLS as u32),
+            ((image.height() as f32 / scale) as u32).max(GRID_ROWS as u32),
+            FilterType::Triangle,
+        )
+        .to_rgb8();
+    let (w, h) = small.dimensions();
+
+    let mut counts = vec![(0u32, 0u32, 0u32); GRID_COLS * GRID_ROWS];
+    for (x, y, pixel) in small.enumerate_pixels() {
+        let col = (x as usize * GRID_COLS / w as usize).min(GRID_COLS - 1);
+        let row = (y as usize * GRID_ROWS / h as usize).min(GRID_ROWS - 1);
+        let tile = &mut counts[row * GRID_COLS + col];
+        tile.0 += is_flame(pixel) as u32;
+        tile.1 += is_smoke(pixel) as u32;
+        tile.2 += 1;
+    }
+    counts
+        .into_iter()
+        .map(|(flame, grey, total)| (flame as f32 / total.max(1) as f32, grey as f32 / total.max(1) as f32))
+        .collect()
+}
+
+// Per-camera scan state: grey baselines (smoke is a change, grey walls are not) and the confirmation pacing
+#[derive(Default)]
+struct CameraWatch {
+    baseline: Vec<f32>,
+    streak: u32,
+    last_confirmation: Option<Instant>,
+}
+
+impl CameraWatch {
+    fn scan(&mut self, config: &FireSettings, image: &DynamicImage) -> Vec<HotTile> {
+        let scores = tile_scores(image);
+        if self.baseline.len() != scores.len() {
+            self.baseline = scores.iter().map(|(_, grey)| *grey).collect();
+        }
+
+        let mut hot = Vec::new();
+        for (index, ((fire, grey), baseline)) in scores.iter().zip(self.baseline.iter_mut()).enumerate() {
+            let smoke = (grey - *baseline).max(0.0);
+            if *fire >= config.fire_threshold || smoke >= config.smoke_threshold {
+                hot.push(HotTile { row: index / GRID_COLS, col: index % GRID_COLS, fire: *fire, smoke });
+            } else {
+                // Only calm tiles adapt, so a slowly thickening plume isn't learned as background
+                *baseline += BASELINE_UPDATE * (grey - *baseline);
+            }
+        }
+        self.streak = if hot.is_empty() { 0 } else { self.streak + 1 };
+        hot
+    }
+}
+
+#[derive(Default)]
+pub struct FireWatch {
+    cameras: HashMap<String, CameraWatch>,
+    confirming: HashSet<String>,
+}
+
+// "fire" or "smoke" when the safety analysis confirms it
+fn confirmed_hazard(analysis: &serde_json::Value, description: &str) -> Option<String> {
+    let hazard_type = analysis["hazard_type"].as_str().unwrap_or("").to_lowercase();
+    if analysis["hazard_detected"].as_bool() == Some(true) && matches!(hazard_type.as_str(), "fire" | "smoke") {
+        return Some(hazard_type);
+    }
+    // Models that ignore the JSON format still tend to name what they see
+    let text = description.to_lowercase();
+    if analysis["hazard_detected"].is_null() && ["fire", "flame"].iter().any(|w| text.contains(w)) {
+        return Some("fire".to_string());
+    }
+    None
+}
+
+async fn confirm(state: AppState, config: FireSettings, camera_id: Option<String>, frame: String, hot: Vec<HotTile>) {
+    let camera = camera_id.clone().unwrap_or_default();
+    let start = Instant::now();
+    let prompt = retail_prompt("safety");
+    let result = pipeline::describe_frame(&state.moondream, &state.providers, &config.provider, &prompt, frame.clone()).await;
+    state.bus.publish(BusEvent::analysis(&config.provider, start.elapsed(), result.is_ok()));
+
+    match result {
+        Ok(Some((description, analysis))) => match confirmed_hazard(&analysis, &description) {
+            Some(hazard) => {
+                let label = if hazard == "fire" { "Fire" } else { "Smoke" };
+                let summary = match camera_id.as_deref() {
+                    Some(camera) => format!("{} detected on {}", label, camera),
+                    None => format!("{} detected", label),
+                };
+                let mut payload = serde_json::json!({
+                    "kind": "fire",
+                    "priority": "critical",
+                    "hazard_type": hazard,
+                    "tiles": hot,
+                    "analysis": analysis,
+                });
+                let second_look = verification::verify(&state, camera_id.as_deref(), &config.provider, &prompt, &frame, None, "critical", |text, data| {
+                    confirmed_hazard(data, text).is_some()
+                })
+                .await;
+                verification::attach(&mut payload, second_look);
+                let alert_id = state.bus.alert(camera_id.as_deref(), &config.provider, &summary, payload, Some(&frame)).await;
+                println!("🔥 {} (alert {})", summary, alert_id);
+            }
+            None => println!("Fire: {} candidate tiles on '{}' not confirmed by {}", hot.len(), camera, config.provider),
+        },
+        Ok(None) => {}
+        Err(e) => eprintln!("Fire confirmation failed: {}", e),
+    }
+    state.fire.lock().await.confirming.remove(&camera);
+}
+
+// Scan every live frame; a candidate that persists is sent for confirmation in the background
+pub async fn watch(state: &AppState, camera_id: Option<&str>, frame_base64: &str) {
+    let config = settings::current().fire;
+    if !config.enabled {
+        return;
+    }
+    let image = match frame_processor::decode_frame(frame_base64) {
+        Ok(image) => image,
+        Err(e) => {
+            eprintln!("Fire scan skipped: {}", e);
+            return;
+        }
+    };
+
+    let camera = camera_id.unwrap_or("").to_string();
+    let hot = {
+        let mut watch = state.fire.lock().await;
+        let FireWatch { cameras, confirming } = &mut *watch;
+        let camera_watch = cameras.entry(camera.clone()).or_default();
+        let hot = camera_watch.scan(&config, &image);
+        let cooled = camera_watch
+            .last_confirmation
+            .map(|at| at.elapsed() >= Duration::from_secs(config.cooldown_secs))
+            .unwrap_or(true);
+        if camera_watch.streak < config.confirm_frames || !cooled || confirming.contains(&camera) {
+            return;
+        }
+        camera_watch.last_confirmation = Some(Instant::now());
+        confirming.insert(camera);
+        hot
+    };
+
+    let state = state.clone();
+    let camera_id = camera_id.map(|c| c.to_string());
+    tokio::spawn(confirm(state, config, camera_id, frame_base64.to_string(), hot));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::RgbImage;
+
+    fn frame(fire: bool, haze: bool) -> DynamicImage {
+        DynamicImage::ImageRgb8(RgbImage::from_fn(320, 240, |x, y| {
+            if fire && x < 80 && y >= 160 {
+                Rgb([250, 140, 30])
+            } else if haze && y < 80 {
+                Rgb([180, 180, 178])
+            } else {
+                Rgb([40, 60, 90])
+            }
+        }))
+    }
+
+    #[test]
+    fn test_scan_flags_flames_and_new_smoke() {
+        let config = FireSettings::default();
+        let mut watch = CameraWatch::default();
+        assert!(watch.scan(&config, &frame(false, false)).is_empty());
+
+        let hot = watch.scan(&config, &frame(true, false));
+        assert!(!hot.is_empty() && hot.iter().all(|t| t.row >= 4 && t.col < 2 && t.fire > 0.9));
+        assert_eq!(watch.streak, 1);
+
+        // Haze rolling in along the ceiling
+        let hot = watch.scan(&config, &frame(false, true));
+        assert!(!hot.is_empty() && hot.iter().all(|t| t.row < 2 && t.smoke > 0.9));
+
+        let analysis = serde_json::json!({ "hazard_detected": true, "hazard_type": "smoke" });
+        assert_eq!(confirmed_hazard(&analysis, "").as_deref(), Some("smoke"));
+        let spill = serde_json::json!({ "hazard_detected": true, "hazard_type": "spill" });
+        assert_eq!(confirmed_hazard(&spill, "a fire exit sign"), None);
+    }
+}
@@ -0,0 +1,199 @@
+// Floor Module - Perspective calibration from image pixels to floor metres
+// Four image points matched to floor coordinates give a homography; people are placed by the bottom centre of their box
+
+use crate::settings;
+use crate::yolo_detector::DetectionData;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+// Gaps longer than this between sightings of a track don't give a speed
+const MAX_SPEED_GAP_SECS: f64 = 5.0;
+
+// Weight of the newest frame in a track's speed
+const SPEED_UPDATE: f32 = 0.5;
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct FloorCalibration {
+    pub image_points: [[f32; 2]; 4],  // Frame pixels, e.g. the corners of a floor tile or parking bay
+    pub floor_points: [[f32; 2]; 4],  // The same points on the floor, in metres
+}
+
+// Where a detected person stands
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct FloorPosition {
+    pub x: f32,
+    pub y: f32,
+    #[serde(default)]
+    pub nearest_person_m: Option<f32>,
+    #[serde(default)]
+    pub speed_mps: Option<f32>,  // Needs re-identification for track ids
+}
+
+// Twice the signed area of a triangle; near zero when the points are in a line
+fn cross(a: [f32; 2], b: [f32; 2], c: [f32; 2]) -> f64 {
+    ((b[0] - a[0]) as f64) * ((c[1] - a[1]) as f64) - ((b[1] - a[1]) as f64) * ((c[0] - a[0]) as f64)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Homography([f64; 9]);
+
+impl Homography {
+    // Solve the 8x8 system for the four correspondences (h33 fixed at 1)
+    pub fn from_points(from: &[[f32; 2]; 4], to: &[[f32; 2]; 4]) -> Result<Self, String> {
+        for points in [from, to] {
+            for skip in 0..4 {
+                let p: Vec<[f32; 2]> = (0..4).filter(|i| *i != skip).map(|i| points[i]).collect();
+                if cross(p[0], p[1], p[2]).abs() < 1e-6 {
+                    return Err("Three of the calibration points are in a line - pick four corners of a shape".to_string());
+                }
+            }
+        }
+
+        let mut rows = [[0f64; 9]; 8];
+        for (i, (a, b)) in from.iter().zip(to).enumerate() {
+            let (x, y) = (a[0] as f64, a[1] as f64);
+            let (u, v) = (b[0] as f64, b[1] as f64);
+            rows[2 * i] = [x, y, 1.0, 0.0, 0.0, 0.0, -x * u, -y * u, u];
+            rows[2 * i + 1] = [0.0, 0.0, 0.0, x, y, 1.0, -x * v, -y * v, v];
+        }
+
+        // Gaussian elimination with partial pivoting
+        for col in 0..8 {
+            let pivot = (col..8).max_by(|a, b| rows[*a][col].abs().total_cmp(&rows[*b][col].abs())).unwrap_or(col);
+            if rows[pivot][col].abs() < 1e-12 {
+                return Err("Calibration points don't define a perspective".to_string());
+            }
+            rows.swap(col, pivot);
+            let pivot_row = rows[col];
+            for (_, row) in rows.iter_mut().enumerate().filter(|(index, _)| *index != col) {
+                let factor = row[col] / pivot_row[col];
+                for (value, p) in row.iter_mut().zip(pivot_row).skip(col) {
+                    *value -= factor * p;
+                }
+            }
+        }
+
+        let mut h = [1.0; 9];
+        for (i, row) in rows.iter().enumerate() {
+            h[i] = row[8] / row[i];
+        }
+        Ok(Homography(h))
+    }
+
+    // None for points on the horizon line, which have no floor position
+    pub fn apply(&self, x: f32, y: f32) -> Option<[f32; 2]> {
+        let h = &self.0;
+        let (x, y) = (x as f64, y as f64);
+        let w = h[6] * x + h[7] * y + h[8];
+        if w.abs() < 1e-9 {
+            return None;
+        }
+        Some([((h[0] * x + h[1] * y + h[2]) / w) as f32, ((h[3] * x + h[4] * y + h[5]) / w) as f32])
+    }
+}
+
+impl FloorCalibration {
+    pub fn homography(&self) -> Result<Homography, String> {
+        Homography::from_points(&self.image_points, &self.floor_points)
+    }
+}
+
+pub fn distance(a: [f32; 2], b: [f32; 2]) -> f32 {
+    ((a[0] - b[0]).powi(2) + (a[1] - b[1]).powi(2)).sqrt()
+}
+
+struct Sighting {
+    position: [f32; 2],
+    seen: DateTime<Utc>,
+    speed: Option<f32>,
+}
+
+// Last floor position of each track, per camera, for walking speed
+#[derive(Default)]
+pub struct FloorTrackers {
+    cameras: HashMap<String, (FloorCalibration, Homography, HashMap<u64, Sighting>)>,
+}
+
+impl FloorTrackers {
+    pub fn apply(&mut self, camera_id: Option<&str>, detection: &mut DetectionData) {
+        let now = Utc::now();
+        let camera = camera_id.unwrap_or("");
+        let Some(calibration) = settings::current().floor_calibrations.get(camera).cloned() else {
+            self.cameras.remove(camera);
+            return;
+        };
+        if self.cameras.get(camera).map(|(c, _, _)| *c != calibration).unwrap_or(true) {
+            match calibration.homography() {
+                Ok(homography) => {
+                    self.cameras.insert(camera.to_string(), (calibration, homography, HashMap::new()));
+                }
+                Err(e) => {
+                    eprintln!("Floor calibration for '{}' skipped: {}", camera, e);
+                    return;
+                }
+            }
+        }
+        let Some((_, homography, tracks)) = self.cameras.get_mut(camera) else {
+            return;
+        };
+
+        let mut placed = Vec::new();
+        for (index, bbox) in detection.boxes.iter_mut().enumerate().filter(|(_, b)| b.class_name == "person") {
+            let Some(position) = homography.apply((bbox.x1 + bbox.x2) / 2.0, bbox.y2) else {
+                continue;
+            };
+            let speed = bbox.track_id.and_then(|id| {
+                let previous = tracks.get(&id);
+                let gap = previous.map(|p| (now - p.seen).num_milliseconds() as f64 / 1000.0).unwrap_or(0.0);
+                let speed = previous
+                    .filter(|_| gap > 0.0 && gap <= MAX_SPEED_GAP_SECS)
+                    .map(|p| {
+                        let current = distance(p.position, position) / gap as f32;
+                        p.speed.map(|s| s + SPEED_UPDATE * (current - s)).unwrap_or(current)
+                    });
+                tracks.insert(id, Sighting { position, seen: now, speed });
+                speed
+            });
+            bbox.floor = Some(FloorPosition { x: position[0], y: position[1], nearest_person_m: None, speed_mps: speed });
+            placed.push((index, position));
+        }
+        tracks.retain(|_, s| (now - s.seen).num_seconds() as f64 <= MAX_SPEED_GAP_SECS);
+
+        for (index, position) in &placed {
+            let nearest = placed
+                .iter()
+                .filter(|(other, _)| other != index)
+                .map(|(_, p)| distance(*position, *p))
+                .min_by(|a, b| a.total_cmp(b));
+            if let Some(floor) = detection.boxes[*index].floor.as_mut() {
+                floor.nearest_person_m = nearest;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_homography_maps_calibration_points() {
+        // A 4m x 3m floor area seen in perspective: the far edge looks narrower
+        let calibration = FloorCalibration {
+            image_points: [[200.0, 200.0], [440.0, 200.0], [560.0, 460.0], [80.0, 460.0]],
+            floor_points: [[0.0, 3.0], [4.0, 3.0], [4.0, 0.0], [0.0, 0.0]],
+        };
+        let homography = calibration.homography().unwrap();
+        for (image, floor) in calibration.image_points.iter().zip(&calibration.floor_points) {
+            let mapped = homography.apply(image[0], image[1]).unwrap();
+            assert!(distance(mapped, *floor) < 1e-3, "{:?} -> {:?}", image, mapped);
+        }
+        // Halfway across the near edge is 2m in
+        let mid = homography.apply(320.0, 460.0).unwrap();
+        assert!(distance(mid, [2.0, 0.0]) < 1e-3);
+
+        let line = FloorCalibration { image_points: [[0.0, 0.0], [1.0, 1.0], [2.0, 2.0], [0.0, 5.0]], ..calibration };
+        assert!(line.homography().is_err());
+    }
+}
@@ -0,0 +1,231 @@
+// Frame Processor Module - Image preprocessing before frames reach a model
+// Handles decoding, cropping and re-encoding of base64 camera frames
+
+use base64::{engine::general_purpose, Engine as _};
+use image::DynamicImage;
+use serde::{Deserialize, Serialize};
+use std::io::Cursor;
+
+// Default padding around a region, as a fraction of the region's size
+const DEFAULT_ROI_PADDING: f32 = 0.15;
+
+// JPEG quality used when re-encoding processed frames
+const JPEG_QUALITY: u8 = 85;
+
+// Limits for multi-frame contact sheets
+pub const MAX_SEQUENCE_FRAMES: usize = 9;
+const CONTACT_SHEET_TILE_WIDTH: u32 = 320;
+const CONTACT_SHEET_GAP: u32 = 4;
+
+// Region of interest in frame pixel coordinates (same convention as YOLO boxes)
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RegionOfInterest {
+    pub x1: f32,
+    pub y1: f32,
+    pub x2: f32,
+    pub y2: f32,
+    pub padding: Option<f32>,  // Fraction of box size added on every side
+}
+
+// Pixel rectangle clamped to the frame bounds
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CropRect {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl RegionOfInterest {
+    // Expand the region by its padding and clamp it to the frame size
+    pub fn to_crop_rect(&self, frame_width: u32, frame_height: u32) -> Result<CropRect, String> {
+        let (left, right) = (self.x1.min(self.x2), self.x1.max(self.x2));
+        let (top, bottom) = (self.y1.min(self.y2), self.y1.max(self.y2));
+
+        let padding = self.padding.unwrap_or(DEFAULT_ROI_PADDING).max(0.0);
+        let pad_x = (right - left) * padding;
+        let pad_y = (bottom - top) * padding;
+
+        let x1 = (left - pad_x).max(0.0).floor() as u32;
+        let y1 = (top - pad_y).max(0.0).floor() as u32;
+        let x2 = ((right + pad_x).ceil() as u32).min(frame_width);
+        let y2 = ((bottom + pad_y).ceil() as u32).min(frame_height);
+
+        if x2 <= x1 || y2 <= y1 {
+            return Err(format!(
+                "Region ({}, {}, {}, {}) is outside the {}x{} frame",
+                self.x1, self.y1, self.x2, self.y2, frame_width, frame_height
+            ));
+        }
+
+        Ok(CropRect {
+            x: x1,
+            y: y1,
+            width: x2 - x1,
+            height: y2 - y1,
+        })
+    }
+}
+
+// Decode a base64 frame into an image
+pub fn decode_frame(frame_base64: &str) -> Result<DynamicImage, String> {
+    let bytes = general_purpose::STANDARD
+        .decode(frame_base64)
+        .map_err(|e| format!("Failed to decode image: {}", e))?;
+
+    image::load_from_memory(&bytes).map_err(|e| format!("Failed to read image: {}", e))
+}
+
+// Encode an image back into a base64 JPEG frame
+pub fn encode_frame(image: &DynamicImage) -> Result<String, String> {
+    let mut buffer = Cursor::new(Vec::new());
+    let encoder = image::codecs::jpeg::JpegEncoder::new_with_quality(&mut buffer, JPEG_QUALITY);
+    image
+        .to_rgb8()
+        .write_with_encoder(encoder)
+        .map_err(|e| format!("Failed to encode image: {}", e))?;
+
+    Ok(general_purpose::STANDARD.encode(buffer.into_inner()))
+}
+
+// Crop a frame to a region of interest (with padding) for escalated analysis
+pub fn crop_to_roi(frame_base64: &str, roi: &RegionOfInterest) -> Result<String, String> {
+    let image = decode_frame(frame_base64)?;
+    let rect = roi.to_crop_rect(image.width(), image.height())?;
+
+    let cropped = image.crop_imm(rect.x, rect.y, rect.width, rect.height);
+    let encoded = encode_frame(&cropped)?;
+
+    println!(
+        "FrameProcessor: Cropped {}x{} frame to {}x{} ROI ({} -> {} bytes base64)",
+        image.width(), image.height(), rect.width, rect.height,
+        frame_base64.len(), encoded.len()
+    );
+
+    Ok(encoded)
+}
+
+// Black out privacy zones, then apply an optional ROI crop
+pub fn prepare_frame(frame_base64: String, roi: Option<&RegionOfInterest>) -> Result<String, String> {
+    let frame_base64 = crate::privacy::mask_frame(frame_base64)?;
+    match roi {
+        Some(roi) => crop_to_roi(&frame_base64, roi),
+        None => Ok(frame_base64),
+    }
+}
+
+// Image file extensions accepted when loading frames from disk
+const IMAGE_EXTENSIONS: [&str; 4] = ["jpg", "jpeg", "png", "webp"];
+
+// Read an image file from disk as a base64 frame with privacy zones masked
+pub fn load_frame_file(path: &std::path::Path) -> Result<String, String> {
+    let bytes = std::fs::read(path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    crate::privacy::mask_frame(general_purpose::STANDARD.encode(bytes))
+}
+
+// List image files in a directory (non-recursive), sorted by name
+pub fn list_image_files(dir: &std::path::Path) -> Result<Vec<std::path::PathBuf>, String> {
+    let entries = std::fs::read_dir(dir)
+        .map_err(|e| format!("Failed to read directory {}: {}", dir.display(), e))?;
+
+    let mut files: Vec<std::path::PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| {
+            path.extension()
+                .and_then(|ext| ext.to_str())
+                .map(|ext| IMAGE_EXTENSIONS.contains(&ext.to_lowercase().as_str()))
+                .unwrap_or(false)
+        })
+        .collect();
+    files.sort();
+
+    Ok(files)
+}
+
+// Tile several frames into a single chronological grid image (left to right, top to bottom)
+pub fn build_contact_sheet(frames: &[String]) -> Result<String, String> {
+    if frames.is_empty() {
+        return Err("No frames provided".to_string());
+    }
+
+    let images = frames
+        .iter()
+        .map(|frame| decode_frame(frame))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    // All tiles share the aspect ratio of the first frame
+    let first = &images[0];
+    let tile_width = CONTACT_SHEET_TILE_WIDTH;
+    let tile_height = ((first.height() as f32 / first.width() as f32) * tile_width as f32).round().max(1.0) as u32;
+
+    let columns = (images.len() as f32).sqrt().ceil() as u32;
+    let rows = (images.len() as u32).div_ceil(columns);
+
+    let sheet_width = columns * tile_width + (columns - 1) * CONTACT_SHEET_GAP;
+    let sheet_height = rows * tile_height + (rows - 1) * CONTACT_SHEET_GAP;
+    let mut sheet = image::RgbImage::new(sheet_width, sheet_height);
+
+    for (index, frame) in images.iter().enumerate() {
+        let column = index as u32 % columns;
+        let row = index as u32 / columns;
+        let tile = frame
+            .resize_exact(tile_width, tile_height, image::imageops::FilterType::Triangle)
+            .to_rgb8();
+
+        image::imageops::replace(
+            &mut sheet,
+            &tile,
+            (column * (tile_width + CONTACT_SHEET_GAP)) as i64,
+            (row * (tile_height + CONTACT_SHEET_GAP)) as i64,
+        );
+    }
+
+    println!(
+        "FrameProcessor: Built {}x{} contact sheet from {} frames ({}x{} grid)",
+        sheet_width, sheet_height, images.len(), columns, rows
+    );
+
+    encode_frame(&DynamicImage::ImageRgb8(sheet))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_frame(width: u32, height: u32) -> String {
+        let image = DynamicImage::new_rgb8(width, height);
+        encode_frame(&image).unwrap()
+    }
+
+    #[test]
+    fn test_roi_padding_is_clamped_to_frame() {
+        let roi = RegionOfInterest { x1: 0.0, y1: 10.0, x2: 100.0, y2: 110.0, padding: Some(0.2) };
+        let rect = roi.to_crop_rect(640, 480).unwrap();
+        assert_eq!(rect, CropRect { x: 0, y: 0, width: 120, height: 130 });
+    }
+
+    #[test]
+    fn test_roi_outside_frame_is_rejected() {
+        let roi = RegionOfInterest { x1: 700.0, y1: 500.0, x2: 800.0, y2: 600.0, padding: None };
+        assert!(roi.to_crop_rect(640, 480).is_err());
+    }
+
+    #[test]
+    fn test_crop_to_roi() {
+        let frame = test_frame(640, 480);
+        let roi = RegionOfInterest { x1: 200.0, y1: 150.0, x2: 300.0, y2: 400.0, padding: Some(0.0) };
+
+        let cropped = decode_frame(&crop_to_roi(&frame, &roi).unwrap()).unwrap();
+        assert_eq!((cropped.width(), cropped.height()), (100, 250));
+    }
+
+    #[test]
+    fn test_contact_sheet_grid_layout() {
+        let frames: Vec<String> = (0..5).map(|_| test_frame(640, 480)).collect();
+
+        let sheet = decode_frame(&build_contact_sheet(&frames).unwrap()).unwrap();
+        // 5 frames -> 3 columns x 2 rows of 320x240 tiles with 4px gaps
+        assert_eq!((sheet.width(), sheet.height()), (3 * 320 + 2 * 4, 2 * 240 + 4));
+    }
+}
@@ -0,0 +1,479 @@
+// Frigate MQTT Module - Publish events in Frigate's MQTT topic/payload layout for Home Assistant
+// One Frigate event per camera and label while that label is in view; VLM text goes out as tracked_object_update descriptions
+
+use crate::audit::{self, AuditAction, AuditEntry};
+use crate::dataset;
+use crate::encryption;
+use crate::event_store::{EventStore, StoredEvent};
+use crate::yolo_detector::DetectionData;
+use rumqttc::{AsyncClient, Event, LastWill, MqttOptions, Packet, QoS};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::Mutex;
+
+pub const DEFAULT_MQTT_PORT: u16 = 1883;
+pub const DEFAULT_TOPIC_PREFIX: &str = "frigate";
+
+// Camera name for events recorded without a camera id
+pub const DEFAULT_CAMERA: &str = "live";
+
+// Unchanged detections repeat every 60s, so a label silent for longer has left the scene
+const STALE_SECS: f64 = 150.0;
+
+// Snapshot topics carry whole JPEG frames
+const MAX_PACKET_BYTES: usize = 8 * 1024 * 1024;
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct MqttSettings {
+    pub enabled: bool,
+    pub host: String,
+    pub port: u16,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    pub topic_prefix: String,  // Frigate's mqtt.topic_prefix
+    pub client_id: String,
+}
+
+impl Default for MqttSettings {
+    fn default() -> Self {
+        MqttSettings {
+            enabled: false,
+            host: "localhost".to_string(),
+            port: DEFAULT_MQTT_PORT,
+            username: None,
+            password: None,
+            topic_prefix: DEFAULT_TOPIC_PREFIX.to_string(),
+            client_id: "live-vision-analyzer".to_string(),
+        }
+    }
+}
+
+impl MqttSettings {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.host.trim().is_empty() {
+            return Err("MQTT host is required".to_string());
+        }
+        let prefix = self.topic_prefix.trim_matches('/');
+        if prefix.is_empty() || prefix.contains(['+', '#']) {
+            return Err(format!("Invalid MQTT topic prefix '{}'", self.topic_prefix));
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MqttMessage {
+    pub topic: String,
+    pub payload: Vec<u8>,
+    pub retain: bool,
+}
+
+// Frigate camera names are topic segments, so wildcards and separators are replaced
+fn camera_name(camera_id: Option<&str>) -> String {
+    match camera_id.filter(|c| !c.trim().is_empty()) {
+        Some(camera) => camera
+            .trim()
+            .chars()
+            .map(|c| if matches!(c, '/' | '+' | '#') || c.is_whitespace() { '_' } else { c })
+            .collect(),
+        None => DEFAULT_CAMERA.to_string(),
+    }
+}
+
+fn epoch_secs(timestamp: &str) -> f64 {
+    chrono::DateTime::parse_from_rfc3339(timestamp)
+        .map(|t| t.timestamp_millis())
+        .unwrap_or_else(|_| chrono::Utc::now().timestamp_millis()) as f64
+        / 1000.0
+}
+
+#[derive(Debug, Clone)]
+struct TrackedObject {
+    id: String,
+    camera: String,
+    label: String,
+    start_time: f64,
+    end_time: Option<f64>,
+    frame_time: f64,
+    snapshot_time: f64,
+    score: f32,
+    top_score: f32,
+    bbox: Option<[f32; 4]>,
+}
+
+impl TrackedObject {
+    // The "before"/"after" object of a frigate/events message
+    fn to_json(&self) -> serde_json::Value {
+        let area = self.bbox.map(|b| ((b[2] - b[0]) * (b[3] - b[1])).max(0.0) as u64).unwrap_or(0);
+        serde_json::json!({
+            "id": self.id,
+            "camera": self.camera,
+            "frame_time": self.frame_time,
+            "snapshot_time": self.snapshot_time,
+            "label": self.label,
+            "sub_label": null,
+            "top_score": self.top_score,
+            "false_positive": false,
+            "start_time": self.start_time,
+            "end_time": self.end_time,
+            "score": self.score,
+            "box": self.bbox,
+            "area": area,
+            "region": self.bbox,
+            "current_zones": [],
+            "entered_zones": [],
+            "thumbnail": null,
+            "has_snapshot": self.snapshot_time > 0.0,
+            "has_clip": false,
+            "stationary": false,
+            "motionless_count": 0,
+            "position_changes": 0,
+            "attributes": {}
+        })
+    }
+}
+
+// Turns stored events into Frigate messages, remembering which labels are in view per camera
+pub struct FrigateTracker {
+    prefix: String,
+    active: HashMap<(String, String), TrackedObject>,
+}
+
+impl FrigateTracker {
+    pub fn new(prefix: &str) -> Self {
+        FrigateTracker {
+            prefix: prefix.trim_matches('/').to_string(),
+            active: HashMap::new(),
+        }
+    }
+
+    fn message(&self, topic: &str, payload: impl Into<Vec<u8>>, retain: bool) -> MqttMessage {
+        MqttMessage {
+            topic: format!("{}/{}", self.prefix, topic),
+            payload: payload.into(),
+            retain,
+        }
+    }
+
+    fn event_message(&self, kind: &str, before: &TrackedObject, after: &TrackedObject) -> MqttMessage {
+        let payload = serde_json::json!({ "type": kind, "before": before.to_json(), "after": after.to_json() });
+        self.message("events", payload.to_string(), false)
+    }
+
+    fn end(&mut self, key: &(String, String), end_time: f64) -> Option<MqttMessage> {
+        let before = self.active.remove(key)?;
+        let after = TrackedObject { end_time: Some(end_time), ..before.clone() };
+        Some(self.event_message("end", &before, &after))
+    }
+
+    pub fn handle(&mut self, event: &StoredEvent, snapshot: Option<&[u8]>) -> Vec<MqttMessage> {
+        match event.event_type.as_str() {
+            "detection" => self.on_detection(event, snapshot),
+            "analysis" => self.on_analysis(event),
+            _ => Vec::new(),
+        }
+    }
+
+    fn on_detection(&mut self, event: &StoredEvent, snapshot: Option<&[u8]>) -> Vec<MqttMessage> {
+        let Some(detection) = event
+            .payload
+            .clone()
+            .and_then(|p| serde_json::from_value::<DetectionData>(p).ok())
+        else {
+            return Vec::new();
+        };
+        let camera = camera_name(event.camera_id.as_deref());
+        let frame_time = epoch_secs(&event.timestamp);
+        let counts: BTreeMap<String, u32> = detection.object_counts.into_iter().filter(|(_, n)| *n > 0).collect();
+        let mut messages = Vec::new();
+
+        for label in counts.keys() {
+            let best = detection
+                .boxes
+                .iter()
+                .filter(|b| &b.class_name == label)
+                .max_by(|a, b| a.confidence.total_cmp(&b.confidence));
+            let score = best.map(|b| b.confidence).unwrap_or(0.0);
+            let bbox = best.map(|b| [b.x1, b.y1, b.x2, b.y2]);
+            let key = (camera.clone(), label.clone());
+
+            if let Some(object) = self.active.get_mut(&key) {
+                let before = object.clone();
+                object.frame_time = frame_time;
+                object.score = score;
+                object.top_score = object.top_score.max(score);
+                object.bbox = bbox.or(object.bbox);
+                let after = object.clone();
+                messages.push(self.event_message("update", &before, &after));
+                continue;
+            }
+
+            // Frigate ids are "<start time>-<suffix>"; the suffix ties the event back to the store
+            let object = TrackedObject {
+                id: format!("{:.6}-{}", frame_time, event.id.chars().take(6).collect::<String>()),
+                camera: camera.clone(),
+                label: label.clone(),
+                start_time: frame_time,
+                end_time: None,
+                frame_time,
+                snapshot_time: if snapshot.is_some() { frame_time } else { 0.0 },
+                score,
+                top_score: score,
+                bbox,
+            };
+            if let Some(jpeg) = snapshot {
+                messages.push(self.message(&format!("{}/{}/snapshot", camera, label), jpeg, true));
+            }
+            messages.push(self.event_message("new", &object, &object));
+            self.active.insert(key, object);
+        }
+
+        let gone: Vec<(String, String)> = self
+            .active
+            .keys()
+            .filter(|(c, label)| c == &camera && !counts.contains_key(label))
+            .cloned()
+            .collect();
+        for key in &gone {
+            messages.extend(self.end(key, frame_time));
+            messages.push(self.message(&format!("{}/{}", camera, key.1), "0", false));
+        }
+
+        for (label, count) in &counts {
+            messages.push(self.message(&format!("{}/{}", camera, label), count.to_string(), false));
+        }
+        messages.push(self.message(&format!("{}/all", camera), counts.values().sum::<u32>().to_string(), false));
+        messages
+    }
+
+    // Attach the VLM description to whatever is in view on that camera
+    fn on_analysis(&self, event: &StoredEvent) -> Vec<MqttMessage> {
+        let Some(description) = event.description.as_deref().filter(|d| !d.is_empty()) else {
+            return Vec::new();
+        };
+        let camera = camera_name(event.camera_id.as_deref());
+        let mut ids: Vec<&str> = self
+            .active
+            .values()
+            .filter(|o| o.camera == camera)
+            .map(|o| o.id.as_str())
+            .collect();
+        ids.sort();
+        if ids.is_empty() {
+            ids.push(&event.id);
+        }
+
+        ids.into_iter()
+            .map(|id| {
+                let payload = serde_json::json!({
+                    "type": "description",
+                    "id": id,
+                    "camera": camera,
+                    "description": description
+                });
+                self.message("tracked_object_update", payload.to_string(), false)
+            })
+            .collect()
+    }
+
+    // End labels whose camera stopped reporting
+    pub fn expire(&mut self, now: f64) -> Vec<MqttMessage> {
+        let stale: Vec<(String, String)> = self
+            .active
+            .iter()
+            .filter(|(_, o)| now - o.frame_time > STALE_SECS)
+            .map(|(key, _)| key.clone())
+            .collect();
+        let mut messages = Vec::new();
+        for key in &stale {
+            let end_time = self.active[key].frame_time;
+            messages.extend(self.end(key, end_time));
+            messages.push(self.message(&format!("{}/{}", key.0, key.1), "0", false));
+        }
+        messages
+    }
+}
+
+// Aborts the connection task when the publisher itself is stopped
+struct AbortOnDrop(tokio::task::JoinHandle<()>);
+
+impl Drop for AbortOnDrop {
+    fn drop(&mut self) {
+        self.0.abort();
+    }
+}
+
+// Publish every new event until the store's channel closes
+pub async fn run(config: MqttSettings, events: Arc<Mutex<EventStore>>) {
+    let prefix = config.topic_prefix.trim_matches('/').to_string();
+    let available = format!("{}/available", prefix);
+
+    let mut options = MqttOptions::new(config.client_id.clone(), config.host.clone(), config.port);
+    options.set_keep_alive(Duration::from_secs(30));
+    options.set_max_packet_size(MAX_PACKET_BYTES, MAX_PACKET_BYTES);
+    options.set_last_will(LastWill::new(available.clone(), "offline", QoS::AtLeastOnce, true));
+    if let Some(username) = &config.username {
+        options.set_credentials(username.clone(), config.password.clone().unwrap_or_default());
+    }
+    let (client, mut eventloop) = AsyncClient::new(options, 64);
+
+    // rumqttc only sends, and reconnects, while its event loop is polled
+    let online_client = client.clone();
+    let broker = format!("mqtt://{}:{}/{}", config.host, config.port, prefix);
+    let _connection = AbortOnDrop(tokio::spawn(async move {
+        loop {
+            match eventloop.poll().await {
+                Ok(Event::Incoming(Packet::ConnAck(_))) => {
+                    println!("📨 Connected to MQTT broker");
+                    audit::record(AuditEntry::new(AuditAction::Transmission, "Live events and snapshots", broker.clone()));
+                    if let Err(e) = online_client.try_publish(available.clone(), QoS::AtLeastOnce, true, "online") {
+                        eprintln!("Failed to publish MQTT availability: {}", e);
+                    }
+                }
+                Ok(_) => {}
+                Err(e) => {
+                    eprintln!("MQTT connection error: {}", e);
+                    tokio::time::sleep(Duration::from_secs(5)).await;
+                }
+            }
+        }
+    }));
+
+    let mut receiver = events.lock().await.subscribe();
+    let mut tracker = FrigateTracker::new(&prefix);
+    let mut expiry = tokio::time::interval(Duration::from_secs(15));
+
+    loop {
+        let messages = tokio::select! {
+            received = receiver.recv() => match received {
+                Ok(event) => {
+                    let snapshot = if event.event_type == "detection" {
+                        // Snapshots are written under the store lock, so taking it waits for the file
+                        drop(events.lock().await);
+                        encryption::read_file(&dataset::snapshot_path(&event.id)).ok()
+                    } else {
+                        None
+                    };
+                    tracker.handle(&event, snapshot.as_deref())
+                }
+                Err(RecvError::Lagged(skipped)) => {
+                    eprintln!("MQTT publisher skipped {} events", skipped);
+                    continue;
+                }
+                Err(RecvError::Closed) => break,
+            },
+            _ = expiry.tick() => tracker.expire(chrono::Utc::now().timestamp_millis() as f64 / 1000.0),
+        };
+
+        for message in messages {
+            if let Err(e) = client
+                .publish(message.topic, QoS::AtLeastOnce, message.retain, message.payload)
+                .await
+            {
+                eprintln!("Failed to publish MQTT message: {}", e);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::yolo_detector::BoundingBox;
+
+    fn detection_event(id: &str, timestamp: &str, people: u32) -> StoredEvent {
+        let boxes = (0..people)
+            .map(|i| BoundingBox {
+                x1: 10.0,
+                y1: 20.0,
+                x2: 110.0,
+                y2: 220.0,
+                confidence: 0.5 + i as f32 * 0.1,
+                class_name: "person".to_string(),
+                track_id: None,
+                role: None,
+                floor: None,
+                motion: None,
+            })
+            .collect();
+        let data = DetectionData {
+            person_count: people,
+            object_counts: HashMap::from([("person".to_string(), people)]),
+            crowd_density: 0.0,
+            motion_intensity: 0.0,
+            zone_occupancy: 0.0,
+            boxes,
+            smoothed_person_count: None,
+            visitors: None,
+            staff_count: None,
+            annotations: BTreeMap::new(),
+            escalate: None,
+        };
+        StoredEvent {
+            id: id.to_string(),
+            timestamp: timestamp.to_string(),
+            camera_id: Some("front door".to_string()),
+            event_type: "detection".to_string(),
+            person_count: Some(people),
+            object_counts: None,
+            provider: Some("yolo".to_string()),
+            prompt: None,
+            description: None,
+            payload: serde_json::to_value(data).ok(),
+        }
+    }
+
+    fn event_type(message: &MqttMessage) -> String {
+        let payload: serde_json::Value = serde_json::from_slice(&message.payload).unwrap();
+        payload["type"].as_str().unwrap().to_string()
+    }
+
+    #[test]
+    fn test_frigate_event_lifecycle() {
+        let mut tracker = FrigateTracker::new("frigate/");
+
+        let messages = tracker.handle(&detection_event("abcdef12", "2025-01-15T10:00:00.000Z", 2), Some(b"jpeg"));
+        let topics: Vec<&str> = messages.iter().map(|m| m.topic.as_str()).collect();
+        assert_eq!(
+            topics,
+            vec!["frigate/front_door/person/snapshot", "frigate/events", "frigate/front_door/person", "frigate/front_door/all"]
+        );
+        assert!(messages[0].retain);
+        assert_eq!(event_type(&messages[1]), "new");
+        let new: serde_json::Value = serde_json::from_slice(&messages[1].payload).unwrap();
+        assert_eq!(new["after"]["id"], "1736935200.000000-abcdef");
+        assert_eq!(new["after"]["has_snapshot"], true);
+        assert_eq!(messages[2].payload, b"2");
+
+        let messages = tracker.handle(&detection_event("12345678", "2025-01-15T10:00:05.000Z", 1), None);
+        assert_eq!(event_type(&messages[0]), "update");
+
+        let mut analysis = detection_event("99999999", "2025-01-15T10:00:06.000Z", 0);
+        analysis.event_type = "analysis".to_string();
+        analysis.description = Some("A courier waiting at the door".to_string());
+        let messages = tracker.handle(&analysis, None);
+        assert_eq!(messages[0].topic, "frigate/tracked_object_update");
+        let update: serde_json::Value = serde_json::from_slice(&messages[0].payload).unwrap();
+        assert_eq!(update["id"], "1736935200.000000-abcdef");
+
+        let messages = tracker.handle(&detection_event("87654321", "2025-01-15T10:00:10.000Z", 0), None);
+        assert_eq!(event_type(&messages[0]), "end");
+        let end: serde_json::Value = serde_json::from_slice(&messages[0].payload).unwrap();
+        assert_eq!(end["after"]["end_time"], 1736935210.0);
+        assert_eq!(messages[1].payload, b"0");
+    }
+
+    #[test]
+    fn test_stale_objects_expire() {
+        let mut tracker = FrigateTracker::new(DEFAULT_TOPIC_PREFIX);
+        tracker.handle(&detection_event("abcdef12", "2025-01-15T10:00:00.000Z", 1), None);
+        assert!(tracker.expire(1736935200.0 + 60.0).is_empty());
+        let messages = tracker.expire(1736935200.0 + STALE_SECS + 1.0);
+        assert_eq!(event_type(&messages[0]), "end");
+        assert!(tracker.active.is_empty());
+    }
+}
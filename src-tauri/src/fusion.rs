@@ -0,0 +1,112 @@
+// Fusion Module - Cross-validates YOLO and Moondream detections of one class
+// Boxes are paired by IoU: pairs are confirmed, YOLO-only boxes are likely VLM misses, VLM-only boxes are YOLO misses or hallucinations
+
+use crate::evaluation::iou;
+use crate::yolo_detector::{BoundingBox, Detection, DetectionSource};
+use serde::{Deserialize, Serialize};
+
+// Loose enough for a VLM box drawn around the same object
+pub const DEFAULT_IOU_THRESHOLD: f32 = 0.3;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct FusedMatch {
+    pub yolo: Detection,
+    pub vlm: Detection,
+    pub iou: f32,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct FusedDetections {
+    pub class_name: String,
+    pub iou_threshold: f32,
+    pub matched: Vec<FusedMatch>,
+    pub yolo_only: Vec<Detection>,
+    pub vlm_only: Vec<Detection>,
+}
+
+// Pair YOLO boxes of the class with VLM detections, best IoU first, each box used at most once
+pub fn associate(class_name: &str, yolo: &[BoundingBox], vlm: Vec<Detection>, iou_threshold: f32) -> FusedDetections {
+    let yolo: Vec<Detection> = yolo
+        .iter()
+        .filter(|b| b.class_name.eq_ignore_ascii_case(class_name))
+        .map(|b| Detection { source: DetectionSource::Yolo, bbox: b.clone() })
+        .collect();
+
+    let mut pairs: Vec<(usize, usize, f32)> = Vec::new();
+    for (i, y) in yolo.iter().enumerate() {
+        for (j, v) in vlm.iter().enumerate() {
+            let overlap = iou(&y.bbox, &v.bbox);
+            if overlap >= iou_threshold && overlap > 0.0 {
+                pairs.push((i, j, overlap));
+            }
+        }
+    }
+    pairs.sort_by(|a, b| b.2.total_cmp(&a.2));
+
+    let mut yolo_used = vec![false; yolo.len()];
+    let mut vlm_used = vec![false; vlm.len()];
+    let mut matched = Vec::new();
+    for (i, j, overlap) in pairs {
+        if yolo_used[i] || vlm_used[j] {
+            continue;
+        }
+        yolo_used[i] = true;
+        vlm_used[j] = true;
+        matched.push(FusedMatch { yolo: yolo[i].clone(), vlm: vlm[j].clone(), iou: overlap });
+    }
+
+    FusedDetections {
+        class_name: class_name.to_string(),
+        iou_threshold,
+        matched,
+        yolo_only: yolo.into_iter().zip(yolo_used).filter(|(_, used)| !used).map(|(d, _)| d).collect(),
+        vlm_only: vlm.into_iter().zip(vlm_used).filter(|(_, used)| !used).map(|(d, _)| d).collect(),
+    }
+}
+
+// Pixel-space detections from a Moondream detect result
+pub fn vlm_detections(structured_data: Option<&serde_json::Value>) -> Vec<Detection> {
+    structured_data
+        .and_then(|data| data.get("detections"))
+        .and_then(|detections| serde_json::from_value(detections.clone()).ok())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bbox(class_name: &str, x1: f32) -> BoundingBox {
+        BoundingBox::new(class_name, x1, 0.0, x1 + 100.0, 100.0, 0.9)
+    }
+
+    fn vlm(x1: f32) -> Detection {
+        Detection { source: DetectionSource::Moondream, bbox: bbox("person", x1) }
+    }
+
+    #[test]
+    fn test_association_splits_matched_and_unmatched() {
+        let yolo = vec![bbox("person", 0.0), bbox("person", 300.0), bbox("car", 600.0)];
+        let fused = associate("person", &yolo, vec![vlm(10.0), vlm(800.0)], DEFAULT_IOU_THRESHOLD);
+
+        assert_eq!(fused.matched.len(), 1);
+        assert_eq!((fused.matched[0].yolo.bbox.x1, fused.matched[0].vlm.bbox.x1), (0.0, 10.0));
+        assert_eq!(fused.yolo_only.iter().map(|d| d.bbox.x1).collect::<Vec<_>>(), vec![300.0]);
+        assert_eq!(fused.vlm_only.iter().map(|d| d.bbox.x1).collect::<Vec<_>>(), vec![800.0]);
+    }
+
+    #[test]
+    fn test_best_overlap_wins() {
+        let fused = associate("person", &[bbox("person", 0.0)], vec![vlm(40.0), vlm(5.0)], DEFAULT_IOU_THRESHOLD);
+        assert_eq!(fused.matched[0].vlm.bbox.x1, 5.0);
+        assert_eq!(fused.vlm_only[0].bbox.x1, 40.0);
+    }
+
+    #[test]
+    fn test_detections_from_structured_data() {
+        let data = serde_json::json!({ "detections": [{ "source": "moondream", "x1": 1.0, "y1": 2.0, "x2": 3.0, "y2": 4.0, "confidence": 0.0, "class_name": "person" }] });
+        let detections = vlm_detections(Some(&data));
+        assert_eq!((detections.len(), detections[0].source), (1, DetectionSource::Moondream));
+        assert!(vlm_detections(None).is_empty());
+    }
+}
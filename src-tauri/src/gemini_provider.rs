@@ -0,0 +1,198 @@
+// Gemini Provider Module - Google generateContent API with inline image parts
+// Safety settings from the provider options are passed through unchanged
+
+use crate::network;
+use crate::vision_provider::{ProviderSettings, TokenUsage, VisionProvider, VisionRequest, VisionResponse};
+use async_trait::async_trait;
+
+pub const DEFAULT_BASE_URL: &str = "https://generativelanguage.googleapis.com/v1beta";
+pub const DEFAULT_MODEL: &str = "gemini-2.5-flash";
+const DEFAULT_MAX_TOKENS: u32 = 300;
+
+pub struct GeminiProvider {
+    name: String,
+    api_key: String,
+    base_url: String,
+    model: String,
+    safety_settings: Option<serde_json::Value>,
+}
+
+impl GeminiProvider {
+    pub fn from_settings(name: &str, settings: &ProviderSettings) -> Result<Self, String> {
+        let api_key = settings
+            .api_key
+            .clone()
+            .or_else(|| std::env::var("GEMINI_API_KEY").ok())
+            .ok_or("Gemini provider needs an API key (settings or GEMINI_API_KEY)")?;
+
+        // e.g. [{"category": "HARM_CATEGORY_DANGEROUS_CONTENT", "threshold": "BLOCK_ONLY_HIGH"}]
+        let safety_settings = settings.options.as_ref().and_then(|o| o.get("safety_settings")).cloned();
+        if let Some(safety) = &safety_settings {
+            if !safety.is_array() {
+                return Err("Gemini safety_settings must be an array".to_string());
+            }
+        }
+
+        Ok(GeminiProvider {
+            name: name.to_string(),
+            api_key,
+            base_url: settings
+                .base_url
+                .clone()
+                .unwrap_or_else(|| DEFAULT_BASE_URL.to_string())
+                .trim_end_matches('/')
+                .to_string(),
+            model: settings.model.clone().unwrap_or_else(|| DEFAULT_MODEL.to_string()),
+            safety_settings,
+        })
+    }
+
+    pub fn build_payload(&self, request: &VisionRequest) -> serde_json::Value {
+        let mut parts: Vec<serde_json::Value> = request
+            .images
+            .iter()
+            .map(|image| serde_json::json!({ "inline_data": { "mime_type": "image/jpeg", "data": image } }))
+            .collect();
+        parts.push(serde_json::json!({ "text": request.prompt }));
+
+        let mut payload = serde_json::json!({
+            "contents": [{ "role": "user", "parts": parts }],
+            "generationConfig": {
+                "temperature": 0.3,
+                "maxOutputTokens": request.max_tokens.unwrap_or(DEFAULT_MAX_TOKENS)
+            }
+        });
+        if let Some(safety) = &self.safety_settings {
+            payload["safetySettings"] = safety.clone();
+        }
+        payload
+    }
+}
+
+// Read the first candidate's text; blocked prompts and safety stops become errors
+pub fn parse_generation(body: &serde_json::Value) -> Result<(String, Option<TokenUsage>), String> {
+    if let Some(error) = body["error"]["message"].as_str() {
+        return Err(format!("Gemini API error: {}", error));
+    }
+    if let Some(reason) = body["promptFeedback"]["blockReason"].as_str() {
+        return Err(format!("Gemini blocked the request: {}", reason));
+    }
+
+    let candidate = &body["candidates"][0];
+    if candidate["finishReason"] == "SAFETY" {
+        return Err("Gemini stopped the response for safety reasons".to_string());
+    }
+
+    let text = candidate["content"]["parts"]
+        .as_array()
+        .ok_or("Gemini response has no content")?
+        .iter()
+        .filter_map(|part| part["text"].as_str())
+        .collect::<Vec<_>>()
+        .join("");
+
+    let usage = body["usageMetadata"]["promptTokenCount"].as_u64().map(|input| TokenUsage {
+        input_tokens: input as u32,
+        output_tokens: body["usageMetadata"]["candidatesTokenCount"].as_u64().unwrap_or(0) as u32,
+        ..Default::default()
+    });
+
+    Ok((text, usage))
+}
+
+#[async_trait]
+impl VisionProvider for GeminiProvider {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn model(&self) -> &str {
+        &self.model
+    }
+
+    fn endpoint(&self) -> Option<&str> {
+        Some(&self.base_url)
+    }
+
+    async fn analyze(&self, request: &VisionRequest) -> Result<VisionResponse, String> {
+        crate::local_only::check_url(&self.base_url)?;
+        crate::offline::check_url(&self.base_url)?;
+
+        let client = network::client_builder()
+            .timeout(std::time::Duration::from_millis(request.timeout_ms))
+            .build()
+            .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
+
+        let response = client
+            .post(format!("{}/models/{}:generateContent", self.base_url, request.model_or(&self.model)))
+            .header("x-goog-api-key", &self.api_key)
+            .json(&self.build_payload(request))
+            .send()
+            .await
+            .map_err(|e| format!("Failed to reach Gemini API: {}", e))?;
+
+        let status = response.status();
+        let body: serde_json::Value = response
+            .json()
+            .await
+            .map_err(|e| format!("Failed to parse response: {}", e))?;
+        if !status.is_success() && body["error"].is_null() {
+            return Err(format!("Analysis failed: {}", status));
+        }
+
+        let (text, usage) = parse_generation(&body)?;
+        Ok(VisionResponse {
+            provider: self.name.clone(),
+            model: request.model_or(&self.model).to_string(),
+            text,
+            usage,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_safety_settings_passthrough() {
+        let settings = ProviderSettings {
+            kind: "gemini".to_string(),
+            api_key: Some("key".to_string()),
+            base_url: None,
+            model: None,
+            options: Some(serde_json::json!({
+                "safety_settings": [{ "category": "HARM_CATEGORY_HARASSMENT", "threshold": "BLOCK_NONE" }]
+            })),
+        };
+        let provider = GeminiProvider::from_settings("gemini", &settings).unwrap();
+        let request = VisionRequest {
+            prompt: "Describe".to_string(),
+            images: vec!["AAAA".to_string()],
+            timeout_ms: 1000,
+            max_tokens: None,
+            ..Default::default()
+        };
+
+        let payload = provider.build_payload(&request);
+        assert_eq!(payload["safetySettings"][0]["threshold"], "BLOCK_NONE");
+        assert_eq!(payload["contents"][0]["parts"][0]["inline_data"]["data"], "AAAA");
+
+        let bad = ProviderSettings { options: Some(serde_json::json!({ "safety_settings": "none" })), ..settings };
+        assert!(GeminiProvider::from_settings("gemini", &bad).is_err());
+    }
+
+    #[test]
+    fn test_parse_generation() {
+        let body = serde_json::json!({
+            "candidates": [{ "content": { "parts": [{ "text": "An empty aisle." }] }, "finishReason": "STOP" }],
+            "usageMetadata": { "promptTokenCount": 300, "candidatesTokenCount": 5 }
+        });
+        let (text, usage) = parse_generation(&body).unwrap();
+        assert_eq!(text, "An empty aisle.");
+        assert_eq!(usage.unwrap().input_tokens, 300);
+
+        let blocked = serde_json::json!({ "promptFeedback": { "blockReason": "SAFETY" } });
+        assert!(parse_generation(&blocked).is_err());
+    }
+}
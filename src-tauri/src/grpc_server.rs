@@ -0,0 +1,201 @@
+// gRPC Server Module - Streaming frame submission over tonic (proto/analyzer.proto)
+// Raw image bytes in, one result per frame out; only built with the `grpc` feature
+
+use crate::AppState;
+use tokio::net::TcpListener;
+
+pub const DEFAULT_GRPC_PORT: u16 = 50051;
+
+pub fn compiled() -> bool {
+    cfg!(feature = "grpc")
+}
+
+#[cfg(feature = "grpc")]
+mod service {
+    use super::{AppState, TcpListener};
+    use crate::bus::BusEvent;
+    use crate::api_keys::{self, Role};
+    use crate::{dewarp, enhance, frame_processor, pipeline, slo};
+    use base64::{engine::general_purpose, Engine as _};
+    use std::pin::Pin;
+    use std::sync::Arc;
+    use tonic::{Request, Response, Status, Streaming};
+
+    pub mod proto {
+        tonic::include_proto!("lva.v1");
+    }
+
+    use proto::analyzer_server::{Analyzer, AnalyzerServer};
+    use proto::{Detection, Frame, FrameResult, HealthReply, HealthRequest};
+
+    // Same ceiling as the REST API body limit
+    const MAX_MESSAGE_BYTES: usize = 25 * 1024 * 1024;
+
+    type ResultStream = Pin<Box<dyn futures_util::Stream<Item = Result<FrameResult, Status>> + Send>>;
+
+    pub struct AnalyzerService {
+        state: AppState,
+    }
+
+    async fn process_frame(state: &AppState, frame: Frame) -> FrameResult {
+        let start_time = std::time::Instant::now();
+        let mut result = FrameResult {
+            frame_id: frame.frame_id.clone(),
+            ..Default::default()
+        };
+        if let Err(e) = run_frame(state, frame, &mut result).await {
+            result.error = e;
+        }
+        result.processing_time_ms = start_time.elapsed().as_millis() as u64;
+        result
+    }
+
+    async fn run_frame(state: &AppState, frame: Frame, result: &mut FrameResult) -> Result<(), String> {
+        crate::pause::check()?;
+        let camera_id = Some(frame.camera_id.as_str()).filter(|c| !c.is_empty());
+        let image = frame_processor::prepare_frame(general_purpose::STANDARD.encode(&frame.image), None)?;
+        let image = dewarp::for_camera(image, camera_id)?;
+
+        let detect_frame = enhance::for_detection(&image, camera_id)?;
+        state.bus.publish(BusEvent::FrameCaptured);
+        let detect_start = std::time::Instant::now();
+        let mut detection = state.yolo.detect(&detect_frame).await.inspect_err(|_| {
+            state.metrics.record_error("detection");
+        })?;
+        let latency = detect_start.elapsed();
+        crate::run_live_analytics(state, camera_id, &image, &mut detection).await;
+        state.bus.publish(BusEvent::DetectionReady {
+            camera_id: camera_id.map(|c| c.to_string()),
+            detection: Arc::new(detection.clone()),
+            latency,
+            live: true,
+            snapshot: None,
+        });
+
+        result.person_count = detection.person_count;
+        result.smoothed_person_count = detection.stable_person_count();
+        if let Some(visitors) = detection.visitors {
+            result.new_visitors = visitors.new;
+            result.returning_visitors = visitors.returning;
+        }
+        result.staff_count = detection.staff_count.unwrap_or(0);
+        result.object_counts = detection.object_counts.clone();
+        result.detections = detection
+            .boxes
+            .iter()
+            .map(|b| Detection {
+                class_name: b.class_name.clone(),
+                confidence: b.confidence,
+                x1: b.x1,
+                y1: b.y1,
+                x2: b.x2,
+                y2: b.y2,
+            })
+            .collect();
+
+        if !frame.analyze {
+            return Ok(());
+        }
+        let provider = Some(frame.provider).filter(|p| !p.is_empty()).unwrap_or_else(slo::default_provider);
+        let prompt = Some(frame.prompt)
+            .filter(|p| !p.is_empty())
+            .unwrap_or_else(|| crate::benchmark::DEFAULT_BENCHMARK_PROMPT.to_string());
+
+        let _in_flight = state.metrics.track_in_flight();
+        let vlm_start = std::time::Instant::now();
+        let analysis = pipeline::describe_frame(&state.moondream, &state.providers, &provider, &prompt, image).await;
+        state.bus.publish(BusEvent::analysis(&provider, vlm_start.elapsed(), analysis.is_ok()));
+        if let Some((description, analysis)) = analysis? {
+            crate::store_camera_analysis(state, camera_id, &provider, &prompt, &description, Some(analysis.clone())).await;
+            result.description = description;
+            result.analysis_json = analysis.to_string();
+        }
+        Ok(())
+    }
+
+    #[tonic::async_trait]
+    impl Analyzer for AnalyzerService {
+        type SubmitFramesStream = ResultStream;
+
+        async fn submit_frames(
+            &self,
+            request: Request<Streaming<Frame>>,
+        ) -> Result<Response<Self::SubmitFramesStream>, Status> {
+            let mut frames = request.into_inner();
+            let state = self.state.clone();
+            let (tx, rx) = tokio::sync::mpsc::channel(16);
+
+            // Frames are processed in order; a full channel slows the producer down
+            tokio::spawn(async move {
+                loop {
+                    let frame = match frames.message().await {
+                        Ok(Some(frame)) => frame,
+                        Ok(None) => break,
+                        Err(status) => {
+                            let _ = tx.send(Err(status)).await;
+                            break;
+                        }
+                    };
+                    if tx.send(Ok(process_frame(&state, frame).await)).await.is_err() {
+                        break;
+                    }
+                }
+            });
+
+            let results = futures_util::stream::unfold(rx, |mut rx| async move { rx.recv().await.map(|item| (item, rx)) });
+            Ok(Response::new(Box::pin(results)))
+        }
+
+        async fn health(&self, _request: Request<HealthRequest>) -> Result<Response<HealthReply>, Status> {
+            let ready = self.state.backend_ready.lock().await.as_ref().map(|b| b.ready).unwrap_or(false);
+            Ok(Response::new(HealthReply {
+                ready,
+                version: env!("CARGO_PKG_VERSION").to_string(),
+            }))
+        }
+    }
+
+    // Interceptors must return tonic's Status, however large clippy thinks it is
+    #[allow(clippy::result_large_err)]
+    pub async fn serve(state: AppState, listener: TcpListener) {
+        let incoming = match tonic::transport::server::TcpIncoming::from_listener(listener, true, None) {
+            Ok(incoming) => incoming,
+            Err(e) => {
+                eprintln!("gRPC server stopped: {}", e);
+                return;
+            }
+        };
+
+        let authenticate = move |request: Request<()>| {
+            let metadata = request.metadata();
+            let given = metadata
+                .get("authorization")
+                .and_then(|v| v.to_str().ok())
+                .and_then(|v| v.strip_prefix("Bearer "))
+                .or_else(|| metadata.get("x-api-key").and_then(|v| v.to_str().ok()));
+            // Every call submits frames, so operator keys and above
+            if api_keys::allows(given, Role::Operator) {
+                Ok(request)
+            } else {
+                Err(Status::unauthenticated("Missing or invalid API key (needs operator role)"))
+            }
+        };
+        let service = AnalyzerServer::new(AnalyzerService { state }).max_decoding_message_size(MAX_MESSAGE_BYTES);
+
+        if let Err(e) = tonic::transport::Server::builder()
+            .add_service(tonic::service::interceptor::InterceptedService::new(service, authenticate))
+            .serve_with_incoming(incoming)
+            .await
+        {
+            eprintln!("gRPC server stopped: {}", e);
+        }
+    }
+}
+
+#[cfg(feature = "grpc")]
+pub use service::serve;
+
+#[cfg(not(feature = "grpc"))]
+pub async fn serve(_state: AppState, _listener: TcpListener) {
+    eprintln!("This build does not include gRPC support (rebuild with --features grpc)");
+}
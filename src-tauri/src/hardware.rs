@@ -0,0 +1,179 @@
+// Hardware Module - GPU, memory and CPU detection for picking a model that fits
+// Recommendations size the LLaVA variant and Ollama thread/context settings to the machine
+
+use crate::settings::OllamaTuning;
+use serde::{Deserialize, Serialize};
+use tokio::process::Command;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct GpuInfo {
+    pub name: String,
+    pub vram_mb: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct HardwareInfo {
+    pub os: String,
+    pub arch: String,
+    pub cpu_cores: u32,
+    pub total_memory_mb: u64,
+    pub apple_silicon: bool,
+    pub gpus: Vec<GpuInfo>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct HardwareRecommendation {
+    pub vision_model: String,
+    pub tuning: OllamaTuning,
+    pub reason: String,
+}
+
+// NVIDIA cards through nvidia-smi; other GPUs are treated as CPU-only
+async fn detect_gpus() -> Vec<GpuInfo> {
+    let Ok(output) = Command::new("nvidia-smi")
+        .args(["--query-gpu=name,memory.total", "--format=csv,noheader,nounits"])
+        .output()
+        .await
+    else {
+        return Vec::new();
+    };
+    if !output.status.success() {
+        return Vec::new();
+    }
+    parse_nvidia_smi(&String::from_utf8_lossy(&output.stdout))
+}
+
+// Lines look like "NVIDIA GeForce RTX 4090, 24564"
+pub fn parse_nvidia_smi(output: &str) -> Vec<GpuInfo> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let (name, vram) = line.rsplit_once(',')?;
+            Some(GpuInfo {
+                name: name.trim().to_string(),
+                vram_mb: vram.trim().parse().ok()?,
+            })
+        })
+        .collect()
+}
+
+async fn detect_total_memory_mb() -> u64 {
+    if cfg!(target_os = "linux") {
+        return std::fs::read_to_string("/proc/meminfo")
+            .ok()
+            .and_then(|info| {
+                info.lines()
+                    .find(|l| l.starts_with("MemTotal:"))
+                    .and_then(|l| l.split_whitespace().nth(1))
+                    .and_then(|kb| kb.parse::<u64>().ok())
+            })
+            .map(|kb| kb / 1024)
+            .unwrap_or(0);
+    }
+
+    let (program, args): (&str, Vec<&str>) = if cfg!(target_os = "macos") {
+        ("sysctl", vec!["-n", "hw.memsize"])
+    } else {
+        ("powershell", vec!["-NoProfile", "-Command", "(Get-CimInstance Win32_ComputerSystem).TotalPhysicalMemory"])
+    };
+    match Command::new(program).args(args).output().await {
+        Ok(output) => String::from_utf8_lossy(&output.stdout).trim().parse::<u64>().unwrap_or(0) / (1024 * 1024),
+        Err(_) => 0,
+    }
+}
+
+pub async fn detect() -> HardwareInfo {
+    HardwareInfo {
+        os: std::env::consts::OS.to_string(),
+        arch: std::env::consts::ARCH.to_string(),
+        cpu_cores: std::thread::available_parallelism().map(|n| n.get() as u32).unwrap_or(4),
+        total_memory_mb: detect_total_memory_mb().await,
+        apple_silicon: cfg!(all(target_os = "macos", target_arch = "aarch64")),
+        gpus: detect_gpus().await,
+    }
+}
+
+fn vram_mb(hardware: &HardwareInfo) -> u64 {
+    hardware.gpus.iter().map(|g| g.vram_mb).max().unwrap_or(0)
+}
+
+// Memory the model can live in: VRAM, most of unified memory, or half of system RAM
+pub fn model_memory_mb(hardware: &HardwareInfo) -> u64 {
+    let vram_mb = vram_mb(hardware);
+    if vram_mb > 0 {
+        vram_mb
+    } else if hardware.apple_silicon {
+        hardware.total_memory_mb * 3 / 4
+    } else {
+        hardware.total_memory_mb / 2
+    }
+}
+
+pub fn recommend(hardware: &HardwareInfo) -> HardwareRecommendation {
+    let vram_mb = vram_mb(hardware);
+    let model_memory_mb = model_memory_mb(hardware);
+    let accelerator = if vram_mb > 0 {
+        format!("{} MB VRAM", vram_mb)
+    } else if hardware.apple_silicon {
+        format!("{} MB unified memory", hardware.total_memory_mb)
+    } else {
+        format!("{} MB RAM (CPU only)", hardware.total_memory_mb)
+    };
+
+    let vision_model = match model_memory_mb {
+        m if m >= 24_000 => "llava:34b",
+        m if m >= 12_000 => "llava:13b",
+        m if m >= 8_000 => "llava:7b",
+        m if m >= 5_000 => "llava:7b-v1.6-mistral-q4_0",
+        _ => "llava-phi3",
+    };
+
+    // Leave a core free for capture and the UI; GPU inference needs fewer CPU threads
+    let accelerated = vram_mb > 0 || hardware.apple_silicon;
+    let num_thread = if accelerated {
+        (hardware.cpu_cores / 2).clamp(2, 8)
+    } else {
+        hardware.cpu_cores.saturating_sub(1).clamp(2, 16)
+    };
+    let num_ctx = if model_memory_mb >= 16_000 { 4096 } else { 2048 };
+
+    HardwareRecommendation {
+        vision_model: vision_model.to_string(),
+        tuning: OllamaTuning { num_thread, num_ctx },
+        reason: format!("{} with {} CPU cores", accelerator, hardware.cpu_cores),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn machine(cores: u32, memory_mb: u64, apple_silicon: bool, gpus: Vec<GpuInfo>) -> HardwareInfo {
+        HardwareInfo {
+            os: "linux".to_string(),
+            arch: "x86_64".to_string(),
+            cpu_cores: cores,
+            total_memory_mb: memory_mb,
+            apple_silicon,
+            gpus,
+        }
+    }
+
+    #[test]
+    fn test_recommendations_scale_with_memory() {
+        let gpus = parse_nvidia_smi("NVIDIA GeForce RTX 4090, 24564\n");
+        assert_eq!(gpus[0].vram_mb, 24564);
+
+        let workstation = recommend(&machine(16, 65536, false, gpus));
+        assert_eq!(workstation.vision_model, "llava:34b");
+        assert_eq!(workstation.tuning.num_thread, 8);
+
+        let macbook = recommend(&machine(8, 16384, true, Vec::new()));
+        assert_eq!(macbook.vision_model, "llava:13b");
+
+        let laptop = recommend(&machine(4, 8192, false, Vec::new()));
+        assert_eq!(laptop.vision_model, "llava-phi3");
+        assert_eq!(laptop.tuning.num_thread, 3);
+        assert_eq!(laptop.tuning.num_ctx, 2048);
+    }
+}
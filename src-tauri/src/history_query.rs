@@ -0,0 +1,498 @@
+// History Query Module - Natural-language questions over the event store
+// Common questions are translated by rules; anything else can be handed to the local LLM as SQL
+
+use crate::encryption;
+use crate::event_store::{format_timestamp, QueryRows, SCHEMA_DESCRIPTION};
+use chrono::{DateTime, Datelike, Duration, Local, NaiveTime, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+
+// Most matches a description search returns
+const SEARCH_LIMIT: usize = 50;
+
+// Columns stored encrypted while encryption is on, which SQL can't filter on
+const SEALED_COLUMNS: [&str; 2] = ["description", "payload"];
+
+// Keywords that must never appear in generated SQL
+const FORBIDDEN_SQL: [&str; 12] = [
+    "insert", "update", "delete", "drop", "alter", "create", "replace",
+    "attach", "detach", "pragma", "vacuum", "reindex",
+];
+
+// Time range a question refers to
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct TimeWindow {
+    pub from: String,
+    pub to: String,
+}
+
+// What the user is asking for
+#[derive(Debug, Clone, PartialEq)]
+pub enum HistoryIntent {
+    Footfall,
+    PeakOccupancy,
+    AverageOccupancy,
+    CountAnalyses,
+    SearchDescriptions(String),
+    ListEvents,
+}
+
+// A translated question ready to run against the store
+#[derive(Debug, Clone)]
+pub struct HistoryQuery {
+    pub intent: HistoryIntent,
+    pub window: TimeWindow,
+    pub sql: String,
+    pub sealed: bool,  // Descriptions are encrypted, so the search term is matched after the rows are decrypted
+}
+
+// Final answer returned by the ask_history command
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct HistoryAnswer {
+    pub question: String,
+    pub answer: String,
+    pub method: String,  // "rules" or "llm"
+    pub sql: String,
+    pub window: Option<TimeWindow>,
+    pub columns: Vec<String>,
+    pub rows: Vec<serde_json::Value>,
+    pub truncated: bool,
+}
+
+// Parse clock times like "6pm", "6:30 pm", "18:00"
+fn parse_clock(text: &str) -> Option<NaiveTime> {
+    let cleaned: String = text.trim().replace(' ', "");
+    let (digits, meridiem) = if let Some(stripped) = cleaned.strip_suffix("pm") {
+        (stripped, Some(true))
+    } else if let Some(stripped) = cleaned.strip_suffix("am") {
+        (stripped, Some(false))
+    } else {
+        (cleaned.as_str(), None)
+    };
+
+    let (hour, minute) = match digits.split_once(':') {
+        Some((h, m)) => (h.parse::<u32>().ok()?, m.parse::<u32>().ok()?),
+        None => (digits.parse::<u32>().ok()?, 0),
+    };
+
+    // Bare numbers without am/pm or minutes are too ambiguous ("after 5 visitors")
+    if meridiem.is_none() && !digits.contains(':') {
+        return None;
+    }
+
+    let hour = match meridiem {
+        Some(true) if hour < 12 => hour + 12,
+        Some(false) if hour == 12 => 0,
+        _ => hour,
+    };
+
+    NaiveTime::from_hms_opt(hour, minute, 0)
+}
+
+// Find the clock time that follows a keyword ("after 6pm" -> 18:00)
+fn clock_after_keyword(question: &str, keyword: &str) -> Option<NaiveTime> {
+    let start = question.find(keyword)? + keyword.len();
+    let rest: Vec<&str> = question[start..].split_whitespace().take(2).collect();
+
+    // Try "6pm" first, then "6 pm"
+    rest.first()
+        .and_then(|word| parse_clock(word.trim_matches(|c: char| !c.is_alphanumeric() && c != ':')))
+        .or_else(|| parse_clock(&rest.join(" ")))
+}
+
+// Number that precedes a unit ("last 3 hours" -> 3)
+fn number_before(question: &str, units: &[&str]) -> Option<i64> {
+    let words: Vec<&str> = question.split_whitespace().collect();
+    for window in words.windows(2) {
+        if units.iter().any(|unit| window[1].starts_with(unit)) {
+            if let Ok(n) = window[0].parse::<i64>() {
+                return Some(n);
+            }
+        }
+    }
+    None
+}
+
+// Local wall-clock time on a given day, converted to UTC
+pub fn local_at(day: chrono::NaiveDate, time: NaiveTime) -> DateTime<Utc> {
+    Local
+        .from_local_datetime(&day.and_time(time))
+        .earliest()
+        .map(|t| t.with_timezone(&Utc))
+        .unwrap_or_else(|| Utc.from_utc_datetime(&day.and_time(time)))
+}
+
+// Work out the time range a question refers to (defaults to the last 24 hours)
+pub fn parse_time_window(question: &str, now: DateTime<Local>) -> TimeWindow {
+    let q = question.to_lowercase();
+    let today = now.date_naive();
+    let now_utc = now.with_timezone(&Utc);
+
+    let (mut from, mut to) = if q.contains("yesterday") {
+        let day = today - Duration::days(1);
+        (local_at(day, NaiveTime::MIN), local_at(today, NaiveTime::MIN))
+    } else if q.contains("today") || q.contains("this morning") || q.contains("tonight") {
+        (local_at(today, NaiveTime::MIN), now_utc)
+    } else if q.contains("this week") {
+        let monday = today - Duration::days(now.weekday().num_days_from_monday() as i64);
+        (local_at(monday, NaiveTime::MIN), now_utc)
+    } else if let Some(n) = number_before(&q, &["hour", "hr"]) {
+        (now_utc - Duration::hours(n), now_utc)
+    } else if let Some(n) = number_before(&q, &["minute", "min"]) {
+        (now_utc - Duration::minutes(n), now_utc)
+    } else if let Some(n) = number_before(&q, &["day"]) {
+        (now_utc - Duration::days(n), now_utc)
+    } else if q.contains("last hour") || q.contains("past hour") {
+        (now_utc - Duration::hours(1), now_utc)
+    } else {
+        (now_utc - Duration::hours(24), now_utc)
+    };
+
+    // Clock times narrow the window within the chosen day
+    let day = if q.contains("yesterday") { today - Duration::days(1) } else { today };
+    if let Some(time) = clock_after_keyword(&q, "after ").or_else(|| clock_after_keyword(&q, "since ")) {
+        from = local_at(day, time);
+    }
+    if let Some(time) = clock_after_keyword(&q, "before ").or_else(|| clock_after_keyword(&q, "until ")) {
+        to = local_at(day, time);
+    }
+    if let (Some(start), Some(end)) = (clock_after_keyword(&q, "between "), clock_after_keyword(&q, " and ")) {
+        from = local_at(day, start);
+        to = local_at(day, end);
+    }
+
+    TimeWindow {
+        from: format_timestamp(from),
+        to: format_timestamp(to),
+    }
+}
+
+// Extract a search phrase from quotes or after "mentioning"/"containing"/"about"
+fn search_term(question: &str) -> Option<String> {
+    if let Some(start) = question.find('"') {
+        if let Some(len) = question[start + 1..].find('"') {
+            let term = &question[start + 1..start + 1 + len];
+            if !term.trim().is_empty() {
+                return Some(term.trim().to_string());
+            }
+        }
+    }
+
+    let q = question.to_lowercase();
+    for keyword in ["mentioning ", "containing ", "with a ", "carrying ", "about "] {
+        if let Some(start) = q.find(keyword) {
+            let term: String = q[start + keyword.len()..]
+                .split(['?', '.', ','])
+                .next()
+                .unwrap_or("")
+                .split_whitespace()
+                .take_while(|w| !["today", "yesterday", "after", "before", "since", "in", "during"].contains(w))
+                .collect::<Vec<_>>()
+                .join(" ");
+            if !term.is_empty() {
+                return Some(term);
+            }
+        }
+    }
+
+    None
+}
+
+pub fn classify_intent(question: &str) -> Option<HistoryIntent> {
+    let q = question.to_lowercase();
+
+    if let Some(term) = search_term(question) {
+        return Some(HistoryIntent::SearchDescriptions(term));
+    }
+    if q.contains("peak") || q.contains("busiest") || q.contains("most people") || q.contains("maximum") || q.contains("max ") {
+        return Some(HistoryIntent::PeakOccupancy);
+    }
+    if q.contains("average") || q.contains("typical") {
+        return Some(HistoryIntent::AverageOccupancy);
+    }
+    if q.contains("how many people") || q.contains("footfall") || q.contains("visitors")
+        || q.contains("came in") || q.contains("entered") || q.contains("customers")
+    {
+        return Some(HistoryIntent::Footfall);
+    }
+    if q.contains("how many analys") || q.contains("how many times") {
+        return Some(HistoryIntent::CountAnalyses);
+    }
+    if q.starts_with("show") || q.starts_with("list") || q.contains("what happened") {
+        return Some(HistoryIntent::ListEvents);
+    }
+
+    None
+}
+
+fn window_clause(window: &TimeWindow) -> String {
+    // Window bounds are generated by format_timestamp, never by the user
+    format!("timestamp >= '{}' AND timestamp < '{}'", window.from, window.to)
+}
+
+// Translate a question into SQL using built-in rules
+pub fn translate(question: &str, now: DateTime<Local>) -> Option<HistoryQuery> {
+    translate_for(question, now, encryption::enabled())
+}
+
+fn translate_for(question: &str, now: DateTime<Local>, sealed: bool) -> Option<HistoryQuery> {
+    let intent = classify_intent(question)?;
+    let window = parse_time_window(question, now);
+    let range = window_clause(&window);
+
+    let sql = match &intent {
+        // Footfall estimate: every increase in the person count is counted as new arrivals
+        HistoryIntent::Footfall => format!(
+            "SELECT COALESCE(SUM(CASE WHEN person_count > prev THEN person_count - prev ELSE 0 END), 0) AS people_entered \
+             FROM (SELECT person_count, LAG(person_count, 1, 0) OVER (ORDER BY timestamp) AS prev \
+                   FROM events WHERE event_type = 'detection' AND {})",
+            range
+        ),
+        HistoryIntent::PeakOccupancy => format!(
+            "SELECT person_count AS peak_people, timestamp FROM events \
+             WHERE event_type = 'detection' AND {} ORDER BY person_count DESC, timestamp ASC LIMIT 1",
+            range
+        ),
+        HistoryIntent::AverageOccupancy => format!(
+            "SELECT ROUND(AVG(person_count), 2) AS average_people FROM events WHERE event_type = 'detection' AND {}",
+            range
+        ),
+        HistoryIntent::CountAnalyses => format!(
+            "SELECT COUNT(*) AS analyses FROM events WHERE event_type = 'analysis' AND {}",
+            range
+        ),
+        // LIKE can't see into sealed descriptions; the newest analyses are fetched and matched by filter_sealed
+        HistoryIntent::SearchDescriptions(_) if sealed => format!(
+            "SELECT id, timestamp, provider, description FROM events \
+             WHERE event_type = 'analysis' AND {} ORDER BY timestamp DESC",
+            range
+        ),
+        HistoryIntent::SearchDescriptions(term) => format!(
+            "SELECT id, timestamp, provider, description FROM events \
+             WHERE event_type = 'analysis' AND description LIKE '%{}%' AND {} ORDER BY timestamp DESC LIMIT {}",
+            term.replace('\'', "''").replace(['%', '_'], ""),
+            range,
+            SEARCH_LIMIT
+        ),
+        HistoryIntent::ListEvents => format!(
+            "SELECT id, timestamp, event_type, person_count, provider, description FROM events \
+             WHERE {} ORDER BY timestamp DESC LIMIT 50",
+            range
+        ),
+    };
+
+    let sealed = sealed && matches!(intent, HistoryIntent::SearchDescriptions(_));
+    Some(HistoryQuery { intent, window, sql, sealed })
+}
+
+// Match a sealed description search against the decrypted rows, case-insensitively like SQLite's LIKE
+pub fn filter_sealed(query: &HistoryQuery, rows: &mut QueryRows) {
+    let HistoryIntent::SearchDescriptions(term) = &query.intent else {
+        return;
+    };
+    if !query.sealed {
+        return;
+    }
+    let term = term.to_lowercase();
+    rows.rows.retain(|row| row["description"].as_str().is_some_and(|d| d.to_lowercase().contains(&term)));
+    rows.rows.truncate(SEARCH_LIMIT);
+}
+
+// Generated SQL filtering on encrypted columns would silently match nothing
+pub fn check_sealed_columns(sql: &str) -> Result<(), String> {
+    let lowered = sql.to_lowercase();
+    let Some(start) = lowered.find(" where ") else {
+        return Ok(());
+    };
+    let words: Vec<&str> = lowered[start..].split(|c: char| !c.is_alphanumeric() && c != '_').collect();
+    match SEALED_COLUMNS.iter().find(|c| words.contains(c)) {
+        Some(column) => Err(format!(
+            "Filtering on {} is unavailable while encryption is on - ask for a quoted phrase instead",
+            column
+        )),
+        None => Ok(()),
+    }
+}
+
+// Prompt asking the local LLM to write a single SQLite SELECT for the question
+pub fn build_sql_prompt(question: &str, now: DateTime<Local>) -> String {
+    format!(
+        "You translate questions about a camera event log into SQLite queries.\n\
+         {}\n\n\
+         The current local time is {} (UTC offset {}). Timestamps in the table are UTC.\n\
+         Write ONE read-only SQLite SELECT statement that answers the question. \
+         Respond with only the SQL, no explanation and no markdown.\n\n\
+         Question: {}\nSQL:",
+        SCHEMA_DESCRIPTION,
+        now.format("%Y-%m-%d %H:%M"),
+        now.format("%:z"),
+        question
+    )
+}
+
+// Clean up and validate LLM-generated SQL so only a single SELECT can run
+pub fn validate_sql(raw: &str) -> Result<String, String> {
+    let mut sql = raw.trim();
+    if let Some(start) = sql.find("```") {
+        sql = &sql[start + 3..];
+        sql = sql.strip_prefix("sql").unwrap_or(sql);
+        sql = sql.split("```").next().unwrap_or(sql);
+    }
+    let sql = sql.trim().trim_end_matches(';').trim();
+
+    let lowered = sql.to_lowercase();
+    if !(lowered.starts_with("select") || lowered.starts_with("with")) {
+        return Err(format!("Generated query is not a SELECT: {}", sql));
+    }
+    if sql.contains(';') {
+        return Err("Generated query contains multiple statements".to_string());
+    }
+
+    let words: Vec<&str> = lowered
+        .split(|c: char| !c.is_alphanumeric() && c != '_')
+        .collect();
+    if let Some(keyword) = FORBIDDEN_SQL.iter().find(|k| words.contains(k)) {
+        return Err(format!("Generated query uses forbidden keyword: {}", keyword));
+    }
+
+    Ok(sql.to_string())
+}
+
+// Turn query rows into a short sentence
+pub fn summarize(intent: Option<&HistoryIntent>, rows: &QueryRows) -> String {
+    let first = rows.rows.first();
+    let value = |column: &str| first.and_then(|row| row.get(column)).cloned().unwrap_or(serde_json::Value::Null);
+
+    match intent {
+        Some(HistoryIntent::Footfall) => format!("An estimated {} people came in.", value("people_entered")),
+        Some(HistoryIntent::PeakOccupancy) => match first {
+            Some(_) => format!("Peak occupancy was {} people at {}.", value("peak_people"), value("timestamp").as_str().unwrap_or("")),
+            None => "No detections were recorded in that period.".to_string(),
+        },
+        Some(HistoryIntent::AverageOccupancy) => match value("average_people") {
+            serde_json::Value::Null => "No detections were recorded in that period.".to_string(),
+            average => format!("On average {} people were visible.", average),
+        },
+        Some(HistoryIntent::CountAnalyses) => format!("{} analyses were run.", value("analyses")),
+        Some(HistoryIntent::SearchDescriptions(term)) => {
+            format!("Found {} analyses mentioning \"{}\".", rows.rows.len(), term)
+        }
+        Some(HistoryIntent::ListEvents) => format!("Found {} events.", rows.rows.len()),
+        None => {
+            // Single scalar results read naturally on their own
+            if rows.rows.len() == 1 && rows.columns.len() == 1 {
+                format!("{}: {}", rows.columns[0], value(&rows.columns[0]))
+            } else {
+                format!("Query returned {} rows.", rows.rows.len())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixed_now() -> DateTime<Local> {
+        Local.with_ymd_and_hms(2025, 3, 12, 21, 0, 0).unwrap()
+    }
+
+    #[test]
+    fn test_clock_parsing() {
+        assert_eq!(parse_clock("6pm"), NaiveTime::from_hms_opt(18, 0, 0));
+        assert_eq!(parse_clock("6:30 am"), NaiveTime::from_hms_opt(6, 30, 0));
+        assert_eq!(parse_clock("12am"), NaiveTime::from_hms_opt(0, 0, 0));
+        assert_eq!(parse_clock("18:15"), NaiveTime::from_hms_opt(18, 15, 0));
+        assert_eq!(parse_clock("5"), None);
+    }
+
+    #[test]
+    fn test_yesterday_after_6pm_window() {
+        let window = parse_time_window("How many people came in after 6pm yesterday?", fixed_now());
+        let from = DateTime::parse_from_rfc3339(&window.from).unwrap().with_timezone(&Local);
+        let to = DateTime::parse_from_rfc3339(&window.to).unwrap().with_timezone(&Local);
+
+        assert_eq!(from, Local.with_ymd_and_hms(2025, 3, 11, 18, 0, 0).unwrap());
+        assert_eq!(to, Local.with_ymd_and_hms(2025, 3, 12, 0, 0, 0).unwrap());
+    }
+
+    #[test]
+    fn test_footfall_counts_increases_only() {
+        let store = crate::event_store::EventStore::open_in_memory().unwrap();
+        let now = Utc::now();
+        for (offset, count) in [(30, 1), (20, 3), (10, 2), (5, 4)] {
+            store.insert_event(&crate::event_store::StoredEvent {
+                id: uuid::Uuid::new_v4().to_string(),
+                timestamp: format_timestamp(now - Duration::minutes(offset)),
+                camera_id: None,
+                event_type: "detection".to_string(),
+                person_count: Some(count),
+                object_counts: None,
+                provider: None,
+                prompt: None,
+                description: None,
+                payload: None,
+            }).unwrap();
+        }
+
+        let query = translate("How many people came in during the last 2 hours?", Local::now()).unwrap();
+        let rows = store.query_read_only(&query.sql).unwrap();
+        // 0 -> 1 -> 3 -> 2 -> 4: arrivals are 1 + 2 + 2
+        assert_eq!(rows.rows[0]["people_entered"], 5);
+    }
+
+    #[test]
+    fn test_description_search_while_encrypted() {
+        crate::encryption::setup("history passphrase", false).unwrap();
+        let store = crate::event_store::EventStore::open_in_memory().unwrap();
+        let now = Utc::now();
+        for (offset, description) in [(30, "A man carrying a Ladder"), (20, "Empty aisle"), (10, "Ladder left by the door")] {
+            store.insert_event(&crate::event_store::StoredEvent {
+                id: uuid::Uuid::new_v4().to_string(),
+                timestamp: format_timestamp(now - Duration::minutes(offset)),
+                camera_id: None,
+                event_type: "analysis".to_string(),
+                person_count: None,
+                object_counts: None,
+                provider: Some("llava".to_string()),
+                prompt: None,
+                description: Some(crate::encryption::force_seal_text(description).unwrap()),
+                payload: None,
+            }).unwrap();
+        }
+
+        let question = "Any analyses mentioning \"ladder\" in the last 2 hours?";
+        let plain = translate_for(question, Local::now(), false).unwrap();
+        assert!(store.query_read_only(&plain.sql).unwrap().rows.is_empty());
+
+        let sealed = translate_for(question, Local::now(), true).unwrap();
+        let mut rows = store.query_read_only(&sealed.sql).unwrap();
+        filter_sealed(&sealed, &mut rows);
+        let found: Vec<&str> = rows.rows.iter().map(|r| r["description"].as_str().unwrap()).collect();
+        assert_eq!(found, vec!["Ladder left by the door", "A man carrying a Ladder"]);
+
+        assert!(check_sealed_columns("SELECT description FROM events WHERE description LIKE '%van%'").is_err());
+        assert!(check_sealed_columns("SELECT id, description FROM events WHERE person_count > 3").is_ok());
+    }
+
+    #[test]
+    fn test_intent_classification() {
+        assert_eq!(classify_intent("How many people came in today?"), Some(HistoryIntent::Footfall));
+        assert_eq!(classify_intent("When was the busiest time?"), Some(HistoryIntent::PeakOccupancy));
+        assert_eq!(
+            classify_intent("Any analyses mentioning a ladder?"),
+            Some(HistoryIntent::SearchDescriptions("a ladder".to_string()))
+        );
+        assert_eq!(classify_intent("Which camera is the coldest?"), None);
+    }
+
+    #[test]
+    fn test_sql_validation() {
+        assert_eq!(
+            validate_sql("```sql\nSELECT COUNT(*) FROM events;\n```").unwrap(),
+            "SELECT COUNT(*) FROM events"
+        );
+        assert!(validate_sql("DELETE FROM events").is_err());
+        assert!(validate_sql("SELECT 1; DROP TABLE events").is_err());
+        assert!(validate_sql("WITH x AS (SELECT 1) SELECT * FROM x; PRAGMA query_only = OFF").is_err());
+    }
+}
@@ -0,0 +1,267 @@
+// Hub Module - Pull events from other analyzer instances into this one (e.g. a small chain of stores)
+// Each remote is caught up over its REST /events endpoint, then followed live over its /ws stream
+
+use crate::event_store::{EventStore, StoredEvent};
+use crate::local_only;
+use crate::network;
+use futures_util::StreamExt;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Mutex;
+use tokio_tungstenite::tungstenite::Message;
+
+// Catch-up pages of the remote's maximum /events page size
+const PAGE_SIZE: usize = 500;
+const MAX_CATCH_UP_PAGES: usize = 20;
+
+// Reconnect delay grows from 5s to a minute while a remote stays down
+const MIN_RETRY_SECS: u64 = 5;
+const MAX_RETRY_SECS: u64 = 60;
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct RemoteInstance {
+    pub name: String,  // Stored with every pulled event; filter on it with EventFilter::instance
+    pub url: String,   // The remote's REST API, e.g. http://store-12.local:8787
+    pub api_key: String,
+}
+
+impl RemoteInstance {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.name.trim().is_empty() || self.name == "local" {
+            return Err("Instance name is required and cannot be 'local'".to_string());
+        }
+        let url = reqwest::Url::parse(&self.url).map_err(|e| format!("Invalid instance URL '{}': {}", self.url, e))?;
+        if !matches!(url.scheme(), "http" | "https") {
+            return Err(format!("Instance URL must be http or https: {}", self.url));
+        }
+        Ok(())
+    }
+
+    fn endpoint(&self, path: &str) -> Result<reqwest::Url, String> {
+        reqwest::Url::parse(&self.url)
+            .and_then(|base| base.join(path))
+            .map_err(|e| format!("Invalid instance URL '{}': {}", self.url, e))
+    }
+
+    // ws(s)://host/ws?api_key=...
+    fn stream_url(&self) -> Result<String, String> {
+        let mut url = self.endpoint("/ws")?;
+        let scheme = if url.scheme() == "https" { "wss" } else { "ws" };
+        url.set_scheme(scheme).map_err(|_| format!("Invalid instance URL '{}'", self.url))?;
+        url.query_pairs_mut().append_pair("api_key", &self.api_key);
+        Ok(url.to_string())
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct InstanceStatus {
+    pub name: String,
+    pub url: String,
+    pub connected: bool,
+    pub events_received: u64,
+    pub last_event_at: Option<String>,
+    pub last_error: Option<String>,
+}
+
+type StatusMap = Arc<std::sync::Mutex<BTreeMap<String, InstanceStatus>>>;
+
+fn update_status(statuses: &StatusMap, name: &str, change: impl FnOnce(&mut InstanceStatus)) {
+    if let Ok(mut statuses) = statuses.lock() {
+        if let Some(status) = statuses.get_mut(name) {
+            change(status);
+        }
+    }
+}
+
+// Check a remote before registering it
+pub async fn probe(instance: &RemoteInstance) -> Result<(), String> {
+    instance.validate()?;
+    local_only::check_url(&instance.url)?;
+    let response = network::client()
+        .get(instance.endpoint("/events")?)
+        .query(&[("limit", "1")])
+        .bearer_auth(&instance.api_key)
+        .timeout(Duration::from_secs(10))
+        .send()
+        .await
+        .map_err(|e| format!("Failed to reach {}: {}", instance.url, e))?;
+    match response.status() {
+        status if status.is_success() => Ok(()),
+        reqwest::StatusCode::UNAUTHORIZED => Err(format!("{} rejected the API key", instance.url)),
+        status => Err(format!("{} answered {}", instance.url, status)),
+    }
+}
+
+// Page backwards from the newest event until everything since `from` is stored
+async fn catch_up(instance: &RemoteInstance, events: &Mutex<EventStore>) -> Result<usize, String> {
+    let from = events.lock().await.last_remote_timestamp(&instance.name)?;
+    let client = network::client();
+    let mut to: Option<String> = None;
+    let mut stored = 0;
+
+    for _ in 0..MAX_CATCH_UP_PAGES {
+        let mut query = vec![("limit", PAGE_SIZE.to_string())];
+        query.extend(from.clone().map(|f| ("from", f)));
+        query.extend(to.clone().map(|t| ("to", t)));
+        let page: Vec<StoredEvent> = client
+            .get(instance.endpoint("/events")?)
+            .query(&query)
+            .bearer_auth(&instance.api_key)
+            .timeout(Duration::from_secs(30))
+            .send()
+            .await
+            .and_then(|r| r.error_for_status())
+            .map_err(|e| format!("Failed to fetch events from {}: {}", instance.name, e))?
+            .json()
+            .await
+            .map_err(|e| format!("Invalid events from {}: {}", instance.name, e))?;
+
+        let store = events.lock().await;
+        for event in &page {
+            if store.store_remote_event(&instance.name, event)? {
+                stored += 1;
+            }
+        }
+        // A first sync only takes the newest page
+        if page.len() < PAGE_SIZE || from.is_none() {
+            break;
+        }
+        to = page.last().map(|e| e.timestamp.clone());
+    }
+    Ok(stored)
+}
+
+async fn follow(instance: &RemoteInstance, events: &Mutex<EventStore>, statuses: &StatusMap) -> Result<(), String> {
+    let (mut socket, _) = tokio_tungstenite::connect_async(instance.stream_url()?)
+        .await
+        .map_err(|e| format!("Failed to connect to {}: {}", instance.name, e))?;
+    update_status(statuses, &instance.name, |s| {
+        s.connected = true;
+        s.last_error = None;
+    });
+
+    while let Some(message) = socket.next().await {
+        let text = match message.map_err(|e| format!("Stream from {} failed: {}", instance.name, e))? {
+            Message::Text(text) => text,
+            Message::Close(_) => break,
+            _ => continue,
+        };
+        let Ok(message) = serde_json::from_str::<serde_json::Value>(&text) else { continue };
+        match message["type"].as_str() {
+            Some("event") => {
+                let Ok(event) = serde_json::from_value::<StoredEvent>(message["event"].clone()) else { continue };
+                if events.lock().await.store_remote_event(&instance.name, &event)? {
+                    update_status(statuses, &instance.name, |s| {
+                        s.events_received += 1;
+                        s.last_event_at = Some(event.timestamp.clone());
+                    });
+                }
+            }
+            // The remote dropped events for us; the next catch-up fills the gap
+            Some("lagged") => break,
+            _ => {}
+        }
+    }
+    Err(format!("{} closed the stream", instance.name))
+}
+
+async fn run_instance(instance: RemoteInstance, events: Arc<Mutex<EventStore>>, statuses: StatusMap) {
+    let mut retry_secs = MIN_RETRY_SECS;
+    loop {
+        let result = match local_only::check_url(&instance.url) {
+            Ok(()) => match catch_up(&instance, &events).await {
+                Ok(stored) => {
+                    if stored > 0 {
+                        println!("Hub: Caught up {} events from {}", stored, instance.name);
+                        update_status(&statuses, &instance.name, |s| s.events_received += stored as u64);
+                    }
+                    retry_secs = MIN_RETRY_SECS;
+                    follow(&instance, &events, &statuses).await
+                }
+                Err(e) => Err(e),
+            },
+            Err(e) => Err(e),
+        };
+        if let Err(e) = result {
+            eprintln!("Hub: {}", e);
+            update_status(&statuses, &instance.name, |s| {
+                s.connected = false;
+                s.last_error = Some(e);
+            });
+        }
+        tokio::time::sleep(Duration::from_secs(retry_secs)).await;
+        retry_secs = (retry_secs * 2).min(MAX_RETRY_SECS);
+    }
+}
+
+// Running pullers, one per registered instance
+#[derive(Default)]
+pub struct Hub {
+    tasks: HashMap<String, tokio::task::JoinHandle<()>>,
+    statuses: StatusMap,
+}
+
+impl Hub {
+    pub fn start(&mut self, instance: RemoteInstance, events: Arc<Mutex<EventStore>>) {
+        self.stop(&instance.name);
+        if let Ok(mut statuses) = self.statuses.lock() {
+            statuses.insert(
+                instance.name.clone(),
+                InstanceStatus {
+                    name: instance.name.clone(),
+                    url: instance.url.clone(),
+                    ..Default::default()
+                },
+            );
+        }
+        let name = instance.name.clone();
+        let task = tokio::spawn(run_instance(instance, events, self.statuses.clone()));
+        self.tasks.insert(name, task);
+    }
+
+    pub fn stop(&mut self, name: &str) -> bool {
+        if let Ok(mut statuses) = self.statuses.lock() {
+            statuses.remove(name);
+        }
+        match self.tasks.remove(name) {
+            Some(task) => {
+                task.abort();
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn stop_all(&mut self) {
+        let names: Vec<String> = self.tasks.keys().cloned().collect();
+        for name in names {
+            self.stop(&name);
+        }
+    }
+
+    pub fn statuses(&self) -> Vec<InstanceStatus> {
+        self.statuses.lock().map(|s| s.values().cloned().collect()).unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_instance_urls() {
+        let instance = RemoteInstance {
+            name: "store-12".to_string(),
+            url: "https://store-12.local:8787".to_string(),
+            api_key: "k3y".to_string(),
+        };
+        assert!(instance.validate().is_ok());
+        assert_eq!(instance.stream_url().unwrap(), "wss://store-12.local:8787/ws?api_key=k3y");
+        assert_eq!(instance.endpoint("/events").unwrap().as_str(), "https://store-12.local:8787/events");
+
+        assert!(RemoteInstance { name: "local".to_string(), ..instance.clone() }.validate().is_err());
+        assert!(RemoteInstance { url: "ftp://store-12".to_string(), ..instance }.validate().is_err());
+    }
+}
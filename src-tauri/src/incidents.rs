@@ -0,0 +1,189 @@
+// Incidents Module - Alerts about the same thing grouped into one incident with a lifecycle
+// A spill seen on every frame is one incident: opened by its first alert, updated by the rest, resolved when it clears or goes quiet
+
+use crate::event_store::{format_timestamp, EventStore};
+use crate::settings;
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+// Longest an operator can silence an incident's alerts for
+pub const MAX_SNOOZE_MINUTES: u32 = 24 * 60;
+
+// Payload fields naming what an alert is about, most specific first; alerts of one kind on one camera
+// with the same subject belong to the same incident
+const SUBJECT_FIELDS: [&str; 8] = ["hazard_type", "rule", "plate", "zone", "scene_type", "stage", "script", "plugin"];
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum IncidentState {
+    Open,
+    Acknowledged,
+    Resolved,
+}
+
+impl IncidentState {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            IncidentState::Open => "open",
+            IncidentState::Acknowledged => "acknowledged",
+            IncidentState::Resolved => "resolved",
+        }
+    }
+
+    pub fn parse(state: &str) -> Result<Self, String> {
+        match state {
+            "open" => Ok(IncidentState::Open),
+            "acknowledged" => Ok(IncidentState::Acknowledged),
+            "resolved" => Ok(IncidentState::Resolved),
+            _ => Err(format!("Unknown incident state '{}' (use open, acknowledged or resolved)", state)),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Incident {
+    pub id: String,
+    pub camera_id: Option<String>,
+    pub kind: String,  // The alerts' payload.kind, e.g. "fire" or "safety"
+    pub subject: Option<String>,
+    pub priority: Option<String>,
+    pub description: String,  // From the latest alert
+    pub state: IncidentState,
+    pub opened_at: String,
+    pub updated_at: String,  // Last alert
+    pub acknowledged_at: Option<String>,
+    pub acknowledged_by: Option<String>,
+    pub resolved_at: Option<String>,
+    pub resolution: Option<String>,  // Why it was resolved: "cleared", "quiet" or an operator's note
+    pub alert_count: u32,
+    pub last_alert_id: Option<String>,
+    pub assigned_to: Option<String>,
+    pub snoozed_until: Option<String>,  // Alert channels stay quiet until then, even while it is open
+    pub duration_secs: u64,  // Until resolved, or until now while it is still going
+}
+
+// One thing an operator did to an incident, for the handover log
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct IncidentActivity {
+    pub timestamp: String,
+    pub actor: Option<String>,
+    pub action: String,  // "acknowledged", "snoozed", "assigned", "note" or "resolved"
+    pub detail: Option<String>,
+}
+
+// An incident with the alerts filed under it and who handled it
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct IncidentDetail {
+    #[serde(flatten)]
+    pub incident: Incident,
+    pub alert_ids: Vec<String>,
+    pub activity: Vec<IncidentActivity>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct IncidentFilter {
+    pub state: Option<String>,
+    pub camera_id: Option<String>,
+    pub kind: Option<String>,
+    pub limit: Option<usize>,
+}
+
+// Response times per kind, for incidents opened in a period
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct IncidentMetrics {
+    pub kind: String,
+    pub opened: u32,
+    pub resolved: u32,
+    pub still_open: u32,
+    pub avg_secs_to_acknowledge: Option<f64>,
+    pub avg_duration_secs: Option<f64>,  // Resolved incidents only
+    pub max_duration_secs: Option<f64>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct IncidentSettings {
+    pub resolve_after_secs: u64,  // An incident with no new alert for this long resolves itself
+}
+
+impl Default for IncidentSettings {
+    fn default() -> Self {
+        IncidentSettings { resolve_after_secs: 300 }
+    }
+}
+
+impl IncidentSettings {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.resolve_after_secs < 30 {
+            return Err("Incidents must stay open for at least 30 seconds without alerts".to_string());
+        }
+        Ok(())
+    }
+}
+
+pub fn alert_kind(payload: &serde_json::Value) -> String {
+    payload["kind"].as_str().unwrap_or("alert").to_string()
+}
+
+pub fn subject(payload: &serde_json::Value) -> Option<String> {
+    SUBJECT_FIELDS.iter().find_map(|field| payload[*field].as_str()).map(str::to_string)
+}
+
+pub fn duration_secs(opened_at: &str, resolved_at: Option<&str>, now: DateTime<Utc>) -> u64 {
+    let parse = |time: &str| DateTime::parse_from_rfc3339(time).ok().map(|t| t.with_timezone(&Utc));
+    match parse(opened_at) {
+        Some(opened) => (resolved_at.and_then(parse).unwrap_or(now) - opened).num_seconds().max(0) as u64,
+        None => 0,
+    }
+}
+
+// Alert channels announce an incident's alerts until someone acknowledges it, and not while it is snoozed
+pub fn should_notify(incident: &Incident, now: DateTime<Utc>) -> bool {
+    let snoozed = incident.snoozed_until.as_deref().is_some_and(|until| until > format_timestamp(now).as_str());
+    incident.state == IncidentState::Open && !snoozed
+}
+
+// Updates older than this make an incident stale
+pub fn quiet_cutoff(config: &IncidentSettings, now: DateTime<Utc>) -> String {
+    format_timestamp(now - Duration::seconds(config.resolve_after_secs as i64))
+}
+
+// Background task: resolve incidents whose alerts stopped
+pub async fn run_sweeper(events: Arc<Mutex<EventStore>>) {
+    loop {
+        tokio::time::sleep(std::time::Duration::from_secs(30)).await;
+        let config = settings::current().incidents;
+        let now = Utc::now();
+        match events.lock().await.resolve_quiet_incidents(&quiet_cutoff(&config, now), now) {
+            Ok(resolved) => {
+                for incident in resolved {
+                    println!("✅ Incident resolved after {}s without alerts: {}", config.resolve_after_secs, incident.description);
+                }
+            }
+            Err(e) => eprintln!("Incident sweep failed: {}", e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_subject_and_kind() {
+        let spill = serde_json::json!({ "kind": "safety", "hazard_type": "spill", "scene_type": "safety" });
+        assert_eq!((alert_kind(&spill), subject(&spill)), ("safety".to_string(), Some("spill".to_string())));
+        assert_eq!((alert_kind(&serde_json::json!({})), subject(&serde_json::json!({}))), ("alert".to_string(), None));
+    }
+
+    #[test]
+    fn test_duration() {
+        let now = DateTime::parse_from_rfc3339("2026-01-01T00:10:00.000Z").unwrap().with_timezone(&Utc);
+        assert_eq!(duration_secs("2026-01-01T00:00:00.000Z", None, now), 600);
+        assert_eq!(duration_secs("2026-01-01T00:00:00.000Z", Some("2026-01-01T00:01:30.000Z"), now), 90);
+        assert_eq!(IncidentState::parse("acknowledged"), Ok(IncidentState::Acknowledged));
+        assert!(IncidentState::parse("closed").is_err());
+    }
+}
@@ -0,0 +1,166 @@
+// Intake Module - Bounded queue between live frame submission and detection
+// When detection falls behind, the oldest waiting frame is dropped so memory stays flat and results stay current
+
+use crate::yolo_detector::DetectionData;
+use crate::AppState;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tokio::sync::{oneshot, Notify};
+
+pub const DEFAULT_CAPACITY: usize = 4;
+
+// Effective FPS is measured over recently finished frames
+const FPS_WINDOW: Duration = Duration::from_secs(10);
+
+type Reply = oneshot::Sender<Result<DetectionData, String>>;
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct PipelineStats {
+    pub submitted: u64,
+    pub processed: u64,
+    pub dropped: u64,
+    pub queue_depth: usize,
+    pub capacity: usize,
+    pub effective_fps: f64,
+}
+
+#[derive(Default)]
+struct Queue {
+    pending: VecDeque<(String, Reply)>,
+    submitted: u64,
+    processed: u64,
+    dropped: u64,
+    finished: VecDeque<Instant>,
+}
+
+pub struct FrameIntake {
+    capacity: usize,
+    queue: Mutex<Queue>,
+    ready: Notify,
+}
+
+impl Default for FrameIntake {
+    fn default() -> Self {
+        FrameIntake::new(DEFAULT_CAPACITY)
+    }
+}
+
+impl FrameIntake {
+    pub fn new(capacity: usize) -> Self {
+        FrameIntake {
+            capacity: capacity.max(1),
+            queue: Mutex::new(Queue::default()),
+            ready: Notify::new(),
+        }
+    }
+
+    // Queue a frame; the receiver gets its detection, or an error if it was dropped
+    pub fn submit(&self, frame: String) -> oneshot::Receiver<Result<DetectionData, String>> {
+        let (reply, receiver) = oneshot::channel();
+        let dropped = {
+            let mut queue = self.queue.lock().unwrap_or_else(|e| e.into_inner());
+            queue.submitted += 1;
+            queue.pending.push_back((frame, reply));
+            if queue.pending.len() > self.capacity {
+                queue.dropped += 1;
+                queue.pending.pop_front()
+            } else {
+                None
+            }
+        };
+        if let Some((_, oldest)) = dropped {
+            let _ = oldest.send(Err("Frame dropped: detection is behind".to_string()));
+        }
+        self.ready.notify_one();
+        receiver
+    }
+
+    // Fail every waiting frame, e.g. when analysis is paused; returns how many were waiting
+    pub fn cancel_pending(&self, reason: &str) -> usize {
+        let pending: Vec<_> = self.queue.lock().unwrap_or_else(|e| e.into_inner()).pending.drain(..).collect();
+        let cancelled = pending.len();
+        for (_, reply) in pending {
+            let _ = reply.send(Err(reason.to_string()));
+        }
+        cancelled
+    }
+
+    async fn next(&self) -> (String, Reply) {
+        loop {
+            if let Some(item) = self.queue.lock().unwrap_or_else(|e| e.into_inner()).pending.pop_front() {
+                return item;
+            }
+            self.ready.notified().await;
+        }
+    }
+
+    fn finished(&self, at: Instant) {
+        let mut queue = self.queue.lock().unwrap_or_else(|e| e.into_inner());
+        queue.processed += 1;
+        queue.finished.push_back(at);
+        while queue.finished.front().is_some_and(|t| at.duration_since(*t) > FPS_WINDOW) {
+            queue.finished.pop_front();
+        }
+    }
+
+    pub fn stats(&self, now: Instant) -> PipelineStats {
+        let queue = self.queue.lock().unwrap_or_else(|e| e.into_inner());
+        let recent = queue.finished.iter().filter(|t| now.duration_since(**t) <= FPS_WINDOW).count();
+        PipelineStats {
+            submitted: queue.submitted,
+            processed: queue.processed,
+            dropped: queue.dropped,
+            queue_depth: queue.pending.len(),
+            capacity: self.capacity,
+            effective_fps: recent as f64 / FPS_WINDOW.as_secs_f64(),
+        }
+    }
+}
+
+// Single detection worker: frames are handled in order, newest ones winning when the queue overflows
+pub async fn run(state: AppState) {
+    loop {
+        let (frame, reply) = state.intake.next().await;
+        // Nobody is waiting for this one any more
+        if reply.is_closed() {
+            continue;
+        }
+        if let Err(e) = crate::pause::check() {
+            let _ = reply.send(Err(e));
+            continue;
+        }
+        let result = crate::detect_live_frame(&state, frame).await;
+        state.intake.finished(Instant::now());
+        let _ = reply.send(result);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_overflow_drops_oldest_frames() {
+        let intake = FrameIntake::new(2);
+        let first = intake.submit("a".to_string());
+        let second = intake.submit("b".to_string());
+        let _third = intake.submit("c".to_string());
+        let _fourth = intake.submit("d".to_string());
+
+        assert!(first.await.unwrap().unwrap_err().contains("dropped"));
+        assert!(second.await.unwrap().is_err());
+        let (frame, _) = intake.next().await;
+        assert_eq!(frame, "c");
+        let waiting = intake.submit("e".to_string());
+        assert_eq!(intake.cancel_pending("paused"), 2);
+        assert_eq!(waiting.await.unwrap().unwrap_err(), "paused");
+
+        let now = Instant::now();
+        intake.finished(now);
+        let stats = intake.stats(now);
+        assert_eq!((stats.submitted, stats.processed, stats.dropped, stats.queue_depth), (5, 1, 2, 0));
+        assert!(stats.effective_fps > 0.0);
+    }
+}
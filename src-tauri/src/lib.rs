@@ -1,20 +1,276 @@
 mod ollama_manager;
+mod ollama_runtime;
 mod yolo_detector;
 mod moondream_manager;
+mod fusion;
+mod scene_prompts;
+mod retail_scenes;
+mod incidents;
+mod alerting;
+mod tts;
+mod desktop_notify;
+mod chime;
+mod push;
+mod verification;
+mod suppression;
+mod zones;
+mod zone_suggest;
+mod preview;
+mod frame_processor;
+mod vision_chat;
+mod event_store;
+mod migrations;
+mod retention;
+mod audit;
+mod encryption;
+mod network;
+mod offline;
+mod downloads;
+mod app_status;
+mod history_query;
+mod semantic_search;
+mod daily_report;
+mod metrics;
+mod benchmark;
+mod ab_testing;
+mod batch;
+mod video_source;
+mod screen_capture;
+mod vision_provider;
+mod openai_provider;
+mod claude_provider;
+mod gemini_provider;
+mod local_provider;
+mod local_inference;
+mod settings;
+mod hardware;
+mod warmup;
+mod storage;
+mod privacy;
+mod local_only;
+mod export;
+mod dataset;
+mod evaluation;
+mod mock;
+mod pipeline;
+pub mod cli;
+mod api_server;
+mod api_keys;
+mod event_stream;
+mod grpc_server;
+mod frigate_mqtt;
+mod trigger;
+mod object_sync;
+mod hub;
+mod smoothing;
+mod reid;
+mod staff;
+mod ppe;
+mod fire;
+mod anpr;
+mod barcode;
+mod ocr;
+mod enhance;
+mod dewarp;
+mod floor;
+mod motion;
+mod rules;
+mod scripting;
+mod plugins;
+mod bus;
+mod intake;
+mod pacing;
+mod power;
+mod sysmon;
+mod slo;
+mod pause;
+mod diagnostics;
+mod setup;
+mod config;
+mod backup;
 
 use ollama_manager::{OllamaManager, OllamaStatus};
-use yolo_detector::{YoloDetector, DetectionData};
+use ollama_runtime::{RuntimeInfo, UpdateCheck};
+use yolo_detector::{YoloDetector, DetectionData, BoundingBox};
 use moondream_manager::{MoondreamManager, AnalysisResult};
+use frame_processor::RegionOfInterest;
+use vision_chat::{VisionChatManager, ChatReply, ChatSessionSummary, ChatTurn};
+use event_store::{DbInfo, EventStore, EventFilter, StoredEvent, EventFeedback, FeedbackStats, ModelPerformance, PlateRead, PpeCompliance};
+use history_query::HistoryAnswer;
+use semantic_search::SearchHit;
+use daily_report::DailyReport;
+use metrics::Metrics;
+use benchmark::BenchmarkReport;
+use evaluation::EvaluationReport;
+use api_server::ApiServerInfo;
+use api_keys::{ApiKey, CreatedApiKey, Role};
+use frigate_mqtt::MqttSettings;
+use trigger::{TriggerRequest, TriggerResult};
+use object_sync::{ObjectStorageSettings, SyncReport};
+use hub::{Hub, InstanceStatus, RemoteInstance};
+use smoothing::{CountSmoother, SmoothingSettings, Smoothers};
+use reid::{ReidSettings, Reidentifiers};
+use staff::{StaffSettings, StaffTracks};
+use ppe::{PpeMonitor, PpeSettings};
+use fire::{FireSettings, FireWatch};
+use anpr::{AnprMonitor, AnprSettings};
+use barcode::ScannedCode;
+use ocr::OcrResult;
+use enhance::LowLightSettings;
+use dewarp::FisheyeCalibration;
+use floor::{FloorCalibration, FloorTrackers};
+use motion::MotionTrackers;
+use rules::{Rule, RuleState};
+use scripting::{Script, ScriptHost, ScriptOutcome};
+use plugins::{PluginConfig, PluginHost, PluginStatus};
+use bus::{BusEvent, EventBus};
+use intake::{FrameIntake, PipelineStats};
+use pacing::{FramePacing, FrameRates, PacingSettings};
+use power::{PowerPolicy, PowerStatus};
+use sysmon::SystemStats;
+use slo::{SloMonitor, SloSettings, SloStatus};
+use retention::{RetentionReport, RetentionSettings};
+use audit::{AuditAction, AuditEntry, AuditFilter};
+use encryption::EncryptionStatus;
+use network::{ConnectivityCheck, NetworkSettings};
+use offline::OfflineStatus;
+use downloads::DownloadStatus;
+use app_status::AppStatus;
+use pause::AnalysisState;
+use diagnostics::DiagnosticsReport;
+use setup::{SetupChoices, SetupPlan, SetupTest};
+use config::{ConfigExport, ConfigProfile};
+use backup::{BackupManifest, BackupSummary};
+use ab_testing::{AbStats, AbTestRecord, ProviderOutcome};
+use batch::{BatchItemResult, BatchProgress, BatchSummary};
+use vision_provider::{ProviderInfo, ProviderRegistry, ProviderSettings, TokenUsage, VisionProvider, VisionRequest};
+use local_inference::{LocalInferenceProvider, LocalInferenceStatus};
+use settings::{AppSettings, InferenceProfile};
+use hardware::{HardwareInfo, HardwareRecommendation};
+use warmup::BackendReady;
+use storage::{ModelUsage, StorageUsage};
+use privacy::{FaceBox, PrivacyMask, RedactionPolicy};
+use local_only::LocalOnlyStatus;
+use export::{ExportFormat, ExportRange, ExportSummary};
+use dataset::{DatasetFormat, DatasetSample, DatasetSummary};
+use screen_capture::{CaptureSourceInfo, CaptureTarget};
+use video_source::{VideoAnalysisOptions, VideoAnalysisSummary, VideoEvent, VideoProgress};
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
-use tauri::{Manager, State};
-use tokio::sync::Mutex;
+use futures_util::StreamExt;
+use tauri::{Emitter, Manager, State};
+use tokio::sync::{Mutex, RwLock};
 
 #[derive(Clone)]
 struct AppState {
     ollama: Arc<Mutex<OllamaManager>>,
-    yolo: Arc<Mutex<YoloDetector>>,
-    moondream: Arc<Mutex<MoondreamManager>>,
+    yolo: Arc<YoloDetector>,
+    moondream: Arc<MoondreamManager>,
+    chat: Arc<Mutex<VisionChatManager>>,
+    events: Arc<Mutex<EventStore>>,
+    metrics: Arc<Metrics>,
+    metrics_server: Arc<Mutex<Option<tauri::async_runtime::JoinHandle<()>>>>,
+    api_server: Arc<Mutex<Option<tauri::async_runtime::JoinHandle<()>>>>,
+    grpc_server: Arc<Mutex<Option<tauri::async_runtime::JoinHandle<()>>>>,
+    mqtt_publisher: Arc<Mutex<Option<tauri::async_runtime::JoinHandle<()>>>>,
+    background_tasks: Arc<std::sync::Mutex<Vec<tauri::async_runtime::JoinHandle<()>>>>,
+    hub: Arc<Mutex<Hub>>,
+    smoothers: Arc<Mutex<Smoothers>>,
+    reidentifiers: Arc<Mutex<Reidentifiers>>,
+    floor: Arc<Mutex<FloorTrackers>>,
+    motion: Arc<Mutex<MotionTrackers>>,
+    staff: Arc<Mutex<StaffTracks>>,
+    ppe: Arc<Mutex<PpeMonitor>>,
+    fire: Arc<Mutex<FireWatch>>,
+    anpr: Arc<Mutex<AnprMonitor>>,
+    rules: Arc<Mutex<RuleState>>,
+    scripts: Arc<Mutex<ScriptHost>>,
+    plugins: Arc<Mutex<PluginHost>>,
+    bus: EventBus,
+    intake: Arc<FrameIntake>,
+    pacing: Arc<Mutex<FrameRates>>,
+    slo: Arc<Mutex<SloMonitor>>,
+    providers: Arc<RwLock<ProviderRegistry>>,
+    backend_ready: Arc<Mutex<Option<BackendReady>>>,
+}
+
+// Per-frame analytics shared by the live detection paths (Tauri command, REST and gRPC)
+async fn run_live_analytics(state: &AppState, camera_id: Option<&str>, frame_base64: &str, detection: &mut DetectionData) {
+    // Before zones drop anyone, so redaction knows about every person in the frame
+    privacy::remember_people(detection.boxes.iter().filter(|b| b.class_name == "person").count());
+    zones::apply(&settings::current().zones, camera_id, detection, chrono::Local::now().time());
+    state.smoothers.lock().await.apply(camera_id, detection);
+    state.reidentifiers.lock().await.apply(camera_id, frame_base64, detection);
+    state.floor.lock().await.apply(camera_id, detection);
+    state.motion.lock().await.apply(camera_id, detection);
+    staff::tag(state, camera_id, frame_base64, detection).await;
+    ppe::watch(state, camera_id, frame_base64, detection).await;
+    verification::remember_frame(camera_id, frame_base64);
+    preview::remember_frame(camera_id, frame_base64);
+    fire::watch(state, camera_id, frame_base64).await;
+    anpr::watch(state, camera_id, frame_base64, detection).await;
+    rules::evaluate(state, camera_id, frame_base64, detection).await;
+    scripting::on_detection(state, camera_id, detection).await;
+    state.plugins.lock().await.transform(detection);
+}
+
+// Persist an analysis result; storage problems are logged but never fail the analysis
+async fn store_analysis(
+    state: &AppState,
+    provider: &str,
+    prompt: &str,
+    description: &str,
+    payload: Option<serde_json::Value>,
+) -> Option<String> {
+    store_camera_analysis(state, None, provider, prompt, description, payload).await
+}
+
+async fn store_camera_analysis(
+    state: &AppState,
+    camera_id: Option<&str>,
+    provider: &str,
+    prompt: &str,
+    description: &str,
+    mut payload: Option<serde_json::Value>,
+) -> Option<String> {
+    scripting::on_analysis(state, camera_id, provider, description, &mut payload).await;
+    match state.events.lock().await.record_analysis(camera_id, provider, prompt, description, payload) {
+        Ok(id) => {
+            // Embed the description in the background so search stays up to date
+            if !description.is_empty() {
+                let events = state.events.clone();
+                let (event_id, text) = (id.clone(), description.to_string());
+                tauri::async_runtime::spawn(async move {
+                    match OllamaManager::embed(semantic_search::EMBEDDING_MODEL, vec![text]).await {
+                        Ok(vectors) => {
+                            if let Some(vector) = vectors.first() {
+                                if let Err(e) = events.lock().await.store_embedding(&event_id, semantic_search::EMBEDDING_MODEL, vector) {
+                                    eprintln!("Failed to store embedding: {}", e);
+                                }
+                            }
+                        }
+                        Err(e) => eprintln!("Failed to embed description: {}", e),
+                    }
+                });
+            }
+            Some(id)
+        }
+        Err(e) => {
+            eprintln!("Failed to store analysis: {}", e);
+            app_status::record_error("storage", &e);
+            None
+        }
+    }
+}
+
+// Token counts and timings next to a stored analysis, when the backend reported them
+async fn store_usage(state: &AppState, event_id: Option<&str>, provider: &str, model: &str, usage: Option<&TokenUsage>) {
+    let (Some(event_id), Some(usage)) = (event_id, usage) else {
+        return;
+    };
+    if let Err(e) = state.events.lock().await.record_usage(event_id, provider, model, usage) {
+        eprintln!("{}", e);
+    }
 }
 
 #[derive(Serialize, Deserialize)]
@@ -34,9 +290,10 @@ async fn start_ollama(state: State<'_, AppState>) -> Result<String, String> {
     let mut ollama = state.ollama.lock().await;
     ollama.start().await?;
 
-    // Pull the vision model
-    println!("Pulling vision model...");
-    ollama.pull_model("llava:7b").await?;
+    // Pull the selected vision model
+    let model = settings::current().vision_model;
+    println!("Pulling vision model {}...", model);
+    ollama.pull_model(&model).await?;
 
     Ok("Ollama started and model ready".to_string())
 }
@@ -52,175 +309,2760 @@ async fn check_ollama_status(_state: State<'_, AppState>) -> Result<OllamaStatus
     Ok(status)
 }
 
+// Installed embedded Ollama version against the newest known-good release
 #[tauri::command]
-async fn analyze_image(
-    _state: State<'_, AppState>,
-    request: AnalyzeRequest,
-) -> Result<AnalyzeResponse, String> {
-    println!("analyze_image called!");
-    println!("Image base64 length: {}", request.image_base64.len());
-    println!("Prompt: {:?}", request.prompt);
+async fn check_ollama_update(state: State<'_, AppState>) -> Result<UpdateCheck, String> {
+    let data_dir = state.ollama.lock().await.data_dir().to_path_buf();
+    Ok(ollama_runtime::check_for_update(&data_dir).await)
+}
 
-    // Check if Ollama is running
+// Upgrades to `version`, or the newest known-good release; the download is checked against its published checksum
+#[tauri::command]
+async fn upgrade_ollama(state: State<'_, AppState>, version: Option<String>) -> Result<RuntimeInfo, String> {
+    state.ollama.lock().await.upgrade(version.as_deref()).await
+}
+
+#[derive(Serialize)]
+struct ModelSettings {
+    vision_model: String,
+    variants: Vec<String>,
+    installed: bool,
+}
+
+#[tauri::command]
+async fn get_model_settings() -> Result<ModelSettings, String> {
     let status = OllamaManager::check_status().await;
-    println!("Ollama status: running={}, model_ready={}", status.running, status.model_ready);
+    Ok(ModelSettings {
+        vision_model: status.model,
+        variants: settings::VISION_MODEL_VARIANTS.iter().map(|m| m.to_string()).collect(),
+        installed: status.model_ready,
+    })
+}
 
-    if !status.running || !status.model_ready {
-        println!("Ollama not ready, returning error");
-        return Ok(AnalyzeResponse {
-            description: String::new(),
-            error: Some("Ollama not ready".to_string()),
-        });
+// Switch the LLaVA variant; run start_ollama afterwards to pull it if needed
+#[tauri::command]
+async fn set_vision_model(model: String) -> Result<AppSettings, String> {
+    let model = settings::validate_vision_model(&model)?;
+    println!("🦙 Switching vision model to {}", model);
+    settings::update(|s| s.vision_model = model)
+}
+
+#[derive(Serialize)]
+struct HardwareReport {
+    hardware: HardwareInfo,
+    recommendation: HardwareRecommendation,
+    applied: bool,
+}
+
+// Inspect the machine and optionally switch to the recommended model and Ollama options
+#[tauri::command]
+async fn detect_hardware(apply: Option<bool>) -> Result<HardwareReport, String> {
+    let hardware = hardware::detect().await;
+    let recommendation = hardware::recommend(&hardware);
+    println!("🖥️ Hardware: {} -> {}", recommendation.reason, recommendation.vision_model);
+
+    let applied = apply.unwrap_or(false);
+    if applied {
+        let vision_model = recommendation.vision_model.clone();
+        let tuning = recommendation.tuning.clone();
+        settings::update(|s| {
+            s.vision_model = vision_model;
+            s.tuning = tuning;
+        })?;
     }
 
-    // Make request to Ollama API with timeout
-    let client = reqwest::Client::builder()
-        .timeout(std::time::Duration::from_secs(30))
-        .build()
-        .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
+    Ok(HardwareReport {
+        hardware,
+        recommendation,
+        applied,
+    })
+}
 
-    let prompt = request.prompt.unwrap_or_else(||
-        "Describe what you see in this image in 2-3 sentences. Focus on the main subjects and activities.".to_string()
-    );
+// First-run wizard: hardware, model options with sizes and what still has to be downloaded for `model` (default: the recommended one)
+#[tauri::command]
+async fn get_setup_plan(state: State<'_, AppState>, model: Option<String>) -> Result<SetupPlan, String> {
+    let model = model.map(|m| settings::validate_vision_model(&m)).transpose()?;
+    let installed = setup::Installed {
+        ollama: OllamaManager::binary_path(&storage::data_dir().join("ollama")).exists() || OllamaManager::check_status().await.running,
+        models: OllamaManager::installed_models().await.unwrap_or_default().into_iter().map(|(name, _)| name).collect(),
+    };
+    let providers = setup::provider_names(&*state.providers.read().await);
+    Ok(setup::plan(
+        hardware::detect().await,
+        model.as_deref(),
+        &installed,
+        storage::free_space_mb(&storage::data_dir()).await,
+        providers,
+    ))
+}
 
-    println!("Sending request to Ollama API...");
-    let json_payload = serde_json::json!({
-        "model": "llava:7b",
-        "prompt": prompt,
-        "images": [request.image_base64],
-        "stream": false
-    });
+// Detection plus one analysis with the chosen provider, on a camera frame or a generated test image
+#[tauri::command]
+async fn test_setup_frame(state: State<'_, AppState>, provider: String, frame_base64: Option<String>) -> Result<SetupTest, String> {
+    let frame = match frame_base64 {
+        Some(frame) => frame_processor::prepare_frame(frame, None)?,
+        None => setup::sample_frame()?,
+    };
+    let test = setup::test_frame(&state.yolo, &state.moondream, &state.providers, &provider, frame).await;
+    println!("🧪 Setup test with {}: detection {}, analysis {}", provider, test.detection_ok, test.analysis_ok);
+    Ok(test)
+}
 
-    let response = client
-        .post("http://127.0.0.1:11434/api/generate")
-        .json(&json_payload)
-        .send()
+// Validate and save every wizard choice in a single settings write
+#[tauri::command]
+async fn complete_setup(state: State<'_, AppState>, choices: SetupChoices) -> Result<AppSettings, String> {
+    let providers = setup::provider_names(&*state.providers.read().await);
+    let mut updated = settings::current();
+    choices.apply(&mut updated, &providers, &event_store::format_timestamp(chrono::Utc::now()))?;
+    let saved = settings::update(|s| *s = updated)?;
+    println!("✅ Setup complete: {} via {}", saved.vision_model, saved.default_provider);
+    Ok(saved)
+}
+
+// Whole configuration without credentials, for moving it to another machine
+#[tauri::command]
+async fn export_config() -> Result<ConfigExport, String> {
+    audit::record(AuditEntry::new(AuditAction::Export, "Configuration without credentials", "app"));
+    Ok(config::export(&settings::current()))
+}
+
+// Replace the configuration with an export (or a bare settings.json); local credentials are kept
+#[tauri::command]
+async fn import_config(state: State<'_, AppState>, json: String) -> Result<AppSettings, String> {
+    let imported = config::import(&json, &settings::current())?;
+    validate_config_scripts(&state, &imported).await?;
+    let saved = settings::update(|s| *s = imported)?;
+    println!("📥 Configuration imported ({} configuration profiles)", saved.config_profiles.len());
+    reload_configuration(&state, &saved).await;
+    Ok(saved)
+}
+
+async fn validate_config_scripts(state: &AppState, settings: &AppSettings) -> Result<(), String> {
+    let host = state.scripts.lock().await;
+    scripting::validate(&host, &settings.scripts)?;
+    for (name, profile) in &settings.config_profiles {
+        scripting::validate(&host, &profile.scripts).map_err(|e| format!("Configuration profile '{}': {}", name, e))?;
+    }
+    Ok(())
+}
+
+// One archive with the event database, configuration and optionally snapshots
+#[tauri::command]
+async fn backup_data(state: State<'_, AppState>, path: String, include_snapshots: Option<bool>) -> Result<BackupSummary, String> {
+    let staging = tempfile::tempdir().map_err(|e| format!("Failed to create temp directory: {}", e))?;
+    let database = staging.path().join(backup::DATABASE);
+    state.events.lock().await.backup_to(&database)?;
+    let snapshots = include_snapshots.unwrap_or(false).then(dataset::snapshots_dir);
+    let target = path.clone();
+    let result = tokio::task::spawn_blocking(move || {
+        backup::write_archive(std::path::Path::new(&path), &database, &storage::data_dir(), snapshots.as_deref())
+    })
+    .await
+    .map_err(|e| e.to_string())?;
+    let data = if include_snapshots == Some(true) { "Event database, configuration and snapshots" } else { "Event database and configuration" };
+    let mut entry = AuditEntry::new(AuditAction::Export, data, target).outcome(&result);
+    if let Ok(summary) = &result {
+        entry = entry.bytes(summary.size_bytes);
+    }
+    audit::record(entry);
+    let summary = result?;
+    println!("💾 Backup written to {} ({} bytes, {} snapshots)", summary.path, summary.size_bytes, summary.manifest.snapshots);
+    Ok(summary)
+}
+
+// Replace the event history and configuration with a backup; the archive is fully checked before anything is overwritten
+#[tauri::command]
+async fn restore_data(state: State<'_, AppState>, path: String) -> Result<BackupManifest, String> {
+    let staging = tempfile::tempdir().map_err(|e| format!("Failed to create temp directory: {}", e))?;
+    let dir = staging.path().to_path_buf();
+    let manifest = tokio::task::spawn_blocking(move || backup::extract(std::path::Path::new(&path), &dir))
         .await
-        .map_err(|e| {
-            println!("Failed to send request to Ollama: {}", e);
-            format!("Failed to analyze image: {}", e)
-        })?;
+        .map_err(|e| e.to_string())??;
+    let restored = match std::fs::read_to_string(staging.path().join("settings.json")) {
+        Ok(json) => Some(config::import(&json, &settings::current())?),
+        Err(_) => None,
+    };
+    if let Some(settings) = &restored {
+        validate_config_scripts(&state, settings).await?;
+    }
 
-    println!("Ollama API response status: {}", response.status());
+    state.events.lock().await.restore_from(&staging.path().join(backup::DATABASE))?;
+    backup::install(staging.path(), &storage::data_dir(), &dataset::snapshots_dir())?;
+    state.providers.write().await.reload();
+    if let Some(settings) = restored {
+        let saved = settings::update(|s| *s = settings)?;
+        // The restored database may be sealed with the backup's key
+        encryption::lock();
+        encryption::unlock_at_startup(&saved.encryption);
+        reload_configuration(&state, &saved).await;
+    }
+    println!("♻️ Restored backup from {} ({} snapshots)", manifest.created_at, manifest.snapshots);
+    Ok(manifest)
+}
 
-    if !response.status().is_success() {
-        let status_text = response.status().to_string();
-        let error_text = response.text().await.unwrap_or_default();
-        println!("Analysis failed with status {}: {}", status_text, error_text);
-        return Ok(AnalyzeResponse {
-            description: String::new(),
-            error: Some(format!("Analysis failed: {} - {}", status_text, error_text)),
-        });
+// What set_rules and set_plugins do after saving, for changes that replace both at once
+async fn reload_configuration(state: &AppState, settings: &AppSettings) {
+    state.rules.lock().await.reset_timers();
+    state.plugins.lock().await.load(&settings.plugins);
+}
+
+#[derive(Serialize)]
+struct ConfigProfiles {
+    active: Option<String>,
+    profiles: std::collections::BTreeMap<String, ConfigProfile>,
+}
+
+#[tauri::command]
+async fn list_config_profiles() -> Result<ConfigProfiles, String> {
+    let settings = settings::current();
+    Ok(ConfigProfiles {
+        active: settings.active_config,
+        profiles: settings.config_profiles,
+    })
+}
+
+// Save the current zones, rules and model choices under a name, or store a profile given in full
+#[tauri::command]
+async fn save_config_profile(state: State<'_, AppState>, name: String, profile: Option<ConfigProfile>) -> Result<ConfigProfile, String> {
+    if name.trim().is_empty() {
+        return Err("Profile name can't be empty".to_string());
+    }
+    let current = settings::current();
+    let profile = profile.unwrap_or_else(|| ConfigProfile::capture(&current));
+    let mut candidate = current;
+    candidate.config_profiles.insert(name.clone(), profile.clone());
+    config::validate(&candidate)?;
+    scripting::validate(&*state.scripts.lock().await, &profile.scripts)?;
+    println!("🗂️ Saving configuration profile '{}'", name);
+    settings::update(|s| {
+        s.config_profiles.insert(name, profile.clone());
+    })?;
+    Ok(profile)
+}
+
+// Switch zones, rules and model choices to a saved profile at runtime
+#[tauri::command]
+async fn activate_config_profile(state: State<'_, AppState>, name: String) -> Result<AppSettings, String> {
+    let current = settings::current();
+    let profile = current
+        .config_profiles
+        .get(&name)
+        .cloned()
+        .ok_or_else(|| format!("Unknown configuration profile: {}", name))?;
+    let mut switched = current;
+    profile.apply_to(&mut switched);
+    switched.active_config = Some(name.clone());
+    config::validate(&switched)?;
+    let saved = settings::update(|s| *s = switched)?;
+    println!("🗂️ Switched to configuration profile '{}'", name);
+    reload_configuration(&state, &saved).await;
+    Ok(saved)
+}
+
+#[tauri::command]
+async fn delete_config_profile(name: String) -> Result<bool, String> {
+    let current = settings::current();
+    if current.active_config.as_deref() == Some(name.as_str()) {
+        return Err(format!("'{}' is the active configuration profile - switch to another one first", name));
+    }
+    if !current.config_profiles.contains_key(&name) {
+        return Ok(false);
+    }
+    settings::update(|s| {
+        s.config_profiles.remove(&name);
+    })?;
+    Ok(true)
+}
+
+#[derive(Serialize)]
+struct InferenceProfiles {
+    active: String,
+    profiles: std::collections::BTreeMap<String, InferenceProfile>,
+}
+
+#[tauri::command]
+async fn list_inference_profiles() -> Result<InferenceProfiles, String> {
+    let settings = settings::current();
+    Ok(InferenceProfiles {
+        active: settings.active_profile,
+        profiles: settings.profiles,
+    })
+}
+
+// Create or overwrite a named profile
+#[tauri::command]
+async fn save_inference_profile(name: String, profile: InferenceProfile) -> Result<InferenceProfile, String> {
+    if name.trim().is_empty() {
+        return Err("Profile name can't be empty".to_string());
+    }
+    profile.validate()?;
+    println!("⚙️ Saving inference profile '{}'", name);
+    let saved = profile.clone();
+    settings::update(|s| {
+        s.profiles.insert(name, profile);
+    })?;
+    Ok(saved)
+}
+
+#[tauri::command]
+async fn delete_inference_profile(name: String) -> Result<bool, String> {
+    let current = settings::current();
+    if current.active_profile == name {
+        return Err(format!("'{}' is the active profile - select another one first", name));
+    }
+    if current.power.battery_profile.as_deref() == Some(name.as_str()) {
+        return Err(format!("'{}' is used on battery - change the power policy first", name));
+    }
+    if current.slo.fallback_profile.as_deref() == Some(name.as_str()) {
+        return Err(format!("'{}' is the SLO fallback profile - change the SLO settings first", name));
+    }
+    if let Some((config, _)) = current.config_profiles.iter().find(|(_, c)| c.active_profile == name) {
+        return Err(format!("'{}' is used by configuration profile '{}'", name, config));
+    }
+    if !current.profiles.contains_key(&name) {
+        return Ok(false);
+    }
+    settings::update(|s| {
+        s.profiles.remove(&name);
+    })?;
+    Ok(true)
+}
+
+// Profile used by calls that don't name one
+#[tauri::command]
+async fn set_active_profile(name: String) -> Result<(), String> {
+    settings::current().profile(Some(&name))?;
+    settings::update(|s| s.active_profile = name)?;
+    Ok(())
+}
+
+// Apply a keep-alive to every profile and to the model if it is already loaded
+#[tauri::command]
+async fn set_keep_alive(duration: String) -> Result<(), String> {
+    settings::validate_keep_alive(&duration)?;
+    println!("⏱️ Setting keep-alive to {}", duration);
+
+    let updated = settings::update(|s| {
+        for profile in s.profiles.values_mut() {
+            profile.keep_alive = duration.clone();
+        }
+    })?;
+
+    let model = settings::normalize_model_tag(&updated.vision_model);
+    let loaded = OllamaManager::loaded_models().await.unwrap_or_default();
+    if loaded.iter().any(|m| settings::normalize_model_tag(m) == model) {
+        OllamaManager::set_model_keep_alive(&model, &duration).await?;
+    }
+    Ok(())
+}
+
+#[tauri::command]
+async fn unload_model() -> Result<bool, String> {
+    OllamaManager::unload_model().await
+}
+
+// Minutes without analysis before the model is unloaded; None disables the policy
+#[tauri::command]
+async fn set_idle_unload(minutes: Option<u32>) -> Result<(), String> {
+    if minutes == Some(0) {
+        return Err("Idle timeout must be at least one minute".to_string());
+    }
+    settings::update(|s| s.idle_unload_minutes = minutes)?;
+    Ok(())
+}
+
+// Last warm-up result, for windows that missed the backend-ready event
+#[tauri::command]
+async fn get_backend_ready(state: State<'_, AppState>) -> Result<Option<BackendReady>, String> {
+    Ok(state.backend_ready.lock().await.clone())
+}
+
+// Models the app still needs: the selected vision model and the embedding model
+fn model_in_use(name: &str) -> bool {
+    let name = settings::normalize_model_tag(name);
+    [settings::current().vision_model.as_str(), semantic_search::EMBEDDING_MODEL]
+        .iter()
+        .any(|m| settings::normalize_model_tag(m) == name)
+}
+
+#[tauri::command]
+async fn get_storage_usage() -> Result<StorageUsage, String> {
+    // Ollama may be down; app directories are still worth reporting
+    let models: Vec<ModelUsage> = OllamaManager::installed_models()
+        .await
+        .unwrap_or_default()
+        .into_iter()
+        .map(|(name, size)| ModelUsage {
+            in_use: model_in_use(&name),
+            size_mb: size / (1024 * 1024),
+            name,
+        })
+        .collect();
+
+    let directories = tokio::task::spawn_blocking(storage::directory_usage)
+        .await
+        .map_err(|e| format!("Failed to measure storage: {}", e))?;
+    let total_mb = directories.iter().map(|d| d.size_mb).sum();
+
+    Ok(StorageUsage {
+        free_mb: storage::free_space_mb(&storage::data_dir()).await,
+        models,
+        directories,
+        total_mb,
+    })
+}
+
+// Delete every installed Ollama model the app no longer uses
+#[tauri::command]
+async fn cleanup_unused_models() -> Result<Vec<String>, String> {
+    let mut removed = Vec::new();
+    for (name, _) in OllamaManager::installed_models().await? {
+        if model_in_use(&name) {
+            continue;
+        }
+        OllamaManager::delete_model(&name).await?;
+        println!("🗑️ Removed unused model {}", name);
+        removed.push(name);
+    }
+    Ok(removed)
+}
+
+#[tauri::command]
+async fn get_redaction_policies() -> Result<std::collections::BTreeMap<String, RedactionPolicy>, String> {
+    Ok(settings::current().redaction_policies)
+}
+
+// "local-only", "redact" or "raw" for a remote provider such as moondream or a configured cloud endpoint
+#[tauri::command]
+async fn set_redaction_policy(provider: String, policy: RedactionPolicy) -> Result<(), String> {
+    println!("🔒 Redaction policy for {}: {:?}", provider, policy);
+    settings::update(|s| {
+        s.redaction_policies.insert(provider, policy);
+    })?;
+    Ok(())
+}
+
+#[derive(Serialize)]
+struct RedactionPreview {
+    frame_base64: String,
+    faces: Vec<FaceBox>,
+}
+
+// Show what a redacted upload would look like
+#[tauri::command]
+async fn preview_redaction(frame_base64: String) -> Result<RedactionPreview, String> {
+    let (frame_base64, faces) = tokio::task::spawn_blocking(move || privacy::redact_frame(&frame_base64))
+        .await
+        .map_err(|e| format!("Face redaction failed: {}", e))??;
+    Ok(RedactionPreview { frame_base64, faces })
+}
+
+// Decode barcodes and QR codes in a frame, e.g. shelf labels to match against detected stock
+#[tauri::command]
+async fn scan_codes(frame_base64: String) -> Result<Vec<ScannedCode>, String> {
+    tokio::task::spawn_blocking(move || barcode::scan_frame(&frame_base64))
+        .await
+        .map_err(|e| format!("Barcode scan failed: {}", e))?
+}
+
+// Read text in the frame (or a region of it) with a local model; defaults to llava through Ollama
+#[tauri::command]
+async fn extract_text(
+    state: State<'_, AppState>,
+    frame_base64: String,
+    region: Option<RegionOfInterest>,
+    provider: Option<String>,
+) -> Result<OcrResult, String> {
+    let provider = provider.unwrap_or_else(|| "llava".to_string());
+    ocr::extract_text(&state, &frame_base64, region.as_ref(), &provider).await
+}
+
+#[tauri::command]
+async fn get_smoothing_settings() -> Result<SmoothingSettings, String> {
+    Ok(settings::current().smoothing)
+}
+
+// Live cameras pick the new settings up on their next frame
+#[tauri::command]
+async fn set_smoothing_settings(smoothing: SmoothingSettings) -> Result<SmoothingSettings, String> {
+    smoothing.validate()?;
+    settings::update(|s| s.smoothing = smoothing.clone())?;
+    Ok(smoothing)
+}
+
+#[tauri::command]
+async fn get_reid_settings() -> Result<ReidSettings, String> {
+    Ok(settings::current().reid)
+}
+
+// Changing the settings starts every camera's visitor memory over
+#[tauri::command]
+async fn set_reid_settings(reid: ReidSettings) -> Result<ReidSettings, String> {
+    reid.validate()?;
+    settings::update(|s| s.reid = reid.clone())?;
+    Ok(reid)
+}
+
+#[tauri::command]
+async fn get_staff_settings() -> Result<StaffSettings, String> {
+    Ok(settings::current().staff)
+}
+
+// Roles are remembered per re-identified track, so VLM verification needs re-identification enabled
+#[tauri::command]
+async fn set_staff_settings(staff: StaffSettings) -> Result<StaffSettings, String> {
+    staff.validate()?;
+    settings::update(|s| s.staff = staff.clone())?;
+    Ok(staff)
+}
+
+#[tauri::command]
+async fn get_ppe_settings() -> Result<PpeSettings, String> {
+    Ok(settings::current().ppe)
+}
+
+#[tauri::command]
+async fn set_ppe_settings(ppe: PpeSettings) -> Result<PpeSettings, String> {
+    ppe.validate()?;
+    settings::update(|s| s.ppe = ppe.clone())?;
+    Ok(ppe)
+}
+
+#[tauri::command]
+async fn list_scene_prompts() -> Result<Vec<scene_prompts::ScenePrompt>, String> {
+    Ok(scene_prompts::list(&settings::current().scene_prompts))
+}
+
+#[tauri::command]
+async fn get_scene_prompt_versions(scene_type: String) -> Result<Vec<scene_prompts::PromptVersion>, String> {
+    Ok(scene_prompts::versions(&settings::current().scene_prompts, &scene_type))
+}
+
+// Save an edited retail scene prompt as the scene's next version
+#[tauri::command]
+async fn set_scene_prompt(scene_type: String, prompt: String) -> Result<scene_prompts::PromptVersion, String> {
+    let mut prompts = settings::current().scene_prompts;
+    let version = scene_prompts::publish(&mut prompts, &scene_type, &prompt)?;
+    settings::update(|s| s.scene_prompts = prompts)?;
+    println!("📝 Scene prompt '{}' is now v{}", scene_type, version.version);
+    Ok(version)
+}
+
+// Make an earlier version (0 for the built-in) current again
+#[tauri::command]
+async fn revert_scene_prompt(scene_type: String, version: u32) -> Result<scene_prompts::PromptVersion, String> {
+    let mut prompts = settings::current().scene_prompts;
+    let reverted = scene_prompts::revert(&mut prompts, &scene_type, version)?;
+    settings::update(|s| s.scene_prompts = prompts)?;
+    println!("📝 Scene prompt '{}' reverted to v{} as v{}", scene_type, version, reverted.version);
+    Ok(reverted)
+}
+
+// Average tokens, tokens/sec and load time per provider and model, for comparing them
+#[tauri::command]
+async fn get_model_performance(
+    state: State<'_, AppState>,
+    from: Option<String>,
+    to: Option<String>,
+) -> Result<Vec<ModelPerformance>, String> {
+    state.events.lock().await.model_performance(from.as_deref(), to.as_deref())
+}
+
+// Compliance rate per zone and shift; both bounds are stored-timestamp strings
+#[tauri::command]
+async fn get_ppe_compliance(
+    state: State<'_, AppState>,
+    from: Option<String>,
+    to: Option<String>,
+) -> Result<Vec<PpeCompliance>, String> {
+    state.events.lock().await.ppe_compliance(from.as_deref(), to.as_deref())
+}
+
+#[tauri::command]
+async fn get_fire_settings() -> Result<FireSettings, String> {
+    Ok(settings::current().fire)
+}
+
+#[tauri::command]
+async fn set_fire_settings(fire: FireSettings) -> Result<FireSettings, String> {
+    fire.validate()?;
+    settings::update(|s| s.fire = fire.clone())?;
+    Ok(fire)
+}
+
+#[tauri::command]
+async fn get_low_light_settings() -> Result<LowLightSettings, String> {
+    Ok(settings::current().low_light)
+}
+
+#[tauri::command]
+async fn set_low_light_settings(low_light: LowLightSettings) -> Result<LowLightSettings, String> {
+    low_light.validate()?;
+    settings::update(|s| s.low_light = low_light.clone())?;
+    Ok(low_light)
+}
+
+#[tauri::command]
+async fn get_fisheye_calibrations() -> Result<std::collections::BTreeMap<String, FisheyeCalibration>, String> {
+    Ok(settings::current().fisheye)
+}
+
+// Set (or with None, remove) the de-warp calibration of one camera; "" is the app's own camera
+#[tauri::command]
+async fn set_fisheye_calibration(camera_id: String, calibration: Option<FisheyeCalibration>) -> Result<(), String> {
+    if let Some(calibration) = &calibration {
+        calibration.validate()?;
+    }
+    settings::update(|s| match &calibration {
+        Some(calibration) => {
+            s.fisheye.insert(camera_id.clone(), calibration.clone());
+        }
+        None => {
+            s.fisheye.remove(&camera_id);
+        }
+    })?;
+    Ok(())
+}
+
+// De-warp one frame with a candidate calibration, for tuning it before saving
+#[tauri::command]
+async fn preview_dewarp(frame_base64: String, calibration: FisheyeCalibration) -> Result<String, String> {
+    calibration.validate()?;
+    tokio::task::spawn_blocking(move || {
+        let image = frame_processor::decode_frame(&frame_base64)?.to_rgb8();
+        frame_processor::encode_frame(&image::DynamicImage::ImageRgb8(dewarp::dewarp("preview", &calibration, &image)))
+    })
+    .await
+    .map_err(|e| format!("De-warp failed: {}", e))?
+}
+
+#[tauri::command]
+async fn get_floor_calibrations() -> Result<std::collections::BTreeMap<String, FloorCalibration>, String> {
+    Ok(settings::current().floor_calibrations)
+}
+
+// Set (or with None, remove) the floor calibration of one camera; "" is the app's own camera
+#[tauri::command]
+async fn set_floor_calibration(camera_id: String, calibration: Option<FloorCalibration>) -> Result<(), String> {
+    if let Some(calibration) = &calibration {
+        calibration.homography()?;
+    }
+    settings::update(|s| match &calibration {
+        Some(calibration) => {
+            s.floor_calibrations.insert(camera_id.clone(), calibration.clone());
+        }
+        None => {
+            s.floor_calibrations.remove(&camera_id);
+        }
+    })?;
+    Ok(())
+}
+
+// Floor coordinates (metres) of image points on a calibrated camera; None past the horizon
+#[tauri::command]
+async fn image_to_floor(camera_id: String, points: Vec<[f32; 2]>) -> Result<Vec<Option<[f32; 2]>>, String> {
+    let calibration = settings::current()
+        .floor_calibrations
+        .get(&camera_id)
+        .cloned()
+        .ok_or_else(|| format!("Camera '{}' has no floor calibration", camera_id))?;
+    let homography = calibration.homography()?;
+    Ok(points.iter().map(|p| homography.apply(p[0], p[1])).collect())
+}
+
+#[tauri::command]
+async fn get_rules() -> Result<Vec<Rule>, String> {
+    Ok(settings::current().rules)
+}
+
+// Replace the whole rule list; names must be unique and zones they name must exist
+#[tauri::command]
+async fn set_rules(state: State<'_, AppState>, rules: Vec<Rule>) -> Result<Vec<Rule>, String> {
+    rules::validate(&rules, &settings::current().zones)?;
+    settings::update(|s| s.rules = rules.clone())?;
+    state.rules.lock().await.reset_timers();
+    Ok(rules)
+}
+
+#[tauri::command]
+async fn get_scripts() -> Result<Vec<Script>, String> {
+    Ok(settings::current().scripts)
+}
+
+// Replace the whole script list; every script must compile and define a hook
+#[tauri::command]
+async fn set_scripts(state: State<'_, AppState>, scripts: Vec<Script>) -> Result<Vec<Script>, String> {
+    scripting::validate(&*state.scripts.lock().await, &scripts)?;
+    settings::update(|s| s.scripts = scripts.clone())?;
+    Ok(scripts)
+}
+
+// Dry run of one script's on_detection hook; nothing is stored or sent
+#[tauri::command]
+async fn test_script(
+    state: State<'_, AppState>,
+    source: String,
+    detection: DetectionData,
+    camera_id: Option<String>,
+) -> Result<ScriptOutcome, String> {
+    let mut host = state.scripts.lock().await;
+    host.check(&source)?;
+    let script = Script { name: "test".to_string(), source, camera_id: None, enabled: true };
+    let value = rhai::serde::to_dynamic(&detection).map_err(|e| format!("Failed to pass detection to script: {}", e))?;
+    Ok(host.run(&[script], "on_detection", &value, camera_id.as_deref()))
+}
+
+#[tauri::command]
+async fn get_plugins(state: State<'_, AppState>) -> Result<Vec<PluginStatus>, String> {
+    Ok(state.plugins.lock().await.status(&settings::current().plugins))
+}
+
+// Replace the plugin list and reload every plugin from disk
+#[tauri::command]
+async fn set_plugins(state: State<'_, AppState>, plugins: Vec<PluginConfig>) -> Result<Vec<PluginStatus>, String> {
+    plugins::validate(&plugins)?;
+    settings::update(|s| s.plugins = plugins.clone())?;
+    let mut host = state.plugins.lock().await;
+    host.load(&plugins);
+    Ok(host.status(&plugins))
+}
+
+// Outputs a plugin has published, by key
+#[tauri::command]
+async fn get_plugin_outputs(state: State<'_, AppState>, plugin: String) -> Result<std::collections::BTreeMap<String, serde_json::Value>, String> {
+    state
+        .plugins
+        .lock()
+        .await
+        .outputs(&plugin)
+        .cloned()
+        .ok_or_else(|| format!("Plugin '{}' has no outputs", plugin))
+}
+
+#[tauri::command]
+async fn get_anpr_settings() -> Result<AnprSettings, String> {
+    Ok(settings::current().anpr)
+}
+
+#[tauri::command]
+async fn set_anpr_settings(anpr: AnprSettings) -> Result<AnprSettings, String> {
+    anpr.validate()?;
+    settings::update(|s| s.anpr = anpr.clone())?;
+    Ok(anpr)
+}
+
+// Stored plate reads, newest first; the plate is normalised the same way reads are
+#[tauri::command]
+async fn get_plate_reads(
+    state: State<'_, AppState>,
+    plate: Option<String>,
+    from: Option<String>,
+    to: Option<String>,
+    limit: Option<usize>,
+) -> Result<Vec<PlateRead>, String> {
+    let plate = plate
+        .map(|p| anpr::normalize_plate(&p).ok_or_else(|| format!("'{}' is not a valid plate", p)))
+        .transpose()?;
+    state.events.lock().await.plate_reads(plate.as_deref(), from.as_deref(), to.as_deref(), limit)
+}
+
+#[tauri::command]
+async fn get_privacy_masks() -> Result<Vec<PrivacyMask>, String> {
+    Ok(settings::current().privacy_masks)
+}
+
+// Replace the privacy zones; they apply to every frame from the next call on
+#[tauri::command]
+async fn set_privacy_masks(masks: Vec<PrivacyMask>) -> Result<(), String> {
+    for mask in &masks {
+        mask.validate()?;
+    }
+    println!("🔒 {} privacy mask(s) configured", masks.len());
+    settings::update(|s| s.privacy_masks = masks)?;
+    Ok(())
+}
+
+#[tauri::command]
+async fn get_zones() -> Result<Vec<zones::Zone>, String> {
+    Ok(settings::current().zones)
+}
+
+// Replace the zones; their class filters apply to live detections from the next frame on
+#[tauri::command]
+async fn set_zones(zones: Vec<zones::Zone>) -> Result<(), String> {
+    zones::validate(&zones)?;
+    // Rules still naming a removed or renamed zone would never match again
+    rules::validate(&settings::current().rules, &zones)?;
+    println!("🗺️ {} zone(s) configured", zones.len());
+    settings::update(|s| s.zones = zones)?;
+    Ok(())
+}
+
+// Candidate entrance, queue and dwell zones from where people were seen over the last days
+#[tauri::command]
+async fn suggest_zones(
+    state: State<'_, AppState>,
+    camera_id: Option<String>,
+    days: Option<u32>,
+) -> Result<Vec<zone_suggest::ZoneSuggestion>, String> {
+    let days = days.unwrap_or(7).clamp(1, 90);
+    let now = chrono::Utc::now();
+    let from = event_store::format_timestamp(now - chrono::Duration::days(days as i64));
+    let events = state.events.lock().await.events_between(&from, &event_store::format_timestamp(now))?;
+    let detections: Vec<DetectionData> = events
+        .into_iter()
+        .filter(|e| e.event_type == "detection" && e.camera_id == camera_id)
+        .filter_map(|e| serde_json::from_value(e.payload?).ok())
+        .collect();
+    let suggestions = zone_suggest::suggest(camera_id.as_deref(), &detections);
+    println!("🗺️ {} zone suggestion(s) from {} stored detections", suggestions.len(), detections.len());
+    Ok(suggestions)
+}
+
+// Whether local-only mode is on and what it has blocked so far
+#[tauri::command]
+async fn get_local_only_status() -> Result<LocalOnlyStatus, String> {
+    Ok(local_only::status())
+}
+
+#[tauri::command]
+async fn set_local_only(enabled: bool) -> Result<LocalOnlyStatus, String> {
+    if !enabled && local_only::forced_by_env() {
+        return Err("Local-only mode is enforced by LVA_LOCAL_ONLY and can't be turned off here".to_string());
+    }
+    println!("🔒 Local-only mode {}", if enabled { "enabled" } else { "disabled" });
+    settings::update(|s| s.local_only = enabled)?;
+    Ok(local_only::status())
+}
+
+#[tauri::command]
+async fn analyze_image(
+    _state: State<'_, AppState>,
+    request: AnalyzeRequest,
+) -> Result<AnalyzeResponse, String> {
+    println!("analyze_image called!");
+    println!("Image base64 length: {}", request.image_base64.len());
+    println!("Prompt: {:?}", request.prompt);
+
+    // Check if Ollama is running
+    let status = OllamaManager::check_status().await;
+    println!("Ollama status: running={}, model_ready={}", status.running, status.model_ready);
+
+    if !status.running || !status.model_ready {
+        println!("Ollama not ready, returning error");
+        return Ok(AnalyzeResponse {
+            description: String::new(),
+            error: Some("Ollama not ready".to_string()),
+        });
+    }
+
+    // Make request to Ollama API with timeout
+    let client = network::client_builder()
+        .timeout(std::time::Duration::from_secs(30))
+        .build()
+        .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
+
+    let prompt = request.prompt.unwrap_or_else(||
+        "Describe what you see in this image in 2-3 sentences. Focus on the main subjects and activities.".to_string()
+    );
+
+    if mock::enabled() {
+        let result = mock::ollama_generate(&prompt).await;
+        return Ok(AnalyzeResponse {
+            description: result["response"].as_str().unwrap_or("").to_string(),
+            error: None,
+        });
+    }
+
+    println!("Sending request to Ollama API...");
+    let json_payload = serde_json::json!({
+        "model": settings::current().vision_model,
+        "prompt": prompt,
+        "images": [frame_processor::prepare_frame(request.image_base64, None)?],
+        "stream": false
+    });
+
+    let response = client
+        .post("http://127.0.0.1:11434/api/generate")
+        .json(&json_payload)
+        .send()
+        .await
+        .map_err(|e| {
+            println!("Failed to send request to Ollama: {}", e);
+            format!("Failed to analyze image: {}", e)
+        })?;
+
+    println!("Ollama API response status: {}", response.status());
+
+    if !response.status().is_success() {
+        let status_text = response.status().to_string();
+        let error_text = response.text().await.unwrap_or_default();
+        println!("Analysis failed with status {}: {}", status_text, error_text);
+        return Ok(AnalyzeResponse {
+            description: String::new(),
+            error: Some(format!("Analysis failed: {} - {}", status_text, error_text)),
+        });
+    }
+
+    let response_text = response.text().await
+        .map_err(|e| {
+            println!("Failed to read response text: {}", e);
+            format!("Failed to read response: {}", e)
+        })?;
+
+    println!("Response text length: {}", response_text.len());
+
+    let result: serde_json::Value = serde_json::from_str(&response_text)
+        .map_err(|e| {
+            println!("Failed to parse JSON response: {}", e);
+            println!("Response was: {}", response_text);
+            format!("Failed to parse response: {}", e)
+        })?;
+
+    let description = result["response"]
+        .as_str()
+        .unwrap_or("No description available")
+        .to_string();
+
+    println!("Analysis successful, description length: {}", description.len());
+
+    Ok(AnalyzeResponse {
+        description,
+        error: None,
+    })
+}
+
+#[tauri::command]
+async fn capture_camera_frame() -> Result<String, String> {
+    // This will be handled by the frontend using WebRTC
+    // Returning a placeholder for now
+    Ok("Camera capture handled by frontend".to_string())
+}
+
+// New command for YOLO detection; frames queue in the intake and the oldest is dropped when it overflows
+#[tauri::command]
+async fn yolo_detect(
+    state: State<'_, AppState>,
+    frame_base64: String,
+    _model: Option<String>,
+) -> Result<DetectionData, String> {
+    pause::check()?;
+    state
+        .intake
+        .submit(frame_base64)
+        .await
+        .map_err(|_| "Detection worker stopped".to_string())?
+}
+
+// Stop all detection and analysis until resume_analysis; queued frames are cancelled and the pause is kept in the event history
+#[tauri::command]
+async fn pause_analysis(
+    app: tauri::AppHandle,
+    state: State<'_, AppState>,
+    reason: Option<String>,
+) -> Result<AnalysisState, String> {
+    let reason = reason
+        .map(|r| r.trim().to_string())
+        .filter(|r| !r.is_empty())
+        .unwrap_or_else(|| pause::DEFAULT_REASON.to_string());
+    let paused = pause::pause(&reason, chrono::Utc::now())?;
+    let cancelled = state.intake.cancel_pending(&pause::paused_error(&reason));
+    if let Err(e) = state.events.lock().await.record_pause(&paused) {
+        eprintln!("Failed to record pause: {}", e);
+    }
+    println!("⏸️ Analysis paused: {} ({} queued frames cancelled)", reason, cancelled);
+    Ok(emit_analysis_state(&app))
+}
+
+#[tauri::command]
+async fn resume_analysis(app: tauri::AppHandle, state: State<'_, AppState>) -> Result<AnalysisState, String> {
+    let interval = pause::resume(chrono::Utc::now())?;
+    if let Err(e) = state.events.lock().await.end_pause(&interval) {
+        eprintln!("Failed to record pause end: {}", e);
+    }
+    println!("▶️ Analysis resumed after {}s ({})", interval.duration_secs, interval.reason);
+    Ok(emit_analysis_state(&app))
+}
+
+#[tauri::command]
+async fn get_analysis_state() -> Result<AnalysisState, String> {
+    Ok(pause::state())
+}
+
+fn emit_analysis_state(app: &tauri::AppHandle) -> AnalysisState {
+    let status = pause::state();
+    if let Err(e) = app.emit(pause::STATE_EVENT, &status) {
+        eprintln!("Failed to emit analysis state: {}", e);
+    }
+    status
+}
+
+// Everything the status bar and health panel need in one call
+#[tauri::command]
+async fn get_app_status(state: State<'_, AppState>) -> Result<AppStatus, String> {
+    Ok(app_status::collect(&state).await)
+}
+
+#[tauri::command]
+async fn get_pipeline_stats(state: State<'_, AppState>) -> Result<PipelineStats, String> {
+    Ok(state.intake.stats(std::time::Instant::now()))
+}
+
+// How long to wait before sending this camera's next frame
+#[tauri::command]
+async fn get_frame_interval(state: State<'_, AppState>, camera_id: Option<String>) -> Result<FramePacing, String> {
+    Ok(state.pacing.lock().await.pacing(camera_id.as_deref(), &settings::current().pacing, power::interval_factor()))
+}
+
+// Machine, app and Ollama resource usage with average latency per pipeline stage
+#[tauri::command]
+async fn get_system_stats(state: State<'_, AppState>) -> Result<SystemStats, String> {
+    Ok(sysmon::collect(state.metrics.stage_latencies()).await)
+}
+
+// Rolling p95 per stage against its SLO, and any fallback currently in effect
+#[tauri::command]
+async fn get_slo_status(state: State<'_, AppState>) -> Result<SloStatus, String> {
+    Ok(state.slo.lock().await.status(&settings::current().slo, std::time::Instant::now()))
+}
+
+#[tauri::command]
+async fn get_slo_settings() -> Result<SloSettings, String> {
+    Ok(settings::current().slo)
+}
+
+#[tauri::command]
+async fn set_slo_settings(slo: SloSettings) -> Result<SloSettings, String> {
+    slo.validate(&settings::current().profiles)?;
+    settings::update(|s| s.slo = slo.clone())?;
+    Ok(slo)
+}
+
+#[tauri::command]
+async fn get_retention_settings() -> Result<RetentionSettings, String> {
+    Ok(settings::current().retention)
+}
+
+#[tauri::command]
+async fn set_retention_settings(retention: RetentionSettings) -> Result<RetentionSettings, String> {
+    retention.validate()?;
+    settings::update(|s| s.retention = retention.clone())?;
+    Ok(retention)
+}
+
+// One retention pass now, whether or not the schedule is enabled
+#[tauri::command]
+async fn run_retention(state: State<'_, AppState>) -> Result<RetentionReport, String> {
+    retention::run_now(&state.events).await
+}
+
+#[tauri::command]
+async fn list_incidents(state: State<'_, AppState>, filter: Option<incidents::IncidentFilter>) -> Result<Vec<incidents::Incident>, String> {
+    state.events.lock().await.list_incidents(&filter.unwrap_or_default())
+}
+
+#[tauri::command]
+async fn get_incident(state: State<'_, AppState>, incident_id: String) -> Result<incidents::IncidentDetail, String> {
+    let events = state.events.lock().await;
+    let incident = events
+        .get_incident(&incident_id)?
+        .ok_or_else(|| format!("Unknown incident: {}", incident_id))?;
+    let alert_ids = events.incident_alert_ids(&incident_id)?;
+    let activity = events.incident_activity(&incident_id)?;
+    Ok(incidents::IncidentDetail { incident, alert_ids, activity })
+}
+
+#[tauri::command]
+async fn acknowledge_incident(
+    state: State<'_, AppState>,
+    incident_id: String,
+    by: Option<String>,
+) -> Result<incidents::Incident, String> {
+    let incident = state.events.lock().await.acknowledge_incident(&incident_id, by.as_deref(), chrono::Utc::now())?;
+    println!("👀 Incident acknowledged{}: {}", by.map(|b| format!(" by {}", b)).unwrap_or_default(), incident.description);
+    Ok(incident)
+}
+
+// Close an incident by hand; the note is kept as its resolution
+#[tauri::command]
+async fn resolve_incident(
+    state: State<'_, AppState>,
+    incident_id: String,
+    note: Option<String>,
+    by: Option<String>,
+) -> Result<incidents::Incident, String> {
+    let events = state.events.lock().await;
+    let resolution = note.filter(|n| !n.trim().is_empty()).unwrap_or_else(|| "resolved by operator".to_string());
+    match events.resolve_incident(&incident_id, &resolution, by.as_deref(), chrono::Utc::now())? {
+        Some(incident) => Ok(incident),
+        None => events
+            .get_incident(&incident_id)?
+            .ok_or_else(|| format!("Unknown incident: {}", incident_id)),
+    }
+}
+
+// The alert commands act on the incident the alert was filed under, so every operator sees the same state
+#[tauri::command]
+async fn acknowledge_alert(
+    state: State<'_, AppState>,
+    alert_id: String,
+    by: Option<String>,
+) -> Result<incidents::Incident, String> {
+    let incident_id = state.events.lock().await.incident_for_alert(&alert_id)?;
+    acknowledge_incident(state, incident_id, by).await
+}
+
+#[tauri::command]
+async fn snooze_alert(
+    state: State<'_, AppState>,
+    alert_id: String,
+    minutes: u32,
+    by: Option<String>,
+) -> Result<incidents::Incident, String> {
+    if !(1..=incidents::MAX_SNOOZE_MINUTES).contains(&minutes) {
+        return Err(format!("Snooze for 1 to {} minutes", incidents::MAX_SNOOZE_MINUTES));
+    }
+    let events = state.events.lock().await;
+    let incident_id = events.incident_for_alert(&alert_id)?;
+    let now = chrono::Utc::now();
+    let incident = events.snooze_incident(&incident_id, now + chrono::Duration::minutes(minutes as i64), by.as_deref(), now)?;
+    println!("💤 Incident snoozed for {} min: {}", minutes, incident.description);
+    Ok(incident)
+}
+
+// No assignee takes the incident off whoever had it
+#[tauri::command]
+async fn assign_alert(
+    state: State<'_, AppState>,
+    alert_id: String,
+    assignee: Option<String>,
+    by: Option<String>,
+) -> Result<incidents::Incident, String> {
+    let events = state.events.lock().await;
+    let incident_id = events.incident_for_alert(&alert_id)?;
+    let assignee = assignee.filter(|a| !a.trim().is_empty());
+    events.assign_incident(&incident_id, assignee.as_deref().map(str::trim), by.as_deref(), chrono::Utc::now())
+}
+
+#[tauri::command]
+async fn annotate_alert(
+    state: State<'_, AppState>,
+    alert_id: String,
+    note: String,
+    by: Option<String>,
+) -> Result<incidents::Incident, String> {
+    let events = state.events.lock().await;
+    let incident_id = events.incident_for_alert(&alert_id)?;
+    events.annotate_incident(&incident_id, &note, by.as_deref(), chrono::Utc::now())
+}
+
+#[tauri::command]
+async fn get_incident_metrics(
+    state: State<'_, AppState>,
+    from: Option<String>,
+    to: Option<String>,
+) -> Result<Vec<incidents::IncidentMetrics>, String> {
+    state.events.lock().await.incident_metrics(from.as_deref(), to.as_deref())
+}
+
+#[tauri::command]
+async fn get_incident_settings() -> Result<incidents::IncidentSettings, String> {
+    Ok(settings::current().incidents)
+}
+
+#[tauri::command]
+async fn set_incident_settings(incidents: incidents::IncidentSettings) -> Result<incidents::IncidentSettings, String> {
+    incidents.validate()?;
+    settings::update(|s| s.incidents = incidents.clone())?;
+    Ok(incidents)
+}
+
+#[tauri::command]
+async fn get_tts_settings() -> Result<tts::TtsSettings, String> {
+    Ok(settings::current().tts)
+}
+
+#[tauri::command]
+async fn set_tts_settings(tts: tts::TtsSettings) -> Result<tts::TtsSettings, String> {
+    tts.validate()?;
+    tts.check_rules(&settings::current().rules)?;
+    settings::update(|s| s.tts = tts.clone())?;
+    Ok(tts)
+}
+
+// Speak a sentence now with the configured voice, ignoring quiet hours
+#[tauri::command]
+async fn test_tts(message: Option<String>) -> Result<(), String> {
+    let config = settings::current().tts;
+    let message = message.filter(|m| !m.trim().is_empty()).unwrap_or_else(|| "Live Vision Analyzer announcements are working".to_string());
+    tts::speak(&message, config.voice.as_deref(), config.rate_wpm).await
+}
+
+#[tauri::command]
+async fn get_desktop_notification_settings() -> Result<desktop_notify::DesktopNotificationSettings, String> {
+    Ok(settings::current().desktop_notifications)
+}
+
+#[tauri::command]
+async fn set_desktop_notification_settings(
+    notifications: desktop_notify::DesktopNotificationSettings,
+) -> Result<desktop_notify::DesktopNotificationSettings, String> {
+    notifications.validate()?;
+    settings::update(|s| s.desktop_notifications = notifications.clone())?;
+    Ok(notifications)
+}
+
+#[tauri::command]
+async fn test_desktop_notification(app: tauri::AppHandle) -> Result<(), String> {
+    desktop_notify::show(&app, "Test alert", "Desktop notifications from Live Vision Analyzer are working")
+}
+
+#[tauri::command]
+async fn get_chime_settings() -> Result<chime::ChimeSettings, String> {
+    Ok(settings::current().chime)
+}
+
+#[tauri::command]
+async fn set_chime_settings(chime: chime::ChimeSettings) -> Result<chime::ChimeSettings, String> {
+    chime.validate()?;
+    settings::update(|s| s.chime = chime.clone())?;
+    Ok(chime)
+}
+
+// Play the sound a priority uses, ignoring quiet hours
+#[tauri::command]
+async fn test_chime(priority: Option<String>) -> Result<(), String> {
+    let priority = priority.unwrap_or_else(|| "high".to_string());
+    alerting::validate_priority(&priority)?;
+    chime::play(&priority, &settings::current().chime).await
+}
+
+#[tauri::command]
+async fn get_push_settings() -> Result<push::PushSettings, String> {
+    Ok(settings::current().push)
+}
+
+#[tauri::command]
+async fn set_push_settings(push: push::PushSettings) -> Result<push::PushSettings, String> {
+    push.validate()?;
+    let rules = settings::current().rules;
+    for target in &push.targets {
+        if let Some(rule) = target.rules.iter().find(|r| !rules.iter().any(|rule| &rule.name == *r)) {
+            return Err(format!("Push target '{}' names unknown rule '{}'", target.name, rule));
+        }
+    }
+    settings::update(|s| s.push = push.clone())?;
+    Ok(push)
+}
+
+// Send a test message to one target, whether or not push is enabled
+#[tauri::command]
+async fn test_push(target: String) -> Result<(), String> {
+    let push = settings::current().push;
+    let target = push
+        .targets
+        .iter()
+        .find(|t| t.name == target)
+        .ok_or_else(|| format!("Unknown push target: {}", target))?;
+    let message = push::PushMessage {
+        title: "Test alert".to_string(),
+        body: "Push notifications from Live Vision Analyzer are working".to_string(),
+        priority: target.min_priority.clone(),
+        alert_id: "test".to_string(),
+        thumbnail: None,
+        preview: None,
+    };
+    push::send(target, &message).await
+}
+
+#[tauri::command]
+async fn get_verification_settings() -> Result<verification::VerificationPolicy, String> {
+    Ok(settings::current().verification)
+}
+
+#[tauri::command]
+async fn set_verification_settings(verification: verification::VerificationPolicy) -> Result<verification::VerificationPolicy, String> {
+    verification.validate()?;
+    settings::update(|s| s.verification = verification.clone())?;
+    Ok(verification)
+}
+
+#[tauri::command]
+async fn get_offline_status() -> Result<OfflineStatus, String> {
+    Ok(offline::status())
+}
+
+#[tauri::command]
+async fn get_network_settings() -> Result<NetworkSettings, String> {
+    Ok(settings::current().network)
+}
+
+// Applies to clients created from now on; the Moondream client and an already running Ollama pick it up on restart
+#[tauri::command]
+async fn set_network_settings(network: NetworkSettings) -> Result<NetworkSettings, String> {
+    network.validate()?;
+    settings::update(|s| s.network = network.clone())?;
+    Ok(network)
+}
+
+#[tauri::command]
+async fn get_download_status() -> Result<DownloadStatus, String> {
+    Ok(downloads::status())
+}
+
+// Pauses the Ollama download and model pulls in progress, and any that start while paused
+#[tauri::command]
+async fn pause_downloads() -> Result<DownloadStatus, String> {
+    downloads::set_paused(true);
+    Ok(downloads::status())
+}
+
+#[tauri::command]
+async fn resume_downloads() -> Result<DownloadStatus, String> {
+    downloads::set_paused(false);
+    Ok(downloads::status())
+}
+
+// Reachability through the configured proxy and CA of the given URLs, or of Ollama and the usual download hosts
+#[tauri::command]
+async fn test_connectivity(urls: Option<Vec<String>>) -> Result<Vec<ConnectivityCheck>, String> {
+    let urls = urls.unwrap_or_else(|| network::DEFAULT_TARGETS.iter().map(|u| u.to_string()).collect());
+    Ok(network::test_connectivity(&urls).await)
+}
+
+#[tauri::command]
+async fn get_encryption_status() -> Result<EncryptionStatus, String> {
+    Ok(encryption::status())
+}
+
+// Seals existing descriptions and snapshots; the store stays locked so nothing is written in plaintext meanwhile
+#[tauri::command]
+async fn enable_encryption(state: State<'_, AppState>, passphrase: String, use_keychain: Option<bool>) -> Result<EncryptionStatus, String> {
+    if encryption::enabled() {
+        return Err("Encryption is already enabled".to_string());
+    }
+    let store = state.events.lock().await;
+    let config = encryption::setup(&passphrase, use_keychain.unwrap_or(false))?;
+    let sealed = store
+        .convert_encryption(true)
+        .and_then(|events| Ok((events, encryption::convert_files(&dataset::snapshots_dir(), true)?)))
+        .and_then(|counts| settings::update(|s| s.encryption = config.clone()).map(|_| counts));
+    // Put back anything already sealed, since the key would be lost with the unsaved settings
+    let (events, snapshots) = sealed.inspect_err(|_| {
+        let _ = store.convert_encryption(false);
+        let _ = encryption::convert_files(&dataset::snapshots_dir(), false);
+        encryption::lock();
+    })?;
+    println!("🔐 Encryption enabled ({} events, {} snapshots sealed)", events, snapshots);
+    Ok(encryption::status())
+}
+
+#[tauri::command]
+async fn unlock_storage(passphrase: String) -> Result<EncryptionStatus, String> {
+    let config = settings::current().encryption;
+    if !config.enabled {
+        return Err("Encryption is not enabled".to_string());
+    }
+    encryption::unlock(&passphrase, &config)?;
+    println!("🔐 Encrypted storage unlocked");
+    Ok(encryption::status())
+}
+
+// Decrypts everything back in place; needs the passphrase even when already unlocked
+#[tauri::command]
+async fn disable_encryption(state: State<'_, AppState>, passphrase: String) -> Result<EncryptionStatus, String> {
+    let config = settings::current().encryption;
+    if !config.enabled {
+        return Err("Encryption is not enabled".to_string());
+    }
+    encryption::unlock(&passphrase, &config)?;
+    let store = state.events.lock().await;
+    let events = store.convert_encryption(false)?;
+    let snapshots = encryption::convert_files(&dataset::snapshots_dir(), false)?;
+    settings::update(|s| s.encryption = Default::default())?;
+    if config.use_keychain {
+        encryption::keychain_forget();
+    }
+    encryption::lock();
+    println!("🔓 Encryption disabled ({} events, {} snapshots decrypted)", events, snapshots);
+    Ok(encryption::status())
+}
+
+// Battery, temperature and what the power policy is currently doing about them
+#[tauri::command]
+async fn get_power_status() -> Result<PowerStatus, String> {
+    Ok(power::status())
+}
+
+#[tauri::command]
+async fn set_power_policy(policy: PowerPolicy) -> Result<PowerStatus, String> {
+    policy.validate(&settings::current().profiles)?;
+    settings::update(|s| s.power = policy)?;
+    Ok(power::status())
+}
+
+#[tauri::command]
+async fn get_pacing_settings() -> Result<PacingSettings, String> {
+    Ok(settings::current().pacing)
+}
+
+#[tauri::command]
+async fn set_pacing_settings(pacing: PacingSettings) -> Result<PacingSettings, String> {
+    pacing.validate()?;
+    settings::update(|s| s.pacing = pacing.clone())?;
+    Ok(pacing)
+}
+
+// Detection for a frame from the intake queue
+async fn detect_live_frame(state: &AppState, frame_base64: String) -> Result<DetectionData, String> {
+    let frame_base64 = dewarp::for_camera(frame_processor::prepare_frame(frame_base64, None)?, None)?;
+    let detect_frame = enhance::for_detection(&frame_base64, None)?;
+    state.bus.publish(BusEvent::FrameCaptured);
+    let start_time = std::time::Instant::now();
+    let mut detection = state.yolo.detect(&detect_frame).await.inspect_err(|e| {
+        state.metrics.record_error("detection");
+        app_status::record_error("detection", e);
+    })?;
+    let latency = start_time.elapsed();
+    run_live_analytics(state, None, &frame_base64, &mut detection).await;
+
+    state.bus.publish(BusEvent::DetectionReady {
+        camera_id: None,
+        detection: Arc::new(detection.clone()),
+        latency,
+        live: true,
+        snapshot: Some(frame_base64.into()),
+    });
+    Ok(detection)
+}
+
+// New command for event-triggered LLaVA analysis
+#[tauri::command]
+async fn analyze_with_llava(
+    state: State<'_, AppState>,
+    frame_base64: String,
+    prompt: String,
+    timeout: Option<u64>,
+    roi: Option<RegionOfInterest>,
+    profile: Option<String>,
+) -> Result<serde_json::Value, String> {
+    println!("analyze_with_llava called with custom prompt");
+    pause::check()?;
+
+    // Crop to the region that triggered the escalation, if one was given
+    let frame_base64 = frame_processor::prepare_frame(frame_base64, roi.as_ref())?;
+
+    // Check if Ollama is running
+    let status = OllamaManager::check_status().await;
+    if !status.running || !status.model_ready {
+        return Err("Ollama not ready".to_string());
+    }
+
+    // Set timeout (default 30 seconds to handle LLaVA processing)
+    let _in_flight = state.metrics.track_in_flight();
+    let start_time = std::time::Instant::now();
+    let result = OllamaManager::generate(&prompt, vec![frame_base64], timeout.unwrap_or(30000), profile.as_deref()).await;
+    state.bus.publish(BusEvent::analysis("llava", start_time.elapsed(), result.is_ok()));
+    let result = result.inspect_err(|e| app_status::record_error("llava", e))?;
+
+    let description = result["response"].as_str().unwrap_or("").to_string();
+    let usage = TokenUsage::from_ollama(&result);
+    let model = result["model"].as_str().map(str::to_string).unwrap_or_else(|| settings::current().vision_model);
+    let parsed = OllamaManager::parse_response_json(result);
+    let event_id = store_analysis(&state, "llava", &prompt, &description, Some(parsed.clone())).await;
+    store_usage(&state, event_id.as_deref(), "llava", &model, usage.as_ref()).await;
+
+    Ok(parsed)
+}
+
+// Per-request choices for `analyze`; anything left out uses the app's settings
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct AnalyzeOptions {
+    provider: Option<String>,  // Any registered provider; defaults to the SLO-aware default
+    model: Option<String>,  // e.g. another pulled LLaVA tag or cloud model
+    profile: Option<String>,  // Inference profile, for LLaVA
+    timeout_ms: Option<u64>,
+    max_tokens: Option<u32>,
+    schema: Option<serde_json::Value>,  // JSON Schema the answer should follow
+    roi: Option<RegionOfInterest>,
+}
+
+// One analysis command for every provider, model and output shape
+#[tauri::command]
+async fn analyze(
+    state: State<'_, AppState>,
+    frame_base64: String,
+    prompt: String,
+    options: Option<AnalyzeOptions>,
+) -> Result<serde_json::Value, String> {
+    let options = options.unwrap_or_default();
+    let provider = options.provider.clone().unwrap_or_else(slo::default_provider);
+    println!("🔌 analyze called (provider: {}, model: {})", provider, options.model.as_deref().unwrap_or("default"));
+    pause::check()?;
+
+    let frame_base64 = frame_processor::prepare_frame(frame_base64, options.roi.as_ref())?;
+    let backend = state
+        .providers
+        .read()
+        .await
+        .get(&provider)
+        .ok_or_else(|| format!("Unknown provider: {}", provider))?;
+
+    let request = VisionRequest {
+        prompt: match &options.schema {
+            Some(schema) => vision_provider::schema_prompt(&prompt, schema),
+            None => prompt.clone(),
+        },
+        images: vec![frame_base64],
+        timeout_ms: options.timeout_ms.unwrap_or(30000),
+        max_tokens: options.max_tokens,
+        model: options.model,
+        profile: options.profile,
+    };
+
+    let _in_flight = state.metrics.track_in_flight();
+    let start_time = std::time::Instant::now();
+    let response = backend.analyze(&request).await;
+    state.bus.publish(BusEvent::analysis(&provider, start_time.elapsed(), response.is_ok()));
+    let response = response.inspect_err(|e| app_status::record_error(&provider, e))?;
+    let processing_time_ms = start_time.elapsed().as_millis() as u64;
+
+    let parsed = vision_provider::parse_text_json(&response.text);
+    let schema_errors = options.schema.as_ref().map(|schema| vision_provider::schema_errors(&parsed, schema));
+    let event_id = store_analysis(&state, &provider, &prompt, &response.text, Some(parsed.clone())).await;
+    store_usage(&state, event_id.as_deref(), &provider, &response.model, response.usage.as_ref()).await;
+
+    Ok(serde_json::json!({
+        "provider": response.provider,
+        "model": response.model,
+        "response": response.text,
+        "result": parsed,
+        "schema_errors": schema_errors,
+        "usage": response.usage,
+        "processing_time_ms": processing_time_ms
+    }))
+}
+
+// Kept for existing callers; same as `analyze` with only the provider chosen
+#[tauri::command]
+async fn analyze_with_provider(
+    state: State<'_, AppState>,
+    provider: String,
+    frame_base64: String,
+    prompt: String,
+    timeout: Option<u64>,
+    max_tokens: Option<u32>,
+    roi: Option<RegionOfInterest>,
+) -> Result<serde_json::Value, String> {
+    let options = AnalyzeOptions { provider: Some(provider), timeout_ms: timeout, max_tokens, roi, ..Default::default() };
+    analyze(state, frame_base64, prompt, Some(options)).await
+}
+
+#[tauri::command]
+async fn list_providers(state: State<'_, AppState>) -> Result<Vec<ProviderInfo>, String> {
+    Ok(state.providers.read().await.list())
+}
+
+#[tauri::command]
+async fn configure_provider(
+    state: State<'_, AppState>,
+    name: String,
+    settings: ProviderSettings,
+) -> Result<ProviderInfo, String> {
+    println!("🔌 Configuring provider '{}' ({})", name, settings.kind);
+    state.providers.write().await.configure(&name, settings)
+}
+
+// Models loaded on a local OpenAI-compatible server (by URL or preset name)
+#[tauri::command]
+async fn discover_local_models(base_url: Option<String>, server: Option<String>) -> Result<Vec<String>, String> {
+    let base_url = match (base_url, server) {
+        (Some(url), _) => url,
+        (None, server) => {
+            let server = server.unwrap_or_else(|| "llama.cpp".to_string());
+            local_provider::preset_url(&server)
+                .ok_or_else(|| format!("Unknown local server: {}", server))?
+                .to_string()
+        }
+    };
+    println!("🔌 Discovering models at {}", base_url);
+    local_provider::list_models(&base_url).await
+}
+
+// Register an in-process provider and let the Moondream commands fall back to it
+async fn install_local_provider(state: &AppState, provider: LocalInferenceProvider) -> LocalInferenceStatus {
+    let status = LocalInferenceStatus {
+        compiled: local_inference::compiled(),
+        loaded: true,
+        model: Some(provider.model().to_string()),
+        device: provider.device().map(|d| d.to_string()),
+    };
+
+    let provider: Arc<dyn VisionProvider> = Arc::new(provider);
+    state.providers.write().await.register_builtin(provider.clone());
+    state.moondream.set_local_backend(Some(provider));
+    status
+}
+
+// Download (first run only) and load the moondream2 weights for offline Moondream
+async fn setup_local_moondream_backend(state: &AppState, device: String) -> Result<LocalInferenceStatus, String> {
+    if !local_inference::compiled() {
+        return Err("In-process inference is not available - rebuild with `--features local-inference`".to_string());
+    }
+    local_inference::validate_device(&device)?;
+
+    let (model_path, tokenizer_path) = local_inference::ensure_moondream2_weights().await?;
+    let provider = tokio::task::spawn_blocking(move || {
+        LocalInferenceProvider::load_moondream2(&model_path, &tokenizer_path, &device)
+    })
+    .await
+    .map_err(|e| format!("Failed to load model: {}", e))??;
+
+    Ok(install_local_provider(state, provider).await)
+}
+
+// In-process inference: load a Moondream GGUF and register it as the "local-inference" provider
+#[tauri::command]
+async fn load_local_model(
+    state: State<'_, AppState>,
+    model_path: String,
+    tokenizer_path: String,
+    device: Option<String>,
+) -> Result<LocalInferenceStatus, String> {
+    println!("🧠 Loading in-process model: {}", model_path);
+    let device = device.unwrap_or_else(|| "auto".to_string());
+
+    // Reading weights is slow and blocking
+    let provider = tokio::task::spawn_blocking(move || {
+        LocalInferenceProvider::load(std::path::Path::new(&model_path), std::path::Path::new(&tokenizer_path), &device)
+    })
+    .await
+    .map_err(|e| format!("Failed to load model: {}", e))??;
+
+    Ok(install_local_provider(&state, provider).await)
+}
+
+// Offline Moondream: fetch moondream2 weights if needed and run them on the best available device
+#[tauri::command]
+async fn setup_local_moondream(
+    state: State<'_, AppState>,
+    device: Option<String>,
+) -> Result<LocalInferenceStatus, String> {
+    println!("🌙 Setting up local moondream2 ({:?})", device);
+    setup_local_moondream_backend(&state, device.unwrap_or_else(|| "auto".to_string())).await
+}
+
+#[tauri::command]
+async fn unload_local_model(state: State<'_, AppState>) -> Result<bool, String> {
+    state.moondream.set_local_backend(None);
+    Ok(state.providers.write().await.unregister_builtin(local_inference::PROVIDER_NAME))
+}
+
+#[tauri::command]
+async fn get_local_inference_status(state: State<'_, AppState>) -> Result<LocalInferenceStatus, String> {
+    let provider = state.providers.read().await.get(local_inference::PROVIDER_NAME);
+    Ok(LocalInferenceStatus {
+        compiled: local_inference::compiled(),
+        loaded: provider.is_some(),
+        model: provider.as_ref().map(|p| p.model().to_string()),
+        device: provider.as_ref().and_then(|p| p.device().map(|d| d.to_string())),
+    })
+}
+
+#[tauri::command]
+async fn remove_provider(state: State<'_, AppState>, name: String) -> Result<bool, String> {
+    state.providers.write().await.remove(&name)
+}
+
+// Multi-frame temporal analysis - lets the VLM reason about changes over time
+#[tauri::command]
+async fn analyze_sequence(
+    state: State<'_, AppState>,
+    frames: Vec<String>,
+    prompt: String,
+    provider: Option<String>,
+    tiled: Option<bool>,
+    timeout: Option<u64>,
+    profile: Option<String>,
+) -> Result<serde_json::Value, String> {
+    println!("🎞️ analyze_sequence called with {} frames", frames.len());
+    pause::check()?;
+
+    if frames.is_empty() {
+        return Err("No frames provided".to_string());
+    }
+    if frames.len() > frame_processor::MAX_SEQUENCE_FRAMES {
+        return Err(format!(
+            "Too many frames: {} (maximum is {})",
+            frames.len(),
+            frame_processor::MAX_SEQUENCE_FRAMES
+        ));
+    }
+
+    let frames = frames
+        .into_iter()
+        .map(|frame| frame_processor::prepare_frame(frame, None))
+        .collect::<Result<Vec<_>, String>>()?;
+    let frame_count = frames.len();
+    let provider = provider.unwrap_or_else(slo::default_provider);
+    // Moondream only accepts a single image, so it always gets the contact sheet
+    let tiled = tiled.unwrap_or(true) || provider == "moondream";
+
+    let (images, sequence_prompt) = if tiled {
+        let sheet = frame_processor::build_contact_sheet(&frames)?;
+        let sequence_prompt = format!(
+            "This image is a contact sheet of {} frames from the same camera in chronological order \
+             (left to right, top to bottom). {}",
+            frame_count, prompt
+        );
+        (vec![sheet], sequence_prompt)
+    } else {
+        let sequence_prompt = format!(
+            "These {} images are consecutive frames from the same camera in chronological order. {}",
+            frame_count, prompt
+        );
+        (frames, sequence_prompt)
+    };
+
+    let _in_flight = state.metrics.track_in_flight();
+    let start_time = std::time::Instant::now();
+
+    let result = match provider.as_str() {
+        "llava" => sequence_with_llava(&sequence_prompt, images, timeout, profile.as_deref()).await,
+        "moondream" => sequence_with_moondream(&state, sequence_prompt, images).await,
+        other => Err(format!("Unknown provider: {}", other)),
+    };
+    state.bus.publish(BusEvent::analysis(&provider, start_time.elapsed(), result.is_ok()));
+    let (description, result) = result?;
+    store_analysis(&state, &provider, &prompt, &description, Some(result.clone())).await;
+
+    Ok(serde_json::json!({
+        "provider": provider,
+        "frame_count": frame_count,
+        "tiled": tiled,
+        "result": result,
+        "processing_time_ms": start_time.elapsed().as_millis() as u64
+    }))
+}
+
+// Returns (description, parsed result) for a sequence analyzed by LLaVA
+async fn sequence_with_llava(
+    prompt: &str,
+    images: Vec<String>,
+    timeout: Option<u64>,
+    profile: Option<&str>,
+) -> Result<(String, serde_json::Value), String> {
+    let status = OllamaManager::check_status().await;
+    if !status.running || !status.model_ready {
+        return Err("Ollama not ready".to_string());
+    }
+
+    let result = OllamaManager::generate(prompt, images, timeout.unwrap_or(60000), profile).await?;
+    let description = result["response"].as_str().unwrap_or("").to_string();
+    Ok((description, OllamaManager::parse_response_json(result)))
+}
+
+// Returns (description, result) for a contact sheet analyzed by Moondream
+async fn sequence_with_moondream(
+    state: &AppState,
+    prompt: String,
+    images: Vec<String>,
+) -> Result<(String, serde_json::Value), String> {
+    let image = images.into_iter().next().unwrap_or_default();
+    let result = state.moondream.query(image, prompt).await?;
+    Ok((result.response.clone(), serde_json::to_value(result).map_err(|e| e.to_string())?))
+}
+
+// Batch mode: analyze a list of images and videos or a whole folder with a bounded worker pool
+#[tauri::command]
+async fn analyze_batch(
+    app: tauri::AppHandle,
+    state: State<'_, AppState>,
+    paths: Option<Vec<String>>,
+    directory: Option<String>,
+    prompt: Option<String>,
+    provider: Option<String>,
+    concurrency: Option<usize>,
+) -> Result<BatchSummary, String> {
+    let mut files: Vec<std::path::PathBuf> = paths
+        .unwrap_or_default()
+        .into_iter()
+        .map(std::path::PathBuf::from)
+        .collect();
+    if let Some(dir) = directory {
+        files.extend(frame_processor::list_image_files(std::path::Path::new(&dir))?);
+    }
+    if files.is_empty() {
+        return Err("No files to analyze".to_string());
+    }
+
+    let provider = provider.unwrap_or_else(|| "llava".to_string());
+    pipeline::ensure_provider_ready(&state.providers, &provider).await?;
+
+    // Videos are sampled as in analyze_video and each frame becomes an item labelled with its timecode;
+    // a video that can't be sampled is one failed item and the rest of the batch goes on
+    let frames_dir = tempfile::tempdir().map_err(|e| format!("Failed to create temp directory: {}", e))?;
+    let mut sources: Vec<Result<(std::path::PathBuf, String), BatchItemResult>> = Vec::new();
+    for (index, file) in files.into_iter().enumerate() {
+        if !video_source::is_video_file(&file) {
+            let label = file.display().to_string();
+            sources.push(Ok((file, label)));
+            continue;
+        }
+        let out_dir = frames_dir.path().join(index.to_string());
+        let sampled = match std::fs::create_dir_all(&out_dir) {
+            Ok(()) => {
+                video_source::extract_frames(&file, video_source::DEFAULT_SAMPLE_FPS, video_source::DEFAULT_MAX_FRAMES, &out_dir).await
+            }
+            Err(e) => Err(format!("Failed to create temp directory: {}", e)),
+        };
+        match sampled {
+            Ok(frames) if !frames.is_empty() => sources.extend(frames.into_iter().map(|frame| {
+                let label = format!("{} @ {}", file.display(), video_source::format_timecode(frame.offset_secs));
+                Ok((frame.path, label))
+            })),
+            Ok(_) => sources.push(Err(BatchItemResult {
+                error: Some(format!("No frames could be sampled from {}", file.display())),
+                ..BatchItemResult::new(file.display().to_string())
+            })),
+            Err(e) => sources.push(Err(BatchItemResult { error: Some(e), ..BatchItemResult::new(file.display().to_string()) })),
+        }
+    }
+
+    let prompt = prompt.unwrap_or_else(|| benchmark::DEFAULT_BENCHMARK_PROMPT.to_string());
+    let concurrency = concurrency.unwrap_or(batch::DEFAULT_CONCURRENCY).clamp(1, batch::MAX_CONCURRENCY);
+    let batch_id = uuid::Uuid::new_v4().to_string();
+    let total = sources.len();
+    println!("🗂️ Batch {} started: {} items, provider={}, workers={}", batch_id, total, provider, concurrency);
+
+    let start_time = std::time::Instant::now();
+    let completed = std::sync::atomic::AtomicUsize::new(0);
+    let (state_ref, app_ref, completed_ref) = (&*state, &app, &completed);
+    let (provider_ref, prompt_ref, batch_id_ref) = (provider.as_str(), prompt.as_str(), batch_id.as_str());
+
+    let items: Vec<BatchItemResult> = futures_util::stream::iter(sources)
+        .map(|source| async move {
+            let item = match source {
+                Ok((path, label)) => analyze_batch_file(state_ref, &path, label, provider_ref, prompt_ref).await,
+                Err(failed) => failed,
+            };
+            let done = completed_ref.fetch_add(1, std::sync::atomic::Ordering::SeqCst) + 1;
+
+            let progress = BatchProgress {
+                batch_id: batch_id_ref.to_string(),
+                completed: done,
+                total,
+                item: item.clone(),
+            };
+            if let Err(e) = app_ref.emit(batch::PROGRESS_EVENT, progress) {
+                eprintln!("Failed to emit batch progress: {}", e);
+            }
+            item
+        })
+        .buffer_unordered(concurrency)
+        .collect()
+        .await;
+
+    let summary = batch::summarize(&batch_id, &provider, items, start_time.elapsed().as_millis() as u64);
+    println!("🗂️ Batch {} finished: {}/{} succeeded", batch_id, summary.succeeded, summary.total);
+
+    if let Err(e) = app.emit(batch::COMPLETE_EVENT, &summary) {
+        eprintln!("Failed to emit batch summary: {}", e);
+    }
+
+    Ok(summary)
+}
+
+// Detection + optional VLM analysis for a single batch file
+// `label` names the item in results: the image path, or the video path and timecode of a sampled frame
+async fn analyze_batch_file(state: &AppState, path: &std::path::Path, label: String, provider: &str, prompt: &str) -> BatchItemResult {
+    let start_time = std::time::Instant::now();
+    let mut item = BatchItemResult::new(label);
+
+    let outcome: Result<(), String> = async {
+        pause::check()?;
+        let frame = frame_processor::load_frame_file(path)?;
+        let detection = state.yolo.detect(&frame).await?;
+        privacy::remember_people(detection.person_count as usize);
+        item.detection = Some(detection);
+
+        let Some((description, analysis)) = pipeline::describe_frame(&state.moondream, &state.providers, provider, prompt, frame).await? else {
+            return Ok(());
+        };
+
+        let payload = serde_json::json!({ "source": item.path, "result": analysis });
+        item.event_id = store_analysis(state, provider, prompt, &description, Some(payload)).await;
+        item.description = Some(description);
+        item.analysis = Some(analysis);
+        Ok(())
+    }
+    .await;
+
+    item.success = outcome.is_ok();
+    item.error = outcome.err();
+    item.processing_time_ms = start_time.elapsed().as_millis() as u64;
+    item
+}
+
+// Offline review of recorded footage: sample frames, detect, escalate changes to the VLM
+#[tauri::command]
+async fn analyze_video(
+    app: tauri::AppHandle,
+    state: State<'_, AppState>,
+    path: String,
+    options: Option<VideoAnalysisOptions>,
+) -> Result<VideoAnalysisSummary, String> {
+    let options = options.unwrap_or_default();
+    let provider = options.provider.clone().unwrap_or_else(|| "llava".to_string());
+    pipeline::ensure_provider_ready(&state.providers, &provider).await?;
+
+    let video_path = std::path::PathBuf::from(&path);
+    if !video_source::is_video_file(&video_path) {
+        return Err(format!(
+            "Unsupported video format: {} (expected one of {:?})",
+            path,
+            video_source::VIDEO_EXTENSIONS
+        ));
+    }
+    let video_id = uuid::Uuid::new_v4().to_string();
+    let prompt = options
+        .prompt
+        .clone()
+        .unwrap_or_else(|| benchmark::DEFAULT_BENCHMARK_PROMPT.to_string());
+    let start_time = std::time::Instant::now();
+
+    // Sampled frames live in a temp dir that is removed when this command returns
+    let frames_dir = tempfile::tempdir().map_err(|e| format!("Failed to create temp directory: {}", e))?;
+    let frames = video_source::extract_frames(
+        &video_path,
+        options.sample_fps.unwrap_or(video_source::DEFAULT_SAMPLE_FPS),
+        options.max_frames.unwrap_or(video_source::DEFAULT_MAX_FRAMES),
+        frames_dir.path(),
+    )
+    .await?;
+    let total = frames.len();
+    println!("🎞️ Video {} started: {} sampled frames, provider={}", video_id, total, provider);
+
+    let mut events: Vec<VideoEvent> = Vec::new();
+    let mut previous_count: Option<u32> = None;
+    let mut last_analysis_secs: Option<f64> = None;
+    let mut smoother = CountSmoother::new(settings::current().smoothing);
+    let mut analyses_run = 0;
+    let mut max_people = 0;
+
+    for sampled in &frames {
+        if let Err(e) = pause::check() {
+            eprintln!("Video {}: stopped at {:.1}s: {}", video_id, sampled.offset_secs, e);
+            break;
+        }
+        let frame = frame_processor::load_frame_file(&sampled.path)?;
+        let detect_start = std::time::Instant::now();
+        let mut detection = match state.yolo.detect(&frame).await {
+            Ok(detection) => detection,
+            Err(e) => {
+                state.metrics.record_error("detection");
+                eprintln!("Video {}: detection failed at {:.1}s: {}", video_id, sampled.offset_secs, e);
+                continue;
+            }
+        };
+        let latency = detect_start.elapsed();
+        privacy::remember_people(detection.person_count as usize);
+        smoother.apply(&mut detection);
+        scripting::on_detection(&state, None, &mut detection).await;
+        state.bus.publish(BusEvent::DetectionReady {
+            camera_id: None,
+            detection: Arc::new(detection.clone()),
+            latency,
+            live: false,
+            snapshot: None,
+        });
+        let person_count = detection.stable_person_count();
+        max_people = max_people.max(person_count);
+
+        let timecode = video_source::format_timecode(sampled.offset_secs);
+        if previous_count != Some(person_count) {
+            events.push(VideoEvent {
+                offset_secs: sampled.offset_secs,
+                timecode: timecode.clone(),
+                event_type: "detection".to_string(),
+                person_count,
+                object_counts: detection.object_counts.clone(),
+                description: None,
+                analysis: None,
+                error: None,
+            });
+        }
+
+        if video_source::should_escalate(&options, &detection, previous_count, last_analysis_secs, sampled.offset_secs) {
+            last_analysis_secs = Some(sampled.offset_secs);
+            analyses_run += 1;
+
+            let _in_flight = state.metrics.track_in_flight();
+            let vlm_start = std::time::Instant::now();
+            let result = pipeline::describe_frame(&state.moondream, &state.providers, &provider, &prompt, frame).await;
+            state.bus.publish(BusEvent::analysis(&provider, vlm_start.elapsed(), result.is_ok()));
+
+            let mut event = VideoEvent {
+                offset_secs: sampled.offset_secs,
+                timecode: timecode.clone(),
+                event_type: "analysis".to_string(),
+                person_count,
+                object_counts: detection.object_counts.clone(),
+                description: None,
+                analysis: None,
+                error: None,
+            };
+            match result {
+                Ok(Some((description, analysis))) => {
+                    let payload = serde_json::json!({ "source": path, "timecode": timecode, "result": analysis });
+                    store_analysis(&state, &provider, &prompt, &description, Some(payload)).await;
+                    event.description = Some(description);
+                    event.analysis = Some(analysis);
+                }
+                Ok(None) => {}
+                Err(e) => event.error = Some(e),
+            }
+            events.push(event);
+        }
+        previous_count = Some(person_count);
+
+        let progress = VideoProgress {
+            video_id: video_id.clone(),
+            processed: sampled.index + 1,
+            total,
+            offset_secs: sampled.offset_secs,
+        };
+        if let Err(e) = app.emit(video_source::PROGRESS_EVENT, progress) {
+            eprintln!("Failed to emit video progress: {}", e);
+        }
+    }
+
+    let log_path = match video_source::write_event_log(&video_id, &video_path, &events) {
+        Ok(log_path) => Some(log_path.display().to_string()),
+        Err(e) => {
+            eprintln!("Video {}: {}", video_id, e);
+            None
+        }
+    };
+    println!("🎞️ Video {} finished: {} events, {} analyses", video_id, events.len(), analyses_run);
+
+    Ok(VideoAnalysisSummary {
+        video_id,
+        path,
+        frames_sampled: total,
+        analyses_run,
+        max_people,
+        log_path,
+        elapsed_ms: start_time.elapsed().as_millis() as u64,
+        events,
+    })
+}
+
+// Screen capture source - grab a frame from a display, window or region instead of the camera
+#[tauri::command]
+async fn capture_screen(target: Option<CaptureTarget>) -> Result<String, String> {
+    let target = target.unwrap_or_default();
+    println!("🖥️ capture_screen called (window: {:?}, display: {:?})", target.window, target.display);
+    screen_capture::capture_frame(&target).await
+}
+
+#[tauri::command]
+async fn get_capture_source_info() -> Result<CaptureSourceInfo, String> {
+    screen_capture::source_info().await
+}
+
+// Conversation mode - follow-up questions about the last analyzed frame
+#[tauri::command]
+async fn vision_chat(
+    state: State<'_, AppState>,
+    session_id: Option<String>,
+    question: String,
+    frame_base64: Option<String>,
+    timeout: Option<u64>,
+    profile: Option<String>,
+) -> Result<ChatReply, String> {
+    println!("💬 vision_chat called (session: {:?})", session_id);
+    let frame_base64 = frame_base64.map(|frame| frame_processor::prepare_frame(frame, None)).transpose()?;
+
+    let status = OllamaManager::check_status().await;
+    if !status.running || !status.model_ready {
+        return Err("Ollama not ready".to_string());
+    }
+
+    // Build the request from a snapshot so the lock isn't held during inference
+    let (session_id, messages) = {
+        let mut chat = state.chat.lock().await;
+        // Checked before the session is created, so a rejected first question leaves none behind
+        let has_frame = session_id.as_deref().and_then(|id| chat.get(id)).is_some_and(|s| s.last_frame.is_some());
+        if frame_base64.is_none() && !has_frame {
+            return Err("No frame in this session yet - send a frame with the first question".to_string());
+        }
+        let session = chat.get_or_create(session_id);
+
+        let mut snapshot = session.clone();
+        if frame_base64.is_some() {
+            snapshot.last_frame = frame_base64.clone();
+        }
+        (session.id.clone(), snapshot.build_messages(&question))
+    };
+
+    let _in_flight = state.metrics.track_in_flight();
+    let start_time = std::time::Instant::now();
+    let answer = OllamaManager::chat(messages, timeout.unwrap_or(30000), profile.as_deref()).await;
+    state.bus.publish(BusEvent::analysis("llava", start_time.elapsed(), answer.is_ok()));
+    let answer = answer?;
+    let processing_time_ms = start_time.elapsed().as_millis() as u64;
+
+    let turn_count = state
+        .chat
+        .lock()
+        .await
+        .record_turn(&session_id, question, answer.clone(), frame_base64);
+
+    Ok(ChatReply {
+        session_id,
+        answer,
+        turn_count,
+        processing_time_ms,
+    })
+}
+
+#[tauri::command]
+async fn list_chat_sessions(state: State<'_, AppState>) -> Result<Vec<ChatSessionSummary>, String> {
+    Ok(state.chat.lock().await.list_sessions())
+}
+
+#[tauri::command]
+async fn get_chat_history(state: State<'_, AppState>, session_id: String) -> Result<Vec<ChatTurn>, String> {
+    state
+        .chat
+        .lock()
+        .await
+        .get_history(&session_id)
+        .ok_or_else(|| format!("Chat session not found: {}", session_id))
+}
+
+#[tauri::command]
+async fn clear_chat_session(state: State<'_, AppState>, session_id: Option<String>) -> Result<usize, String> {
+    let mut chat = state.chat.lock().await;
+    match session_id {
+        Some(id) => Ok(chat.clear_session(&id) as usize),
+        None => Ok(chat.clear_all()),
+    }
+}
+
+// Event history commands
+
+// Dump events in a time range to CSV, JSON Lines or Parquet for pandas/Excel
+#[tauri::command]
+async fn export_events(
+    state: State<'_, AppState>,
+    range: Option<ExportRange>,
+    format: String,
+    path: String,
+) -> Result<ExportSummary, String> {
+    let format = ExportFormat::parse(&format)?;
+    let range = range.unwrap_or_default();
+    let events = state.events.lock().await.events_between(
+        range.from.as_deref().unwrap_or(""),
+        range.to.as_deref().unwrap_or("9999-12-31T23:59:59Z"),
+    )?;
+
+    let target = path.clone();
+    let result = tokio::task::spawn_blocking(move || export::export(&events, format, std::path::Path::new(&path)))
+        .await
+        .map_err(|e| format!("Export failed: {}", e))?;
+    let data = match &result {
+        Ok(summary) => format!("{} events ({})", summary.rows, format.name()),
+        Err(_) => format!("Events ({})", format.name()),
+    };
+    audit::record(AuditEntry::new(AuditAction::Export, data, target).outcome(&result));
+    let summary = result?;
+    println!("📤 Exported {} events to {}", summary.rows, summary.path);
+    Ok(summary)
+}
+
+// Package archived detection snapshots as a COCO or YOLO dataset for fine-tuning
+#[tauri::command]
+async fn export_dataset(
+    state: State<'_, AppState>,
+    range: Option<ExportRange>,
+    format: String,
+    out_dir: String,
+) -> Result<DatasetSummary, String> {
+    let format = DatasetFormat::parse(&format)?;
+    let range = range.unwrap_or_default();
+    let store = state.events.lock().await;
+    let events = store.events_between(
+        range.from.as_deref().unwrap_or(""),
+        range.to.as_deref().unwrap_or("9999-12-31T23:59:59Z"),
+    )?;
+
+    let mut samples: Vec<DatasetSample> = Vec::new();
+    for event in events.into_iter().filter(|event| event.event_type == "detection") {
+        let image_path = dataset::snapshot_path(&event.id);
+        if !image_path.exists() {
+            continue;
+        }
+        // Human corrections win; frames rated incorrect without a fix are left out
+        let boxes = match store.get_feedback(&event.id)? {
+            Some(EventFeedback { corrected_boxes: Some(boxes), .. }) => boxes,
+            Some(feedback) if feedback.verdict == "incorrect" => continue,
+            _ => event
+                .payload
+                .and_then(|payload| serde_json::from_value(payload["boxes"].clone()).ok())
+                .unwrap_or_default(),
+        };
+        samples.push(DatasetSample { event_id: event.id, image_path, boxes });
+    }
+    drop(store);
+    if samples.is_empty() {
+        return Err("No detection snapshots in this range".to_string());
+    }
+
+    let target = out_dir.clone();
+    let result =
+        tokio::task::spawn_blocking(move || dataset::export_dataset(&samples, format, std::path::Path::new(&out_dir)))
+            .await
+            .map_err(|e| format!("Dataset export failed: {}", e))?;
+    let data = format!("Detection snapshots with boxes ({})", format.name());
+    audit::record(AuditEntry::new(AuditAction::Export, data, target).outcome(&result));
+    let summary = result?;
+    println!("🏷️ Exported {} images / {} boxes to {}", summary.images, summary.annotations, summary.path);
+    Ok(summary)
+}
+
+// Exports, uploads, API reads and transmissions to external services, newest first
+#[tauri::command]
+async fn query_audit_log(state: State<'_, AppState>, filter: Option<AuditFilter>) -> Result<Vec<AuditEntry>, String> {
+    let store = state.events.lock().await;
+    audit::flush(&store)?;
+    store.audit_log(&filter.unwrap_or_default())
+}
+
+// Schema version, applied migrations and row counts of the event database
+#[tauri::command]
+async fn get_db_info(state: State<'_, AppState>) -> Result<DbInfo, String> {
+    state.events.lock().await.db_info()
+}
+
+#[tauri::command]
+async fn get_events(state: State<'_, AppState>, filter: Option<EventFilter>) -> Result<Vec<StoredEvent>, String> {
+    state.events.lock().await.list_events(&filter.unwrap_or_default())
+}
+
+#[tauri::command]
+async fn get_event(state: State<'_, AppState>, event_id: String) -> Result<StoredEvent, String> {
+    state
+        .events
+        .lock()
+        .await
+        .get_event(&event_id)?
+        .ok_or_else(|| format!("Event not found: {}", event_id))
+}
+
+// Rate an event and optionally correct its boxes or description
+#[tauri::command]
+async fn submit_feedback(
+    state: State<'_, AppState>,
+    event_id: String,
+    verdict: String,
+    corrected_labels: Option<Vec<BoundingBox>>,
+    corrected_description: Option<String>,
+) -> Result<EventFeedback, String> {
+    let feedback = EventFeedback {
+        event_id,
+        verdict: verdict.to_lowercase(),
+        corrected_boxes: corrected_labels,
+        corrected_description: corrected_description.filter(|d| !d.trim().is_empty()),
+        submitted_at: event_store::format_timestamp(chrono::Utc::now()),
+    };
+
+    let is_alert = {
+        let events = state.events.lock().await;
+        if !events.submit_feedback(&feedback)? {
+            return Err(format!("Event not found: {}", feedback.event_id));
+        }
+        events.get_event(&feedback.event_id)?.is_some_and(|e| e.event_type == "alert")
+    };
+    println!("📝 Feedback for {}: {}", feedback.event_id, feedback.verdict);
+    if is_alert {
+        relearn_suppression(&state).await?;
+    }
+    Ok(feedback)
+}
+
+// Rebuild learned suppressions from the alerts dismissed within the window
+async fn relearn_suppression(state: &AppState) -> Result<suppression::Learned, String> {
+    let config = settings::current().suppression;
+    let now = chrono::Utc::now();
+    let since = event_store::format_timestamp(now - chrono::Duration::days(config.window_days as i64));
+    let dismissed = state.events.lock().await.dismissed_alerts(&since)?;
+    let learned = suppression::learn(&config, &dismissed, now);
+    settings::update(|s| s.suppression.learned = learned.clone())?;
+    Ok(learned)
+}
+
+// Mark an alert a false positive: its incident is resolved, and alerts like it fade out once dismissed often enough
+#[tauri::command]
+async fn dismiss_alert(state: State<'_, AppState>, alert_id: String, by: Option<String>) -> Result<suppression::Learned, String> {
+    let now = chrono::Utc::now();
+    let alert = {
+        let events = state.events.lock().await;
+        let alert = events
+            .get_event(&alert_id)?
+            .filter(|e| e.event_type == "alert")
+            .ok_or_else(|| format!("Unknown alert: {}", alert_id))?;
+        events.submit_feedback(&EventFeedback {
+            event_id: alert_id.clone(),
+            verdict: "incorrect".to_string(),
+            corrected_boxes: None,
+            corrected_description: None,
+            submitted_at: event_store::format_timestamp(now),
+        })?;
+        if let Ok(incident_id) = events.incident_for_alert(&alert_id) {
+            events.resolve_incident(&incident_id, "false positive", by.as_deref(), now)?;
+        }
+        alert
+    };
+    println!("🙈 Dismissed as a false positive: {}", alert.description.unwrap_or(alert_id));
+    relearn_suppression(&state).await
+}
+
+// An alert's animated preview as a base64 GIF, when one was made
+#[tauri::command]
+async fn get_alert_preview(alert_id: String) -> Result<Option<String>, String> {
+    let path = preview::preview_path(&alert_id);
+    if !path.exists() {
+        return Ok(None);
+    }
+    let gif = encryption::read_file(&path)?;
+    Ok(Some(base64::Engine::encode(&base64::engine::general_purpose::STANDARD, gif)))
+}
+
+#[tauri::command]
+async fn get_preview_settings() -> Result<preview::PreviewSettings, String> {
+    Ok(settings::current().preview)
+}
+
+#[tauri::command]
+async fn set_preview_settings(preview: preview::PreviewSettings) -> Result<preview::PreviewSettings, String> {
+    preview.validate()?;
+    settings::update(|s| s.preview = preview.clone())?;
+    Ok(preview)
+}
+
+#[tauri::command]
+async fn get_suppression_settings() -> Result<suppression::SuppressionSettings, String> {
+    Ok(settings::current().suppression)
+}
+
+#[tauri::command]
+async fn set_suppression_settings(
+    state: State<'_, AppState>,
+    suppression: suppression::SuppressionSettings,
+) -> Result<suppression::SuppressionSettings, String> {
+    suppression.validate()?;
+    settings::update(|s| s.suppression = suppression.clone())?;
+    // A new window or count changes what the dismissals teach
+    relearn_suppression(&state).await?;
+    Ok(settings::current().suppression)
+}
+
+#[tauri::command]
+async fn get_feedback(state: State<'_, AppState>, event_id: String) -> Result<Option<EventFeedback>, String> {
+    state.events.lock().await.get_feedback(&event_id)
+}
+
+#[tauri::command]
+async fn get_feedback_stats(state: State<'_, AppState>) -> Result<Vec<FeedbackStats>, String> {
+    state.events.lock().await.feedback_stats()
+}
+
+// Answer a natural-language question about past events
+#[tauri::command]
+async fn ask_history(
+    state: State<'_, AppState>,
+    question: String,
+    use_llm: Option<bool>,
+) -> Result<HistoryAnswer, String> {
+    println!("📜 ask_history called: {}", question);
+    let now = chrono::Local::now();
+
+    // Built-in rules handle common questions; the local LLM writes SQL for the rest
+    let translated = if use_llm.unwrap_or(false) {
+        None
+    } else {
+        history_query::translate(&question, now)
+    };
+
+    let (sql, method, query) = match translated {
+        Some(query) => (query.sql.clone(), "rules", Some(query)),
+        None => {
+            let status = OllamaManager::check_status().await;
+            if !status.running || !status.model_ready {
+                return Err("Could not understand the question and Ollama is not ready to generate a query".to_string());
+            }
+
+            let prompt = history_query::build_sql_prompt(&question, now);
+            let result = OllamaManager::generate(&prompt, Vec::new(), 30000, None).await?;
+            let sql = history_query::validate_sql(result["response"].as_str().unwrap_or(""))?;
+            if encryption::enabled() {
+                history_query::check_sealed_columns(&sql)?;
+            }
+            (sql, "llm", None)
+        }
+    };
+
+    println!("📜 Running {} query: {}", method, sql);
+    let mut rows = state.events.lock().await.query_read_only(&sql)?;
+    if let Some(query) = &query {
+        history_query::filter_sealed(query, &mut rows);
+    }
+    let answer = history_query::summarize(query.as_ref().map(|q| &q.intent), &rows);
+
+    Ok(HistoryAnswer {
+        question,
+        answer,
+        method: method.to_string(),
+        sql,
+        window: query.map(|q| q.window),
+        columns: rows.columns,
+        rows: rows.rows,
+        truncated: rows.truncated,
+    })
+}
+
+// Semantic search over stored descriptions ("someone carrying a ladder")
+#[tauri::command]
+async fn search_events(
+    state: State<'_, AppState>,
+    text: String,
+    limit: Option<usize>,
+    min_score: Option<f32>,
+) -> Result<Vec<SearchHit>, String> {
+    println!("🔎 search_events called: {}", text);
+
+    let query = OllamaManager::embed(semantic_search::EMBEDDING_MODEL, vec![text])
+        .await?
+        .into_iter()
+        .next()
+        .ok_or("Embedding model returned no vector")?;
+
+    let events = state.events.lock().await;
+    let candidates = events.load_embeddings(semantic_search::EMBEDDING_MODEL)?;
+    let ranked = semantic_search::rank(
+        &query,
+        candidates,
+        limit.unwrap_or(20),
+        min_score.unwrap_or(semantic_search::DEFAULT_MIN_SCORE),
+    );
+
+    let mut hits = Vec::new();
+    for (event_id, score) in ranked {
+        if let Some(event) = events.get_event(&event_id)? {
+            hits.push(SearchHit { event, score });
+        }
+    }
+
+    Ok(hits)
+}
+
+// Embed descriptions that were stored before search was available (or while Ollama was down)
+#[tauri::command]
+async fn reindex_embeddings(state: State<'_, AppState>, batch_size: Option<usize>) -> Result<usize, String> {
+    let batch_size = batch_size.unwrap_or(32).max(1);
+    let mut indexed = 0;
+
+    loop {
+        let pending = state
+            .events
+            .lock()
+            .await
+            .events_missing_embeddings(semantic_search::EMBEDDING_MODEL, batch_size)?;
+        if pending.is_empty() {
+            break;
+        }
+
+        let texts = pending.iter().map(|(_, text)| text.clone()).collect();
+        let vectors = OllamaManager::embed(semantic_search::EMBEDDING_MODEL, texts).await?;
+        if vectors.len() != pending.len() {
+            return Err(format!("Expected {} embeddings, got {}", pending.len(), vectors.len()));
+        }
+
+        let events = state.events.lock().await;
+        for ((event_id, _), vector) in pending.iter().zip(vectors.iter()) {
+            events.store_embedding(event_id, semantic_search::EMBEDDING_MODEL, vector)?;
+        }
+        indexed += pending.len();
+    }
+
+    println!("🔎 Reindexed {} event descriptions", indexed);
+    Ok(indexed)
+}
+
+// Daily summary report (defaults to yesterday, HTML, with an LLM-written summary)
+#[tauri::command]
+async fn generate_report(
+    state: State<'_, AppState>,
+    date: Option<String>,
+    format: Option<String>,
+    use_llm: Option<bool>,
+) -> Result<DailyReport, String> {
+    let date = match date {
+        Some(date) => chrono::NaiveDate::parse_from_str(&date, "%Y-%m-%d")
+            .map_err(|e| format!("Invalid date '{}' (expected YYYY-MM-DD): {}", date, e))?,
+        None => chrono::Local::now().date_naive() - chrono::Duration::days(1),
+    };
+
+    println!("📊 generate_report called for {}", date);
+    daily_report::generate_report(
+        &state.events,
+        date,
+        format.as_deref().unwrap_or("html"),
+        use_llm.unwrap_or(true),
+    )
+    .await
+}
+
+// Prometheus metrics commands
+
+#[tauri::command]
+async fn get_metrics(state: State<'_, AppState>) -> Result<String, String> {
+    Ok(state.metrics.render())
+}
+
+// Lets the frontend report how many analysis jobs are waiting in its queue
+#[tauri::command]
+async fn report_queue_depth(state: State<'_, AppState>, depth: i64) -> Result<(), String> {
+    state.metrics.set_queue_depth(depth);
+    Ok(())
+}
+
+#[tauri::command]
+async fn start_metrics_server(state: State<'_, AppState>, port: Option<u16>) -> Result<String, String> {
+    start_metrics_endpoint(&state, port.unwrap_or(metrics::DEFAULT_METRICS_PORT)).await
+}
+
+#[tauri::command]
+async fn stop_metrics_server(state: State<'_, AppState>) -> Result<bool, String> {
+    match state.metrics_server.lock().await.take() {
+        Some(handle) => {
+            handle.abort();
+            println!("📈 Metrics endpoint stopped");
+            Ok(true)
+        }
+        None => Ok(false),
+    }
+}
+
+async fn start_metrics_endpoint(state: &AppState, port: u16) -> Result<String, String> {
+    let mut server = state.metrics_server.lock().await;
+    if let Some(handle) = server.take() {
+        handle.abort();
+    }
+
+    // Bind to localhost only - metrics are for local scraping
+    let address = format!("127.0.0.1:{}", port);
+    let listener = tokio::net::TcpListener::bind(&address)
+        .await
+        .map_err(|e| format!("Failed to bind metrics endpoint on {}: {}", address, e))?;
+
+    *server = Some(tauri::async_runtime::spawn(metrics::serve(state.metrics.clone(), listener)));
+
+    let url = format!("http://{}/metrics", address);
+    println!("📈 Metrics endpoint listening on {}", url);
+    Ok(url)
+}
+
+// REST API for other applications on the LAN; returns the URL and key to give them
+#[tauri::command]
+async fn start_api_server(state: State<'_, AppState>, port: Option<u16>) -> Result<ApiServerInfo, String> {
+    start_api_endpoint(&state, port.unwrap_or(api_server::DEFAULT_API_PORT)).await
+}
+
+#[tauri::command]
+async fn stop_api_server(state: State<'_, AppState>) -> Result<bool, String> {
+    match state.api_server.lock().await.take() {
+        Some(handle) => {
+            handle.abort();
+            println!("🌐 API server stopped");
+            Ok(true)
+        }
+        None => Ok(false),
+    }
+}
+
+async fn start_api_endpoint(state: &AppState, port: u16) -> Result<ApiServerInfo, String> {
+    let mut server = state.api_server.lock().await;
+    if let Some(handle) = server.take() {
+        handle.abort();
+    }
+
+    let api_key = api_server::api_key()?;
+    let listener = api_server::bind(port).await?;
+    let address = listener.local_addr().map_err(|e| e.to_string())?;
+    *server = Some(tauri::async_runtime::spawn(api_server::serve(state.clone(), listener)));
+
+    let url = format!("http://{}", address);
+    println!("🌐 API server listening on {}", url);
+    Ok(ApiServerInfo { url, api_key })
+}
+
+#[tauri::command]
+async fn list_api_keys() -> Result<Vec<ApiKey>, String> {
+    Ok(settings::current().api_keys)
+}
+
+// The key is returned only here; the list shows its prefix
+#[tauri::command]
+async fn create_api_key(name: String, role: Role) -> Result<CreatedApiKey, String> {
+    let created = api_keys::create(&name, role)?;
+    println!("🔑 API key '{}' created ({})", created.info.name, role.as_str());
+    Ok(created)
+}
+
+#[tauri::command]
+async fn revoke_api_key(id: String) -> Result<bool, String> {
+    api_keys::revoke(&id)
+}
+
+// Streaming frame submission over gRPC; uses the same keys as the REST API
+#[tauri::command]
+async fn start_grpc_server(state: State<'_, AppState>, port: Option<u16>) -> Result<ApiServerInfo, String> {
+    start_grpc_endpoint(&state, port.unwrap_or(grpc_server::DEFAULT_GRPC_PORT)).await
+}
+
+#[tauri::command]
+async fn stop_grpc_server(state: State<'_, AppState>) -> Result<bool, String> {
+    match state.grpc_server.lock().await.take() {
+        Some(handle) => {
+            handle.abort();
+            println!("📡 gRPC server stopped");
+            Ok(true)
+        }
+        None => Ok(false),
     }
+}
 
-    let response_text = response.text().await
-        .map_err(|e| {
-            println!("Failed to read response text: {}", e);
-            format!("Failed to read response: {}", e)
-        })?;
+async fn start_grpc_endpoint(state: &AppState, port: u16) -> Result<ApiServerInfo, String> {
+    if !grpc_server::compiled() {
+        return Err("This build does not include gRPC support (rebuild with --features grpc)".to_string());
+    }
+    let mut server = state.grpc_server.lock().await;
+    if let Some(handle) = server.take() {
+        handle.abort();
+    }
 
-    println!("Response text length: {}", response_text.len());
+    let api_key = api_server::api_key()?;
+    let listener = api_server::bind(port).await?;
+    let address = listener.local_addr().map_err(|e| e.to_string())?;
+    *server = Some(tauri::async_runtime::spawn(grpc_server::serve(state.clone(), listener)));
 
-    let result: serde_json::Value = serde_json::from_str(&response_text)
-        .map_err(|e| {
-            println!("Failed to parse JSON response: {}", e);
-            println!("Response was: {}", response_text);
-            format!("Failed to parse response: {}", e)
-        })?;
+    let url = format!("http://{}", address);
+    println!("📡 gRPC server listening on {}", url);
+    Ok(ApiServerInfo { url, api_key })
+}
 
-    let description = result["response"]
-        .as_str()
-        .unwrap_or("No description available")
-        .to_string();
+// Same path as the REST /trigger endpoint, for integrations that drive the app directly
+#[tauri::command]
+async fn trigger_analysis(state: State<'_, AppState>, request: TriggerRequest) -> Result<TriggerResult, String> {
+    trigger::run(&state, request).await
+}
 
-    println!("Analysis successful, description length: {}", description.len());
+#[tauri::command]
+async fn get_trigger_events(state: State<'_, AppState>, external_id: String) -> Result<Vec<StoredEvent>, String> {
+    state.events.lock().await.trigger_events(&external_id)
+}
 
-    Ok(AnalyzeResponse {
-        description,
-        error: None,
-    })
+// Hub mode: pull another analyzer's events into this store; re-adding a name updates it
+#[tauri::command]
+async fn add_remote_instance(state: State<'_, AppState>, instance: RemoteInstance) -> Result<Vec<InstanceStatus>, String> {
+    hub::probe(&instance).await?;
+    settings::update(|s| {
+        s.remote_instances.retain(|i| i.name != instance.name);
+        s.remote_instances.push(instance.clone());
+    })?;
+    println!("🏬 Pulling events from {} ({})", instance.name, instance.url);
+    let mut hub = state.hub.lock().await;
+    hub.start(instance, state.events.clone());
+    Ok(hub.statuses())
 }
 
 #[tauri::command]
-async fn capture_camera_frame() -> Result<String, String> {
-    // This will be handled by the frontend using WebRTC
-    // Returning a placeholder for now
-    Ok("Camera capture handled by frontend".to_string())
+async fn remove_remote_instance(state: State<'_, AppState>, name: String) -> Result<bool, String> {
+    settings::update(|s| s.remote_instances.retain(|i| i.name != name))?;
+    Ok(state.hub.lock().await.stop(&name))
 }
 
-// New command for YOLO detection
 #[tauri::command]
-async fn yolo_detect(
-    state: State<'_, AppState>,
-    frame_base64: String,
-    _model: Option<String>,
-) -> Result<DetectionData, String> {
-    let detector = state.yolo.lock().await;
-    detector.detect(&frame_base64).await
+async fn list_remote_instances(state: State<'_, AppState>) -> Result<Vec<InstanceStatus>, String> {
+    Ok(state.hub.lock().await.statuses())
 }
 
-// New command for event-triggered LLaVA analysis
 #[tauri::command]
-async fn analyze_with_llava(
-    _state: State<'_, AppState>,
-    frame_base64: String,
-    prompt: String,
-    timeout: Option<u64>,
-) -> Result<serde_json::Value, String> {
-    println!("analyze_with_llava called with custom prompt");
+async fn get_object_storage_settings() -> Result<ObjectStorageSettings, String> {
+    Ok(settings::current().object_storage)
+}
 
-    // Check if Ollama is running
-    let status = OllamaManager::check_status().await;
-    if !status.running || !status.model_ready {
-        return Err("Ollama not ready".to_string());
+#[tauri::command]
+async fn update_object_storage_settings(config: ObjectStorageSettings) -> Result<ObjectStorageSettings, String> {
+    if config.enabled {
+        config.validate()?;
     }
+    settings::update(|s| s.object_storage = config.clone())?;
+    println!("☁️ Object storage sync {}", if config.enabled { "enabled" } else { "disabled" });
+    Ok(config)
+}
 
-    // Set timeout (default 30 seconds to handle LLaVA processing)
-    let timeout_duration = std::time::Duration::from_millis(timeout.unwrap_or(30000));
-
-    let client = reqwest::Client::builder()
-        .timeout(timeout_duration)
-        .build()
-        .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
-
-    // Use the installed llava:7b model with optimized settings
-    let json_payload = serde_json::json!({
-        "model": "llava:7b",
-        "prompt": prompt,
-        "images": [frame_base64],
-        "stream": false,
-        "keep_alive": "5m",  // Keep model loaded for 5 minutes
-        "options": {
-            "temperature": 0.3,  // Lower temperature for more consistent output
-            "num_predict": 200,  // Reduce response length for faster processing
-            "num_ctx": 2048,     // Smaller context window for vision tasks
-            "num_thread": 4      // Limit threads to prevent overload
-        }
-    });
+// Upload anything new right away instead of waiting for the next scheduled sync
+#[tauri::command]
+async fn sync_object_storage(state: State<'_, AppState>) -> Result<SyncReport, String> {
+    object_sync::sync_once(&settings::current().object_storage, &state.events).await
+}
 
-    let response = client
-        .post("http://127.0.0.1:11434/api/generate")
-        .json(&json_payload)
-        .send()
-        .await
-        .map_err(|e| format!("Failed to analyze: {}", e))?;
+#[tauri::command]
+async fn get_object_sync_status() -> Result<SyncReport, String> {
+    Ok(object_sync::status())
+}
 
-    if !response.status().is_success() {
-        return Err(format!("Analysis failed: {}", response.status()));
-    }
+#[tauri::command]
+async fn get_mqtt_settings() -> Result<MqttSettings, String> {
+    Ok(settings::current().mqtt)
+}
 
-    let result: serde_json::Value = response.json().await
-        .map_err(|e| format!("Failed to parse response: {}", e))?;
+// Publish events to an MQTT broker in Frigate's format; remembered across restarts
+#[tauri::command]
+async fn start_mqtt_publisher(state: State<'_, AppState>, config: MqttSettings) -> Result<MqttSettings, String> {
+    config.validate()?;
+    local_only::check_url(&format!("mqtt://{}:{}", config.host, config.port))?;
+    let config = MqttSettings { enabled: true, ..config };
+    settings::update(|s| s.mqtt = config.clone())?;
+    start_mqtt(&state, config.clone()).await;
+    Ok(config)
+}
 
-    // Try to parse the LLaVA response as JSON if possible
-    if let Some(response_text) = result["response"].as_str() {
-        // Try to parse as JSON first
-        if let Ok(json) = serde_json::from_str::<serde_json::Value>(response_text) {
-            return Ok(json);
+#[tauri::command]
+async fn stop_mqtt_publisher(state: State<'_, AppState>) -> Result<bool, String> {
+    settings::update(|s| s.mqtt.enabled = false)?;
+    match state.mqtt_publisher.lock().await.take() {
+        Some(handle) => {
+            handle.abort();
+            println!("📨 MQTT publisher stopped");
+            Ok(true)
         }
+        None => Ok(false),
     }
+}
 
-    Ok(result)
+async fn start_mqtt(state: &AppState, config: MqttSettings) {
+    let mut publisher = state.mqtt_publisher.lock().await;
+    if let Some(handle) = publisher.take() {
+        handle.abort();
+    }
+    println!("📨 Publishing Frigate events to {}:{} under {}/", config.host, config.port, config.topic_prefix);
+    *publisher = Some(tauri::async_runtime::spawn(frigate_mqtt::run(config, state.events.clone())));
 }
 
 // Phase 1 POC: Moondream 3 MoE Integration Commands
@@ -230,10 +3072,30 @@ async fn analyze_with_moondream(
     state: State<'_, AppState>,
     frame_base64: String,
     prompt: String,
+    roi: Option<RegionOfInterest>,
 ) -> Result<AnalysisResult, String> {
     println!("🌙 analyze_with_moondream called");
-    let moondream = state.moondream.lock().await;
-    moondream.query(frame_base64, prompt).await
+    pause::check()?;
+    let frame_base64 = frame_processor::prepare_frame(frame_base64, roi.as_ref())?;
+    let _in_flight = state.metrics.track_in_flight();
+    let start_time = std::time::Instant::now();
+    let result = state.moondream.query(frame_base64, prompt.clone()).await;
+    state.bus.publish(BusEvent::analysis(
+        "moondream",
+        start_time.elapsed(),
+        result.as_ref().map(|r| r.error.is_none()).unwrap_or(false),
+    ));
+    let result = result.inspect_err(|e| app_status::record_error("moondream", e))?;
+    if let Some(error) = &result.error {
+        app_status::record_error("moondream", error);
+    }
+
+    if result.error.is_none() {
+        let event_id = store_analysis(&state, "moondream", &prompt, &result.response, result.structured_data.clone()).await;
+        store_usage(&state, event_id.as_deref(), "moondream", "moondream", result.usage.as_ref()).await;
+    }
+
+    Ok(result)
 }
 
 #[tauri::command]
@@ -243,8 +3105,8 @@ async fn moondream_caption(
     length: Option<String>,
 ) -> Result<AnalysisResult, String> {
     println!("🌙 moondream_caption called");
-    let moondream = state.moondream.lock().await;
-    moondream.caption(frame_base64, length).await
+    let frame_base64 = frame_processor::prepare_frame(frame_base64, None)?;
+    state.moondream.caption(frame_base64, length).await
 }
 
 #[tauri::command]
@@ -254,8 +3116,8 @@ async fn moondream_detect(
     object: String,
 ) -> Result<AnalysisResult, String> {
     println!("🌙 moondream_detect called");
-    let moondream = state.moondream.lock().await;
-    moondream.detect(frame_base64, object).await
+    let frame_base64 = frame_processor::prepare_frame(frame_base64, None)?;
+    state.moondream.detect(frame_base64, object).await
 }
 
 #[tauri::command]
@@ -265,8 +3127,62 @@ async fn moondream_point(
     object: String,
 ) -> Result<AnalysisResult, String> {
     println!("🌙 moondream_point called");
-    let moondream = state.moondream.lock().await;
-    moondream.point(frame_base64, object).await
+    let frame_base64 = frame_processor::prepare_frame(frame_base64, None)?;
+    state.moondream.point(frame_base64, object).await
+}
+
+// Where customers are looking; faces are found automatically unless passed as fractions of the frame
+#[tauri::command]
+async fn moondream_gaze(
+    state: State<'_, AppState>,
+    frame_base64: String,
+    faces: Option<Vec<moondream_manager::BoundingBox>>,
+) -> Result<AnalysisResult, String> {
+    println!("🌙 moondream_gaze called");
+    let frame_base64 = frame_processor::prepare_frame(frame_base64, None)?;
+    state.moondream.gaze(frame_base64, faces).await
+}
+
+// Run YOLO and Moondream detect for one class and pair their boxes, to catch YOLO misses and VLM hallucinations
+#[tauri::command]
+async fn fused_detect(
+    state: State<'_, AppState>,
+    frame_base64: String,
+    class_name: String,
+    iou_threshold: Option<f32>,
+) -> Result<fusion::FusedDetections, String> {
+    println!("🔀 fused_detect called for {}", class_name);
+    pause::check()?;
+    let frame_base64 = frame_processor::prepare_frame(frame_base64, None)?;
+    let (detection, vlm) = tokio::join!(
+        state.yolo.detect(&frame_base64),
+        state.moondream.detect(frame_base64.clone(), class_name.clone())
+    );
+    let detection = detection?;
+    let vlm = vlm.inspect_err(|e| app_status::record_error("moondream", e))?;
+    if let Some(error) = &vlm.error {
+        app_status::record_error("moondream", error);
+        return Err(error.clone());
+    }
+    let threshold = iou_threshold.unwrap_or(fusion::DEFAULT_IOU_THRESHOLD).clamp(0.0, 1.0);
+    let fused = fusion::associate(&class_name, &detection.boxes, fusion::vlm_detections(vlm.structured_data.as_ref()), threshold);
+    println!(
+        "🔀 {}: {} confirmed, {} YOLO-only, {} VLM-only",
+        class_name, fused.matched.len(), fused.yolo_only.len(), fused.vlm_only.len()
+    );
+    Ok(fused)
+}
+
+// Publish a scene alert once a second look (when the verification policy asks for one) has had its say
+async fn raise_scene_alert(state: AppState, scene_type: String, alert: retail_scenes::SceneAlert, mut payload: serde_json::Value, frame_base64: String) {
+    let prompt = scene_prompts::current(&scene_type).prompt;
+    let second_look = verification::verify(&state, None, "moondream", &prompt, &frame_base64, None, alert.priority, |_, data| {
+        retail_scenes::alert(&scene_type, data).is_some_and(|again| again.kind == alert.kind && again.hazard_type == alert.hazard_type)
+    })
+    .await;
+    verification::attach(&mut payload, second_look);
+    let alert_id = state.bus.alert(None, "moondream", &alert.description, payload, Some(&frame_base64)).await;
+    println!("🛒 {} (alert {})", alert.description, alert_id);
 }
 
 #[tauri::command]
@@ -274,10 +3190,73 @@ async fn moondream_analyze_retail(
     state: State<'_, AppState>,
     frame_base64: String,
     scene_type: String,
+    roi: Option<RegionOfInterest>,
 ) -> Result<AnalysisResult, String> {
     println!("🌙 moondream_analyze_retail called for scene: {}", scene_type);
-    let moondream = state.moondream.lock().await;
-    moondream.analyze_retail_scene(frame_base64, &scene_type).await
+    let frame_base64 = frame_processor::prepare_frame(frame_base64, roi.as_ref())?;
+    let _in_flight = state.metrics.track_in_flight();
+    let start_time = std::time::Instant::now();
+    let result = state.moondream.analyze_retail_scene(frame_base64.clone(), &scene_type).await;
+    state.bus.publish(BusEvent::analysis(
+        "moondream",
+        start_time.elapsed(),
+        result.as_ref().map(|r| r.error.is_none()).unwrap_or(false),
+    ));
+    let mut result = result.inspect_err(|e| app_status::record_error("moondream", e))?;
+    if let Some(error) = &result.error {
+        app_status::record_error("moondream", error);
+    }
+
+    if result.error.is_none() {
+        // Answers that don't fit the scene's schema are kept with the reasons, and never alert
+        if let Some(data) = result.structured_data.as_mut() {
+            if let Err(errors) = retail_scenes::validate(&scene_type, data) {
+                eprintln!("🌙 {} answer doesn't match its schema: {}", scene_type, errors.join("; "));
+                if let Some(fields) = data.as_object_mut() {
+                    fields.insert("schema_errors".to_string(), serde_json::json!(errors));
+                }
+            }
+        }
+
+        let prompt = format!("retail:{}@v{}", scene_type, scene_prompts::current(&scene_type).version);
+        let event_id = store_analysis(&state, "moondream", &prompt, &result.response, result.structured_data.clone()).await;
+        store_usage(&state, event_id.as_deref(), "moondream", "moondream", result.usage.as_ref()).await;
+
+        if let Some(alert) = result.structured_data.as_ref().and_then(|data| retail_scenes::alert(&scene_type, data)) {
+            let payload = serde_json::json!({
+                "kind": alert.kind,
+                "priority": alert.priority,
+                "scene_type": scene_type,
+                "hazard_type": alert.hazard_type,
+                "analysis_event_id": event_id,
+                "analysis": result.structured_data,
+            });
+            // A delayed second look would hold up the answer, so it runs in the background
+            let raise = raise_scene_alert(state.inner().clone(), scene_type.clone(), alert.clone(), payload, frame_base64.clone());
+            if settings::current().verification.applies_to(alert.priority) {
+                tokio::spawn(raise);
+            } else {
+                raise.await;
+            }
+        } else if result.structured_data.as_ref().is_some_and(|data| retail_scenes::all_clear(&scene_type, data)) {
+            // A clean look at the scene ends the incidents its earlier alerts opened
+            let cleared = state.events.lock().await.resolve_cleared_incidents(&scene_type, None, chrono::Utc::now());
+            match cleared {
+                Ok(cleared) => cleared.iter().for_each(|i| println!("✅ Incident cleared: {}", i.description)),
+                Err(e) => eprintln!("Failed to resolve cleared incidents: {}", e),
+            }
+        }
+    }
+
+    Ok(result)
+}
+
+// Setup checklist: camera, Ollama and its model, Moondream key, disk space and data folder
+#[tauri::command]
+async fn run_diagnostics(state: State<'_, AppState>) -> Result<DiagnosticsReport, String> {
+    let report = diagnostics::run(&state.moondream).await;
+    println!("🩺 Diagnostics: {}", if report.ready { "ready" } else { "setup incomplete" });
+    Ok(report)
 }
 
 #[tauri::command]
@@ -285,8 +3264,7 @@ async fn check_moondream_status(
     state: State<'_, AppState>,
 ) -> Result<serde_json::Value, String> {
     println!("🌙 check_moondream_status called");
-    let moondream = state.moondream.lock().await;
-    moondream.check_status().await
+    state.moondream.check_status().await
 }
 
 // A/B Testing Command - Compare LLaVA vs Moondream
@@ -295,26 +3273,255 @@ async fn analyze_ab_test(
     state: State<'_, AppState>,
     frame_base64: String,
     prompt: String,
+    roi: Option<RegionOfInterest>,
+    providers: Option<Vec<String>>,
 ) -> Result<serde_json::Value, String> {
-    println!("🔬 Running A/B test: LLaVA vs Moondream");
+    let providers = providers.unwrap_or_else(|| vec!["llava".to_string(), "moondream".to_string()]);
+    if providers.len() < 2 {
+        return Err("An A/B test needs at least two providers".to_string());
+    }
+    {
+        let registry = state.providers.read().await;
+        if let Some(unknown) = providers.iter().find(|p| registry.get(p).is_none()) {
+            return Err(format!("Unknown provider: {}", unknown));
+        }
+    }
+    println!("🔬 Running A/B test: {}", providers.join(" vs "));
 
+    // Each provider masks and crops the frame itself; both steps are deterministic
     let start_time = std::time::Instant::now();
 
-    // Run both analyses concurrently
-    let (llava_result, moondream_result) = tokio::join!(
-        analyze_with_llava_internal(&state, frame_base64.clone(), prompt.clone()),
-        analyze_with_moondream_internal(&state, frame_base64, prompt)
-    );
+    // Run all analyses concurrently
+    let results: Vec<serde_json::Value> = futures_util::future::join_all(providers.iter().map(|provider| {
+        let (frame, prompt, roi) = (frame_base64.clone(), prompt.clone(), roi.clone());
+        let state = &state;
+        async move {
+            match provider.as_str() {
+                "llava" => analyze_with_llava_internal(state, frame, prompt, roi).await,
+                "moondream" => analyze_with_moondream_internal(state, frame, prompt, roi).await,
+                other => analyze_with_provider_internal(state, other.to_string(), frame, prompt, roi).await,
+            }
+        }
+    }))
+    .await;
 
     let total_time = start_time.elapsed().as_millis() as u64;
+    let test_id = uuid::Uuid::new_v4().to_string();
+    let timestamp = chrono::Utc::now().to_rfc3339();
 
-    Ok(serde_json::json!({
-        "timestamp": chrono::Utc::now().to_rfc3339(),
-        "llava": llava_result,
-        "moondream": moondream_result,
+    // Persist the comparison so it can be rated and included in win-rate stats
+    let record = AbTestRecord {
+        test_id: test_id.clone(),
+        timestamp: event_store::format_timestamp(chrono::Utc::now()),
+        prompt,
+        total_time_ms: total_time,
+        outcomes: results.iter().map(provider_outcome).collect(),
+        winner: None,
+        notes: None,
+        rated_at: None,
+    };
+    if let Err(e) = state.events.lock().await.save_ab_test(&record) {
+        eprintln!("Failed to store A/B test: {}", e);
+    }
+
+    // Each provider's result is keyed by its name, next to the test metadata
+    let mut response = serde_json::json!({
+        "timestamp": timestamp,
+        "providers": providers,
         "total_comparison_time_ms": total_time,
-        "test_id": uuid::Uuid::new_v4().to_string()
-    }))
+        "test_id": test_id
+    });
+    for (provider, result) in providers.iter().zip(results) {
+        response[provider.as_str()] = result;
+    }
+
+    Ok(response)
+}
+
+// Convert an internal A/B helper result into a stored outcome
+fn provider_outcome(result: &serde_json::Value) -> ProviderOutcome {
+    ProviderOutcome {
+        provider: result["provider"].as_str().unwrap_or("unknown").to_string(),
+        success: result["success"].as_bool().unwrap_or(false),
+        latency_ms: result["latency_ms"].as_u64().unwrap_or(0),
+        result: result.get("result").cloned(),
+        error: result["error"].as_str().map(|e| e.to_string()),
+    }
+}
+
+// Record a human judgment for a stored A/B comparison
+#[tauri::command]
+async fn rate_ab_result(
+    state: State<'_, AppState>,
+    test_id: String,
+    winner: String,
+    notes: Option<String>,
+) -> Result<AbTestRecord, String> {
+    let events = state.events.lock().await;
+    let record = events
+        .get_ab_test(&test_id)?
+        .ok_or_else(|| format!("A/B test not found: {}", test_id))?;
+    ab_testing::validate_winner(&winner, &record)?;
+
+    events.rate_ab_test(&test_id, &winner, notes.as_deref())?;
+    println!("🔬 A/B test {} rated: {}", test_id, winner);
+
+    events
+        .get_ab_test(&test_id)?
+        .ok_or_else(|| format!("A/B test not found: {}", test_id))
+}
+
+#[tauri::command]
+async fn list_ab_results(
+    state: State<'_, AppState>,
+    from: Option<String>,
+    to: Option<String>,
+) -> Result<Vec<AbTestRecord>, String> {
+    state.events.lock().await.list_ab_tests(from.as_deref(), to.as_deref())
+}
+
+// Win rates and latency deltas per provider (optionally within a time range)
+#[tauri::command]
+async fn get_ab_stats(
+    state: State<'_, AppState>,
+    from: Option<String>,
+    to: Option<String>,
+) -> Result<AbStats, String> {
+    let records = state.events.lock().await.list_ab_tests(from.as_deref(), to.as_deref())?;
+    Ok(ab_testing::compute_stats(&records))
+}
+
+// Benchmark: run test frames through YOLO and each VLM and report latency percentiles
+#[tauri::command]
+async fn run_benchmark(
+    state: State<'_, AppState>,
+    frames: Option<Vec<String>>,
+    frames_dir: Option<String>,
+    providers: Option<Vec<String>>,
+    iterations: Option<usize>,
+    prompt: Option<String>,
+) -> Result<BenchmarkReport, String> {
+    let mut test_frames = frames.unwrap_or_default();
+    if let Some(dir) = frames_dir {
+        for path in frame_processor::list_image_files(std::path::Path::new(&dir))? {
+            test_frames.push(frame_processor::load_frame_file(&path)?);
+        }
+    }
+    if test_frames.is_empty() {
+        return Err("No test frames provided".to_string());
+    }
+
+    let providers = providers.unwrap_or_else(|| benchmark::SUPPORTED_PROVIDERS.iter().map(|p| p.to_string()).collect());
+    if let Some(unknown) = providers.iter().find(|p| !benchmark::SUPPORTED_PROVIDERS.contains(&p.as_str())) {
+        return Err(format!("Unknown provider: {}", unknown));
+    }
+
+    let iterations = iterations.unwrap_or(1).max(1);
+    let prompt = prompt.unwrap_or_else(|| benchmark::DEFAULT_BENCHMARK_PROMPT.to_string());
+    println!(
+        "⏱️ Running benchmark: {} frames x {} iterations on {:?}",
+        test_frames.len(), iterations, providers
+    );
+
+    let mut results = Vec::new();
+    for provider in &providers {
+        let mut samples = Vec::new();
+        let provider_start = std::time::Instant::now();
+
+        for _ in 0..iterations {
+            for frame in &test_frames {
+                let start_time = std::time::Instant::now();
+                let outcome = benchmark_call(&state, provider, frame, &prompt).await;
+                samples.push(benchmark::Sample {
+                    latency_ms: start_time.elapsed().as_millis() as u64,
+                    success: outcome.is_ok(),
+                    error: outcome.err(),
+                });
+            }
+        }
+
+        let summary = benchmark::summarize(provider, &samples, provider_start.elapsed().as_millis() as u64);
+        println!(
+            "⏱️ {}: p50={}ms p95={}ms failures={}/{}",
+            provider, summary.latency.p50_ms, summary.latency.p95_ms, summary.failures, summary.runs
+        );
+        results.push(summary);
+    }
+
+    Ok(BenchmarkReport {
+        benchmark_id: uuid::Uuid::new_v4().to_string(),
+        timestamp: chrono::Utc::now().to_rfc3339(),
+        frame_count: test_frames.len(),
+        iterations,
+        providers: results,
+    })
+}
+
+// One benchmark call; results are discarded and nothing is written to the event store
+async fn benchmark_call(state: &AppState, provider: &str, frame: &str, prompt: &str) -> Result<(), String> {
+    match provider {
+        "yolo" => state.yolo.detect(frame).await.map(|_| ()),
+        "llava" => OllamaManager::generate(prompt, vec![frame.to_string()], 60000, None).await.map(|_| ()),
+        "moondream" => {
+            let result = state.moondream.query(frame.to_string(), prompt.to_string()).await?;
+            match result.error {
+                Some(error) => Err(error),
+                None => Ok(()),
+            }
+        }
+        other => Err(format!("Unknown provider: {}", other)),
+    }
+}
+
+// Accuracy against a labeled folder: ground_truth.json plus the images it names
+#[tauri::command]
+async fn run_evaluation(
+    state: State<'_, AppState>,
+    dir: String,
+    iou_threshold: Option<f32>,
+) -> Result<EvaluationReport, String> {
+    let dir = std::path::PathBuf::from(dir);
+    let labels = evaluation::load_ground_truth(&dir)?;
+    if labels.is_empty() {
+        return Err("Ground truth has no labeled images".to_string());
+    }
+    let iou_threshold = iou_threshold.unwrap_or(evaluation::DEFAULT_IOU_THRESHOLD).clamp(0.05, 0.95);
+    println!("🎯 Evaluating {} labeled images from {}", labels.len(), dir.display());
+
+    let mut tally = evaluation::Tally::default();
+    let mut errors = Vec::new();
+    for label in &labels {
+        let frame = match frame_processor::load_frame_file(&dir.join(&label.image)) {
+            Ok(frame) => frame,
+            Err(e) => {
+                errors.push(e);
+                continue;
+            }
+        };
+
+        if let Some(truth) = &label.boxes {
+            match state.yolo.detect(&frame).await {
+                Ok(detection) => tally.add_detections(truth, &detection.boxes, iou_threshold),
+                Err(e) => errors.push(format!("{}: detection failed: {}", label.image, e)),
+            }
+        }
+
+        if let (Some(scene_type), Some(expected)) = (&label.scene_type, &label.expected) {
+            let result = state.moondream.analyze_retail_scene(frame, scene_type).await;
+            match result {
+                Ok(result) if result.error.is_none() => tally.add_fields(expected, result.structured_data.as_ref()),
+                Ok(result) => errors.push(format!("{}: {}", label.image, result.error.unwrap_or_default())),
+                Err(e) => errors.push(format!("{}: retail analysis failed: {}", label.image, e)),
+            }
+        }
+    }
+
+    let report = tally.report(labels.len(), iou_threshold, errors);
+    println!(
+        "🎯 Detection precision={:.2} recall={:.2}, field accuracy={:.2}",
+        report.detection_overall.precision, report.detection_overall.recall, report.field_accuracy
+    );
+    Ok(report)
 }
 
 // Internal helper functions for A/B testing
@@ -322,17 +3529,21 @@ async fn analyze_with_llava_internal(
     state: &State<'_, AppState>,
     frame_base64: String,
     prompt: String,
+    roi: Option<RegionOfInterest>,
 ) -> serde_json::Value {
-    match analyze_with_llava(state.clone(), frame_base64, prompt, Some(30000)).await {
+    let start_time = std::time::Instant::now();
+    match analyze_with_llava(state.clone(), frame_base64, prompt, Some(30000), roi, None).await {
         Ok(result) => serde_json::json!({
             "success": true,
             "result": result,
-            "provider": "llava"
+            "provider": "llava",
+            "latency_ms": start_time.elapsed().as_millis() as u64
         }),
         Err(error) => serde_json::json!({
             "success": false,
             "error": error,
-            "provider": "llava"
+            "provider": "llava",
+            "latency_ms": start_time.elapsed().as_millis() as u64
         })
     }
 }
@@ -341,50 +3552,177 @@ async fn analyze_with_moondream_internal(
     state: &State<'_, AppState>,
     frame_base64: String,
     prompt: String,
+    roi: Option<RegionOfInterest>,
+) -> serde_json::Value {
+    let start_time = std::time::Instant::now();
+    match analyze_with_moondream(state.clone(), frame_base64, prompt, roi).await {
+        // Moondream reports API errors inside the result rather than as Err
+        Ok(result) => serde_json::json!({
+            "success": result.error.is_none(),
+            "error": result.error.clone(),
+            "result": result,
+            "provider": "moondream",
+            "latency_ms": start_time.elapsed().as_millis() as u64
+        }),
+        Err(error) => serde_json::json!({
+            "success": false,
+            "error": error,
+            "provider": "moondream",
+            "latency_ms": start_time.elapsed().as_millis() as u64
+        })
+    }
+}
+
+async fn analyze_with_provider_internal(
+    state: &State<'_, AppState>,
+    provider: String,
+    frame_base64: String,
+    prompt: String,
+    roi: Option<RegionOfInterest>,
 ) -> serde_json::Value {
-    match analyze_with_moondream(state.clone(), frame_base64, prompt).await {
+    let start_time = std::time::Instant::now();
+    match analyze_with_provider(state.clone(), provider.clone(), frame_base64, prompt, Some(30000), None, roi).await {
         Ok(result) => serde_json::json!({
             "success": true,
             "result": result,
-            "provider": "moondream"
+            "provider": provider,
+            "latency_ms": start_time.elapsed().as_millis() as u64
         }),
         Err(error) => serde_json::json!({
             "success": false,
             "error": error,
-            "provider": "moondream"
+            "provider": provider,
+            "latency_ms": start_time.elapsed().as_millis() as u64
         })
     }
 }
 
+// Start Ollama and pull the models it needs
+async fn prepare_ollama(state: &AppState, model: &str) -> Result<(), String> {
+    println!("Starting embedded Ollama...");
+    state.ollama.lock().await.start().await
+        .map_err(|e| format!("Failed to start Ollama: {}", e))?;
+    println!("Ollama started successfully");
+
+    state.ollama.lock().await.pull_model(model).await
+        .map_err(|e| format!("Failed to pull model: {}", e))?;
+    println!("Model pulled successfully, warming up...");
+
+    // The embedding model powers semantic event search
+    if let Err(e) = state.ollama.lock().await.pull_model(semantic_search::EMBEDDING_MODEL).await {
+        eprintln!("Failed to pull embedding model: {}", e);
+    }
+    Ok(())
+}
+
+// Bring the backend up, warm the model and tell the UI when analysis will be fast
+async fn start_backend(app: &tauri::AppHandle, state: &AppState) {
+    let model = settings::current().vision_model;
+    let status = if mock::enabled() {
+        BackendReady {
+            backend: mock::PROVIDER_NAME.to_string(),
+            model: mock::PROVIDER_NAME.to_string(),
+            ready: true,
+            load_ms: 0,
+            first_inference_ms: 0,
+            error: None,
+        }
+    } else {
+        match prepare_ollama(state, &model).await {
+            Ok(()) => warmup::warm_up_llava().await,
+            Err(e) => BackendReady::failed(&model, 0, e),
+        }
+    };
+
+    if status.ready {
+        println!(
+            "🔥 {} warmed up (load {} ms, first inference {} ms)",
+            status.model, status.load_ms, status.first_inference_ms
+        );
+        app_status::record_ok("ollama");
+    } else {
+        eprintln!("Backend warm-up failed: {:?}", status.error);
+        app_status::record_error("ollama", status.error.as_deref().unwrap_or("Warm-up failed"));
+    }
+
+    *state.backend_ready.lock().await = Some(status.clone());
+    if let Err(e) = app.emit(warmup::READY_EVENT, &status) {
+        eprintln!("Failed to emit backend-ready event: {}", e);
+    }
+}
+
+// Stop background work, write what's still queued and take Ollama down with the app
+async fn shutdown(state: &AppState) {
+    println!("👋 Shutting down...");
+    for task in state.background_tasks.lock().unwrap_or_else(|e| e.into_inner()).drain(..) {
+        task.abort();
+    }
+    for server in [&state.metrics_server, &state.api_server, &state.grpc_server, &state.mqtt_publisher] {
+        if let Some(handle) = server.lock().await.take() {
+            handle.abort();
+        }
+    }
+    state.hub.lock().await.stop_all();
+    // A paused pull holds the Ollama lock
+    downloads::cancel_all();
+
+    if let Err(e) = audit::flush(&*state.events.lock().await) {
+        eprintln!("Audit: {}", e);
+    }
+    state.ollama.lock().await.stop();
+}
+
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     tauri::Builder::default()
         .plugin(tauri_plugin_opener::init())
+        .plugin(tauri_plugin_notification::init())
         .setup(|app| {
-            let ollama_manager = OllamaManager::new(&app.handle());
-            let mut yolo_detector = YoloDetector::new();
+            settings::init(AppSettings::load(&AppSettings::default_path()));
+            encryption::unlock_at_startup(&settings::current().encryption);
+            println!("🦙 Vision model: {}", settings::current().vision_model);
 
-            // Initialize Moondream manager with API key from environment
-            let moondream_api_key = std::env::var("MOONDREAM_API_KEY")
-                .unwrap_or_else(|_| {
-                    eprintln!("⚠️ MOONDREAM_API_KEY not found in environment");
-                    "".to_string()
-                });
-
-            let moondream_manager = MoondreamManager::new(moondream_api_key);
-            println!("🌙 Moondream 3 MoE Manager initialized");
+            let ollama_manager = OllamaManager::new(&app.handle());
+            let backends = tauri::async_runtime::block_on(pipeline::init_backends());
 
-            // Initialize YOLO detector
-            tauri::async_runtime::block_on(async {
-                if let Err(e) = yolo_detector.initialize().await {
-                    eprintln!("Failed to initialize YOLO: {}", e);
-                }
-            });
+            // Open the event store, falling back to memory so the app still runs
+            let event_store = EventStore::open(&EventStore::default_path())
+                .or_else(|e| {
+                    eprintln!("Failed to open event store, using in-memory store: {}", e);
+                    EventStore::open_in_memory()
+                })?;
+            let (bus, alerts) = EventBus::new();
 
             let app_state = AppState {
                 ollama: Arc::new(Mutex::new(ollama_manager)),
-                yolo: Arc::new(Mutex::new(yolo_detector)),
-                moondream: Arc::new(Mutex::new(moondream_manager)),
+                yolo: backends.yolo,
+                moondream: backends.moondream,
+                chat: Arc::new(Mutex::new(VisionChatManager::new())),
+                events: Arc::new(Mutex::new(event_store)),
+                metrics: Arc::new(Metrics::new()),
+                metrics_server: Arc::new(Mutex::new(None)),
+                api_server: Arc::new(Mutex::new(None)),
+                grpc_server: Arc::new(Mutex::new(None)),
+                mqtt_publisher: Arc::new(Mutex::new(None)),
+                background_tasks: Arc::new(std::sync::Mutex::new(Vec::new())),
+                hub: Arc::new(Mutex::new(Hub::default())),
+                smoothers: Arc::new(Mutex::new(Smoothers::default())),
+                reidentifiers: Arc::new(Mutex::new(Reidentifiers::default())),
+                floor: Arc::new(Mutex::new(FloorTrackers::default())),
+                motion: Arc::new(Mutex::new(MotionTrackers::default())),
+                staff: Arc::new(Mutex::new(StaffTracks::default())),
+                ppe: Arc::new(Mutex::new(PpeMonitor::default())),
+                fire: Arc::new(Mutex::new(FireWatch::default())),
+                anpr: Arc::new(Mutex::new(AnprMonitor::default())),
+                rules: Arc::new(Mutex::new(RuleState::default())),
+                scripts: Arc::new(Mutex::new(ScriptHost::default())),
+                plugins: Arc::new(Mutex::new(PluginHost::default())),
+                bus,
+                intake: Arc::new(FrameIntake::default()),
+                pacing: Arc::new(Mutex::new(FrameRates::default())),
+                slo: Arc::new(Mutex::new(SloMonitor::default())),
+                providers: backends.providers,
+                backend_ready: Arc::new(Mutex::new(None)),
             };
 
             app.manage(app_state);
@@ -393,37 +3731,109 @@ pub fn run() {
             let state = app.state::<AppState>();
             let state_clone = state.inner().clone();
 
-            tauri::async_runtime::spawn(async move {
-                println!("Starting embedded Ollama...");
-                if let Err(e) = state_clone.ollama.lock().await.start().await {
-                    eprintln!("Failed to start Ollama: {}", e);
-                } else {
-                    println!("Ollama started successfully");
-                    // Pull the standard llava model
-                    if let Err(e) = state_clone.ollama.lock().await.pull_model("llava:7b").await {
-                        eprintln!("Failed to pull model: {}", e);
-                    } else {
-                        println!("Model pulled successfully, preloading...");
-
-                        // Preload the model to avoid cold starts
-                        let client = reqwest::Client::new();
-                        let preload_payload = serde_json::json!({
-                            "model": "llava:7b",
-                            "keep_alive": "10m"  // Keep loaded for 10 minutes
-                        });
-
-                        if let Err(e) = client
-                            .post("http://127.0.0.1:11434/api/generate")
-                            .json(&preload_payload)
-                            .send()
-                            .await
-                        {
-                            eprintln!("Failed to preload model: {}", e);
-                        } else {
-                            println!("LLaVA model preloaded and ready!");
-                        }
+            // Storage and metrics follow the bus; subscribe before anything can publish
+            tauri::async_runtime::spawn(event_store::follow_bus(state_clone.events.clone(), state_clone.bus.subscribe()));
+            tauri::async_runtime::spawn(event_store::file_alerts(state_clone.events.clone(), alerts));
+            tauri::async_runtime::spawn(metrics::follow_bus(state_clone.metrics.clone(), state_clone.bus.subscribe()));
+            tauri::async_runtime::spawn(pacing::follow_bus(state_clone.pacing.clone(), state_clone.bus.subscribe()));
+            tauri::async_runtime::spawn(slo::follow_bus(state_clone.slo.clone(), state_clone.bus.clone()));
+            tauri::async_runtime::spawn(app_status::follow_bus(state_clone.bus.subscribe()));
+
+            if backends.use_local_moondream {
+                let local_state = state_clone.clone();
+                let device = std::env::var("LVA_INFERENCE_DEVICE").unwrap_or_else(|_| "auto".to_string());
+                tauri::async_runtime::spawn(async move {
+                    match setup_local_moondream_backend(&local_state, device).await {
+                        Ok(status) => println!("🌙 Local moondream2 ready on {:?}", status.device),
+                        Err(e) => eprintln!("Failed to set up local moondream2: {}", e),
+                    }
+                });
+            }
+
+            // Monitors and schedulers are stopped on exit, before Ollama goes away
+            let tasks = vec![
+                // Free RAM/VRAM when nothing has been analyzed for a while
+                tauri::async_runtime::spawn(OllamaManager::run_idle_monitor()),
+                tauri::async_runtime::spawn(power::run_monitor()),
+                // Generate the previous day's report shortly after midnight
+                tauri::async_runtime::spawn(daily_report::run_scheduler(state_clone.events.clone())),
+                tauri::async_runtime::spawn(object_sync::run_scheduler(state_clone.events.clone())),
+                tauri::async_runtime::spawn(retention::run_scheduler(state_clone.events.clone())),
+                tauri::async_runtime::spawn(incidents::run_sweeper(state_clone.events.clone())),
+                tauri::async_runtime::spawn(tts::run(state_clone.events.clone())),
+                tauri::async_runtime::spawn(chime::run(state_clone.events.clone())),
+                tauri::async_runtime::spawn(push::run(state_clone.events.clone())),
+                tauri::async_runtime::spawn(desktop_notify::run(app.handle().clone(), state_clone.events.clone())),
+                tauri::async_runtime::spawn(audit::run(state_clone.events.clone())),
+                tauri::async_runtime::spawn(plugins::run(state_clone.clone())),
+                tauri::async_runtime::spawn(intake::run(state_clone.clone())),
+                tauri::async_runtime::spawn(offline::run_monitor(app.handle().clone(), state_clone.clone())),
+                // Pushes status-changed events so the UI doesn't have to poll
+                tauri::async_runtime::spawn(app_status::watch(app.handle().clone(), state_clone.clone())),
+            ];
+            state_clone.background_tasks.lock().unwrap_or_else(|e| e.into_inner()).extend(tasks);
+
+            // Optional Prometheus endpoint, enabled with LVA_METRICS_PORT
+            if let Some(port) = std::env::var("LVA_METRICS_PORT").ok().and_then(|p| p.parse::<u16>().ok()) {
+                let metrics_state = state_clone.clone();
+                tauri::async_runtime::spawn(async move {
+                    if let Err(e) = start_metrics_endpoint(&metrics_state, port).await {
+                        eprintln!("{}", e);
                     }
+                });
+            }
+
+            // Optional REST API, enabled with LVA_API_PORT
+            if let Some(port) = std::env::var("LVA_API_PORT").ok().and_then(|p| p.parse::<u16>().ok()) {
+                let api_state = state_clone.clone();
+                tauri::async_runtime::spawn(async move {
+                    if let Err(e) = start_api_endpoint(&api_state, port).await {
+                        eprintln!("{}", e);
+                    }
+                });
+            }
+
+            // Optional gRPC service, enabled with LVA_GRPC_PORT
+            if let Some(port) = std::env::var("LVA_GRPC_PORT").ok().and_then(|p| p.parse::<u16>().ok()) {
+                let grpc_state = state_clone.clone();
+                tauri::async_runtime::spawn(async move {
+                    if let Err(e) = start_grpc_endpoint(&grpc_state, port).await {
+                        eprintln!("{}", e);
+                    }
+                });
+            }
+
+            // Hub mode: resume pulling from registered instances
+            let instances = settings::current().remote_instances;
+            if !instances.is_empty() {
+                let hub_state = state_clone.clone();
+                tauri::async_runtime::spawn(async move {
+                    let mut hub = hub_state.hub.lock().await;
+                    for instance in instances {
+                        hub.start(instance, hub_state.events.clone());
+                    }
+                });
+            }
+
+            // Resume the MQTT publisher if it was left running
+            let mqtt = settings::current().mqtt;
+            if mqtt.enabled {
+                let mqtt_state = state_clone.clone();
+                tauri::async_runtime::spawn(async move {
+                    match local_only::check_url(&format!("mqtt://{}:{}", mqtt.host, mqtt.port)) {
+                        Ok(()) => start_mqtt(&mqtt_state, mqtt).await,
+                        Err(e) => eprintln!("{}", e),
+                    }
+                });
+            }
+
+            let warmup_app = app.handle().clone();
+            tauri::async_runtime::spawn(async move {
+                // Know whether we're offline before anything tries to download
+                if offline::refresh().await {
+                    println!("📴 Starting offline: using what is installed locally");
                 }
+                start_backend(&warmup_app, &state_clone).await;
             });
 
             Ok(())
@@ -431,19 +3841,219 @@ pub fn run() {
         .invoke_handler(tauri::generate_handler![
             start_ollama,
             check_ollama_status,
+            check_ollama_update,
+            upgrade_ollama,
+            get_model_settings,
+            set_vision_model,
+            detect_hardware,
+            list_inference_profiles,
+            save_inference_profile,
+            delete_inference_profile,
+            set_active_profile,
+            set_keep_alive,
+            unload_model,
+            set_idle_unload,
+            get_backend_ready,
+            get_storage_usage,
+            cleanup_unused_models,
+            get_redaction_policies,
+            set_redaction_policy,
+            preview_redaction,
+            scan_codes,
+            extract_text,
+            get_smoothing_settings,
+            set_smoothing_settings,
+            get_reid_settings,
+            set_reid_settings,
+            get_staff_settings,
+            set_staff_settings,
+            get_ppe_settings,
+            set_ppe_settings,
+            get_ppe_compliance,
+            get_fire_settings,
+            set_fire_settings,
+            get_low_light_settings,
+            set_low_light_settings,
+            get_fisheye_calibrations,
+            set_fisheye_calibration,
+            preview_dewarp,
+            get_floor_calibrations,
+            set_floor_calibration,
+            image_to_floor,
+            get_rules,
+            set_rules,
+            get_scripts,
+            set_scripts,
+            test_script,
+            get_plugins,
+            set_plugins,
+            get_plugin_outputs,
+            get_anpr_settings,
+            set_anpr_settings,
+            get_plate_reads,
+            get_model_performance,
+            get_privacy_masks,
+            set_privacy_masks,
+            get_zones,
+            set_zones,
+            suggest_zones,
+            get_local_only_status,
+            set_local_only,
             analyze_image,
             capture_camera_frame,
             yolo_detect,
+            get_app_status,
+            get_pipeline_stats,
+            pause_analysis,
+            resume_analysis,
+            get_analysis_state,
+            get_frame_interval,
+            get_pacing_settings,
+            set_pacing_settings,
+            get_power_status,
+            get_system_stats,
+            get_slo_status,
+            get_slo_settings,
+            set_slo_settings,
+            get_retention_settings,
+            set_retention_settings,
+            run_retention,
+            get_offline_status,
+            get_network_settings,
+            set_network_settings,
+            get_download_status,
+            pause_downloads,
+            resume_downloads,
+            test_connectivity,
+            get_encryption_status,
+            enable_encryption,
+            unlock_storage,
+            disable_encryption,
+            set_power_policy,
             analyze_with_llava,
+            analyze,
+            analyze_with_provider,
+            list_providers,
+            configure_provider,
+            remove_provider,
+            discover_local_models,
+            load_local_model,
+            setup_local_moondream,
+            unload_local_model,
+            get_local_inference_status,
+            analyze_sequence,
+            analyze_batch,
+            analyze_video,
+            capture_screen,
+            get_capture_source_info,
+            vision_chat,
+            list_chat_sessions,
+            get_chat_history,
+            clear_chat_session,
+            get_events,
+            get_db_info,
+            query_audit_log,
+            export_events,
+            export_dataset,
+            get_event,
+            submit_feedback,
+            get_feedback,
+            get_feedback_stats,
+            ask_history,
+            search_events,
+            reindex_embeddings,
+            generate_report,
+            get_metrics,
+            report_queue_depth,
+            start_metrics_server,
+            stop_metrics_server,
+            start_api_server,
+            stop_api_server,
+            list_api_keys,
+            create_api_key,
+            revoke_api_key,
+            start_grpc_server,
+            stop_grpc_server,
+            trigger_analysis,
+            get_trigger_events,
+            add_remote_instance,
+            remove_remote_instance,
+            list_remote_instances,
+            get_object_storage_settings,
+            update_object_storage_settings,
+            sync_object_storage,
+            get_object_sync_status,
+            get_mqtt_settings,
+            start_mqtt_publisher,
+            stop_mqtt_publisher,
             // Phase 1 POC: Moondream 3 MoE commands
             analyze_with_moondream,
             moondream_caption,
             moondream_detect,
             moondream_point,
+            moondream_gaze,
+            fused_detect,
             moondream_analyze_retail,
+            list_incidents,
+            get_incident,
+            acknowledge_incident,
+            resolve_incident,
+            acknowledge_alert,
+            snooze_alert,
+            assign_alert,
+            annotate_alert,
+            get_incident_metrics,
+            get_incident_settings,
+            set_incident_settings,
+            get_tts_settings,
+            set_tts_settings,
+            test_tts,
+            get_desktop_notification_settings,
+            set_desktop_notification_settings,
+            test_desktop_notification,
+            get_chime_settings,
+            set_chime_settings,
+            test_chime,
+            get_push_settings,
+            set_push_settings,
+            test_push,
+            get_verification_settings,
+            set_verification_settings,
+            dismiss_alert,
+            get_alert_preview,
+            get_preview_settings,
+            set_preview_settings,
+            get_suppression_settings,
+            set_suppression_settings,
+            list_scene_prompts,
+            get_scene_prompt_versions,
+            set_scene_prompt,
+            revert_scene_prompt,
             check_moondream_status,
-            analyze_ab_test
+            run_diagnostics,
+            get_setup_plan,
+            test_setup_frame,
+            complete_setup,
+            export_config,
+            import_config,
+            list_config_profiles,
+            save_config_profile,
+            activate_config_profile,
+            delete_config_profile,
+            backup_data,
+            restore_data,
+            analyze_ab_test,
+            rate_ab_result,
+            list_ab_results,
+            get_ab_stats,
+            run_benchmark,
+            run_evaluation
         ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while running tauri application")
+        .run(|app, event| {
+            if let tauri::RunEvent::Exit = event {
+                tauri::async_runtime::block_on(shutdown(&app.state::<AppState>()));
+            }
+        });
 }
\ No newline at end of file
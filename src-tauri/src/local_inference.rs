@@ -0,0 +1,439 @@
+// Local Inference Module - In-process Moondream (GGUF or moondream2 safetensors) through candle
+// Optional backend (feature "local-inference") for setups without Ollama or a Moondream API key
+
+use crate::network;
+use crate::vision_provider::{VisionProvider, VisionRequest, VisionResponse};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use futures_util::StreamExt;
+use std::path::{Path, PathBuf};
+#[cfg(feature = "local-inference")]
+use std::sync::{Arc, Mutex};
+use tokio::io::AsyncWriteExt;
+
+// Registry name of the in-process provider
+pub const PROVIDER_NAME: &str = "local-inference";
+
+#[cfg(feature = "local-inference")]
+const DEFAULT_MAX_TOKENS: u32 = 200;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct LocalInferenceStatus {
+    pub compiled: bool,  // Built with the local-inference feature
+    pub loaded: bool,
+    pub model: Option<String>,
+    pub device: Option<String>,
+}
+
+pub fn compiled() -> bool {
+    cfg!(feature = "local-inference")
+}
+
+// Only Moondream GGUF files are supported here - candle has no quantized LLaVA implementation
+pub fn validate_model_file(model_path: &Path) -> Result<(), String> {
+    if !model_path.exists() {
+        return Err(format!("Model file not found: {}", model_path.display()));
+    }
+    let name = model_path.file_name().and_then(|n| n.to_str()).unwrap_or("").to_lowercase();
+    if !name.ends_with(".gguf") {
+        return Err(format!("Expected a .gguf model file, got {}", model_path.display()));
+    }
+    if name.contains("llava") {
+        return Err("LLaVA GGUF files aren't supported in-process yet - use a Moondream GGUF or Ollama".to_string());
+    }
+    Ok(())
+}
+
+pub const DEVICE_PREFERENCES: [&str; 4] = ["auto", "cpu", "cuda", "metal"];
+
+pub fn validate_device(device: &str) -> Result<(), String> {
+    if DEVICE_PREFERENCES.contains(&device) {
+        Ok(())
+    } else {
+        Err(format!("Unknown device '{}' - expected one of {:?}", device, DEVICE_PREFERENCES))
+    }
+}
+
+// moondream2 release that matches candle's Moondream implementation
+pub const MOONDREAM2_REPO: &str = "vikhyatk/moondream2";
+pub const MOONDREAM2_REVISION: &str = "2024-03-06";
+const MOONDREAM2_MODEL_FILE: &str = "model.safetensors";
+const MOONDREAM2_TOKENIZER_FILE: &str = "tokenizer.json";
+
+pub fn models_dir() -> PathBuf {
+    let home_dir = std::env::var("HOME").unwrap_or_else(|_| "/tmp".to_string());
+    PathBuf::from(home_dir).join(".live-vision-analyzer").join("models")
+}
+
+pub fn huggingface_url(repo: &str, revision: &str, file: &str) -> String {
+    format!("https://huggingface.co/{}/resolve/{}/{}", repo, revision, file)
+}
+
+// Stream a file to disk; the .part suffix keeps interrupted downloads from looking complete
+async fn download_file(url: &str, dest: &Path) -> Result<(), String> {
+    crate::local_only::check_url(url)?;
+    crate::offline::check_url(url)?;
+    println!("LocalInference: Downloading {}...", url);
+    let response = network::client()
+        .get(url)
+        .send()
+        .await
+        .map_err(|e| format!("Failed to download {}: {}", url, e))?;
+    if !response.status().is_success() {
+        return Err(format!("Download failed for {}: {}", url, response.status()));
+    }
+
+    let partial = dest.with_extension("part");
+    let mut file = tokio::fs::File::create(&partial)
+        .await
+        .map_err(|e| format!("Failed to create file: {}", e))?;
+    let mut stream = response.bytes_stream();
+    let mut written: u64 = 0;
+    while let Some(chunk) = stream.next().await {
+        let chunk = chunk.map_err(|e| format!("Failed to read download: {}", e))?;
+        file.write_all(&chunk).await.map_err(|e| format!("Failed to write file: {}", e))?;
+        written += chunk.len() as u64;
+    }
+    file.flush().await.map_err(|e| e.to_string())?;
+
+    tokio::fs::rename(&partial, dest).await.map_err(|e| e.to_string())?;
+    println!("LocalInference: Saved {} ({} MB)", dest.display(), written / 1_048_576);
+    Ok(())
+}
+
+// Download the moondream2 weights and tokenizer once; returns (model, tokenizer) paths
+pub async fn ensure_moondream2_weights() -> Result<(PathBuf, PathBuf), String> {
+    let dir = models_dir().join("moondream2").join(MOONDREAM2_REVISION);
+    tokio::fs::create_dir_all(&dir)
+        .await
+        .map_err(|e| format!("Failed to create models directory: {}", e))?;
+
+    if !dir.join(MOONDREAM2_MODEL_FILE).exists() {
+        crate::storage::ensure_free_space(&dir, crate::storage::MOONDREAM2_DOWNLOAD_MB, "download moondream2").await?;
+    }
+
+    let mut paths = Vec::new();
+    for file in [MOONDREAM2_MODEL_FILE, MOONDREAM2_TOKENIZER_FILE] {
+        let path = dir.join(file);
+        if !path.exists() {
+            download_file(&huggingface_url(MOONDREAM2_REPO, MOONDREAM2_REVISION, file), &path).await?;
+        }
+        paths.push(path);
+    }
+
+    let tokenizer = paths.pop().unwrap_or_default();
+    let model = paths.pop().unwrap_or_default();
+    Ok((model, tokenizer))
+}
+
+#[cfg(feature = "local-inference")]
+mod engine {
+    use candle_core::{DType, Device, Tensor};
+    use candle_transformers::generation::LogitsProcessor;
+    use candle_transformers::models::{moondream, quantized_moondream};
+    use std::path::Path;
+    use tokenizers::Tokenizer;
+
+    // Moondream's vision encoder expects 378x378 inputs
+    const IMAGE_SIZE: usize = 378;
+    // "<END>" as emitted by the Moondream tokenizer
+    const END_TOKENS: [u32; 3] = [27, 10619, 29];
+
+    // Quantized GGUF weights or the original safetensors release
+    enum MoondreamModel {
+        Quantized(quantized_moondream::Model),
+        Full(moondream::Model),
+    }
+
+    impl MoondreamModel {
+        fn encode_image(&self, image: &Tensor) -> candle_core::Result<Tensor> {
+            match self {
+                MoondreamModel::Quantized(m) => image.apply(m.vision_encoder()),
+                MoondreamModel::Full(m) => image.apply(m.vision_encoder()),
+            }
+        }
+
+        fn forward(&mut self, input: &Tensor) -> candle_core::Result<Tensor> {
+            match self {
+                MoondreamModel::Quantized(m) => m.text_model().forward(input),
+                MoondreamModel::Full(m) => m.text_model().forward(input),
+            }
+        }
+
+        fn forward_with_img(&mut self, bos: &Tensor, input: &Tensor, image_embeds: &Tensor) -> candle_core::Result<Tensor> {
+            match self {
+                MoondreamModel::Quantized(m) => m.text_model().forward_with_img(bos, input, image_embeds),
+                MoondreamModel::Full(m) => m.text_model().forward_with_img(bos, input, image_embeds),
+            }
+        }
+
+        fn clear_kv_cache(&mut self) {
+            match self {
+                MoondreamModel::Quantized(m) => m.text_model().clear_kv_cache(),
+                MoondreamModel::Full(m) => m.text_model().clear_kv_cache(),
+            }
+        }
+    }
+
+    // "auto" prefers CUDA, then Metal, then CPU; GPU backends need the matching cargo feature
+    pub fn select_device(preference: &str) -> Result<Device, String> {
+        match preference {
+            "cpu" => Ok(Device::Cpu),
+            "cuda" => Device::new_cuda(0).map_err(|e| format!("CUDA unavailable: {}", e)),
+            "metal" => Device::new_metal(0).map_err(|e| format!("Metal unavailable: {}", e)),
+            _ => {
+                if candle_core::utils::cuda_is_available() {
+                    if let Ok(device) = Device::new_cuda(0) {
+                        return Ok(device);
+                    }
+                }
+                if candle_core::utils::metal_is_available() {
+                    if let Ok(device) = Device::new_metal(0) {
+                        return Ok(device);
+                    }
+                }
+                Ok(Device::Cpu)
+            }
+        }
+    }
+
+    pub struct Engine {
+        model: MoondreamModel,
+        tokenizer: Tokenizer,
+        device: Device,
+        dtype: DType,
+        eos_token: u32,
+    }
+
+    fn load_tokenizer(tokenizer_path: &Path) -> Result<(Tokenizer, u32), String> {
+        let tokenizer = Tokenizer::from_file(tokenizer_path)
+            .map_err(|e| format!("Failed to load tokenizer: {}", e))?;
+        let eos_token = *tokenizer
+            .get_vocab(true)
+            .get("<|endoftext|>")
+            .ok_or("Tokenizer has no <|endoftext|> token")?;
+        Ok((tokenizer, eos_token))
+    }
+
+    impl Engine {
+        pub fn load_gguf(model_path: &Path, tokenizer_path: &Path, device: Device) -> Result<Self, String> {
+            let (tokenizer, eos_token) = load_tokenizer(tokenizer_path)?;
+            let vb = candle_transformers::quantized_var_builder::VarBuilder::from_gguf(model_path, &device)
+                .map_err(|e| format!("Failed to read GGUF: {}", e))?;
+            let model = quantized_moondream::Model::new(&moondream::Config::v2(), vb)
+                .map_err(|e| format!("Failed to build model: {}", e))?;
+
+            Ok(Engine { model: MoondreamModel::Quantized(model), tokenizer, device, dtype: DType::F32, eos_token })
+        }
+
+        pub fn load_safetensors(model_path: &Path, tokenizer_path: &Path, device: Device) -> Result<Self, String> {
+            let (tokenizer, eos_token) = load_tokenizer(tokenizer_path)?;
+            // Half precision on CUDA; Metal and CPU kernels are more reliable in F32
+            let dtype = if device.is_cuda() { DType::F16 } else { DType::F32 };
+            // Safety: the weights file is not modified while it is memory-mapped
+            let vb = unsafe { candle_nn::VarBuilder::from_mmaped_safetensors(&[model_path], dtype, &device) }
+                .map_err(|e| format!("Failed to read safetensors: {}", e))?;
+            let model = moondream::Model::new(&moondream::Config::v2(), vb)
+                .map_err(|e| format!("Failed to build model: {}", e))?;
+
+            Ok(Engine { model: MoondreamModel::Full(model), tokenizer, device, dtype, eos_token })
+        }
+
+        pub fn device_name(&self) -> &'static str {
+            match self.device {
+                Device::Cpu => "cpu",
+                Device::Cuda(_) => "cuda",
+                Device::Metal(_) => "metal",
+            }
+        }
+
+        fn image_tensor(&self, image: &image::DynamicImage) -> candle_core::Result<Tensor> {
+            let image = image
+                .resize_exact(IMAGE_SIZE as u32, IMAGE_SIZE as u32, image::imageops::FilterType::Triangle)
+                .to_rgb8();
+            // Normalize to [-1, 1] (mean 0.5, std 0.5), channels first
+            Tensor::from_vec(image.into_raw(), (IMAGE_SIZE, IMAGE_SIZE, 3), &self.device)?
+                .permute((2, 0, 1))?
+                .to_dtype(DType::F32)?
+                .affine(2.0 / 255.0, -1.0)?
+                .to_dtype(self.dtype)
+        }
+
+        fn sample(&mut self, image: &image::DynamicImage, mut tokens: Vec<u32>, max_tokens: usize) -> candle_core::Result<Vec<u32>> {
+            let image_embeds = self.model.encode_image(&self.image_tensor(image)?.unsqueeze(0)?)?;
+            let mut logits_processor = LogitsProcessor::new(0, None, None);  // Greedy decoding
+            let mut generated = Vec::new();
+            self.model.clear_kv_cache();
+
+            for index in 0..max_tokens {
+                let context_size = if index > 0 { 1 } else { tokens.len() };
+                let context = &tokens[tokens.len().saturating_sub(context_size)..];
+                let input = Tensor::new(context, &self.device)?.unsqueeze(0)?;
+
+                let logits = if index == 0 {
+                    let bos = Tensor::new(&[self.eos_token], &self.device)?.unsqueeze(0)?;
+                    self.model.forward_with_img(&bos, &input, &image_embeds)?
+                } else {
+                    self.model.forward(&input)?
+                };
+
+                let next = logits_processor.sample(&logits.squeeze(0)?.to_dtype(DType::F32)?)?;
+                tokens.push(next);
+                if next == self.eos_token || tokens.ends_with(&END_TOKENS) {
+                    break;
+                }
+                generated.push(next);
+            }
+
+            Ok(generated)
+        }
+
+        pub fn generate(&mut self, image_base64: &str, prompt: &str, max_tokens: usize) -> Result<String, String> {
+            let image = crate::frame_processor::decode_frame(image_base64)?;
+            let encoded = self
+                .tokenizer
+                .encode(format!("\n\nQuestion: {}\n\nAnswer:", prompt), true)
+                .map_err(|e| format!("Failed to tokenize prompt: {}", e))?;
+
+            let generated = self
+                .sample(&image, encoded.get_ids().to_vec(), max_tokens)
+                .map_err(|e| format!("Local inference failed: {}", e))?;
+
+            let text = self
+                .tokenizer
+                .decode(&generated, true)
+                .map_err(|e| format!("Failed to decode output: {}", e))?;
+            Ok(text.trim_end_matches("<END").trim().to_string())
+        }
+    }
+}
+
+// In-process provider; the engine is blocking, so calls run on the blocking thread pool
+#[cfg_attr(not(feature = "local-inference"), allow(dead_code))]
+pub struct LocalInferenceProvider {
+    model_name: String,
+    device: String,
+    #[cfg(feature = "local-inference")]
+    engine: Arc<Mutex<engine::Engine>>,
+}
+
+impl LocalInferenceProvider {
+    #[cfg(feature = "local-inference")]
+    fn from_engine(model_name: String, engine: engine::Engine) -> Self {
+        let device = engine.device_name().to_string();
+        println!("LocalInference: {} loaded on {}", model_name, device);
+        LocalInferenceProvider {
+            model_name,
+            device,
+            engine: Arc::new(Mutex::new(engine)),
+        }
+    }
+
+    // Quantized Moondream GGUF
+    #[cfg(feature = "local-inference")]
+    pub fn load(model_path: &Path, tokenizer_path: &Path, device: &str) -> Result<Self, String> {
+        validate_model_file(model_path)?;
+        validate_device(device)?;
+        println!("LocalInference: Loading {}...", model_path.display());
+
+        let engine = engine::Engine::load_gguf(model_path, tokenizer_path, engine::select_device(device)?)?;
+        let model_name = model_path.file_name().and_then(|n| n.to_str()).unwrap_or("gguf").to_string();
+        Ok(Self::from_engine(model_name, engine))
+    }
+
+    // Full-precision moondream2 safetensors (see ensure_moondream2_weights)
+    #[cfg(feature = "local-inference")]
+    pub fn load_moondream2(model_path: &Path, tokenizer_path: &Path, device: &str) -> Result<Self, String> {
+        validate_device(device)?;
+        println!("LocalInference: Loading moondream2 from {}...", model_path.display());
+
+        let engine = engine::Engine::load_safetensors(model_path, tokenizer_path, engine::select_device(device)?)?;
+        Ok(Self::from_engine(format!("moondream2-{}", MOONDREAM2_REVISION), engine))
+    }
+
+    #[cfg(not(feature = "local-inference"))]
+    pub fn load(model_path: &Path, _tokenizer_path: &Path, device: &str) -> Result<Self, String> {
+        validate_model_file(model_path)?;
+        validate_device(device)?;
+        Err("In-process inference is not available - rebuild with `--features local-inference`".to_string())
+    }
+
+    #[cfg(not(feature = "local-inference"))]
+    pub fn load_moondream2(_model_path: &Path, _tokenizer_path: &Path, device: &str) -> Result<Self, String> {
+        validate_device(device)?;
+        Err("In-process inference is not available - rebuild with `--features local-inference`".to_string())
+    }
+}
+
+#[async_trait]
+impl VisionProvider for LocalInferenceProvider {
+    fn name(&self) -> &str {
+        PROVIDER_NAME
+    }
+
+    fn model(&self) -> &str {
+        &self.model_name
+    }
+
+    fn device(&self) -> Option<&str> {
+        Some(&self.device)
+    }
+
+    #[cfg(feature = "local-inference")]
+    async fn analyze(&self, request: &VisionRequest) -> Result<VisionResponse, String> {
+        let image = request.images.first().cloned().ok_or("No image provided")?;
+        let prompt = request.prompt.clone();
+        let max_tokens = request.max_tokens.unwrap_or(DEFAULT_MAX_TOKENS) as usize;
+        let engine = self.engine.clone();
+
+        let text = tokio::task::spawn_blocking(move || {
+            let mut engine = engine.lock().map_err(|_| "Local inference engine is poisoned".to_string())?;
+            engine.generate(&image, &prompt, max_tokens)
+        })
+        .await
+        .map_err(|e| format!("Local inference task failed: {}", e))??;
+
+        Ok(VisionResponse {
+            provider: PROVIDER_NAME.to_string(),
+            model: self.model_name.clone(),
+            text,
+            usage: None,
+        })
+    }
+
+    #[cfg(not(feature = "local-inference"))]
+    async fn analyze(&self, _request: &VisionRequest) -> Result<VisionResponse, String> {
+        Err("In-process inference is not available in this build".to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_model_file_validation() {
+        let dir = tempfile::tempdir().unwrap();
+        let moondream = dir.path().join("moondream2-q4_k.gguf");
+        let llava = dir.path().join("llava-v1.6-q4.gguf");
+        let safetensors = dir.path().join("model.safetensors");
+        for path in [&moondream, &llava, &safetensors] {
+            std::fs::write(path, b"").unwrap();
+        }
+
+        assert!(validate_model_file(&moondream).is_ok());
+        assert!(validate_model_file(&llava).unwrap_err().contains("LLaVA"));
+        assert!(validate_model_file(&safetensors).is_err());
+        assert!(validate_model_file(&dir.path().join("missing.gguf")).is_err());
+    }
+
+    #[test]
+    fn test_weights_url_and_device_names() {
+        assert_eq!(
+            huggingface_url(MOONDREAM2_REPO, MOONDREAM2_REVISION, "tokenizer.json"),
+            "https://huggingface.co/vikhyatk/moondream2/resolve/2024-03-06/tokenizer.json"
+        );
+        assert!(validate_device("metal").is_ok());
+        assert!(validate_device("tpu").is_err());
+    }
+}
@@ -0,0 +1,98 @@
+// Local-only Module - Global switch that blocks every outbound request except to localhost
+// Enabled from settings or forced with LVA_LOCAL_ONLY=1 for deployments that must not reach the internet
+
+use crate::settings;
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+// Prefix of every blocked-call error, so the UI can tell them apart from network failures
+pub const BLOCKED_CODE: &str = "LOCAL_ONLY_BLOCKED";
+
+static BLOCKED_COUNT: AtomicU64 = AtomicU64::new(0);
+static LAST_BLOCKED: Mutex<Option<BlockedRequest>> = Mutex::new(None);
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct BlockedRequest {
+    pub target: String,
+    pub timestamp: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct LocalOnlyStatus {
+    pub enabled: bool,
+    pub forced_by_env: bool,
+    pub blocked_requests: u64,
+    pub last_blocked: Option<BlockedRequest>,
+}
+
+pub fn forced_by_env() -> bool {
+    matches!(std::env::var("LVA_LOCAL_ONLY").as_deref(), Ok("1") | Ok("true"))
+}
+
+pub fn enabled() -> bool {
+    forced_by_env() || settings::current().local_only
+}
+
+pub fn is_loopback_url(url: &str) -> bool {
+    let Ok(url) = reqwest::Url::parse(url) else {
+        return false;
+    };
+    let host = url.host_str().unwrap_or("").trim_matches(['[', ']']);
+    match host.parse::<std::net::IpAddr>() {
+        Ok(ip) => ip.is_loopback(),
+        Err(_) => host == "localhost",
+    }
+}
+
+fn block(target: &str) -> String {
+    BLOCKED_COUNT.fetch_add(1, Ordering::Relaxed);
+    if let Ok(mut last) = LAST_BLOCKED.lock() {
+        *last = Some(BlockedRequest {
+            target: target.to_string(),
+            timestamp: chrono::Utc::now().to_rfc3339(),
+        });
+    }
+    eprintln!("Local-only mode blocked: {}", target);
+    format!("{}: {} is blocked while local-only mode is enabled", BLOCKED_CODE, target)
+}
+
+// Call before any request to `url`
+pub fn check_url(url: &str) -> Result<(), String> {
+    if enabled() && !is_loopback_url(url) {
+        return Err(block(url));
+    }
+    Ok(())
+}
+
+// For operations that reach the internet indirectly, such as Ollama pulling a model
+pub fn check_remote(what: &str) -> Result<(), String> {
+    if enabled() {
+        return Err(block(what));
+    }
+    Ok(())
+}
+
+pub fn status() -> LocalOnlyStatus {
+    LocalOnlyStatus {
+        enabled: enabled(),
+        forced_by_env: forced_by_env(),
+        blocked_requests: BLOCKED_COUNT.load(Ordering::Relaxed),
+        last_blocked: LAST_BLOCKED.lock().ok().and_then(|last| last.clone()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_loopback_detection() {
+        assert!(is_loopback_url("http://127.0.0.1:11434/api/tags"));
+        assert!(is_loopback_url("http://localhost:8080/v1"));
+        assert!(is_loopback_url("http://[::1]:8000/v1"));
+        assert!(!is_loopback_url("http://192.168.1.20:8000/v1"));
+        assert!(!is_loopback_url("https://api.moondream.ai/v1"));
+        assert!(!is_loopback_url("not a url"));
+    }
+}
@@ -0,0 +1,127 @@
+// Local Provider Module - Self-hosted OpenAI-compatible vision servers
+// llama.cpp server, LM Studio and vLLM speak the same chat format, so they reuse the OpenAI client
+
+use crate::network;
+use crate::openai_provider::OpenAiProvider;
+use crate::vision_provider::ProviderSettings;
+
+// Default endpoints for the servers we know about
+pub const SERVER_PRESETS: [(&str, &str); 3] = [
+    ("llama.cpp", "http://127.0.0.1:8080/v1"),
+    ("lmstudio", "http://127.0.0.1:1234/v1"),
+    ("vllm", "http://127.0.0.1:8000/v1"),
+];
+
+// llama.cpp serves whatever model it was started with and ignores this name
+const DEFAULT_MODEL: &str = "default";
+
+pub fn preset_url(server: &str) -> Option<&'static str> {
+    SERVER_PRESETS.iter().find(|(name, _)| *name == server).map(|(_, url)| *url)
+}
+
+// Only loopback and private-network hosts; public endpoints belong to the "openai" kind
+pub fn validate_local_url(base_url: &str) -> Result<(), String> {
+    let url = reqwest::Url::parse(base_url).map_err(|e| format!("Invalid endpoint URL '{}': {}", base_url, e))?;
+    let host = url.host_str().unwrap_or("").trim_matches(['[', ']']);
+
+    let is_local = match host.parse::<std::net::IpAddr>() {
+        Ok(std::net::IpAddr::V4(ip)) => ip.is_loopback() || ip.is_private() || ip.is_link_local(),
+        Ok(std::net::IpAddr::V6(ip)) => ip.is_loopback(),
+        Err(_) => host == "localhost" || host.ends_with(".local"),
+    };
+
+    if is_local {
+        Ok(())
+    } else {
+        Err(format!("'{}' is not a local address - use the \"openai\" provider kind for remote endpoints", host))
+    }
+}
+
+// Resolve the endpoint from base_url, or from options.server ("llama.cpp", "lmstudio", "vllm")
+pub fn resolve_base_url(settings: &ProviderSettings) -> Result<String, String> {
+    if let Some(base_url) = &settings.base_url {
+        return Ok(base_url.clone());
+    }
+    let server = settings.options.as_ref().and_then(|o| o["server"].as_str()).unwrap_or("llama.cpp");
+    preset_url(server)
+        .map(|url| url.to_string())
+        .ok_or_else(|| format!("Unknown local server '{}' - set base_url instead", server))
+}
+
+pub fn from_settings(name: &str, settings: &ProviderSettings) -> Result<OpenAiProvider, String> {
+    let base_url = resolve_base_url(settings)?;
+    validate_local_url(&base_url)?;
+
+    Ok(OpenAiProvider::for_endpoint(
+        name,
+        &base_url,
+        settings.api_key.clone(),
+        settings.model.as_deref().unwrap_or(DEFAULT_MODEL),
+    ))
+}
+
+// Ask the server which models it has loaded (GET /models)
+pub async fn list_models(base_url: &str) -> Result<Vec<String>, String> {
+    validate_local_url(base_url)?;
+    crate::local_only::check_url(base_url)?;
+    crate::offline::check_url(base_url)?;
+
+    let client = network::client_builder()
+        .timeout(std::time::Duration::from_secs(5))
+        .build()
+        .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
+
+    let response = client
+        .get(format!("{}/models", base_url.trim_end_matches('/')))
+        .send()
+        .await
+        .map_err(|e| format!("Failed to reach {}: {}", base_url, e))?;
+    if !response.status().is_success() {
+        return Err(format!("Model listing failed: {}", response.status()));
+    }
+
+    let body: serde_json::Value = response
+        .json()
+        .await
+        .map_err(|e| format!("Failed to parse response: {}", e))?;
+
+    Ok(body["data"]
+        .as_array()
+        .map(|models| {
+            models
+                .iter()
+                .filter_map(|m| m["id"].as_str().map(|id| id.to_string()))
+                .collect()
+        })
+        .unwrap_or_default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_only_local_endpoints_are_accepted() {
+        assert!(validate_local_url("http://127.0.0.1:8080/v1").is_ok());
+        assert!(validate_local_url("http://localhost:1234/v1").is_ok());
+        assert!(validate_local_url("http://192.168.1.20:8000/v1").is_ok());
+        assert!(validate_local_url("http://[::1]:8080/v1").is_ok());
+        assert!(validate_local_url("https://api.openai.com/v1").is_err());
+        assert!(validate_local_url("not a url").is_err());
+    }
+
+    #[test]
+    fn test_server_presets() {
+        let settings = ProviderSettings {
+            kind: "local".to_string(),
+            api_key: None,
+            base_url: None,
+            model: None,
+            options: Some(serde_json::json!({ "server": "lmstudio" })),
+        };
+        assert_eq!(resolve_base_url(&settings).unwrap(), "http://127.0.0.1:1234/v1");
+
+        let unknown = ProviderSettings { options: Some(serde_json::json!({ "server": "tgi" })), ..settings };
+        assert!(resolve_base_url(&unknown).is_err());
+    }
+}
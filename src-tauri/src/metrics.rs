@@ -0,0 +1,310 @@
+// Metrics Module - Prometheus-style counters and latency histograms
+// Served as text exposition format from an optional local HTTP endpoint
+
+use crate::bus::BusEvent;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicI64, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+use tokio::sync::broadcast::{self, error::RecvError};
+
+pub const DEFAULT_METRICS_PORT: u16 = 9464;
+
+// Latency buckets in seconds
+const DETECTION_BUCKETS: [f64; 8] = [0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0];
+const VLM_BUCKETS: [f64; 9] = [0.25, 0.5, 1.0, 2.0, 5.0, 10.0, 20.0, 30.0, 60.0];
+
+#[derive(Debug, Clone)]
+struct Histogram {
+    buckets: Vec<f64>,
+    counts: Vec<u64>,
+    sum: f64,
+    count: u64,
+}
+
+impl Histogram {
+    fn new(buckets: &[f64]) -> Self {
+        Histogram {
+            buckets: buckets.to_vec(),
+            counts: vec![0; buckets.len()],
+            sum: 0.0,
+            count: 0,
+        }
+    }
+
+    fn observe(&mut self, value: f64) {
+        for (bound, count) in self.buckets.iter().zip(self.counts.iter_mut()) {
+            if value <= *bound {
+                *count += 1;
+            }
+        }
+        self.sum += value;
+        self.count += 1;
+    }
+
+    fn render(&self, name: &str, labels: &str, out: &mut String) {
+        let separator = if labels.is_empty() { "" } else { "," };
+        for (bound, count) in self.buckets.iter().zip(self.counts.iter()) {
+            out.push_str(&format!("{}_bucket{{{}{}le=\"{}\"}} {}\n", name, labels, separator, bound, count));
+        }
+        out.push_str(&format!("{}_bucket{{{}{}le=\"+Inf\"}} {}\n", name, labels, separator, self.count));
+
+        let braces = if labels.is_empty() { String::new() } else { format!("{{{}}}", labels) };
+        out.push_str(&format!("{}_sum{} {}\n", name, braces, self.sum));
+        out.push_str(&format!("{}_count{} {}\n", name, braces, self.count));
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct StageLatency {
+    pub count: u64,
+    pub average_ms: f64,
+}
+
+impl From<&Histogram> for StageLatency {
+    fn from(histogram: &Histogram) -> Self {
+        StageLatency {
+            count: histogram.count,
+            average_ms: if histogram.count == 0 { 0.0 } else { histogram.sum * 1000.0 / histogram.count as f64 },
+        }
+    }
+}
+
+// Process-wide metrics registry (shared through AppState)
+pub struct Metrics {
+    frames_captured: AtomicU64,
+    frames_processed: AtomicU64,
+    queue_depth: AtomicI64,
+    vlm_in_flight: AtomicI64,
+    detection_latency: Mutex<Histogram>,
+    vlm_latency: Mutex<BTreeMap<String, Histogram>>,
+    vlm_requests: Mutex<BTreeMap<(String, String), u64>>,
+    errors: Mutex<BTreeMap<String, u64>>,
+}
+
+// Decrements the in-flight gauge when an analysis finishes (or is cancelled)
+pub struct InFlightGuard {
+    metrics: Arc<Metrics>,
+}
+
+impl Drop for InFlightGuard {
+    fn drop(&mut self) {
+        self.metrics.vlm_in_flight.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+impl Metrics {
+    pub fn new() -> Self {
+        Metrics {
+            frames_captured: AtomicU64::new(0),
+            frames_processed: AtomicU64::new(0),
+            queue_depth: AtomicI64::new(0),
+            vlm_in_flight: AtomicI64::new(0),
+            detection_latency: Mutex::new(Histogram::new(&DETECTION_BUCKETS)),
+            vlm_latency: Mutex::new(BTreeMap::new()),
+            vlm_requests: Mutex::new(BTreeMap::new()),
+            errors: Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    pub fn observe_detection(&self, latency: Duration) {
+        self.frames_processed.fetch_add(1, Ordering::Relaxed);
+        if let Ok(mut histogram) = self.detection_latency.lock() {
+            histogram.observe(latency.as_secs_f64());
+        }
+    }
+
+    pub fn observe_vlm(&self, provider: &str, latency: Duration, success: bool) {
+        if let Ok(mut histograms) = self.vlm_latency.lock() {
+            histograms
+                .entry(provider.to_string())
+                .or_insert_with(|| Histogram::new(&VLM_BUCKETS))
+                .observe(latency.as_secs_f64());
+        }
+
+        let status = if success { "success" } else { "error" };
+        if let Ok(mut requests) = self.vlm_requests.lock() {
+            *requests.entry((provider.to_string(), status.to_string())).or_insert(0) += 1;
+        }
+
+        if !success {
+            self.record_error(provider);
+        }
+    }
+
+    pub fn record_error(&self, stage: &str) {
+        if let Ok(mut errors) = self.errors.lock() {
+            *errors.entry(stage.to_string()).or_insert(0) += 1;
+        }
+    }
+
+    pub fn set_queue_depth(&self, depth: i64) {
+        self.queue_depth.store(depth, Ordering::Relaxed);
+    }
+
+    pub fn track_in_flight(self: &Arc<Self>) -> InFlightGuard {
+        self.vlm_in_flight.fetch_add(1, Ordering::Relaxed);
+        InFlightGuard { metrics: self.clone() }
+    }
+
+    // Mean latency of detection and of each VLM provider ("vlm:<provider>") since startup
+    pub fn stage_latencies(&self) -> BTreeMap<String, StageLatency> {
+        let mut stages = BTreeMap::new();
+        if let Ok(histogram) = self.detection_latency.lock() {
+            stages.insert("detection".to_string(), StageLatency::from(&*histogram));
+        }
+        if let Ok(histograms) = self.vlm_latency.lock() {
+            for (provider, histogram) in histograms.iter() {
+                stages.insert(format!("vlm:{}", provider), StageLatency::from(histogram));
+            }
+        }
+        stages
+    }
+
+    // Render everything in Prometheus text exposition format
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+
+        out.push_str("# HELP lva_frames_captured_total Live frames received for detection.\n");
+        out.push_str("# TYPE lva_frames_captured_total counter\n");
+        out.push_str(&format!("lva_frames_captured_total {}\n", self.frames_captured.load(Ordering::Relaxed)));
+
+        out.push_str("# HELP lva_frames_processed_total Frames run through object detection.\n");
+        out.push_str("# TYPE lva_frames_processed_total counter\n");
+        out.push_str(&format!("lva_frames_processed_total {}\n", self.frames_processed.load(Ordering::Relaxed)));
+
+        out.push_str("# HELP lva_queue_depth Analysis jobs waiting in the queue.\n");
+        out.push_str("# TYPE lva_queue_depth gauge\n");
+        out.push_str(&format!("lva_queue_depth {}\n", self.queue_depth.load(Ordering::Relaxed)));
+
+        out.push_str("# HELP lva_vlm_in_flight VLM analyses currently running.\n");
+        out.push_str("# TYPE lva_vlm_in_flight gauge\n");
+        out.push_str(&format!("lva_vlm_in_flight {}\n", self.vlm_in_flight.load(Ordering::Relaxed)));
+
+        out.push_str("# HELP lva_detection_latency_seconds Object detection latency.\n");
+        out.push_str("# TYPE lva_detection_latency_seconds histogram\n");
+        if let Ok(histogram) = self.detection_latency.lock() {
+            histogram.render("lva_detection_latency_seconds", "", &mut out);
+        }
+
+        out.push_str("# HELP lva_vlm_latency_seconds Vision-language model latency per provider.\n");
+        out.push_str("# TYPE lva_vlm_latency_seconds histogram\n");
+        if let Ok(histograms) = self.vlm_latency.lock() {
+            for (provider, histogram) in histograms.iter() {
+                histogram.render("lva_vlm_latency_seconds", &format!("provider=\"{}\"", provider), &mut out);
+            }
+        }
+
+        out.push_str("# HELP lva_vlm_requests_total VLM requests per provider and status.\n");
+        out.push_str("# TYPE lva_vlm_requests_total counter\n");
+        if let Ok(requests) = self.vlm_requests.lock() {
+            for ((provider, status), count) in requests.iter() {
+                out.push_str(&format!(
+                    "lva_vlm_requests_total{{provider=\"{}\",status=\"{}\"}} {}\n",
+                    provider, status, count
+                ));
+            }
+        }
+
+        out.push_str("# HELP lva_errors_total Errors per pipeline stage.\n");
+        out.push_str("# TYPE lva_errors_total counter\n");
+        if let Ok(errors) = self.errors.lock() {
+            for (stage, count) in errors.iter() {
+                out.push_str(&format!("lva_errors_total{{stage=\"{}\"}} {}\n", stage, count));
+            }
+        }
+
+        out
+    }
+}
+
+// Minimal HTTP server answering GET /metrics on localhost
+pub async fn serve(metrics: Arc<Metrics>, listener: TcpListener) {
+    loop {
+        let (mut socket, _) = match listener.accept().await {
+            Ok(connection) => connection,
+            Err(e) => {
+                eprintln!("Metrics: Failed to accept connection: {}", e);
+                continue;
+            }
+        };
+
+        let metrics = metrics.clone();
+        tokio::spawn(async move {
+            let mut buffer = [0u8; 1024];
+            let read = socket.read(&mut buffer).await.unwrap_or(0);
+            let request = String::from_utf8_lossy(&buffer[..read]);
+
+            let response = if request.starts_with("GET /metrics") {
+                let body = metrics.render();
+                format!(
+                    "HTTP/1.1 200 OK\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    body.len(),
+                    body
+                )
+            } else {
+                "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n".to_string()
+            };
+
+            socket.write_all(response.as_bytes()).await.ok();
+        });
+    }
+}
+
+// Metrics side of the bus: frame counts and detection/VLM latencies
+pub async fn follow_bus(metrics: Arc<Metrics>, mut bus: broadcast::Receiver<BusEvent>) {
+    loop {
+        match bus.recv().await {
+            Ok(BusEvent::FrameCaptured) => {
+                metrics.frames_captured.fetch_add(1, Ordering::Relaxed);
+            }
+            Ok(BusEvent::DetectionReady { latency, .. }) => metrics.observe_detection(latency),
+            Ok(BusEvent::AnalysisReady { provider, latency, success }) => metrics.observe_vlm(&provider, latency, success),
+            Ok(BusEvent::AlertFired(_)) => {}
+            Err(RecvError::Lagged(skipped)) => eprintln!("Metrics missed {} bus events", skipped),
+            Err(RecvError::Closed) => break,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_histogram_buckets_are_cumulative() {
+        let mut histogram = Histogram::new(&[0.1, 1.0]);
+        histogram.observe(0.05);
+        histogram.observe(0.5);
+        histogram.observe(5.0);
+
+        assert_eq!(histogram.counts, vec![1, 2]);
+        assert_eq!(histogram.count, 3);
+    }
+
+    #[test]
+    fn test_render_includes_provider_labels() {
+        let metrics = Arc::new(Metrics::new());
+        metrics.observe_detection(Duration::from_millis(20));
+        metrics.observe_vlm("llava", Duration::from_secs(3), true);
+        metrics.observe_vlm("moondream", Duration::from_secs(1), false);
+        {
+            let _guard = metrics.track_in_flight();
+            assert!(metrics.render().contains("lva_vlm_in_flight 1"));
+        }
+
+        let text = metrics.render();
+        assert!(text.contains("lva_frames_processed_total 1"));
+        assert!(text.contains("lva_vlm_latency_seconds_bucket{provider=\"llava\",le=\"5\"} 1"));
+        assert!(text.contains("lva_vlm_requests_total{provider=\"moondream\",status=\"error\"} 1"));
+        assert!(text.contains("lva_errors_total{stage=\"moondream\"} 1"));
+        assert!(text.contains("lva_vlm_in_flight 0"));
+
+        let stages = metrics.stage_latencies();
+        assert_eq!(stages["detection"], StageLatency { count: 1, average_ms: 20.0 });
+        assert_eq!(stages["vlm:llava"].average_ms, 3000.0);
+    }
+}
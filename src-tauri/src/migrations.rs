@@ -0,0 +1,322 @@
+// Migrations Module - Versioned schema changes for the event store
+// The applied version lives in SQLite's user_version; every step runs in its own transaction
+
+use crate::event_store::format_timestamp;
+use rusqlite::{params, Connection, DatabaseName};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+#[derive(Clone, Copy)]
+pub struct Migration {
+    pub version: u32,
+    pub name: &'static str,
+    pub sql: &'static str,
+}
+
+// Append only: a released migration is never edited, a new one is added instead
+pub const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        name: "baseline",
+        // IF NOT EXISTS so databases created before versioning adopt it unchanged
+        sql: "
+CREATE TABLE IF NOT EXISTS events (
+    id TEXT PRIMARY KEY,
+    timestamp TEXT NOT NULL,
+    camera_id TEXT,
+    event_type TEXT NOT NULL,
+    person_count INTEGER,
+    object_counts TEXT,
+    provider TEXT,
+    prompt TEXT,
+    description TEXT,
+    payload TEXT
+);
+CREATE INDEX IF NOT EXISTS idx_events_timestamp ON events(timestamp);
+CREATE INDEX IF NOT EXISTS idx_events_type ON events(event_type);
+CREATE TABLE IF NOT EXISTS event_embeddings (
+    event_id TEXT PRIMARY KEY REFERENCES events(id) ON DELETE CASCADE,
+    model TEXT NOT NULL,
+    dims INTEGER NOT NULL,
+    vector BLOB NOT NULL
+);
+CREATE TABLE IF NOT EXISTS ab_tests (
+    id TEXT PRIMARY KEY,
+    timestamp TEXT NOT NULL,
+    prompt TEXT NOT NULL,
+    total_time_ms INTEGER NOT NULL,
+    winner TEXT,
+    notes TEXT,
+    rated_at TEXT
+);
+CREATE TABLE IF NOT EXISTS ab_test_results (
+    test_id TEXT NOT NULL REFERENCES ab_tests(id) ON DELETE CASCADE,
+    provider TEXT NOT NULL,
+    success INTEGER NOT NULL,
+    latency_ms INTEGER NOT NULL,
+    result TEXT,
+    error TEXT,
+    PRIMARY KEY (test_id, provider)
+);
+CREATE INDEX IF NOT EXISTS idx_ab_tests_timestamp ON ab_tests(timestamp);
+CREATE TABLE IF NOT EXISTS event_feedback (
+    event_id TEXT PRIMARY KEY REFERENCES events(id) ON DELETE CASCADE,
+    verdict TEXT NOT NULL,
+    corrected_boxes TEXT,
+    corrected_description TEXT,
+    submitted_at TEXT NOT NULL
+);
+CREATE TABLE IF NOT EXISTS external_triggers (
+    event_id TEXT PRIMARY KEY REFERENCES events(id) ON DELETE CASCADE,
+    source TEXT NOT NULL,
+    external_id TEXT NOT NULL,
+    received_at TEXT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS idx_external_triggers_id ON external_triggers(external_id);
+CREATE TABLE IF NOT EXISTS event_origins (
+    event_id TEXT PRIMARY KEY REFERENCES events(id) ON DELETE CASCADE,
+    instance TEXT NOT NULL,
+    received_at TEXT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS idx_event_origins_instance ON event_origins(instance);
+CREATE TABLE IF NOT EXISTS ppe_checks (
+    checked_at TEXT NOT NULL,
+    camera_id TEXT,
+    zone TEXT,
+    shift TEXT,
+    track_id INTEGER,
+    missing TEXT NOT NULL,
+    alert_id TEXT REFERENCES events(id) ON DELETE SET NULL
+);
+CREATE INDEX IF NOT EXISTS idx_ppe_checks_time ON ppe_checks(checked_at);
+CREATE TABLE IF NOT EXISTS plate_reads (
+    read_at TEXT NOT NULL,
+    camera_id TEXT,
+    plate TEXT NOT NULL,
+    list TEXT NOT NULL,
+    alert_id TEXT REFERENCES events(id) ON DELETE SET NULL
+);
+CREATE INDEX IF NOT EXISTS idx_plate_reads_plate ON plate_reads(plate, read_at);
+",
+    },
+    Migration {
+        version: 2,
+        name: "anonymized_at",
+        // Set once the retention job has checked an event's text
+        sql: "ALTER TABLE events ADD COLUMN anonymized_at TEXT;",
+    },
+    Migration {
+        version: 3,
+        name: "audit_log",
+        sql: "
+CREATE TABLE audit_log (
+    seq INTEGER PRIMARY KEY AUTOINCREMENT,
+    timestamp TEXT NOT NULL,
+    action TEXT NOT NULL,
+    data TEXT NOT NULL,
+    destination TEXT NOT NULL,
+    provider TEXT,
+    bytes INTEGER,
+    error TEXT
+);
+CREATE INDEX idx_audit_log_time ON audit_log(timestamp);
+CREATE TRIGGER audit_log_no_update BEFORE UPDATE ON audit_log BEGIN SELECT RAISE(ABORT, 'audit log is append-only'); END;
+CREATE TRIGGER audit_log_no_delete BEFORE DELETE ON audit_log BEGIN SELECT RAISE(ABORT, 'audit log is append-only'); END;
+",
+    },
+    Migration {
+        version: 4,
+        name: "analysis_usage",
+        // Token counts and timings per analysis, for comparing models
+        sql: "
+CREATE TABLE analysis_usage (
+    event_id TEXT PRIMARY KEY REFERENCES events(id) ON DELETE CASCADE,
+    recorded_at TEXT NOT NULL,
+    provider TEXT NOT NULL,
+    model TEXT NOT NULL,
+    input_tokens INTEGER NOT NULL,
+    output_tokens INTEGER NOT NULL,
+    tokens_per_second REAL,
+    load_ms INTEGER,
+    prompt_eval_ms INTEGER,
+    eval_ms INTEGER,
+    total_ms INTEGER
+);
+CREATE INDEX idx_analysis_usage_model ON analysis_usage(provider, model, recorded_at);
+",
+    },
+    Migration {
+        version: 5,
+        name: "incidents",
+        // Alerts about the same thing on one camera, grouped while it continues
+        sql: "
+CREATE TABLE incidents (
+    id TEXT PRIMARY KEY,
+    camera_id TEXT,
+    kind TEXT NOT NULL,
+    subject TEXT,
+    priority TEXT,
+    description TEXT NOT NULL,
+    state TEXT NOT NULL,
+    opened_at TEXT NOT NULL,
+    updated_at TEXT NOT NULL,
+    acknowledged_at TEXT,
+    acknowledged_by TEXT,
+    resolved_at TEXT,
+    resolution TEXT,
+    alert_count INTEGER NOT NULL,
+    last_alert_id TEXT
+);
+CREATE INDEX idx_incidents_match ON incidents(kind, camera_id, state);
+CREATE INDEX idx_incidents_opened ON incidents(opened_at);
+CREATE TABLE incident_alerts (
+    incident_id TEXT NOT NULL REFERENCES incidents(id) ON DELETE CASCADE,
+    alert_id TEXT NOT NULL REFERENCES events(id) ON DELETE CASCADE,
+    PRIMARY KEY (incident_id, alert_id)
+);
+",
+    },
+    Migration {
+        version: 6,
+        name: "incident_workflow",
+        // Who an incident is assigned to, how long it is snoozed, and everything operators did to it
+        sql: "
+ALTER TABLE incidents ADD COLUMN assigned_to TEXT;
+ALTER TABLE incidents ADD COLUMN snoozed_until TEXT;
+CREATE TABLE incident_activity (
+    seq INTEGER PRIMARY KEY AUTOINCREMENT,
+    incident_id TEXT NOT NULL REFERENCES incidents(id) ON DELETE CASCADE,
+    timestamp TEXT NOT NULL,
+    actor TEXT,
+    action TEXT NOT NULL,
+    detail TEXT
+);
+CREATE INDEX idx_incident_activity ON incident_activity(incident_id, seq);
+",
+    },
+];
+
+const HISTORY: &str = "CREATE TABLE IF NOT EXISTS schema_migrations (
+    version INTEGER PRIMARY KEY,
+    name TEXT NOT NULL,
+    applied_at TEXT NOT NULL
+)";
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct AppliedMigration {
+    pub version: u32,
+    pub name: String,
+    pub applied_at: String,
+}
+
+pub fn latest_version() -> u32 {
+    MIGRATIONS.last().map_or(0, |m| m.version)
+}
+
+pub fn schema_version(conn: &Connection) -> Result<u32, String> {
+    conn.pragma_query_value(None, "user_version", |row| row.get(0))
+        .map_err(|e| format!("Failed to read schema version: {}", e))
+}
+
+// A database written by a newer build would be misread, so it's refused rather than opened
+pub fn check_supported(version: u32, latest: u32) -> Result<(), String> {
+    if version > latest {
+        return Err(format!("Event database schema v{} is newer than this version supports (v{}); update the app", version, latest));
+    }
+    Ok(())
+}
+
+pub fn migrate(conn: &mut Connection, file: Option<&Path>) -> Result<u32, String> {
+    apply(conn, MIGRATIONS, file)
+}
+
+// `file` gets a copy of the database from before the first upgrade step
+fn apply(conn: &mut Connection, migrations: &[Migration], file: Option<&Path>) -> Result<u32, String> {
+    let current = schema_version(conn)?;
+    let latest = migrations.last().map_or(0, |m| m.version);
+    check_supported(current, latest)?;
+    let pending: Vec<&Migration> = migrations.iter().filter(|m| m.version > current).collect();
+    if pending.is_empty() {
+        return Ok(current);
+    }
+
+    if let Some(file) = file.filter(|_| current > 0) {
+        let copy = file.with_extension(format!("v{}.bak", current));
+        conn.backup(DatabaseName::Main, &copy, None)
+            .map_err(|e| format!("Failed to back up the event database before migrating: {}", e))?;
+        println!("💾 Saved schema v{} database to {}", current, copy.display());
+    }
+
+    for migration in pending {
+        let tx = conn.transaction().map_err(|e| e.to_string())?;
+        tx.execute_batch(HISTORY)
+            .and_then(|_| tx.execute_batch(migration.sql))
+            .and_then(|_| {
+                tx.execute(
+                    "INSERT OR REPLACE INTO schema_migrations (version, name, applied_at) VALUES (?1, ?2, ?3)",
+                    params![migration.version, migration.name, format_timestamp(chrono::Utc::now())],
+                )
+            })
+            .and_then(|_| tx.pragma_update(None, "user_version", migration.version))
+            .and_then(|_| tx.commit())
+            .map_err(|e| format!("Migration v{} ({}) failed: {}", migration.version, migration.name, e))?;
+        if current > 0 {
+            println!("🗃️ Migrated event database to schema v{} ({})", migration.version, migration.name);
+        }
+    }
+    Ok(latest)
+}
+
+pub fn applied(conn: &Connection) -> Result<Vec<AppliedMigration>, String> {
+    let mut stmt = conn
+        .prepare("SELECT version, name, applied_at FROM schema_migrations ORDER BY version")
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map([], |row| {
+            Ok(AppliedMigration {
+                version: row.get(0)?,
+                name: row.get(1)?,
+                applied_at: row.get(2)?,
+            })
+        })
+        .map_err(|e| e.to_string())?;
+    rows.collect::<Result<_, _>>().map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_migrations_upgrade_in_order() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("events.db");
+
+        // A database from before versioning keeps its rows
+        let mut conn = Connection::open(&path).unwrap();
+        conn.execute_batch("CREATE TABLE events (id TEXT PRIMARY KEY, timestamp TEXT NOT NULL, camera_id TEXT, event_type TEXT NOT NULL, person_count INTEGER, object_counts TEXT, provider TEXT, prompt TEXT, description TEXT, payload TEXT);
+             INSERT INTO events (id, timestamp, event_type) VALUES ('e1', '2026-01-01T00:00:00.000Z', 'detection');")
+            .unwrap();
+        assert_eq!(migrate(&mut conn, Some(&path)).unwrap(), latest_version());
+        assert_eq!(migrate(&mut conn, Some(&path)).unwrap(), latest_version());
+        assert_eq!(applied(&conn).unwrap().len(), MIGRATIONS.len());
+        assert!(!dir.path().join("events.v0.bak").exists());
+
+        // A later step is applied on top, after a copy of the old version is saved
+        let next = [Migration { version: latest_version() + 1, name: "tags", sql: "ALTER TABLE events ADD COLUMN tags TEXT;" }];
+        let upgraded: Vec<Migration> = MIGRATIONS.iter().copied().chain(next).collect();
+        assert_eq!(apply(&mut conn, &upgraded, Some(&path)).unwrap(), latest_version() + 1);
+        assert!(path.with_extension(format!("v{}.bak", latest_version())).exists());
+        let tagged: i64 = conn.query_row("SELECT COUNT(*) FROM events WHERE tags IS NULL", [], |r| r.get(0)).unwrap();
+        assert_eq!(tagged, 1);
+
+        // A broken step rolls back and leaves the version alone
+        let broken = [Migration { version: latest_version() + 2, name: "broken", sql: "ALTER TABLE missing ADD COLUMN x TEXT;" }];
+        let failing: Vec<Migration> = upgraded.iter().copied().chain(broken).collect();
+        assert!(apply(&mut conn, &failing, None).unwrap_err().contains("broken"));
+        assert_eq!(schema_version(&conn).unwrap(), latest_version() + 1);
+
+        // The current build refuses the newer database
+        assert!(migrate(&mut conn, None).unwrap_err().contains("newer"));
+    }
+}
@@ -0,0 +1,198 @@
+// Mock Module - Deterministic canned results for frontend development without models
+// Enabled with mock_mode in settings or LVA_MOCK=1; fixtures come from a JSON file or the built-in defaults
+
+use crate::settings;
+use crate::vision_provider::{VisionProvider, VisionRequest, VisionResponse};
+use crate::yolo_detector::{BoundingBox, DetectionData};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::OnceLock;
+
+pub const PROVIDER_NAME: &str = "mock";
+
+static FIXTURES: OnceLock<MockFixtures> = OnceLock::new();
+
+// First response whose keyword appears in the prompt wins
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct MockResponse {
+    pub contains: String,
+    pub text: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct MockFixtures {
+    pub responses: Vec<MockResponse>,
+    pub default_text: String,
+    pub detection: DetectionData,
+    pub latency_ms: u64,  // Simulated inference time, to exercise loading states
+}
+
+impl Default for MockFixtures {
+    fn default() -> Self {
+        let response = |contains: &str, text: &str| MockResponse {
+            contains: contains.to_string(),
+            text: text.to_string(),
+        };
+        let person = |x1: f32| BoundingBox {
+            x1,
+            y1: 120.0,
+            x2: x1 + 80.0,
+            y2: 360.0,
+            confidence: 0.9,
+            class_name: "person".to_string(),
+            track_id: None,
+            role: None,
+            floor: None,
+            motion: None,
+        };
+
+        MockFixtures {
+            responses: vec![
+                response(
+                    "queue",
+                    r#"{"people_count": 4, "queue_formation": "line", "estimated_wait_minutes": 3, "crowd_density": "medium", "customer_mood": ["calm"], "staff_needed": false, "description": "Four customers wait in an orderly line at the checkout."}"#,
+                ),
+                response(
+                    "inventory",
+                    r#"{"products_visible": 42, "shelf_capacity_used": 70, "restocking_needed": true, "empty_spots": 3, "product_categories": ["snacks", "drinks"], "organization_quality": "good", "description": "Shelves are mostly stocked with a few gaps on the middle row."}"#,
+                ),
+                response(
+                    "safety",
+                    r#"{"hazard_detected": false, "hazard_type": "none", "immediate_action_required": false, "affected_area": "none", "severity": "low", "description": "The aisle is clear with no visible hazards."}"#,
+                ),
+            ],
+            default_text: "Two people are standing near a counter in a well-lit store. One is holding a shopping basket.".to_string(),
+            detection: DetectionData {
+                person_count: 2,
+                object_counts: HashMap::from([("person".to_string(), 2)]),
+                crowd_density: 0.2,
+                motion_intensity: 0.3,
+                zone_occupancy: 0.25,
+                boxes: vec![person(200.0), person(420.0)],
+                smoothed_person_count: None,
+                visitors: None,
+                staff_count: None,
+                annotations: Default::default(),
+                escalate: None,
+            },
+            latency_ms: 0,
+        }
+    }
+}
+
+impl MockFixtures {
+    pub fn text_for(&self, prompt: &str) -> &str {
+        let prompt = prompt.to_lowercase();
+        self.responses
+            .iter()
+            .find(|r| prompt.contains(&r.contains.to_lowercase()))
+            .map(|r| r.text.as_str())
+            .unwrap_or(&self.default_text)
+    }
+}
+
+pub fn enabled() -> bool {
+    matches!(std::env::var("LVA_MOCK").as_deref(), Ok("1") | Ok("true")) || settings::current().mock_mode
+}
+
+fn fixtures_path() -> Option<PathBuf> {
+    std::env::var("LVA_MOCK_FIXTURES")
+        .ok()
+        .or_else(|| settings::current().mock_fixtures)
+        .map(PathBuf::from)
+}
+
+// Loaded once; a missing or broken file falls back to the defaults
+pub fn fixtures() -> &'static MockFixtures {
+    FIXTURES.get_or_init(|| {
+        let Some(path) = fixtures_path() else {
+            return MockFixtures::default();
+        };
+        match std::fs::read_to_string(&path).map_err(|e| e.to_string()).and_then(|contents| {
+            serde_json::from_str::<MockFixtures>(&contents).map_err(|e| e.to_string())
+        }) {
+            Ok(fixtures) => {
+                println!("Mock: Loaded fixtures from {}", path.display());
+                fixtures
+            }
+            Err(e) => {
+                eprintln!("Failed to load mock fixtures from {}: {}", path.display(), e);
+                MockFixtures::default()
+            }
+        }
+    })
+}
+
+pub async fn simulate_latency() {
+    let latency_ms = fixtures().latency_ms;
+    if latency_ms > 0 {
+        tokio::time::sleep(std::time::Duration::from_millis(latency_ms)).await;
+    }
+}
+
+// Same shape as an Ollama /api/generate reply
+pub async fn ollama_generate(prompt: &str) -> serde_json::Value {
+    simulate_latency().await;
+    serde_json::json!({
+        "model": PROVIDER_NAME,
+        "response": fixtures().text_for(prompt),
+        "done": true
+    })
+}
+
+// Stands in for a real backend under any provider name
+pub struct MockProvider {
+    name: String,
+}
+
+impl MockProvider {
+    pub fn new(name: &str) -> Self {
+        MockProvider { name: name.to_string() }
+    }
+}
+
+#[async_trait]
+impl VisionProvider for MockProvider {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn model(&self) -> &str {
+        PROVIDER_NAME
+    }
+
+    async fn analyze(&self, request: &VisionRequest) -> Result<VisionResponse, String> {
+        if request.images.is_empty() {
+            return Err("No image provided".to_string());
+        }
+        simulate_latency().await;
+        Ok(VisionResponse {
+            provider: self.name.clone(),
+            model: PROVIDER_NAME.to_string(),
+            text: fixtures().text_for(&request.prompt).to_string(),
+            usage: None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fixture_selection() {
+        let fixtures: MockFixtures = serde_json::from_str(
+            r#"{"responses": [{"contains": "Queue", "text": "{\"people_count\": 7}"}], "default_text": "A quiet room."}"#,
+        )
+        .unwrap();
+
+        assert_eq!(fixtures.text_for("Analyze the queue length"), "{\"people_count\": 7}");
+        assert_eq!(fixtures.text_for("Describe the scene"), "A quiet room.");
+        // Fields left out of the file keep their defaults
+        assert_eq!(fixtures.detection.person_count, 2);
+        assert!(MockFixtures::default().text_for("inventory check").contains("restocking_needed"));
+    }
+}
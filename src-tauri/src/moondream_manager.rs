@@ -1,15 +1,24 @@
 // Moondream 3 MoE Vision Model Integration
 // Phase 1: Cloud API Proof of Concept
 
+use crate::audit::{self, AuditAction, AuditEntry};
+use crate::frame_processor;
+use crate::local_only;
+use crate::yolo_detector::{self, Detection, DetectionSource};
+use crate::{network, offline, privacy, scene_prompts, settings};
+use crate::vision_provider::{TokenUsage, VisionProvider, VisionRequest};
 use serde::{Deserialize, Serialize};
+use std::sync::{Arc, RwLock};
 use std::time::{Duration, Instant};
 use reqwest::Client;
 
-#[derive(Clone)]
+// Shared as a plain Arc: every call is independent, so concurrent analyses don't queue behind each other
 pub struct MoondreamManager {
     client: Client,
     api_key: String,
     base_url: String,
+    // In-process moondream used when there is no API key; swapped at runtime when it is loaded or unloaded
+    local: RwLock<Option<Arc<dyn VisionProvider>>>,
 }
 
 #[derive(Serialize)]
@@ -40,33 +49,172 @@ struct MoondreamCaptionRequest {
     stream: bool,
 }
 
-#[derive(Deserialize, Clone)]
+#[derive(Serialize)]
+struct MoondreamGazeRequest {
+    image_url: String,
+    eye: Coordinates,
+    face: BoundingBox,
+    stream: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+struct Coordinates {
+    x: f64,
+    y: f64,
+}
+
+#[derive(Deserialize, Default)]
+struct MoondreamGazeResponse {
+    #[serde(default)]
+    gaze: Option<Coordinates>,  // Null when the person looks out of the frame
+}
+
+// Body of every cloud endpoint; each fills in its own field
+#[derive(Deserialize, Clone, Default)]
 pub struct MoondreamResponse {
     pub answer: Option<String>,
     pub caption: Option<String>,
     pub objects: Option<Vec<ObjectDetection>>,
     pub points: Option<Vec<Point>>,
+    pub confidence: Option<f64>,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+// Corners as fractions of the frame width and height, as the API returns them
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct BoundingBox {
+    pub x_min: f64,
+    pub y_min: f64,
+    pub x_max: f64,
+    pub y_max: f64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct ObjectDetection {
-    pub label: String,
-    pub confidence: f64,
+    #[serde(default)]
+    pub label: String,  // The object asked for; the API doesn't echo it back
+    #[serde(default)]
+    pub confidence: Option<f64>,  // Only when the model reports one
+    #[serde(flatten)]
     pub bbox: BoundingBox,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
-pub struct BoundingBox {
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Point {
+    #[serde(default)]
+    pub label: String,
     pub x: f64,
     pub y: f64,
-    pub width: f64,
-    pub height: f64,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
-pub struct Point {
-    pub x: f64,
-    pub y: f64,
+// Frames that can't be decoded are assumed to be at YOLO's processing resolution
+const DEFAULT_FRAME_SIZE: (u32, u32) = (640, 480);
+
+fn frame_size(image_base64: &str) -> (u32, u32) {
+    frame_processor::decode_frame(image_base64)
+        .map(|image| (image.width(), image.height()))
+        .unwrap_or(DEFAULT_FRAME_SIZE)
+}
+
+impl ObjectDetection {
+    /// The box in frame pixels, as YOLO reports it; a missing confidence becomes 0 like a human label
+    pub fn to_detection(&self, (width, height): (u32, u32)) -> Detection {
+        let (w, h) = (width as f64, height as f64);
+        Detection {
+            source: DetectionSource::Moondream,
+            bbox: yolo_detector::BoundingBox {
+                x1: (self.bbox.x_min * w) as f32,
+                y1: (self.bbox.y_min * h) as f32,
+                x2: (self.bbox.x_max * w) as f32,
+                y2: (self.bbox.y_max * h) as f32,
+                confidence: self.confidence.unwrap_or(0.0) as f32,
+                class_name: self.label.clone(),
+                track_id: None,
+                role: None,
+                floor: None,
+                motion: None,
+            },
+        }
+    }
+}
+
+impl Point {
+    /// A zero-size box at the point, so area checks by box centre still apply
+    pub fn to_detection(&self, (width, height): (u32, u32)) -> Detection {
+        let (x, y) = ((self.x * width as f64) as f32, (self.y * height as f64) as f32);
+        Detection {
+            source: DetectionSource::Moondream,
+            bbox: yolo_detector::BoundingBox {
+                x1: x,
+                y1: y,
+                x2: x,
+                y2: y,
+                confidence: 0.0,
+                class_name: self.label.clone(),
+                track_id: None,
+                role: None,
+                floor: None,
+                motion: None,
+            },
+        }
+    }
+}
+
+fn describe_objects(object: &str, objects: &[ObjectDetection]) -> String {
+    if objects.is_empty() {
+        return format!("No {} found", object);
+    }
+    let boxes: Vec<String> = objects
+        .iter()
+        .map(|o| {
+            let confidence = o.confidence.map(|c| format!(" ({:.0}%)", c * 100.0)).unwrap_or_default();
+            format!("[{:.2}, {:.2}, {:.2}, {:.2}]{}", o.bbox.x_min, o.bbox.y_min, o.bbox.x_max, o.bbox.y_max, confidence)
+        })
+        .collect();
+    format!("Found {} {}: {}", objects.len(), object, boxes.join(", "))
+}
+
+fn describe_points(object: &str, points: &[Point]) -> String {
+    if points.is_empty() {
+        return format!("No {} found", object);
+    }
+    let points: Vec<String> = points.iter().map(|p| format!("({:.2}, {:.2})", p.x, p.y)).collect();
+    format!("Found {} {} at {}", points.len(), object, points.join(", "))
+}
+
+// Where one face is looking, all as fractions of the frame
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Gaze {
+    pub face: BoundingBox,
+    pub eye: Point,  // Keypoint between the eyes the gaze is traced from
+    pub target: Option<Point>,  // None when they look out of the frame
+}
+
+// Eyes sit a little above the middle of a face box
+fn eye_point(face: &BoundingBox) -> Point {
+    Point {
+        label: "eye".to_string(),
+        x: (face.x_min + face.x_max) / 2.0,
+        y: face.y_min + (face.y_max - face.y_min) * 0.4,
+    }
+}
+
+fn describe_gazes(gazes: &[Gaze]) -> String {
+    if gazes.is_empty() {
+        return "No faces found".to_string();
+    }
+    let targets: Vec<String> = gazes
+        .iter()
+        .map(|g| match &g.target {
+            Some(t) => format!("({:.2}, {:.2})", t.x, t.y),
+            None => "out of frame".to_string(),
+        })
+        .collect();
+    format!("{} faces looking at {}", gazes.len(), targets.join(", "))
+}
+
+// Highest per-object confidence, as the result's overall confidence
+fn max_confidence(objects: &[ObjectDetection]) -> Option<f64> {
+    objects.iter().filter_map(|o| o.confidence).reduce(f64::max)
 }
 
 #[derive(Serialize, Deserialize)]
@@ -77,11 +225,13 @@ pub struct AnalysisResult {
     pub processing_time_ms: u64,
     pub confidence: Option<f64>,
     pub error: Option<String>,
+    #[serde(default)]
+    pub usage: Option<TokenUsage>,  // Tokens and timings, when the backend reports them
 }
 
 impl MoondreamManager {
     pub fn new(api_key: String) -> Self {
-        let client = Client::builder()
+        let client = network::client_builder()
             .timeout(Duration::from_secs(30))
             .user_agent("live-vision-analyzer/1.0")
             .build()
@@ -91,13 +241,81 @@ impl MoondreamManager {
             client,
             api_key,
             base_url: "https://api.moondream.ai/v1".to_string(),
+            local: RwLock::new(None),
+        }
+    }
+
+    pub fn set_local_backend(&self, backend: Option<Arc<dyn VisionProvider>>) {
+        if let Ok(mut local) = self.local.write() {
+            *local = backend;
         }
     }
 
+    // The cloud API wins whenever a key is configured
+    fn local_backend(&self) -> Option<Arc<dyn VisionProvider>> {
+        if self.api_key.is_empty() {
+            self.local.read().ok().and_then(|local| local.clone())
+        } else {
+            None
+        }
+    }
+
+    /// Run a prompt through the local backend, shaped like a cloud API result
+    async fn local_query(&self, backend: Arc<dyn VisionProvider>, image_base64: String, prompt: String) -> AnalysisResult {
+        let start_time = Instant::now();
+        let request = VisionRequest {
+            prompt,
+            images: vec![image_base64],
+            timeout_ms: 120000,
+            max_tokens: None,
+            ..Default::default()
+        };
+
+        match backend.analyze(&request).await {
+            Ok(response) => AnalysisResult {
+                provider: "moondream".to_string(),
+                structured_data: self.try_parse_structured(&response.text),
+                response: response.text,
+                processing_time_ms: start_time.elapsed().as_millis() as u64,
+                confidence: None,
+                error: None,
+                usage: response.usage,
+            },
+            Err(e) => AnalysisResult {
+                provider: "moondream".to_string(),
+                response: String::new(),
+                structured_data: None,
+                processing_time_ms: start_time.elapsed().as_millis() as u64,
+                confidence: None,
+                error: Some(format!("Local moondream error: {}", e)),
+                usage: None,
+            },
+        }
+    }
+
+    // Cloud API gate: local-only mode and the redaction policy apply before the frame leaves the machine
+    async fn cloud_image(&self, image_base64: String, endpoint: &str) -> Result<String, String> {
+        local_only::check_url(&self.base_url)?;
+        offline::check_url(&self.base_url)?;
+        let redacted = settings::current().redaction_policy("moondream") == privacy::RedactionPolicy::Redact;
+        let image = privacy::prepare_image("moondream", image_base64).await?;
+        let data = if redacted { "1 frame, faces redacted" } else { "1 frame" };
+        let destination = format!("{}/{}", self.base_url, endpoint);
+        let bytes = audit::base64_bytes(std::slice::from_ref(&image));
+        audit::record(AuditEntry::new(AuditAction::Transmission, data, destination).provider("moondream").bytes(bytes));
+        Ok(image)
+    }
+
     /// Analyze image with custom question using Moondream 3
     pub async fn query(&self, image_base64: String, question: String) -> Result<AnalysisResult, String> {
+        if let Some(backend) = self.local_backend() {
+            return Ok(self.local_query(backend, image_base64, question).await);
+        }
+
         let start_time = Instant::now();
 
+        let image_base64 = self.cloud_image(image_base64, "query").await?;
+
         let request = MoondreamRequest {
             image_url: format!("data:image/jpeg;base64,{}", image_base64),
             question,
@@ -128,22 +346,20 @@ impl MoondreamManager {
                 processing_time_ms: processing_time,
                 confidence: None,
                 error: Some(format!("API error {}: {}", status, error_text)),
+                usage: None,
             });
         }
 
-        let result: serde_json::Value = response
+        let result: MoondreamResponse = response
             .json()
             .await
             .map_err(|e| format!("Failed to parse Moondream response: {}", e))?;
 
-        let answer = result["answer"]
-            .as_str()
-            .unwrap_or("")
-            .to_string();
+        let answer = result.answer.unwrap_or_default();
 
         // Try to parse structured data from the response
         let structured_data = self.try_parse_structured(&answer);
-        let confidence = result["confidence"].as_f64();
+        let confidence = result.confidence;
 
         println!("🌙 Moondream: Analysis completed in {}ms", processing_time);
 
@@ -154,13 +370,26 @@ impl MoondreamManager {
             processing_time_ms: processing_time,
             confidence,
             error: None,
+            usage: None,
         })
     }
 
     /// Generate image caption
     pub async fn caption(&self, image_base64: String, length: Option<String>) -> Result<AnalysisResult, String> {
+        if let Some(backend) = self.local_backend() {
+            let prompt = match length.as_deref() {
+                Some("short") => "Describe this image in one short sentence.",
+                _ => "Describe this image.",
+            };
+            let mut result = self.local_query(backend, image_base64, prompt.to_string()).await;
+            result.structured_data = None;
+            return Ok(result);
+        }
+
         let start_time = Instant::now();
 
+        let image_base64 = self.cloud_image(image_base64, "caption").await?;
+
         let request = MoondreamCaptionRequest {
             image_url: format!("data:image/jpeg;base64,{}", image_base64),
             length: length.unwrap_or("normal".to_string()),
@@ -189,18 +418,16 @@ impl MoondreamManager {
                 processing_time_ms: processing_time,
                 confidence: None,
                 error: Some(format!("Caption API error: {}", response.status())),
+                usage: None,
             });
         }
 
-        let result: serde_json::Value = response
+        let result: MoondreamResponse = response
             .json()
             .await
             .map_err(|e| format!("Failed to parse caption response: {}", e))?;
 
-        let caption = result["caption"]
-            .as_str()
-            .unwrap_or("")
-            .to_string();
+        let caption = result.caption.unwrap_or_default();
 
         println!("🌙 Moondream: Caption generated in {}ms", processing_time);
 
@@ -211,16 +438,37 @@ impl MoondreamManager {
             processing_time_ms: processing_time,
             confidence: None,
             error: None,
+            usage: None,
         })
     }
 
     /// Detect objects in image
     pub async fn detect(&self, image_base64: String, object: String) -> Result<AnalysisResult, String> {
+        let size = frame_size(&image_base64);
+        if let Some(backend) = self.local_backend() {
+            let mut result = self.local_query(backend, image_base64, box_prompt(&object)).await;
+            // moondream2 has no detection head, so boxes come from a prompted answer
+            let objects: Vec<ObjectDetection> = parse_box(&result.response)
+                .map(|[x_min, y_min, x_max, y_max]| ObjectDetection {
+                    label: object.clone(),
+                    confidence: None,
+                    bbox: BoundingBox { x_min, y_min, x_max, y_max },
+                })
+                .into_iter()
+                .collect();
+            let detections: Vec<Detection> = objects.iter().map(|o| o.to_detection(size)).collect();
+            result.response = describe_objects(&object, &objects);
+            result.structured_data = Some(serde_json::json!({ "objects": objects, "detections": detections, "source": "local" }));
+            return Ok(result);
+        }
+
         let start_time = Instant::now();
 
+        let image_base64 = self.cloud_image(image_base64, "detect").await?;
+
         let request = MoondreamDetectRequest {
             image_url: format!("data:image/jpeg;base64,{}", image_base64),
-            object,
+            object: object.clone(),
             stream: false,
         };
 
@@ -246,36 +494,58 @@ impl MoondreamManager {
                 processing_time_ms: processing_time,
                 confidence: None,
                 error: Some(format!("Detect API error: {}", response.status())),
+                usage: None,
             });
         }
 
-        let result: serde_json::Value = response
+        let result: MoondreamResponse = response
             .json()
             .await
             .map_err(|e| format!("Failed to parse detect response: {}", e))?;
 
-        let objects_data = result["objects"].clone();
-        let objects_description = format!("Detected objects: {:?}", objects_data);
+        let mut objects = result.objects.unwrap_or_default();
+        for detected in &mut objects {
+            detected.label = object.clone();
+        }
+
+        let detections: Vec<Detection> = objects.iter().map(|o| o.to_detection(size)).collect();
 
         println!("🌙 Moondream: Object detection completed in {}ms", processing_time);
 
         Ok(AnalysisResult {
             provider: "moondream".to_string(),
-            response: objects_description,
-            structured_data: Some(serde_json::json!({ "objects": objects_data })),
+            response: describe_objects(&object, &objects),
+            confidence: max_confidence(&objects),
+            structured_data: Some(serde_json::json!({ "objects": objects, "detections": detections })),
             processing_time_ms: processing_time,
-            confidence: None,
             error: None,
+            usage: None,
         })
     }
 
     /// Get precise coordinates for objects
     pub async fn point(&self, image_base64: String, object: String) -> Result<AnalysisResult, String> {
+        let size = frame_size(&image_base64);
+        if let Some(backend) = self.local_backend() {
+            let mut result = self.local_query(backend, image_base64, box_prompt(&object)).await;
+            // Points are the centers of the prompted bounding boxes
+            let points: Vec<Point> = parse_box(&result.response)
+                .map(|[x_min, y_min, x_max, y_max]| Point { label: object.clone(), x: (x_min + x_max) / 2.0, y: (y_min + y_max) / 2.0 })
+                .into_iter()
+                .collect();
+            let detections: Vec<Detection> = points.iter().map(|p| p.to_detection(size)).collect();
+            result.response = describe_points(&object, &points);
+            result.structured_data = Some(serde_json::json!({ "points": points, "detections": detections, "source": "local" }));
+            return Ok(result);
+        }
+
         let start_time = Instant::now();
 
+        let image_base64 = self.cloud_image(image_base64, "point").await?;
+
         let request = MoondreamPointRequest {
             image_url: format!("data:image/jpeg;base64,{}", image_base64),
-            object,
+            object: object.clone(),
             stream: false,
         };
 
@@ -301,65 +571,127 @@ impl MoondreamManager {
                 processing_time_ms: processing_time,
                 confidence: None,
                 error: Some(format!("Point API error: {}", response.status())),
+                usage: None,
             });
         }
 
-        let result: serde_json::Value = response
+        let result: MoondreamResponse = response
             .json()
             .await
             .map_err(|e| format!("Failed to parse point response: {}", e))?;
 
-        let points_data = result.clone();
-        let points_description = format!("Object coordinates: {:?}", points_data);
+        let mut points = result.points.unwrap_or_default();
+        for point in &mut points {
+            point.label = object.clone();
+        }
+
+        let detections: Vec<Detection> = points.iter().map(|p| p.to_detection(size)).collect();
 
         println!("🌙 Moondream: Object pointing completed in {}ms", processing_time);
 
         Ok(AnalysisResult {
             provider: "moondream".to_string(),
-            response: points_description,
-            structured_data: Some(points_data),
+            response: describe_points(&object, &points),
+            structured_data: Some(serde_json::json!({ "points": points, "detections": detections })),
             processing_time_ms: processing_time,
             confidence: None,
             error: None,
+            usage: None,
         })
     }
 
-    /// Advanced structured analysis with custom prompt for retail scenarios
-    pub async fn analyze_retail_scene(&self, image_base64: String, scene_type: &str) -> Result<AnalysisResult, String> {
-        let prompt = match scene_type {
-            "queue" => r#"Analyze this retail scene and return a JSON response with:
-{
-  "people_count": number,
-  "queue_formation": "line|cluster|scattered",
-  "estimated_wait_minutes": number,
-  "crowd_density": "low|medium|high",
-  "customer_mood": ["calm", "impatient", "frustrated"],
-  "staff_needed": boolean,
-  "description": "natural language description"
-}"#,
-            "inventory" => r#"Analyze this retail inventory scene and return JSON:
-{
-  "products_visible": number,
-  "shelf_capacity_used": number (0-100),
-  "restocking_needed": boolean,
-  "empty_spots": number,
-  "product_categories": ["category1", "category2"],
-  "organization_quality": "poor|good|excellent",
-  "description": "natural language description"
-}"#,
-            "safety" => r#"Analyze this scene for safety concerns and return JSON:
-{
-  "hazard_detected": boolean,
-  "hazard_type": "spill|obstruction|crowd|equipment|none",
-  "immediate_action_required": boolean,
-  "affected_area": "description of area",
-  "severity": "low|medium|high",
-  "description": "natural language description"
-}"#,
-            _ => "Describe this retail scene in detail, focusing on people, objects, activities, and any notable patterns or issues.",
+    /// Where each face is looking; faces are found with detect unless given. Cloud API only
+    pub async fn gaze(&self, image_base64: String, faces: Option<Vec<BoundingBox>>) -> Result<AnalysisResult, String> {
+        if self.api_key.is_empty() {
+            return Err("Gaze detection needs a Moondream API key".to_string());
+        }
+
+        let start_time = Instant::now();
+        let size = frame_size(&image_base64);
+
+        let faces = match faces {
+            Some(faces) => faces,
+            None => {
+                let detected = self.detect(image_base64.clone(), "face".to_string()).await?;
+                if detected.error.is_some() {
+                    return Ok(detected);
+                }
+                detected
+                    .structured_data
+                    .and_then(|data| data.get("objects").cloned())
+                    .and_then(|objects| serde_json::from_value::<Vec<ObjectDetection>>(objects).ok())
+                    .unwrap_or_default()
+                    .into_iter()
+                    .map(|o| o.bbox)
+                    .collect()
+            }
         };
 
-        self.query(image_base64, prompt.to_string()).await
+        let image_base64 = self.cloud_image(image_base64, "detect_gaze").await?;
+        let image_url = format!("data:image/jpeg;base64,{}", image_base64);
+
+        println!("🌙 Moondream: Detecting gaze for {} faces...", faces.len());
+
+        let mut gazes = Vec::new();
+        for face in faces {
+            let eye = eye_point(&face);
+            let request = MoondreamGazeRequest {
+                image_url: image_url.clone(),
+                eye: Coordinates { x: eye.x, y: eye.y },
+                face: face.clone(),
+                stream: false,
+            };
+
+            let response = self
+                .client
+                .post(&format!("{}/detect_gaze", self.base_url))
+                .header("X-Moondream-Auth", &self.api_key)
+                .header("Content-Type", "application/json")
+                .json(&request)
+                .send()
+                .await
+                .map_err(|e| format!("Moondream gaze request failed: {}", e))?;
+
+            if !response.status().is_success() {
+                return Ok(AnalysisResult {
+                    provider: "moondream".to_string(),
+                    response: String::new(),
+                    structured_data: None,
+                    processing_time_ms: start_time.elapsed().as_millis() as u64,
+                    confidence: None,
+                    error: Some(format!("Gaze API error: {}", response.status())),
+                    usage: None,
+                });
+            }
+
+            let result: MoondreamGazeResponse = response
+                .json()
+                .await
+                .map_err(|e| format!("Failed to parse gaze response: {}", e))?;
+
+            let target = result.gaze.map(|g| Point { label: "gaze".to_string(), x: g.x, y: g.y });
+            gazes.push(Gaze { face, eye, target });
+        }
+
+        let processing_time = start_time.elapsed().as_millis() as u64;
+        let detections: Vec<Detection> = gazes.iter().filter_map(|g| g.target.as_ref()).map(|t| t.to_detection(size)).collect();
+
+        println!("🌙 Moondream: Gaze detection completed in {}ms", processing_time);
+
+        Ok(AnalysisResult {
+            provider: "moondream".to_string(),
+            response: describe_gazes(&gazes),
+            structured_data: Some(serde_json::json!({ "gazes": gazes, "detections": detections })),
+            processing_time_ms: processing_time,
+            confidence: None,
+            error: None,
+            usage: None,
+        })
+    }
+
+    /// Advanced structured analysis with custom prompt for retail scenarios
+    pub async fn analyze_retail_scene(&self, image_base64: String, scene_type: &str) -> Result<AnalysisResult, String> {
+        self.query(image_base64, retail_prompt(scene_type)).await
     }
 
     /// Try to parse structured data from response text
@@ -380,19 +712,81 @@ impl MoondreamManager {
         None
     }
 
+    /// Whether the cloud API accepts the configured key; None when no key is set
+    pub async fn verify_api_key(&self) -> Result<Option<bool>, String> {
+        if self.api_key.is_empty() {
+            return Ok(None);
+        }
+        local_only::check_url(&self.base_url)?;
+        offline::check_url(&self.base_url)?;
+        // An empty body is rejected either way, but the key is checked first, so no image leaves the machine
+        let response = self
+            .client
+            .post(format!("{}/query", self.base_url))
+            .header("X-Moondream-Auth", &self.api_key)
+            .json(&serde_json::json!({}))
+            .timeout(Duration::from_secs(10))
+            .send()
+            .await
+            .map_err(|e| format!("Moondream request failed: {}", e))?;
+        let status = response.status();
+        Ok(Some(status != reqwest::StatusCode::UNAUTHORIZED && status != reqwest::StatusCode::FORBIDDEN))
+    }
+
     /// Check API status and quota
     pub async fn check_status(&self) -> Result<serde_json::Value, String> {
         // This would be a health check endpoint if available
         // For now, just return basic status
+        let local_model = self.local.read().ok().and_then(|local| local.as_ref().map(|backend| backend.model().to_string()));
+        let mode = if !self.api_key.is_empty() {
+            "cloud"
+        } else if local_model.is_some() {
+            "local"
+        } else {
+            "unconfigured"
+        };
+
         Ok(serde_json::json!({
             "provider": "moondream",
             "status": "ready",
+            "mode": mode,
             "base_url": self.base_url,
-            "has_api_key": !self.api_key.is_empty()
+            "has_api_key": !self.api_key.is_empty(),
+            "local_model": local_model
         }))
     }
 }
 
+fn box_prompt(object: &str) -> String {
+    format!(
+        "Give the bounding box of the {} as [x_min, y_min, x_max, y_max] with coordinates between 0 and 1.",
+        object
+    )
+}
+
+// First four numbers in the answer, if they form a normalized box
+fn parse_box(text: &str) -> Option<[f64; 4]> {
+    let numbers: Vec<f64> = text
+        .split(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .filter_map(|part| part.parse::<f64>().ok())
+        .take(4)
+        .collect();
+
+    match numbers.as_slice() {
+        [x_min, y_min, x_max, y_max]
+            if numbers.iter().all(|n| (0.0..=1.0).contains(n)) && x_max > x_min && y_max > y_min =>
+        {
+            Some([*x_min, *y_min, *x_max, *y_max])
+        }
+        _ => None,
+    }
+}
+
+/// Current prompt for a retail scene from the editable registry; unknown scenes get a general description
+pub fn retail_prompt(scene_type: &str) -> String {
+    scene_prompts::current(scene_type).prompt
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -416,4 +810,69 @@ mod tests {
         let result = manager.try_parse_structured(text_without_json);
         assert!(result.is_none());
     }
+
+    #[test]
+    fn test_local_box_parsing() {
+        assert_eq!(parse_box("[0.12, 0.3, 0.5, 0.9]"), Some([0.12, 0.3, 0.5, 0.9]));
+        assert_eq!(parse_box("There is no person."), None);
+        assert_eq!(parse_box("[0.5, 0.3, 0.2, 0.9]"), None);
+    }
+
+    #[test]
+    fn test_typed_cloud_responses() {
+        let detect: MoondreamResponse = serde_json::from_value(serde_json::json!({
+            "request_id": "abc",
+            "objects": [
+                { "x_min": 0.1, "y_min": 0.2, "x_max": 0.4, "y_max": 0.9, "confidence": 0.82 },
+                { "x_min": 0.5, "y_min": 0.2, "x_max": 0.7, "y_max": 0.8 }
+            ]
+        }))
+        .unwrap();
+        let objects = detect.objects.unwrap();
+        assert_eq!(objects[0].bbox, BoundingBox { x_min: 0.1, y_min: 0.2, x_max: 0.4, y_max: 0.9 });
+        assert_eq!((objects[0].confidence, objects[1].confidence), (Some(0.82), None));
+        assert_eq!(max_confidence(&objects), Some(0.82));
+        assert_eq!(describe_objects("person", &objects[..1]), "Found 1 person: [0.10, 0.20, 0.40, 0.90] (82%)");
+
+        let point: MoondreamResponse = serde_json::from_value(serde_json::json!({ "points": [{ "x": 0.25, "y": 0.5 }] })).unwrap();
+        assert_eq!(describe_points("cart", &point.points.unwrap()), "Found 1 cart at (0.25, 0.50)");
+        assert_eq!(describe_points("cart", &[]), "No cart found");
+    }
+
+    #[test]
+    fn test_gaze_from_face() {
+        let face = BoundingBox { x_min: 0.2, y_min: 0.1, x_max: 0.4, y_max: 0.6 };
+        let eye = eye_point(&face);
+        assert!((eye.x - 0.3).abs() < 1e-9 && (eye.y - 0.3).abs() < 1e-9);
+
+        let looking: MoondreamGazeResponse = serde_json::from_value(serde_json::json!({ "gaze": { "x": 0.7, "y": 0.8 } })).unwrap();
+        assert_eq!(looking.gaze, Some(Coordinates { x: 0.7, y: 0.8 }));
+        let away: MoondreamGazeResponse = serde_json::from_value(serde_json::json!({ "gaze": null })).unwrap();
+        assert_eq!(away.gaze, None);
+
+        let gazes = vec![
+            Gaze { face: face.clone(), eye: eye.clone(), target: Some(Point { label: "gaze".to_string(), x: 0.7, y: 0.8 }) },
+            Gaze { face, eye, target: None },
+        ];
+        assert_eq!(describe_gazes(&gazes), "2 faces looking at (0.70, 0.80), out of frame");
+        assert_eq!(describe_gazes(&[]), "No faces found");
+    }
+
+    #[test]
+    fn test_detections_in_yolo_pixels() {
+        let object = ObjectDetection {
+            label: "person".to_string(),
+            confidence: Some(0.5),
+            bbox: BoundingBox { x_min: 0.25, y_min: 0.5, x_max: 0.5, y_max: 1.0 },
+        };
+        let detection = object.to_detection((640, 480));
+        assert_eq!(detection.source, DetectionSource::Moondream);
+        assert_eq!((detection.bbox.x1, detection.bbox.y1, detection.bbox.x2, detection.bbox.y2), (160.0, 240.0, 320.0, 480.0));
+        assert_eq!((detection.bbox.class_name.as_str(), detection.bbox.confidence), ("person", 0.5));
+
+        let point = Point { label: "cart".to_string(), x: 0.5, y: 0.25 }.to_detection((200, 100));
+        assert_eq!((point.bbox.x1, point.bbox.y1, point.bbox.x2, point.bbox.y2), (100.0, 25.0, 100.0, 25.0));
+        let json = serde_json::to_value(&point).unwrap();
+        assert_eq!((json["source"].as_str(), json["class_name"].as_str()), (Some("moondream"), Some("cart")));
+    }
 }
\ No newline at end of file
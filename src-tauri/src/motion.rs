@@ -0,0 +1,206 @@
+// Motion Module - Direction of travel and speed for every detected object
+// Boxes are linked frame to frame by class and distance, so vehicles get tracks too; calibrated cameras add a floor speed
+
+use crate::floor::{self, Homography};
+use crate::settings;
+use crate::yolo_detector::DetectionData;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+// Tracks not seen for longer than this are dropped
+const MAX_GAP_SECS: f32 = 2.0;
+
+// Weight of the newest frame in a track's velocity
+const VELOCITY_UPDATE: f32 = 0.5;
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Motion {
+    pub track_id: u64,         // Motion track; separate from re-identification ids
+    pub direction: [f32; 2],   // Unit vector in the image (x right, y down)
+    pub heading_degrees: f32,  // The same direction as an angle: 0 right, 90 down
+    pub pixels_per_sec: f32,
+    #[serde(default)]
+    pub speed_mps: Option<f32>,  // On cameras with a floor calibration
+}
+
+// Angle between two headings, 0-180
+pub fn heading_difference(a: f32, b: f32) -> f32 {
+    (a - b).rem_euclid(360.0).min((b - a).rem_euclid(360.0))
+}
+
+struct Track {
+    id: u64,
+    class_name: String,
+    center: [f32; 2],
+    floor: Option<[f32; 2]>,
+    seen: DateTime<Utc>,
+    velocity: Option<[f32; 2]>,
+    speed_mps: Option<f32>,
+}
+
+#[derive(Default)]
+struct CameraTracks {
+    tracks: Vec<Track>,
+    next_id: u64,
+}
+
+impl CameraTracks {
+    fn update(&mut self, detection: &mut DetectionData, homography: Option<&Homography>, now: DateTime<Utc>) {
+        let seconds = |t: DateTime<Utc>| (now - t).num_milliseconds() as f32 / 1000.0;
+        self.tracks.retain(|t| seconds(t.seen) <= MAX_GAP_SECS);
+
+        // Nearest pairs first; a box may only continue a track of its class within one box size
+        let mut pairs = Vec::new();
+        for (b, bbox) in detection.boxes.iter().enumerate() {
+            let center = [(bbox.x1 + bbox.x2) / 2.0, (bbox.y1 + bbox.y2) / 2.0];
+            let gate = (bbox.x2 - bbox.x1).max(bbox.y2 - bbox.y1);
+            for (t, track) in self.tracks.iter().enumerate().filter(|(_, t)| t.class_name == bbox.class_name) {
+                let d = floor::distance(center, track.center);
+                if d <= gate {
+                    pairs.push((d, b, t));
+                }
+            }
+        }
+        pairs.sort_by(|a, b| a.0.total_cmp(&b.0));
+        let mut box_track = vec![None; detection.boxes.len()];
+        let mut taken = vec![false; self.tracks.len()];
+        for (_, b, t) in pairs {
+            if box_track[b].is_none() && !taken[t] {
+                box_track[b] = Some(t);
+                taken[t] = true;
+            }
+        }
+
+        for (bbox, matched) in detection.boxes.iter_mut().zip(box_track) {
+            let center = [(bbox.x1 + bbox.x2) / 2.0, (bbox.y1 + bbox.y2) / 2.0];
+            let floor = homography.and_then(|h| h.apply(center[0], bbox.y2));
+            let Some(t) = matched else {
+                self.next_id += 1;
+                self.tracks.push(Track {
+                    id: self.next_id,
+                    class_name: bbox.class_name.clone(),
+                    center,
+                    floor,
+                    seen: now,
+                    velocity: None,
+                    speed_mps: None,
+                });
+                continue;
+            };
+
+            let track = &mut self.tracks[t];
+            let gap = seconds(track.seen);
+            if gap > 0.0 {
+                let current = [(center[0] - track.center[0]) / gap, (center[1] - track.center[1]) / gap];
+                track.velocity = Some(match track.velocity {
+                    Some(v) => [v[0] + VELOCITY_UPDATE * (current[0] - v[0]), v[1] + VELOCITY_UPDATE * (current[1] - v[1])],
+                    None => current,
+                });
+                if let (Some(from), Some(to)) = (track.floor, floor) {
+                    let current = floor::distance(from, to) / gap;
+                    track.speed_mps = Some(track.speed_mps.map(|s| s + VELOCITY_UPDATE * (current - s)).unwrap_or(current));
+                }
+            }
+            track.center = center;
+            track.floor = floor;
+            track.seen = now;
+
+            bbox.motion = track.velocity.map(|v| {
+                let pixels_per_sec = floor::distance(v, [0.0, 0.0]);
+                let direction = if pixels_per_sec > 0.0 { [v[0] / pixels_per_sec, v[1] / pixels_per_sec] } else { [0.0, 0.0] };
+                Motion {
+                    track_id: track.id,
+                    direction,
+                    heading_degrees: v[1].atan2(v[0]).to_degrees().rem_euclid(360.0),
+                    pixels_per_sec,
+                    speed_mps: track.speed_mps,
+                }
+            });
+        }
+    }
+}
+
+// Motion tracks per camera
+#[derive(Default)]
+pub struct MotionTrackers {
+    cameras: HashMap<String, CameraTracks>,
+}
+
+impl MotionTrackers {
+    // Sets BoundingBox::motion on every box continuing a track
+    pub fn apply(&mut self, camera_id: Option<&str>, detection: &mut DetectionData) {
+        let camera = camera_id.unwrap_or("");
+        let homography = settings::current().floor_calibrations.get(camera).and_then(|c| c.homography().ok());
+        self.cameras.entry(camera.to_string()).or_default().update(detection, homography.as_ref(), Utc::now());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::yolo_detector::BoundingBox;
+
+    fn frame(boxes: &[(&str, f32, f32)]) -> DetectionData {
+        DetectionData {
+            boxes: boxes
+                .iter()
+                .map(|(class_name, x, y)| BoundingBox {
+                    x1: *x,
+                    y1: *y,
+                    x2: x + 40.0,
+                    y2: y + 40.0,
+                    confidence: 0.9,
+                    class_name: class_name.to_string(),
+                    track_id: None,
+                    role: None,
+                    floor: None,
+                    motion: None,
+                })
+                .collect(),
+            person_count: 0,
+            object_counts: HashMap::new(),
+            crowd_density: 0.0,
+            motion_intensity: 0.0,
+            zone_occupancy: 0.0,
+            smoothed_person_count: None,
+            visitors: None,
+            staff_count: None,
+            annotations: Default::default(),
+            escalate: None,
+        }
+    }
+
+    #[test]
+    fn test_tracks_give_direction_and_speed() {
+        // 10 pixels = 1 metre on this floor
+        let scale = Homography::from_points(
+            &[[0.0, 0.0], [100.0, 0.0], [100.0, 100.0], [0.0, 100.0]],
+            &[[0.0, 0.0], [10.0, 0.0], [10.0, 10.0], [0.0, 10.0]],
+        )
+        .unwrap();
+        let mut tracks = CameraTracks::default();
+        let start = Utc::now();
+        let at = |ms: i64| start + chrono::Duration::milliseconds(ms);
+
+        // A car heading right at 40 px/s and a person heading up at 20 px/s
+        tracks.update(&mut frame(&[("car", 0.0, 100.0), ("person", 200.0, 200.0)]), Some(&scale), at(0));
+        let mut second = frame(&[("person", 200.0, 190.0), ("car", 20.0, 100.0)]);
+        tracks.update(&mut second, Some(&scale), at(500));
+
+        let person = second.boxes[0].motion.clone().unwrap();
+        let car = second.boxes[1].motion.clone().unwrap();
+        assert_ne!(person.track_id, car.track_id);
+        assert!((car.pixels_per_sec - 40.0).abs() < 1e-3);
+        assert!((car.speed_mps.unwrap() - 4.0).abs() < 1e-3);
+        assert!(heading_difference(car.heading_degrees, 0.0) < 1e-3);
+        assert!((person.heading_degrees - 270.0).abs() < 1e-3);
+        assert_eq!(person.direction, [0.0, -1.0]);
+
+        // Too far to be the same car: starts a new track without motion
+        let mut third = frame(&[("car", 400.0, 100.0)]);
+        tracks.update(&mut third, None, at(1000));
+        assert!(third.boxes[0].motion.is_none());
+        assert_eq!(heading_difference(350.0, 10.0), 20.0);
+    }
+}
@@ -0,0 +1,296 @@
+// OCR Module - Read text in scenes (price tags, signage, whiteboards) without leaving the machine
+// Text lines are located with a local stroke detector; each line crop is then transcribed by a local vision model
+
+use crate::frame_processor::{self, RegionOfInterest};
+use crate::local_inference;
+use crate::pipeline;
+use crate::privacy;
+use crate::bus::BusEvent;
+use crate::AppState;
+use image::imageops::FilterType;
+use image::GrayImage;
+use serde::{Deserialize, Serialize};
+
+// Local window (pixels) a pixel is compared against, and how far it must stand out to count as ink
+const WINDOW_RADIUS: i64 = 12;
+const INK_CONTRAST: i64 = 24;
+
+// Characters shorter than this (in pixels) are noise, longer than this share of the image are shapes
+const MIN_CHAR_HEIGHT: u32 = 6;
+const MAX_CHAR_SHARE: f32 = 0.3;
+
+// Lines beyond this many are skipped (largest are read first)
+const MAX_LINES: usize = 24;
+
+// Crops shorter than this are upscaled so small print stays legible to the model
+const MIN_READ_HEIGHT: u32 = 48;
+
+const PROMPT: &str = "Transcribe the text in this image exactly as written. Reply with the text only, \
+or NONE if there is no readable text.";
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct TextLine {
+    pub text: String,
+    pub x1: f32,
+    pub y1: f32,
+    pub x2: f32,
+    pub y2: f32,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct OcrResult {
+    pub provider: String,
+    pub lines: Vec<TextLine>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Rect {
+    x1: u32,
+    y1: u32,
+    x2: u32,
+    y2: u32,
+}
+
+impl Rect {
+    fn height(&self) -> u32 {
+        self.y2 - self.y1
+    }
+
+    fn area(&self) -> u32 {
+        (self.x2 - self.x1) * self.height()
+    }
+
+    fn union(&self, other: &Rect) -> Rect {
+        Rect {
+            x1: self.x1.min(other.x1),
+            y1: self.y1.min(other.y1),
+            x2: self.x2.max(other.x2),
+            y2: self.y2.max(other.y2),
+        }
+    }
+
+    fn overlap(&self, other: &Rect) -> u32 {
+        let w = self.x2.min(other.x2).saturating_sub(self.x1.max(other.x1));
+        let h = self.y2.min(other.y2).saturating_sub(self.y1.max(other.y1));
+        w * h
+    }
+}
+
+// Dark-on-light (or light-on-dark) pixels relative to their neighbourhood, via an integral image
+fn ink_mask(image: &GrayImage, dark_text: bool) -> Vec<bool> {
+    let (w, h) = (image.width() as i64, image.height() as i64);
+    let mut integral = vec![0i64; ((w + 1) * (h + 1)) as usize];
+    for y in 0..h {
+        let mut row = 0;
+        for x in 0..w {
+            row += image.get_pixel(x as u32, y as u32).0[0] as i64;
+            integral[((y + 1) * (w + 1) + x + 1) as usize] = integral[(y * (w + 1) + x + 1) as usize] + row;
+        }
+    }
+    let sum = |x1: i64, y1: i64, x2: i64, y2: i64| {
+        integral[(y2 * (w + 1) + x2) as usize] - integral[(y1 * (w + 1) + x2) as usize]
+            - integral[(y2 * (w + 1) + x1) as usize]
+            + integral[(y1 * (w + 1) + x1) as usize]
+    };
+
+    let mut mask = vec![false; (w * h) as usize];
+    for y in 0..h {
+        for x in 0..w {
+            let (x1, y1) = ((x - WINDOW_RADIUS).max(0), (y - WINDOW_RADIUS).max(0));
+            let (x2, y2) = ((x + WINDOW_RADIUS + 1).min(w), (y + WINDOW_RADIUS + 1).min(h));
+            let mean = sum(x1, y1, x2, y2) / ((x2 - x1) * (y2 - y1));
+            let value = image.get_pixel(x as u32, y as u32).0[0] as i64;
+            let contrast = if dark_text { mean - value } else { value - mean };
+            mask[(y * w + x) as usize] = contrast > INK_CONTRAST;
+        }
+    }
+    mask
+}
+
+// Connected ink regions shaped like characters
+fn character_boxes(mask: &[bool], width: u32, height: u32) -> Vec<Rect> {
+    let max_height = ((height as f32 * MAX_CHAR_SHARE) as u32).max(MIN_CHAR_HEIGHT);
+    let mut seen = vec![false; mask.len()];
+    let mut boxes = Vec::new();
+
+    for start in 0..mask.len() {
+        if !mask[start] || seen[start] {
+            continue;
+        }
+        let mut stack = vec![start];
+        seen[start] = true;
+        let (mut x1, mut y1, mut x2, mut y2, mut area) = (width, height, 0, 0, 0u32);
+        while let Some(i) = stack.pop() {
+            let (x, y) = ((i as u32) % width, (i as u32) / width);
+            x1 = x1.min(x);
+            y1 = y1.min(y);
+            x2 = x2.max(x + 1);
+            y2 = y2.max(y + 1);
+            area += 1;
+            let neighbours = [
+                (x > 0).then(|| i - 1),
+                (x + 1 < width).then(|| i + 1),
+                (y > 0).then(|| i - width as usize),
+                (y + 1 < height).then(|| i + width as usize),
+            ];
+            for n in neighbours.into_iter().flatten() {
+                if mask[n] && !seen[n] {
+                    seen[n] = true;
+                    stack.push(n);
+                }
+            }
+        }
+
+        let rect = Rect { x1, y1, x2, y2 };
+        let (w, h) = (x2 - x1, y2 - y1);
+        let fill = area as f32 / rect.area() as f32;
+        if (MIN_CHAR_HEIGHT..=max_height).contains(&h) && w <= h * 2 && fill > 0.1 {
+            boxes.push(rect);
+        }
+    }
+    boxes
+}
+
+// Neighbouring characters of similar height on the same baseline form a line
+fn group_lines(mut chars: Vec<Rect>) -> Vec<Rect> {
+    chars.sort_by_key(|c| c.x1);
+    let mut lines: Vec<(Rect, u32, u32)> = Vec::new();  // (bounds, last char height, characters)
+    for c in chars {
+        let joined = lines.iter_mut().find(|(line, last_height, _)| {
+            let vertical = c.y2.min(line.y2).saturating_sub(c.y1.max(line.y1));
+            let (short, tall) = (c.height().min(*last_height), c.height().max(*last_height));
+            vertical * 2 >= short && tall <= short * 2 && c.x1 <= line.x2 + tall
+        });
+        match joined {
+            Some((line, last_height, count)) => {
+                *line = line.union(&c);
+                *last_height = c.height();
+                *count += 1;
+            }
+            None => lines.push((c, c.height(), 1)),
+        }
+    }
+    lines.into_iter().filter(|(_, _, count)| *count >= 2).map(|(line, _, _)| line).collect()
+}
+
+// Text line boxes in image pixels, largest first
+fn detect_lines(image: &GrayImage) -> Vec<Rect> {
+    let (width, height) = image.dimensions();
+    let mut lines: Vec<Rect> = Vec::new();
+    for dark_text in [true, false] {
+        let chars = character_boxes(&ink_mask(image, dark_text), width, height);
+        for line in group_lines(chars) {
+            // The inside of a bold stroke can pass as light-on-dark text; keep the first reading
+            if !lines.iter().any(|kept| kept.overlap(&line) * 2 > line.area().min(kept.area())) {
+                lines.push(line);
+            }
+        }
+    }
+    lines.sort_by_key(|l| std::cmp::Reverse(l.area()));
+    lines.truncate(MAX_LINES);
+    lines
+}
+
+// Only providers that run on this machine: Ollama, in-process inference and "local" endpoints
+async fn ensure_local(state: &AppState, provider: &str) -> Result<(), String> {
+    let local = provider == "llava"
+        || provider == local_inference::PROVIDER_NAME
+        || state.providers.read().await.info(provider).map(|i| i.kind == "local").unwrap_or(false);
+    if local {
+        Ok(())
+    } else {
+        Err(format!("'{}' is not a local provider - OCR uses llava, {} or a \"local\" endpoint", provider, local_inference::PROVIDER_NAME))
+    }
+}
+
+fn clean_text(answer: &str) -> Option<String> {
+    let text = answer.trim().trim_matches(|c| c == '"' || c == '`').trim();
+    if text.is_empty() || text.eq_ignore_ascii_case("none") {
+        return None;
+    }
+    Some(text.to_string())
+}
+
+pub async fn extract_text(
+    state: &AppState,
+    frame_base64: &str,
+    region: Option<&RegionOfInterest>,
+    provider: &str,
+) -> Result<OcrResult, String> {
+    ensure_local(state, provider).await?;
+    // Masked zones are blacked out before cropping, so the model never reads them
+    let image = frame_processor::decode_frame(&privacy::mask_frame(frame_base64.to_string())?)?;
+    let (offset_x, offset_y, image) = match region {
+        Some(region) => {
+            let rect = region.to_crop_rect(image.width(), image.height())?;
+            (rect.x, rect.y, image.crop_imm(rect.x, rect.y, rect.width, rect.height))
+        }
+        None => (0, 0, image),
+    };
+
+    let gray = image.to_luma8();
+    let rects = tokio::task::spawn_blocking(move || detect_lines(&gray))
+        .await
+        .map_err(|e| format!("Text detection failed: {}", e))?;
+
+    let mut lines = Vec::new();
+    for rect in rects {
+        // A little margin so ascenders and descenders aren't clipped
+        let margin = rect.height() / 4;
+        let (x1, y1) = (rect.x1.saturating_sub(margin), rect.y1.saturating_sub(margin));
+        let (x2, y2) = ((rect.x2 + margin).min(image.width()), (rect.y2 + margin).min(image.height()));
+        let mut crop = image.crop_imm(x1, y1, x2 - x1, y2 - y1);
+        if crop.height() < MIN_READ_HEIGHT {
+            let scale = MIN_READ_HEIGHT as f32 / crop.height() as f32;
+            crop = crop.resize((crop.width() as f32 * scale) as u32, MIN_READ_HEIGHT, FilterType::CatmullRom);
+        }
+
+        let start = std::time::Instant::now();
+        let result = pipeline::describe_frame(
+            &state.moondream,
+            &state.providers,
+            provider,
+            PROMPT,
+            frame_processor::encode_frame(&crop)?,
+        )
+        .await;
+        state.bus.publish(BusEvent::analysis(provider, start.elapsed(), result.is_ok()));
+        if let Some(text) = result?.and_then(|(answer, _)| clean_text(&answer)) {
+            lines.push(TextLine {
+                text,
+                x1: (offset_x + rect.x1) as f32,
+                y1: (offset_y + rect.y1) as f32,
+                x2: (offset_x + rect.x2) as f32,
+                y2: (offset_y + rect.y2) as f32,
+            });
+        }
+    }
+
+    // Reading order: top to bottom, then left to right
+    lines.sort_by(|a, b| a.y1.total_cmp(&b.y1).then(a.x1.total_cmp(&b.x1)));
+    Ok(OcrResult { provider: provider.to_string(), lines })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::Luma;
+
+    #[test]
+    fn test_detects_text_lines_but_not_shapes() {
+        // A price tag: two rows of 10x16 "glyphs" with 4px gaps, plus a big dark box and a speck
+        let image = GrayImage::from_fn(200, 120, |x, y| {
+            let glyph = |top: u32, count: u32| (top..top + 16).contains(&y) && x >= 20 && x < 20 + count * 14 && (x - 20) % 14 < 10;
+            let shape = (130..190).contains(&x) && (60..115).contains(&y);
+            let speck = x == 5 && y == 100;
+            Luma([if glyph(20, 6) || glyph(50, 4) || shape || speck { 20 } else { 235 }])
+        });
+
+        let mut lines = detect_lines(&image);
+        lines.sort_by_key(|l| l.y1);
+        assert_eq!(lines, vec![Rect { x1: 20, y1: 20, x2: 100, y2: 36 }, Rect { x1: 20, y1: 50, x2: 72, y2: 66 }]);
+
+        assert_eq!(clean_text("  \"$4.99\" ").as_deref(), Some("$4.99"));
+        assert_eq!(clean_text("NONE"), None);
+    }
+}
@@ -0,0 +1,163 @@
+// Offline Module - Detects when the internet is unreachable and defers what needs it until it's back
+// LAN hosts (cameras, other instances, a local OpenAI-compatible server) keep working while offline
+
+use crate::event_store::format_timestamp;
+use crate::{local_only, network, AppState};
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+
+// Prefix of every deferred-call error, next to local-only's LOCAL_ONLY_BLOCKED
+pub const OFFLINE_CODE: &str = "OFFLINE";
+
+// Any HTTP answer from one of these means the internet is reachable
+const PROBE_URLS: [&str; 2] = ["https://registry.ollama.ai/v2/", "https://github.com"];
+const PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+const ONLINE_INTERVAL: Duration = Duration::from_secs(300);
+const OFFLINE_INTERVAL: Duration = Duration::from_secs(30);
+
+static OFFLINE: AtomicBool = AtomicBool::new(false);
+static STATE: Mutex<OfflineState> = Mutex::new(OfflineState { since: None, last_checked: None, deferred: Vec::new() });
+
+struct OfflineState {
+    since: Option<String>,
+    last_checked: Option<String>,
+    deferred: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct OfflineStatus {
+    pub offline: bool,
+    pub since: Option<String>,
+    pub last_checked: Option<String>,
+    pub deferred: Vec<String>,  // Downloads and uploads waiting for the connection
+}
+
+pub fn is_offline() -> bool {
+    OFFLINE.load(Ordering::Relaxed)
+}
+
+pub fn status() -> OfflineStatus {
+    let state = STATE.lock().unwrap_or_else(|e| e.into_inner());
+    OfflineStatus {
+        offline: is_offline(),
+        since: state.since.clone(),
+        last_checked: state.last_checked.clone(),
+        deferred: state.deferred.clone(),
+    }
+}
+
+// Loopback, private and link-local addresses and .local names don't need the internet
+pub fn is_local_network_url(url: &str) -> bool {
+    let Ok(url) = reqwest::Url::parse(url) else {
+        return false;
+    };
+    let host = url.host_str().unwrap_or("").trim_matches(['[', ']']);
+    match host.parse::<std::net::IpAddr>() {
+        Ok(std::net::IpAddr::V4(ip)) => ip.is_loopback() || ip.is_private() || ip.is_link_local(),
+        Ok(std::net::IpAddr::V6(ip)) => ip.is_loopback() || (ip.segments()[0] & 0xfe00) == 0xfc00,
+        Err(_) => host == "localhost" || host.ends_with(".local"),
+    }
+}
+
+fn defer(what: &str) -> String {
+    let mut state = STATE.lock().unwrap_or_else(|e| e.into_inner());
+    if !state.deferred.iter().any(|d| d == what) {
+        state.deferred.push(what.to_string());
+    }
+    format!("{}: {} is deferred until the internet connection is back", OFFLINE_CODE, what)
+}
+
+// Call next to local_only::check_url for requests that may leave the LAN
+pub fn check_url(url: &str) -> Result<(), String> {
+    if is_offline() && !is_local_network_url(url) {
+        return Err(defer(url));
+    }
+    Ok(())
+}
+
+// For downloads made on our behalf, such as Ollama pulling a model
+pub fn check_remote(what: &str) -> Result<(), String> {
+    if is_offline() {
+        return Err(defer(what));
+    }
+    Ok(())
+}
+
+// Returns true when this changed the mode
+fn set_offline(offline: bool) -> bool {
+    let now = format_timestamp(chrono::Utc::now());
+    let mut state = STATE.lock().unwrap_or_else(|e| e.into_inner());
+    state.last_checked = Some(now.clone());
+    if OFFLINE.swap(offline, Ordering::Relaxed) == offline {
+        return false;
+    }
+    state.since = offline.then_some(now);
+    true
+}
+
+async fn probe() -> bool {
+    let Ok(client) = network::client_builder().timeout(PROBE_TIMEOUT).build() else {
+        return true;
+    };
+    for url in PROBE_URLS {
+        if client.head(url).send().await.is_ok() {
+            return true;
+        }
+    }
+    false
+}
+
+// Probe now and update the mode; returns whether it changed
+pub async fn refresh() -> bool {
+    // Local-only mode blocks the internet on purpose; that isn't being offline
+    let online = local_only::enabled() || probe().await;
+    set_offline(!online)
+}
+
+// Background task: probe on a short interval while offline, a long one while online
+pub async fn run_monitor(app: tauri::AppHandle, state: AppState) {
+    loop {
+        tokio::time::sleep(if is_offline() { OFFLINE_INTERVAL } else { ONLINE_INTERVAL }).await;
+        if refresh().await {
+            if !is_offline() {
+                let deferred = std::mem::take(&mut STATE.lock().unwrap_or_else(|e| e.into_inner()).deferred);
+                println!("🌐 Back online ({} deferred)", deferred.len());
+                // Downloads skipped at startup are retried; uploads resume on their own schedule
+                let backend_ready = state.backend_ready.lock().await.as_ref().is_some_and(|b| b.ready);
+                if !deferred.is_empty() && !backend_ready {
+                    crate::start_backend(&app, &state).await;
+                }
+            } else {
+                println!("📴 Offline: downloads and cloud providers are deferred, local models keep working");
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_offline_defers_internet_only() {
+        assert!(is_local_network_url("http://192.168.1.20:8000/v1"));
+        assert!(is_local_network_url("rtsp://10.0.0.5:554/stream"));
+        assert!(is_local_network_url("http://nvr.local:5000"));
+        assert!(!is_local_network_url("https://api.openai.com/v1"));
+
+        assert!(set_offline(true));
+        assert!(!set_offline(true));
+        assert!(check_url("http://127.0.0.1:11434/api/tags").is_ok());
+        let error = check_url("https://api.moondream.ai/v1").unwrap_err();
+        assert!(error.starts_with(OFFLINE_CODE));
+        assert!(check_remote("Pulling llava").is_err());
+        let status = status();
+        assert!(status.offline && status.since.is_some());
+        assert!(status.deferred.contains(&"Pulling llava".to_string()));
+
+        assert!(set_offline(false));
+        assert!(check_url("https://api.moondream.ai/v1").is_ok());
+    }
+}
@@ -1,24 +1,109 @@
-use std::path::PathBuf;
-use std::process::{Child, Command};
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
 use std::fs;
-use std::io::Write;
 use serde::{Deserialize, Serialize};
-use tauri::AppHandle;
+use tauri::{AppHandle, Emitter};
+use crate::settings;
+use crate::downloads::{self, Transfer};
+use crate::network;
+use crate::offline;
+use crate::storage;
+use crate::ollama_runtime::{self, RuntimeInfo};
+use crate::local_only;
+use crate::power;
+use crate::slo;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+const OLLAMA_ADDRESS: &str = "127.0.0.1:11434";
+const READY_TIMEOUT: Duration = Duration::from_secs(60);
+pub const STARTUP_EVENT: &str = "ollama-startup";
+// How far a pull may run ahead of the download limit before it's stopped and resumed later
+const PULL_SLACK: Duration = Duration::from_secs(10);
+
+// Unix time of the last generate/chat call, for the idle unload policy
+static LAST_ACTIVITY: AtomicU64 = AtomicU64::new(0);
+
+fn now_secs() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct OllamaStatus {
     pub running: bool,
     pub model_ready: bool,
+    pub model: String,
     pub error: Option<String>,
 }
 
+// Progress while waiting for the embedded server to answer
+#[derive(Debug, Serialize, Clone)]
+pub struct StartupProgress {
+    pub attempt: u32,
+    pub elapsed_ms: u64,
+}
+
 pub struct OllamaManager {
     process: Option<Child>,
+    adopted: Option<u32>,  // Pid of an instance a previous session started
     data_dir: PathBuf,
+    app: AppHandle,
+}
+
+// 100 ms doubling up to 2 s between readiness checks
+fn backoff(attempt: u32) -> Duration {
+    Duration::from_millis(100u64.saturating_mul(1 << attempt.min(5))).min(Duration::from_secs(2))
+}
+
+// Whether `pid` is still an Ollama process, so a recycled pid is never signalled
+fn is_ollama(pid: u32) -> bool {
+    let output = if cfg!(windows) {
+        Command::new("tasklist").args(["/FI", &format!("PID eq {}", pid), "/NH"]).output()
+    } else {
+        Command::new("ps").args(["-p", &pid.to_string(), "-o", "comm="]).output()
+    };
+    output.is_ok_and(|o| String::from_utf8_lossy(&o.stdout).to_lowercase().contains("ollama"))
+}
+
+// Ask the whole process tree to exit, forcing it after a grace period
+fn terminate_tree(pid: u32, mut exited: impl FnMut() -> bool) {
+    #[cfg(unix)]
+    {
+        // Negative pid: the process group started with process_group(0)
+        let signal = |sig| unsafe { libc::kill(-(pid as libc::pid_t), sig) };
+        signal(libc::SIGTERM);
+        for _ in 0..30 {
+            if exited() {
+                return;
+            }
+            std::thread::sleep(Duration::from_millis(100));
+        }
+        signal(libc::SIGKILL);
+    }
+    #[cfg(windows)]
+    {
+        let _ = &mut exited;
+        Command::new("taskkill").args(["/T", "/F", "/PID", &pid.to_string()]).output().ok();
+    }
+}
+
+// Why `ollama serve` exited before answering, from its exit status and log
+fn exit_error(status: &str, log: &str) -> String {
+    if log.contains("address already in use") {
+        return format!("Ollama could not bind {}: the port is already in use", OLLAMA_ADDRESS);
+    }
+    match log.lines().rev().map(str::trim).find(|line| !line.is_empty()) {
+        Some(last) => format!("Ollama exited during startup ({}): {}", status, last),
+        None => format!("Ollama exited during startup ({})", status),
+    }
 }
 
 impl OllamaManager {
-    pub fn new(_app_handle: &AppHandle) -> Self {
+    pub fn new(app_handle: &AppHandle) -> Self {
         // For now, use a fixed path in the user's home directory
         let home_dir = std::env::var("HOME").unwrap_or_else(|_| "/tmp".to_string());
         let data_dir = PathBuf::from(home_dir).join(".live-vision-analyzer").join("ollama");
@@ -27,67 +112,47 @@ impl OllamaManager {
 
         Self {
             process: None,
+            adopted: None,
             data_dir,
+            app: app_handle.clone(),
         }
     }
 
-    pub async fn download_ollama(&self) -> Result<PathBuf, String> {
-        let ollama_dir = self.data_dir.join("bin");
-        fs::create_dir_all(&ollama_dir).map_err(|e| e.to_string())?;
+    // Where download_ollama puts the binary under the Ollama data directory
+    pub fn binary_path(data_dir: &Path) -> PathBuf {
+        data_dir.join("bin").join(if cfg!(windows) { "ollama.exe" } else { "ollama" })
+    }
 
-        let ollama_path = ollama_dir.join(if cfg!(windows) {
-            "ollama.exe"
-        } else {
-            "ollama"
-        });
+    pub fn data_dir(&self) -> &Path {
+        &self.data_dir
+    }
 
-        if ollama_path.exists() {
-            return Ok(ollama_path);
+    // Installs the pinned release on first use; upgrades go through upgrade()
+    pub async fn download_ollama(&self) -> Result<PathBuf, String> {
+        let ollama_path = Self::binary_path(&self.data_dir);
+        if !ollama_path.exists() {
+            ollama_runtime::install(&self.data_dir, &ollama_path, ollama_runtime::PINNED_VERSION).await?;
         }
+        Ok(ollama_path)
+    }
 
-        // Download Ollama binary based on platform
-        let download_url = if cfg!(target_os = "macos") {
-            if cfg!(target_arch = "aarch64") {
-                "https://github.com/ollama/ollama/releases/download/v0.4.7/ollama-darwin"
-            } else {
-                "https://github.com/ollama/ollama/releases/download/v0.4.7/ollama-darwin"
-            }
-        } else if cfg!(target_os = "windows") {
-            "https://github.com/ollama/ollama/releases/download/v0.4.7/ollama-windows-amd64.exe"
-        } else {
-            "https://github.com/ollama/ollama/releases/download/v0.4.7/ollama-linux-amd64"
+    // Replace the embedded binary with a known-good release, restarting it if it was running
+    pub async fn upgrade(&mut self, version: Option<&str>) -> Result<RuntimeInfo, String> {
+        let installed = ollama_runtime::installed(&self.data_dir).map(|info| info.version);
+        let version = match version {
+            Some(version) => version.trim_start_matches('v').to_string(),
+            None => ollama_runtime::newer_known_good(installed.as_deref())
+                .ok_or_else(|| format!("Ollama {} is already the latest known-good release", installed.unwrap_or_default()))?
+                .to_string(),
         };
 
-        println!("Downloading Ollama from: {}", download_url);
-
-        let response = reqwest::get(download_url)
-            .await
-            .map_err(|e| format!("Failed to download Ollama: {}", e))?;
-
-        let bytes = response
-            .bytes()
-            .await
-            .map_err(|e| format!("Failed to read download: {}", e))?;
-
-        let mut file = fs::File::create(&ollama_path)
-            .map_err(|e| format!("Failed to create file: {}", e))?;
-
-        file.write_all(&bytes)
-            .map_err(|e| format!("Failed to write file: {}", e))?;
-
-        // Make executable on Unix
-        #[cfg(unix)]
-        {
-            use std::os::unix::fs::PermissionsExt;
-            let mut perms = fs::metadata(&ollama_path)
-                .map_err(|e| e.to_string())?
-                .permissions();
-            perms.set_mode(0o755);
-            fs::set_permissions(&ollama_path, perms)
-                .map_err(|e| e.to_string())?;
+        let was_running = self.process.is_some() || self.adopted.is_some();
+        self.stop();
+        let result = ollama_runtime::install(&self.data_dir, &Self::binary_path(&self.data_dir), &version).await;
+        if was_running {
+            self.start().await?;
         }
-
-        Ok(ollama_path)
+        result
     }
 
     pub async fn start(&mut self) -> Result<(), String> {
@@ -96,18 +161,19 @@ impl OllamaManager {
         }
 
         // First check if Ollama is already running
-        let client = reqwest::Client::new();
-        match client.get("http://127.0.0.1:11434/api/version").send().await {
-            Ok(response) if response.status().is_success() => {
+        let responding = Self::responding().await;
+        self.reclaim_orphan(responding);
+        if responding {
+            if self.adopted.is_none() {
                 println!("Ollama already running on system, using existing instance");
-                // Don't start a new instance, just return success
-                return Ok(());
-            }
-            _ => {
-                // Ollama not running, start embedded instance
-                println!("Starting embedded Ollama...");
             }
+            return Ok(());
         }
+        // Something else holding the port would make `ollama serve` exit straight away
+        if std::net::TcpListener::bind(OLLAMA_ADDRESS).is_err() {
+            return Err(format!("Port {} is in use by another program that isn't answering as Ollama", OLLAMA_ADDRESS));
+        }
+        println!("Starting embedded Ollama...");
 
         let ollama_path = self.download_ollama().await?;
 
@@ -115,43 +181,117 @@ impl OllamaManager {
         let models_dir = self.data_dir.join("models");
         fs::create_dir_all(&models_dir).map_err(|e| e.to_string())?;
 
+        // Server output goes to a log so a crash can be explained
+        let log_path = self.data_dir.join("ollama.log");
+        let log = fs::File::create(&log_path).map_err(|e| format!("Failed to create {}: {}", log_path.display(), e))?;
+
         // Start Ollama server
         let mut cmd = Command::new(ollama_path);
         cmd.env("OLLAMA_MODELS", models_dir)
-            .env("OLLAMA_HOST", "127.0.0.1:11434")
-            .arg("serve");
+            .env("OLLAMA_HOST", OLLAMA_ADDRESS)
+            .envs(network::child_env(&settings::current()))
+            .arg("serve")
+            .stdout(Stdio::null())
+            .stderr(log);
+        // Its own process group, so the runners it spawns are stopped with it
+        #[cfg(unix)]
+        {
+            use std::os::unix::process::CommandExt;
+            cmd.process_group(0);
+        }
 
         let child = cmd.spawn()
             .map_err(|e| format!("Failed to start Ollama: {}", e))?;
 
+        // Lets the next launch find this instance if the app dies without stopping it
+        if let Err(e) = fs::write(self.pid_path(), child.id().to_string()) {
+            eprintln!("Failed to record Ollama pid: {}", e);
+        }
         self.process = Some(child);
+        self.wait_until_ready(&log_path).await
+    }
 
-        // Wait for server to be ready
-        tokio::time::sleep(tokio::time::Duration::from_secs(2)).await;
+    async fn responding() -> bool {
+        let Ok(client) = network::client_builder().timeout(Duration::from_secs(1)).build() else {
+            return false;
+        };
+        let url = format!("http://{}/api/version", OLLAMA_ADDRESS);
+        client.get(url).send().await.is_ok_and(|r| r.status().is_success())
+    }
 
-        Ok(())
+    // Poll /api/version with growing gaps until it answers, the process dies or READY_TIMEOUT passes
+    async fn wait_until_ready(&mut self, log_path: &Path) -> Result<(), String> {
+        let started = std::time::Instant::now();
+        let mut attempt = 0;
+        loop {
+            if Self::responding().await {
+                println!("Ollama ready after {} ms", started.elapsed().as_millis());
+                return Ok(());
+            }
+            if let Some(status) = self.process.as_mut().and_then(|child| child.try_wait().ok().flatten()) {
+                self.process = None;
+                let log = fs::read_to_string(log_path).unwrap_or_default();
+                return Err(exit_error(&status.to_string(), &log));
+            }
+            if started.elapsed() >= READY_TIMEOUT {
+                return Err(format!(
+                    "Ollama is still starting after {} s; it may be slow to load on this machine (see {})",
+                    READY_TIMEOUT.as_secs(),
+                    log_path.display()
+                ));
+            }
+
+            attempt += 1;
+            let progress = StartupProgress { attempt, elapsed_ms: started.elapsed().as_millis() as u64 };
+            if let Err(e) = self.app.emit(STARTUP_EVENT, progress) {
+                eprintln!("Failed to emit Ollama startup progress: {}", e);
+            }
+            tokio::time::sleep(backoff(attempt)).await;
+        }
     }
 
     pub async fn pull_model(&self, model_name: &str) -> Result<(), String> {
         // Check if model already exists
+        // Manifests are stored as library/<name>/<tag>
+        let tagged = settings::normalize_model_tag(model_name);
+        let (name, tag) = tagged.split_once(':').unwrap_or((model_name, "latest"));
         let models_dir = self.data_dir.join("models");
         let model_manifest = models_dir.join("manifests")
             .join("registry.ollama.ai")
             .join("library")
-            .join(model_name);
+            .join(name)
+            .join(tag);
 
         if model_manifest.exists() {
             println!("Model {} already exists", model_name);
             return Ok(());
         }
 
-        // Pull model using API
-        let client = reqwest::Client::new();
-        let response = client
+        // Ollama fetches the weights from its registry on our behalf
+        local_only::check_remote(&format!("Pulling {} from the Ollama registry", model_name))?;
+        offline::check_remote(&format!("Pulling {} from the Ollama registry", model_name))?;
+
+        let required_mb = storage::estimated_model_mb(model_name);
+        storage::ensure_free_space(&models_dir, required_mb, &format!("pull {}", model_name)).await?;
+
+        // Streamed so progress is visible and the pull can be cut short to pace it;
+        // Ollama keeps the partial layers and picks up where it left off on the next request
+        let client = network::client();
+        let mut transfer = Transfer::start(&format!("Pulling {}", model_name), None);
+        let mut layers = HashMap::new();
+        while !Self::pull_once(&client, model_name, &mut transfer, &mut layers).await? {
+            transfer.pace().await?;
+        }
+        Ok(())
+    }
+
+    // One pull request; false when it was stopped early for a pause or the rate limit
+    async fn pull_once(client: &reqwest::Client, model_name: &str, transfer: &mut Transfer, layers: &mut HashMap<String, (u64, u64)>) -> Result<bool, String> {
+        let mut response = client
             .post("http://127.0.0.1:11434/api/pull")
             .json(&serde_json::json!({
                 "name": model_name,
-                "stream": false
+                "stream": true
             }))
             .send()
             .await
@@ -161,43 +301,228 @@ impl OllamaManager {
             return Err(format!("Failed to pull model: {}", response.status()));
         }
 
-        Ok(())
+        let mut pending = Vec::new();
+        while let Some(chunk) = response.chunk().await.map_err(|e| format!("Failed to pull model: {}", e))? {
+            pending.extend_from_slice(&chunk);
+            while let Some(end) = pending.iter().position(|&b| b == b'\n') {
+                let line: Vec<u8> = pending.drain(..=end).collect();
+                let Ok(update) = serde_json::from_slice::<serde_json::Value>(&line) else {
+                    continue;
+                };
+                if let Some(error) = update["error"].as_str() {
+                    return Err(format!("Failed to pull model: {}", error));
+                }
+                if update["status"] == "success" {
+                    return Ok(true);
+                }
+                if let (Some(digest), Some(total)) = (update["digest"].as_str(), update["total"].as_u64()) {
+                    layers.insert(digest.to_string(), (update["completed"].as_u64().unwrap_or(0), total));
+                    transfer.set_total(layers.values().map(|(_, total)| total).sum());
+                    transfer.record(layers.values().map(|(completed, _)| completed).sum());
+                }
+            }
+            // Dropping the response cancels the pull on Ollama's side
+            if downloads::paused() || transfer.ahead() > PULL_SLACK {
+                return Ok(false);
+            }
+        }
+        Err("Failed to pull model: Ollama closed the connection".to_string())
+    }
+
+    // Run a vision prompt through LLaVA with one or more base64 images
+    // `profile` names an inference profile; None uses the active one
+    pub async fn generate(prompt: &str, images: Vec<String>, timeout_ms: u64, profile: Option<&str>) -> Result<serde_json::Value, String> {
+        Self::generate_with_model(None, prompt, images, timeout_ms, profile).await
+    }
+
+    // Same, with another pulled model than the selected one
+    pub async fn generate_with_model(
+        model: Option<&str>,
+        prompt: &str,
+        images: Vec<String>,
+        timeout_ms: u64,
+        profile: Option<&str>,
+    ) -> Result<serde_json::Value, String> {
+        if crate::mock::enabled() {
+            return Ok(crate::mock::ollama_generate(prompt).await);
+        }
+        let client = network::client_builder()
+            .timeout(std::time::Duration::from_millis(timeout_ms))
+            .build()
+            .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
+
+        LAST_ACTIVITY.store(now_secs(), Ordering::Relaxed);
+
+        // Use the selected vision model with the profile's generation options
+        let settings = settings::current();
+        let fallback_profile = slo::profile_override().or_else(power::profile_override);
+        let profile = settings.profile(profile.or(fallback_profile.as_deref()))?;
+        let json_payload = serde_json::json!({
+            "model": model.unwrap_or(&settings.vision_model),
+            "prompt": prompt,
+            "images": images,
+            "stream": false,
+            "keep_alive": profile.keep_alive,
+            "options": profile.options(&settings.tuning)
+        });
+
+        let response = client
+            .post("http://127.0.0.1:11434/api/generate")
+            .json(&json_payload)
+            .send()
+            .await
+            .map_err(|e| format!("Failed to analyze: {}", e))?;
+
+        if !response.status().is_success() {
+            return Err(format!("Analysis failed: {}", response.status()));
+        }
+
+        response.json().await
+            .map_err(|e| format!("Failed to parse response: {}", e))
+    }
+
+    // Send a multi-turn conversation to LLaVA through the chat API
+    pub async fn chat(messages: Vec<serde_json::Value>, timeout_ms: u64, profile: Option<&str>) -> Result<String, String> {
+        if crate::mock::enabled() {
+            let last = messages.last().and_then(|m| m["content"].as_str()).unwrap_or("");
+            let reply = crate::mock::ollama_generate(last).await;
+            return Ok(reply["response"].as_str().unwrap_or("").to_string());
+        }
+        let client = network::client_builder()
+            .timeout(std::time::Duration::from_millis(timeout_ms))
+            .build()
+            .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
+
+        LAST_ACTIVITY.store(now_secs(), Ordering::Relaxed);
+
+        let settings = settings::current();
+        let fallback_profile = slo::profile_override().or_else(power::profile_override);
+        let profile = settings.profile(profile.or(fallback_profile.as_deref()))?;
+        let mut options = profile.options(&settings.tuning);
+        // Larger context to fit the conversation history
+        options["num_ctx"] = serde_json::json!(options["num_ctx"].as_u64().unwrap_or(0).max(4096));
+
+        let json_payload = serde_json::json!({
+            "model": settings.vision_model,
+            "messages": messages,
+            "stream": false,
+            "keep_alive": profile.keep_alive,
+            "options": options
+        });
+
+        let response = client
+            .post("http://127.0.0.1:11434/api/chat")
+            .json(&json_payload)
+            .send()
+            .await
+            .map_err(|e| format!("Failed to send chat request: {}", e))?;
+
+        if !response.status().is_success() {
+            return Err(format!("Chat failed: {}", response.status()));
+        }
+
+        let result: serde_json::Value = response.json().await
+            .map_err(|e| format!("Failed to parse chat response: {}", e))?;
+
+        Ok(result["message"]["content"]
+            .as_str()
+            .unwrap_or("")
+            .trim()
+            .to_string())
+    }
+
+    // Embed one or more texts with an Ollama embedding model
+    pub async fn embed(model: &str, inputs: Vec<String>) -> Result<Vec<Vec<f32>>, String> {
+        let client = network::client_builder()
+            .timeout(std::time::Duration::from_secs(30))
+            .build()
+            .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
+
+        let response = client
+            .post("http://127.0.0.1:11434/api/embed")
+            .json(&serde_json::json!({
+                "model": model,
+                "input": inputs
+            }))
+            .send()
+            .await
+            .map_err(|e| format!("Failed to request embeddings: {}", e))?;
+
+        if !response.status().is_success() {
+            return Err(format!("Embedding failed: {}", response.status()));
+        }
+
+        #[derive(Deserialize)]
+        struct EmbedResponse {
+            embeddings: Vec<Vec<f32>>,
+        }
+
+        let result: EmbedResponse = response.json().await
+            .map_err(|e| format!("Failed to parse embedding response: {}", e))?;
+
+        Ok(result.embeddings)
+    }
+
+    // Return the LLaVA output parsed as JSON when possible, otherwise the raw API result
+    pub fn parse_response_json(result: serde_json::Value) -> serde_json::Value {
+        if let Some(response_text) = result["response"].as_str() {
+            if let Ok(json) = serde_json::from_str::<serde_json::Value>(response_text) {
+                return json;
+            }
+        }
+
+        result
+    }
+
+    // Exact tag match against the /api/tags listing
+    pub fn has_model(tags_body: &str, model: &str) -> bool {
+        let wanted = settings::normalize_model_tag(model);
+        serde_json::from_str::<serde_json::Value>(tags_body)
+            .ok()
+            .and_then(|tags| tags["models"].as_array().cloned())
+            .map(|models| {
+                models.iter().any(|m| {
+                    m["name"].as_str().map(settings::normalize_model_tag).as_deref() == Some(wanted.as_str())
+                })
+            })
+            .unwrap_or(false)
     }
 
     pub async fn check_status() -> OllamaStatus {
-        println!("OllamaManager: Checking status...");
+        let model = settings::current().vision_model;
+        if crate::mock::enabled() {
+            return OllamaStatus {
+                running: true,
+                model_ready: true,
+                model,
+                error: None,
+            };
+        }
         // Check if server is responding (either our process or system Ollama)
-        let client = reqwest::Client::builder()
+        let client = network::client_builder()
             .timeout(std::time::Duration::from_secs(2))
             .build()
             .unwrap();
 
-        println!("OllamaManager: Making request to Ollama API...");
         match client.get("http://127.0.0.1:11434/api/tags").send().await {
             Ok(response) if response.status().is_success() => {
-                // Check if vision model is available
+                // Check that the selected vision model specifically is installed
                 let body = response.text().await.unwrap_or_default();
-                println!("Ollama API response: {}", &body[..body.len().min(200)]);
-
-                // More specific check for llava:7b model
-                let model_ready = body.contains("llava:7b") ||
-                                  body.contains("llava:") ||
-                                  body.contains("llama3.2-vision");
-
-                println!("Model ready status: {}", model_ready);
+                let model_ready = Self::has_model(&body, &model);
 
                 OllamaStatus {
                     running: true,
                     model_ready,
+                    model,
                     error: None,
                 }
             }
             Err(e) => {
-                println!("OllamaManager: Request failed: {}", e);
                 // Server not responding
                 OllamaStatus {
                     running: false,
                     model_ready: false,
+                    model,
                     error: Some(format!("Ollama server not responding: {}", e)),
                 }
             }
@@ -206,16 +531,168 @@ impl OllamaManager {
                 OllamaStatus {
                     running: false,
                     model_ready: false,
+                    model,
                     error: Some(format!("Ollama server returned: {}", response.status())),
                 }
             }
         }
     }
 
+    // Installed models with their size in bytes (GET /api/tags)
+    pub async fn installed_models() -> Result<Vec<(String, u64)>, String> {
+        let client = network::client_builder()
+            .timeout(std::time::Duration::from_secs(5))
+            .build()
+            .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
+
+        let body: serde_json::Value = client
+            .get("http://127.0.0.1:11434/api/tags")
+            .send()
+            .await
+            .map_err(|e| format!("Failed to list models: {}", e))?
+            .json()
+            .await
+            .map_err(|e| format!("Failed to parse response: {}", e))?;
+
+        Ok(body["models"]
+            .as_array()
+            .map(|models| {
+                models
+                    .iter()
+                    .filter_map(|m| Some((m["name"].as_str()?.to_string(), m["size"].as_u64().unwrap_or(0))))
+                    .collect()
+            })
+            .unwrap_or_default())
+    }
+
+    pub async fn delete_model(model: &str) -> Result<(), String> {
+        let client = network::client();
+        let response = client
+            .delete("http://127.0.0.1:11434/api/delete")
+            .json(&serde_json::json!({ "model": model }))
+            .send()
+            .await
+            .map_err(|e| format!("Failed to delete {}: {}", model, e))?;
+
+        if !response.status().is_success() {
+            return Err(format!("Deleting {} failed: {}", model, response.status()));
+        }
+        Ok(())
+    }
+
+    // Models currently held in memory (GET /api/ps)
+    pub async fn loaded_models() -> Result<Vec<String>, String> {
+        let client = network::client_builder()
+            .timeout(std::time::Duration::from_secs(2))
+            .build()
+            .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
+
+        let body: serde_json::Value = client
+            .get("http://127.0.0.1:11434/api/ps")
+            .send()
+            .await
+            .map_err(|e| format!("Failed to list loaded models: {}", e))?
+            .json()
+            .await
+            .map_err(|e| format!("Failed to parse response: {}", e))?;
+
+        Ok(body["models"]
+            .as_array()
+            .map(|models| models.iter().filter_map(|m| m["name"].as_str().map(|n| n.to_string())).collect())
+            .unwrap_or_default())
+    }
+
+    // Change how long a loaded model stays in memory; "0" unloads it right away
+    pub async fn set_model_keep_alive(model: &str, keep_alive: &str) -> Result<(), String> {
+        let client = network::client_builder()
+            .timeout(std::time::Duration::from_secs(30))
+            .build()
+            .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
+
+        // A request without a prompt only updates the model's keep-alive
+        let response = client
+            .post("http://127.0.0.1:11434/api/generate")
+            .json(&serde_json::json!({
+                "model": model,
+                "keep_alive": keep_alive
+            }))
+            .send()
+            .await
+            .map_err(|e| format!("Failed to update keep-alive: {}", e))?;
+
+        if !response.status().is_success() {
+            return Err(format!("Keep-alive update failed: {}", response.status()));
+        }
+        Ok(())
+    }
+
+    // Unload the vision model if it is in memory; returns whether anything was unloaded
+    pub async fn unload_model() -> Result<bool, String> {
+        let model = settings::normalize_model_tag(&settings::current().vision_model);
+        let loaded = Self::loaded_models().await?;
+        if !loaded.iter().any(|m| settings::normalize_model_tag(m) == model) {
+            return Ok(false);
+        }
+
+        Self::set_model_keep_alive(&model, "0").await?;
+        println!("Unloaded {} from memory", model);
+        Ok(true)
+    }
+
+    // Unload the model after `idle_unload_minutes` without any analysis
+    pub async fn run_idle_monitor() {
+        LAST_ACTIVITY.compare_exchange(0, now_secs(), Ordering::Relaxed, Ordering::Relaxed).ok();
+
+        loop {
+            tokio::time::sleep(tokio::time::Duration::from_secs(60)).await;
+
+            let Some(minutes) = settings::current().idle_unload_minutes else {
+                continue;
+            };
+            let idle_secs = now_secs().saturating_sub(LAST_ACTIVITY.load(Ordering::Relaxed));
+            if idle_secs < minutes as u64 * 60 {
+                continue;
+            }
+
+            if let Err(e) = Self::unload_model().await {
+                eprintln!("Idle unload failed: {}", e);
+            }
+        }
+    }
+
+    fn pid_path(&self) -> PathBuf {
+        self.data_dir.join("ollama.pid")
+    }
+
+    // An embedded Ollama left behind by a crash is reused if it answers and stopped if it doesn't
+    fn reclaim_orphan(&mut self, responding: bool) {
+        let Some(pid) = fs::read_to_string(self.pid_path()).ok().and_then(|p| p.trim().parse::<u32>().ok()) else {
+            return;
+        };
+        if self.adopted == Some(pid) {
+            return;
+        }
+        if !is_ollama(pid) {
+            fs::remove_file(self.pid_path()).ok();
+        } else if responding {
+            println!("Adopting Ollama left running by a previous session (pid {})", pid);
+            self.adopted = Some(pid);
+        } else {
+            println!("Stopping unresponsive Ollama left by a previous session (pid {})", pid);
+            terminate_tree(pid, || !is_ollama(pid));
+            fs::remove_file(self.pid_path()).ok();
+        }
+    }
+
     pub fn stop(&mut self) {
         if let Some(mut child) = self.process.take() {
-            child.kill().ok();
+            terminate_tree(child.id(), || child.try_wait().ok().flatten().is_some());
+            child.wait().ok();
+        }
+        if let Some(pid) = self.adopted.take() {
+            terminate_tree(pid, || !is_ollama(pid));
         }
+        fs::remove_file(self.pid_path()).ok();
     }
 }
 
@@ -223,4 +700,21 @@ impl Drop for OllamaManager {
     fn drop(&mut self) {
         self.stop();
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_startup_backoff_and_exit_errors() {
+        let waits: Vec<u64> = (1..=7).map(|a| backoff(a).as_millis() as u64).collect();
+        assert_eq!(waits, vec![200, 400, 800, 1600, 2000, 2000, 2000]);
+
+        let busy = "Error: listen tcp 127.0.0.1:11434: bind: address already in use\n";
+        assert!(exit_error("exit status: 1", busy).contains("already in use"));
+        let crashed = "time=... msg=\"starting\"\nError: unable to load CUDA driver\n\n";
+        assert_eq!(exit_error("exit status: 2", crashed), "Ollama exited during startup (exit status: 2): Error: unable to load CUDA driver");
+        assert_eq!(exit_error("signal: 9", ""), "Ollama exited during startup (signal: 9)");
+    }
+}
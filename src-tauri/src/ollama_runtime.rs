@@ -0,0 +1,292 @@
+// Ollama Runtime Module - Version tracking, update checks and checksum-verified installs of the embedded Ollama binary
+// Only releases listed in KNOWN_GOOD are installed; newer upstream releases are reported but need an app update first
+
+use crate::downloads::Transfer;
+use crate::event_store::format_timestamp;
+use crate::local_only;
+use crate::network;
+use crate::offline;
+use crate::storage;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+
+// Installed on first start
+pub const PINNED_VERSION: &str = "0.4.7";
+
+// Releases tested with this app, oldest first
+pub const KNOWN_GOOD: [&str; 3] = ["0.4.7", "0.5.7", "0.6.8"];
+
+const RELEASES_URL: &str = "https://github.com/ollama/ollama/releases/download";
+const LATEST_RELEASE_API: &str = "https://api.github.com/repos/ollama/ollama/releases/latest";
+const CHECKSUMS: &str = "sha256sum.txt";
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct RuntimeInfo {
+    pub version: String,
+    pub artifact: String,
+    pub sha256: String,
+    pub installed_at: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct UpdateCheck {
+    pub installed: Option<String>,  // None for binaries installed before versions were recorded
+    pub latest_known_good: String,
+    pub update_available: bool,
+    pub upstream_latest: Option<String>,  // Newest GitHub release, when it could be fetched
+}
+
+fn info_path(data_dir: &Path) -> PathBuf {
+    data_dir.join("runtime.json")
+}
+
+pub fn installed(data_dir: &Path) -> Option<RuntimeInfo> {
+    let json = std::fs::read_to_string(info_path(data_dir)).ok()?;
+    serde_json::from_str(&json).ok()
+}
+
+fn parse_version(version: &str) -> Option<(u32, u32, u32)> {
+    let mut parts = version.trim_start_matches('v').split('.').map(|p| p.parse::<u32>().ok());
+    Some((parts.next()??, parts.next()??, parts.next().flatten().unwrap_or(0)))
+}
+
+pub fn latest_known_good() -> &'static str {
+    KNOWN_GOOD.iter().max_by_key(|v| parse_version(v)).copied().unwrap_or(PINNED_VERSION)
+}
+
+// Newest known-good release above `installed`; an unrecorded install always counts as outdated
+pub fn newer_known_good(installed: Option<&str>) -> Option<&'static str> {
+    let latest = latest_known_good();
+    match installed.and_then(parse_version) {
+        Some(current) if parse_version(latest).is_some_and(|l| l <= current) => None,
+        _ => Some(latest),
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ArchiveKind {
+    Binary,
+    TarGz,
+    Zip,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Artifact {
+    pub file: &'static str,
+    pub kind: ArchiveKind,
+    pub into_bin: bool,  // Contents go in bin/; the Linux archives carry their own bin/ and lib/
+}
+
+const fn artifact(file: &'static str, kind: ArchiveKind, into_bin: bool) -> Artifact {
+    Artifact { file, kind, into_bin }
+}
+
+// Release files for a platform in order of preference; a release uses whichever its checksum list names
+pub fn artifacts(os: &str, arch: &str) -> Result<&'static [Artifact], String> {
+    const DARWIN: [Artifact; 2] = [artifact("ollama-darwin.tgz", ArchiveKind::TarGz, true), artifact("ollama-darwin", ArchiveKind::Binary, true)];
+    const LINUX_AMD64: [Artifact; 1] = [artifact("ollama-linux-amd64.tgz", ArchiveKind::TarGz, false)];
+    const LINUX_ARM64: [Artifact; 1] = [artifact("ollama-linux-arm64.tgz", ArchiveKind::TarGz, false)];
+    const WINDOWS_AMD64: [Artifact; 1] = [artifact("ollama-windows-amd64.zip", ArchiveKind::Zip, true)];
+    const WINDOWS_ARM64: [Artifact; 1] = [artifact("ollama-windows-arm64.zip", ArchiveKind::Zip, true)];
+    match (os, arch) {
+        // Universal binaries cover both Apple Silicon and Intel
+        ("macos", "aarch64" | "x86_64") => Ok(&DARWIN),
+        ("linux", "x86_64") => Ok(&LINUX_AMD64),
+        ("linux", "aarch64") => Ok(&LINUX_ARM64),
+        ("windows", "x86_64") => Ok(&WINDOWS_AMD64),
+        ("windows", "aarch64") => Ok(&WINDOWS_ARM64),
+        _ => Err(format!("Ollama has no build for {}/{}", os, arch)),
+    }
+}
+
+// First candidate the release publishes, with its checksum
+pub fn choose_artifact(sums: &str, candidates: &[Artifact]) -> Option<(Artifact, String)> {
+    candidates.iter().find_map(|a| expected_checksum(sums, a.file).map(|hash| (*a, hash)))
+}
+
+fn release_url(version: &str, file: &str) -> String {
+    format!("{}/v{}/{}", RELEASES_URL, version, file)
+}
+
+// Lines of `<hash>  <file>`, with or without a leading "./" or "*"
+pub fn expected_checksum(sums: &str, artifact: &str) -> Option<String> {
+    sums.lines().find_map(|line| {
+        let (hash, file) = line.trim().split_once(char::is_whitespace)?;
+        let file = file.trim().trim_start_matches('*').trim_start_matches("./");
+        (file == artifact).then(|| hash.to_lowercase())
+    })
+}
+
+pub fn sha256_hex(bytes: &[u8]) -> String {
+    Sha256::digest(bytes).iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+// Read in chunks so the download limit and pause apply
+async fn fetch(client: &reqwest::Client, url: &str, name: &str) -> Result<Vec<u8>, String> {
+    local_only::check_url(url)?;
+    offline::check_url(url)?;
+    let mut response = client.get(url).send().await.map_err(|e| format!("Failed to download {}: {}", url, e))?;
+    if !response.status().is_success() {
+        return Err(format!("Failed to download {}: {}", url, response.status()));
+    }
+    let mut transfer = Transfer::start(name, response.content_length());
+    let mut bytes = Vec::new();
+    while let Some(chunk) = response.chunk().await.map_err(|e| format!("Failed to read download: {}", e))? {
+        bytes.extend_from_slice(&chunk);
+        transfer.advance(chunk.len()).await?;
+    }
+    Ok(bytes)
+}
+
+async fn upstream_latest() -> Option<String> {
+    local_only::check_url(LATEST_RELEASE_API).ok()?;
+    offline::check_url(LATEST_RELEASE_API).ok()?;
+    let client = network::client_builder()
+        .timeout(std::time::Duration::from_secs(10))
+        .user_agent("live-vision-analyzer")
+        .build()
+        .ok()?;
+    let release: serde_json::Value = client.get(LATEST_RELEASE_API).send().await.ok()?.json().await.ok()?;
+    release["tag_name"].as_str().map(|tag| tag.trim_start_matches('v').to_string())
+}
+
+pub async fn check_for_update(data_dir: &Path) -> UpdateCheck {
+    let installed = installed(data_dir).map(|info| info.version);
+    UpdateCheck {
+        update_available: newer_known_good(installed.as_deref()).is_some(),
+        installed,
+        latest_known_good: latest_known_good().to_string(),
+        upstream_latest: upstream_latest().await,
+    }
+}
+
+// Download `version`, check it against the release's published checksums and swap it in; the old files are kept as .previous
+pub async fn install(data_dir: &Path, binary: &Path, version: &str) -> Result<RuntimeInfo, String> {
+    if !KNOWN_GOOD.contains(&version) {
+        return Err(format!("Ollama {} is not a known-good release (supported: {})", version, KNOWN_GOOD.join(", ")));
+    }
+    std::fs::create_dir_all(data_dir).map_err(|e| e.to_string())?;
+    // The download plus its unpacked copy
+    storage::ensure_free_space(data_dir, storage::OLLAMA_DOWNLOAD_MB * 2, "download Ollama").await?;
+
+    let candidates = artifacts(std::env::consts::OS, std::env::consts::ARCH)?;
+    let client = network::client();
+    let sums = fetch(&client, &release_url(version, CHECKSUMS), &format!("Ollama {} checksums", version)).await?;
+    let (artifact, expected) = choose_artifact(&String::from_utf8_lossy(&sums), candidates)
+        .ok_or_else(|| format!("Ollama {} has no published build for {}/{}", version, std::env::consts::OS, std::env::consts::ARCH))?;
+
+    println!("Downloading Ollama {} from: {}", version, release_url(version, artifact.file));
+    let bytes = fetch(&client, &release_url(version, artifact.file), &format!("Ollama {}", version)).await?;
+    let actual = sha256_hex(&bytes);
+    if actual != expected {
+        return Err(format!("Checksum mismatch for {} (expected {}, got {})", artifact.file, expected, actual));
+    }
+
+    // Unpacked aside, then bin/ (and lib/) are swapped in whole
+    let staging = data_dir.join("runtime.partial");
+    let _ = std::fs::remove_dir_all(&staging);
+    let binary_name = binary.file_name().ok_or("Invalid Ollama binary path")?;
+    unpack(&artifact, &bytes, &staging, binary_name)?;
+    let relative = binary.strip_prefix(data_dir).map_err(|_| "Ollama binary must be inside its data directory".to_string())?;
+    if !staging.join(relative).is_file() {
+        let _ = std::fs::remove_dir_all(&staging);
+        return Err(format!("{} does not contain {}", artifact.file, relative.display()));
+    }
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(staging.join(relative), std::fs::Permissions::from_mode(0o755)).map_err(|e| e.to_string())?;
+    }
+    swap_in(&staging, data_dir)?;
+
+    let info = RuntimeInfo {
+        version: version.to_string(),
+        artifact: artifact.file.to_string(),
+        sha256: actual,
+        installed_at: format_timestamp(chrono::Utc::now()),
+    };
+    let json = serde_json::to_string_pretty(&info).map_err(|e| e.to_string())?;
+    std::fs::write(info_path(data_dir), json).map_err(|e| format!("Failed to record Ollama version: {}", e))?;
+    println!("📦 Ollama {} installed", version);
+    Ok(info)
+}
+
+fn unpack(artifact: &Artifact, bytes: &[u8], staging: &Path, binary_name: &std::ffi::OsStr) -> Result<(), String> {
+    let target = if artifact.into_bin { staging.join("bin") } else { staging.to_path_buf() };
+    std::fs::create_dir_all(&target).map_err(|e| e.to_string())?;
+    let result = match artifact.kind {
+        ArchiveKind::Binary => std::fs::write(target.join(binary_name), bytes).map_err(|e| e.to_string()),
+        // Both refuse entries that would land outside `target`
+        ArchiveKind::TarGz => tar::Archive::new(flate2::read::GzDecoder::new(bytes)).unpack(&target).map_err(|e| e.to_string()),
+        ArchiveKind::Zip => zip::ZipArchive::new(std::io::Cursor::new(bytes))
+            .and_then(|mut archive| archive.extract(&target))
+            .map_err(|e| e.to_string()),
+    };
+    result.map_err(|e| format!("Failed to unpack {}: {}", artifact.file, e))
+}
+
+// Each top-level entry of `staging` replaces its namesake in `data_dir`
+fn swap_in(staging: &Path, data_dir: &Path) -> Result<(), String> {
+    for entry in std::fs::read_dir(staging).map_err(|e| e.to_string())?.flatten() {
+        let name = entry.file_name();
+        let target = data_dir.join(&name);
+        let previous = data_dir.join(format!("{}.previous", name.to_string_lossy()));
+        let _ = std::fs::remove_dir_all(&previous).or_else(|_| std::fs::remove_file(&previous));
+        if target.exists() {
+            std::fs::rename(&target, &previous).map_err(|e| format!("Failed to replace Ollama: {}", e))?;
+        }
+        std::fs::rename(entry.path(), &target).map_err(|e| format!("Failed to install Ollama: {}", e))?;
+    }
+    let _ = std::fs::remove_dir_all(staging);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_versions_and_checksums() {
+        assert_eq!(latest_known_good(), "0.6.8");
+        assert_eq!(newer_known_good(Some("0.4.7")), Some("0.6.8"));
+        assert_eq!(newer_known_good(Some("v0.6.8")), None);
+        assert_eq!(newer_known_good(Some("0.7.0")), None);
+        assert_eq!(newer_known_good(None), Some("0.6.8"));
+
+        let sums = format!("{}  ./ollama-darwin\n{} *ollama-linux-amd64.tgz\n", "AB12", sha256_hex(b"ollama"));
+        assert_eq!(expected_checksum(&sums, "ollama-darwin").as_deref(), Some("ab12"));
+        assert_eq!(expected_checksum(&sums, "ollama-linux-amd64.tgz"), Some(sha256_hex(b"ollama")));
+        assert_eq!(expected_checksum(&sums, "ollama-windows-amd64.zip"), None);
+
+        // Older macOS releases only have the bare binary
+        let (darwin, _) = choose_artifact(&sums, artifacts("macos", "aarch64").unwrap()).unwrap();
+        assert_eq!(darwin.kind, ArchiveKind::Binary);
+        assert_eq!(artifacts("linux", "aarch64").unwrap()[0].file, "ollama-linux-arm64.tgz");
+        assert!(choose_artifact(&sums, artifacts("windows", "aarch64").unwrap()).is_none());
+        assert!(artifacts("freebsd", "x86_64").is_err());
+    }
+
+    #[test]
+    fn test_archive_unpacked_and_swapped_in() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut tgz = tar::Builder::new(flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::fast()));
+        for (path, contents) in [("bin/ollama", "new"), ("lib/ollama/libggml.so", "lib")] {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(contents.len() as u64);
+            header.set_mode(0o644);
+            tgz.append_data(&mut header, path, contents.as_bytes()).unwrap();
+        }
+        let bytes = tgz.into_inner().unwrap().finish().unwrap();
+        std::fs::create_dir_all(dir.path().join("bin")).unwrap();
+        std::fs::write(dir.path().join("bin").join("ollama"), "old").unwrap();
+
+        let staging = dir.path().join("runtime.partial");
+        let linux = artifacts("linux", "x86_64").unwrap()[0];
+        unpack(&linux, &bytes, &staging, std::ffi::OsStr::new("ollama")).unwrap();
+        swap_in(&staging, dir.path()).unwrap();
+        assert_eq!(std::fs::read_to_string(dir.path().join("bin/ollama")).unwrap(), "new");
+        assert_eq!(std::fs::read_to_string(dir.path().join("bin.previous/ollama")).unwrap(), "old");
+        assert!(dir.path().join("lib/ollama/libggml.so").is_file() && !staging.exists());
+    }
+}
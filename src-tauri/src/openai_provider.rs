@@ -0,0 +1,196 @@
+// OpenAI Provider Module - Chat-completions vision API (GPT-4o and compatible services)
+// The base URL is configurable, so OpenRouter and LM Studio work with the same client
+
+use crate::network;
+use crate::vision_provider::{ProviderSettings, TokenUsage, VisionProvider, VisionRequest, VisionResponse};
+use async_trait::async_trait;
+
+pub const DEFAULT_BASE_URL: &str = "https://api.openai.com/v1";
+pub const DEFAULT_MODEL: &str = "gpt-4o";
+const DEFAULT_MAX_TOKENS: u32 = 300;
+
+pub struct OpenAiProvider {
+    name: String,
+    api_key: Option<String>,
+    base_url: String,
+    model: String,
+    detail: String,  // Image detail level: "low", "high" or "auto"
+}
+
+impl OpenAiProvider {
+    pub fn from_settings(name: &str, settings: &ProviderSettings) -> Result<Self, String> {
+        let base_url = settings
+            .base_url
+            .clone()
+            .unwrap_or_else(|| DEFAULT_BASE_URL.to_string())
+            .trim_end_matches('/')
+            .to_string();
+
+        // Fall back to the environment so keys don't have to be stored on disk
+        let api_key = settings.api_key.clone().or_else(|| std::env::var("OPENAI_API_KEY").ok());
+        if api_key.is_none() && base_url == DEFAULT_BASE_URL {
+            return Err("OpenAI provider needs an API key (settings or OPENAI_API_KEY)".to_string());
+        }
+
+        let detail = settings
+            .options
+            .as_ref()
+            .and_then(|o| o["detail"].as_str())
+            .unwrap_or("low")
+            .to_string();
+
+        Ok(OpenAiProvider {
+            name: name.to_string(),
+            api_key,
+            base_url,
+            model: settings.model.clone().unwrap_or_else(|| DEFAULT_MODEL.to_string()),
+            detail,
+        })
+    }
+
+    // Client for a self-hosted endpoint; no key fallback so cloud keys never leave the machine
+    pub fn for_endpoint(name: &str, base_url: &str, api_key: Option<String>, model: &str) -> Self {
+        OpenAiProvider {
+            name: name.to_string(),
+            api_key,
+            base_url: base_url.trim_end_matches('/').to_string(),
+            model: model.to_string(),
+            detail: "low".to_string(),
+        }
+    }
+
+    // One user message with the prompt followed by every image as a data URL
+    pub fn build_payload(&self, request: &VisionRequest) -> serde_json::Value {
+        let mut content = vec![serde_json::json!({ "type": "text", "text": request.prompt })];
+        for image in &request.images {
+            content.push(serde_json::json!({
+                "type": "image_url",
+                "image_url": {
+                    "url": format!("data:image/jpeg;base64,{}", image),
+                    "detail": self.detail
+                }
+            }));
+        }
+
+        serde_json::json!({
+            "model": request.model_or(&self.model),
+            "messages": [{ "role": "user", "content": content }],
+            "max_tokens": request.max_tokens.unwrap_or(DEFAULT_MAX_TOKENS),
+            "temperature": 0.3
+        })
+    }
+}
+
+// Extract the answer text and token usage from a chat-completions response
+pub fn parse_completion(body: &serde_json::Value) -> Result<(String, Option<TokenUsage>), String> {
+    if let Some(error) = body["error"]["message"].as_str() {
+        return Err(format!("OpenAI API error: {}", error));
+    }
+
+    let text = body["choices"][0]["message"]["content"]
+        .as_str()
+        .ok_or("OpenAI response has no message content")?
+        .to_string();
+
+    let usage = body["usage"]["prompt_tokens"].as_u64().map(|input| TokenUsage {
+        input_tokens: input as u32,
+        output_tokens: body["usage"]["completion_tokens"].as_u64().unwrap_or(0) as u32,
+        ..Default::default()
+    });
+
+    Ok((text, usage))
+}
+
+#[async_trait]
+impl VisionProvider for OpenAiProvider {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn model(&self) -> &str {
+        &self.model
+    }
+
+    fn endpoint(&self) -> Option<&str> {
+        Some(&self.base_url)
+    }
+
+    async fn analyze(&self, request: &VisionRequest) -> Result<VisionResponse, String> {
+        crate::local_only::check_url(&self.base_url)?;
+        crate::offline::check_url(&self.base_url)?;
+
+        let client = network::client_builder()
+            .timeout(std::time::Duration::from_millis(request.timeout_ms))
+            .build()
+            .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
+
+        let mut builder = client
+            .post(format!("{}/chat/completions", self.base_url))
+            .json(&self.build_payload(request));
+        if let Some(api_key) = &self.api_key {
+            builder = builder.bearer_auth(api_key);
+        }
+
+        let response = builder
+            .send()
+            .await
+            .map_err(|e| format!("Failed to reach {}: {}", self.base_url, e))?;
+
+        let status = response.status();
+        let body: serde_json::Value = response
+            .json()
+            .await
+            .map_err(|e| format!("Failed to parse response: {}", e))?;
+        if !status.is_success() && body["error"].is_null() {
+            return Err(format!("Analysis failed: {}", status));
+        }
+
+        let (text, usage) = parse_completion(&body)?;
+        Ok(VisionResponse {
+            provider: self.name.clone(),
+            model: request.model_or(&self.model).to_string(),
+            text,
+            usage,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_payload_and_response_shape() {
+        let settings = ProviderSettings {
+            kind: "openai".to_string(),
+            api_key: None,
+            base_url: Some("http://localhost:1234/v1/".to_string()),
+            model: Some("llava-v1.6".to_string()),
+            options: None,
+        };
+        let provider = OpenAiProvider::from_settings("lmstudio", &settings).unwrap();
+        assert_eq!(provider.base_url, "http://localhost:1234/v1");
+
+        let request = VisionRequest {
+            prompt: "Count people".to_string(),
+            images: vec!["AAAA".to_string()],
+            timeout_ms: 1000,
+            max_tokens: None,
+            ..Default::default()
+        };
+        let payload = provider.build_payload(&request);
+        assert_eq!(payload["model"], "llava-v1.6");
+        assert_eq!(payload["messages"][0]["content"][1]["image_url"]["url"], "data:image/jpeg;base64,AAAA");
+
+        let body = serde_json::json!({
+            "choices": [{ "message": { "content": "Two people" } }],
+            "usage": { "prompt_tokens": 120, "completion_tokens": 4 }
+        });
+        let (text, usage) = parse_completion(&body).unwrap();
+        assert_eq!(text, "Two people");
+        assert_eq!(usage.unwrap().input_tokens, 120);
+
+        let error = serde_json::json!({ "error": { "message": "invalid key" } });
+        assert!(parse_completion(&error).unwrap_err().contains("invalid key"));
+    }
+}
@@ -0,0 +1,196 @@
+// Pacing Module - Adaptive detection frame rate per camera
+// Busy scenes are sampled often, static ones drop to a frame every few seconds, and a loaded CPU stretches the interval
+
+use crate::bus::BusEvent;
+use crate::yolo_detector::DetectionData;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::broadcast::{self, error::RecvError};
+use tokio::sync::Mutex;
+
+// Boxes moving faster than this count as activity
+const MOVING_PIXELS_PER_SEC: f32 = 30.0;
+
+// Activity rises quickly when something happens and settles slowly afterwards
+const RISE: f32 = 0.6;
+const DECAY: f32 = 0.1;
+
+const LOAD_SAMPLE_INTERVAL: Duration = Duration::from_secs(2);
+
+const MAX_INTERVAL_MS: u64 = 60_000;
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct PacingSettings {
+    pub enabled: bool,
+    pub min_interval_ms: u64,  // Busiest scene
+    pub max_interval_ms: u64,  // Static scene
+    pub busy_load: f32,        // 1-minute load per core above which frames are spaced out further
+}
+
+impl Default for PacingSettings {
+    fn default() -> Self {
+        PacingSettings {
+            enabled: true,
+            min_interval_ms: 100,
+            max_interval_ms: 5000,
+            busy_load: 0.8,
+        }
+    }
+}
+
+impl PacingSettings {
+    pub fn validate(&self) -> Result<(), String> {
+        if !(20..=10_000).contains(&self.min_interval_ms) {
+            return Err("min_interval_ms must be between 20 and 10000".to_string());
+        }
+        if self.max_interval_ms < self.min_interval_ms || self.max_interval_ms > MAX_INTERVAL_MS {
+            return Err("max_interval_ms must be at least min_interval_ms and at most 60000".to_string());
+        }
+        if !(self.busy_load > 0.0 && self.busy_load <= 4.0) {
+            return Err("busy_load must be greater than 0 and at most 4".to_string());
+        }
+        Ok(())
+    }
+
+    // Geometric between the bounds, so a quiet scene slows down gradually; CPU pressure only ever lengthens it
+    pub fn interval_ms(&self, activity: f32, cpu_load: Option<f32>) -> u64 {
+        if !self.enabled {
+            return self.min_interval_ms;
+        }
+        let (min, max) = (self.min_interval_ms as f32, self.max_interval_ms as f32);
+        let mut interval = max * (min / max).powf(activity.clamp(0.0, 1.0));
+        if let Some(load) = cpu_load.filter(|load| *load > self.busy_load) {
+            interval *= load / self.busy_load;
+        }
+        (interval.round() as u64).clamp(self.min_interval_ms, self.max_interval_ms)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct FramePacing {
+    pub interval_ms: u64,
+    pub activity: f32,  // 0 (static) to 1 (busy)
+    pub cpu_load: Option<f32>,  // Load per core; None where the OS doesn't report it
+    pub power_factor: f32,  // Stretch applied by the power policy
+    pub adaptive: bool,
+}
+
+// How busy one frame looks: overall motion, share of boxes moving, or a change in head count
+fn activity_sample(detection: &DetectionData, previous_count: Option<u32>) -> f32 {
+    let moving = detection
+        .boxes
+        .iter()
+        .filter(|b| b.motion.as_ref().is_some_and(|m| m.pixels_per_sec >= MOVING_PIXELS_PER_SEC))
+        .count();
+    let moving_share = if detection.boxes.is_empty() { 0.0 } else { moving as f32 / detection.boxes.len() as f32 };
+    let count_change = previous_count
+        .map(|previous| (detection.person_count.abs_diff(previous) as f32 / 3.0).min(1.0))
+        .unwrap_or(0.0);
+    detection.motion_intensity.clamp(0.0, 1.0).max(moving_share).max(count_change)
+}
+
+#[derive(Default)]
+struct CameraPace {
+    activity: f32,
+    last_count: Option<u32>,
+}
+
+#[derive(Default)]
+pub struct FrameRates {
+    cameras: HashMap<String, CameraPace>,
+    load: Option<(Instant, Option<f32>)>,
+}
+
+impl FrameRates {
+    pub fn observe(&mut self, camera_id: Option<&str>, detection: &DetectionData) {
+        let pace = self.cameras.entry(camera_id.unwrap_or_default().to_string()).or_default();
+        let sample = activity_sample(detection, pace.last_count);
+        let weight = if sample > pace.activity { RISE } else { DECAY };
+        pace.activity += (sample - pace.activity) * weight;
+        pace.last_count = Some(detection.person_count);
+    }
+
+    fn cpu_load(&mut self, now: Instant) -> Option<f32> {
+        match self.load {
+            Some((at, load)) if now.duration_since(at) < LOAD_SAMPLE_INTERVAL => load,
+            _ => {
+                let load = read_load();
+                self.load = Some((now, load));
+                load
+            }
+        }
+    }
+
+    pub fn pacing(&mut self, camera_id: Option<&str>, settings: &PacingSettings, power_factor: f32) -> FramePacing {
+        let activity = self.cameras.get(camera_id.unwrap_or_default()).map(|p| p.activity).unwrap_or(1.0);
+        let cpu_load = self.cpu_load(Instant::now());
+        let interval_ms = settings.interval_ms(activity, cpu_load) as f32 * power_factor.max(1.0);
+        FramePacing {
+            interval_ms: (interval_ms.round() as u64).min(MAX_INTERVAL_MS),
+            activity,
+            cpu_load,
+            power_factor,
+            adaptive: settings.enabled,
+        }
+    }
+}
+
+fn read_load() -> Option<f32> {
+    let text = std::fs::read_to_string("/proc/loadavg").ok()?;
+    let one_minute: f32 = text.split_whitespace().next()?.parse().ok()?;
+    let cores = std::thread::available_parallelism().map(|n| n.get()).unwrap_or(1);
+    Some(one_minute / cores as f32)
+}
+
+// Pacing side of the bus: only live detections move a camera's activity
+pub async fn follow_bus(rates: Arc<Mutex<FrameRates>>, mut bus: broadcast::Receiver<BusEvent>) {
+    loop {
+        match bus.recv().await {
+            Ok(BusEvent::DetectionReady { camera_id, detection, live: true, .. }) => {
+                rates.lock().await.observe(camera_id.as_deref(), &detection);
+            }
+            Ok(_) | Err(RecvError::Lagged(_)) => {}
+            Err(RecvError::Closed) => break,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_interval_follows_activity_and_load() {
+        let settings = PacingSettings::default();
+        assert!(settings.validate().is_ok());
+        let detection = |people: u32| -> DetectionData {
+            serde_json::from_value(serde_json::json!({
+                "person_count": people, "object_counts": {}, "crowd_density": 0, "motion_intensity": 0, "zone_occupancy": 0
+            }))
+            .unwrap()
+        };
+
+        // A camera not seen yet runs at full rate
+        let mut rates = FrameRates::default();
+        assert_eq!(settings.interval_ms(rates.pacing(Some("door"), &settings, 1.0).activity, None), 100);
+
+        rates.observe(Some("door"), &detection(0));
+        rates.observe(Some("door"), &detection(4));
+        let busy = rates.cameras["door"].activity;
+        assert!(busy > 0.5);
+        for _ in 0..60 {
+            rates.observe(Some("door"), &detection(4));
+        }
+        let quiet = rates.cameras["door"].activity;
+        assert!(quiet < 0.01);
+
+        assert!(settings.interval_ms(busy, None) < 500);
+        assert!(settings.interval_ms(quiet, None) > 4500);
+        // Twice the busy load doubles a mid-range interval
+        assert_eq!(settings.interval_ms(0.5, Some(1.6)), 2 * settings.interval_ms(0.5, None));
+        assert_eq!(PacingSettings { enabled: false, ..settings }.interval_ms(0.0, None), 100);
+    }
+}
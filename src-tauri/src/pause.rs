@@ -0,0 +1,122 @@
+// Pause Module - Operator pause/resume of all analysis, with the reason kept
+// Each pause is stored as an event so gaps in the detection history can be explained later
+
+use crate::event_store::format_timestamp;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+
+pub const STATE_EVENT: &str = "analysis-state";
+
+pub const DEFAULT_REASON: &str = "Paused by operator";
+
+static CURRENT: Mutex<Option<Pause>> = Mutex::new(None);
+static LAST: Mutex<Option<PauseInterval>> = Mutex::new(None);
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Pause {
+    pub event_id: String,
+    pub reason: String,
+    pub paused_at: DateTime<Utc>,
+}
+
+// A finished pause, as stored in its event's payload
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct PauseInterval {
+    pub event_id: String,
+    pub reason: String,
+    pub paused_at: String,
+    pub resumed_at: String,
+    pub duration_secs: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct AnalysisState {
+    pub paused: bool,
+    pub reason: Option<String>,
+    pub paused_at: Option<String>,
+    pub last_pause: Option<PauseInterval>,
+}
+
+pub fn pause(reason: &str, now: DateTime<Utc>) -> Result<Pause, String> {
+    let mut current = CURRENT.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some(existing) = current.as_ref() {
+        return Err(format!("Analysis is already paused: {}", existing.reason));
+    }
+    let pause = Pause {
+        event_id: uuid::Uuid::new_v4().to_string(),
+        reason: reason.to_string(),
+        paused_at: now,
+    };
+    *current = Some(pause.clone());
+    Ok(pause)
+}
+
+pub fn resume(now: DateTime<Utc>) -> Result<PauseInterval, String> {
+    let pause = CURRENT
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .take()
+        .ok_or_else(|| "Analysis is not paused".to_string())?;
+    let interval = PauseInterval {
+        event_id: pause.event_id,
+        reason: pause.reason,
+        paused_at: format_timestamp(pause.paused_at),
+        resumed_at: format_timestamp(now),
+        duration_secs: (now - pause.paused_at).num_seconds().max(0) as u64,
+    };
+    *LAST.lock().unwrap_or_else(|e| e.into_inner()) = Some(interval.clone());
+    Ok(interval)
+}
+
+pub fn state() -> AnalysisState {
+    let current = CURRENT.lock().unwrap_or_else(|e| e.into_inner()).clone();
+    AnalysisState {
+        paused: current.is_some(),
+        reason: current.as_ref().map(|p| p.reason.clone()),
+        paused_at: current.map(|p| format_timestamp(p.paused_at)),
+        last_pause: LAST.lock().unwrap_or_else(|e| e.into_inner()).clone(),
+    }
+}
+
+pub fn paused_error(reason: &str) -> String {
+    format!("Analysis is paused: {}", reason)
+}
+
+// Called at every entry point that would run detection or a VLM
+pub fn check() -> Result<(), String> {
+    match CURRENT.lock().unwrap_or_else(|e| e.into_inner()).as_ref() {
+        Some(pause) => Err(paused_error(&pause.reason)),
+        None => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::event_store::EventStore;
+
+    #[test]
+    fn test_pause_is_recorded_until_resume() {
+        let store = EventStore::open_in_memory().unwrap();
+        let start = Utc::now();
+        assert!(check().is_ok());
+        assert!(resume(start).is_err());
+
+        let pause = pause("camera maintenance", start).unwrap();
+        store.record_pause(&pause).unwrap();
+        assert_eq!(check().unwrap_err(), "Analysis is paused: camera maintenance");
+        assert!(super::pause("again", start).is_err());
+        assert_eq!(state().reason.as_deref(), Some("camera maintenance"));
+
+        let interval = resume(start + chrono::Duration::seconds(95)).unwrap();
+        store.end_pause(&interval).unwrap();
+        assert!(check().is_ok() && !state().paused);
+        assert_eq!(state().last_pause, Some(interval.clone()));
+
+        let event = store.get_event(&pause.event_id).unwrap().unwrap();
+        assert_eq!(event.event_type, "pause");
+        assert_eq!(event.description.as_deref(), Some("camera maintenance"));
+        assert_eq!(event.payload.unwrap()["duration_secs"], 95);
+    }
+}
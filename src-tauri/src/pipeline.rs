@@ -0,0 +1,124 @@
+// Pipeline Module - Detector and VLM backends shared by the Tauri app and lva-cli
+// Nothing here depends on Tauri, so the same analysis path runs headless
+
+use crate::local_inference;
+use crate::mock;
+use crate::moondream_manager::MoondreamManager;
+use crate::ollama_manager::OllamaManager;
+use crate::vision_provider::{self, ProviderRegistry, VisionRequest};
+use crate::yolo_detector::YoloDetector;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+// Detection and VLM calls take &self, so concurrent frames only contend on the registry while looking up a provider
+pub struct Backends {
+    pub yolo: Arc<YoloDetector>,
+    pub moondream: Arc<MoondreamManager>,
+    pub providers: Arc<RwLock<ProviderRegistry>>,
+    // No cloud key but moondream2 is compiled in; the caller loads it in the background
+    pub use_local_moondream: bool,
+}
+
+// Build every backend from settings and the environment
+pub async fn init_backends() -> Backends {
+    let mut yolo_detector = YoloDetector::new();
+
+    // Initialize Moondream manager with API key from environment
+    let moondream_api_key = std::env::var("MOONDREAM_API_KEY")
+        .unwrap_or_else(|_| {
+            eprintln!("⚠️ MOONDREAM_API_KEY not found in environment");
+            "".to_string()
+        });
+
+    // Mock mode: fixtures stand in for YOLO, Ollama and Moondream
+    let mock_mode = mock::enabled();
+    let moondream_api_key = if mock_mode { String::new() } else { moondream_api_key };
+
+    // Without a cloud key, Moondream falls back to in-process moondream2 when available
+    let use_local_moondream = !mock_mode && moondream_api_key.is_empty() && local_inference::compiled();
+    let moondream = MoondreamManager::new(moondream_api_key);
+    if mock_mode {
+        println!("🧪 Mock mode enabled, no models will be loaded");
+        moondream.set_local_backend(Some(Arc::new(mock::MockProvider::new("moondream"))));
+        yolo_detector.use_fixture(mock::fixtures().detection.clone());
+    }
+    let moondream_manager = Arc::new(moondream);
+    println!("🌙 Moondream 3 MoE Manager initialized");
+
+    // Built-in providers plus any cloud/local endpoints configured earlier
+    let mut providers = ProviderRegistry::load(ProviderRegistry::default_path());
+    providers.register_builtin(Arc::new(vision_provider::LlavaProvider));
+    providers.register_builtin(Arc::new(vision_provider::MoondreamProvider::new(moondream_manager.clone())));
+    if mock_mode {
+        providers.register_builtin(Arc::new(mock::MockProvider::new(mock::PROVIDER_NAME)));
+    }
+
+    if let Err(e) = yolo_detector.initialize().await {
+        eprintln!("Failed to initialize YOLO: {}", e);
+    }
+
+    Backends {
+        yolo: Arc::new(yolo_detector),
+        moondream: moondream_manager,
+        providers: Arc::new(RwLock::new(providers)),
+        use_local_moondream,
+    }
+}
+
+// Fail fast before a long offline run if the chosen provider can't be used
+pub async fn ensure_provider_ready(providers: &RwLock<ProviderRegistry>, provider: &str) -> Result<(), String> {
+    match provider {
+        "llava" => {
+            let status = OllamaManager::check_status().await;
+            if !status.running || !status.model_ready {
+                return Err("Ollama not ready".to_string());
+            }
+            Ok(())
+        }
+        "moondream" | "none" => Ok(()),
+        other if providers.read().await.get(other).is_some() => Ok(()),
+        other => Err(format!("Unknown provider: {}", other)),
+    }
+}
+
+// Single-frame VLM call shared by the offline modes; None when provider is "none"
+pub async fn describe_frame(
+    moondream: &MoondreamManager,
+    providers: &RwLock<ProviderRegistry>,
+    provider: &str,
+    prompt: &str,
+    frame: String,
+) -> Result<Option<(String, serde_json::Value)>, String> {
+    match provider {
+        "llava" => {
+            let result = OllamaManager::generate(prompt, vec![frame], 60000, None).await?;
+            let description = result["response"].as_str().unwrap_or("").to_string();
+            Ok(Some((description, OllamaManager::parse_response_json(result))))
+        }
+        "moondream" => {
+            let result = moondream.query(frame, prompt.to_string()).await?;
+            if let Some(error) = result.error {
+                return Err(error);
+            }
+            Ok(Some((result.response.clone(), serde_json::to_value(result).map_err(|e| e.to_string())?)))
+        }
+        "none" => Ok(None),
+        other => {
+            let backend = providers
+                .read()
+                .await
+                .get(other)
+                .ok_or_else(|| format!("Unknown provider: {}", other))?;
+            let request = VisionRequest {
+                prompt: prompt.to_string(),
+                images: vec![frame],
+                timeout_ms: 60000,
+                max_tokens: None,
+                ..Default::default()
+            };
+            let response = backend.analyze(&request).await?;
+            let parsed = vision_provider::parse_text_json(&response.text);
+            Ok(Some((response.text, parsed)))
+        }
+    }
+}
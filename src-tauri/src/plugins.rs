@@ -0,0 +1,363 @@
+// Plugins Module - Third-party post-processors as sandboxed WebAssembly modules
+// Plugins follow events on the bus, can rewrite live detections and publish named outputs the app can query
+//
+// A plugin exports `memory`, `alloc(len: i32) -> i32` and at least one of:
+//   on_event(ptr: i32, len: i32) -> i64   a stored event as JSON; replies {"outputs": {..}, "alerts": [{"message", "priority"}]}
+//   transform(ptr: i32, len: i32) -> i64  live DetectionData as JSON; replies with the DetectionData to use
+// Replies are (ptr << 32) | len in the plugin's memory, or 0 for no reply. Inputs are only valid during the call.
+
+use crate::event_store::StoredEvent;
+use crate::event_stream::{PRIORITIES, TOPICS};
+use crate::settings::AppSettings;
+use crate::yolo_detector::DetectionData;
+use crate::{settings, AppState};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use tokio::sync::broadcast::error::RecvError;
+use wasmi::{Config, Engine, Instance, Linker, Memory, Module, Store, TypedFunc};
+
+fn default_enabled() -> bool {
+    true
+}
+
+fn default_fuel() -> u64 {
+    50_000_000
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct PluginConfig {
+    pub name: String,
+    pub path: String,  // .wasm file; relative paths are under the app's plugins folder
+    #[serde(default)]
+    pub topics: Vec<String>,  // Event types sent to on_event; empty means all
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    #[serde(default = "default_fuel")]
+    pub fuel: u64,  // Instruction budget per call, so a stuck plugin can't hold up the pipeline
+}
+
+impl PluginConfig {
+    fn wasm_path(&self) -> PathBuf {
+        let path = PathBuf::from(&self.path);
+        if path.is_absolute() {
+            return path;
+        }
+        AppSettings::default_path().parent().map(|dir| dir.join("plugins").join(&path)).unwrap_or(path)
+    }
+}
+
+pub fn validate(plugins: &[PluginConfig]) -> Result<(), String> {
+    for (index, plugin) in plugins.iter().enumerate() {
+        if plugin.name.trim().is_empty() || plugin.path.trim().is_empty() {
+            return Err("Plugins need a name and a path".to_string());
+        }
+        if plugins[..index].iter().any(|p| p.name == plugin.name) {
+            return Err(format!("Duplicate plugin name '{}'", plugin.name));
+        }
+        if let Some(topic) = plugin.topics.iter().find(|t| !TOPICS.contains(&t.as_str())) {
+            return Err(format!("Unknown topic '{}' (use {})", topic, TOPICS.join(", ")));
+        }
+        if plugin.fuel == 0 {
+            return Err("fuel must be at least 1".to_string());
+        }
+    }
+    Ok(())
+}
+
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+pub struct PluginAlert {
+    pub message: String,
+    #[serde(default = "default_priority")]
+    pub priority: String,
+}
+
+fn default_priority() -> String {
+    "medium".to_string()
+}
+
+#[derive(Debug, Deserialize, Default)]
+struct EventReply {
+    #[serde(default)]
+    outputs: BTreeMap<String, serde_json::Value>,
+    #[serde(default)]
+    alerts: Vec<PluginAlert>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct PluginStatus {
+    pub name: String,
+    pub enabled: bool,
+    pub loaded: bool,
+    pub error: Option<String>,
+    pub handles_events: bool,
+    pub transforms: bool,
+    pub outputs: Vec<String>,
+}
+
+struct LoadedPlugin {
+    config: PluginConfig,
+    store: Store<()>,
+    memory: Memory,
+    alloc: TypedFunc<i32, i32>,
+    on_event: Option<TypedFunc<(i32, i32), i64>>,
+    transform: Option<TypedFunc<(i32, i32), i64>>,
+}
+
+impl LoadedPlugin {
+    fn instantiate(engine: &Engine, config: PluginConfig, wasm: &[u8]) -> Result<Self, String> {
+        let module = Module::new(engine, wasm).map_err(|e| format!("Invalid WebAssembly module: {}", e))?;
+        let mut store = Store::new(engine, ());
+        store.set_fuel(config.fuel).map_err(|e| e.to_string())?;
+        // No imports: plugins get no file, network or clock access
+        let instance: Instance = Linker::<()>::new(engine)
+            .instantiate(&mut store, &module)
+            .and_then(|pre| pre.start(&mut store))
+            .map_err(|e| format!("Failed to start plugin: {}", e))?;
+
+        let memory = instance.get_memory(&store, "memory").ok_or("Plugin doesn't export memory")?;
+        let alloc = instance.get_typed_func::<i32, i32>(&store, "alloc").map_err(|e| format!("Plugin alloc: {}", e))?;
+        let on_event = instance.get_typed_func::<(i32, i32), i64>(&store, "on_event").ok();
+        let transform = instance.get_typed_func::<(i32, i32), i64>(&store, "transform").ok();
+        if on_event.is_none() && transform.is_none() {
+            return Err("Plugin exports neither on_event nor transform".to_string());
+        }
+        Ok(LoadedPlugin { config, store, memory, alloc, on_event, transform })
+    }
+
+    fn call(&mut self, function: TypedFunc<(i32, i32), i64>, input: &[u8]) -> Result<Option<Vec<u8>>, String> {
+        self.store.set_fuel(self.config.fuel).map_err(|e| e.to_string())?;
+        let len = i32::try_from(input.len()).map_err(|_| "Plugin input too large".to_string())?;
+        let ptr = self.alloc.call(&mut self.store, len).map_err(|e| format!("alloc failed: {}", e))?;
+        self.memory
+            .write(&mut self.store, ptr as u32 as usize, input)
+            .map_err(|e| format!("Failed to write plugin input: {}", e))?;
+
+        let packed = function.call(&mut self.store, (ptr, len)).map_err(|e| e.to_string())? as u64;
+        if packed == 0 {
+            return Ok(None);
+        }
+        let mut reply = vec![0u8; (packed & 0xffff_ffff) as usize];
+        self.memory
+            .read(&self.store, (packed >> 32) as usize, &mut reply)
+            .map_err(|e| format!("Failed to read plugin reply: {}", e))?;
+        Ok(Some(reply))
+    }
+}
+
+// Loaded plugins and the outputs they have published
+pub struct PluginHost {
+    engine: Engine,
+    plugins: Vec<LoadedPlugin>,
+    errors: BTreeMap<String, String>,
+    outputs: BTreeMap<String, BTreeMap<String, serde_json::Value>>,
+}
+
+impl Default for PluginHost {
+    fn default() -> Self {
+        let mut config = Config::default();
+        config.consume_fuel(true);
+        PluginHost {
+            engine: Engine::new(&config),
+            plugins: Vec::new(),
+            errors: BTreeMap::new(),
+            outputs: BTreeMap::new(),
+        }
+    }
+}
+
+impl PluginHost {
+    // Replace the loaded plugins; one that fails to load is reported in status and skipped
+    pub fn load(&mut self, configs: &[PluginConfig]) {
+        self.plugins.clear();
+        self.errors.clear();
+        self.outputs.retain(|name, _| configs.iter().any(|c| c.name == *name));
+        for config in configs.iter().filter(|c| c.enabled) {
+            let loaded = std::fs::read(config.wasm_path())
+                .map_err(|e| format!("Failed to read {}: {}", config.wasm_path().display(), e))
+                .and_then(|wasm| self.add(config.clone(), &wasm));
+            if let Err(e) = loaded {
+                eprintln!("Plugin '{}' not loaded: {}", config.name, e);
+                self.errors.insert(config.name.clone(), e);
+            }
+        }
+    }
+
+    fn add(&mut self, config: PluginConfig, wasm: &[u8]) -> Result<(), String> {
+        let name = config.name.clone();
+        self.plugins.push(LoadedPlugin::instantiate(&self.engine, config, wasm)?);
+        println!("🧩 Plugin '{}' loaded", name);
+        Ok(())
+    }
+
+    // Hand a bus event to subscribed plugins; returns the alerts they raised
+    pub fn deliver(&mut self, event: &StoredEvent) -> Vec<(String, PluginAlert)> {
+        // A plugin never hears its own alerts back
+        let source = event.payload.as_ref().and_then(|p| p["plugin"].as_str()).unwrap_or("");
+        let mut raised = Vec::new();
+        let Ok(input) = serde_json::to_vec(event) else {
+            return raised;
+        };
+        for plugin in &mut self.plugins {
+            let subscribed = plugin.config.topics.is_empty() || plugin.config.topics.contains(&event.event_type);
+            let Some(function) = plugin.on_event.filter(|_| subscribed && plugin.config.name != source) else {
+                continue;
+            };
+            let reply = plugin.call(function, &input).and_then(|reply| match reply {
+                Some(bytes) => serde_json::from_slice::<EventReply>(&bytes).map_err(|e| format!("Invalid reply: {}", e)),
+                None => Ok(EventReply::default()),
+            });
+            match reply {
+                Ok(reply) => {
+                    let name = plugin.config.name.clone();
+                    self.outputs.entry(name.clone()).or_default().extend(reply.outputs);
+                    for alert in reply.alerts {
+                        if PRIORITIES.contains(&alert.priority.as_str()) {
+                            raised.push((name.clone(), alert));
+                        } else {
+                            eprintln!("Plugin '{}' alert has unknown priority '{}'", name, alert.priority);
+                        }
+                    }
+                }
+                Err(e) => eprintln!("Plugin '{}' failed on event {}: {}", plugin.config.name, event.id, e),
+            }
+        }
+        raised
+    }
+
+    // Let each transforming plugin rewrite the live detection in turn; a failing plugin leaves it unchanged
+    pub fn transform(&mut self, detection: &mut DetectionData) {
+        for plugin in &mut self.plugins {
+            let Some(function) = plugin.transform else {
+                continue;
+            };
+            let result = serde_json::to_vec(&*detection)
+                .map_err(|e| e.to_string())
+                .and_then(|input| plugin.call(function, &input))
+                .and_then(|reply| reply.map(|bytes| serde_json::from_slice(&bytes).map_err(|e| format!("Invalid reply: {}", e))).transpose());
+            match result {
+                Ok(Some(transformed)) => *detection = transformed,
+                Ok(None) => {}
+                Err(e) => eprintln!("Plugin '{}' transform failed: {}", plugin.config.name, e),
+            }
+        }
+    }
+
+    pub fn status(&self, configs: &[PluginConfig]) -> Vec<PluginStatus> {
+        configs
+            .iter()
+            .map(|config| {
+                let loaded = self.plugins.iter().find(|p| p.config.name == config.name);
+                PluginStatus {
+                    name: config.name.clone(),
+                    enabled: config.enabled,
+                    loaded: loaded.is_some(),
+                    error: self.errors.get(&config.name).cloned(),
+                    handles_events: loaded.map(|p| p.on_event.is_some()).unwrap_or(false),
+                    transforms: loaded.map(|p| p.transform.is_some()).unwrap_or(false),
+                    outputs: self.outputs.get(&config.name).map(|o| o.keys().cloned().collect()).unwrap_or_default(),
+                }
+            })
+            .collect()
+    }
+
+    pub fn outputs(&self, plugin: &str) -> Option<&BTreeMap<String, serde_json::Value>> {
+        self.outputs.get(plugin)
+    }
+}
+
+// Feed every bus event to the plugins and store the alerts they raise
+pub async fn run(state: AppState) {
+    state.plugins.lock().await.load(&settings::current().plugins);
+    let mut receiver = state.events.lock().await.subscribe();
+    loop {
+        let event = match receiver.recv().await {
+            Ok(event) => event,
+            Err(RecvError::Lagged(skipped)) => {
+                eprintln!("Plugins skipped {} events", skipped);
+                continue;
+            }
+            Err(RecvError::Closed) => break,
+        };
+        let alerts = state.plugins.lock().await.deliver(&event);
+        for (plugin, alert) in alerts {
+            let payload = serde_json::json!({
+                "kind": "plugin",
+                "priority": alert.priority,
+                "plugin": plugin,
+                "source_event": event.id,
+            });
+            state.bus.alert(event.camera_id.as_deref(), "plugins", &alert.message, payload, None).await;
+            println!("🧩 {} ({})", alert.message, plugin);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn plugin(name: &str, wat: &str) -> (PluginConfig, Vec<u8>) {
+        let config = PluginConfig {
+            name: name.to_string(),
+            path: format!("{}.wasm", name),
+            topics: vec!["detection".to_string()],
+            enabled: true,
+            fuel: 100_000,
+        };
+        (config, wat::parse_str(wat).unwrap())
+    }
+
+    #[test]
+    fn test_plugins_reply_transform_and_run_out_of_fuel() {
+        let reply = r#"{"outputs":{"visits":1},"alerts":[{"message":"Someone is here","priority":"low"}]}"#;
+        // Bump allocator; on_event replies with the JSON in the data segment, transform echoes its input
+        let counter = format!(
+            r#"(module
+                (memory (export "memory") 1)
+                (global $next (mut i32) (i32.const 1024))
+                (data (i32.const 0) "{}")
+                (func (export "alloc") (param $len i32) (result i32)
+                    (global.get $next)
+                    (global.set $next (i32.add (global.get $next) (local.get $len))))
+                (func (export "on_event") (param i32 i32) (result i64) (i64.const {}))
+                (func (export "transform") (param $ptr i32) (param $len i32) (result i64)
+                    (i64.or (i64.shl (i64.extend_i32_u (local.get $ptr)) (i64.const 32)) (i64.extend_i32_u (local.get $len)))))"#,
+            reply.replace('"', "\\\""),
+            reply.len()
+        );
+        let spinner = r#"(module
+            (memory (export "memory") 1)
+            (func (export "alloc") (param i32) (result i32) (i32.const 0))
+            (func (export "on_event") (param i32 i32) (result i64) (loop $forever (br $forever)) (i64.const 0)))"#;
+
+        let mut host = PluginHost::default();
+        let (config, wasm) = plugin("counter", &counter);
+        host.add(config, &wasm).unwrap();
+        let (config, wasm) = plugin("spinner", spinner);
+        host.add(config, &wasm).unwrap();
+        let (config, wasm) = plugin("empty", "(module (memory (export \"memory\") 1))");
+        assert!(host.add(config, &wasm).is_err());
+
+        let event: StoredEvent = serde_json::from_value(serde_json::json!({
+            "id": "e1", "timestamp": "2026-10-16T10:00:00Z", "camera_id": "door", "event_type": "detection",
+            "person_count": 1, "object_counts": null, "provider": null, "prompt": null, "description": null, "payload": null
+        }))
+        .unwrap();
+        // The spinner burns its fuel and is skipped; the counter still answers
+        let alerts = host.deliver(&event);
+        assert_eq!(alerts, vec![("counter".to_string(), PluginAlert { message: "Someone is here".to_string(), priority: "low".to_string() })]);
+        assert_eq!(host.outputs("counter").unwrap()["visits"], serde_json::json!(1));
+        assert!(host.outputs("spinner").is_none());
+
+        let alert = StoredEvent { event_type: "alert".to_string(), ..event };
+        assert!(host.deliver(&alert).is_empty());
+
+        let mut detection: DetectionData = serde_json::from_value(serde_json::json!({
+            "person_count": 2, "object_counts": {"person": 2}, "crowd_density": 0.1, "motion_intensity": 0, "zone_occupancy": 0
+        }))
+        .unwrap();
+        host.transform(&mut detection);
+        assert_eq!(detection.person_count, 2);
+    }
+}
@@ -0,0 +1,279 @@
+// Power Module - Battery and thermal awareness for laptops
+// On battery or when running hot, detection slows down, LLaVA uses a lighter profile and object storage uploads wait
+
+use crate::settings;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::sync::Mutex;
+use tokio::process::Command;
+
+const SAMPLE_SECS: u64 = 30;
+
+static CURRENT: Mutex<Option<PowerState>> = Mutex::new(None);
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct PowerPolicy {
+    pub enabled: bool,
+    pub battery_interval_factor: f32,  // Frame interval multiplier on battery
+    pub battery_profile: Option<String>,  // Inference profile on battery; None keeps the active one
+    pub pause_uploads_on_battery: bool,
+    pub hot_celsius: f32,  // Hottest sensor above this counts as thermal pressure
+    pub hot_interval_factor: f32,
+}
+
+impl Default for PowerPolicy {
+    fn default() -> Self {
+        PowerPolicy {
+            enabled: true,
+            battery_interval_factor: 3.0,
+            battery_profile: Some("fast".to_string()),
+            pause_uploads_on_battery: true,
+            hot_celsius: 85.0,
+            hot_interval_factor: 2.0,
+        }
+    }
+}
+
+impl PowerPolicy {
+    pub fn validate(&self, profiles: &BTreeMap<String, settings::InferenceProfile>) -> Result<(), String> {
+        for factor in [self.battery_interval_factor, self.hot_interval_factor] {
+            if !(1.0..=20.0).contains(&factor) {
+                return Err("Interval factors must be between 1 and 20".to_string());
+            }
+        }
+        if !(40.0..=110.0).contains(&self.hot_celsius) {
+            return Err("hot_celsius must be between 40 and 110".to_string());
+        }
+        if let Some(profile) = self.battery_profile.as_deref().filter(|p| !profiles.contains_key(*p)) {
+            return Err(format!("Unknown inference profile: {}", profile));
+        }
+        Ok(())
+    }
+
+    // What the policy does in this state; battery and heat both slow detection, whichever asks for more wins
+    pub fn apply(&self, state: &PowerState) -> PowerStatus {
+        let on_battery = self.enabled && state.on_battery == Some(true);
+        let hot = self.enabled && (state.temperature_c.is_some_and(|t| t >= self.hot_celsius) || state.thermal_throttled);
+        let mut interval_factor: f32 = 1.0;
+        if on_battery {
+            interval_factor = interval_factor.max(self.battery_interval_factor);
+        }
+        if hot {
+            interval_factor = interval_factor.max(self.hot_interval_factor);
+        }
+        PowerStatus {
+            state: state.clone(),
+            thermal_pressure: hot,
+            interval_factor,
+            profile: self.battery_profile.clone().filter(|_| on_battery),
+            uploads_paused: on_battery && self.pause_uploads_on_battery,
+            policy: self.clone(),
+        }
+    }
+}
+
+// None wherever the platform doesn't say
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct PowerState {
+    pub on_battery: Option<bool>,
+    pub battery_percent: Option<u8>,
+    pub temperature_c: Option<f32>,  // Hottest thermal zone
+    pub thermal_throttled: bool,  // The OS reports it is limiting the CPU
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct PowerStatus {
+    pub state: PowerState,
+    pub thermal_pressure: bool,
+    pub interval_factor: f32,
+    pub profile: Option<String>,
+    pub uploads_paused: bool,
+    pub policy: PowerPolicy,
+}
+
+// Linux: /sys/class/power_supply and /sys/class/thermal
+fn read_sysfs(root: &Path) -> PowerState {
+    let read = |path: &Path| std::fs::read_to_string(path).ok().map(|s| s.trim().to_string());
+    let mut state = PowerState::default();
+    let (mut mains_online, mut discharging) = (None, None);
+
+    for supply in std::fs::read_dir(root.join("power_supply")).into_iter().flatten().flatten() {
+        let path = supply.path();
+        match read(&path.join("type")).as_deref() {
+            Some("Mains") => {
+                if let Some(online) = read(&path.join("online")) {
+                    mains_online = Some(mains_online == Some(true) || online == "1");
+                }
+            }
+            Some("Battery") => {
+                state.battery_percent = read(&path.join("capacity")).and_then(|c| c.parse().ok());
+                discharging = read(&path.join("status")).map(|s| s == "Discharging");
+            }
+            _ => {}
+        }
+    }
+    // A desktop has no battery and is never "on battery", even when it reports a mains supply
+    state.on_battery = match (mains_online, discharging) {
+        (_, None) => None,
+        (Some(online), Some(_)) => Some(!online),
+        (None, discharging) => discharging,
+    };
+
+    state.temperature_c = std::fs::read_dir(root.join("thermal"))
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter(|zone| zone.file_name().to_string_lossy().starts_with("thermal_zone"))
+        .filter_map(|zone| read(&zone.path().join("temp")).and_then(|t| t.parse::<f32>().ok()))
+        .map(|millidegrees| millidegrees / 1000.0)
+        .reduce(f32::max);
+    state
+}
+
+// macOS: pmset reports the power source and whether the CPU is being limited
+async fn read_pmset() -> PowerState {
+    let run = |args: &'static [&'static str]| async move {
+        Command::new("pmset")
+            .args(args)
+            .output()
+            .await
+            .map(|o| String::from_utf8_lossy(&o.stdout).to_string())
+            .unwrap_or_default()
+    };
+    let battery = run(&["-g", "batt"]).await;
+    let thermal = run(&["-g", "therm"]).await;
+    PowerState {
+        on_battery: if battery.contains("'Battery Power'") {
+            Some(true)
+        } else if battery.contains("'AC Power'") {
+            Some(false)
+        } else {
+            None
+        },
+        battery_percent: battery
+            .split_whitespace()
+            .find_map(|word| word.strip_suffix("%;").and_then(|p| p.parse().ok())),
+        temperature_c: None,
+        thermal_throttled: thermal
+            .lines()
+            .filter_map(|line| line.trim().strip_prefix("CPU_Speed_Limit"))
+            .any(|limit| limit.trim_start_matches([' ', '=']).trim().parse::<u32>().is_ok_and(|l| l < 100)),
+    }
+}
+
+// Windows: Win32_Battery status 1 means discharging
+async fn read_wmi() -> PowerState {
+    let output = Command::new("powershell")
+        .args(["-NoProfile", "-Command", "$b = Get-CimInstance Win32_Battery; if ($b) { \"$($b.BatteryStatus) $($b.EstimatedChargeRemaining)\" }"])
+        .output()
+        .await
+        .map(|o| String::from_utf8_lossy(&o.stdout).to_string())
+        .unwrap_or_default();
+    let mut fields = output.split_whitespace();
+    PowerState {
+        on_battery: fields.next().and_then(|s| s.parse::<u32>().ok()).map(|status| status == 1),
+        battery_percent: fields.next().and_then(|p| p.parse().ok()),
+        ..Default::default()
+    }
+}
+
+pub async fn read() -> PowerState {
+    if cfg!(target_os = "linux") {
+        read_sysfs(Path::new("/sys/class"))
+    } else if cfg!(target_os = "macos") {
+        read_pmset().await
+    } else if cfg!(target_os = "windows") {
+        read_wmi().await
+    } else {
+        PowerState::default()
+    }
+}
+
+// Policy applied to the last sample
+pub fn status() -> PowerStatus {
+    let state = CURRENT.lock().ok().and_then(|s| s.clone()).unwrap_or_default();
+    settings::current().power.apply(&state)
+}
+
+pub fn interval_factor() -> f32 {
+    status().interval_factor
+}
+
+// Profile to use when the caller didn't ask for one
+pub fn profile_override() -> Option<String> {
+    status().profile
+}
+
+pub fn uploads_paused() -> bool {
+    status().uploads_paused
+}
+
+// Sample power and temperature periodically, logging when the policy starts or stops applying
+pub async fn run_monitor() {
+    let mut previous: Option<PowerStatus> = None;
+    loop {
+        let state = read().await;
+        if let Ok(mut current) = CURRENT.lock() {
+            *current = Some(state);
+        }
+        let status = status();
+        let changed = previous.as_ref().map(|p| (p.state.on_battery, p.thermal_pressure)) != Some((status.state.on_battery, status.thermal_pressure));
+        if changed {
+            match (status.state.on_battery, status.thermal_pressure) {
+                (Some(true), _) | (_, true) => println!(
+                    "🔋 Power saving: {}{}, frames {}x further apart",
+                    if status.state.on_battery == Some(true) { "on battery" } else { "on AC" },
+                    if status.thermal_pressure { ", running hot" } else { "" },
+                    status.interval_factor
+                ),
+                (Some(false), false) if previous.is_some() => println!("🔌 On AC power, full detection rate"),
+                _ => {}
+            }
+        }
+        previous = Some(status);
+        tokio::time::sleep(std::time::Duration::from_secs(SAMPLE_SECS)).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sysfs_battery_and_policy() {
+        let root = std::env::temp_dir().join(format!("lva-power-{}", uuid::Uuid::new_v4()));
+        let write = |path: &str, contents: &str| {
+            let path = root.join(path);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, contents).unwrap();
+        };
+        write("power_supply/AC/type", "Mains\n");
+        write("power_supply/AC/online", "0\n");
+        write("power_supply/BAT0/type", "Battery\n");
+        write("power_supply/BAT0/capacity", "57\n");
+        write("power_supply/BAT0/status", "Discharging\n");
+        write("thermal/thermal_zone0/temp", "48000\n");
+        write("thermal/thermal_zone1/temp", "91500\n");
+
+        let state = read_sysfs(&root);
+        assert_eq!(state.on_battery, Some(true));
+        assert_eq!(state.battery_percent, Some(57));
+        assert_eq!(state.temperature_c, Some(91.5));
+
+        let policy = PowerPolicy::default();
+        assert!(policy.validate(&settings::default_profiles()).is_ok());
+        let status = policy.apply(&state);
+        assert!(status.thermal_pressure && status.uploads_paused);
+        assert_eq!(status.interval_factor, 3.0);
+        assert_eq!(status.profile.as_deref(), Some("fast"));
+
+        write("power_supply/AC/online", "1\n");
+        let plugged = policy.apply(&PowerState { temperature_c: Some(50.0), ..read_sysfs(&root) });
+        assert_eq!(plugged.state.on_battery, Some(false));
+        assert_eq!((plugged.interval_factor, plugged.profile, plugged.uploads_paused), (1.0, None, false));
+        assert!(!PowerPolicy { enabled: false, ..policy }.apply(&state).uploads_paused);
+        std::fs::remove_dir_all(root).ok();
+    }
+}
@@ -0,0 +1,261 @@
+// PPE Module - Safety mode that checks people for helmets and hi-vis vests
+// Person crops go to a VLM in the background; violations become alerts with an evidence snapshot naming the zone they were in
+
+use crate::bus::BusEvent;
+use crate::event_store::{self, format_timestamp, PpeCheck};
+use crate::frame_processor::{self, RegionOfInterest};
+use crate::yolo_detector::{BoundingBox, DetectionData};
+use crate::{pipeline, settings, verification, zones, AppState};
+use chrono::{Local, NaiveTime};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::time::{Duration, Instant};
+
+pub const PPE_ITEMS: [&str; 2] = ["helmet", "vest"];
+
+const PROMPT: &str = "Look at the person in this image. Reply with JSON only: \
+{\"helmet\": true or false, \"vest\": true or false} where helmet means a hard hat or safety helmet \
+and vest means a high-visibility (hi-vis) vest or jacket.";
+
+// The same tracked person is alerted at most once per cooldown
+const ALERT_COOLDOWN: Duration = Duration::from_secs(300);
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Shift {
+    pub name: String,
+    pub start: String,  // Local "HH:MM"; a shift may run past midnight
+    pub end: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct PpeSettings {
+    pub enabled: bool,
+    pub provider: String,
+    pub required: Vec<String>,  // Items from PPE_ITEMS everyone must wear
+    pub interval_secs: u64,     // Minimum time between checks of the same camera
+    pub shifts: Vec<Shift>,
+}
+
+impl Default for PpeSettings {
+    fn default() -> Self {
+        PpeSettings {
+            enabled: false,
+            provider: "llava".to_string(),
+            required: PPE_ITEMS.iter().map(|i| i.to_string()).collect(),
+            interval_secs: 30,
+            shifts: Vec::new(),
+        }
+    }
+}
+
+fn parse_time(time: &str) -> Result<NaiveTime, String> {
+    NaiveTime::parse_from_str(time, "%H:%M").map_err(|_| format!("Invalid shift time '{}' (use HH:MM)", time))
+}
+
+impl PpeSettings {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.required.is_empty() {
+            return Err("At least one required PPE item is needed".to_string());
+        }
+        if let Some(item) = self.required.iter().find(|i| !PPE_ITEMS.contains(&i.as_str())) {
+            return Err(format!("Unknown PPE item '{}' (use {})", item, PPE_ITEMS.join(", ")));
+        }
+        if self.interval_secs == 0 {
+            return Err("interval_secs must be at least 1".to_string());
+        }
+        for shift in &self.shifts {
+            parse_time(&shift.start)?;
+            parse_time(&shift.end)?;
+        }
+        Ok(())
+    }
+
+    pub fn shift_at(&self, time: NaiveTime) -> Option<String> {
+        self.shifts
+            .iter()
+            .find(|shift| match (parse_time(&shift.start), parse_time(&shift.end)) {
+                (Ok(start), Ok(end)) if start <= end => time >= start && time < end,
+                (Ok(start), Ok(end)) => time >= start || time < end,
+                _ => false,
+            })
+            .map(|shift| shift.name.clone())
+    }
+}
+
+// Required items the VLM did not see; None when the answer can't be read
+fn missing_items(answer: &str, required: &[String]) -> Option<Vec<String>> {
+    let start = answer.find('{')?;
+    let end = answer.rfind('}')?;
+    let json: serde_json::Value = serde_json::from_str(answer.get(start..=end)?).ok()?;
+    required
+        .iter()
+        .map(|item| json[item.as_str()].as_bool().map(|worn| (item, worn)))
+        .collect::<Option<Vec<_>>>()
+        .map(|items| items.into_iter().filter(|(_, worn)| !worn).map(|(item, _)| item.clone()).collect())
+}
+
+// Per-camera pacing so live frames never wait on the VLM
+#[derive(Default)]
+pub struct PpeMonitor {
+    last_check: HashMap<String, Instant>,
+    running: HashSet<String>,
+    alerted: HashMap<(String, u64), Instant>,
+}
+
+async fn check_person(
+    state: &AppState,
+    config: &PpeSettings,
+    camera_id: Option<&str>,
+    frame_base64: &str,
+    bbox: &BoundingBox,
+) -> Result<(), String> {
+    let roi = RegionOfInterest {
+        x1: bbox.x1,
+        y1: bbox.y1,
+        x2: bbox.x2,
+        y2: bbox.y2,
+        padding: Some(0.1),
+    };
+    let crop = frame_processor::crop_to_roi(frame_base64, &roi)?;
+    let start = Instant::now();
+    let result = pipeline::describe_frame(&state.moondream, &state.providers, &config.provider, PROMPT, crop).await;
+    state.bus.publish(BusEvent::analysis(&config.provider, start.elapsed(), result.is_ok()));
+    let (answer, _) = result?.unwrap_or_default();
+    let missing =
+        missing_items(&answer, &config.required).ok_or_else(|| format!("Unreadable PPE answer: {}", answer))?;
+
+    let camera = camera_id.unwrap_or("").to_string();
+    let zone = zones::zone_for(&settings::current().zones, camera_id, bbox).map(|z| z.name.clone());
+    let shift = config.shift_at(Local::now().time());
+    let repeat = match bbox.track_id {
+        Some(track_id) if !missing.is_empty() => {
+            let mut monitor = state.ppe.lock().await;
+            let now = Instant::now();
+            let recent = monitor
+                .alerted
+                .get(&(camera.clone(), track_id))
+                .map(|at| now.duration_since(*at) < ALERT_COOLDOWN)
+                .unwrap_or(false);
+            if !recent {
+                monitor.alerted.retain(|_, at| now.duration_since(*at) < ALERT_COOLDOWN);
+                monitor.alerted.insert((camera.clone(), track_id), now);
+            }
+            recent
+        }
+        _ => false,
+    };
+
+    let alert = if missing.is_empty() || repeat {
+        None
+    } else {
+        let description = format!(
+            "PPE violation{}: missing {}",
+            zone.as_deref().map(|z| format!(" in {}", z)).unwrap_or_default(),
+            missing.join(", ")
+        );
+        let mut payload = serde_json::json!({
+            "kind": "ppe",
+            "priority": "high",
+            "missing": missing,
+            "zone": zone,
+            "shift": shift,
+            "track_id": bbox.track_id,
+            "bbox": bbox,
+        });
+        let second_look = verification::verify(state, camera_id, &config.provider, PROMPT, frame_base64, Some(&roi), "high", |text, _| {
+            missing_items(text, &config.required).is_some_and(|again| !again.is_empty())
+        })
+        .await;
+        verification::attach(&mut payload, second_look);
+        Some((description, payload))
+    };
+
+    // Stored directly rather than over the bus: the check row references the alert
+    let alert_id = match alert {
+        Some((description, payload)) => {
+            let alert_id = event_store::record_alert(&state.events, camera_id, &config.provider, &description, payload, frame_base64).await?;
+            println!("🦺 {}", description);
+            Some(alert_id)
+        }
+        None => None,
+    };
+
+    state.events.lock().await.record_ppe_check(&PpeCheck {
+        checked_at: format_timestamp(chrono::Utc::now()),
+        camera_id: camera_id.map(|c| c.to_string()),
+        zone,
+        shift,
+        track_id: bbox.track_id,
+        missing,
+        alert_id,
+    })
+}
+
+async fn inspect(state: AppState, config: PpeSettings, camera_id: Option<String>, frame: String, people: Vec<BoundingBox>) {
+    for bbox in &people {
+        if let Err(e) = check_person(&state, &config, camera_id.as_deref(), &frame, bbox).await {
+            eprintln!("PPE check failed: {}", e);
+        }
+    }
+    state.ppe.lock().await.running.remove(camera_id.as_deref().unwrap_or(""));
+}
+
+// Start a background check of this frame's people when the camera is due
+pub async fn watch(state: &AppState, camera_id: Option<&str>, frame_base64: &str, detection: &DetectionData) {
+    let config = settings::current().ppe;
+    let people: Vec<BoundingBox> = detection.boxes.iter().filter(|b| b.class_name == "person").cloned().collect();
+    if !config.enabled || people.is_empty() {
+        return;
+    }
+
+    let camera = camera_id.unwrap_or("").to_string();
+    {
+        let mut monitor = state.ppe.lock().await;
+        let due = monitor
+            .last_check
+            .get(&camera)
+            .map(|at| at.elapsed() >= Duration::from_secs(config.interval_secs))
+            .unwrap_or(true);
+        if !due || monitor.running.contains(&camera) {
+            return;
+        }
+        monitor.last_check.insert(camera.clone(), Instant::now());
+        monitor.running.insert(camera);
+    }
+
+    let state = state.clone();
+    let camera_id = camera_id.map(|c| c.to_string());
+    let frame = frame_base64.to_string();
+    tokio::spawn(inspect(state, config, camera_id, frame, people));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_answers_and_shifts() {
+        let required: Vec<String> = PPE_ITEMS.iter().map(|i| i.to_string()).collect();
+        assert_eq!(missing_items(r#"{"helmet": true, "vest": true}"#, &required), Some(vec![]));
+        assert_eq!(
+            missing_items("Sure! {\"helmet\": false, \"vest\": true}", &required),
+            Some(vec!["helmet".to_string()])
+        );
+        assert_eq!(missing_items("The worker wears a helmet", &required), None);
+
+        let config = PpeSettings {
+            shifts: vec![
+                Shift { name: "day".to_string(), start: "06:00".to_string(), end: "18:00".to_string() },
+                Shift { name: "night".to_string(), start: "18:00".to_string(), end: "06:00".to_string() },
+            ],
+            ..Default::default()
+        };
+        assert!(config.validate().is_ok());
+
+        let at = |h, m| NaiveTime::from_hms_opt(h, m, 0).unwrap();
+        assert_eq!(config.shift_at(at(9, 30)).as_deref(), Some("day"));
+        assert_eq!(config.shift_at(at(2, 0)).as_deref(), Some("night"));
+        assert!(PpeSettings { required: vec!["gloves".to_string()], ..config }.validate().is_err());
+    }
+}
@@ -0,0 +1,156 @@
+// Preview Module - Small animated GIFs of the seconds before an alert, for channels that show images but not video
+// Recent live frames are kept per camera at a low rate; an alert's preview plays through them and ends on its evidence frame.
+// The image crate can encode animated GIF but not animated WebP, so previews are GIFs
+
+use crate::{dataset, encryption, frame_processor, settings};
+use image::codecs::gif::{GifEncoder, Repeat};
+use image::{Delay, DynamicImage, Frame};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+// The evidence frame stays up this long before the preview loops
+const FINAL_FRAME_HOLD: Duration = Duration::from_millis(1500);
+
+// Camera id ("" for frames sent without one) -> recent frames, oldest first
+static RECENT_FRAMES: Mutex<Option<HashMap<String, VecDeque<(Arc<str>, Instant)>>>> = Mutex::new(None);
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct PreviewSettings {
+    pub enabled: bool,
+    pub frames: usize,  // Including the evidence frame
+    pub interval_ms: u64,  // Minimum time between kept frames
+    pub width: u32,  // Previews are scaled down to at most this wide
+}
+
+impl Default for PreviewSettings {
+    fn default() -> Self {
+        PreviewSettings {
+            enabled: true,
+            frames: 8,
+            interval_ms: 500,
+            width: 320,
+        }
+    }
+}
+
+impl PreviewSettings {
+    pub fn validate(&self) -> Result<(), String> {
+        if !(2..=30).contains(&self.frames) {
+            return Err("Previews need between 2 and 30 frames".to_string());
+        }
+        if !(100..=5000).contains(&self.interval_ms) {
+            return Err("Preview frame interval must be between 100 and 5000 ms".to_string());
+        }
+        if !(80..=640).contains(&self.width) {
+            return Err("Preview width must be between 80 and 640 pixels".to_string());
+        }
+        Ok(())
+    }
+}
+
+pub fn preview_path(event_id: &str) -> PathBuf {
+    dataset::snapshots_dir().join(format!("{}.gif", event_id))
+}
+
+// Called for every live frame; keeps one per interval while previews are on
+pub fn remember_frame(camera_id: Option<&str>, frame_base64: &str) {
+    let config = settings::current().preview;
+    let mut recent = RECENT_FRAMES.lock().unwrap_or_else(|e| e.into_inner());
+    if !config.enabled {
+        *recent = None;
+        return;
+    }
+    let frames = recent.get_or_insert_with(HashMap::new).entry(camera_id.unwrap_or("").to_string()).or_default();
+    let due = frames.back().is_none_or(|(_, at)| at.elapsed() >= Duration::from_millis(config.interval_ms));
+    if due {
+        frames.push_back((frame_base64.into(), Instant::now()));
+    }
+    while frames.len() >= config.frames {
+        frames.pop_front();
+    }
+}
+
+// Looping GIF of the frames scaled to at most width; frames of another size than the last are left out
+pub fn encode(frames: &[(DynamicImage, Duration)], width: u32) -> Result<Vec<u8>, String> {
+    let Some((last, _)) = frames.last() else {
+        return Err("A preview needs at least one frame".to_string());
+    };
+    let size = (last.width(), last.height());
+    let mut out = Vec::new();
+    {
+        let mut encoder = GifEncoder::new_with_speed(&mut out, 10);
+        encoder.set_repeat(Repeat::Infinite).map_err(|e| format!("Failed to encode preview: {}", e))?;
+        let scaled = frames
+            .iter()
+            .filter(|(image, _)| (image.width(), image.height()) == size)
+            .map(|(image, shown)| {
+                let small = image.thumbnail(width.min(image.width()), image.height()).to_rgba8();
+                Frame::from_parts(small, 0, 0, Delay::from_saturating_duration(*shown))
+            });
+        encoder.encode_frames(scaled).map_err(|e| format!("Failed to encode preview: {}", e))?;
+    }
+    Ok(out)
+}
+
+// Preview from the camera's recent frames ending on the evidence; None when off or there is nothing to animate
+pub fn save(camera_id: Option<&str>, event_id: &str, snapshot_base64: &str) -> Result<Option<PathBuf>, String> {
+    let config = settings::current().preview;
+    // Frames from before a gap in the feed would show another moment
+    let window = Duration::from_millis(config.interval_ms * config.frames as u64 * 2);
+    let recent: Vec<(Arc<str>, Instant)> = {
+        let recent = RECENT_FRAMES.lock().unwrap_or_else(|e| e.into_inner());
+        let frames = recent.as_ref().and_then(|r| r.get(camera_id.unwrap_or("")));
+        frames.map(|f| f.iter().filter(|(_, at)| at.elapsed() <= window).cloned().collect()).unwrap_or_default()
+    };
+    if !config.enabled || recent.is_empty() {
+        return Ok(None);
+    }
+
+    // Each frame stays up until the next one was captured
+    let now = Instant::now();
+    let shown_until = recent.iter().skip(1).map(|(_, at)| *at).chain([now]);
+    let mut frames: Vec<(DynamicImage, Duration)> = recent
+        .iter()
+        .zip(shown_until)
+        .filter_map(|((frame, at), until)| Some((frame_processor::decode_frame(frame).ok()?, until.duration_since(*at))))
+        .collect();
+    frames.push((frame_processor::decode_frame(snapshot_base64)?, FINAL_FRAME_HOLD));
+
+    let gif = encode(&frames, config.width)?;
+    let path = preview_path(event_id);
+    std::fs::create_dir_all(dataset::snapshots_dir()).map_err(|e| format!("Failed to create snapshots directory: {}", e))?;
+    std::fs::write(&path, encryption::seal_bytes(&gif)?).map_err(|e| format!("Failed to save preview: {}", e))?;
+    Ok(Some(path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::codecs::gif::GifDecoder;
+    use image::{AnimationDecoder, Rgb, RgbImage};
+
+    #[test]
+    fn test_encode_animated_preview() {
+        let frame = |shade: u8, width: u32| DynamicImage::ImageRgb8(RgbImage::from_pixel(width, 480, Rgb([shade, 40, 200 - shade])));
+        let frames = vec![
+            (frame(0, 640), Duration::from_millis(500)),
+            (frame(60, 320), Duration::from_millis(500)),  // Left out: a different size
+            (frame(120, 640), Duration::from_millis(400)),
+            (frame(180, 640), FINAL_FRAME_HOLD),
+        ];
+        let gif = encode(&frames, 320).unwrap();
+        assert_eq!(&gif[..6], b"GIF89a");
+
+        let decoded = GifDecoder::new(std::io::Cursor::new(gif)).unwrap().into_frames().collect_frames().unwrap();
+        assert_eq!(decoded.len(), 3);
+        assert_eq!(decoded[0].buffer().dimensions(), (320, 240));
+        let (numerator, denominator) = decoded[2].delay().numer_denom_ms();
+        assert_eq!(numerator / denominator, 1500);
+        assert!(encode(&[], 320).is_err());
+        assert!(PreviewSettings { frames: 1, ..Default::default() }.validate().is_err());
+    }
+}
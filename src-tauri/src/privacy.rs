@@ -0,0 +1,403 @@
+// Privacy Module - Masked zones and face redaction applied before frames reach any model
+// Each remote provider has a policy: never send frames, send them with faces pixelated, or send them as-is
+
+use crate::audit::{self, AuditAction, AuditEntry};
+use crate::frame_processor;
+use crate::settings;
+use crate::vision_provider::{VisionProvider, VisionRequest, VisionResponse};
+use async_trait::async_trait;
+use image::imageops::FilterType;
+use image::{DynamicImage, Rgb, RgbImage};
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+// Detection runs on a small copy of the frame
+const DETECT_WIDTH: u32 = 160;
+// Smallest skin region (in detection pixels) treated as a face
+const MIN_FACE_PIXELS: u32 = 30;
+// Pixelation block size relative to the face width
+const PIXELATE_BLOCKS: u32 = 8;
+// Blocks across a whole frame pixelated because its faces couldn't be found
+const FALLBACK_BLOCKS: u32 = 24;
+// Average chroma distance from grey below which a frame counts as colourless (IR or grayscale)
+const MIN_CHROMA: f32 = 6.0;
+// For this long after detection last saw people, uploads without a face to find are pixelated whole
+const PEOPLE_WINDOW: Duration = Duration::from_secs(30);
+
+// When detection last saw a person, on any camera. Uploads are crops, dewarps and re-encodes of detected frames
+// that can't be matched back to them, and don't say which camera they came from
+static PEOPLE_SEEN: Mutex<Option<Instant>> = Mutex::new(None);
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "kebab-case")]
+pub enum RedactionPolicy {
+    LocalOnly,  // Frames never reach this provider
+    Redact,     // Faces are pixelated before upload
+    #[default]
+    Raw,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct FaceBox {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+// Region blacked out in every frame, in normalized (0-1) frame coordinates
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct PrivacyMask {
+    pub name: String,
+    pub points: Vec<[f32; 2]>,
+}
+
+impl PrivacyMask {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.points.len() < 3 {
+            return Err(format!("Privacy mask '{}' needs at least 3 points", self.name));
+        }
+        if self.points.iter().flatten().any(|c| !(0.0..=1.0).contains(c)) {
+            return Err(format!("Privacy mask '{}' has points outside the frame (0-1)", self.name));
+        }
+        Ok(())
+    }
+}
+
+// Even-odd scanline fill, sampling at pixel centres
+pub fn fill_polygon(image: &mut RgbImage, points: &[(f32, f32)], color: Rgb<u8>) {
+    let (w, h) = image.dimensions();
+    for y in 0..h {
+        let yc = y as f32 + 0.5;
+        let mut crossings: Vec<f32> = points
+            .iter()
+            .zip(points.iter().cycle().skip(1))
+            .filter(|((_, y1), (_, y2))| (*y1 <= yc) != (*y2 <= yc))
+            .map(|((x1, y1), (x2, y2))| x1 + (yc - y1) * (x2 - x1) / (y2 - y1))
+            .collect();
+        crossings.sort_by(|a, b| a.total_cmp(b));
+
+        for pair in crossings.chunks_exact(2) {
+            let start = (pair[0] - 0.5).ceil().max(0.0) as u32;
+            let end = ((pair[1] - 0.5).floor() + 1.0).clamp(0.0, w as f32) as u32;
+            for x in start..end {
+                image.put_pixel(x, y, color);
+            }
+        }
+    }
+}
+
+pub fn apply_masks(image: &mut RgbImage, masks: &[PrivacyMask]) {
+    let (w, h) = (image.width() as f32, image.height() as f32);
+    for mask in masks {
+        let points: Vec<(f32, f32)> = mask.points.iter().map(|[x, y]| (x * w, y * h)).collect();
+        fill_polygon(image, &points, Rgb([0, 0, 0]));
+    }
+}
+
+// Black out the configured privacy zones; frames pass through untouched when there are none
+pub fn mask_frame(frame_base64: String) -> Result<String, String> {
+    let masks = settings::current().privacy_masks;
+    if masks.is_empty() {
+        return Ok(frame_base64);
+    }
+    let mut image = frame_processor::decode_frame(&frame_base64)?.to_rgb8();
+    apply_masks(&mut image, &masks);
+    frame_processor::encode_frame(&DynamicImage::ImageRgb8(image))
+}
+
+fn chroma(pixel: &Rgb<u8>) -> (f32, f32) {
+    let [r, g, b] = pixel.0.map(|c| c as f32);
+    (128.0 - 0.168736 * r - 0.331264 * g + 0.5 * b, 128.0 + 0.5 * r - 0.418688 * g - 0.081312 * b)
+}
+
+// Skin tone test in YCbCr space, which holds up across lighting and skin colours
+fn is_skin(pixel: &Rgb<u8>) -> bool {
+    let (cb, cr) = chroma(pixel);
+    (77.0..=127.0).contains(&cb) && (133.0..=173.0).contains(&cr)
+}
+
+// Grayscale and IR night footage has no skin tones for the face finder to see
+fn is_colourless(image: &DynamicImage) -> bool {
+    let small = image.thumbnail(DETECT_WIDTH, DETECT_WIDTH).to_rgb8();
+    let total: f32 = small.pixels().map(chroma).map(|(cb, cr)| (cb - 128.0).abs() + (cr - 128.0).abs()).sum();
+    total / (small.width() * small.height()).max(1) as f32 < MIN_CHROMA
+}
+
+// Called after detection, so redaction knows people are about
+pub fn remember_people(person_count: usize) {
+    if person_count > 0 {
+        *PEOPLE_SEEN.lock().unwrap_or_else(|e| e.into_inner()) = Some(Instant::now());
+    }
+}
+
+fn people_recently() -> bool {
+    PEOPLE_SEEN.lock().unwrap_or_else(|e| e.into_inner()).is_some_and(|at| at.elapsed() < PEOPLE_WINDOW)
+}
+
+// Lightweight face detector: connected skin regions with a face-like shape.
+// It errs towards over-redaction (hands and arms may be caught too).
+pub fn detect_faces(image: &DynamicImage) -> Vec<FaceBox> {
+    let scale = (image.width() as f32 / DETECT_WIDTH as f32).max(1.0);
+    let small = image
+        .resize(
+            ((image.width() as f32 / scale) as u32).max(1),
+            ((image.height() as f32 / scale) as u32).max(1),
+            FilterType::Triangle,
+        )
+        .to_rgb8();
+    let (w, h) = small.dimensions();
+    let mask: Vec<bool> = small.pixels().map(is_skin).collect();
+    let mut seen = vec![false; mask.len()];
+    let mut faces = Vec::new();
+
+    for start in 0..mask.len() {
+        if !mask[start] || seen[start] {
+            continue;
+        }
+
+        // Flood fill one region and track its bounds
+        let mut stack = vec![start];
+        seen[start] = true;
+        let (mut min_x, mut min_y, mut max_x, mut max_y, mut area) = (w, h, 0, 0, 0u32);
+        while let Some(i) = stack.pop() {
+            let (x, y) = (i as u32 % w, i as u32 / w);
+            area += 1;
+            min_x = min_x.min(x);
+            min_y = min_y.min(y);
+            max_x = max_x.max(x);
+            max_y = max_y.max(y);
+
+            let neighbours = [
+                (x > 0).then(|| i - 1),
+                (x + 1 < w).then(|| i + 1),
+                (y > 0).then(|| i - w as usize),
+                (y + 1 < h).then(|| i + w as usize),
+            ];
+            for j in neighbours.into_iter().flatten() {
+                if mask[j] && !seen[j] {
+                    seen[j] = true;
+                    stack.push(j);
+                }
+            }
+        }
+
+        let (box_w, box_h) = (max_x - min_x + 1, max_y - min_y + 1);
+        let aspect = box_h as f32 / box_w as f32;
+        let fill = area as f32 / (box_w * box_h) as f32;
+        if area < MIN_FACE_PIXELS || !(0.8..=2.2).contains(&aspect) || fill < 0.35 {
+            continue;
+        }
+
+        // Back to full resolution with some padding for hair and ears
+        let pad_x = box_w as f32 * scale * 0.15;
+        let pad_y = box_h as f32 * scale * 0.15;
+        let x1 = ((min_x as f32 * scale) - pad_x).max(0.0) as u32;
+        let y1 = ((min_y as f32 * scale) - pad_y).max(0.0) as u32;
+        let x2 = (((max_x + 1) as f32 * scale) + pad_x).min(image.width() as f32) as u32;
+        let y2 = (((max_y + 1) as f32 * scale) + pad_y).min(image.height() as f32) as u32;
+        faces.push(FaceBox {
+            x: x1,
+            y: y1,
+            width: x2 - x1,
+            height: y2 - y1,
+        });
+    }
+
+    faces
+}
+
+fn pixelate(image: &mut DynamicImage, region: &FaceBox, blocks: u32) {
+    if region.width == 0 || region.height == 0 {
+        return;
+    }
+    let block = (region.width / blocks).max(1);
+    let patch = image
+        .crop_imm(region.x, region.y, region.width, region.height)
+        .resize_exact((region.width / block).max(1), (region.height / block).max(1), FilterType::Triangle)
+        .resize_exact(region.width, region.height, FilterType::Nearest);
+    image::imageops::overlay(image, &patch, region.x as i64, region.y as i64);
+}
+
+// Pixelate each region in place; coarse blocks can't be reversed the way a light blur can
+pub fn pixelate_regions(image: &mut DynamicImage, regions: &[FaceBox]) {
+    for region in regions {
+        pixelate(image, region, PIXELATE_BLOCKS);
+    }
+}
+
+// Returns the redacted frame and the pixelated boxes. Redaction fails closed: when no faces are found while people
+// were detected recently, or in a frame with no colour to find them by, the whole frame is pixelated
+pub fn redact_frame(frame_base64: &str) -> Result<(String, Vec<FaceBox>), String> {
+    let mut image = frame_processor::decode_frame(frame_base64)?;
+    let faces = detect_faces(&image);
+    if !faces.is_empty() {
+        pixelate_regions(&mut image, &faces);
+        return Ok((frame_processor::encode_frame(&image)?, faces));
+    }
+    if !people_recently() && !is_colourless(&image) {
+        return Ok((frame_base64.to_string(), faces));
+    }
+    let whole = FaceBox { x: 0, y: 0, width: image.width(), height: image.height() };
+    pixelate(&mut image, &whole, FALLBACK_BLOCKS);
+    Ok((frame_processor::encode_frame(&image)?, vec![whole]))
+}
+
+// Apply the provider's policy to frames about to be uploaded
+pub async fn prepare_upload(provider: &str, images: Vec<String>) -> Result<Vec<String>, String> {
+    match settings::current().redaction_policy(provider) {
+        RedactionPolicy::Raw => Ok(images),
+        RedactionPolicy::LocalOnly => Err(format!(
+            "Provider '{}' is set to local-only - frames are not sent to remote services",
+            provider
+        )),
+        RedactionPolicy::Redact => tokio::task::spawn_blocking(move || {
+            images
+                .iter()
+                .map(|image| redact_frame(image).map(|(frame, _)| frame))
+                .collect::<Result<Vec<_>, String>>()
+        })
+        .await
+        .map_err(|e| format!("Face redaction failed: {}", e))?,
+    }
+}
+
+pub async fn prepare_image(provider: &str, image: String) -> Result<String, String> {
+    Ok(prepare_upload(provider, vec![image]).await?.into_iter().next().unwrap_or_default())
+}
+
+// Wraps a remote provider so every request passes through its redaction policy
+pub struct PrivacyGuard {
+    inner: Arc<dyn VisionProvider>,
+}
+
+impl PrivacyGuard {
+    pub fn new(inner: Arc<dyn VisionProvider>) -> Self {
+        PrivacyGuard { inner }
+    }
+}
+
+#[async_trait]
+impl VisionProvider for PrivacyGuard {
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+
+    fn model(&self) -> &str {
+        self.inner.model()
+    }
+
+    fn endpoint(&self) -> Option<&str> {
+        self.inner.endpoint()
+    }
+
+    async fn analyze(&self, request: &VisionRequest) -> Result<VisionResponse, String> {
+        let name = self.inner.name();
+        let policy = settings::current().redaction_policy(name);
+        let images = prepare_upload(name, request.images.clone()).await?;
+        let request = VisionRequest { images, ..request.clone() };
+        let result = self.inner.analyze(&request).await;
+
+        let redacted = if policy == RedactionPolicy::Redact { ", faces redacted" } else { "" };
+        let data = format!("{} frame(s) + prompt ({} chars){}", request.images.len(), request.prompt.len(), redacted);
+        let entry = AuditEntry::new(AuditAction::Transmission, data, self.endpoint().unwrap_or(name))
+            .provider(name)
+            .bytes(audit::base64_bytes(&request.images) + request.prompt.len() as u64)
+            .outcome(&result);
+        audit::record(entry);
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Blue background with one skin-toned oval
+    fn frame_with_face() -> DynamicImage {
+        let mut image = RgbImage::from_pixel(320, 240, Rgb([40, 60, 160]));
+        for y in 60..140u32 {
+            for x in 140..200u32 {
+                let (dx, dy) = ((x as f32 - 170.0) / 30.0, (y as f32 - 100.0) / 40.0);
+                if dx * dx + dy * dy <= 1.0 {
+                    image.put_pixel(x, y, Rgb([224, 172, 140]));
+                }
+            }
+        }
+        DynamicImage::ImageRgb8(image)
+    }
+
+    #[test]
+    fn test_detects_and_pixelates_face() {
+        let mut image = frame_with_face();
+        let faces = detect_faces(&image);
+        assert_eq!(faces.len(), 1);
+        let face = &faces[0];
+        assert!(face.x <= 140 && face.x + face.width >= 200);
+
+        let before = image.to_rgb8();
+        pixelate_regions(&mut image, &faces);
+        let after = image.to_rgb8();
+        assert_ne!(before, after);
+        // Pixels outside the face box are untouched
+        assert_eq!(before.get_pixel(5, 5), after.get_pixel(5, 5));
+
+        let empty = DynamicImage::ImageRgb8(RgbImage::from_pixel(320, 240, Rgb([40, 60, 160])));
+        assert!(detect_faces(&empty).is_empty());
+    }
+
+    #[test]
+    fn test_redaction_fails_closed() {
+        // IR night footage: a face-shaped grey oval the skin test can't see
+        let mut night = RgbImage::from_pixel(320, 240, Rgb([30, 30, 30]));
+        for (x, y, pixel) in night.enumerate_pixels_mut() {
+            if (x / 20 + y / 20) % 2 == 0 {
+                *pixel = Rgb([200, 200, 200]);
+            }
+        }
+        let night = frame_processor::encode_frame(&DynamicImage::ImageRgb8(night)).unwrap();
+        let (redacted, boxes) = redact_frame(&night).unwrap();
+        assert_eq!(boxes, vec![FaceBox { x: 0, y: 0, width: 320, height: 240 }]);
+        assert_ne!(redacted, night);
+
+        // A colour frame without a face passes through, until detection has seen people recently; then any crop or
+        // re-encode of a frame is pixelated too
+        let empty = frame_processor::encode_frame(&DynamicImage::ImageRgb8(RgbImage::from_pixel(320, 240, Rgb([40, 60, 160])))).unwrap();
+        assert_eq!(redact_frame(&empty).unwrap(), (empty.clone(), Vec::new()));
+        remember_people(0);
+        assert!(redact_frame(&empty).unwrap().1.is_empty());
+        remember_people(2);
+        assert_eq!(redact_frame(&empty).unwrap().1.len(), 1);
+        let crop = frame_processor::encode_frame(&DynamicImage::ImageRgb8(RgbImage::from_pixel(64, 128, Rgb([40, 60, 160])))).unwrap();
+        assert_eq!(redact_frame(&crop).unwrap().1, vec![FaceBox { x: 0, y: 0, width: 64, height: 128 }]);
+        *PEOPLE_SEEN.lock().unwrap() = Some(Instant::now() - PEOPLE_WINDOW);
+        assert!(redact_frame(&empty).unwrap().1.is_empty());
+    }
+
+    #[test]
+    fn test_mask_polygon_fill() {
+        let mut image = RgbImage::from_pixel(100, 100, Rgb([255, 255, 255]));
+        let mask = PrivacyMask {
+            name: "register".to_string(),
+            points: vec![[0.1, 0.1], [0.5, 0.1], [0.5, 0.5], [0.1, 0.5]],
+        };
+        assert!(mask.validate().is_ok());
+        apply_masks(&mut image, &[mask]);
+
+        assert_eq!(image.get_pixel(30, 30).0, [0, 0, 0]);
+        assert_eq!(image.get_pixel(10, 10).0, [0, 0, 0]);
+        assert_eq!(image.get_pixel(60, 30).0, [255, 255, 255]);
+        assert_eq!(image.get_pixel(30, 60).0, [255, 255, 255]);
+
+        let line = PrivacyMask { name: "line".to_string(), points: vec![[0.0, 0.0], [1.0, 1.0]] };
+        assert!(line.validate().is_err());
+    }
+
+    #[test]
+    fn test_policy_serialization() {
+        assert_eq!(serde_json::to_string(&RedactionPolicy::LocalOnly).unwrap(), "\"local-only\"");
+        let policy: RedactionPolicy = serde_json::from_str("\"redact\"").unwrap();
+        assert_eq!(policy, RedactionPolicy::Redact);
+    }
+}
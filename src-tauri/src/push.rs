@@ -0,0 +1,408 @@
+// Push Module - Alert summaries and snapshot thumbnails pushed to phones
+// ntfy needs only a topic (the ntfy.sh app or a self-hosted server); FCM reaches your own app through a Firebase service account
+
+use crate::alerting::{self, AlertNotice};
+use crate::audit::{self, AuditAction, AuditEntry};
+use crate::event_store::EventStore;
+use crate::{dataset, encryption, local_only, network, offline, preview, settings};
+use base64::{engine::general_purpose, Engine as _};
+use rsa::pkcs1v15::SigningKey;
+use rsa::pkcs8::DecodePrivateKey;
+use rsa::signature::{SignatureEncoding, Signer};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+
+pub const DEFAULT_NTFY_SERVER: &str = "https://ntfy.sh";
+
+const THUMBNAIL_SIZE: u32 = 320;
+const FCM_SCOPE: &str = "https://www.googleapis.com/auth/firebase.messaging";
+
+// Service account file -> OAuth access token and when to fetch a new one
+static FCM_TOKENS: std::sync::Mutex<BTreeMap<String, (String, Instant)>> = std::sync::Mutex::new(BTreeMap::new());
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum PushService {
+    Ntfy,
+    Fcm,
+}
+
+fn default_server() -> String {
+    DEFAULT_NTFY_SERVER.to_string()
+}
+
+fn default_min_priority() -> String {
+    "high".to_string()
+}
+
+fn default_include_snapshot() -> bool {
+    true
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct PushTarget {
+    pub name: String,
+    pub service: PushService,
+    #[serde(default)]
+    pub topic: String,  // ntfy topic, or FCM topic when there is no device token
+    #[serde(default = "default_server")]
+    pub server: String,  // ntfy only
+    #[serde(default)]
+    pub access_token: Option<String>,  // ntfy token for protected topics
+    #[serde(default)]
+    pub service_account: Option<String>,  // FCM: path to the Firebase service account JSON
+    #[serde(default)]
+    pub device_token: Option<String>,  // FCM: one device instead of a topic
+    #[serde(default = "default_min_priority")]
+    pub min_priority: String,
+    #[serde(default)]
+    pub rules: Vec<String>,  // Only alerts from these rules; empty sends alerts of every kind
+    #[serde(default = "default_include_snapshot")]
+    pub include_snapshot: bool,  // ntfy attaches an animated preview or thumbnail; FCM carries the alert id for the app to fetch it
+}
+
+impl PushTarget {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.name.trim().is_empty() {
+            return Err("Push targets need a name".to_string());
+        }
+        alerting::validate_priority(&self.min_priority).map_err(|e| format!("Push target '{}': {}", self.name, e))?;
+        let topic_ok = !self.topic.is_empty()
+            && self.topic.len() <= 64
+            && self.topic.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+        match self.service {
+            PushService::Ntfy => {
+                reqwest::Url::parse(&self.server).map_err(|e| format!("Invalid ntfy server '{}': {}", self.server, e))?;
+                if !topic_ok {
+                    return Err(format!("Push target '{}' needs an ntfy topic of letters, digits, '-' and '_'", self.name));
+                }
+            }
+            PushService::Fcm => {
+                if self.service_account.as_deref().is_none_or(|path| !std::path::Path::new(path).is_file()) {
+                    return Err(format!("Push target '{}' needs the path of a Firebase service account file", self.name));
+                }
+                if self.device_token.is_none() && !topic_ok {
+                    return Err(format!("Push target '{}' needs an FCM topic or device token", self.name));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub fn wants(&self, notice: &AlertNotice) -> bool {
+        let rule_ok = self.rules.is_empty() || notice.rule.as_ref().is_some_and(|rule| self.rules.contains(rule));
+        rule_ok && notice.at_least(&self.min_priority)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+#[serde(default)]
+pub struct PushSettings {
+    pub enabled: bool,
+    pub targets: Vec<PushTarget>,
+}
+
+impl PushSettings {
+    pub fn validate(&self) -> Result<(), String> {
+        for (index, target) in self.targets.iter().enumerate() {
+            target.validate()?;
+            if self.targets[..index].iter().any(|t| t.name == target.name) {
+                return Err(format!("Duplicate push target '{}'", target.name));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PushMessage {
+    pub title: String,
+    pub body: String,
+    pub priority: String,
+    pub alert_id: String,
+    pub thumbnail: Option<Vec<u8>>,  // JPEG
+    pub preview: Option<Vec<u8>>,  // Animated GIF, attached instead of the thumbnail
+}
+
+impl PushMessage {
+    pub fn from_notice(notice: &AlertNotice, thumbnail: Option<Vec<u8>>, preview: Option<Vec<u8>>) -> Self {
+        PushMessage {
+            title: notice.title(),
+            body: notice.description.clone(),
+            priority: notice.priority.clone(),
+            alert_id: notice.id.clone(),
+            thumbnail,
+            preview,
+        }
+    }
+}
+
+// ntfy's 1 (min) to 5 (max, rings through do-not-disturb on Android)
+pub fn ntfy_priority(priority: &str) -> u8 {
+    match priority {
+        "critical" => 5,
+        "high" => 4,
+        "medium" => 3,
+        _ => 2,
+    }
+}
+
+// A small JPEG of the alert's evidence frame
+pub fn thumbnail(jpeg: &[u8]) -> Result<Vec<u8>, String> {
+    let image = image::load_from_memory(jpeg).map_err(|e| format!("Failed to decode snapshot: {}", e))?;
+    let mut out = std::io::Cursor::new(Vec::new());
+    image
+        .thumbnail(THUMBNAIL_SIZE, THUMBNAIL_SIZE)
+        .to_rgb8()
+        .write_to(&mut out, image::ImageFormat::Jpeg)
+        .map_err(|e| format!("Failed to encode thumbnail: {}", e))?;
+    Ok(out.into_inner())
+}
+
+fn check_url(url: &str) -> Result<(), String> {
+    local_only::check_url(url)?;
+    offline::check_url(url)
+}
+
+async fn send_ntfy(client: &reqwest::Client, target: &PushTarget, message: &PushMessage) -> Result<(), String> {
+    let mut url = reqwest::Url::parse(&format!("{}/{}", target.server.trim_end_matches('/'), target.topic))
+        .map_err(|e| format!("Invalid ntfy URL: {}", e))?;
+    check_url(url.as_str())?;
+    // Title and message go in the query so the body can carry the thumbnail
+    url.query_pairs_mut()
+        .append_pair("title", &message.title)
+        .append_pair("message", &message.body)
+        .append_pair("priority", &ntfy_priority(&message.priority).to_string())
+        .append_pair("tags", "rotating_light");
+    // The preview shows the motion leading up to the alert; the thumbnail is the fallback
+    let attachment = match (&message.preview, &message.thumbnail) {
+        (Some(gif), _) => Some((gif, "gif")),
+        (None, Some(jpeg)) => Some((jpeg, "jpg")),
+        (None, None) => None,
+    };
+    let mut request = match attachment {
+        Some((bytes, extension)) => {
+            url.query_pairs_mut().append_pair("filename", &format!("{}.{}", message.alert_id, extension));
+            client.put(url.clone()).body(bytes.clone())
+        }
+        None => client.post(url.clone()),
+    };
+    if let Some(token) = &target.access_token {
+        request = request.bearer_auth(token);
+    }
+    let response = request.send().await.map_err(|e| format!("ntfy request failed: {}", e))?;
+    if !response.status().is_success() {
+        let status = response.status();
+        return Err(format!("ntfy rejected the message ({}): {}", status, response.text().await.unwrap_or_default()));
+    }
+    Ok(())
+}
+
+#[derive(Deserialize)]
+struct ServiceAccount {
+    project_id: String,
+    client_email: String,
+    private_key: String,
+    #[serde(default = "default_token_uri")]
+    token_uri: String,
+}
+
+fn default_token_uri() -> String {
+    "https://oauth2.googleapis.com/token".to_string()
+}
+
+fn load_service_account(path: &str) -> Result<ServiceAccount, String> {
+    let json = std::fs::read_to_string(path).map_err(|e| format!("Failed to read service account {}: {}", path, e))?;
+    serde_json::from_str(&json).map_err(|e| format!("Invalid service account {}: {}", path, e))
+}
+
+// RS256-signed JWT asking Google for a messaging token
+fn signed_assertion(account: &ServiceAccount, now: i64) -> Result<String, String> {
+    let encode = |value: serde_json::Value| general_purpose::URL_SAFE_NO_PAD.encode(value.to_string());
+    let header = encode(serde_json::json!({ "alg": "RS256", "typ": "JWT" }));
+    let claims = encode(serde_json::json!({
+        "iss": account.client_email,
+        "scope": FCM_SCOPE,
+        "aud": account.token_uri,
+        "iat": now,
+        "exp": now + 3600,
+    }));
+    let key = rsa::RsaPrivateKey::from_pkcs8_pem(&account.private_key).map_err(|e| format!("Invalid service account key: {}", e))?;
+    let signing_input = format!("{}.{}", header, claims);
+    let signature = SigningKey::<Sha256>::new(key).sign(signing_input.as_bytes());
+    Ok(format!("{}.{}", signing_input, general_purpose::URL_SAFE_NO_PAD.encode(signature.to_bytes())))
+}
+
+async fn fcm_access_token(client: &reqwest::Client, path: &str, account: &ServiceAccount) -> Result<String, String> {
+    let cached = FCM_TOKENS.lock().unwrap_or_else(|e| e.into_inner()).get(path).cloned();
+    if let Some((token, _)) = cached.filter(|(_, refresh_at)| Instant::now() < *refresh_at) {
+        return Ok(token);
+    }
+    check_url(&account.token_uri)?;
+    let assertion = signed_assertion(account, chrono::Utc::now().timestamp())?;
+    let response: serde_json::Value = client
+        .post(&account.token_uri)
+        .form(&[("grant_type", "urn:ietf:params:oauth:grant-type:jwt-bearer"), ("assertion", assertion.as_str())])
+        .send()
+        .await
+        .map_err(|e| format!("FCM token request failed: {}", e))?
+        .json()
+        .await
+        .map_err(|e| format!("Invalid FCM token response: {}", e))?;
+    let token = response["access_token"]
+        .as_str()
+        .ok_or_else(|| format!("FCM token request was refused: {}", response["error_description"].as_str().unwrap_or("no token")))?
+        .to_string();
+    // Refresh a minute early
+    let lifetime = response["expires_in"].as_u64().unwrap_or(3600).saturating_sub(60);
+    FCM_TOKENS
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .insert(path.to_string(), (token.clone(), Instant::now() + Duration::from_secs(lifetime)));
+    Ok(token)
+}
+
+pub fn fcm_message(target: &PushTarget, message: &PushMessage) -> serde_json::Value {
+    let mut body = serde_json::json!({
+        "notification": { "title": message.title, "body": message.body },
+        "data": { "alert_id": message.alert_id, "priority": message.priority },
+        "android": { "priority": if ntfy_priority(&message.priority) >= 4 { "high" } else { "normal" } },
+    });
+    match &target.device_token {
+        Some(token) => body["token"] = serde_json::json!(token),
+        None => body["topic"] = serde_json::json!(target.topic),
+    }
+    serde_json::json!({ "message": body })
+}
+
+async fn send_fcm(client: &reqwest::Client, target: &PushTarget, message: &PushMessage) -> Result<(), String> {
+    let path = target.service_account.as_deref().ok_or("FCM targets need a service account")?;
+    let account = load_service_account(path)?;
+    let url = format!("https://fcm.googleapis.com/v1/projects/{}/messages:send", account.project_id);
+    check_url(&url)?;
+    let token = fcm_access_token(client, path, &account).await?;
+    let response = client
+        .post(&url)
+        .bearer_auth(token)
+        .json(&fcm_message(target, message))
+        .send()
+        .await
+        .map_err(|e| format!("FCM request failed: {}", e))?;
+    if !response.status().is_success() {
+        let status = response.status();
+        return Err(format!("FCM rejected the message ({}): {}", status, response.text().await.unwrap_or_default()));
+    }
+    Ok(())
+}
+
+pub async fn send(target: &PushTarget, message: &PushMessage) -> Result<(), String> {
+    let client = network::client();
+    let result = match target.service {
+        PushService::Ntfy => send_ntfy(&client, target, message).await,
+        PushService::Fcm => send_fcm(&client, target, message).await,
+    };
+    let data = match (&message.preview, &message.thumbnail) {
+        (Some(_), _) => "Alert summary and animated preview",
+        (None, Some(_)) => "Alert summary and thumbnail",
+        (None, None) => "Alert summary",
+    };
+    let destination = match target.service {
+        PushService::Ntfy => format!("{}/{}", target.server.trim_end_matches('/'), target.topic),
+        PushService::Fcm => format!("fcm:{}", target.name),
+    };
+    audit::record(AuditEntry::new(AuditAction::Transmission, data, destination).outcome(&result));
+    result
+}
+
+// Background task: push alerts to every target that wants them
+pub async fn run(events: Arc<Mutex<EventStore>>) {
+    let mut receiver = events.lock().await.subscribe();
+    while let Some(notice) = alerting::next_notice(&mut receiver, "Push notifications").await {
+        let config = settings::current().push;
+        let targets: Vec<&PushTarget> = config.targets.iter().filter(|t| t.wants(&notice)).collect();
+        if !config.enabled || targets.is_empty() {
+            continue;
+        }
+        let (thumbnail, preview) = if targets.iter().any(|t| t.include_snapshot && t.service == PushService::Ntfy) {
+            // Evidence and its preview are saved before the alert is stored, so they are on disk by now
+            let thumbnail = encryption::read_file(&dataset::snapshot_path(&notice.id)).ok().and_then(|jpeg| thumbnail(&jpeg).ok());
+            (thumbnail, encryption::read_file(&preview::preview_path(&notice.id)).ok())
+        } else {
+            (None, None)
+        };
+        for target in targets {
+            let attach = target.include_snapshot && target.service == PushService::Ntfy;
+            let message = PushMessage::from_notice(&notice, thumbnail.clone().filter(|_| attach), preview.clone().filter(|_| attach));
+            if let Err(e) = send(target, &message).await {
+                eprintln!("Push to '{}' failed: {}", target.name, e);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ntfy(rules: Vec<String>) -> PushTarget {
+        serde_json::from_value(serde_json::json!({ "name": "phones", "service": "ntfy", "topic": "store-42-alerts" }))
+            .map(|t: PushTarget| PushTarget { rules, ..t })
+            .unwrap()
+    }
+
+    fn notice(priority: &str, rule: Option<&str>) -> AlertNotice {
+        AlertNotice {
+            id: "a1".to_string(),
+            camera_id: Some("stockroom".to_string()),
+            description: "Person in the stockroom after hours".to_string(),
+            kind: "rule".to_string(),
+            priority: priority.to_string(),
+            rule: rule.map(str::to_string),
+            box_count: 1,
+            incident_id: None,
+        }
+    }
+
+    #[test]
+    fn test_targets_filter_by_rule_and_severity() {
+        let all = ntfy(Vec::new());
+        assert!(all.validate().is_ok());
+        assert_eq!((all.server.as_str(), all.min_priority.as_str(), all.include_snapshot), (DEFAULT_NTFY_SERVER, "high", true));
+        assert!(all.wants(&notice("critical", None)));
+        assert!(!all.wants(&notice("medium", Some("Stockroom"))));
+
+        let stockroom = ntfy(vec!["Stockroom".to_string()]);
+        assert!(stockroom.wants(&notice("high", Some("Stockroom"))));
+        assert!(!stockroom.wants(&notice("high", Some("Queue"))));
+        assert!(!stockroom.wants(&notice("critical", None)));
+
+        assert!(PushTarget { topic: "has spaces".to_string(), ..all.clone() }.validate().is_err());
+        let fcm = PushTarget { service: PushService::Fcm, service_account: Some("/no/such/account.json".to_string()), ..all.clone() };
+        assert!(fcm.validate().is_err());
+        let twice = PushSettings { enabled: true, targets: vec![all.clone(), all] };
+        assert!(twice.validate().is_err());
+    }
+
+    #[test]
+    fn test_messages() {
+        let message = PushMessage::from_notice(&notice("critical", None), None, None);
+        assert_eq!(message.title, "Critical alert on stockroom");
+        assert_eq!(ntfy_priority(&message.priority), 5);
+
+        let device = PushTarget { service: PushService::Fcm, device_token: Some("device-1".to_string()), ..ntfy(Vec::new()) };
+        let body = fcm_message(&device, &message);
+        assert_eq!(body["message"]["token"], "device-1");
+        assert_eq!(body["message"]["android"]["priority"], "high");
+        assert!(body["message"].get("topic").is_none());
+
+        let frame = image::RgbImage::new(1280, 720);
+        let mut jpeg = std::io::Cursor::new(Vec::new());
+        frame.write_to(&mut jpeg, image::ImageFormat::Jpeg).unwrap();
+        let small = image::load_from_memory(&thumbnail(&jpeg.into_inner()).unwrap()).unwrap();
+        assert_eq!((small.width(), small.height()), (320, 180));
+    }
+}
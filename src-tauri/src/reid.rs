@@ -0,0 +1,264 @@
+// Re-identification Module - Recognise people who step out of frame and come back
+// Each person box gets a colour-histogram appearance embedding that is matched against recently seen visitors
+
+use crate::frame_processor;
+use crate::settings;
+use crate::yolo_detector::{BoundingBox, DetectionData};
+use chrono::{DateTime, Utc};
+use image::{DynamicImage, GenericImageView};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+// 4 levels per RGB channel, for the upper (torso) and lower (legs) half of the box
+const LEVELS: usize = 4;
+const EMBEDDING_DIMS: usize = 2 * LEVELS * LEVELS * LEVELS;
+
+// Weight of the newest sighting when a visitor's embedding is refreshed
+const EMBEDDING_UPDATE: f32 = 0.2;
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct ReidSettings {
+    pub enabled: bool,
+    pub window_secs: u64,  // How long someone who left can come back without counting again
+    pub threshold: f32,    // Cosine similarity needed to call two sightings the same person
+}
+
+impl Default for ReidSettings {
+    fn default() -> Self {
+        ReidSettings {
+            enabled: false,
+            window_secs: 600,
+            threshold: 0.9,
+        }
+    }
+}
+
+impl ReidSettings {
+    pub fn validate(&self) -> Result<(), String> {
+        if !(1..=86_400).contains(&self.window_secs) {
+            return Err("window_secs must be between 1 second and 24 hours".to_string());
+        }
+        if !(self.threshold > 0.0 && self.threshold < 1.0) {
+            return Err("threshold must be between 0 and 1".to_string());
+        }
+        Ok(())
+    }
+}
+
+// Visitor counts for one frame, attached to DetectionData::visitors
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq)]
+pub struct VisitorCounts {
+    pub new: u32,        // People not seen within the window
+    pub returning: u32,  // People who left the frame and were re-identified
+    pub unique: u32,     // Distinct visitors since the tracker started
+    #[serde(default)]
+    pub new_staff: u32,  // New visitors tagged as staff (see staff.rs)
+}
+
+// Normalised colour histogram of a person crop; None when the box misses the frame
+pub fn embed(image: &DynamicImage, bbox: &BoundingBox) -> Option<Vec<f32>> {
+    let (width, height) = image.dimensions();
+    let x1 = bbox.x1.max(0.0) as u32;
+    let y1 = bbox.y1.max(0.0) as u32;
+    let x2 = (bbox.x2.max(0.0) as u32).min(width);
+    let y2 = (bbox.y2.max(0.0) as u32).min(height);
+    if x2 <= x1 || y2 <= y1 {
+        return None;
+    }
+
+    let crop = image.crop_imm(x1, y1, x2 - x1, y2 - y1).to_rgb8();
+    let middle = crop.height() / 2;
+    let mut histogram = vec![0f32; EMBEDDING_DIMS];
+    for (_, y, pixel) in crop.enumerate_pixels() {
+        let [r, g, b] = pixel.0.map(|c| c as usize * LEVELS / 256);
+        let half = if y < middle { 0 } else { 1 };
+        histogram[half * LEVELS * LEVELS * LEVELS + (r * LEVELS + g) * LEVELS + b] += 1.0;
+    }
+
+    // Square roots damp the dominant colour so the cosine compares distributions (Hellinger)
+    histogram.iter_mut().for_each(|v| *v = v.sqrt());
+    normalize(&mut histogram);
+    Some(histogram)
+}
+
+fn normalize(vector: &mut [f32]) {
+    let norm = vector.iter().map(|v| v * v).sum::<f32>().sqrt();
+    if norm > 0.0 {
+        vector.iter_mut().for_each(|v| *v /= norm);
+    }
+}
+
+fn similarity(a: &[f32], b: &[f32]) -> f32 {
+    a.iter().zip(b).map(|(x, y)| x * y).sum()
+}
+
+struct Visitor {
+    id: u64,
+    embedding: Vec<f32>,
+    last_seen: DateTime<Utc>,
+    in_frame: bool,
+}
+
+pub struct ReIdentifier {
+    settings: ReidSettings,
+    visitors: Vec<Visitor>,
+    unique: u32,
+}
+
+impl ReIdentifier {
+    pub fn new(settings: ReidSettings) -> Self {
+        ReIdentifier {
+            settings,
+            visitors: Vec::new(),
+            unique: 0,
+        }
+    }
+
+    // Match this frame's embeddings to known visitors, best pairs first; also returns each embedding's track id
+    pub fn observe(&mut self, now: DateTime<Utc>, embeddings: Vec<Vec<f32>>) -> (VisitorCounts, Vec<u64>) {
+        let window = chrono::Duration::seconds(self.settings.window_secs as i64);
+        self.visitors.retain(|v| now - v.last_seen <= window);
+
+        let mut pairs: Vec<(f32, usize, usize)> = Vec::new();
+        for (i, embedding) in embeddings.iter().enumerate() {
+            for (j, visitor) in self.visitors.iter().enumerate() {
+                let score = similarity(embedding, &visitor.embedding);
+                if score >= self.settings.threshold {
+                    pairs.push((score, i, j));
+                }
+            }
+        }
+        pairs.sort_by(|a, b| b.0.total_cmp(&a.0));
+
+        let mut track_ids: Vec<Option<u64>> = vec![None; embeddings.len()];
+        let mut visitor_matched = vec![false; self.visitors.len()];
+        let mut counts = VisitorCounts::default();
+        for (_, i, j) in pairs {
+            if track_ids[i].is_some() || visitor_matched[j] {
+                continue;
+            }
+            visitor_matched[j] = true;
+
+            let visitor = &mut self.visitors[j];
+            track_ids[i] = Some(visitor.id);
+            if !visitor.in_frame {
+                counts.returning += 1;
+            }
+            for (kept, seen) in visitor.embedding.iter_mut().zip(&embeddings[i]) {
+                *kept = (1.0 - EMBEDDING_UPDATE) * *kept + EMBEDDING_UPDATE * seen;
+            }
+            normalize(&mut visitor.embedding);
+            visitor.last_seen = now;
+        }
+
+        for (visitor, matched) in self.visitors.iter_mut().zip(&visitor_matched) {
+            visitor.in_frame = *matched;
+        }
+        let track_ids = embeddings
+            .into_iter()
+            .zip(track_ids)
+            .map(|(embedding, track_id)| {
+                track_id.unwrap_or_else(|| {
+                    // Track ids double as the running unique-visitor count
+                    counts.new += 1;
+                    self.unique += 1;
+                    self.visitors.push(Visitor {
+                        id: self.unique as u64,
+                        embedding,
+                        last_seen: now,
+                        in_frame: true,
+                    });
+                    self.unique as u64
+                })
+            })
+            .collect();
+
+        counts.unique = self.unique;
+        (counts, track_ids)
+    }
+
+    // Sets visitor counts on the detection and a track id on every person box that could be embedded
+    pub fn apply(&mut self, frame_base64: &str, detection: &mut DetectionData) -> Result<(), String> {
+        let mut people: Vec<&mut BoundingBox> = detection.boxes.iter_mut().filter(|b| b.class_name == "person").collect();
+        let mut embedded = Vec::new();
+        let mut embeddings = Vec::new();
+        if !people.is_empty() {
+            let image = frame_processor::decode_frame(frame_base64)?;
+            for (index, bbox) in people.iter().enumerate() {
+                if let Some(embedding) = embed(&image, bbox) {
+                    embedded.push(index);
+                    embeddings.push(embedding);
+                }
+            }
+        }
+
+        let (counts, track_ids) = self.observe(Utc::now(), embeddings);
+        for (index, track_id) in embedded.into_iter().zip(track_ids) {
+            people[index].track_id = Some(track_id);
+        }
+        detection.visitors = Some(counts);
+        Ok(())
+    }
+}
+
+// One re-identifier per camera for the live detection paths
+#[derive(Default)]
+pub struct Reidentifiers {
+    cameras: HashMap<String, ReIdentifier>,
+}
+
+impl Reidentifiers {
+    pub fn apply(&mut self, camera_id: Option<&str>, frame_base64: &str, detection: &mut DetectionData) {
+        let settings = settings::current().reid;
+        if !settings.enabled {
+            self.cameras.clear();
+            return;
+        }
+        let tracker = self
+            .cameras
+            .entry(camera_id.unwrap_or("").to_string())
+            .or_insert_with(|| ReIdentifier::new(settings.clone()));
+        if tracker.settings != settings {
+            *tracker = ReIdentifier::new(settings);
+        }
+        if let Err(e) = tracker.apply(frame_base64, detection) {
+            eprintln!("Re-identification skipped: {}", e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{Rgb, RgbImage};
+
+    fn person(x1: f32, x2: f32) -> BoundingBox {
+        BoundingBox::new("person", x1, 0.0, x2, 40.0, 0.9)
+    }
+
+    #[test]
+    fn test_returning_visitor_is_not_counted_twice() {
+        // A red-shirted and a blue-shirted person side by side
+        let frame = DynamicImage::ImageRgb8(RgbImage::from_fn(40, 40, |x, y| match (x < 20, y < 20) {
+            (true, true) => Rgb([220, 30, 30]),
+            (false, true) => Rgb([30, 30, 220]),
+            _ => Rgb([40, 40, 40]),
+        }));
+        let red = embed(&frame, &person(0.0, 20.0)).unwrap();
+        let blue = embed(&frame, &person(20.0, 40.0)).unwrap();
+        assert!(embed(&frame, &person(50.0, 60.0)).is_none());
+
+        let mut tracker = ReIdentifier::new(ReidSettings { enabled: true, ..Default::default() });
+        let start = Utc::now();
+        let at = |secs| start + chrono::Duration::seconds(secs);
+
+        assert_eq!(tracker.observe(at(0), vec![red.clone()]), (VisitorCounts { new: 1, returning: 0, unique: 1, new_staff: 0 }, vec![1]));
+        assert_eq!(tracker.observe(at(1), vec![red.clone(), blue.clone()]).0, VisitorCounts { new: 1, returning: 0, unique: 2, new_staff: 0 });
+        assert_eq!(tracker.observe(at(2), vec![blue.clone()]).0, VisitorCounts { new: 0, returning: 0, unique: 2, new_staff: 0 });
+        // Red steps back in within the window and keeps their track
+        assert_eq!(tracker.observe(at(60), vec![blue, red.clone()]), (VisitorCounts { new: 0, returning: 1, unique: 2, new_staff: 0 }, vec![2, 1]));
+        // ...but after the window they count as a new visit
+        assert_eq!(tracker.observe(at(2000), vec![red]).0, VisitorCounts { new: 1, returning: 0, unique: 3, new_staff: 0 });
+    }
+}
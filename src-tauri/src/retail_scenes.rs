@@ -0,0 +1,221 @@
+// Retail Scenes Module - JSON schemas for the built-in scene analyses and the alerts they raise
+// An answer that doesn't match its scene's schema is still stored, but never raises an alert; a clean answer clears the scene's incidents
+
+use serde_json::Value;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum FieldType {
+    Count,  // Whole number, zero or more
+    Boolean,
+    Text,
+}
+
+const CHECKOUT_SCHEMA: [(&str, FieldType); 6] = [
+    ("lanes_total", FieldType::Count),
+    ("lanes_open", FieldType::Count),
+    ("items_on_belt", FieldType::Count),
+    ("cashier_present", FieldType::Boolean),
+    ("customers_waiting", FieldType::Count),
+    ("description", FieldType::Text),
+];
+
+const PARKING_SCHEMA: [(&str, FieldType); 6] = [
+    ("spaces_total", FieldType::Count),
+    ("spaces_free", FieldType::Count),
+    ("vehicles_count", FieldType::Count),
+    ("double_parked_vehicles", FieldType::Count),
+    ("blocked_access", FieldType::Boolean),
+    ("description", FieldType::Text),
+];
+
+fn schema(scene_type: &str) -> Option<&'static [(&'static str, FieldType)]> {
+    match scene_type {
+        "checkout" => Some(&CHECKOUT_SCHEMA),
+        "parking" => Some(&PARKING_SCHEMA),
+        _ => None,
+    }
+}
+
+impl FieldType {
+    fn name(self) -> &'static str {
+        match self {
+            FieldType::Count => "count",
+            FieldType::Boolean => "boolean",
+            FieldType::Text => "string",
+        }
+    }
+}
+
+fn matches(value: &Value, field_type: FieldType) -> bool {
+    match field_type {
+        FieldType::Count => value.as_u64().is_some() || value.as_f64().is_some_and(|n| n >= 0.0 && n.fract() == 0.0),
+        FieldType::Boolean => value.is_boolean(),
+        FieldType::Text => value.is_string(),
+    }
+}
+
+fn count(data: &Value, field: &str) -> u64 {
+    data[field].as_u64().or_else(|| data[field].as_f64().map(|n| n as u64)).unwrap_or(0)
+}
+
+// Check an answer against its scene's schema; scenes without one always pass
+pub fn validate(scene_type: &str, data: &Value) -> Result<(), Vec<String>> {
+    let Some(fields) = schema(scene_type) else {
+        return Ok(());
+    };
+    if !data.is_object() {
+        return Err(vec!["answer is not a JSON object".to_string()]);
+    }
+
+    let mut errors: Vec<String> = fields
+        .iter()
+        .filter(|(field, field_type)| !matches(&data[*field], *field_type))
+        .map(|(field, field_type)| match data.get(*field) {
+            None => format!("missing '{}'", field),
+            Some(_) => format!("'{}' is not a {}", field, field_type.name()),
+        })
+        .collect();
+
+    // Cross-field checks only make sense once every field has the right type
+    let exceeds = |part: &str, whole: &str| count(data, part) > count(data, whole);
+    if errors.is_empty() && scene_type == "checkout" && exceeds("lanes_open", "lanes_total") {
+        errors.push("'lanes_open' is more than 'lanes_total'".to_string());
+    }
+    if errors.is_empty() && scene_type == "parking" && exceeds("spaces_free", "spaces_total") {
+        errors.push("'spaces_free' is more than 'spaces_total'".to_string());
+    }
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SceneAlert {
+    pub kind: &'static str,
+    pub priority: &'static str,
+    pub description: String,
+    pub hazard_type: Option<String>,  // Safety scenes: keeps a spill and a blocked exit apart as incidents
+}
+
+// The alert a valid answer calls for, if any
+pub fn alert(scene_type: &str, data: &Value) -> Option<SceneAlert> {
+    validate(scene_type, data).ok()?;
+    match scene_type {
+        "safety" if data["hazard_detected"] == Value::Bool(true) => {
+            let hazard = data["hazard_type"].as_str().filter(|h| *h != "none").unwrap_or("unknown");
+            let priority = match (data["immediate_action_required"].as_bool(), data["severity"].as_str()) {
+                (Some(true), _) => "critical",
+                (_, Some("high")) => "high",
+                (_, Some("medium")) => "medium",
+                _ => "low",
+            };
+            Some(SceneAlert {
+                kind: "safety",
+                priority,
+                description: format!("Safety hazard: {}", hazard),
+                hazard_type: Some(hazard.to_string()),
+            })
+        }
+        "checkout" => {
+            let waiting = count(data, "customers_waiting");
+            let unstaffed = data["cashier_present"] == Value::Bool(false) || count(data, "lanes_open") == 0;
+            (waiting > 0 && unstaffed).then(|| SceneAlert {
+                kind: "checkout",
+                priority: if waiting >= 3 { "high" } else { "medium" },
+                description: format!("Checkout unstaffed with {} customers waiting", waiting),
+                hazard_type: None,
+            })
+        }
+        "parking" => {
+            let double_parked = count(data, "double_parked_vehicles");
+            let blocked = data["blocked_access"] == Value::Bool(true);
+            match (double_parked, blocked) {
+                (_, true) => Some(SceneAlert {
+                    kind: "parking",
+                    priority: "high",
+                    description: "Parking access blocked".to_string(),
+                    hazard_type: None,
+                }),
+                (0, false) => None,
+                (n, false) => Some(SceneAlert {
+                    kind: "parking",
+                    priority: "low",
+                    description: format!("{} vehicles double-parked", n),
+                    hazard_type: None,
+                }),
+            }
+        }
+        _ => None,
+    }
+}
+
+// Whether an answer shows the scene back to normal, resolving its open incidents
+pub fn all_clear(scene_type: &str, data: &Value) -> bool {
+    match scene_type {
+        "safety" => data["hazard_detected"] == Value::Bool(false),
+        "checkout" | "parking" => validate(scene_type, data).is_ok() && alert(scene_type, data).is_none(),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn checkout(cashier_present: bool, lanes_open: u64, customers_waiting: u64) -> Value {
+        json!({
+            "lanes_total": 4,
+            "lanes_open": lanes_open,
+            "items_on_belt": 2,
+            "cashier_present": cashier_present,
+            "customers_waiting": customers_waiting,
+            "description": "checkout area"
+        })
+    }
+
+    #[test]
+    fn test_schema_validation() {
+        assert!(validate("checkout", &checkout(true, 2, 1)).is_ok());
+        assert!(validate("queue", &json!("anything")).is_ok());
+
+        let errors = validate("parking", &json!({ "spaces_total": 10, "spaces_free": -1, "vehicles_count": 9, "blocked_access": "no", "description": "" })).unwrap_err();
+        assert_eq!(errors, vec!["'spaces_free' is not a count", "missing 'double_parked_vehicles'", "'blocked_access' is not a boolean"]);
+
+        let errors = validate("checkout", &checkout(true, 5, 0)).unwrap_err();
+        assert_eq!(errors, vec!["'lanes_open' is more than 'lanes_total'"]);
+    }
+
+    #[test]
+    fn test_checkout_alerts() {
+        assert_eq!(alert("checkout", &checkout(true, 2, 4)), None);
+        let unstaffed = alert("checkout", &checkout(false, 1, 4)).unwrap();
+        assert_eq!((unstaffed.kind, unstaffed.priority), ("checkout", "high"));
+        assert_eq!(alert("checkout", &checkout(true, 0, 1)).unwrap().priority, "medium");
+        assert_eq!(alert("checkout", &checkout(false, 0, 0)), None);
+    }
+
+    #[test]
+    fn test_parking_alerts() {
+        let parking = |double_parked: u64, blocked: bool| {
+            json!({ "spaces_total": 20, "spaces_free": 3, "vehicles_count": 17, "double_parked_vehicles": double_parked, "blocked_access": blocked, "description": "car park" })
+        };
+        assert_eq!(alert("parking", &parking(0, false)), None);
+        assert_eq!(alert("parking", &parking(2, false)).unwrap().description, "2 vehicles double-parked");
+        assert_eq!(alert("parking", &parking(0, true)).unwrap().priority, "high");
+        assert!(all_clear("parking", &parking(0, false)));
+    }
+
+    #[test]
+    fn test_safety_alerts_and_clearing() {
+        let spill = json!({ "hazard_detected": true, "hazard_type": "spill", "immediate_action_required": false, "severity": "medium" });
+        let alert = alert("safety", &spill).unwrap();
+        assert_eq!((alert.kind, alert.priority, alert.hazard_type.as_deref()), ("safety", "medium", Some("spill")));
+        assert!(!all_clear("safety", &spill));
+        assert!(all_clear("safety", &json!({ "hazard_detected": false, "hazard_type": "none" })));
+        assert!(!all_clear("queue", &json!({})));
+    }
+}
@@ -0,0 +1,212 @@
+// Retention Module - Scheduled snapshot purging and anonymization of old event text
+// Event rows and their counts are kept, so daily reports and metrics over old periods still add up
+
+use crate::dataset;
+use crate::event_store::{format_timestamp, EventStore};
+use crate::settings;
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::path::Path;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+// Written in place of removed text, so a gap reads as deliberate
+pub const ANONYMIZED: &str = "[anonymized]";
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct RetentionSettings {
+    pub enabled: bool,
+    pub snapshot_days: Option<u32>,  // None keeps snapshots forever
+    pub anonymize_after_days: Option<u32>,  // None never anonymizes
+    pub identifying_terms: Vec<String>,  // Words or phrases that make a description identifying
+    pub interval_hours: u64,
+}
+
+impl Default for RetentionSettings {
+    fn default() -> Self {
+        RetentionSettings {
+            enabled: false,
+            snapshot_days: Some(30),
+            anonymize_after_days: Some(14),
+            identifying_terms: [
+                "face", "hair", "beard", "mustache", "glasses", "tattoo", "skin", "wearing", "shirt", "jacket", "dress",
+                "hat", "man", "woman", "boy", "girl", "child", "years old", "license plate", "name",
+            ]
+            .iter()
+            .map(|t| t.to_string())
+            .collect(),
+            interval_hours: 24,
+        }
+    }
+}
+
+impl RetentionSettings {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.snapshot_days == Some(0) || self.anonymize_after_days == Some(0) {
+            return Err("Retention periods must be at least 1 day".to_string());
+        }
+        if !(1..=168).contains(&self.interval_hours) {
+            return Err("interval_hours must be between 1 and 168".to_string());
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct RetentionReport {
+    pub ran_at: String,
+    pub snapshots_deleted: usize,
+    pub bytes_freed: u64,
+    pub events_anonymized: usize,
+}
+
+fn words(text: &str) -> String {
+    let cleaned: String = text.to_lowercase().chars().map(|c| if c.is_alphanumeric() { c } else { ' ' }).collect();
+    format!(" {} ", cleaned.split_whitespace().collect::<Vec<_>>().join(" "))
+}
+
+// Whole-word match, so "hat" doesn't hit "that"
+pub fn mentions_identity(text: &str, terms: &[String]) -> bool {
+    let text = words(text);
+    terms.iter().map(|t| words(t)).any(|t| !t.trim().is_empty() && text.contains(&t))
+}
+
+// Every matching string is replaced, not just the first
+fn scrub_value(value: &mut Value, terms: &[String]) -> bool {
+    let children: Vec<&mut Value> = match value {
+        Value::String(text) if mentions_identity(text, terms) => {
+            *text = ANONYMIZED.to_string();
+            return true;
+        }
+        Value::Array(items) => items.iter_mut().collect(),
+        Value::Object(fields) => fields.values_mut().collect(),
+        _ => return false,
+    };
+    let mut hit = false;
+    for child in children {
+        hit |= scrub_value(child, terms);
+    }
+    hit
+}
+
+// Cleaned description and payload, or None when neither mentions anything identifying
+pub fn scrub(description: Option<&str>, payload: Option<Value>, terms: &[String]) -> Option<(Option<String>, Option<Value>)> {
+    let description_hit = description.is_some_and(|d| mentions_identity(d, terms));
+    let mut payload = payload;
+    let payload_hit = payload.as_mut().is_some_and(|p| scrub_value(p, terms));
+    if !description_hit && !payload_hit {
+        return None;
+    }
+    let description = if description_hit { Some(ANONYMIZED.to_string()) } else { description.map(str::to_string) };
+    Some((description, payload))
+}
+
+// Snapshots are named after their event; ones without an event go by file age
+fn purge_snapshots(dir: &Path, cutoff: DateTime<Utc>, store: &EventStore) -> (usize, u64) {
+    let cutoff_timestamp = format_timestamp(cutoff);
+    let mut deleted = (0, 0);
+    for entry in std::fs::read_dir(dir).into_iter().flatten().flatten() {
+        let path = entry.path();
+        let Some(event_id) = path.file_stem().and_then(|s| s.to_str()) else {
+            continue;
+        };
+        let expired = match store.event_timestamp(event_id) {
+            Ok(Some(timestamp)) => timestamp < cutoff_timestamp,
+            Ok(None) => entry
+                .metadata()
+                .and_then(|m| m.modified())
+                .is_ok_and(|modified| DateTime::<Utc>::from(modified) < cutoff),
+            Err(_) => false,
+        };
+        if !expired {
+            continue;
+        }
+        let size = entry.metadata().map(|m| m.len()).unwrap_or(0);
+        match std::fs::remove_file(&path) {
+            Ok(()) => deleted = (deleted.0 + 1, deleted.1 + size),
+            Err(e) => eprintln!("Retention: Failed to delete {}: {}", path.display(), e),
+        }
+    }
+    deleted
+}
+
+pub fn run(store: &EventStore, config: &RetentionSettings, snapshots: &Path, now: DateTime<Utc>) -> Result<RetentionReport, String> {
+    let mut report = RetentionReport { ran_at: format_timestamp(now), ..Default::default() };
+    if let Some(days) = config.snapshot_days {
+        (report.snapshots_deleted, report.bytes_freed) = purge_snapshots(snapshots, now - Duration::days(days as i64), store);
+    }
+    if let Some(days) = config.anonymize_after_days {
+        let before = format_timestamp(now - Duration::days(days as i64));
+        let terms = &config.identifying_terms;
+        report.events_anonymized = store.anonymize_before(&before, &report.ran_at, |description, payload| scrub(description, payload, terms))?;
+    }
+    Ok(report)
+}
+
+pub async fn run_now(events: &Mutex<EventStore>) -> Result<RetentionReport, String> {
+    let config = settings::current().retention;
+    let report = run(&*events.lock().await, &config, &dataset::snapshots_dir(), Utc::now())?;
+    if report.snapshots_deleted + report.events_anonymized > 0 {
+        println!(
+            "🧹 Retention: Deleted {} snapshots ({} MB), anonymized {} events",
+            report.snapshots_deleted,
+            report.bytes_freed / 1_048_576,
+            report.events_anonymized
+        );
+    }
+    Ok(report)
+}
+
+// Background task: one pass per interval while enabled
+pub async fn run_scheduler(events: Arc<Mutex<EventStore>>) {
+    loop {
+        let config = settings::current().retention;
+        tokio::time::sleep(std::time::Duration::from_secs(config.interval_hours.max(1) * 3600)).await;
+
+        if !settings::current().retention.enabled {
+            continue;
+        }
+        if let Err(e) = run_now(&events).await {
+            eprintln!("Retention: {}", e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bus::Alert;
+    use crate::event_store::EventFilter;
+
+    #[test]
+    fn test_old_snapshots_purged_and_text_anonymized() {
+        let store = EventStore::open_in_memory().unwrap();
+        let config = RetentionSettings { snapshot_days: Some(7), anonymize_after_days: Some(7), ..Default::default() };
+        let payload = serde_json::json!({"result": {"caption": "A man with a beard at the till"}, "latency_ms": 900});
+        let alert = Alert::new(None, "llava", "A man with a beard at the till", payload, None);
+        let neutral = Alert::new(None, "llava", "That shelf is empty", serde_json::json!({}), None);
+        store.store_alert(&alert).unwrap();
+        store.store_alert(&neutral).unwrap();
+        let (alert, neutral) = (alert.id, neutral.id);
+
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join(format!("{}.jpg", alert)), [0u8; 64]).unwrap();
+
+        // Nothing is old enough yet
+        let report = run(&store, &config, dir.path(), Utc::now()).unwrap();
+        assert_eq!((report.snapshots_deleted, report.events_anonymized), (0, 0));
+
+        let report = run(&store, &config, dir.path(), Utc::now() + Duration::days(8)).unwrap();
+        assert_eq!((report.snapshots_deleted, report.bytes_freed, report.events_anonymized), (1, 64, 1));
+        let event = store.get_event(&alert).unwrap().unwrap();
+        assert_eq!(event.description.as_deref(), Some(ANONYMIZED));
+        assert_eq!(event.payload.unwrap(), serde_json::json!({"result": {"caption": ANONYMIZED}, "latency_ms": 900}));
+        assert_eq!(store.get_event(&neutral).unwrap().unwrap().description.as_deref(), Some("That shelf is empty"));
+        assert_eq!(store.list_events(&EventFilter::default()).unwrap().len(), 2);
+
+        // Each event is only checked once
+        assert_eq!(run(&store, &config, dir.path(), Utc::now() + Duration::days(9)).unwrap().events_anonymized, 0);
+    }
+}
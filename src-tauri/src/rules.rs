@@ -0,0 +1,421 @@
+// Rules Module - User-defined alert rules evaluated on every live detection
+// A rule names a camera, a condition tree over the tracked boxes and time, and the alert priority; matches become alerts with a snapshot
+
+use crate::event_stream::PRIORITIES;
+use crate::motion::heading_difference;
+use crate::staff::Role;
+use crate::yolo_detector::{BoundingBox, DetectionData};
+use crate::zones::Zone;
+use crate::event_store::format_timestamp;
+use crate::{settings, suppression, AppState};
+use chrono::{Local, NaiveTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+fn default_tolerance() -> f32 {
+    45.0
+}
+
+fn default_min_pixels_per_sec() -> f32 {
+    20.0
+}
+
+fn default_min_count() -> u32 {
+    1
+}
+
+fn parse_time(time: &str) -> Result<NaiveTime, String> {
+    NaiveTime::parse_from_str(time, "%H:%M").map_err(|_| format!("Invalid time '{}' (use HH:MM)", time))
+}
+
+// A rule condition as a tree: leaves look at the frame, operators combine them over time
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Condition {
+    // At least min_count objects, e.g. a person in the loading-dock zone
+    Present {
+        class_name: String,
+        #[serde(default)]
+        zone: Option<String>,  // Name of a zone the boxes must be in
+        #[serde(default)]
+        role: Option<Role>,  // Needs staff classification
+        #[serde(default = "default_min_count")]
+        min_count: u32,
+    },
+    // An object faster than the limit, e.g. a car over 3 m/s in the car park
+    Speed {
+        class_name: String,
+        #[serde(default)]
+        zone: Option<String>,
+        #[serde(default)]
+        min_mps: Option<f32>,  // Needs a floor calibration
+        #[serde(default)]
+        min_pixels_per_sec: Option<f32>,
+    },
+    // An object moving within tolerance of a heading (0 right, 90 down), e.g. against the entrance flow
+    Direction {
+        class_name: String,
+        #[serde(default)]
+        zone: Option<String>,
+        heading_degrees: f32,
+        #[serde(default = "default_tolerance")]
+        tolerance_degrees: f32,
+        #[serde(default = "default_min_pixels_per_sec")]
+        min_pixels_per_sec: f32,  // Standing still has no real heading
+    },
+    // Local "HH:MM" to "HH:MM"; may run past midnight
+    TimeBetween { start: String, end: String },
+    All { conditions: Vec<Condition> },
+    Any { conditions: Vec<Condition> },
+    Not { condition: Box<Condition> },
+    // True once the inner condition has held on every frame for secs, e.g. no staff for 2 minutes
+    HeldFor { condition: Box<Condition>, secs: u64 },
+    // True while the inner condition held on some frame in the last secs
+    Within { condition: Box<Condition>, secs: u64 },
+}
+
+// When each timed node last changed, keyed by its position in the tree
+type Timers = HashMap<String, Instant>;
+
+impl Condition {
+    fn validate(&self, has_zone: &dyn Fn(&str) -> bool) -> Result<(), String> {
+        let (class_name, zone) = match self {
+            Condition::Present { class_name, zone, min_count, .. } => {
+                if *min_count == 0 {
+                    return Err("min_count must be at least 1 (wrap the condition in not for absence)".to_string());
+                }
+                (class_name, zone)
+            }
+            Condition::Speed { class_name, zone, min_mps, min_pixels_per_sec } => {
+                if min_mps.is_none() && min_pixels_per_sec.is_none() {
+                    return Err("Speed conditions need min_mps or min_pixels_per_sec".to_string());
+                }
+                (class_name, zone)
+            }
+            Condition::Direction { class_name, zone, tolerance_degrees, .. } => {
+                if !(0.0..=180.0).contains(tolerance_degrees) {
+                    return Err("tolerance_degrees must be between 0 and 180".to_string());
+                }
+                (class_name, zone)
+            }
+            Condition::TimeBetween { start, end } => {
+                parse_time(start)?;
+                parse_time(end)?;
+                return Ok(());
+            }
+            Condition::All { conditions } | Condition::Any { conditions } => {
+                if conditions.is_empty() {
+                    return Err("all and any need at least one condition".to_string());
+                }
+                return conditions.iter().try_for_each(|c| c.validate(has_zone));
+            }
+            Condition::Not { condition } => return condition.validate(has_zone),
+            Condition::HeldFor { condition, secs } | Condition::Within { condition, secs } => {
+                if *secs == 0 {
+                    return Err("Time windows need secs of at least 1".to_string());
+                }
+                return condition.validate(has_zone);
+            }
+        };
+        if class_name.trim().is_empty() {
+            return Err("Conditions need a class_name".to_string());
+        }
+        match zone {
+            Some(zone) if !has_zone(zone) => Err(format!("Unknown zone '{}'", zone)),
+            _ => Ok(()),
+        }
+    }
+
+    // None when the condition is false, otherwise the boxes that made it true (possibly none, e.g. for not).
+    // zones are the ones on the frame's camera
+    pub fn check<'a>(
+        &self,
+        detection: &'a DetectionData,
+        zones: &[&Zone],
+        now: Instant,
+        time: NaiveTime,
+        timers: &mut Timers,
+    ) -> Option<Vec<&'a BoundingBox>> {
+        self.check_node("0", detection, zones, now, time, timers)
+    }
+
+    fn check_node<'a>(
+        &self,
+        path: &str,
+        detection: &'a DetectionData,
+        zones: &[&Zone],
+        now: Instant,
+        time: NaiveTime,
+        timers: &mut Timers,
+    ) -> Option<Vec<&'a BoundingBox>> {
+        let in_scope = |bbox: &BoundingBox, class_name: &str, zone: &Option<String>| {
+            bbox.class_name == class_name
+                && zone.as_deref().is_none_or(|name| zones.iter().any(|z| z.name == name && z.contains(bbox)))
+        };
+        let boxes = |test: &dyn Fn(&BoundingBox) -> bool| {
+            let found: Vec<&BoundingBox> = detection.boxes.iter().filter(|b| test(b)).collect();
+            Some(found).filter(|f| !f.is_empty())
+        };
+        // Children are all checked, never short-circuited, so their timers see every frame
+        let children = |conditions: &[Condition], timers: &mut Timers| -> Vec<Option<Vec<&'a BoundingBox>>> {
+            conditions
+                .iter()
+                .enumerate()
+                .map(|(i, c)| c.check_node(&format!("{}.{}", path, i), detection, zones, now, time, timers))
+                .collect()
+        };
+
+        match self {
+            Condition::Present { class_name, zone, role, min_count } => {
+                let found = boxes(&|b| in_scope(b, class_name, zone) && (role.is_none() || b.role == *role))?;
+                Some(found).filter(|f| f.len() >= *min_count as usize)
+            }
+            Condition::Speed { class_name, zone, min_mps, min_pixels_per_sec } => boxes(&|b| {
+                in_scope(b, class_name, zone)
+                    && b.motion.as_ref().is_some_and(|m| {
+                        let fast_on_floor = min_mps.is_some_and(|min| m.speed_mps.is_some_and(|s| s > min));
+                        let fast_in_image = min_pixels_per_sec.is_some_and(|min| m.pixels_per_sec > min);
+                        fast_on_floor || fast_in_image
+                    })
+            }),
+            Condition::Direction { class_name, zone, heading_degrees, tolerance_degrees, min_pixels_per_sec } => boxes(&|b| {
+                in_scope(b, class_name, zone)
+                    && b.motion.as_ref().is_some_and(|m| {
+                        m.pixels_per_sec >= *min_pixels_per_sec
+                            && heading_difference(m.heading_degrees, *heading_degrees) <= *tolerance_degrees
+                    })
+            }),
+            Condition::TimeBetween { start, end } => {
+                let inside = match (parse_time(start), parse_time(end)) {
+                    (Ok(start), Ok(end)) if start <= end => time >= start && time < end,
+                    (Ok(start), Ok(end)) => time >= start || time < end,
+                    _ => false,
+                };
+                inside.then(Vec::new)
+            }
+            Condition::All { conditions } => {
+                let results = children(conditions, timers);
+                results.into_iter().collect::<Option<Vec<_>>>().map(|found| found.concat())
+            }
+            Condition::Any { conditions } => {
+                let found: Vec<Vec<&BoundingBox>> = children(conditions, timers).into_iter().flatten().collect();
+                (!found.is_empty()).then(|| found.concat())
+            }
+            Condition::Not { condition } => {
+                match condition.check_node(&format!("{}.0", path), detection, zones, now, time, timers) {
+                    Some(_) => None,
+                    None => Some(Vec::new()),
+                }
+            }
+            Condition::HeldFor { condition, secs } => {
+                let found = condition.check_node(&format!("{}.0", path), detection, zones, now, time, timers);
+                let Some(found) = found else {
+                    timers.remove(path);
+                    return None;
+                };
+                let since = *timers.entry(path.to_string()).or_insert(now);
+                Some(found).filter(|_| now.duration_since(since) >= Duration::from_secs(*secs))
+            }
+            Condition::Within { condition, secs } => {
+                let found = condition.check_node(&format!("{}.0", path), detection, zones, now, time, timers);
+                if found.is_some() {
+                    timers.insert(path.to_string(), now);
+                }
+                let recent = timers.get(path).is_some_and(|last| now.duration_since(*last) <= Duration::from_secs(*secs));
+                recent.then(|| found.unwrap_or_default())
+            }
+        }
+    }
+}
+
+fn default_priority() -> String {
+    "medium".to_string()
+}
+
+fn default_cooldown() -> u64 {
+    60
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Rule {
+    pub name: String,
+    #[serde(default)]
+    pub camera_id: Option<String>,  // None applies the rule to every camera
+    pub condition: Condition,
+    #[serde(default = "default_priority")]
+    pub priority: String,
+    #[serde(default = "default_cooldown")]
+    pub cooldown_secs: u64,  // Minimum time between alerts from this rule on one camera
+}
+
+// Conditions may only name zones that exist on the rule's camera
+pub fn validate(rules: &[Rule], zones: &[Zone]) -> Result<(), String> {
+    for (index, rule) in rules.iter().enumerate() {
+        if rule.name.trim().is_empty() {
+            return Err("Rules need a name".to_string());
+        }
+        if rules[..index].iter().any(|r| r.name == rule.name) {
+            return Err(format!("Duplicate rule name '{}'", rule.name));
+        }
+        if !PRIORITIES.contains(&rule.priority.as_str()) {
+            return Err(format!("Unknown priority '{}' (use {})", rule.priority, PRIORITIES.join(", ")));
+        }
+        let has_zone = |name: &str| {
+            zones.iter().any(|z| z.name == name && (rule.camera_id.is_none() || z.applies_to(rule.camera_id.as_deref())))
+        };
+        rule.condition.validate(&has_zone).map_err(|e| format!("Rule '{}': {}", rule.name, e))?;
+    }
+    Ok(())
+}
+
+// Per rule and camera: when it last alerted and the timers of its time windows
+#[derive(Default)]
+pub struct RuleState {
+    fired: HashMap<(String, String), Instant>,
+    timers: HashMap<(String, String), Timers>,
+}
+
+impl RuleState {
+    // Timers are keyed by tree position, so they restart whenever the rules change
+    pub fn reset_timers(&mut self) {
+        self.timers.clear();
+    }
+}
+
+async fn fire(state: &AppState, rule: &Rule, camera_id: Option<&str>, frame_base64: &str, boxes: Vec<&BoundingBox>) {
+    let description = match camera_id {
+        Some(camera) => format!("Rule '{}' triggered on {}", rule.name, camera),
+        None => format!("Rule '{}' triggered", rule.name),
+    };
+    let payload = serde_json::json!({
+        "kind": "rule",
+        "priority": rule.priority,
+        "rule": rule.name,
+        "boxes": boxes,
+    });
+    state.bus.alert(camera_id, "rules", &description, payload, Some(frame_base64)).await;
+    println!("📏 {}", description);
+}
+
+// Alert on every rule for this camera that holds on the frame and is out of cooldown
+pub async fn evaluate(state: &AppState, camera_id: Option<&str>, frame_base64: &str, detection: &DetectionData) {
+    let settings = settings::current();
+    let (rules, suppression) = (settings.rules, settings.suppression);
+    let zones: Vec<&Zone> = settings.zones.iter().filter(|z| z.applies_to(camera_id)).collect();
+    let camera = camera_id.unwrap_or("");
+    let (now, time) = (Instant::now(), Local::now().time());
+    let timestamp = format_timestamp(Utc::now());
+    for rule in rules.iter().filter(|r| r.camera_id.is_none() || r.camera_id.as_deref() == camera_id) {
+        let key = (rule.name.clone(), camera.to_string());
+        let boxes = {
+            let mut rule_state = state.rules.lock().await;
+            let timers = rule_state.timers.entry(key.clone()).or_default();
+            let Some(boxes) = rule.condition.check(detection, &zones, now, time, timers) else {
+                continue;
+            };
+            // Boxes no more confident than the ones operators kept dismissing for this rule don't fire it
+            let min_confidence = suppression::min_confidence(&suppression, &rule.name, &timestamp);
+            if min_confidence.is_some_and(|min| !boxes.is_empty() && boxes.iter().all(|b| b.confidence < min)) {
+                continue;
+            }
+            let cooling = rule_state.fired.get(&key).is_some_and(|t| t.elapsed() < Duration::from_secs(rule.cooldown_secs));
+            if cooling {
+                continue;
+            }
+            rule_state.fired.insert(key, now);
+            boxes
+        };
+        fire(state, rule, camera_id, frame_base64, boxes).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn zone(name: &str, size: f32) -> Zone {
+        serde_json::from_value(serde_json::json!({ "name": name, "points": [[0, 0], [size, 0], [size, size], [0, size]] })).unwrap()
+    }
+
+    #[test]
+    fn test_rule_conditions() {
+        let zones = vec![zone("entrance", 200.0), zone("dock", 100.0)];
+        let json = serde_json::json!([
+            {
+                "name": "Speeding in car park",
+                "camera_id": "car-park",
+                "condition": {"type": "speed", "class_name": "car", "min_mps": 3.0},
+                "priority": "high"
+            },
+            {
+                "name": "Against entrance flow",
+                "condition": {"type": "direction", "class_name": "person", "heading_degrees": 270, "zone": "entrance"}
+            }
+        ]);
+        let rules: Vec<Rule> = serde_json::from_value(json).unwrap();
+        assert!(validate(&rules, &zones).is_ok());
+        assert!(validate(&rules, &zones[1..]).is_err());
+        assert_eq!(rules[1].priority, "medium");
+
+        let moving = |class_name: &str, heading_degrees: f32, speed_mps: Option<f32>| {
+            serde_json::json!({
+                "x1": 50, "y1": 50, "x2": 90, "y2": 90, "confidence": 0.9, "class_name": class_name,
+                "motion": {"track_id": 1, "direction": [0, 0], "heading_degrees": heading_degrees, "pixels_per_sec": 60, "speed_mps": speed_mps}
+            })
+        };
+        let detection = |boxes: Vec<serde_json::Value>| -> DetectionData {
+            serde_json::from_value(serde_json::json!({
+                "person_count": 0, "object_counts": {}, "crowd_density": 0, "motion_intensity": 0, "zone_occupancy": 0, "boxes": boxes
+            }))
+            .unwrap()
+        };
+
+        let start = Instant::now();
+        let noon = NaiveTime::from_hms_opt(12, 0, 0).unwrap();
+        let zones: Vec<&Zone> = zones.iter().collect();
+        let count = |condition: &Condition, frame: &DetectionData| {
+            condition.check(frame, &zones, start, noon, &mut Timers::new()).map(|boxes| boxes.len())
+        };
+        let frame = detection(vec![moving("car", 0.0, Some(5.0)), moving("car", 0.0, Some(1.0)), moving("person", 300.0, None)]);
+        assert_eq!(count(&rules[0].condition, &frame), Some(1));
+        assert_eq!(count(&rules[1].condition, &frame), Some(1));
+        let with_flow = detection(vec![moving("person", 90.0, None)]);
+        assert_eq!(count(&rules[1].condition, &with_flow), None);
+
+        // Person at the loading dock, no staff for 2 minutes, outside 08:00-20:00
+        let dock: Condition = serde_json::from_value(serde_json::json!({
+            "type": "all",
+            "conditions": [
+                {"type": "present", "class_name": "person", "zone": "dock"},
+                {"type": "held_for", "secs": 120, "condition": {"type": "not", "condition": {"type": "present", "class_name": "person", "role": "staff"}}},
+                {"type": "not", "condition": {"type": "time_between", "start": "08:00", "end": "20:00"}}
+            ]
+        }))
+        .unwrap();
+        assert!(dock.validate(&|name| name == "dock").is_ok());
+        assert!(dock.validate(&|name| name == "entrance").is_err());
+        let night = NaiveTime::from_hms_opt(23, 30, 0).unwrap();
+        let mut timers = Timers::new();
+        let at = |secs: u64| start + Duration::from_secs(secs);
+        assert_eq!(dock.check(&with_flow, &zones, at(0), night, &mut timers).map(|b| b.len()), None);
+        assert_eq!(dock.check(&with_flow, &zones, at(121), night, &mut timers).map(|b| b.len()), Some(1));
+        assert!(dock.check(&with_flow, &zones, at(122), noon, &mut timers).is_none());
+        // Staff showing up restarts the two minutes
+        let mut staffed = detection(vec![moving("person", 90.0, None)]);
+        staffed.boxes[0].role = Some(Role::Staff);
+        assert!(dock.check(&staffed, &zones, at(130), night, &mut timers).is_none());
+        assert!(dock.check(&with_flow, &zones, at(200), night, &mut timers).is_none());
+
+        let recent = Condition::Within { condition: Box::new(rules[0].condition.clone()), secs: 10 };
+        let mut timers = Timers::new();
+        assert!(recent.check(&frame, &zones, at(0), noon, &mut timers).is_some());
+        assert!(recent.check(&with_flow, &zones, at(5), noon, &mut timers).is_some());
+        assert!(recent.check(&with_flow, &zones, at(11), noon, &mut timers).is_none());
+        assert!(Condition::Any { conditions: Vec::new() }.validate(&|_| true).is_err());
+
+        let mut duplicate = rules.clone();
+        duplicate[1].name = duplicate[0].name.clone();
+        assert!(validate(&duplicate, &[]).is_err());
+    }
+}
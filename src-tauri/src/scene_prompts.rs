@@ -0,0 +1,229 @@
+// Scene Prompts Module - Editable prompts for the retail scene analyses
+// Built-in prompts are version 0; every edit is kept as a new version, so a store can tune its JSON fields and roll back
+
+use crate::event_store::format_timestamp;
+use crate::settings;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+pub const BUILTIN_SCENES: [&str; 5] = ["queue", "inventory", "safety", "checkout", "parking"];
+
+// Scene types without a prompt of their own
+pub const GENERAL_PROMPT: &str =
+    "Describe this retail scene in detail, focusing on people, objects, activities, and any notable patterns or issues.";
+
+const MAX_PROMPT_CHARS: usize = 8000;
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct PromptVersion {
+    pub version: u32,
+    pub prompt: String,
+    pub created_at: String,
+}
+
+// Scene type -> edited versions, oldest first
+pub type PromptHistory = BTreeMap<String, Vec<PromptVersion>>;
+
+// The prompt a scene analysis uses right now
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ScenePrompt {
+    pub scene_type: String,
+    pub version: u32,
+    pub prompt: String,
+    pub builtin: bool,  // Still the prompt shipped with the app
+}
+
+pub fn builtin(scene_type: &str) -> Option<&'static str> {
+    match scene_type {
+        "queue" => Some(r#"Analyze this retail scene and return a JSON response with:
+{
+  "people_count": number,
+  "queue_formation": "line|cluster|scattered",
+  "estimated_wait_minutes": number,
+  "crowd_density": "low|medium|high",
+  "customer_mood": ["calm", "impatient", "frustrated"],
+  "staff_needed": boolean,
+  "description": "natural language description"
+}"#),
+        "inventory" => Some(r#"Analyze this retail inventory scene and return JSON:
+{
+  "products_visible": number,
+  "shelf_capacity_used": number (0-100),
+  "restocking_needed": boolean,
+  "empty_spots": number,
+  "product_categories": ["category1", "category2"],
+  "organization_quality": "poor|good|excellent",
+  "description": "natural language description"
+}"#),
+        "safety" => Some(r#"Analyze this scene for safety concerns and return JSON:
+{
+  "hazard_detected": boolean,
+  "hazard_type": "spill|obstruction|crowd|equipment|fire|smoke|none",
+  "immediate_action_required": boolean,
+  "affected_area": "description of area",
+  "severity": "low|medium|high",
+  "description": "natural language description"
+}"#),
+        "checkout" => Some(r#"Analyze this checkout area and return JSON only:
+{
+  "lanes_total": number,
+  "lanes_open": number (lanes with a cashier or a working self-checkout),
+  "items_on_belt": number,
+  "cashier_present": boolean,
+  "customers_waiting": number,
+  "description": "natural language description"
+}"#),
+        "parking" => Some(r#"Analyze this parking area and return JSON only:
+{
+  "spaces_total": number,
+  "spaces_free": number,
+  "vehicles_count": number,
+  "double_parked_vehicles": number,
+  "blocked_access": boolean (a vehicle blocks an entrance, exit or driveway),
+  "description": "natural language description"
+}"#),
+        _ => None,
+    }
+}
+
+pub fn resolve(history: &PromptHistory, scene_type: &str) -> ScenePrompt {
+    match history.get(scene_type).and_then(|versions| versions.last()) {
+        Some(latest) => ScenePrompt {
+            scene_type: scene_type.to_string(),
+            version: latest.version,
+            prompt: latest.prompt.clone(),
+            builtin: false,
+        },
+        None => ScenePrompt {
+            scene_type: scene_type.to_string(),
+            version: 0,
+            prompt: builtin(scene_type).unwrap_or(GENERAL_PROMPT).to_string(),
+            builtin: true,
+        },
+    }
+}
+
+// Current prompt for a scene from the settings in effect
+pub fn current(scene_type: &str) -> ScenePrompt {
+    resolve(&settings::current().scene_prompts, scene_type)
+}
+
+// Built-in scenes first, then scenes only the user has defined
+pub fn list(history: &PromptHistory) -> Vec<ScenePrompt> {
+    let custom = history.keys().filter(|scene| !BUILTIN_SCENES.contains(&scene.as_str()));
+    BUILTIN_SCENES
+        .iter()
+        .map(|scene| scene.to_string())
+        .chain(custom.cloned())
+        .map(|scene| resolve(history, &scene))
+        .collect()
+}
+
+// Every version of a scene's prompt, the built-in one included when there is one
+pub fn versions(history: &PromptHistory, scene_type: &str) -> Vec<PromptVersion> {
+    let original = builtin(scene_type).map(|prompt| PromptVersion {
+        version: 0,
+        prompt: prompt.to_string(),
+        created_at: String::new(),
+    });
+    original.into_iter().chain(history.get(scene_type).cloned().unwrap_or_default()).collect()
+}
+
+fn validate_scene_type(scene_type: &str) -> Result<(), String> {
+    let valid = !scene_type.is_empty()
+        && scene_type.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_' || c == '-');
+    if valid {
+        Ok(())
+    } else {
+        Err(format!("Invalid scene type '{}' (use lowercase letters, digits, '-' and '_')", scene_type))
+    }
+}
+
+fn validate_prompt(prompt: &str) -> Result<(), String> {
+    if prompt.trim().is_empty() {
+        return Err("Scene prompt cannot be empty".to_string());
+    }
+    if prompt.chars().count() > MAX_PROMPT_CHARS {
+        return Err(format!("Scene prompt is longer than {} characters", MAX_PROMPT_CHARS));
+    }
+    Ok(())
+}
+
+pub fn validate(history: &PromptHistory) -> Result<(), String> {
+    for (scene_type, versions) in history {
+        validate_scene_type(scene_type)?;
+        for version in versions {
+            validate_prompt(&version.prompt).map_err(|e| format!("Scene '{}' v{}: {}", scene_type, version.version, e))?;
+        }
+    }
+    Ok(())
+}
+
+// Store a prompt as the scene's next version
+pub fn publish(history: &mut PromptHistory, scene_type: &str, prompt: &str) -> Result<PromptVersion, String> {
+    validate_scene_type(scene_type)?;
+    validate_prompt(prompt)?;
+    let versions = history.entry(scene_type.to_string()).or_default();
+    let version = PromptVersion {
+        version: versions.last().map(|v| v.version).unwrap_or(0) + 1,
+        prompt: prompt.trim().to_string(),
+        created_at: format_timestamp(chrono::Utc::now()),
+    };
+    versions.push(version.clone());
+    Ok(version)
+}
+
+// Republish an earlier version (0 is the built-in prompt) as the newest one
+pub fn revert(history: &mut PromptHistory, scene_type: &str, version: u32) -> Result<PromptVersion, String> {
+    let prompt = versions(history, scene_type)
+        .into_iter()
+        .find(|v| v.version == version)
+        .map(|v| v.prompt)
+        .ok_or_else(|| format!("Scene '{}' has no prompt version {}", scene_type, version))?;
+    publish(history, scene_type, &prompt)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builtin_until_edited() {
+        let mut history = PromptHistory::new();
+        let queue = resolve(&history, "queue");
+        assert_eq!((queue.version, queue.builtin), (0, true));
+        assert!(queue.prompt.contains("queue_formation"));
+        assert_eq!(resolve(&history, "lobby").prompt, GENERAL_PROMPT);
+
+        let edited = publish(&mut history, "queue", "Count the queue. Return {\"people_count\": number}").unwrap();
+        assert_eq!(edited.version, 1);
+        let queue = resolve(&history, "queue");
+        assert_eq!((queue.version, queue.builtin), (1, false));
+        assert!(queue.prompt.starts_with("Count the queue"));
+    }
+
+    #[test]
+    fn test_revert_keeps_history() {
+        let mut history = PromptHistory::new();
+        publish(&mut history, "safety", "first edit").unwrap();
+        publish(&mut history, "safety", "second edit").unwrap();
+
+        let reverted = revert(&mut history, "safety", 0).unwrap();
+        assert_eq!(reverted.version, 3);
+        assert_eq!(Some(reverted.prompt.as_str()), builtin("safety"));
+        assert_eq!(versions(&history, "safety").len(), 4);
+        assert!(revert(&mut history, "safety", 9).is_err());
+    }
+
+    #[test]
+    fn test_listing_and_validation() {
+        let mut history = PromptHistory::new();
+        publish(&mut history, "fitting-room", "Count people waiting.").unwrap();
+        let scenes: Vec<String> = list(&history).into_iter().map(|s| s.scene_type).collect();
+        assert_eq!(scenes, vec!["queue", "inventory", "safety", "checkout", "parking", "fitting-room"]);
+
+        assert!(publish(&mut history, "Fitting Room", "x").is_err());
+        assert!(publish(&mut history, "queue", "   ").is_err());
+        assert!(validate(&history).is_ok());
+    }
+}
@@ -0,0 +1,241 @@
+// Screen Capture Module - Screen or window capture as an alternative frame source
+// Uses each platform's native capture tool so dashboards and other apps' feeds can be analyzed
+
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use tokio::process::Command;
+
+// Captures wider than this are downscaled before they reach a model
+pub const DEFAULT_MAX_WIDTH: u32 = 1280;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Platform {
+    MacOs,
+    Windows,
+    LinuxX11,
+    LinuxWayland,
+}
+
+impl Platform {
+    pub fn current() -> Result<Platform, String> {
+        if cfg!(target_os = "macos") {
+            Ok(Platform::MacOs)
+        } else if cfg!(target_os = "windows") {
+            Ok(Platform::Windows)
+        } else if cfg!(target_os = "linux") {
+            if std::env::var("WAYLAND_DISPLAY").is_ok() {
+                Ok(Platform::LinuxWayland)
+            } else {
+                Ok(Platform::LinuxX11)
+            }
+        } else {
+            Err("Screen capture is not supported on this platform".to_string())
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Platform::MacOs => "macos",
+            Platform::Windows => "windows",
+            Platform::LinuxX11 => "linux-x11",
+            Platform::LinuxWayland => "linux-wayland",
+        }
+    }
+}
+
+// Screen-space rectangle in pixels
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CaptureRegion {
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+}
+
+// What to capture; an empty target captures the main display
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct CaptureTarget {
+    pub display: Option<u32>,        // 1-based display index (macOS) / X11 screen number
+    pub window: Option<String>,      // Window id (macOS) or window title (Windows)
+    pub region: Option<CaptureRegion>,
+    pub max_width: Option<u32>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CaptureSourceInfo {
+    pub platform: String,
+    pub tool: String,
+    pub available: bool,
+    pub supports_window: bool,
+    pub supports_region: bool,
+}
+
+// Capture tool and arguments for a target, writing a JPEG to `output`
+pub fn capture_command(platform: Platform, target: &CaptureTarget, output: &Path) -> Result<(String, Vec<String>), String> {
+    let output = output.display().to_string();
+    let ffmpeg = std::env::var("LVA_FFMPEG_PATH").unwrap_or_else(|_| "ffmpeg".to_string());
+    let mut args: Vec<String> = Vec::new();
+
+    match platform {
+        Platform::MacOs => {
+            args.extend(["-x", "-t", "jpg"].map(String::from));
+            if let Some(window) = &target.window {
+                let window_id: u32 = window
+                    .parse()
+                    .map_err(|_| format!("macOS window capture needs a numeric window id, got '{}'", window))?;
+                args.push("-l".to_string());
+                args.push(window_id.to_string());
+            } else if let Some(display) = target.display {
+                args.push("-D".to_string());
+                args.push(display.to_string());
+            }
+            if let Some(region) = &target.region {
+                args.push("-R".to_string());
+                args.push(format!("{},{},{},{}", region.x, region.y, region.width, region.height));
+            }
+            args.push(output);
+            Ok(("screencapture".to_string(), args))
+        }
+        Platform::Windows => {
+            args.extend(["-hide_banner", "-loglevel", "error", "-y", "-f", "gdigrab"].map(String::from));
+            if let Some(region) = &target.region {
+                args.extend([
+                    "-offset_x".to_string(), region.x.to_string(),
+                    "-offset_y".to_string(), region.y.to_string(),
+                    "-video_size".to_string(), format!("{}x{}", region.width, region.height),
+                ]);
+            }
+            args.push("-i".to_string());
+            args.push(match &target.window {
+                Some(title) => format!("title={}", title),
+                None => "desktop".to_string(),
+            });
+            args.extend(["-frames:v", "1"].map(String::from));
+            args.push(output);
+            Ok((ffmpeg, args))
+        }
+        Platform::LinuxX11 => {
+            if target.window.is_some() {
+                return Err("Window capture is not supported on X11 - use a region instead".to_string());
+            }
+            let display = std::env::var("DISPLAY").unwrap_or_else(|_| ":0".to_string());
+            let screen = target.display.unwrap_or(0);
+            args.extend(["-hide_banner", "-loglevel", "error", "-y", "-f", "x11grab"].map(String::from));
+            let input = match &target.region {
+                Some(region) => {
+                    args.push("-video_size".to_string());
+                    args.push(format!("{}x{}", region.width, region.height));
+                    format!("{}.{}+{},{}", display, screen, region.x, region.y)
+                }
+                None => format!("{}.{}", display, screen),
+            };
+            args.push("-i".to_string());
+            args.push(input);
+            args.extend(["-frames:v", "1"].map(String::from));
+            args.push(output);
+            Ok((ffmpeg, args))
+        }
+        Platform::LinuxWayland => {
+            if target.window.is_some() {
+                return Err("Window capture is not supported on Wayland - use a region instead".to_string());
+            }
+            args.extend(["-t", "jpeg"].map(String::from));
+            if let Some(region) = &target.region {
+                args.push("-g".to_string());
+                args.push(format!("{},{} {}x{}", region.x, region.y, region.width, region.height));
+            }
+            args.push(output);
+            Ok(("grim".to_string(), args))
+        }
+    }
+}
+
+// Report which capture tool this platform uses and whether it can be run
+pub async fn source_info() -> Result<CaptureSourceInfo, String> {
+    let platform = Platform::current()?;
+    let (tool, _) = capture_command(platform, &CaptureTarget::default(), Path::new("probe.jpg"))?;
+
+    // screencapture has no version flag; it ships with macOS
+    let available = match platform {
+        Platform::MacOs => true,
+        Platform::LinuxWayland => Command::new(&tool).arg("-h").output().await.is_ok(),
+        _ => Command::new(&tool).arg("-version").output().await.is_ok(),
+    };
+
+    Ok(CaptureSourceInfo {
+        platform: platform.name().to_string(),
+        tool,
+        available,
+        supports_window: matches!(platform, Platform::MacOs | Platform::Windows),
+        supports_region: true,
+    })
+}
+
+// Grab one frame and return it as a base64 JPEG, downscaled to `max_width`
+pub async fn capture_frame(target: &CaptureTarget) -> Result<String, String> {
+    let platform = Platform::current()?;
+    let temp_dir = tempfile::tempdir().map_err(|e| format!("Failed to create temp directory: {}", e))?;
+    let output = temp_dir.path().join("capture.jpg");
+    let (tool, args) = capture_command(platform, target, &output)?;
+
+    let result = Command::new(&tool)
+        .args(&args)
+        .output()
+        .await
+        .map_err(|e| format!("Failed to run {} (is it installed?): {}", tool, e))?;
+
+    if !result.status.success() || !output.exists() {
+        return Err(format!(
+            "Screen capture failed ({}): {}",
+            tool,
+            String::from_utf8_lossy(&result.stderr).trim()
+        ));
+    }
+
+    let image = image::open(&output).map_err(|e| format!("Failed to read capture: {}", e))?;
+    let max_width = target.max_width.unwrap_or(DEFAULT_MAX_WIDTH);
+    let image = if image.width() > max_width {
+        image.resize(max_width, u32::MAX, image::imageops::FilterType::Triangle)
+    } else {
+        image
+    };
+
+    crate::frame_processor::encode_frame(&image)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_macos_window_and_region_args() {
+        let target = CaptureTarget {
+            window: Some("4242".to_string()),
+            region: Some(CaptureRegion { x: 10, y: 20, width: 640, height: 480 }),
+            ..CaptureTarget::default()
+        };
+        let (tool, args) = capture_command(Platform::MacOs, &target, Path::new("/tmp/c.jpg")).unwrap();
+
+        assert_eq!(tool, "screencapture");
+        assert_eq!(args, vec!["-x", "-t", "jpg", "-l", "4242", "-R", "10,20,640,480", "/tmp/c.jpg"]);
+
+        let bad = CaptureTarget { window: Some("Grafana".to_string()), ..CaptureTarget::default() };
+        assert!(capture_command(Platform::MacOs, &bad, Path::new("/tmp/c.jpg")).is_err());
+    }
+
+    #[test]
+    fn test_ffmpeg_grab_inputs() {
+        let target = CaptureTarget { window: Some("Grafana".to_string()), ..CaptureTarget::default() };
+        let (_, args) = capture_command(Platform::Windows, &target, Path::new("c.jpg")).unwrap();
+        assert!(args.contains(&"title=Grafana".to_string()));
+
+        let region = CaptureTarget {
+            region: Some(CaptureRegion { x: 100, y: 50, width: 800, height: 600 }),
+            ..CaptureTarget::default()
+        };
+        let (_, args) = capture_command(Platform::LinuxX11, &region, Path::new("c.jpg")).unwrap();
+        assert!(args.contains(&"800x600".to_string()));
+        assert!(args.iter().any(|a| a.ends_with(".0+100,50")));
+        assert!(capture_command(Platform::LinuxX11, &target, Path::new("c.jpg")).is_err());
+    }
+}
@@ -0,0 +1,320 @@
+// Scripting Module - Site-specific logic in Rhai scripts, without forking the crate
+// Scripts define on_detection(detection, camera) and/or on_analysis(analysis, camera) and call escalate, annotate or notify
+
+use crate::event_stream::PRIORITIES;
+use crate::yolo_detector::DetectionData;
+use crate::{settings, AppState};
+use rhai::{Dynamic, Engine, EvalAltResult, Scope, AST};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+
+pub const HOOKS: [&str; 2] = ["on_detection", "on_analysis"];
+
+// A runaway loop in one script must not stall the frame
+const MAX_OPERATIONS: u64 = 200_000;
+
+fn default_enabled() -> bool {
+    true
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Script {
+    pub name: String,
+    pub source: String,
+    #[serde(default)]
+    pub camera_id: Option<String>,  // None runs the script for every camera
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Notification {
+    pub script: String,
+    pub message: String,
+    pub priority: String,
+}
+
+// What the scripts asked for on one detection or analysis
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct ScriptOutcome {
+    pub escalate: Option<String>,
+    pub annotations: BTreeMap<String, serde_json::Value>,
+    pub notifications: Vec<Notification>,
+    pub errors: Vec<String>,
+}
+
+enum Action {
+    Escalate(String),
+    Annotate(String, serde_json::Value),
+    Notify(String, String),
+}
+
+type Actions = Arc<std::sync::Mutex<Vec<Action>>>;
+
+fn push(actions: &Actions, action: Action) {
+    if let Ok(mut actions) = actions.lock() {
+        actions.push(action);
+    }
+}
+
+fn build_engine(actions: &Actions) -> Engine {
+    let mut engine = Engine::new();
+    engine.set_max_operations(MAX_OPERATIONS);
+    engine.set_max_call_levels(32);
+    engine.set_max_string_size(16_384);
+    engine.set_max_array_size(10_000);
+    engine.set_max_map_size(10_000);
+
+    let sink = actions.clone();
+    engine.register_fn("escalate", move |reason: &str| push(&sink, Action::Escalate(reason.to_string())));
+    let sink = actions.clone();
+    engine.register_fn("annotate", move |key: &str, value: Dynamic| -> Result<(), Box<EvalAltResult>> {
+        let value = rhai::serde::from_dynamic::<serde_json::Value>(&value)?;
+        push(&sink, Action::Annotate(key.to_string(), value));
+        Ok(())
+    });
+    let sink = actions.clone();
+    engine.register_fn("notify", move |message: &str| push(&sink, Action::Notify(message.to_string(), "medium".to_string())));
+    let sink = actions.clone();
+    engine.register_fn("notify", move |message: &str, priority: &str| -> Result<(), Box<EvalAltResult>> {
+        if !PRIORITIES.contains(&priority) {
+            return Err(format!("Unknown priority '{}' (use {})", priority, PRIORITIES.join(", ")).into());
+        }
+        push(&sink, Action::Notify(message.to_string(), priority.to_string()));
+        Ok(())
+    });
+    engine
+}
+
+// Compiled scripts, recompiled only when their source changes
+pub struct ScriptHost {
+    engine: Engine,
+    actions: Actions,
+    compiled: HashMap<String, (String, Arc<AST>)>,
+}
+
+impl Default for ScriptHost {
+    fn default() -> Self {
+        let actions = Actions::default();
+        ScriptHost {
+            engine: build_engine(&actions),
+            actions,
+            compiled: HashMap::new(),
+        }
+    }
+}
+
+impl ScriptHost {
+    // Compile a script and report which hooks it defines
+    pub fn check(&self, source: &str) -> Result<Vec<String>, String> {
+        let ast = self.engine.compile(source).map_err(|e| format!("Failed to compile script: {}", e))?;
+        let hooks: Vec<String> = ast
+            .iter_functions()
+            .filter(|f| HOOKS.contains(&f.name) && (1..=2).contains(&f.params.len()))
+            .map(|f| f.name.to_string())
+            .collect();
+        if hooks.is_empty() {
+            return Err(format!("Scripts must define {} with (value) or (value, camera)", HOOKS.join(" or ")));
+        }
+        Ok(hooks)
+    }
+
+    fn ast(&mut self, script: &Script) -> Result<Arc<AST>, String> {
+        if let Some((source, ast)) = self.compiled.get(&script.name) {
+            if *source == script.source {
+                return Ok(ast.clone());
+            }
+        }
+        let ast = Arc::new(self.engine.compile(&script.source).map_err(|e| format!("Failed to compile script: {}", e))?);
+        self.compiled.insert(script.name.clone(), (script.source.clone(), ast.clone()));
+        Ok(ast)
+    }
+
+    // Call the hook in every enabled script for this camera that defines it
+    pub fn run(&mut self, scripts: &[Script], hook: &str, value: &Dynamic, camera_id: Option<&str>) -> ScriptOutcome {
+        let mut outcome = ScriptOutcome::default();
+        let camera = camera_id.unwrap_or("");
+        for script in scripts.iter().filter(|s| s.enabled && (s.camera_id.is_none() || s.camera_id.as_deref() == camera_id)) {
+            let result = self.ast(script).and_then(|ast| {
+                let Some(params) = ast.iter_functions().find(|f| f.name == hook).map(|f| f.params.len()) else {
+                    return Ok(());
+                };
+                if let Ok(mut actions) = self.actions.lock() {
+                    actions.clear();
+                }
+                let mut scope = Scope::new();
+                let called = match params {
+                    1 => self.engine.call_fn::<Dynamic>(&mut scope, &ast, hook, (value.clone(),)),
+                    _ => self.engine.call_fn::<Dynamic>(&mut scope, &ast, hook, (value.clone(), camera.to_string())),
+                };
+                called.map(|_| ()).map_err(|e| e.to_string())
+            });
+            if let Err(e) = result {
+                eprintln!("Script '{}' failed in {}: {}", script.name, hook, e);
+                outcome.errors.push(format!("{}: {}", script.name, e));
+                continue;
+            }
+
+            // Actions only count when the script ran to the end
+            let actions = self.actions.lock().map(|mut a| std::mem::take(&mut *a)).unwrap_or_default();
+            for action in actions {
+                match action {
+                    Action::Escalate(reason) => {
+                        outcome.escalate.get_or_insert_with(|| format!("{}: {}", script.name, reason));
+                    }
+                    Action::Annotate(key, value) => {
+                        outcome.annotations.insert(key, value);
+                    }
+                    Action::Notify(message, priority) => outcome.notifications.push(Notification {
+                        script: script.name.clone(),
+                        message,
+                        priority,
+                    }),
+                }
+            }
+        }
+        outcome
+    }
+}
+
+pub fn validate(host: &ScriptHost, scripts: &[Script]) -> Result<(), String> {
+    for (index, script) in scripts.iter().enumerate() {
+        if script.name.trim().is_empty() {
+            return Err("Scripts need a name".to_string());
+        }
+        if scripts[..index].iter().any(|s| s.name == script.name) {
+            return Err(format!("Duplicate script name '{}'", script.name));
+        }
+        host.check(&script.source).map_err(|e| format!("Script '{}': {}", script.name, e))?;
+    }
+    Ok(())
+}
+
+async fn send_notifications(state: &AppState, camera_id: Option<&str>, notifications: Vec<Notification>) {
+    for notification in notifications {
+        let payload = serde_json::json!({
+            "kind": "script",
+            "priority": notification.priority,
+            "script": notification.script,
+        });
+        state.bus.alert(camera_id, "scripts", &notification.message, payload, None).await;
+        println!("📜 {} ({})", notification.message, notification.script);
+    }
+}
+
+// Adds script annotations to the detection and sets DetectionData::escalate when a script asks for analysis
+pub async fn on_detection(state: &AppState, camera_id: Option<&str>, detection: &mut DetectionData) {
+    let scripts = settings::current().scripts;
+    if scripts.is_empty() {
+        return;
+    }
+    let value = match rhai::serde::to_dynamic(&*detection) {
+        Ok(value) => value,
+        Err(e) => {
+            eprintln!("Failed to pass detection to scripts: {}", e);
+            return;
+        }
+    };
+    let outcome = state.scripts.lock().await.run(&scripts, "on_detection", &value, camera_id);
+    detection.annotations.extend(outcome.annotations);
+    if detection.escalate.is_none() {
+        detection.escalate = outcome.escalate;
+    }
+    send_notifications(state, camera_id, outcome.notifications).await;
+}
+
+// Annotations go into the stored payload; escalating a finished analysis raises a high-priority alert
+pub async fn on_analysis(
+    state: &AppState,
+    camera_id: Option<&str>,
+    provider: &str,
+    description: &str,
+    payload: &mut Option<serde_json::Value>,
+) {
+    let scripts = settings::current().scripts;
+    if scripts.is_empty() {
+        return;
+    }
+    let analysis = serde_json::json!({
+        "provider": provider,
+        "description": description,
+        "payload": payload,
+    });
+    let value = match rhai::serde::to_dynamic(&analysis) {
+        Ok(value) => value,
+        Err(e) => {
+            eprintln!("Failed to pass analysis to scripts: {}", e);
+            return;
+        }
+    };
+    let mut outcome = state.scripts.lock().await.run(&scripts, "on_analysis", &value, camera_id);
+
+    if !outcome.annotations.is_empty() {
+        let annotations = serde_json::json!(outcome.annotations);
+        match payload {
+            Some(serde_json::Value::Object(map)) => {
+                map.insert("annotations".to_string(), annotations);
+            }
+            Some(_) => eprintln!("Script annotations dropped: analysis payload is not an object"),
+            None => *payload = Some(serde_json::json!({ "annotations": annotations })),
+        }
+    }
+    if let Some(reason) = outcome.escalate.take() {
+        let (script, message) = reason.split_once(": ").unwrap_or(("script", reason.as_str()));
+        outcome.notifications.push(Notification {
+            script: script.to_string(),
+            message: message.to_string(),
+            priority: "high".to_string(),
+        });
+    }
+    send_notifications(state, camera_id, outcome.notifications).await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scripts_escalate_annotate_and_notify() {
+        let mut host = ScriptHost::default();
+        let queue = Script {
+            name: "queue".to_string(),
+            source: r#"
+                fn on_detection(detection, camera) {
+                    let people = detection.person_count;
+                    annotate("queue_length", people);
+                    if people > 3 && camera == "tills" {
+                        escalate("long queue");
+                        notify(`${people} people waiting`, "high");
+                    }
+                }
+            "#
+            .to_string(),
+            camera_id: None,
+            enabled: true,
+        };
+        let runaway = Script {
+            name: "runaway".to_string(),
+            source: "fn on_detection(d) { annotate(\"lost\", true); loop {} }".to_string(),
+            camera_id: None,
+            enabled: true,
+        };
+        let scripts = vec![queue, runaway];
+        assert!(validate(&host, &scripts).is_ok());
+
+        let detection = serde_json::json!({"person_count": 5, "object_counts": {}, "boxes": []});
+        let value = rhai::serde::to_dynamic(&detection).unwrap();
+        let outcome = host.run(&scripts, "on_detection", &value, Some("tills"));
+        assert_eq!(outcome.escalate.as_deref(), Some("queue: long queue"));
+        assert_eq!(outcome.annotations, BTreeMap::from([("queue_length".to_string(), serde_json::json!(5))]));
+        assert_eq!(outcome.notifications[0].message, "5 people waiting");
+        // The runaway loop hits the operation limit and its actions are discarded
+        assert_eq!(outcome.errors.len(), 1);
+
+        let quiet = host.run(&scripts[..1], "on_detection", &value, Some("entrance"));
+        assert!(quiet.escalate.is_none() && quiet.notifications.is_empty());
+        assert!(host.check("let x = 1;").is_err());
+        assert!(host.check("fn on_detection(").is_err());
+    }
+}
@@ -0,0 +1,86 @@
+// Semantic Search Module - Caption embeddings for meaning-based event retrieval
+// Descriptions are embedded with a local Ollama model and ranked by cosine similarity
+
+use crate::event_store::StoredEvent;
+use serde::{Deserialize, Serialize};
+
+// Small, fast embedding model served by Ollama
+pub const EMBEDDING_MODEL: &str = "nomic-embed-text";
+
+// Results below this similarity are usually unrelated
+pub const DEFAULT_MIN_SCORE: f32 = 0.35;
+
+// A search result with its similarity to the query
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SearchHit {
+    pub event: StoredEvent,
+    pub score: f32,
+}
+
+// Embeddings are stored as little-endian f32 blobs
+pub fn vector_to_blob(vector: &[f32]) -> Vec<u8> {
+    vector.iter().flat_map(|v| v.to_le_bytes()).collect()
+}
+
+pub fn blob_to_vector(blob: &[u8]) -> Vec<f32> {
+    blob.chunks_exact(4)
+        .map(|chunk| f32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]))
+        .collect()
+}
+
+pub fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
+    if a.len() != b.len() || a.is_empty() {
+        return 0.0;
+    }
+
+    let dot: f32 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+    let norm_a: f32 = a.iter().map(|x| x * x).sum::<f32>().sqrt();
+    let norm_b: f32 = b.iter().map(|x| x * x).sum::<f32>().sqrt();
+
+    if norm_a == 0.0 || norm_b == 0.0 {
+        0.0
+    } else {
+        dot / (norm_a * norm_b)
+    }
+}
+
+// Rank stored vectors against the query, best matches first
+pub fn rank(query: &[f32], candidates: Vec<(String, Vec<f32>)>, limit: usize, min_score: f32) -> Vec<(String, f32)> {
+    let mut scored: Vec<(String, f32)> = candidates
+        .into_iter()
+        .map(|(id, vector)| {
+            let score = cosine_similarity(query, &vector);
+            (id, score)
+        })
+        .filter(|(_, score)| *score >= min_score)
+        .collect();
+
+    scored.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+    scored.truncate(limit);
+    scored
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_blob_round_trip() {
+        let vector = vec![0.5, -1.25, 3.0];
+        assert_eq!(blob_to_vector(&vector_to_blob(&vector)), vector);
+    }
+
+    #[test]
+    fn test_ranking_orders_by_similarity() {
+        let query = vec![1.0, 0.0];
+        let candidates = vec![
+            ("orthogonal".to_string(), vec![0.0, 1.0]),
+            ("close".to_string(), vec![0.9, 0.1]),
+            ("exact".to_string(), vec![2.0, 0.0]),
+        ];
+
+        let ranked = rank(&query, candidates, 10, 0.5);
+        let ids: Vec<&str> = ranked.iter().map(|(id, _)| id.as_str()).collect();
+        assert_eq!(ids, vec!["exact", "close"]);
+    }
+}
@@ -0,0 +1,428 @@
+// Settings Module - Persisted preferences for the local Ollama backend
+// Loaded once at startup; static Ollama calls read the current values without holding app state
+
+use crate::anpr::AnprSettings;
+use crate::config::ConfigProfile;
+use crate::dewarp::FisheyeCalibration;
+use crate::enhance::LowLightSettings;
+use crate::floor::FloorCalibration;
+use crate::fire::FireSettings;
+use crate::frigate_mqtt::MqttSettings;
+use crate::hub::RemoteInstance;
+use crate::incidents::IncidentSettings;
+use crate::object_sync::ObjectStorageSettings;
+use crate::pacing::PacingSettings;
+use crate::power::PowerPolicy;
+use crate::plugins::PluginConfig;
+use crate::ppe::PpeSettings;
+use crate::privacy::{PrivacyMask, RedactionPolicy};
+use crate::reid::ReidSettings;
+use crate::rules::Rule;
+use crate::scene_prompts::PromptHistory;
+use crate::scripting::Script;
+use crate::slo::SloSettings;
+use crate::retention::RetentionSettings;
+use crate::encryption::EncryptionSettings;
+use crate::network::NetworkSettings;
+use crate::api_keys::ApiKey;
+use crate::smoothing::SmoothingSettings;
+use crate::staff::StaffSettings;
+use crate::tts::TtsSettings;
+use crate::chime::ChimeSettings;
+use crate::desktop_notify::DesktopNotificationSettings;
+use crate::push::PushSettings;
+use crate::suppression::SuppressionSettings;
+use crate::preview::PreviewSettings;
+use crate::verification::VerificationPolicy;
+use crate::zones::{self, Zone};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+
+pub const DEFAULT_VISION_MODEL: &str = "llava:7b";
+
+// Model families that accept images through Ollama; any tag of these is allowed
+pub const VISION_MODEL_FAMILIES: [&str; 5] = ["llava", "llava-phi3", "llava-llama3", "bakllava", "llama3.2-vision"];
+
+// Suggested variants for the model picker, including quantized builds
+pub const VISION_MODEL_VARIANTS: [&str; 9] = [
+    "llava:7b",
+    "llava:13b",
+    "llava:34b",
+    "llava-phi3",
+    "llama3.2-vision",
+    "llava:7b-v1.6-mistral-q4_0",
+    "llava:7b-v1.6-mistral-q8_0",
+    "llava:13b-v1.6-vicuna-q4_K_M",
+    "llama3.2-vision:11b-instruct-q4_K_M",
+];
+
+// Ollama thread and context sizes, usually applied from detect_hardware
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct OllamaTuning {
+    pub num_thread: u32,
+    pub num_ctx: u32,
+}
+
+impl Default for OllamaTuning {
+    fn default() -> Self {
+        OllamaTuning {
+            num_thread: 4,
+            num_ctx: 2048,
+        }
+    }
+}
+
+pub const DEFAULT_PROFILE: &str = "balanced";
+pub const DEFAULT_IDLE_UNLOAD_MINUTES: u32 = 15;
+
+// Named set of Ollama generation options; unset num_ctx/num_thread use the hardware tuning
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct InferenceProfile {
+    pub temperature: f32,
+    pub num_predict: u32,
+    pub num_ctx: Option<u32>,
+    pub num_thread: Option<u32>,
+    pub keep_alive: String,
+    // Same seed, sampling options and model give the same answer for the same frame and prompt
+    #[serde(default)]
+    pub seed: Option<i64>,
+    #[serde(default)]
+    pub top_k: Option<u32>,
+    #[serde(default)]
+    pub top_p: Option<f32>,
+    #[serde(default)]
+    pub repeat_penalty: Option<f32>,
+}
+
+impl InferenceProfile {
+    pub fn validate(&self) -> Result<(), String> {
+        if !(0.0..=2.0).contains(&self.temperature) {
+            return Err("temperature must be between 0 and 2".to_string());
+        }
+        if self.num_predict == 0 {
+            return Err("num_predict must be at least 1".to_string());
+        }
+        if self.top_k == Some(0) {
+            return Err("top_k must be at least 1".to_string());
+        }
+        if self.top_p.is_some_and(|p| !(0.0..=1.0).contains(&p)) {
+            return Err("top_p must be between 0 and 1".to_string());
+        }
+        if self.repeat_penalty.is_some_and(|p| p <= 0.0) {
+            return Err("repeat_penalty must be above 0".to_string());
+        }
+        validate_keep_alive(&self.keep_alive)
+    }
+
+    // The "options" object for an Ollama request
+    pub fn options(&self, tuning: &OllamaTuning) -> serde_json::Value {
+        let mut options = serde_json::json!({
+            "temperature": self.temperature,
+            "num_predict": self.num_predict,
+            "num_ctx": self.num_ctx.unwrap_or(tuning.num_ctx),
+            "num_thread": self.num_thread.unwrap_or(tuning.num_thread)
+        });
+        // Unset sampling options keep Ollama's defaults
+        let sampling = [
+            ("seed", self.seed.map(serde_json::Value::from)),
+            ("top_k", self.top_k.map(serde_json::Value::from)),
+            ("top_p", self.top_p.map(serde_json::Value::from)),
+            ("repeat_penalty", self.repeat_penalty.map(serde_json::Value::from)),
+        ];
+        for (name, value) in sampling {
+            if let Some(value) = value {
+                options[name] = value;
+            }
+        }
+        options
+    }
+}
+
+pub fn default_profiles() -> BTreeMap<String, InferenceProfile> {
+    let profile = |temperature, num_predict, num_ctx, keep_alive: &str| InferenceProfile {
+        temperature,
+        num_predict,
+        num_ctx,
+        num_thread: None,
+        keep_alive: keep_alive.to_string(),
+        seed: None,
+        top_k: None,
+        top_p: None,
+        repeat_penalty: None,
+    };
+    BTreeMap::from([
+        ("fast".to_string(), profile(0.2, 100, None, "10m")),
+        ("balanced".to_string(), profile(0.3, 200, None, "5m")),
+        ("quality".to_string(), profile(0.3, 400, Some(4096), "5m")),
+    ])
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct AppSettings {
+    pub vision_model: String,
+    pub tuning: OllamaTuning,
+    pub active_profile: String,
+    pub profiles: BTreeMap<String, InferenceProfile>,
+    pub idle_unload_minutes: Option<u32>,  // None keeps the model loaded while Ollama allows it
+    pub redaction_policies: BTreeMap<String, RedactionPolicy>,  // Providers not listed send frames raw
+    pub privacy_masks: Vec<PrivacyMask>,
+    pub zones: Vec<Zone>,  // Named areas with class filters for live detections; rules and PPE refer to them by name
+    pub local_only: bool,  // Block every outbound request except localhost
+    pub network: NetworkSettings,  // Proxy and extra CA certificates
+    pub mock_mode: bool,  // Canned results instead of real models; read at startup
+    pub mock_fixtures: Option<String>,
+    pub api_key: Option<String>,  // REST API key, generated when the server first starts; always admin
+    pub api_keys: Vec<ApiKey>,  // Additional keys with narrower roles
+    pub mqtt: MqttSettings,
+    pub object_storage: ObjectStorageSettings,
+    pub remote_instances: Vec<RemoteInstance>,  // Hub mode: other analyzers whose events are pulled here
+    pub smoothing: SmoothingSettings,
+    pub reid: ReidSettings,
+    pub staff: StaffSettings,
+    pub ppe: PpeSettings,
+    pub fire: FireSettings,
+    pub anpr: AnprSettings,
+    pub scene_prompts: PromptHistory,  // Edited retail scene prompts; unedited scenes use the built-ins
+    pub incidents: IncidentSettings,
+    pub tts: TtsSettings,  // Spoken announcements for chosen rules
+    pub desktop_notifications: DesktopNotificationSettings,
+    pub chime: ChimeSettings,
+    pub push: PushSettings,  // ntfy topics and FCM apps that get alerts on phones
+    pub verification: VerificationPolicy,  // Second look before VLM-raised high-severity alerts
+    pub suppression: SuppressionSettings,  // What operators' false-positive dismissals have taught
+    pub preview: PreviewSettings,  // Animated GIFs of the frames before each alert
+    pub low_light: LowLightSettings,
+    pub floor_calibrations: BTreeMap<String, FloorCalibration>,  // Camera id -> image-to-floor calibration
+    pub fisheye: BTreeMap<String, FisheyeCalibration>,  // Camera id ("" for frames sent without one) -> de-warp calibration
+    pub rules: Vec<Rule>,
+    pub scripts: Vec<Script>,
+    pub plugins: Vec<PluginConfig>,
+    pub pacing: PacingSettings,
+    pub power: PowerPolicy,
+    pub slo: SloSettings,
+    pub retention: RetentionSettings,
+    pub encryption: EncryptionSettings,
+    pub default_provider: String,  // Analyses that don't name a provider, unless an SLO fallback is active
+    pub setup_completed_at: Option<String>,  // Set by the first-run wizard
+    pub config_profiles: BTreeMap<String, ConfigProfile>,
+    pub active_config: Option<String>,  // Configuration profile last switched to
+}
+
+impl Default for AppSettings {
+    fn default() -> Self {
+        AppSettings {
+            vision_model: DEFAULT_VISION_MODEL.to_string(),
+            tuning: OllamaTuning::default(),
+            active_profile: DEFAULT_PROFILE.to_string(),
+            profiles: default_profiles(),
+            idle_unload_minutes: Some(DEFAULT_IDLE_UNLOAD_MINUTES),
+            redaction_policies: BTreeMap::new(),
+            privacy_masks: Vec::new(),
+            zones: Vec::new(),
+            local_only: false,
+            network: NetworkSettings::default(),
+            mock_mode: false,
+            mock_fixtures: None,
+            api_key: None,
+            api_keys: Vec::new(),
+            mqtt: MqttSettings::default(),
+            object_storage: ObjectStorageSettings::default(),
+            remote_instances: Vec::new(),
+            smoothing: SmoothingSettings::default(),
+            reid: ReidSettings::default(),
+            staff: StaffSettings::default(),
+            ppe: PpeSettings::default(),
+            fire: FireSettings::default(),
+            anpr: AnprSettings::default(),
+            scene_prompts: PromptHistory::new(),
+            incidents: IncidentSettings::default(),
+            tts: TtsSettings::default(),
+            desktop_notifications: DesktopNotificationSettings::default(),
+            chime: ChimeSettings::default(),
+            push: PushSettings::default(),
+            verification: VerificationPolicy::default(),
+            suppression: SuppressionSettings::default(),
+            preview: PreviewSettings::default(),
+            low_light: LowLightSettings::default(),
+            fisheye: BTreeMap::new(),
+            floor_calibrations: BTreeMap::new(),
+            rules: Vec::new(),
+            scripts: Vec::new(),
+            plugins: Vec::new(),
+            pacing: PacingSettings::default(),
+            power: PowerPolicy::default(),
+            slo: SloSettings::default(),
+            retention: RetentionSettings::default(),
+            encryption: EncryptionSettings::default(),
+            default_provider: "llava".to_string(),
+            setup_completed_at: None,
+            config_profiles: BTreeMap::new(),
+            active_config: None,
+        }
+    }
+}
+
+static CURRENT: RwLock<Option<AppSettings>> = RwLock::new(None);
+
+impl AppSettings {
+    pub fn default_path() -> PathBuf {
+        let home_dir = std::env::var("HOME").unwrap_or_else(|_| "/tmp".to_string());
+        PathBuf::from(home_dir).join(".live-vision-analyzer").join("settings.json")
+    }
+
+    // Missing or unreadable files fall back to defaults
+    pub fn load(path: &Path) -> Self {
+        let Ok(contents) = std::fs::read_to_string(path) else {
+            return AppSettings::default();
+        };
+        let parsed = serde_json::from_str(&contents).and_then(|mut value| {
+            zones::adopt_legacy_areas(&mut value);
+            serde_json::from_value(value)
+        });
+        parsed.unwrap_or_else(|e| {
+            eprintln!("Failed to parse {}: {}", path.display(), e);
+            AppSettings::default()
+        })
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent).map_err(|e| format!("Failed to create config directory: {}", e))?;
+        }
+        let contents = serde_json::to_string_pretty(self).map_err(|e| e.to_string())?;
+        // Write beside the file and rename over it, so a crash never leaves half a settings file
+        let staging = path.with_extension("json.tmp");
+        std::fs::write(&staging, contents).map_err(|e| format!("Failed to save settings: {}", e))?;
+        std::fs::rename(&staging, path).map_err(|e| format!("Failed to save settings: {}", e))
+    }
+
+    // The named profile, or the active one when no name is given
+    pub fn profile(&self, name: Option<&str>) -> Result<&InferenceProfile, String> {
+        let name = name.unwrap_or(&self.active_profile);
+        self.profiles
+            .get(name)
+            .ok_or_else(|| format!("Unknown inference profile: {}", name))
+    }
+
+    pub fn redaction_policy(&self, provider: &str) -> RedactionPolicy {
+        self.redaction_policies.get(provider).copied().unwrap_or_default()
+    }
+}
+
+// Settings in effect for this process
+pub fn current() -> AppSettings {
+    CURRENT.read().ok().and_then(|s| s.clone()).unwrap_or_default()
+}
+
+pub fn init(settings: AppSettings) {
+    if let Ok(mut current) = CURRENT.write() {
+        *current = Some(settings);
+    }
+}
+
+// Apply a change, persist it and return the new settings
+pub fn update(change: impl FnOnce(&mut AppSettings)) -> Result<AppSettings, String> {
+    let mut settings = current();
+    change(&mut settings);
+    settings.save(&AppSettings::default_path())?;
+    init(settings.clone());
+    Ok(settings)
+}
+
+// Ollama durations: seconds ("300"), "-1" for forever, or a number with an s/m/h unit
+pub fn validate_keep_alive(duration: &str) -> Result<(), String> {
+    let duration = duration.trim();
+    let digits = duration.trim_end_matches(['s', 'm', 'h']);
+    let valid = duration == "-1"
+        || (!digits.is_empty() && digits.chars().all(|c| c.is_ascii_digit()) && duration.len() - digits.len() <= 1);
+    if valid {
+        Ok(())
+    } else {
+        Err(format!("Invalid keep_alive '{}' - use a duration like \"5m\", \"1h\", \"0\" or \"-1\"", duration))
+    }
+}
+
+// Ollama treats an untagged name as ":latest"
+pub fn normalize_model_tag(model: &str) -> String {
+    if model.contains(':') {
+        model.to_string()
+    } else {
+        format!("{}:latest", model)
+    }
+}
+
+pub fn validate_vision_model(model: &str) -> Result<String, String> {
+    let model = model.trim();
+    let family = model.split(':').next().unwrap_or("");
+    if model.is_empty() || !VISION_MODEL_FAMILIES.contains(&family) {
+        return Err(format!(
+            "'{}' is not a supported vision model (families: {})",
+            model,
+            VISION_MODEL_FAMILIES.join(", ")
+        ));
+    }
+    Ok(model.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_vision_model_validation() {
+        assert!(validate_vision_model("llava:13b").is_ok());
+        assert!(validate_vision_model("llava:7b-v1.6-mistral-q4_0").is_ok());
+        assert!(validate_vision_model("llava-phi3").is_ok());
+        assert!(validate_vision_model("llama3.2").is_err());
+        assert!(validate_vision_model("").is_err());
+        assert_eq!(normalize_model_tag("llava-phi3"), "llava-phi3:latest");
+
+        assert!(validate_keep_alive("10m").is_ok());
+        assert!(validate_keep_alive("-1").is_ok());
+        assert!(validate_keep_alive("0").is_ok());
+        assert!(validate_keep_alive("5 minutes").is_err());
+        assert!(validate_keep_alive("m").is_err());
+    }
+
+    #[test]
+    fn test_settings_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("settings.json");
+        assert_eq!(AppSettings::load(&path).vision_model, DEFAULT_VISION_MODEL);
+
+        let mut settings = AppSettings { vision_model: "llava:13b".to_string(), ..Default::default() };
+        settings.profiles.get_mut("fast").unwrap().num_thread = Some(2);
+        settings.save(&path).unwrap();
+        assert!(!path.with_extension("json.tmp").exists());
+
+        let loaded = AppSettings::load(&path);
+        assert_eq!(loaded.vision_model, "llava:13b");
+        let fast = loaded.profile(Some("fast")).unwrap();
+        assert_eq!(fast.options(&loaded.tuning)["num_thread"], 2);
+        assert_eq!(loaded.profile(None).unwrap().num_predict, 200);
+        assert!(loaded.profile(Some("turbo")).is_err());
+    }
+
+    #[test]
+    fn test_sampling_options() {
+        let mut profile = default_profiles().remove("balanced").unwrap();
+        let tuning = OllamaTuning::default();
+        assert!(profile.options(&tuning).get("seed").is_none());
+
+        profile.seed = Some(42);
+        profile.top_k = Some(20);
+        let options = profile.options(&tuning);
+        assert_eq!((options["seed"].as_i64(), options["top_k"].as_u64()), (Some(42), Some(20)));
+        assert!(options.get("top_p").is_none());
+        assert!(profile.validate().is_ok());
+
+        profile.top_p = Some(1.5);
+        assert!(profile.validate().is_err());
+        // Profiles saved before these options existed still load
+        let old = r#"{"temperature": 0.3, "num_predict": 200, "num_ctx": null, "num_thread": null, "keep_alive": "5m"}"#;
+        assert!(serde_json::from_str::<InferenceProfile>(old).unwrap().seed.is_none());
+    }
+}
@@ -0,0 +1,240 @@
+// Setup Module - Backend for the first-run wizard
+// Sizes the downloads for this machine, runs a sample frame end to end and saves every choice in one settings write
+
+use crate::hardware::{self, HardwareInfo, HardwareRecommendation};
+use crate::moondream_manager::MoondreamManager;
+use crate::settings::{self, AppSettings, OllamaTuning};
+use crate::vision_provider::ProviderRegistry;
+use crate::yolo_detector::YoloDetector;
+use crate::{frame_processor, pipeline, semantic_search, storage};
+use image::{DynamicImage, RgbImage};
+use serde::{Deserialize, Serialize};
+use tokio::sync::RwLock;
+
+// Always selectable; configured endpoints are added from the provider registry
+const BUILTIN_PROVIDERS: [&str; 2] = ["llava", "moondream"];
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ModelOption {
+    pub model: String,
+    pub size_mb: u64,
+    pub installed: bool,
+    pub fits: bool,  // Fits in the memory the model would run from
+    pub recommended: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Download {
+    pub name: String,
+    pub size_mb: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SetupPlan {
+    pub hardware: HardwareInfo,
+    pub recommendation: HardwareRecommendation,
+    pub models: Vec<ModelOption>,
+    pub downloads: Vec<Download>,  // Still needed for the planned model
+    pub download_mb: u64,
+    pub free_mb: Option<u64>,
+    pub providers: Vec<String>,
+    pub completed_at: Option<String>,
+}
+
+// What the machine already has, gathered before planning
+pub struct Installed {
+    pub ollama: bool,
+    pub models: Vec<String>,
+}
+
+impl Installed {
+    fn has_model(&self, model: &str) -> bool {
+        let wanted = settings::normalize_model_tag(model);
+        self.models.iter().any(|m| settings::normalize_model_tag(m) == wanted)
+    }
+}
+
+pub fn plan(hardware: HardwareInfo, model: Option<&str>, installed: &Installed, free_mb: Option<u64>, providers: Vec<String>) -> SetupPlan {
+    let recommendation = hardware::recommend(&hardware);
+    let memory_mb = hardware::model_memory_mb(&hardware);
+    let models = settings::VISION_MODEL_VARIANTS
+        .iter()
+        .map(|m| ModelOption {
+            model: m.to_string(),
+            size_mb: storage::estimated_model_mb(m),
+            installed: installed.has_model(m),
+            fits: storage::estimated_model_mb(m) <= memory_mb,
+            recommended: *m == recommendation.vision_model,
+        })
+        .collect();
+
+    let model = model.unwrap_or(&recommendation.vision_model);
+    let mut downloads = Vec::new();
+    if !installed.ollama {
+        downloads.push(Download { name: "ollama".to_string(), size_mb: storage::OLLAMA_DOWNLOAD_MB });
+    }
+    for name in [model, semantic_search::EMBEDDING_MODEL] {
+        if !installed.has_model(name) {
+            downloads.push(Download { name: name.to_string(), size_mb: storage::estimated_model_mb(name) });
+        }
+    }
+
+    SetupPlan {
+        download_mb: downloads.iter().map(|d| d.size_mb).sum(),
+        hardware,
+        recommendation,
+        models,
+        downloads,
+        free_mb,
+        providers,
+        completed_at: settings::current().setup_completed_at,
+    }
+}
+
+pub fn provider_names(registry: &ProviderRegistry) -> Vec<String> {
+    let mut names: Vec<String> = BUILTIN_PROVIDERS.iter().map(|p| p.to_string()).collect();
+    for info in registry.list() {
+        if !names.contains(&info.name) {
+            names.push(info.name);
+        }
+    }
+    names
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SetupChoices {
+    pub vision_model: String,
+    pub tuning: Option<OllamaTuning>,  // None keeps the current tuning
+    pub active_profile: Option<String>,
+    pub default_provider: String,
+    pub local_only: Option<bool>,
+}
+
+impl SetupChoices {
+    // Checked as a whole before anything is written
+    pub fn apply(&self, settings: &mut AppSettings, providers: &[String], now: &str) -> Result<(), String> {
+        let vision_model = settings::validate_vision_model(&self.vision_model)?;
+        if !providers.contains(&self.default_provider) {
+            return Err(format!("Unknown provider: {}", self.default_provider));
+        }
+        if let Some(tuning) = &self.tuning {
+            if tuning.num_thread == 0 || tuning.num_ctx < 512 {
+                return Err("Tuning needs at least 1 thread and a 512 token context".to_string());
+            }
+        }
+        if let Some(profile) = &self.active_profile {
+            settings.profile(Some(profile))?;
+        }
+        if self.local_only == Some(false) && crate::local_only::forced_by_env() {
+            return Err("Local-only mode is enforced by LVA_LOCAL_ONLY and can't be turned off here".to_string());
+        }
+
+        settings.vision_model = vision_model;
+        if let Some(tuning) = &self.tuning {
+            settings.tuning = tuning.clone();
+        }
+        if let Some(profile) = &self.active_profile {
+            settings.active_profile = profile.clone();
+        }
+        settings.default_provider = self.default_provider.clone();
+        if let Some(local_only) = self.local_only {
+            settings.local_only = local_only;
+        }
+        settings.setup_completed_at = Some(now.to_string());
+        Ok(())
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct SetupTest {
+    pub provider: String,
+    pub detection_ok: bool,
+    pub detection_ms: u64,
+    pub objects: usize,
+    pub analysis_ok: bool,
+    pub analysis_ms: u64,
+    pub description: Option<String>,
+    pub errors: Vec<String>,
+}
+
+// Plain gradient, for when the wizard runs before a camera is connected
+pub fn sample_frame() -> Result<String, String> {
+    let image = RgbImage::from_fn(320, 240, |x, y| image::Rgb([(x * 255 / 320) as u8, (y * 255 / 240) as u8, 128]));
+    frame_processor::encode_frame(&DynamicImage::ImageRgb8(image))
+}
+
+// Detection and one VLM call on the frame; nothing is stored or published
+pub async fn test_frame(
+    yolo: &YoloDetector,
+    moondream: &MoondreamManager,
+    providers: &RwLock<ProviderRegistry>,
+    provider: &str,
+    frame: String,
+) -> SetupTest {
+    let mut test = SetupTest { provider: provider.to_string(), ..Default::default() };
+
+    let start = std::time::Instant::now();
+    match yolo.detect(&frame).await {
+        Ok(detection) => {
+            test.detection_ok = true;
+            test.objects = detection.boxes.len();
+        }
+        Err(e) => test.errors.push(format!("Detection: {}", e)),
+    }
+    test.detection_ms = start.elapsed().as_millis() as u64;
+
+    let start = std::time::Instant::now();
+    let prompt = "Describe this image in one sentence.";
+    match pipeline::describe_frame(moondream, providers, provider, prompt, frame).await {
+        Ok(result) => {
+            test.analysis_ok = true;
+            test.description = result.map(|(description, _)| description);
+        }
+        Err(e) => test.errors.push(format!("{}: {}", provider, e)),
+    }
+    test.analysis_ms = start.elapsed().as_millis() as u64;
+    test
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_plan_and_choices() {
+        let hardware = HardwareInfo {
+            os: "linux".to_string(),
+            arch: "x86_64".to_string(),
+            cpu_cores: 8,
+            total_memory_mb: 16_000,
+            apple_silicon: false,
+            gpus: Vec::new(),
+        };
+        let installed = Installed { ollama: true, models: vec!["llava:7b".to_string()] };
+        let providers = vec!["llava".to_string(), "moondream".to_string()];
+        let plan = plan(hardware, None, &installed, Some(20_000), providers.clone());
+        assert_eq!(plan.recommendation.vision_model, "llava:7b");
+        assert_eq!(plan.downloads, vec![Download { name: semantic_search::EMBEDDING_MODEL.to_string(), size_mb: 3000 }]);
+        assert!(plan.models.iter().any(|m| m.model == "llava:7b" && m.installed && m.recommended));
+        assert!(plan.models.iter().any(|m| m.model == "llava:34b" && !m.fits));
+
+        let mut settings = AppSettings::default();
+        let choices = SetupChoices {
+            vision_model: "llava:13b".to_string(),
+            tuning: None,
+            active_profile: Some("fast".to_string()),
+            default_provider: "moondream".to_string(),
+            local_only: None,
+        };
+        choices.apply(&mut settings, &providers, "2026-01-01T00:00:00.000Z").unwrap();
+        assert_eq!((settings.vision_model.as_str(), settings.default_provider.as_str()), ("llava:13b", "moondream"));
+        assert!(settings.setup_completed_at.is_some());
+
+        // A bad choice leaves the settings untouched
+        let mut untouched = AppSettings::default();
+        let bad = SetupChoices { default_provider: "gpt-4o".to_string(), ..choices };
+        assert!(bad.apply(&mut untouched, &providers, "now").is_err());
+        assert_eq!(untouched.vision_model, AppSettings::default().vision_model);
+        assert!(sample_frame().is_ok());
+    }
+}
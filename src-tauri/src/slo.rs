@@ -0,0 +1,305 @@
+// SLO Module - Rolling p95 latency per pipeline stage against configured objectives
+// A stage over its SLO for long enough raises a degradation alert and can switch analyses to a faster provider/profile
+
+use crate::bus::{BusEvent, EventBus};
+use crate::event_store::format_timestamp;
+use crate::settings;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::Mutex;
+
+// Stale stages are re-checked without traffic, so a switched-away provider eventually gets retried
+const CHECK_INTERVAL: Duration = Duration::from_secs(30);
+
+static FALLBACK: RwLock<Option<Fallback>> = RwLock::new(None);
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct SloSettings {
+    pub enabled: bool,
+    pub window_secs: u64,  // Rolling window the p95 is taken over
+    pub sustain_secs: u64,  // How long p95 must stay over the SLO before it counts as degraded
+    pub min_samples: usize,
+    pub vlm_p95_ms: u64,
+    pub detection_p95_ms: u64,
+    pub auto_switch: bool,
+    pub fallback_provider: Option<String>,  // Used by analyses that don't name a provider
+    pub fallback_profile: Option<String>,  // LLaVA profile while degraded
+}
+
+impl Default for SloSettings {
+    fn default() -> Self {
+        SloSettings {
+            enabled: true,
+            window_secs: 300,
+            sustain_secs: 180,
+            min_samples: 5,
+            vlm_p95_ms: 15_000,
+            detection_p95_ms: 500,
+            auto_switch: false,
+            fallback_provider: None,
+            fallback_profile: Some("fast".to_string()),
+        }
+    }
+}
+
+impl SloSettings {
+    pub fn validate(&self, profiles: &BTreeMap<String, settings::InferenceProfile>) -> Result<(), String> {
+        if !(30..=3600).contains(&self.window_secs) {
+            return Err("window_secs must be between 30 and 3600".to_string());
+        }
+        if self.sustain_secs > 3600 {
+            return Err("sustain_secs must be at most 3600".to_string());
+        }
+        if self.min_samples == 0 {
+            return Err("min_samples must be at least 1".to_string());
+        }
+        if self.vlm_p95_ms == 0 || self.detection_p95_ms == 0 {
+            return Err("SLOs must be greater than 0 ms".to_string());
+        }
+        if let Some(profile) = self.fallback_profile.as_deref().filter(|p| !profiles.contains_key(*p)) {
+            return Err(format!("Unknown inference profile: {}", profile));
+        }
+        Ok(())
+    }
+
+    fn slo_ms(&self, stage: &str) -> u64 {
+        if stage.starts_with("vlm:") {
+            self.vlm_p95_ms
+        } else {
+            self.detection_p95_ms
+        }
+    }
+}
+
+// What analyses are using instead while a VLM stage is degraded
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Fallback {
+    pub stage: String,
+    pub provider: Option<String>,
+    pub profile: Option<String>,
+    pub since: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct StageSlo {
+    pub stage: String,
+    pub samples: usize,
+    pub p95_ms: Option<f64>,  // None until min_samples are in the window
+    pub slo_ms: u64,
+    pub breached_secs: Option<u64>,  // How long p95 has been over the SLO
+    pub degraded: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct SloStatus {
+    pub stages: Vec<StageSlo>,
+    pub fallback: Option<Fallback>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Change {
+    Degraded { stage: String, p95_ms: f64, slo_ms: u64 },
+    Recovered { stage: String },
+}
+
+#[derive(Default)]
+struct Stage {
+    samples: VecDeque<(Instant, f64)>,
+    breached_since: Option<Instant>,
+    degraded: bool,
+}
+
+impl Stage {
+    // Nearest-rank p95 of the window
+    fn p95(&self, min_samples: usize) -> Option<f64> {
+        if self.samples.len() < min_samples {
+            return None;
+        }
+        let mut latencies: Vec<f64> = self.samples.iter().map(|(_, ms)| *ms).collect();
+        latencies.sort_by(f64::total_cmp);
+        let rank = (latencies.len() as f64 * 0.95).ceil() as usize;
+        latencies.get(rank.saturating_sub(1)).copied()
+    }
+}
+
+#[derive(Default)]
+pub struct SloMonitor {
+    stages: BTreeMap<String, Stage>,
+}
+
+impl SloMonitor {
+    pub fn observe(&mut self, stage: &str, latency: Duration, now: Instant) {
+        let stage = self.stages.entry(stage.to_string()).or_default();
+        stage.samples.push_back((now, latency.as_secs_f64() * 1000.0));
+    }
+
+    // Age out old samples and report stages that crossed into or out of degradation
+    pub fn evaluate(&mut self, settings: &SloSettings, now: Instant) -> Vec<Change> {
+        let window = Duration::from_secs(settings.window_secs);
+        let mut changes = Vec::new();
+        for (name, stage) in self.stages.iter_mut() {
+            while stage.samples.front().is_some_and(|(at, _)| now.duration_since(*at) > window) {
+                stage.samples.pop_front();
+            }
+            let slo_ms = settings.slo_ms(name);
+            // Too few samples to judge counts as healthy, so a provider nobody is using any more is retried
+            match stage.p95(settings.min_samples).filter(|p95| *p95 > slo_ms as f64) {
+                Some(p95_ms) => {
+                    let since = *stage.breached_since.get_or_insert(now);
+                    if !stage.degraded && now.duration_since(since) >= Duration::from_secs(settings.sustain_secs) {
+                        stage.degraded = true;
+                        changes.push(Change::Degraded { stage: name.clone(), p95_ms, slo_ms });
+                    }
+                }
+                None => {
+                    stage.breached_since = None;
+                    if stage.degraded {
+                        stage.degraded = false;
+                        changes.push(Change::Recovered { stage: name.clone() });
+                    }
+                }
+            }
+        }
+        changes
+    }
+
+    pub fn status(&self, settings: &SloSettings, now: Instant) -> SloStatus {
+        SloStatus {
+            stages: self
+                .stages
+                .iter()
+                .map(|(name, stage)| StageSlo {
+                    stage: name.clone(),
+                    samples: stage.samples.len(),
+                    p95_ms: stage.p95(settings.min_samples),
+                    slo_ms: settings.slo_ms(name),
+                    breached_secs: stage.breached_since.map(|since| now.duration_since(since).as_secs()),
+                    degraded: stage.degraded,
+                })
+                .collect(),
+            fallback: fallback(),
+        }
+    }
+}
+
+pub fn fallback() -> Option<Fallback> {
+    FALLBACK.read().ok().and_then(|f| f.clone())
+}
+
+// Provider for analyses that don't name one
+pub fn default_provider() -> String {
+    fallback().and_then(|f| f.provider).unwrap_or_else(|| settings::current().default_provider)
+}
+
+pub fn profile_override() -> Option<String> {
+    fallback().and_then(|f| f.profile)
+}
+
+async fn apply(change: &Change, settings: &SloSettings, bus: &EventBus) {
+    match change {
+        Change::Degraded { stage, p95_ms, slo_ms } => {
+            let description = format!("{} p95 latency {:.0} ms is over its {} ms SLO", stage, p95_ms, slo_ms);
+            println!("🐢 {}", description);
+            let payload = serde_json::json!({
+                "kind": "slo",
+                "priority": "high",
+                "stage": stage,
+                "p95_ms": p95_ms,
+                "slo_ms": slo_ms,
+            });
+            bus.alert(None, "slo", &description, payload, None).await;
+
+            let Some(provider) = stage.strip_prefix("vlm:").filter(|_| settings.auto_switch) else {
+                return;
+            };
+            let Ok(mut current) = FALLBACK.write() else {
+                return;
+            };
+            let switch = Fallback {
+                stage: stage.clone(),
+                provider: settings.fallback_provider.clone().filter(|p| p != provider),
+                profile: settings.fallback_profile.clone().filter(|_| provider == "llava"),
+                since: format_timestamp(chrono::Utc::now()),
+            };
+            if current.is_none() && (switch.provider.is_some() || switch.profile.is_some()) {
+                println!("⏩ Switching analyses to provider {:?}, profile {:?}", switch.provider, switch.profile);
+                *current = Some(switch);
+            }
+        }
+        Change::Recovered { stage } => {
+            println!("✅ {} latency is back within its SLO", stage);
+            if let Ok(mut current) = FALLBACK.write() {
+                if current.as_ref().is_some_and(|f| &f.stage == stage) {
+                    *current = None;
+                }
+            }
+        }
+    }
+}
+
+// SLO side of the bus: detection and VLM latencies, re-evaluated on every sample and periodically
+pub async fn follow_bus(monitor: Arc<Mutex<SloMonitor>>, bus: EventBus) {
+    let mut events = bus.subscribe();
+    let mut ticker = tokio::time::interval(CHECK_INTERVAL);
+    loop {
+        let sample = tokio::select! {
+            event = events.recv() => match event {
+                Ok(BusEvent::DetectionReady { latency, live: true, .. }) => Some(("detection".to_string(), latency)),
+                Ok(BusEvent::AnalysisReady { provider, latency, .. }) => Some((format!("vlm:{}", provider), latency)),
+                Ok(_) | Err(RecvError::Lagged(_)) => continue,
+                Err(RecvError::Closed) => break,
+            },
+            _ = ticker.tick() => None,
+        };
+        let settings = settings::current().slo;
+        if !settings.enabled {
+            continue;
+        }
+        let now = Instant::now();
+        let changes = {
+            let mut monitor = monitor.lock().await;
+            if let Some((stage, latency)) = sample {
+                monitor.observe(&stage, latency, now);
+            }
+            monitor.evaluate(&settings, now)
+        };
+        for change in &changes {
+            apply(change, &settings, &bus).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sustained_breach_degrades_then_recovers() {
+        let settings = SloSettings { vlm_p95_ms: 1000, sustain_secs: 60, min_samples: 3, ..Default::default() };
+        let start = Instant::now();
+        let mut monitor = SloMonitor::default();
+        let at = |secs: u64| start + Duration::from_secs(secs);
+
+        for secs in 0..3 {
+            monitor.observe("vlm:llava", Duration::from_millis(800), at(secs));
+        }
+        assert!(monitor.evaluate(&settings, at(3)).is_empty());
+
+        // One slow call in four puts p95 over the SLO, but it has to stay there for a minute
+        monitor.observe("vlm:llava", Duration::from_secs(4), at(10));
+        assert!(monitor.evaluate(&settings, at(10)).is_empty());
+        let changes = monitor.evaluate(&settings, at(70));
+        assert_eq!(changes, vec![Change::Degraded { stage: "vlm:llava".to_string(), p95_ms: 4000.0, slo_ms: 1000 }]);
+        assert!(monitor.evaluate(&settings, at(80)).is_empty());
+        assert!(monitor.status(&settings, at(80)).stages[0].degraded);
+
+        // Once the slow call leaves the window the stage is healthy again
+        let changes = monitor.evaluate(&settings, at(400));
+        assert_eq!(changes, vec![Change::Recovered { stage: "vlm:llava".to_string() }]);
+        assert_eq!(monitor.status(&settings, at(400)).stages[0].samples, 0);
+    }
+}
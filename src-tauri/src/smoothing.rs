@@ -0,0 +1,153 @@
+// Smoothing Module - Temporal filtering of per-frame person counts
+// Raw counts flicker between frames; recorded events and escalation rules follow the smoothed series instead
+
+use crate::settings;
+use crate::yolo_detector::DetectionData;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+
+pub const SMOOTHING_METHODS: [&str; 3] = ["majority", "ema", "off"];
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct SmoothingSettings {
+    pub method: String,
+    pub window: usize,  // Frames in the majority vote
+    pub alpha: f32,     // EMA weight of the newest frame
+}
+
+impl Default for SmoothingSettings {
+    fn default() -> Self {
+        SmoothingSettings {
+            method: "majority".to_string(),
+            window: 5,
+            alpha: 0.3,
+        }
+    }
+}
+
+impl SmoothingSettings {
+    pub fn validate(&self) -> Result<(), String> {
+        if !SMOOTHING_METHODS.contains(&self.method.as_str()) {
+            return Err(format!(
+                "Unknown smoothing method '{}' (use {})",
+                self.method,
+                SMOOTHING_METHODS.join(", ")
+            ));
+        }
+        if !(1..=60).contains(&self.window) {
+            return Err("window must be between 1 and 60 frames".to_string());
+        }
+        if !(self.alpha > 0.0 && self.alpha <= 1.0) {
+            return Err("alpha must be greater than 0 and at most 1".to_string());
+        }
+        Ok(())
+    }
+}
+
+pub struct CountSmoother {
+    settings: SmoothingSettings,
+    history: VecDeque<u32>,
+    ema: Option<f32>,
+    last: Option<u32>,
+}
+
+impl CountSmoother {
+    pub fn new(settings: SmoothingSettings) -> Self {
+        CountSmoother {
+            settings,
+            history: VecDeque::new(),
+            ema: None,
+            last: None,
+        }
+    }
+
+    // Most common count in the window; on a tie the previous output wins, then the newest frame
+    fn majority(&self) -> u32 {
+        let mut votes: HashMap<u32, usize> = HashMap::new();
+        for count in &self.history {
+            *votes.entry(*count).or_insert(0) += 1;
+        }
+        let best = votes.values().copied().max().unwrap_or(0);
+        match self.last.filter(|last| votes.get(last) == Some(&best)) {
+            Some(last) => last,
+            None => self
+                .history
+                .iter()
+                .rev()
+                .find(|count| votes[*count] == best)
+                .copied()
+                .unwrap_or(0),
+        }
+    }
+
+    pub fn push(&mut self, count: u32) -> u32 {
+        self.history.push_back(count);
+        while self.history.len() > self.settings.window {
+            self.history.pop_front();
+        }
+
+        let smoothed = match self.settings.method.as_str() {
+            "majority" => self.majority(),
+            "ema" => {
+                let alpha = self.settings.alpha;
+                let ema = self.ema.map(|prev| alpha * count as f32 + (1.0 - alpha) * prev).unwrap_or(count as f32);
+                self.ema = Some(ema);
+                ema.round() as u32
+            }
+            _ => count,
+        };
+        self.last = Some(smoothed);
+        smoothed
+    }
+
+    pub fn apply(&mut self, detection: &mut DetectionData) {
+        detection.smoothed_person_count = Some(self.push(detection.person_count));
+    }
+}
+
+// One smoother per camera for the live detection paths
+#[derive(Default)]
+pub struct Smoothers {
+    cameras: HashMap<String, CountSmoother>,
+}
+
+impl Smoothers {
+    pub fn apply(&mut self, camera_id: Option<&str>, detection: &mut DetectionData) {
+        let settings = settings::current().smoothing;
+        let smoother = self
+            .cameras
+            .entry(camera_id.unwrap_or("").to_string())
+            .or_insert_with(|| CountSmoother::new(settings.clone()));
+        // Start over when the settings change
+        if smoother.settings != settings {
+            *smoother = CountSmoother::new(settings);
+        }
+        smoother.apply(detection);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(settings: SmoothingSettings, counts: &[u32]) -> Vec<u32> {
+        let mut smoother = CountSmoother::new(settings);
+        counts.iter().map(|c| smoother.push(*c)).collect()
+    }
+
+    #[test]
+    fn test_majority_and_ema_suppress_flicker() {
+        let majority = SmoothingSettings::default();
+        assert_eq!(run(majority.clone(), &[2, 2, 3, 2, 2, 3, 3, 3]), vec![2, 2, 2, 2, 2, 2, 3, 3]);
+
+        let ema = SmoothingSettings { method: "ema".to_string(), alpha: 0.5, ..majority.clone() };
+        assert_eq!(run(ema, &[0, 0, 4, 0, 0]), vec![0, 0, 2, 1, 1]);
+
+        let off = SmoothingSettings { method: "off".to_string(), ..majority.clone() };
+        assert_eq!(run(off, &[1, 3, 1]), vec![1, 3, 1]);
+
+        assert!(SmoothingSettings { window: 0, ..majority.clone() }.validate().is_err());
+        assert!(SmoothingSettings { method: "median".to_string(), ..majority }.validate().is_err());
+    }
+}
@@ -0,0 +1,256 @@
+// Staff Module - Tag people as staff or customer so analytics can leave employees out
+// Uniform colours decide clear cases; an optional VLM looks at the crop when the colours are inconclusive
+
+use crate::bus::BusEvent;
+use crate::frame_processor::{self, RegionOfInterest};
+use crate::yolo_detector::{BoundingBox, DetectionData};
+use crate::{pipeline, settings, AppState};
+use chrono::{DateTime, Utc};
+use image::{DynamicImage, GenericImageView};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+const VERIFY_PROMPT: &str = "Look at the person in this image. Are they a store employee (uniform, name badge, apron, \
+working behind a counter) or a customer? Answer with one word: staff or customer.";
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    Staff,
+    Customer,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct UniformColor {
+    pub name: String,
+    pub rgb: [u8; 3],
+    #[serde(default = "default_tolerance")]
+    pub tolerance: u8,  // Largest per-channel difference that still counts as this colour
+}
+
+fn default_tolerance() -> u8 {
+    40
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct StaffSettings {
+    pub enabled: bool,
+    pub uniform_colors: Vec<UniformColor>,
+    pub min_coverage: f32,  // Share of the torso in a uniform colour that marks someone as staff
+    pub verify_provider: Option<String>,  // VLM asked about inconclusive tracks; None counts them as customers
+}
+
+impl Default for StaffSettings {
+    fn default() -> Self {
+        StaffSettings {
+            enabled: false,
+            uniform_colors: Vec::new(),
+            min_coverage: 0.35,
+            verify_provider: None,
+        }
+    }
+}
+
+impl StaffSettings {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.enabled && self.uniform_colors.is_empty() && self.verify_provider.is_none() {
+            return Err("Add at least one uniform colour or a verify_provider".to_string());
+        }
+        if self.uniform_colors.iter().any(|c| c.name.trim().is_empty()) {
+            return Err("Uniform colours need a name".to_string());
+        }
+        if !(self.min_coverage > 0.0 && self.min_coverage <= 1.0) {
+            return Err("min_coverage must be greater than 0 and at most 1".to_string());
+        }
+        Ok(())
+    }
+}
+
+// Share of the torso (upper-middle of the box) matching any uniform colour
+pub fn uniform_coverage(image: &DynamicImage, bbox: &BoundingBox, colors: &[UniformColor]) -> Option<f32> {
+    let (width, height) = image.dimensions();
+    let box_width = bbox.x2 - bbox.x1;
+    let box_height = bbox.y2 - bbox.y1;
+    let x1 = (bbox.x1 + box_width * 0.2).max(0.0) as u32;
+    let x2 = ((bbox.x2 - box_width * 0.2).max(0.0) as u32).min(width);
+    let y1 = (bbox.y1 + box_height * 0.2).max(0.0) as u32;
+    let y2 = ((bbox.y1 + box_height * 0.55).max(0.0) as u32).min(height);
+    if x2 <= x1 || y2 <= y1 {
+        return None;
+    }
+
+    let torso = image.crop_imm(x1, y1, x2 - x1, y2 - y1).to_rgb8();
+    let matching = torso
+        .pixels()
+        .filter(|pixel| {
+            colors.iter().any(|color| {
+                pixel.0.iter().zip(color.rgb).all(|(p, c)| p.abs_diff(c) <= color.tolerance)
+            })
+        })
+        .count();
+    Some(matching as f32 / (torso.width() * torso.height()) as f32)
+}
+
+// Clear coverage either way decides; the band between half and full min_coverage is inconclusive
+pub fn classify_coverage(coverage: f32, min_coverage: f32) -> Option<Role> {
+    if coverage >= min_coverage {
+        Some(Role::Staff)
+    } else if coverage < min_coverage / 2.0 {
+        Some(Role::Customer)
+    } else {
+        None
+    }
+}
+
+fn parse_answer(answer: &str) -> Option<Role> {
+    let answer = answer.to_lowercase();
+    let staff = ["staff", "employee"].iter().filter_map(|w| answer.find(w)).min();
+    let customer = answer.find("customer");
+    match (staff, customer) {
+        (Some(s), Some(c)) => Some(if s < c { Role::Staff } else { Role::Customer }),
+        (Some(_), None) => Some(Role::Staff),
+        (None, Some(_)) => Some(Role::Customer),
+        (None, None) => None,
+    }
+}
+
+async fn verify(state: &AppState, provider: &str, frame_base64: &str, bbox: &BoundingBox) -> Result<Role, String> {
+    let roi = RegionOfInterest {
+        x1: bbox.x1,
+        y1: bbox.y1,
+        x2: bbox.x2,
+        y2: bbox.y2,
+        padding: Some(0.1),
+    };
+    let crop = frame_processor::crop_to_roi(frame_base64, &roi)?;
+
+    let start = std::time::Instant::now();
+    let result = pipeline::describe_frame(&state.moondream, &state.providers, provider, VERIFY_PROMPT, crop).await;
+    state.bus.publish(BusEvent::analysis(provider, start.elapsed(), result.is_ok()));
+    let (answer, _) = result?.unwrap_or_default();
+    parse_answer(&answer).ok_or_else(|| format!("Unclear staff verification answer: {}", answer))
+}
+
+// Roles of re-identified tracks per camera, so each person is classified once
+#[derive(Default)]
+pub struct StaffTracks {
+    cameras: HashMap<String, HashMap<u64, (Role, DateTime<Utc>)>>,
+}
+
+impl StaffTracks {
+    fn known(&mut self, camera_id: &str, now: DateTime<Utc>) -> HashMap<u64, Role> {
+        // Tracks past the re-identification window are never matched again
+        let window = chrono::Duration::seconds(settings::current().reid.window_secs as i64);
+        let tracks = self.cameras.entry(camera_id.to_string()).or_default();
+        tracks.retain(|_, (_, seen)| now - *seen <= window);
+        tracks.iter().map(|(id, (role, _))| (*id, *role)).collect()
+    }
+
+    fn remember(&mut self, camera_id: &str, now: DateTime<Utc>, roles: Vec<(u64, Role)>) {
+        let tracks = self.cameras.entry(camera_id.to_string()).or_default();
+        for (id, role) in roles {
+            tracks.insert(id, (role, now));
+        }
+    }
+}
+
+async fn classify(
+    state: &AppState,
+    config: &StaffSettings,
+    camera_id: &str,
+    frame_base64: &str,
+    detection: &mut DetectionData,
+) -> Result<(), String> {
+    let now = Utc::now();
+    let known = state.staff.lock().await.known(camera_id, now);
+    let mut image: Option<DynamicImage> = None;
+    let mut seen = Vec::new();
+
+    for bbox in detection.boxes.iter_mut().filter(|b| b.class_name == "person") {
+        let role = match bbox.track_id.and_then(|id| known.get(&id)) {
+            Some(role) => *role,
+            None => {
+                if image.is_none() {
+                    image = Some(frame_processor::decode_frame(frame_base64)?);
+                }
+                let coverage = image
+                    .as_ref()
+                    .and_then(|image| uniform_coverage(image, bbox, &config.uniform_colors))
+                    .unwrap_or(0.0);
+                match (classify_coverage(coverage, config.min_coverage), &config.verify_provider) {
+                    (Some(role), _) => role,
+                    // Only tracked people are worth a VLM call: the answer is kept for the whole visit
+                    (None, Some(provider)) if bbox.track_id.is_some() => {
+                        verify(state, provider, frame_base64, bbox).await.unwrap_or_else(|e| {
+                            eprintln!("Staff verification failed: {}", e);
+                            Role::Customer
+                        })
+                    }
+                    (None, _) => Role::Customer,
+                }
+            }
+        };
+        bbox.role = Some(role);
+        seen.extend(bbox.track_id.map(|id| (id, role)));
+    }
+
+    let staff = detection.boxes.iter().filter(|b| b.role == Some(Role::Staff)).count() as u32;
+    detection.staff_count = Some(staff);
+    // Track ids are handed out in order, so this frame's new visitors hold the highest ones
+    if let Some(visitors) = detection.visitors.as_mut() {
+        let first_new = (visitors.unique - visitors.new) as u64;
+        visitors.new_staff = seen.iter().filter(|(id, role)| *id > first_new && *role == Role::Staff).count() as u32;
+    }
+
+    state.staff.lock().await.remember(camera_id, now, seen);
+    Ok(())
+}
+
+// Sets a role on every person box and DetectionData::staff_count when classification is enabled
+pub async fn tag(state: &AppState, camera_id: Option<&str>, frame_base64: &str, detection: &mut DetectionData) {
+    let config = settings::current().staff;
+    if !config.enabled {
+        return;
+    }
+    if let Err(e) = classify(state, &config, camera_id.unwrap_or(""), frame_base64, detection).await {
+        eprintln!("Staff classification skipped: {}", e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{Rgb, RgbImage};
+
+    #[test]
+    fn test_uniform_coverage_and_answers() {
+        // Left person wears the green store polo, right person a grey jacket
+        let frame = DynamicImage::ImageRgb8(RgbImage::from_fn(40, 40, |x, _| {
+            if x < 20 { Rgb([20, 140, 60]) } else { Rgb([120, 120, 120]) }
+        }));
+        let green = UniformColor { name: "polo".to_string(), rgb: [30, 150, 70], tolerance: default_tolerance() };
+        let person = |x1: f32| BoundingBox {
+            x1,
+            y1: 0.0,
+            x2: x1 + 20.0,
+            y2: 40.0,
+            confidence: 0.9,
+            class_name: "person".to_string(),
+            track_id: None,
+            role: None,
+            floor: None,
+            motion: None,
+        };
+
+        let staff = uniform_coverage(&frame, &person(0.0), std::slice::from_ref(&green)).unwrap();
+        let customer = uniform_coverage(&frame, &person(20.0), &[green]).unwrap();
+        assert_eq!(classify_coverage(staff, 0.35), Some(Role::Staff));
+        assert_eq!(classify_coverage(customer, 0.35), Some(Role::Customer));
+        assert_eq!(classify_coverage(0.2, 0.35), None);
+
+        assert_eq!(parse_answer("Staff."), Some(Role::Staff));
+        assert_eq!(parse_answer("This is a customer, not an employee"), Some(Role::Customer));
+        assert_eq!(parse_answer("I can't tell"), None);
+    }
+}
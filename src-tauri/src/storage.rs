@@ -0,0 +1,162 @@
+// Storage Module - Free-space checks before large downloads and disk usage reporting
+// Covers Ollama models plus everything the app keeps under ~/.live-vision-analyzer
+
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use tokio::process::Command;
+
+// The Ollama release binary, rounded up
+pub const OLLAMA_DOWNLOAD_MB: u64 = 1024;
+// moondream2 safetensors plus tokenizer
+pub const MOONDREAM2_DOWNLOAD_MB: u64 = 4096;
+// Keep some headroom so the event store and OS still have room after a download
+const SAFETY_MARGIN_MB: u64 = 512;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ModelUsage {
+    pub name: String,
+    pub size_mb: u64,
+    pub in_use: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct DirectoryUsage {
+    pub name: String,
+    pub path: String,
+    pub size_mb: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct StorageUsage {
+    pub free_mb: Option<u64>,
+    pub models: Vec<ModelUsage>,
+    pub directories: Vec<DirectoryUsage>,
+    pub total_mb: u64,
+}
+
+pub fn data_dir() -> PathBuf {
+    let home_dir = std::env::var("HOME").unwrap_or_else(|_| "/tmp".to_string());
+    PathBuf::from(home_dir).join(".live-vision-analyzer")
+}
+
+// Rough download size of an Ollama vision model from its tag
+pub fn estimated_model_mb(model: &str) -> u64 {
+    let model = model.to_lowercase();
+    if model.contains("34b") {
+        20_000
+    } else if model.contains("13b") || model.contains("11b") || model.contains("llama3.2-vision") {
+        8_000
+    } else if model.contains("phi3") || model.contains("embed") {
+        3_000
+    } else {
+        5_000
+    }
+}
+
+// Recursive size of a file or directory in bytes
+pub fn dir_size(path: &Path) -> u64 {
+    let Ok(metadata) = std::fs::symlink_metadata(path) else {
+        return 0;
+    };
+    if !metadata.is_dir() {
+        return metadata.len();
+    }
+    std::fs::read_dir(path)
+        .map(|entries| entries.flatten().map(|entry| dir_size(&entry.path())).sum())
+        .unwrap_or(0)
+}
+
+// Second line of `df -Pk`: filesystem, blocks, used, available, ...
+pub fn parse_df(output: &str) -> Option<u64> {
+    let line = output.lines().nth(1)?;
+    let available_kb: u64 = line.split_whitespace().nth(3)?.parse().ok()?;
+    Some(available_kb / 1024)
+}
+
+// Free space on the volume holding `path`, or None if it can't be determined
+pub async fn free_space_mb(path: &Path) -> Option<u64> {
+    // Check the closest directory that exists
+    let mut existing = path.to_path_buf();
+    while !existing.exists() {
+        existing = existing.parent()?.to_path_buf();
+    }
+
+    if cfg!(target_os = "windows") {
+        let drive = existing.to_string_lossy().chars().next()?;
+        let output = Command::new("powershell")
+            .args(["-NoProfile", "-Command", &format!("(Get-PSDrive {}).Free", drive)])
+            .output()
+            .await
+            .ok()?;
+        let bytes: u64 = String::from_utf8_lossy(&output.stdout).trim().parse().ok()?;
+        return Some(bytes / (1024 * 1024));
+    }
+
+    let output = Command::new("df").arg("-Pk").arg(&existing).output().await.ok()?;
+    parse_df(&String::from_utf8_lossy(&output.stdout))
+}
+
+// Fail before a download that would not fit; unknown free space is let through
+pub async fn ensure_free_space(path: &Path, required_mb: u64, what: &str) -> Result<(), String> {
+    let Some(free_mb) = free_space_mb(path).await else {
+        eprintln!("Could not determine free disk space at {}", path.display());
+        return Ok(());
+    };
+
+    if free_mb < required_mb + SAFETY_MARGIN_MB {
+        return Err(format!(
+            "Not enough disk space to {}: needs about {} MB but only {} MB is free at {}",
+            what,
+            required_mb + SAFETY_MARGIN_MB,
+            free_mb,
+            path.display()
+        ));
+    }
+    Ok(())
+}
+
+// App data grouped the way users think about it
+pub fn directory_usage() -> Vec<DirectoryUsage> {
+    let root = data_dir();
+    [
+        ("Event archive", root.join("events.db")),
+        ("Video logs", root.join("video_logs")),
+        ("Daily reports", root.join("reports")),
+        ("Detection snapshots", root.join("snapshots")),
+        ("Daily exports", root.join("exports")),
+        ("Local inference models", root.join("models")),
+        ("Embedded Ollama", root.join("ollama")),
+    ]
+    .into_iter()
+    .map(|(name, path)| DirectoryUsage {
+        name: name.to_string(),
+        size_mb: dir_size(&path) / (1024 * 1024),
+        path: path.to_string_lossy().to_string(),
+    })
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_df_and_estimates() {
+        let output = "Filesystem 1024-blocks Used Available Capacity Mounted on\n/dev/sda1 102400000 51200000 20480000 72% /\n";
+        assert_eq!(parse_df(output), Some(20000));
+        assert_eq!(parse_df("garbage"), None);
+
+        assert_eq!(estimated_model_mb("llava:34b"), 20_000);
+        assert_eq!(estimated_model_mb("llava-phi3"), 3_000);
+        assert_eq!(estimated_model_mb("llava:7b"), 5_000);
+    }
+
+    #[test]
+    fn test_dir_size() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("a.bin"), vec![0u8; 1000]).unwrap();
+        std::fs::create_dir(dir.path().join("clips")).unwrap();
+        std::fs::write(dir.path().join("clips").join("b.bin"), vec![0u8; 500]).unwrap();
+        assert_eq!(dir_size(dir.path()), 1500);
+    }
+}
@@ -0,0 +1,283 @@
+// Suppression Module - Learns from the alerts operators dismiss as false positives, so recurring nuisance alerts fade out
+// A dismissed alert is fingerprinted by camera, subject, class, zone and hour of day; enough dismissals of one fingerprint hold
+// matching alerts back, and a rule dismissed often enough needs more confident boxes to fire. Both lapse once the dismissals stop
+
+use crate::bus::Alert;
+use crate::event_store::{format_timestamp, StoredEvent};
+use crate::incidents;
+use chrono::{DateTime, Duration, Local, Timelike, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+// Boxes outside a named zone are placed on a grid of this many pixels
+const CELL_PIXELS: f64 = 160.0;
+
+// A learned rule threshold sits this far above the confidence of the boxes that were dismissed
+const CONFIDENCE_MARGIN: f32 = 0.05;
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Fingerprint {
+    pub camera_id: Option<String>,
+    pub subject: String,  // Rule name, hazard, zone... or the alert kind
+    pub class_name: Option<String>,  // Most common class among the alert's boxes
+    pub zone: Option<String>,  // Named zone, or the grid cell of the first box
+    pub hour: u32,  // Local hour of day
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct LearnedFingerprint {
+    #[serde(flatten)]
+    pub fingerprint: Fingerprint,
+    pub dismissals: u32,
+    pub expires_at: String,  // One window after the latest dismissal
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct LearnedThreshold {
+    pub min_confidence: f32,
+    pub dismissals: u32,
+    pub expires_at: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+#[serde(default)]
+pub struct Learned {
+    pub fingerprints: Vec<LearnedFingerprint>,
+    pub rule_thresholds: BTreeMap<String, LearnedThreshold>,  // Rule name -> minimum box confidence
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct SuppressionSettings {
+    pub enabled: bool,
+    pub learn_after: u32,  // Dismissals of one fingerprint, or one rule, within the window before it is suppressed
+    pub window_days: u32,
+    pub max_rule_confidence: f32,  // Learned thresholds never go above this
+    pub learned: Learned,  // Rebuilt from the dismissals on every new one
+}
+
+impl Default for SuppressionSettings {
+    fn default() -> Self {
+        SuppressionSettings {
+            enabled: true,
+            learn_after: 3,
+            window_days: 14,
+            max_rule_confidence: 0.8,
+            learned: Learned::default(),
+        }
+    }
+}
+
+impl SuppressionSettings {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.learn_after < 2 {
+            return Err("Suppression needs at least 2 dismissals to learn from".to_string());
+        }
+        if !(1..=365).contains(&self.window_days) {
+            return Err("Suppression window must be between 1 and 365 days".to_string());
+        }
+        if !(self.max_rule_confidence > 0.0 && self.max_rule_confidence <= 1.0) {
+            return Err("max_rule_confidence must be greater than 0 and at most 1".to_string());
+        }
+        Ok(())
+    }
+}
+
+fn boxes(payload: &serde_json::Value) -> Vec<&serde_json::Value> {
+    match payload["boxes"].as_array() {
+        Some(boxes) => boxes.iter().collect(),
+        None => payload.get("bbox").into_iter().filter(|b| b.is_object()).collect(),
+    }
+}
+
+pub fn fingerprint(camera_id: Option<&str>, payload: &serde_json::Value, hour: u32) -> Fingerprint {
+    let boxes = boxes(payload);
+    let mut classes: BTreeMap<&str, usize> = BTreeMap::new();
+    for class_name in boxes.iter().filter_map(|b| b["class_name"].as_str()) {
+        *classes.entry(class_name).or_default() += 1;
+    }
+    let cell = boxes.first().and_then(|b| {
+        let x = (b["x1"].as_f64()? + b["x2"].as_f64()?) / 2.0;
+        let y = (b["y1"].as_f64()? + b["y2"].as_f64()?) / 2.0;
+        Some(format!("cell {},{}", (x / CELL_PIXELS) as u32, (y / CELL_PIXELS) as u32))
+    });
+    Fingerprint {
+        camera_id: camera_id.map(str::to_string),
+        subject: incidents::subject(payload).unwrap_or_else(|| incidents::alert_kind(payload)),
+        class_name: classes.into_iter().max_by_key(|(_, count)| *count).map(|(c, _)| c.to_string()),
+        zone: payload["zone"].as_str().map(str::to_string).or(cell),
+        hour,
+    }
+}
+
+fn best_confidence(payload: &serde_json::Value) -> Option<f32> {
+    boxes(payload).iter().filter_map(|b| b["confidence"].as_f64()).map(|c| c as f32).reduce(f32::max)
+}
+
+fn local_hour(timestamp: &str) -> Option<u32> {
+    DateTime::parse_from_rfc3339(timestamp).ok().map(|t| t.with_timezone(&Local).hour())
+}
+
+fn expires_at(dismissed_at: &str, window_days: u32) -> String {
+    DateTime::parse_from_rfc3339(dismissed_at)
+        .map(|t| format_timestamp(t.with_timezone(&Utc) + Duration::days(window_days as i64)))
+        .unwrap_or_default()
+}
+
+// Critical alerts are never learned from or held back
+fn critical(payload: &serde_json::Value) -> bool {
+    payload["priority"].as_str() == Some("critical")
+}
+
+// Rebuild what is suppressed from dismissals within the window
+pub fn learn(config: &SuppressionSettings, dismissed: &[(StoredEvent, String)], now: DateTime<Utc>) -> Learned {
+    let cutoff = format_timestamp(now - Duration::days(config.window_days as i64));
+    let mut fingerprints: BTreeMap<Fingerprint, (u32, &str)> = BTreeMap::new();
+    let mut rules: BTreeMap<&str, (Vec<f32>, &str)> = BTreeMap::new();
+    for (alert, dismissed_at) in dismissed.iter().filter(|(_, at)| at.as_str() >= cutoff.as_str()) {
+        let (Some(payload), Some(hour)) = (&alert.payload, local_hour(&alert.timestamp)) else {
+            continue;
+        };
+        if critical(payload) {
+            continue;
+        }
+        let entry = fingerprints.entry(fingerprint(alert.camera_id.as_deref(), payload, hour)).or_insert((0, dismissed_at.as_str()));
+        entry.0 += 1;
+        entry.1 = entry.1.max(dismissed_at.as_str());
+        if let (Some(rule), Some(confidence)) = (payload["rule"].as_str().filter(|_| payload["kind"] == "rule"), best_confidence(payload)) {
+            let entry = rules.entry(rule).or_insert((Vec::new(), dismissed_at.as_str()));
+            entry.0.push(confidence);
+            entry.1 = entry.1.max(dismissed_at.as_str());
+        }
+    }
+
+    Learned {
+        fingerprints: fingerprints
+            .into_iter()
+            .filter(|(_, (count, _))| *count >= config.learn_after)
+            .map(|(fingerprint, (dismissals, latest))| LearnedFingerprint {
+                fingerprint,
+                dismissals,
+                expires_at: expires_at(latest, config.window_days),
+            })
+            .collect(),
+        rule_thresholds: rules
+            .into_iter()
+            .filter(|(_, (confidences, _))| confidences.len() >= config.learn_after as usize)
+            .map(|(rule, (confidences, latest))| {
+                let mean = confidences.iter().sum::<f32>() / confidences.len() as f32;
+                let threshold = LearnedThreshold {
+                    min_confidence: (mean + CONFIDENCE_MARGIN).min(config.max_rule_confidence),
+                    dismissals: confidences.len() as u32,
+                    expires_at: expires_at(latest, config.window_days),
+                };
+                (rule.to_string(), threshold)
+            })
+            .collect(),
+    }
+}
+
+// The learned fingerprint an alert raised now matches, if any
+pub fn matching<'a>(
+    config: &'a SuppressionSettings,
+    camera_id: Option<&str>,
+    payload: &serde_json::Value,
+    now: DateTime<Local>,
+) -> Option<&'a LearnedFingerprint> {
+    if !config.enabled || critical(payload) {
+        return None;
+    }
+    let fingerprint = fingerprint(camera_id, payload, now.hour());
+    let now = format_timestamp(now.with_timezone(&Utc));
+    config.learned.fingerprints.iter().find(|l| l.fingerprint == fingerprint && l.expires_at > now)
+}
+
+// Mark an alert that matches a learned fingerprint; it is still stored, but opens no incident and notifies no one
+pub fn mark(config: &SuppressionSettings, alert: Arc<Alert>, now: DateTime<Local>) -> Arc<Alert> {
+    let Some(learned) = matching(config, alert.camera_id.as_deref(), &alert.payload, now) else {
+        return alert;
+    };
+    let mut marked = (*alert).clone();
+    if let Some(fields) = marked.payload.as_object_mut() {
+        let suppressed = serde_json::json!({ "dismissals": learned.dismissals, "until": learned.expires_at });
+        fields.insert("suppressed".to_string(), suppressed);
+    }
+    Arc::new(marked)
+}
+
+pub fn suppressed(payload: &serde_json::Value) -> bool {
+    payload["suppressed"].is_object()
+}
+
+// Minimum box confidence learned for a rule, while it lasts
+pub fn min_confidence(config: &SuppressionSettings, rule: &str, now: &str) -> Option<f32> {
+    let threshold = config.learned.rule_thresholds.get(rule)?;
+    (config.enabled && threshold.expires_at.as_str() > now).then_some(threshold.min_confidence)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dismissed(id: &str, timestamp: &str, payload: serde_json::Value) -> (StoredEvent, String) {
+        let alert = StoredEvent {
+            id: id.to_string(),
+            timestamp: timestamp.to_string(),
+            camera_id: Some("loading-dock".to_string()),
+            event_type: "alert".to_string(),
+            person_count: None,
+            object_counts: None,
+            provider: Some("rules".to_string()),
+            prompt: None,
+            description: None,
+            payload: Some(payload),
+        };
+        (alert, timestamp.to_string())
+    }
+
+    fn dock_alert(x1: f32, confidence: f32) -> serde_json::Value {
+        serde_json::json!({
+            "kind": "rule", "priority": "medium", "rule": "Person at dock",
+            "boxes": [{ "x1": x1, "y1": 200, "x2": x1 + 60.0, "y2": 300, "confidence": confidence, "class_name": "person" }]
+        })
+    }
+
+    #[test]
+    fn test_fingerprint() {
+        let print = fingerprint(Some("dock"), &dock_alert(20.0, 0.5), 23);
+        assert_eq!((print.subject.as_str(), print.class_name.as_deref(), print.zone.as_deref()), ("Person at dock", Some("person"), Some("cell 0,1")));
+        let ppe = serde_json::json!({ "kind": "ppe", "zone": "loading bay", "bbox": { "x1": 0, "y1": 0, "x2": 10, "y2": 10, "class_name": "person" } });
+        assert_eq!(fingerprint(None, &ppe, 9).zone.as_deref(), Some("loading bay"));
+        assert_eq!(fingerprint(None, &serde_json::json!({ "kind": "fire" }), 9).subject, "fire");
+    }
+
+    #[test]
+    fn test_learning_and_decay() {
+        let config = SuppressionSettings::default();
+        let now = DateTime::parse_from_rfc3339("2026-06-10T22:45:00.000Z").unwrap().with_timezone(&Utc);
+        let alerts = vec![
+            dismissed("a1", "2026-06-01T22:40:00.000Z", dock_alert(20.0, 0.4)),
+            dismissed("a2", "2026-06-05T22:50:00.000Z", dock_alert(40.0, 0.5)),
+            dismissed("a3", "2026-06-08T22:41:00.000Z", dock_alert(30.0, 0.6)),
+            // Another part of the frame: counts toward the rule, but is its own fingerprint
+            dismissed("a4", "2026-06-09T22:42:00.000Z", dock_alert(500.0, 0.5)),
+        ];
+        let learned = learn(&config, &alerts, now);
+        assert_eq!(learned.fingerprints.len(), 1);
+        assert_eq!((learned.fingerprints[0].dismissals, learned.fingerprints[0].expires_at.as_str()), (3, "2026-06-22T22:41:00.000Z"));
+        let threshold = &learned.rule_thresholds["Person at dock"];
+        assert!((threshold.min_confidence - 0.55).abs() < 1e-6 && threshold.dismissals == 4);
+
+        let config = SuppressionSettings { learned, ..config };
+        let same_hour = now.with_timezone(&Local);
+        assert!(matching(&config, Some("loading-dock"), &dock_alert(25.0, 0.9), same_hour).is_some());
+        assert!(matching(&config, Some("loading-dock"), &dock_alert(500.0, 0.9), same_hour).is_none());
+        assert!(matching(&config, Some("loading-dock"), &dock_alert(25.0, 0.9), same_hour + Duration::hours(12)).is_none());
+        assert!(matching(&config, Some("loading-dock"), &dock_alert(25.0, 0.9), same_hour + Duration::days(14)).is_none());
+        assert_eq!(min_confidence(&config, "Person at dock", &format_timestamp(now)), Some(threshold.min_confidence));
+
+        // Three weeks on, the dismissals have aged out and nothing is suppressed
+        assert_eq!(learn(&config, &alerts, now + Duration::days(21)), Learned::default());
+    }
+}
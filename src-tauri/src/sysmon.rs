@@ -0,0 +1,246 @@
+// System Monitor Module - CPU, memory and GPU usage of the machine, the app and Ollama
+// Linux reads /proc over a short sampling window; macOS asks ps; GPUs come from nvidia-smi where available
+
+use crate::metrics::StageLatency;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::time::Duration;
+use tokio::process::Command;
+
+// CPU percentages are measured over this window
+const SAMPLE_WINDOW: Duration = Duration::from_millis(250);
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ProcessUsage {
+    pub pids: Vec<u32>,
+    pub cpu_percent: f32,  // 100 = one full core
+    pub memory_mb: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct GpuUsage {
+    pub name: String,
+    pub utilization_percent: f32,
+    pub memory_used_mb: u64,
+    pub memory_total_mb: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct SystemStats {
+    pub cpu_cores: u32,
+    pub cpu_percent: Option<f32>,  // Whole machine, 100 = all cores busy
+    pub memory_used_mb: Option<u64>,
+    pub memory_total_mb: Option<u64>,
+    pub app: Option<ProcessUsage>,
+    pub ollama: Option<ProcessUsage>,  // Server plus model runners; None when Ollama isn't running
+    pub gpus: Vec<GpuUsage>,
+    pub gpu_memory_mb: BTreeMap<String, u64>,  // "app" / "ollama" -> GPU memory held, from nvidia-smi
+    pub stages: BTreeMap<String, StageLatency>,
+}
+
+fn is_ollama(name: &str) -> bool {
+    name.rsplit('/').next().is_some_and(|n| n.starts_with("ollama"))
+}
+
+// Process CPU ticks from /proc/<pid>/stat: utime + stime, read after the parenthesised command name
+fn parse_proc_ticks(stat: &str) -> Option<u64> {
+    let fields: Vec<&str> = stat.rsplit_once(')')?.1.split_whitespace().collect();
+    Some(fields.get(11)?.parse::<u64>().ok()? + fields.get(12)?.parse::<u64>().ok()?)
+}
+
+// (busy, total) ticks from the aggregate line of /proc/stat
+fn parse_cpu_ticks(stat: &str) -> Option<(u64, u64)> {
+    let values: Vec<u64> = stat.lines().next()?.strip_prefix("cpu ")?.split_whitespace().filter_map(|v| v.parse().ok()).collect();
+    let total: u64 = values.iter().take(8).sum();
+    let idle = values.get(3)? + values.get(4).copied().unwrap_or(0);
+    Some((total - idle, total))
+}
+
+fn meminfo_mb(meminfo: &str, key: &str) -> Option<u64> {
+    meminfo
+        .lines()
+        .find_map(|line| line.strip_prefix(key)?.trim_start_matches(':').split_whitespace().next()?.parse::<u64>().ok())
+        .map(|kb| kb / 1024)
+}
+
+struct ProcSample {
+    cpu: Option<(u64, u64)>,
+    ticks: HashMap<u32, u64>,
+}
+
+fn proc_sample(pids: &[u32]) -> ProcSample {
+    ProcSample {
+        cpu: std::fs::read_to_string("/proc/stat").ok().as_deref().and_then(parse_cpu_ticks),
+        ticks: pids
+            .iter()
+            .filter_map(|pid| Some((*pid, parse_proc_ticks(&std::fs::read_to_string(format!("/proc/{}/stat", pid)).ok()?)?)))
+            .collect(),
+    }
+}
+
+fn ollama_pids_linux() -> Vec<u32> {
+    std::fs::read_dir("/proc")
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter_map(|entry| {
+            let pid = entry.file_name().to_string_lossy().parse::<u32>().ok()?;
+            let comm = std::fs::read_to_string(entry.path().join("comm")).ok()?;
+            is_ollama(comm.trim()).then_some(pid)
+        })
+        .collect()
+}
+
+async fn linux_stats(stats: &mut SystemStats) {
+    let app = vec![std::process::id()];
+    let ollama = ollama_pids_linux();
+    let all: Vec<u32> = app.iter().chain(&ollama).copied().collect();
+
+    let before = proc_sample(&all);
+    tokio::time::sleep(SAMPLE_WINDOW).await;
+    let after = proc_sample(&all);
+
+    let (elapsed_total, machine) = match (before.cpu, after.cpu) {
+        (Some((busy0, total0)), Some((busy1, total1))) if total1 > total0 => {
+            (Some(total1 - total0), Some(100.0 * busy1.saturating_sub(busy0) as f32 / (total1 - total0) as f32))
+        }
+        _ => (None, None),
+    };
+    stats.cpu_percent = machine;
+
+    let meminfo = std::fs::read_to_string("/proc/meminfo").unwrap_or_default();
+    stats.memory_total_mb = meminfo_mb(&meminfo, "MemTotal");
+    stats.memory_used_mb = stats.memory_total_mb.zip(meminfo_mb(&meminfo, "MemAvailable")).map(|(total, free)| total - free);
+
+    // Aggregate ticks span every core, so scale back to "percent of one core"
+    let cores = stats.cpu_cores as f32;
+    let usage = |pids: &[u32]| -> Option<ProcessUsage> {
+        if pids.is_empty() {
+            return None;
+        }
+        let ticks: u64 = pids.iter().filter_map(|p| Some(after.ticks.get(p)?.saturating_sub(*before.ticks.get(p)?))).sum();
+        let memory_mb = pids
+            .iter()
+            .filter_map(|p| meminfo_mb(&std::fs::read_to_string(format!("/proc/{}/status", p)).ok()?, "VmRSS"))
+            .sum();
+        Some(ProcessUsage {
+            pids: pids.to_vec(),
+            cpu_percent: elapsed_total.map(|t| 100.0 * ticks as f32 * cores / t as f32).unwrap_or(0.0),
+            memory_mb,
+        })
+    };
+    stats.app = usage(&app);
+    stats.ollama = usage(&ollama);
+}
+
+// Lines of `ps -axo pid=,pcpu=,rss=,comm=`
+fn parse_ps(output: &str) -> Vec<(u32, f32, u64, String)> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let pid = fields.next()?.parse().ok()?;
+            let cpu = fields.next()?.parse().ok()?;
+            let rss_kb = fields.next()?.parse().ok()?;
+            Some((pid, cpu, rss_kb, fields.collect::<Vec<_>>().join(" ")))
+        })
+        .collect()
+}
+
+async fn ps_stats(stats: &mut SystemStats) {
+    let Ok(output) = Command::new("ps").args(["-axo", "pid=,pcpu=,rss=,comm="]).output().await else {
+        return;
+    };
+    let processes = parse_ps(&String::from_utf8_lossy(&output.stdout));
+    let usage = |keep: &dyn Fn(u32, &str) -> bool| -> Option<ProcessUsage> {
+        let matching: Vec<_> = processes.iter().filter(|(pid, _, _, name)| keep(*pid, name)).collect();
+        (!matching.is_empty()).then(|| ProcessUsage {
+            pids: matching.iter().map(|p| p.0).collect(),
+            cpu_percent: matching.iter().map(|p| p.1).sum(),
+            memory_mb: matching.iter().map(|p| p.2).sum::<u64>() / 1024,
+        })
+    };
+    let own = std::process::id();
+    stats.app = usage(&|pid, _| pid == own);
+    stats.ollama = usage(&|_, name| is_ollama(name));
+    let all: f32 = processes.iter().map(|p| p.1).sum();
+    stats.cpu_percent = Some((all / stats.cpu_cores as f32).min(100.0));
+}
+
+// nvidia-smi: whole-GPU usage, then GPU memory per process
+async fn nvidia_stats(stats: &mut SystemStats) {
+    let query = |args: &'static [&'static str]| async move {
+        match Command::new("nvidia-smi").args(args).output().await {
+            Ok(output) if output.status.success() => String::from_utf8_lossy(&output.stdout).to_string(),
+            _ => String::new(),
+        }
+    };
+    let gpus = query(&["--query-gpu=name,utilization.gpu,memory.used,memory.total", "--format=csv,noheader,nounits"]).await;
+    stats.gpus = gpus
+        .lines()
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.rsplitn(4, ',').map(str::trim).collect();
+            Some(GpuUsage {
+                name: fields.get(3)?.to_string(),
+                utilization_percent: fields.get(2)?.parse().ok()?,
+                memory_used_mb: fields.get(1)?.parse().ok()?,
+                memory_total_mb: fields.first()?.parse().ok()?,
+            })
+        })
+        .collect();
+    if stats.gpus.is_empty() {
+        return;
+    }
+
+    let apps = query(&["--query-compute-apps=pid,used_memory", "--format=csv,noheader,nounits"]).await;
+    for line in apps.lines() {
+        let Some((pid, memory)) = line.split_once(',') else { continue };
+        let (Ok(pid), Ok(memory)) = (pid.trim().parse::<u32>(), memory.trim().parse::<u64>()) else { continue };
+        let owner = [("app", &stats.app), ("ollama", &stats.ollama)]
+            .into_iter()
+            .find(|(_, usage)| usage.as_ref().is_some_and(|u| u.pids.contains(&pid)));
+        if let Some((owner, _)) = owner {
+            *stats.gpu_memory_mb.entry(owner.to_string()).or_insert(0) += memory;
+        }
+    }
+}
+
+pub async fn collect(stages: BTreeMap<String, StageLatency>) -> SystemStats {
+    let mut stats = SystemStats {
+        cpu_cores: std::thread::available_parallelism().map(|n| n.get() as u32).unwrap_or(1),
+        cpu_percent: None,
+        memory_used_mb: None,
+        memory_total_mb: None,
+        app: None,
+        ollama: None,
+        gpus: Vec::new(),
+        gpu_memory_mb: BTreeMap::new(),
+        stages,
+    };
+    if cfg!(target_os = "linux") {
+        linux_stats(&mut stats).await;
+    } else if cfg!(unix) {
+        ps_stats(&mut stats).await;
+    }
+    nvidia_stats(&mut stats).await;
+    stats
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_proc_and_ps_parsing() {
+        let stat = "4242 (tokio (worker)) S 1 4242 4242 0 -1 4194560 1000 0 0 0 150 50 0 0 20 0 8 0 100";
+        assert_eq!(parse_proc_ticks(stat), Some(200));
+        assert_eq!(parse_cpu_ticks("cpu  100 0 50 800 50 0 0 0 0 0\ncpu0 1 2 3"), Some((150, 1000)));
+        assert_eq!(meminfo_mb("MemTotal:       16384000 kB\nMemAvailable:    8192000 kB\n", "MemAvailable"), Some(8000));
+
+        let ps = parse_ps("  101  12.5  204800 /usr/local/bin/ollama\n  102 150.0 4096000 ollama_llama_server\nbad line\n");
+        assert_eq!(ps.len(), 2);
+        assert_eq!(ps[1], (102, 150.0, 4096000, "ollama_llama_server".to_string()));
+        assert!(ps.iter().all(|p| is_ollama(&p.3)));
+        assert!(!is_ollama("live-vision-analyzer"));
+    }
+}
@@ -0,0 +1,127 @@
+// Trigger Module - Capture and analyze on demand when an external system fires (door sensor, POS, alarm panel)
+// The analysis is linked to the caller's event id so either side can look the other up
+
+use crate::bus::BusEvent;
+use crate::event_store::{format_timestamp, ExternalTrigger};
+use crate::screen_capture::{self, CaptureTarget};
+use crate::{benchmark, frame_processor, pipeline, video_source, AppState};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct TriggerRequest {
+    pub source: String,       // What fired, e.g. "front-door-sensor" or "pos"
+    pub external_id: String,  // The caller's id for this occurrence
+    pub prompt: Option<String>,
+    pub provider: Option<String>,
+    pub camera_id: Option<String>,
+    // Exactly one frame source
+    pub frame_base64: Option<String>,
+    pub stream_url: Option<String>,
+    pub screen: Option<CaptureTarget>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TriggerResult {
+    pub event_id: Option<String>,
+    pub source: String,
+    pub external_id: String,
+    pub provider: String,
+    pub description: String,
+    pub result: serde_json::Value,
+    pub processing_time_ms: u64,
+}
+
+impl TriggerRequest {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.source.trim().is_empty() || self.external_id.trim().is_empty() {
+            return Err("source and external_id are required".to_string());
+        }
+        let sources = [self.frame_base64.is_some(), self.stream_url.is_some(), self.screen.is_some()];
+        if sources.iter().filter(|s| **s).count() != 1 {
+            return Err("Provide exactly one of frame_base64, stream_url or screen".to_string());
+        }
+        Ok(())
+    }
+
+    async fn capture(&self) -> Result<String, String> {
+        let frame = match (&self.frame_base64, &self.stream_url, &self.screen) {
+            (Some(frame), _, _) => frame.clone(),
+            (_, Some(url), _) => video_source::grab_stream_frame(url).await?,
+            (_, _, Some(target)) => screen_capture::capture_frame(target).await?,
+            _ => return Err("No frame source".to_string()),
+        };
+        frame_processor::prepare_frame(frame, None)
+    }
+}
+
+pub async fn run(state: &AppState, request: TriggerRequest) -> Result<TriggerResult, String> {
+    request.validate()?;
+    crate::pause::check()?;
+    let received_at = format_timestamp(chrono::Utc::now());
+    let provider = request.provider.clone().unwrap_or_else(|| "llava".to_string());
+    let prompt = request
+        .prompt
+        .clone()
+        .unwrap_or_else(|| benchmark::DEFAULT_BENCHMARK_PROMPT.to_string());
+    pipeline::ensure_provider_ready(&state.providers, &provider).await?;
+
+    let start_time = std::time::Instant::now();
+    let frame = request.capture().await?;
+    println!("🔔 Trigger {} from {}: analyzing with {}", request.external_id, request.source, provider);
+
+    let _in_flight = state.metrics.track_in_flight();
+    let vlm_start = std::time::Instant::now();
+    let result = pipeline::describe_frame(&state.moondream, &state.providers, &provider, &prompt, frame).await;
+    state.bus.publish(BusEvent::analysis(&provider, vlm_start.elapsed(), result.is_ok()));
+    let (description, analysis) = result?.unwrap_or_default();
+
+    let event_id = crate::store_camera_analysis(
+        state,
+        request.camera_id.as_deref(),
+        &provider,
+        &prompt,
+        &description,
+        Some(analysis.clone()),
+    )
+    .await;
+    if let Some(event_id) = &event_id {
+        let trigger = ExternalTrigger {
+            event_id: event_id.clone(),
+            source: request.source.clone(),
+            external_id: request.external_id.clone(),
+            received_at,
+        };
+        if let Err(e) = state.events.lock().await.link_trigger(&trigger) {
+            eprintln!("{}", e);
+        }
+    }
+
+    Ok(TriggerResult {
+        event_id,
+        source: request.source,
+        external_id: request.external_id,
+        provider,
+        description,
+        result: analysis,
+        processing_time_ms: start_time.elapsed().as_millis() as u64,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_trigger_validation() {
+        let request = TriggerRequest {
+            source: "pos".to_string(),
+            external_id: "txn-1042".to_string(),
+            stream_url: Some("rtsp://cam/stream".to_string()),
+            ..Default::default()
+        };
+        assert!(request.validate().is_ok());
+        assert!(TriggerRequest { stream_url: None, ..request.clone() }.validate().is_err());
+        assert!(TriggerRequest { frame_base64: Some("abc".to_string()), ..request.clone() }.validate().is_err());
+        assert!(TriggerRequest { external_id: " ".to_string(), ..request }.validate().is_err());
+    }
+}
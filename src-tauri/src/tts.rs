@@ -0,0 +1,181 @@
+// TTS Module - Speaks alerts from chosen rules on the host's speakers
+// Uses the platform's own speech engine: say on macOS, System.Speech on Windows, espeak-ng or spd-say on Linux
+
+use crate::alerting::{self, AlertNotice, QuietHours};
+use crate::event_store::EventStore;
+use crate::rules::Rule;
+use crate::settings;
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tokio::process::Command;
+use tokio::sync::Mutex;
+
+const MAX_MESSAGE_CHARS: usize = 300;
+
+// What to say when one rule fires, e.g. "Queue is forming at {camera}"
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct VoiceTemplate {
+    pub rule: String,
+    pub message: String,
+    #[serde(default)]
+    pub voice: Option<String>,  // Overrides the default voice for this rule
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+#[serde(default)]
+pub struct TtsSettings {
+    pub enabled: bool,
+    pub voice: Option<String>,  // Engine voice name; None uses the system voice
+    pub rate_wpm: Option<u32>,
+    pub quiet_hours: Option<QuietHours>,
+    pub templates: Vec<VoiceTemplate>,  // Only rules listed here are announced
+}
+
+impl TtsSettings {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.rate_wpm.is_some_and(|rate| !(80..=400).contains(&rate)) {
+            return Err("Speech rate must be between 80 and 400 words per minute".to_string());
+        }
+        if let Some(quiet_hours) = &self.quiet_hours {
+            quiet_hours.validate()?;
+        }
+        for (index, template) in self.templates.iter().enumerate() {
+            if self.templates[..index].iter().any(|t| t.rule == template.rule) {
+                return Err(format!("Rule '{}' has two voice templates", template.rule));
+            }
+            if template.message.trim().is_empty() || template.message.chars().count() > MAX_MESSAGE_CHARS {
+                return Err(format!("Voice template for '{}' needs a message of up to {} characters", template.rule, MAX_MESSAGE_CHARS));
+            }
+        }
+        Ok(())
+    }
+
+    // Checked when templates are saved; a rule removed later just goes quiet
+    pub fn check_rules(&self, rules: &[Rule]) -> Result<(), String> {
+        match self.templates.iter().find(|t| !rules.iter().any(|r| r.name == t.rule)) {
+            Some(template) => Err(format!("Voice template for unknown rule '{}'", template.rule)),
+            None => Ok(()),
+        }
+    }
+
+    // The sentence and voice for an alert, if its rule is announced and it is not quiet time
+    pub fn announcement(&self, notice: &AlertNotice, time: chrono::NaiveTime) -> Option<(String, Option<String>)> {
+        if !self.enabled || self.quiet_hours.as_ref().is_some_and(|q| q.contains(time)) {
+            return None;
+        }
+        let rule = notice.rule.as_deref()?;
+        let template = self.templates.iter().find(|t| t.rule == rule)?;
+        let voice = template.voice.clone().or_else(|| self.voice.clone());
+        Some((notice.render(&template.message), voice))
+    }
+}
+
+fn speech_command(text: &str, voice: Option<&str>, rate_wpm: Option<u32>) -> Vec<Command> {
+    if cfg!(target_os = "macos") {
+        let mut say = Command::new("say");
+        if let Some(voice) = voice {
+            say.args(["-v", voice]);
+        }
+        if let Some(rate) = rate_wpm {
+            say.args(["-r", &rate.to_string()]);
+        }
+        say.arg(text);
+        vec![say]
+    } else if cfg!(target_os = "windows") {
+        // The text goes through the environment so nothing in it is read as PowerShell
+        let mut script = "Add-Type -AssemblyName System.Speech; $s = New-Object System.Speech.Synthesis.SpeechSynthesizer;".to_string();
+        if voice.is_some() {
+            script.push_str(" $s.SelectVoice($env:LVA_TTS_VOICE);");
+        }
+        if let Some(rate) = rate_wpm {
+            // SAPI rates run -10..10 around roughly 180 wpm
+            script.push_str(&format!(" $s.Rate = {};", ((rate as i32 - 180) / 20).clamp(-10, 10)));
+        }
+        script.push_str(" $s.Speak($env:LVA_TTS_TEXT)");
+        let mut powershell = Command::new("powershell");
+        powershell.args(["-NoProfile", "-Command", &script]).env("LVA_TTS_TEXT", text);
+        if let Some(voice) = voice {
+            powershell.env("LVA_TTS_VOICE", voice);
+        }
+        vec![powershell]
+    } else {
+        let mut espeak = Command::new("espeak-ng");
+        let mut spd = Command::new("spd-say");
+        spd.arg("--wait");
+        if let Some(voice) = voice {
+            espeak.args(["-v", voice]);
+            spd.args(["-y", voice]);
+        }
+        if let Some(rate) = rate_wpm {
+            espeak.args(["-s", &rate.to_string()]);
+        }
+        espeak.arg(text);
+        spd.arg(text);
+        vec![espeak, spd]
+    }
+}
+
+// Speak and wait until done, so announcements never talk over each other
+pub async fn speak(text: &str, voice: Option<&str>, rate_wpm: Option<u32>) -> Result<(), String> {
+    alerting::run_first(speech_command(text, voice, rate_wpm), "speech engine").await
+}
+
+// Background task: announce alerts from rules with a voice template
+pub async fn run(events: Arc<Mutex<EventStore>>) {
+    let mut receiver = events.lock().await.subscribe();
+    while let Some(notice) = alerting::next_notice(&mut receiver, "Speech announcements").await {
+        let config = settings::current().tts;
+        if let Some((text, voice)) = config.announcement(&notice, Local::now().time()) {
+            println!("🔊 {}", text);
+            if let Err(e) = speak(&text, voice.as_deref(), config.rate_wpm).await {
+                eprintln!("Failed to announce alert: {}", e);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveTime;
+
+    #[test]
+    fn test_announcements_follow_templates_and_quiet_hours() {
+        let rules: Vec<Rule> = serde_json::from_value(serde_json::json!([
+            { "name": "Queue", "condition": { "type": "present", "class_name": "person", "min_count": 4 } }
+        ]))
+        .unwrap();
+        let tts = TtsSettings {
+            enabled: true,
+            voice: Some("en-us".to_string()),
+            quiet_hours: Some(QuietHours { start: "22:00".to_string(), end: "07:00".to_string() }),
+            templates: vec![VoiceTemplate { rule: "Queue".to_string(), message: "Queue is forming at {camera}".to_string(), voice: None }],
+            ..Default::default()
+        };
+        assert!(tts.validate().is_ok() && tts.check_rules(&rules).is_ok());
+
+        let notice = |rule: &str| AlertNotice {
+            id: "a1".to_string(),
+            camera_id: Some("register-2".to_string()),
+            description: String::new(),
+            kind: "rule".to_string(),
+            priority: "medium".to_string(),
+            rule: Some(rule.to_string()),
+            box_count: 4,
+            incident_id: None,
+        };
+        let noon = NaiveTime::from_hms_opt(12, 0, 0).unwrap();
+        assert_eq!(
+            tts.announcement(&notice("Queue"), noon),
+            Some(("Queue is forming at register 2".to_string(), Some("en-us".to_string())))
+        );
+        assert_eq!(tts.announcement(&notice("Loitering"), noon), None);
+        assert_eq!(tts.announcement(&notice("Queue"), NaiveTime::from_hms_opt(23, 0, 0).unwrap()), None);
+
+        let unknown = TtsSettings { templates: vec![VoiceTemplate { rule: "Gone".to_string(), ..tts.templates[0].clone() }], ..tts.clone() };
+        assert!(unknown.check_rules(&rules).is_err());
+        let duplicate = TtsSettings { templates: vec![tts.templates[0].clone(), tts.templates[0].clone()], ..tts.clone() };
+        assert!(duplicate.validate().is_err());
+    }
+}
@@ -0,0 +1,201 @@
+// Verification Module - A second look before a VLM-raised high-severity alert goes out
+// Either another model checks the same frame, or the same model looks at the camera again a few seconds later;
+// an alert the second look doesn't back is still stored, but opens no incident and notifies no one
+
+use crate::alerting;
+use crate::bus::BusEvent;
+use crate::event_stream::priority_rank;
+use crate::frame_processor::{self, RegionOfInterest};
+use crate::{pipeline, settings, AppState};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+// Camera id ("" for frames sent without one) -> latest live frame, kept only for delayed verification
+static LATEST_FRAMES: Mutex<Option<HashMap<String, (Arc<str>, Instant)>>> = Mutex::new(None);
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum VerificationMode {
+    SecondModel,  // Another provider on the same frame
+    Delayed,  // The same provider on a newer frame
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct VerificationPolicy {
+    pub enabled: bool,
+    pub min_priority: String,  // Alerts at least this urgent need confirming
+    pub mode: VerificationMode,
+    pub provider: Option<String>,  // Second model; required for second_model
+    pub delay_secs: u64,  // How long delayed mode waits before looking again
+}
+
+impl Default for VerificationPolicy {
+    fn default() -> Self {
+        VerificationPolicy {
+            enabled: false,
+            min_priority: "high".to_string(),
+            mode: VerificationMode::Delayed,
+            provider: None,
+            delay_secs: 5,
+        }
+    }
+}
+
+impl VerificationPolicy {
+    pub fn validate(&self) -> Result<(), String> {
+        alerting::validate_priority(&self.min_priority)?;
+        if !(1..=300).contains(&self.delay_secs) {
+            return Err("Verification delay must be between 1 and 300 seconds".to_string());
+        }
+        if self.mode == VerificationMode::SecondModel && self.provider.as_deref().is_none_or(|p| p.trim().is_empty()) {
+            return Err("Second-model verification needs a provider".to_string());
+        }
+        Ok(())
+    }
+
+    pub fn applies_to(&self, priority: &str) -> bool {
+        match (priority_rank(priority), priority_rank(&self.min_priority)) {
+            (Some(rank), Some(min)) => self.enabled && rank <= min,
+            _ => false,
+        }
+    }
+}
+
+// Kept in the alert payload as "verification"
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Verification {
+    pub mode: VerificationMode,
+    pub provider: String,
+    pub confirmed: bool,
+    pub same_frame: bool,  // Delayed mode without a newer live frame asks again about the first one
+    pub answer: Option<String>,
+    pub error: Option<String>,  // A verifier that can't answer never holds back an alert
+}
+
+// Whether an alert was held back by its verification
+pub fn rejected(payload: &serde_json::Value) -> bool {
+    payload["verification"]["confirmed"] == serde_json::Value::Bool(false)
+}
+
+// Called for every live frame; only remembered while delayed verification is on
+pub fn remember_frame(camera_id: Option<&str>, frame_base64: &str) {
+    let policy = settings::current().verification;
+    let mut latest = LATEST_FRAMES.lock().unwrap_or_else(|e| e.into_inner());
+    if !policy.enabled || policy.mode != VerificationMode::Delayed {
+        *latest = None;
+        return;
+    }
+    latest
+        .get_or_insert_with(HashMap::new)
+        .insert(camera_id.unwrap_or("").to_string(), (frame_base64.into(), Instant::now()));
+}
+
+fn frame_since(camera_id: Option<&str>, since: Instant) -> Option<Arc<str>> {
+    let latest = LATEST_FRAMES.lock().unwrap_or_else(|e| e.into_inner());
+    let (frame, at) = latest.as_ref()?.get(camera_id.unwrap_or(""))?;
+    (*at > since).then(|| frame.clone())
+}
+
+// The JSON answer, whether the provider returned it directly or inside a Moondream result
+fn answer_data(analysis: &serde_json::Value) -> &serde_json::Value {
+    analysis.get("structured_data").filter(|d| !d.is_null()).unwrap_or(analysis)
+}
+
+// Second look at an alert's frame (or its region); None when the policy doesn't cover the alert.
+// confirms reads the answer the way the first one was read
+pub async fn verify<F>(
+    state: &AppState,
+    camera_id: Option<&str>,
+    first_provider: &str,
+    prompt: &str,
+    frame_base64: &str,
+    region: Option<&RegionOfInterest>,
+    priority: &str,
+    confirms: F,
+) -> Option<Verification>
+where
+    F: Fn(&str, &serde_json::Value) -> bool,
+{
+    let policy = settings::current().verification;
+    if !policy.applies_to(priority) {
+        return None;
+    }
+    let raised_at = Instant::now();
+    let (provider, frame, same_frame) = match policy.mode {
+        VerificationMode::SecondModel => (policy.provider.clone().unwrap_or_default(), frame_base64.to_string(), true),
+        VerificationMode::Delayed => {
+            tokio::time::sleep(Duration::from_secs(policy.delay_secs)).await;
+            match frame_since(camera_id, raised_at) {
+                Some(frame) => (first_provider.to_string(), frame.to_string(), false),
+                None => (first_provider.to_string(), frame_base64.to_string(), true),
+            }
+        }
+    };
+
+    // Alerts about one box (a person, a plate) are checked on that part of the frame
+    let frame = match region.map(|roi| frame_processor::crop_to_roi(&frame, roi)).transpose() {
+        Ok(cropped) => cropped.unwrap_or(frame),
+        Err(e) => return Some(Verification { mode: policy.mode, provider, confirmed: true, same_frame, answer: None, error: Some(e) }),
+    };
+    let start = Instant::now();
+    let result = pipeline::describe_frame(&state.moondream, &state.providers, &provider, prompt, frame).await;
+    state.bus.publish(BusEvent::analysis(&provider, start.elapsed(), result.is_ok()));
+    let (confirmed, answer, error) = match result {
+        Ok(Some((text, analysis))) => (confirms(&text, answer_data(&analysis)), Some(text), None),
+        Ok(None) => (true, None, Some(format!("Provider '{}' gives no answers", provider))),
+        Err(e) => (true, None, Some(e)),
+    };
+    println!(
+        "🔁 Second look by {} {}",
+        provider,
+        match (&error, confirmed) {
+            (Some(_), _) => "failed; alerting anyway",
+            (None, true) => "confirmed the alert",
+            (None, false) => "did not confirm the alert",
+        }
+    );
+    Some(Verification { mode: policy.mode, provider, confirmed, same_frame, answer, error })
+}
+
+// Attach a verification to an alert payload
+pub fn attach(payload: &mut serde_json::Value, verification: Option<Verification>) {
+    if let (Some(verification), Some(fields)) = (verification, payload.as_object_mut()) {
+        fields.insert("verification".to_string(), serde_json::json!(verification));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_policy_scope_and_rejection() {
+        let policy = VerificationPolicy { enabled: true, ..Default::default() };
+        assert!(policy.validate().is_ok());
+        assert!(policy.applies_to("critical") && policy.applies_to("high"));
+        assert!(!policy.applies_to("medium"));
+        assert!(!VerificationPolicy::default().applies_to("critical"));
+        assert!(VerificationPolicy { mode: VerificationMode::SecondModel, ..policy.clone() }.validate().is_err());
+
+        let mut payload = serde_json::json!({ "kind": "fire", "priority": "critical" });
+        assert!(!rejected(&payload));
+        let verification = Verification {
+            mode: VerificationMode::SecondModel,
+            provider: "moondream".to_string(),
+            confirmed: false,
+            same_frame: true,
+            answer: Some("An orange jacket on a chair".to_string()),
+            error: None,
+        };
+        attach(&mut payload, Some(verification));
+        assert!(rejected(&payload));
+
+        let moondream = serde_json::json!({ "response": "…", "structured_data": { "hazard_detected": false } });
+        assert_eq!(answer_data(&moondream)["hazard_detected"], false);
+        let llava = serde_json::json!({ "hazard_detected": true });
+        assert_eq!(answer_data(&llava)["hazard_detected"], true);
+    }
+}
@@ -0,0 +1,299 @@
+// Video Source Module - Offline analysis of recorded footage
+// Frames are sampled with the ffmpeg CLI, then run through detection and VLM escalation
+
+use crate::yolo_detector::DetectionData;
+use base64::{engine::general_purpose, Engine as _};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use tokio::process::Command;
+
+pub const VIDEO_EXTENSIONS: [&str; 6] = ["mp4", "mkv", "mov", "avi", "webm", "m4v"];
+
+// Tauri event emitted after each sampled frame
+pub const PROGRESS_EVENT: &str = "video-progress";
+
+pub const DEFAULT_SAMPLE_FPS: f32 = 1.0;
+pub const DEFAULT_MAX_FRAMES: usize = 3600;
+
+// How sampled frames are escalated to the VLM
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct VideoAnalysisOptions {
+    pub sample_fps: Option<f32>,
+    pub max_frames: Option<usize>,
+    pub provider: Option<String>,            // "llava", "moondream" or "none"
+    pub prompt: Option<String>,
+    pub min_people: Option<u32>,             // Escalate when at least this many people are visible
+    pub escalate_on_change: Option<bool>,    // Escalate when the person count changes
+    pub min_analysis_gap_secs: Option<f64>,  // Minimum video time between VLM analyses
+}
+
+impl Default for VideoAnalysisOptions {
+    fn default() -> Self {
+        VideoAnalysisOptions {
+            sample_fps: Some(DEFAULT_SAMPLE_FPS),
+            max_frames: Some(DEFAULT_MAX_FRAMES),
+            provider: Some("llava".to_string()),
+            prompt: None,
+            min_people: None,
+            escalate_on_change: Some(true),
+            min_analysis_gap_secs: Some(10.0),
+        }
+    }
+}
+
+// A frame extracted from the video
+#[derive(Debug, Clone)]
+pub struct SampledFrame {
+    pub index: usize,
+    pub offset_secs: f64,
+    pub path: PathBuf,
+}
+
+// One entry in the footage event log
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct VideoEvent {
+    pub offset_secs: f64,
+    pub timecode: String,
+    pub event_type: String,  // "detection" or "analysis"
+    pub person_count: u32,
+    pub object_counts: HashMap<String, u32>,
+    pub description: Option<String>,
+    pub analysis: Option<serde_json::Value>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct VideoProgress {
+    pub video_id: String,
+    pub processed: usize,
+    pub total: usize,
+    pub offset_secs: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct VideoAnalysisSummary {
+    pub video_id: String,
+    pub path: String,
+    pub frames_sampled: usize,
+    pub analyses_run: usize,
+    pub max_people: u32,
+    pub log_path: Option<String>,
+    pub elapsed_ms: u64,
+    pub events: Vec<VideoEvent>,
+}
+
+pub fn is_video_file(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| VIDEO_EXTENSIONS.contains(&ext.to_lowercase().as_str()))
+        .unwrap_or(false)
+}
+
+pub fn format_timecode(offset_secs: f64) -> String {
+    let total_ms = (offset_secs.max(0.0) * 1000.0).round() as u64;
+    format!(
+        "{:02}:{:02}:{:02}.{:03}",
+        total_ms / 3_600_000,
+        (total_ms / 60_000) % 60,
+        (total_ms / 1000) % 60,
+        total_ms % 1000
+    )
+}
+
+pub fn video_logs_dir() -> PathBuf {
+    let home_dir = std::env::var("HOME").unwrap_or_else(|_| "/tmp".to_string());
+    PathBuf::from(home_dir).join(".live-vision-analyzer").join("video_logs")
+}
+
+// ffmpeg from LVA_FFMPEG_PATH, or whatever is on PATH
+fn ffmpeg_command() -> Command {
+    let binary = std::env::var("LVA_FFMPEG_PATH").unwrap_or_else(|_| "ffmpeg".to_string());
+    Command::new(binary)
+}
+
+// Sample frames at `sample_fps` into `out_dir` as numbered JPEGs
+pub async fn extract_frames(
+    video_path: &Path,
+    sample_fps: f32,
+    max_frames: usize,
+    out_dir: &Path,
+) -> Result<Vec<SampledFrame>, String> {
+    if !video_path.exists() {
+        return Err(format!("Video file not found: {}", video_path.display()));
+    }
+    let sample_fps = if sample_fps > 0.0 { sample_fps } else { DEFAULT_SAMPLE_FPS };
+
+    println!("VideoSource: Sampling {} at {} fps...", video_path.display(), sample_fps);
+
+    let output = ffmpeg_command()
+        .arg("-hide_banner")
+        .arg("-loglevel").arg("error")
+        .arg("-i").arg(video_path)
+        .arg("-vf").arg(format!("fps={}", sample_fps))
+        .arg("-frames:v").arg(max_frames.to_string())
+        .arg("-q:v").arg("4")
+        .arg(out_dir.join("frame_%06d.jpg"))
+        .output()
+        .await
+        .map_err(|e| format!("Failed to run ffmpeg (is it installed? set LVA_FFMPEG_PATH): {}", e))?;
+
+    if !output.status.success() {
+        return Err(format!(
+            "ffmpeg failed to decode {}: {}",
+            video_path.display(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+
+    let mut files: Vec<PathBuf> = std::fs::read_dir(out_dir)
+        .map_err(|e| e.to_string())?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| path.extension().map(|ext| ext == "jpg").unwrap_or(false))
+        .collect();
+    files.sort();
+
+    // The fps filter emits frame N at roughly N / fps seconds
+    Ok(files
+        .into_iter()
+        .enumerate()
+        .map(|(index, path)| SampledFrame {
+            index,
+            offset_secs: index as f64 / sample_fps as f64,
+            path,
+        })
+        .collect())
+}
+
+// Grab a single frame from a live stream (RTSP, HTTP or a device ffmpeg can open) as base64 JPEG
+pub async fn grab_stream_frame(url: &str) -> Result<String, String> {
+    let mut command = ffmpeg_command();
+    command.arg("-hide_banner").arg("-loglevel").arg("error");
+    if url.starts_with("rtsp://") {
+        command.arg("-rtsp_transport").arg("tcp");
+    }
+    let output = command
+        .arg("-i").arg(url)
+        .arg("-frames:v").arg("1")
+        .arg("-q:v").arg("4")
+        .arg("-f").arg("image2pipe")
+        .arg("-vcodec").arg("mjpeg")
+        .arg("-")
+        .output()
+        .await
+        .map_err(|e| format!("Failed to run ffmpeg (is it installed? set LVA_FFMPEG_PATH): {}", e))?;
+
+    if !output.status.success() || output.stdout.is_empty() {
+        return Err(format!(
+            "ffmpeg failed to read a frame from {}: {}",
+            url,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(general_purpose::STANDARD.encode(output.stdout))
+}
+
+// Decide whether a sampled frame should be escalated to the VLM; `previous_count` is the previous stable count
+pub fn should_escalate(
+    options: &VideoAnalysisOptions,
+    detection: &DetectionData,
+    previous_count: Option<u32>,
+    last_analysis_secs: Option<f64>,
+    offset_secs: f64,
+) -> bool {
+    if options.provider.as_deref() == Some("none") {
+        return false;
+    }
+
+    let gap = options.min_analysis_gap_secs.unwrap_or(10.0);
+    if let Some(last) = last_analysis_secs {
+        if offset_secs - last < gap {
+            return false;
+        }
+    }
+    // A script asking for analysis overrides the count rules
+    if detection.escalate.is_some() {
+        return true;
+    }
+
+    let person_count = detection.stable_person_count();
+    let changed = options.escalate_on_change.unwrap_or(true) && previous_count != Some(person_count);
+    // Staff behind the counter don't make a queue
+    let crowded = options.min_people.map(|m| detection.customer_count() >= m).unwrap_or(false);
+
+    changed || crowded
+}
+
+// Write the event log as JSON Lines for later review
+pub fn write_event_log(video_id: &str, video_path: &Path, events: &[VideoEvent]) -> Result<PathBuf, String> {
+    let dir = video_logs_dir();
+    std::fs::create_dir_all(&dir).map_err(|e| format!("Failed to create log directory: {}", e))?;
+
+    let stem = video_path.file_stem().and_then(|s| s.to_str()).unwrap_or("video");
+    let log_path = dir.join(format!("{}-{}.jsonl", stem, &video_id[..8.min(video_id.len())]));
+
+    let mut lines = String::new();
+    for event in events {
+        lines.push_str(&serde_json::to_string(event).map_err(|e| e.to_string())?);
+        lines.push('\n');
+    }
+    std::fs::write(&log_path, lines).map_err(|e| format!("Failed to write event log: {}", e))?;
+
+    Ok(log_path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn detection(person_count: u32) -> DetectionData {
+        DetectionData {
+            person_count,
+            object_counts: HashMap::new(),
+            crowd_density: 0.0,
+            motion_intensity: 0.0,
+            zone_occupancy: 0.0,
+            boxes: Vec::new(),
+            smoothed_person_count: None,
+            visitors: None,
+            staff_count: None,
+            annotations: Default::default(),
+            escalate: None,
+        }
+    }
+
+    #[test]
+    fn test_timecode_formatting() {
+        assert_eq!(format_timecode(0.0), "00:00:00.000");
+        assert_eq!(format_timecode(3725.5), "01:02:05.500");
+    }
+
+    #[test]
+    fn test_video_extension_detection() {
+        assert!(is_video_file(Path::new("/footage/entrance.MP4")));
+        assert!(!is_video_file(Path::new("/footage/frame.jpg")));
+    }
+
+    #[test]
+    fn test_escalation_rules() {
+        let options = VideoAnalysisOptions::default();
+
+        // First frame always escalates, unchanged counts don't
+        assert!(should_escalate(&options, &detection(1), None, None, 0.0));
+        assert!(!should_escalate(&options, &detection(1), Some(1), Some(0.0), 30.0));
+        // Changes inside the minimum gap are suppressed
+        assert!(!should_escalate(&options, &detection(2), Some(1), Some(0.0), 5.0));
+        assert!(should_escalate(&options, &detection(2), Some(1), Some(0.0), 15.0));
+        // A one-frame blip that the smoother filtered out is not a change
+        let blip = DetectionData { smoothed_person_count: Some(1), ..detection(2) };
+        assert!(!should_escalate(&options, &blip, Some(1), Some(0.0), 15.0));
+
+        let crowd_only = VideoAnalysisOptions {
+            escalate_on_change: Some(false),
+            min_people: Some(3),
+            ..VideoAnalysisOptions::default()
+        };
+        assert!(!should_escalate(&crowd_only, &detection(2), Some(1), None, 0.0));
+        assert!(should_escalate(&crowd_only, &detection(3), Some(3), None, 0.0));
+    }
+}
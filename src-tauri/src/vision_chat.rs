@@ -0,0 +1,214 @@
+// Vision Chat Module - Conversation mode with per-session context
+// Keeps prior Q&A turns and the last analyzed frame so users can ask follow-up questions
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+// Only the most recent turns are replayed to keep the context window small
+const MAX_HISTORY_TURNS: usize = 10;
+
+const SYSTEM_PROMPT: &str = "You are a vision assistant watching a live camera feed. \
+Answer questions about the most recent frame concisely, using earlier answers in this \
+conversation as context for follow-up questions.";
+
+// One question/answer exchange
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ChatTurn {
+    pub question: String,
+    pub answer: String,
+    pub timestamp: DateTime<Utc>,
+    pub had_frame: bool,  // Whether a new frame was attached to this question
+}
+
+// A conversation about a camera view
+#[derive(Debug, Clone)]
+pub struct ChatSession {
+    pub id: String,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub turns: Vec<ChatTurn>,
+    pub last_frame: Option<String>,
+}
+
+// Lightweight session info for listing (frames are not sent back to the UI)
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ChatSessionSummary {
+    pub id: String,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub turn_count: usize,
+    pub has_frame: bool,
+    pub last_question: Option<String>,
+}
+
+// Reply returned by the vision_chat command
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ChatReply {
+    pub session_id: String,
+    pub answer: String,
+    pub turn_count: usize,
+    pub processing_time_ms: u64,
+}
+
+impl ChatSession {
+    fn new(id: String) -> Self {
+        let now = Utc::now();
+        ChatSession {
+            id,
+            created_at: now,
+            updated_at: now,
+            turns: Vec::new(),
+            last_frame: None,
+        }
+    }
+
+    fn summary(&self) -> ChatSessionSummary {
+        ChatSessionSummary {
+            id: self.id.clone(),
+            created_at: self.created_at,
+            updated_at: self.updated_at,
+            turn_count: self.turns.len(),
+            has_frame: self.last_frame.is_some(),
+            last_question: self.turns.last().map(|turn| turn.question.clone()),
+        }
+    }
+
+    // Build Ollama chat messages: system prompt, recent turns, then the new question with the frame
+    pub fn build_messages(&self, question: &str) -> Vec<serde_json::Value> {
+        let mut messages = vec![serde_json::json!({
+            "role": "system",
+            "content": SYSTEM_PROMPT
+        })];
+
+        let skip = self.turns.len().saturating_sub(MAX_HISTORY_TURNS);
+        for turn in self.turns.iter().skip(skip) {
+            messages.push(serde_json::json!({ "role": "user", "content": turn.question }));
+            messages.push(serde_json::json!({ "role": "assistant", "content": turn.answer }));
+        }
+
+        let mut question_message = serde_json::json!({ "role": "user", "content": question });
+        if let Some(frame) = &self.last_frame {
+            question_message["images"] = serde_json::json!([frame]);
+        }
+        messages.push(question_message);
+
+        messages
+    }
+}
+
+pub struct VisionChatManager {
+    sessions: HashMap<String, ChatSession>,
+}
+
+impl VisionChatManager {
+    pub fn new() -> Self {
+        VisionChatManager {
+            sessions: HashMap::new(),
+        }
+    }
+
+    pub fn get(&self, session_id: &str) -> Option<&ChatSession> {
+        self.sessions.get(session_id)
+    }
+
+    // Get an existing session or start a new one (a fresh ID is generated when none is given)
+    pub fn get_or_create(&mut self, session_id: Option<String>) -> &mut ChatSession {
+        let id = session_id.unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+        self.sessions
+            .entry(id.clone())
+            .or_insert_with(|| ChatSession::new(id))
+    }
+
+    // Record a completed turn, updating the session's frame if a new one was sent
+    pub fn record_turn(&mut self, session_id: &str, question: String, answer: String, frame: Option<String>) -> usize {
+        let session = self
+            .sessions
+            .entry(session_id.to_string())
+            .or_insert_with(|| ChatSession::new(session_id.to_string()));
+
+        let had_frame = frame.is_some();
+        if frame.is_some() {
+            session.last_frame = frame;
+        }
+
+        session.turns.push(ChatTurn {
+            question,
+            answer,
+            timestamp: Utc::now(),
+            had_frame,
+        });
+        session.updated_at = Utc::now();
+
+        session.turns.len()
+    }
+
+    pub fn list_sessions(&self) -> Vec<ChatSessionSummary> {
+        let mut summaries: Vec<ChatSessionSummary> = self.sessions.values().map(|s| s.summary()).collect();
+        summaries.sort_by_key(|s| std::cmp::Reverse(s.updated_at));
+        summaries
+    }
+
+    pub fn get_history(&self, session_id: &str) -> Option<Vec<ChatTurn>> {
+        self.sessions.get(session_id).map(|s| s.turns.clone())
+    }
+
+    pub fn clear_session(&mut self, session_id: &str) -> bool {
+        self.sessions.remove(session_id).is_some()
+    }
+
+    pub fn clear_all(&mut self) -> usize {
+        let count = self.sessions.len();
+        self.sessions.clear();
+        count
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_session_created_with_generated_id() {
+        let mut manager = VisionChatManager::new();
+        let id = manager.get_or_create(None).id.clone();
+        assert!(!id.is_empty());
+        assert_eq!(manager.list_sessions().len(), 1);
+
+        // Asking again with the same ID reuses the session
+        manager.get_or_create(Some(id.clone()));
+        assert_eq!(manager.list_sessions().len(), 1);
+        assert!(manager.get(&id).is_some_and(|s| s.last_frame.is_none()));
+        assert!(manager.get("unknown").is_none());
+        assert_eq!(manager.list_sessions().len(), 1);
+    }
+
+    #[test]
+    fn test_messages_include_history_and_frame() {
+        let mut manager = VisionChatManager::new();
+        manager.get_or_create(Some("s1".to_string()));
+        manager.record_turn("s1", "Who is at the counter?".to_string(), "A man in a red jacket.".to_string(), Some("frame".to_string()));
+
+        let session = manager.get_or_create(Some("s1".to_string()));
+        let messages = session.build_messages("What was he holding?");
+
+        // system + one prior Q&A pair + new question
+        assert_eq!(messages.len(), 4);
+        assert_eq!(messages[2]["role"], "assistant");
+        assert_eq!(messages[3]["images"][0], "frame");
+    }
+
+    #[test]
+    fn test_history_is_trimmed() {
+        let mut manager = VisionChatManager::new();
+        for i in 0..(MAX_HISTORY_TURNS + 5) {
+            manager.record_turn("s1", format!("q{}", i), format!("a{}", i), None);
+        }
+
+        let session = manager.get_or_create(Some("s1".to_string()));
+        let messages = session.build_messages("next");
+        assert_eq!(messages.len(), 1 + MAX_HISTORY_TURNS * 2 + 1);
+        assert!(manager.clear_session("s1"));
+        assert!(manager.get_history("s1").is_none());
+    }
+}
@@ -0,0 +1,429 @@
+// Vision Provider Module - Common interface for vision-language model backends
+// Analyses pick a provider by name; cloud providers are configured at runtime and persisted
+
+use crate::moondream_manager::MoondreamManager;
+use crate::ollama_manager::OllamaManager;
+use crate::encryption;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+// One analysis request, independent of the backend
+#[derive(Debug, Clone, Default)]
+pub struct VisionRequest {
+    pub prompt: String,
+    pub images: Vec<String>,  // base64 JPEG frames
+    pub timeout_ms: u64,
+    pub max_tokens: Option<u32>,
+    pub model: Option<String>,  // Overrides the provider's model; single-model providers ignore it
+    pub profile: Option<String>,  // Inference profile, for LLaVA
+}
+
+impl VisionRequest {
+    pub fn model_or<'a>(&'a self, configured: &'a str) -> &'a str {
+        self.model.as_deref().unwrap_or(configured)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct TokenUsage {
+    pub input_tokens: u32,
+    pub output_tokens: u32,
+    // Timings only Ollama reports
+    #[serde(default)]
+    pub tokens_per_second: Option<f64>,
+    #[serde(default)]
+    pub load_ms: Option<u64>,
+    #[serde(default)]
+    pub prompt_eval_ms: Option<u64>,
+    #[serde(default)]
+    pub eval_ms: Option<u64>,
+    #[serde(default)]
+    pub total_ms: Option<u64>,
+}
+
+impl TokenUsage {
+    // From an Ollama generate/chat response; its durations are in nanoseconds
+    pub fn from_ollama(body: &serde_json::Value) -> Option<TokenUsage> {
+        let output_tokens = body["eval_count"].as_u64()?;
+        let ms = |field: &str| body[field].as_u64().map(|ns| ns / 1_000_000);
+        let eval_ns = body["eval_duration"].as_u64().filter(|ns| *ns > 0);
+        Some(TokenUsage {
+            input_tokens: body["prompt_eval_count"].as_u64().unwrap_or(0) as u32,
+            output_tokens: output_tokens as u32,
+            tokens_per_second: eval_ns.map(|ns| output_tokens as f64 / (ns as f64 / 1e9)),
+            load_ms: ms("load_duration"),
+            prompt_eval_ms: ms("prompt_eval_duration"),
+            eval_ms: ms("eval_duration"),
+            total_ms: ms("total_duration"),
+        })
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct VisionResponse {
+    pub provider: String,
+    pub model: String,
+    pub text: String,
+    pub usage: Option<TokenUsage>,
+}
+
+#[async_trait]
+pub trait VisionProvider: Send + Sync {
+    fn name(&self) -> &str;
+    fn model(&self) -> &str;
+    // Compute device, for providers that run in-process
+    fn device(&self) -> Option<&str> {
+        None
+    }
+    // Remote URL requests go to, for the audit log
+    fn endpoint(&self) -> Option<&str> {
+        None
+    }
+    async fn analyze(&self, request: &VisionRequest) -> Result<VisionResponse, String>;
+}
+
+// Persisted configuration for a runtime-configured provider
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ProviderSettings {
+    pub kind: String,  // "openai", "claude", "gemini" or "local"
+    pub api_key: Option<String>,
+    pub base_url: Option<String>,
+    pub model: Option<String>,
+    pub options: Option<serde_json::Value>,
+}
+
+// What the UI sees - never includes the API key itself
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ProviderInfo {
+    pub name: String,
+    pub kind: String,
+    pub model: String,
+    pub base_url: Option<String>,
+    pub has_api_key: bool,
+    pub builtin: bool,
+}
+
+// Shared by providers that answer in free text: pull out a JSON object if there is one
+pub fn parse_text_json(text: &str) -> serde_json::Value {
+    let trimmed = text.trim().trim_start_matches("```json").trim_start_matches("```").trim_end_matches("```");
+    serde_json::from_str(trimmed.trim()).unwrap_or_else(|_| serde_json::json!({ "description": text }))
+}
+
+// Prompt suffix asking for an answer in the caller's JSON Schema
+pub fn schema_prompt(prompt: &str, schema: &serde_json::Value) -> String {
+    format!("{}\n\nRespond only with a JSON object matching this JSON Schema:\n{}", prompt, schema)
+}
+
+// Where the answer falls short of the schema: missing required fields and top-level type mismatches
+pub fn schema_errors(value: &serde_json::Value, schema: &serde_json::Value) -> Vec<String> {
+    let Some(object) = value.as_object() else {
+        return vec!["Response is not a JSON object".to_string()];
+    };
+    let mut errors: Vec<String> = schema["required"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|field| field.as_str())
+        .filter(|field| !object.contains_key(*field))
+        .map(|field| format!("Missing required field '{}'", field))
+        .collect();
+    for (field, property) in schema["properties"].as_object().into_iter().flatten() {
+        let (Some(actual), Some(expected)) = (object.get(field), property["type"].as_str()) else {
+            continue;
+        };
+        let matches = match expected {
+            "string" => actual.is_string(),
+            "number" => actual.is_number(),
+            "integer" => actual.is_i64() || actual.is_u64(),
+            "boolean" => actual.is_boolean(),
+            "array" => actual.is_array(),
+            "object" => actual.is_object(),
+            "null" => actual.is_null(),
+            _ => true,
+        };
+        if !matches {
+            errors.push(format!("Field '{}' should be {}", field, expected));
+        }
+    }
+    errors
+}
+
+// Local LLaVA through Ollama
+pub struct LlavaProvider;
+
+#[async_trait]
+impl VisionProvider for LlavaProvider {
+    fn name(&self) -> &str {
+        "llava"
+    }
+
+    // The exact tag is a user setting, reported per response
+    fn model(&self) -> &str {
+        "llava"
+    }
+
+    async fn analyze(&self, request: &VisionRequest) -> Result<VisionResponse, String> {
+        let result = OllamaManager::generate_with_model(
+            request.model.as_deref(),
+            &request.prompt,
+            request.images.clone(),
+            request.timeout_ms,
+            request.profile.as_deref(),
+        )
+        .await?;
+        Ok(VisionResponse {
+            provider: self.name().to_string(),
+            model: result["model"].as_str().unwrap_or(self.model()).to_string(),
+            text: result["response"].as_str().unwrap_or("").to_string(),
+            usage: TokenUsage::from_ollama(&result),
+        })
+    }
+}
+
+// Moondream cloud API (single image)
+pub struct MoondreamProvider {
+    manager: Arc<MoondreamManager>,
+}
+
+impl MoondreamProvider {
+    pub fn new(manager: Arc<MoondreamManager>) -> Self {
+        MoondreamProvider { manager }
+    }
+}
+
+#[async_trait]
+impl VisionProvider for MoondreamProvider {
+    fn name(&self) -> &str {
+        "moondream"
+    }
+
+    fn model(&self) -> &str {
+        "moondream-3"
+    }
+
+    async fn analyze(&self, request: &VisionRequest) -> Result<VisionResponse, String> {
+        let image = request.images.first().cloned().ok_or("No image provided")?;
+        let result = self.manager.query(image, request.prompt.clone()).await?;
+        if let Some(error) = result.error {
+            return Err(error);
+        }
+        Ok(VisionResponse {
+            provider: self.name().to_string(),
+            model: self.model().to_string(),
+            text: result.response,
+            usage: None,
+        })
+    }
+}
+
+// Build a provider from its persisted settings
+pub fn build_provider(name: &str, settings: &ProviderSettings) -> Result<Arc<dyn VisionProvider>, String> {
+    let provider: Arc<dyn VisionProvider> = match settings.kind.as_str() {
+        "openai" => Arc::new(crate::openai_provider::OpenAiProvider::from_settings(name, settings)?),
+        "claude" | "anthropic" => Arc::new(crate::claude_provider::ClaudeProvider::from_settings(name, settings)?),
+        "gemini" => Arc::new(crate::gemini_provider::GeminiProvider::from_settings(name, settings)?),
+        "local" => return Ok(Arc::new(crate::local_provider::from_settings(name, settings)?)),
+        other => return Err(format!("Unknown provider kind: {}", other)),
+    };
+    // Remote endpoints go through the provider's redaction policy
+    Ok(Arc::new(crate::privacy::PrivacyGuard::new(provider)))
+}
+
+pub struct ProviderRegistry {
+    providers: BTreeMap<String, Arc<dyn VisionProvider>>,
+    settings: BTreeMap<String, ProviderSettings>,
+    path: Option<PathBuf>,
+}
+
+impl ProviderRegistry {
+    pub fn new() -> Self {
+        ProviderRegistry {
+            providers: BTreeMap::new(),
+            settings: BTreeMap::new(),
+            path: None,
+        }
+    }
+
+    pub fn default_path() -> PathBuf {
+        let home_dir = std::env::var("HOME").unwrap_or_else(|_| "/tmp".to_string());
+        PathBuf::from(home_dir).join(".live-vision-analyzer").join("providers.json")
+    }
+
+    // Load persisted providers; entries that fail to build are logged and skipped
+    pub fn load(path: PathBuf) -> Self {
+        let mut registry = ProviderRegistry::new();
+        if let Ok(contents) = std::fs::read_to_string(&path) {
+            match serde_json::from_str::<BTreeMap<String, ProviderSettings>>(&contents) {
+                Ok(settings) => {
+                    for (name, settings) in settings {
+                        match build_provider(&name, &settings) {
+                            Ok(provider) => {
+                                registry.providers.insert(name.clone(), provider);
+                                registry.settings.insert(name, settings);
+                            }
+                            Err(e) => eprintln!("Skipping provider '{}': {}", name, e),
+                        }
+                    }
+                }
+                Err(e) => eprintln!("Failed to parse {}: {}", path.display(), e),
+            }
+        }
+        registry.path = Some(path);
+        registry
+    }
+
+    // Re-read the configured endpoints from disk; built-in providers are kept
+    pub fn reload(&mut self) {
+        let Some(path) = self.path.clone() else {
+            return;
+        };
+        let reloaded = ProviderRegistry::load(path);
+        for name in std::mem::take(&mut self.settings).into_keys() {
+            self.providers.remove(&name);
+        }
+        self.providers.extend(reloaded.providers);
+        self.settings = reloaded.settings;
+    }
+
+    pub fn register_builtin(&mut self, provider: Arc<dyn VisionProvider>) {
+        self.providers.insert(provider.name().to_string(), provider);
+    }
+
+    pub fn unregister_builtin(&mut self, name: &str) -> bool {
+        !self.settings.contains_key(name) && self.providers.remove(name).is_some()
+    }
+
+    pub fn configure(&mut self, name: &str, settings: ProviderSettings) -> Result<ProviderInfo, String> {
+        if self.providers.contains_key(name) && !self.settings.contains_key(name) {
+            return Err(format!("'{}' is a built-in provider and can't be reconfigured", name));
+        }
+
+        let provider = build_provider(name, &settings)?;
+        self.providers.insert(name.to_string(), provider);
+        self.settings.insert(name.to_string(), settings);
+        self.save()?;
+
+        self.info(name).ok_or_else(|| format!("Provider not found: {}", name))
+    }
+
+    pub fn remove(&mut self, name: &str) -> Result<bool, String> {
+        if self.settings.remove(name).is_none() {
+            return Ok(false);
+        }
+        self.providers.remove(name);
+        self.save()?;
+        Ok(true)
+    }
+
+    pub fn get(&self, name: &str) -> Option<Arc<dyn VisionProvider>> {
+        self.providers.get(name).cloned()
+    }
+
+    pub fn info(&self, name: &str) -> Option<ProviderInfo> {
+        let provider = self.providers.get(name)?;
+        let settings = self.settings.get(name);
+        Some(ProviderInfo {
+            name: name.to_string(),
+            kind: settings.map(|s| s.kind.clone()).unwrap_or_else(|| name.to_string()),
+            model: provider.model().to_string(),
+            base_url: settings.and_then(|s| s.base_url.clone()),
+            has_api_key: settings.map(|s| s.api_key.is_some()).unwrap_or(false),
+            builtin: settings.is_none(),
+        })
+    }
+
+    pub fn list(&self) -> Vec<ProviderInfo> {
+        self.providers.keys().filter_map(|name| self.info(name)).collect()
+    }
+
+    fn save(&self) -> Result<(), String> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent).map_err(|e| format!("Failed to create config directory: {}", e))?;
+        }
+        // API keys live in this file
+        let contents = serde_json::to_string_pretty(&self.settings).map_err(|e| e.to_string())?;
+        encryption::write_private(path, contents.as_bytes()).map_err(|e| format!("Failed to save provider settings: {}", e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn openai_settings() -> ProviderSettings {
+        ProviderSettings {
+            kind: "openai".to_string(),
+            api_key: Some("sk-test".to_string()),
+            base_url: None,
+            model: None,
+            options: None,
+        }
+    }
+
+    #[test]
+    fn test_registry_persists_configured_providers() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("providers.json");
+
+        let mut registry = ProviderRegistry::load(path.clone());
+        registry.register_builtin(Arc::new(LlavaProvider));
+        let info = registry.configure("gpt4o", openai_settings()).unwrap();
+        assert!(info.has_api_key);
+        assert!(registry.configure("llava", openai_settings()).is_err());
+
+        let reloaded = ProviderRegistry::load(path.clone());
+        assert!(reloaded.get("gpt4o").is_some());
+        assert!(!std::fs::read_to_string(dir.path().join("providers.json")).unwrap().is_empty());
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            assert_eq!(std::fs::metadata(&path).unwrap().permissions().mode() & 0o777, 0o600);
+        }
+    }
+
+    #[test]
+    fn test_parse_text_json_handles_fences() {
+        let parsed = parse_text_json("```json\n{\"people_count\": 2}\n```");
+        assert_eq!(parsed["people_count"], 2);
+        assert_eq!(parse_text_json("Two people.")["description"], "Two people.");
+    }
+
+    #[test]
+    fn test_schema_errors() {
+        let schema = serde_json::json!({
+            "type": "object",
+            "required": ["people_count", "summary"],
+            "properties": { "people_count": { "type": "integer" }, "summary": { "type": "string" } }
+        });
+        assert!(schema_errors(&serde_json::json!({ "people_count": 2, "summary": "Two shoppers" }), &schema).is_empty());
+        assert_eq!(
+            schema_errors(&serde_json::json!({ "people_count": "two" }), &schema),
+            vec!["Missing required field 'summary'", "Field 'people_count' should be integer"]
+        );
+        assert_eq!(schema_errors(&serde_json::json!([1, 2]), &schema).len(), 1);
+        assert!(schema_prompt("Count people", &schema).contains("\"required\""));
+    }
+
+    #[test]
+    fn test_usage_from_ollama_response() {
+        let body = serde_json::json!({
+            "response": "Two people.",
+            "prompt_eval_count": 600,
+            "eval_count": 40,
+            "eval_duration": 2_000_000_000u64,
+            "load_duration": 1_500_000_000u64,
+            "total_duration": 4_000_000_000u64
+        });
+        let usage = TokenUsage::from_ollama(&body).unwrap();
+        assert_eq!((usage.input_tokens, usage.output_tokens), (600, 40));
+        assert_eq!(usage.tokens_per_second, Some(20.0));
+        assert_eq!((usage.load_ms, usage.total_ms, usage.prompt_eval_ms), (Some(1500), Some(4000), None));
+        assert!(TokenUsage::from_ollama(&serde_json::json!({ "response": "" })).is_none());
+    }
+}
@@ -0,0 +1,77 @@
+// Warm-up Module - Loads the vision model and runs a tiny inference before the UI relies on it
+// The result is emitted as a `backend-ready` event with the measured cold-start latency
+
+use crate::frame_processor;
+use crate::ollama_manager::OllamaManager;
+use crate::settings;
+use image::{DynamicImage, Rgb, RgbImage};
+use serde::{Deserialize, Serialize};
+
+pub const READY_EVENT: &str = "backend-ready";
+
+const WARMUP_PROMPT: &str = "Reply with the single word OK.";
+const WARMUP_TIMEOUT_MS: u64 = 120_000;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct BackendReady {
+    pub backend: String,
+    pub model: String,
+    pub ready: bool,
+    pub load_ms: u64,             // Time to get the weights into memory
+    pub first_inference_ms: u64,  // Dummy inference right after loading
+    pub error: Option<String>,
+}
+
+impl BackendReady {
+    pub fn failed(model: &str, load_ms: u64, error: String) -> Self {
+        BackendReady {
+            backend: "llava".to_string(),
+            model: model.to_string(),
+            ready: false,
+            load_ms,
+            first_inference_ms: 0,
+            error: Some(error),
+        }
+    }
+}
+
+// Small grey frame - enough to exercise the vision encoder
+fn dummy_frame() -> Result<String, String> {
+    let image = RgbImage::from_pixel(64, 64, Rgb([128, 128, 128]));
+    frame_processor::encode_frame(&DynamicImage::ImageRgb8(image))
+}
+
+// Load the selected model, run one inference and report how long each step took
+pub async fn warm_up_llava() -> BackendReady {
+    let settings = settings::current();
+    let model = settings.vision_model.clone();
+    let keep_alive = settings
+        .profile(None)
+        .map(|p| p.keep_alive.clone())
+        .unwrap_or_else(|_| "5m".to_string());
+
+    let load_start = std::time::Instant::now();
+    if let Err(e) = OllamaManager::set_model_keep_alive(&model, &keep_alive).await {
+        return BackendReady::failed(&model, 0, e);
+    }
+    let load_ms = load_start.elapsed().as_millis() as u64;
+
+    let frame = match dummy_frame() {
+        Ok(frame) => frame,
+        Err(e) => return BackendReady::failed(&model, load_ms, e),
+    };
+
+    let inference_start = std::time::Instant::now();
+    if let Err(e) = OllamaManager::generate(WARMUP_PROMPT, vec![frame], WARMUP_TIMEOUT_MS, None).await {
+        return BackendReady::failed(&model, load_ms, e);
+    }
+
+    BackendReady {
+        backend: "llava".to_string(),
+        model,
+        ready: true,
+        load_ms,
+        first_inference_ms: inference_start.elapsed().as_millis() as u64,
+        error: None,
+    }
+}
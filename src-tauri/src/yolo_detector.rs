@@ -1,8 +1,12 @@
 // YOLO Detector Module - Lightweight object detection for event triggering
 // This module handles YOLO nano model for continuous detection
 
+use crate::floor::FloorPosition;
+use crate::motion::Motion;
+use crate::reid::VisitorCounts;
+use crate::staff::Role;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 
 // Detection result structure matching TypeScript interface
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -12,6 +16,30 @@ pub struct DetectionData {
     pub crowd_density: f32,  // 0.0 to 1.0
     pub motion_intensity: f32,  // 0.0 to 1.0
     pub zone_occupancy: f32,  // 0.0 to 1.0
+    #[serde(default)]
+    pub boxes: Vec<BoundingBox>,  // Raw detections, kept for dataset export
+    #[serde(default)]
+    pub smoothed_person_count: Option<u32>,  // Temporally filtered count; None for one-off frames
+    #[serde(default)]
+    pub visitors: Option<VisitorCounts>,  // Re-identification results when enabled
+    #[serde(default)]
+    pub staff_count: Option<u32>,  // People tagged as staff when staff classification is enabled
+    #[serde(default)]
+    pub annotations: BTreeMap<String, serde_json::Value>,  // Added by user scripts
+    #[serde(default)]
+    pub escalate: Option<String>,  // Why a script asked for VLM analysis of this frame
+}
+
+impl DetectionData {
+    // The count rules act on: smoothed when a smoother saw this frame
+    pub fn stable_person_count(&self) -> u32 {
+        self.smoothed_person_count.unwrap_or(self.person_count)
+    }
+
+    // Stable count without staff, for queue and conversion analytics
+    pub fn customer_count(&self) -> u32 {
+        self.stable_person_count().saturating_sub(self.staff_count.unwrap_or(0))
+    }
 }
 
 // Bounding box for detected objects
@@ -21,8 +49,41 @@ pub struct BoundingBox {
     pub y1: f32,
     pub x2: f32,
     pub y2: f32,
+    #[serde(default)]  // Human labels (corrections, ground truth) omit it
     pub confidence: f32,
     pub class_name: String,
+    #[serde(default)]
+    pub track_id: Option<u64>,  // Re-identified person, stable while they stay within the window
+    #[serde(default)]
+    pub role: Option<Role>,  // Staff or customer, when staff classification is on
+    #[serde(default)]
+    pub floor: Option<FloorPosition>,  // Position in metres on a calibrated camera
+    #[serde(default)]
+    pub motion: Option<Motion>,  // Direction and speed once the object has been seen twice
+}
+
+#[cfg(test)]
+impl BoundingBox {
+    // Untracked, unclassified box for tests
+    pub fn new(class_name: &str, x1: f32, y1: f32, x2: f32, y2: f32, confidence: f32) -> Self {
+        BoundingBox { x1, y1, x2, y2, confidence, class_name: class_name.to_string(), track_id: None, role: None, floor: None, motion: None }
+    }
+}
+
+// Which detector produced a box
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum DetectionSource {
+    Yolo,
+    Moondream,
+}
+
+// A box from any detector, in frame pixels, so consumers don't care where it came from
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Detection {
+    pub source: DetectionSource,
+    #[serde(flatten)]
+    pub bbox: BoundingBox,
 }
 
 // YOLO Detector structure
@@ -30,15 +91,28 @@ pub struct YoloDetector {
     model_loaded: bool,
     // In a real implementation, this would hold the actual YOLO model
     // For now, we'll simulate detection
+    fixture: Option<DetectionData>,  // Mock mode: returned for every frame
+}
+
+// Share of the frame covered by boxes; zone occupancy is the same measure
+pub fn crowd_density(boxes: &[BoundingBox]) -> f32 {
+    let frame_area = 640.0 * 480.0;  // Assuming 640x480 processing resolution
+    let total_area: f32 = boxes.iter().map(|b| (b.x2 - b.x1) * (b.y2 - b.y1)).sum();
+    (total_area / frame_area).min(1.0)
 }
 
 impl YoloDetector {
     pub fn new() -> Self {
         YoloDetector {
             model_loaded: false,
+            fixture: None,
         }
     }
 
+    pub fn use_fixture(&mut self, detection: DetectionData) {
+        self.fixture = Some(detection);
+    }
+
     // Initialize YOLO model
     pub async fn initialize(&mut self) -> Result<(), String> {
         println!("YoloDetector: Initializing YOLO nano model...");
@@ -59,6 +133,9 @@ impl YoloDetector {
 
     // Run detection on a frame
     pub async fn detect(&self, frame_base64: &str) -> Result<DetectionData, String> {
+        if let Some(fixture) = &self.fixture {
+            return Ok(fixture.clone());
+        }
         if !self.model_loaded {
             return Err("YOLO model not loaded".to_string());
         }
@@ -116,6 +193,10 @@ impl YoloDetector {
                 y2: 400.0,
                 confidence: 0.85 + (activity_level * 0.1),
                 class_name: "person".to_string(),
+                track_id: None,
+                role: None,
+                floor: None,
+                motion: None,
             });
 
             // Additional people based on brightness variations
@@ -127,6 +208,10 @@ impl YoloDetector {
                     y2: 420.0,
                     confidence: 0.75,
                     class_name: "person".to_string(),
+                    track_id: None,
+                    role: None,
+                    floor: None,
+                    motion: None,
                 });
             }
 
@@ -138,6 +223,10 @@ impl YoloDetector {
                     y2: 450.0,
                     confidence: 0.72,
                     class_name: "person".to_string(),
+                    track_id: None,
+                    role: None,
+                    floor: None,
+                    motion: None,
                 });
             }
         }
@@ -152,6 +241,10 @@ impl YoloDetector {
                 y2: 380.0,
                 confidence: 0.8,
                 class_name: "backpack".to_string(),
+                track_id: None,
+                role: None,
+                floor: None,
+                motion: None,
             });
         }
 
@@ -163,6 +256,10 @@ impl YoloDetector {
                 y2: 430.0,
                 confidence: 0.75,
                 class_name: "handbag".to_string(),
+                track_id: None,
+                role: None,
+                floor: None,
+                motion: None,
             });
         }
 
@@ -176,7 +273,6 @@ impl YoloDetector {
     fn process_detections(&self, detections: Vec<BoundingBox>) -> DetectionData {
         let mut object_counts: HashMap<String, u32> = HashMap::new();
         let mut person_count = 0;
-        let mut total_area = 0.0;
 
         // Count objects by class
         for detection in &detections {
@@ -185,15 +281,10 @@ impl YoloDetector {
             if detection.class_name == "person" {
                 person_count += 1;
             }
-
-            // Calculate area for density
-            let area = (detection.x2 - detection.x1) * (detection.y2 - detection.y1);
-            total_area += area;
         }
 
         // Calculate metrics
-        let frame_area = 640.0 * 480.0;  // Assuming 640x480 processing resolution
-        let crowd_density = (total_area / frame_area).min(1.0);
+        let crowd_density = crowd_density(&detections);
 
         // Motion intensity would be calculated from frame differences
         // For now, simulate based on person count
@@ -208,6 +299,12 @@ impl YoloDetector {
             crowd_density,
             motion_intensity,
             zone_occupancy,
+            boxes: detections,
+            smoothed_person_count: None,
+            visitors: None,
+            staff_count: None,
+            annotations: BTreeMap::new(),
+            escalate: None,
         }
     }
 
@@ -274,10 +371,18 @@ mod tests {
             BoundingBox {
                 x1: 100.0, y1: 100.0, x2: 150.0, y2: 150.0,
                 confidence: 0.9, class_name: "person".to_string(),
+                track_id: None,
+                role: None,
+                floor: None,
+                motion: None,
             },
             BoundingBox {
                 x1: 300.0, y1: 300.0, x2: 350.0, y2: 350.0,
                 confidence: 0.8, class_name: "person".to_string(),
+                track_id: None,
+                role: None,
+                floor: None,
+                motion: None,
             },
         ];
 
@@ -0,0 +1,274 @@
+// Zone Suggestions Module - Candidate zones proposed from where people have been, without any calibration
+// Person boxes in stored detections are binned by the point zones place them by (the box centre): people moving near the frame
+// edge suggest an entrance, several standing still at once a queue, and anyone else lingering a dwell hotspot
+
+use crate::yolo_detector::{BoundingBox, DetectionData};
+use crate::zones::{self, Zone};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, VecDeque};
+
+const CELL_PIXELS: f32 = 40.0;
+
+// Slower than this counts as standing still
+const STILL_PIXELS_PER_SEC: f32 = 15.0;
+
+// Entrances are looked for within this many cells of the frame edge
+const EDGE_CELLS: usize = 2;
+
+// People standing still in one frame before they count as a queue
+const QUEUE_MIN_PEOPLE: usize = 3;
+
+// A cluster needs this many sightings to be suggested
+const MIN_SAMPLES: u32 = 20;
+
+// Cells with at least this share of the busiest cell's sightings make up clusters
+const HOT_SHARE: f32 = 0.25;
+
+const MAX_PER_KIND: usize = 3;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SuggestionKind {
+    Entrance,
+    Queue,
+    Dwell,
+}
+
+impl SuggestionKind {
+    fn label(self) -> &'static str {
+        match self {
+            SuggestionKind::Entrance => "Entrance",
+            SuggestionKind::Queue => "Queue area",
+            SuggestionKind::Dwell => "Dwell hotspot",
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ZoneSuggestion {
+    pub kind: SuggestionKind,
+    pub zone: Zone,  // Ready to add with set_zones, without any filters yet
+    pub samples: u32,  // Sightings inside it
+    pub share: f32,  // Of all sightings of its kind
+}
+
+struct Grid {
+    cols: usize,
+    rows: usize,
+    counts: Vec<u32>,
+}
+
+impl Grid {
+    fn new(cols: usize, rows: usize) -> Self {
+        Grid { cols, rows, counts: vec![0; cols * rows] }
+    }
+
+    fn cell(&self, [x, y]: [f32; 2]) -> (usize, usize) {
+        let clamp = |v: f32, n: usize| ((v.max(0.0) / CELL_PIXELS) as usize).min(n - 1);
+        (clamp(x, self.cols), clamp(y, self.rows))
+    }
+
+    fn add(&mut self, point: [f32; 2]) {
+        let (col, row) = self.cell(point);
+        self.counts[row * self.cols + col] += 1;
+    }
+
+    fn near_edge(&self, point: [f32; 2]) -> bool {
+        let (col, row) = self.cell(point);
+        col < EDGE_CELLS || row < EDGE_CELLS || col + EDGE_CELLS >= self.cols || row + EDGE_CELLS >= self.rows
+    }
+
+    // Connected groups of busy cells, busiest first
+    fn clusters(&self) -> Vec<Vec<usize>> {
+        let busiest = self.counts.iter().copied().max().unwrap_or(0);
+        let threshold = ((busiest as f32 * HOT_SHARE).ceil() as u32).max(1);
+        let mut seen = vec![false; self.counts.len()];
+        let mut clusters = Vec::new();
+        for start in 0..self.counts.len() {
+            if seen[start] || self.counts[start] < threshold {
+                continue;
+            }
+            let mut cluster = Vec::new();
+            let mut queue = VecDeque::from([start]);
+            seen[start] = true;
+            while let Some(index) = queue.pop_front() {
+                cluster.push(index);
+                let (col, row) = (index % self.cols, index / self.cols);
+                let neighbours = [
+                    (col > 0).then(|| index - 1),
+                    (col + 1 < self.cols).then(|| index + 1),
+                    (row > 0).then(|| index - self.cols),
+                    (row + 1 < self.rows).then(|| index + self.cols),
+                ];
+                for next in neighbours.into_iter().flatten() {
+                    if !seen[next] && self.counts[next] >= threshold {
+                        seen[next] = true;
+                        queue.push_back(next);
+                    }
+                }
+            }
+            clusters.push(cluster);
+        }
+        clusters.sort_by_key(|cluster| std::cmp::Reverse(self.samples(cluster)));
+        clusters
+    }
+
+    fn samples(&self, cluster: &[usize]) -> u32 {
+        cluster.iter().map(|i| self.counts[*i]).sum()
+    }
+
+    // Convex hull of the cluster's cells
+    fn outline(&self, cluster: &[usize]) -> Vec<[f32; 2]> {
+        let corners = cluster.iter().flat_map(|index| {
+            let (x, y) = ((index % self.cols) as f32 * CELL_PIXELS, (index / self.cols) as f32 * CELL_PIXELS);
+            [[x, y], [x + CELL_PIXELS, y], [x + CELL_PIXELS, y + CELL_PIXELS], [x, y + CELL_PIXELS]]
+        });
+        convex_hull(corners.collect())
+    }
+}
+
+// Monotone chain; points come back counter-clockwise in image coordinates
+fn convex_hull(mut points: Vec<[f32; 2]>) -> Vec<[f32; 2]> {
+    points.sort_by(|a, b| a[0].total_cmp(&b[0]).then(a[1].total_cmp(&b[1])));
+    points.dedup();
+    let cross = |o: [f32; 2], a: [f32; 2], b: [f32; 2]| (a[0] - o[0]) * (b[1] - o[1]) - (a[1] - o[1]) * (b[0] - o[0]);
+    let mut hull: Vec<[f32; 2]> = Vec::new();
+    for pass in [points.clone(), points.into_iter().rev().collect()] {
+        let start = hull.len();
+        for point in pass {
+            while hull.len() >= start + 2 && cross(hull[hull.len() - 2], hull[hull.len() - 1], point) <= 0.0 {
+                hull.pop();
+            }
+            hull.push(point);
+        }
+        hull.pop();
+    }
+    hull
+}
+
+// Suggestions for a grid's busiest clusters, with the cells they cover
+fn clustered(kind: SuggestionKind, grid: &Grid, camera_id: Option<&str>) -> (Vec<ZoneSuggestion>, Vec<usize>) {
+    let total: u32 = grid.counts.iter().sum();
+    let clusters = grid.clusters().into_iter().filter(|c| grid.samples(c) >= MIN_SAMPLES).take(MAX_PER_KIND);
+    let (mut suggestions, mut cells) = (Vec::new(), Vec::new());
+    for (number, cluster) in clusters.enumerate() {
+        let samples = grid.samples(&cluster);
+        suggestions.push(ZoneSuggestion {
+            kind,
+            zone: Zone {
+                name: format!("{} {}", kind.label(), number + 1),
+                camera_id: camera_id.map(str::to_string),
+                points: grid.outline(&cluster),
+                ignore_classes: Vec::new(),
+                only_classes: Vec::new(),
+                min_confidence: BTreeMap::new(),
+                hours: None,
+            },
+            samples,
+            share: samples as f32 / total as f32,
+        });
+        cells.extend(cluster);
+    }
+    (suggestions, cells)
+}
+
+// Entrance, queue and dwell zones for a camera from its stored detections
+pub fn suggest(camera_id: Option<&str>, detections: &[DetectionData]) -> Vec<ZoneSuggestion> {
+    // Frame size as far as any box has reached
+    let all_boxes = detections.iter().flat_map(|d| d.boxes.iter());
+    let (width, height) = all_boxes.fold((0.0f32, 0.0f32), |(w, h), b| (w.max(b.x2), h.max(b.y2)));
+    if width <= 0.0 || height <= 0.0 {
+        return Vec::new();
+    }
+    let (cols, rows) = ((width / CELL_PIXELS).ceil() as usize, (height / CELL_PIXELS).ceil() as usize);
+    let (mut entrance, mut queue, mut dwell) = (Grid::new(cols, rows), Grid::new(cols, rows), Grid::new(cols, rows));
+
+    let mut standing = Vec::new();
+    for detection in detections {
+        let (mut moving, mut still) = (Vec::new(), Vec::new());
+        for person in detection.boxes.iter().filter(|b| b.class_name == "person") {
+            match &person.motion {
+                Some(motion) if motion.pixels_per_sec >= STILL_PIXELS_PER_SEC => moving.push(zones::anchor(person)),
+                Some(_) => still.push(zones::anchor(person)),
+                None => {}
+            }
+        }
+        for point in moving {
+            if entrance.near_edge(point) {
+                entrance.add(point);
+            }
+        }
+        if still.len() >= QUEUE_MIN_PEOPLE {
+            still.iter().for_each(|p| queue.add(*p));
+        }
+        standing.extend(still);
+    }
+
+    let (mut suggestions, _) = clustered(SuggestionKind::Entrance, &entrance, camera_id);
+    let (queues, queue_cells) = clustered(SuggestionKind::Queue, &queue, camera_id);
+    // Standing in a queue isn't lingering
+    for point in standing {
+        let (col, row) = dwell.cell(point);
+        if !queue_cells.contains(&(row * cols + col)) {
+            dwell.add(point);
+        }
+    }
+    suggestions.extend(queues);
+    suggestions.extend(clustered(SuggestionKind::Dwell, &dwell, camera_id).0);
+    suggestions
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::motion::Motion;
+
+    fn person(x: f32, y2: f32, pixels_per_sec: f32) -> BoundingBox {
+        let motion = Motion { track_id: 1, direction: [1.0, 0.0], heading_degrees: 0.0, pixels_per_sec, speed_mps: None };
+        BoundingBox { motion: Some(motion), ..BoundingBox::new("person", x - 20.0, y2 - 120.0, x + 20.0, y2, 0.9) }
+    }
+
+    fn frame(mut boxes: Vec<BoundingBox>) -> DetectionData {
+        // A parked car in the bottom-right corner gives the frame its 640x480 extent
+        boxes.push(BoundingBox::new("car", 600.0, 440.0, 640.0, 480.0, 0.9));
+        serde_json::from_value(serde_json::json!({
+            "person_count": 0, "object_counts": {}, "crowd_density": 0, "motion_intensity": 0, "zone_occupancy": 0, "boxes": boxes
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_suggests_entrance_queue_and_dwell() {
+        let entering = person(20.0, 320.0, 80.0);
+        let queueing = [200.0, 240.0, 280.0, 320.0].map(|y| person(300.0, y, 5.0));
+        let lingering = person(500.0, 120.0, 2.0);
+        // Someone walking through the middle is none of these
+        let passing = person(420.0, 300.0, 60.0);
+        let mut detections = Vec::new();
+        detections.extend((0..30).map(|_| frame(vec![entering.clone()])));
+        detections.extend((0..10).map(|_| frame(queueing.to_vec())));
+        detections.extend((0..25).map(|_| frame(vec![lingering.clone()])));
+        detections.extend((0..5).map(|_| frame(vec![passing.clone()])));
+
+        let suggestions = suggest(Some("lobby"), &detections);
+        let kinds: Vec<SuggestionKind> = suggestions.iter().map(|s| s.kind).collect();
+        assert_eq!(kinds, vec![SuggestionKind::Entrance, SuggestionKind::Queue, SuggestionKind::Dwell]);
+        assert_eq!(suggestions.iter().map(|s| s.samples).collect::<Vec<_>>(), vec![30, 40, 25]);
+        assert_eq!(suggestions[1].zone.name, "Queue area 1");
+        // Each suggested zone holds the very person boxes it was suggested from, and no others
+        let (entrance, queue, dwell) = (&suggestions[0].zone, &suggestions[1].zone, &suggestions[2].zone);
+        assert!(entrance.contains(&entering) && !queue.contains(&entering) && !dwell.contains(&entering));
+        assert!(queueing.iter().all(|p| queue.contains(p) && !entrance.contains(p) && !dwell.contains(p)));
+        assert!(dwell.contains(&lingering) && !queue.contains(&lingering) && !entrance.contains(&lingering));
+        assert!(suggestions.iter().all(|s| !s.zone.contains(&passing)));
+        assert!(crate::zones::validate(&suggestions.into_iter().map(|s| s.zone).collect::<Vec<_>>()).is_ok());
+        assert!(suggest(None, &[]).is_empty());
+    }
+
+    #[test]
+    fn test_convex_hull() {
+        let hull = convex_hull(vec![[0.0, 0.0], [2.0, 0.0], [1.0, 1.0], [2.0, 2.0], [0.0, 2.0], [2.0, 0.0]]);
+        assert_eq!(hull.len(), 4);
+        assert!(!hull.contains(&[1.0, 1.0]));
+    }
+}
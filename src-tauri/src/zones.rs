@@ -0,0 +1,325 @@
+// Zones Module - Named areas of a camera's image with their own class filters and thresholds
+// Inside a zone, boxes of ignored classes, of classes off its allow list or below its confidence thresholds are dropped
+// before anything else sees the frame, so counts, tracking and rules only ever see what the zone lets through.
+// Rule conditions and PPE reports refer to zones by name; a zone without filters is just a named area
+
+use crate::yolo_detector::{self, BoundingBox, DetectionData};
+use chrono::NaiveTime;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+fn parse_time(time: &str) -> Result<NaiveTime, String> {
+    NaiveTime::parse_from_str(time, "%H:%M").map_err(|_| format!("Invalid zone time '{}' (use HH:MM)", time))
+}
+
+// Local times a zone's filters apply, e.g. 20:00-07:00 for after hours
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ActiveHours {
+    pub start: String,
+    pub end: String,
+}
+
+impl ActiveHours {
+    fn contains(&self, time: NaiveTime) -> bool {
+        match (parse_time(&self.start), parse_time(&self.end)) {
+            (Ok(start), Ok(end)) if start <= end => time >= start && time < end,
+            (Ok(start), Ok(end)) => time >= start || time < end,
+            _ => false,
+        }
+    }
+}
+
+// The point a box is placed in zones by, its centre; zone suggestions bin boxes by the same point
+pub fn anchor(bbox: &BoundingBox) -> [f32; 2] {
+    [(bbox.x1 + bbox.x2) / 2.0, (bbox.y1 + bbox.y2) / 2.0]
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Zone {
+    pub name: String,
+    #[serde(default)]
+    pub camera_id: Option<String>,  // None applies the zone to every camera
+    pub points: Vec<[f32; 2]>,  // Polygon in frame pixels; a box is inside by its centre
+    #[serde(default)]
+    pub ignore_classes: Vec<String>,  // e.g. "car" in the lobby
+    #[serde(default)]
+    pub only_classes: Vec<String>,  // When set, every other class is dropped
+    #[serde(default)]
+    pub min_confidence: BTreeMap<String, f32>,  // Class -> threshold within the zone
+    #[serde(default)]
+    pub hours: Option<ActiveHours>,  // Filters apply only then; always when unset
+}
+
+impl Zone {
+    fn validate(&self) -> Result<(), String> {
+        if self.name.trim().is_empty() {
+            return Err("Zones need a name".to_string());
+        }
+        if self.points.len() < 3 {
+            return Err(format!("Zone '{}' needs at least 3 points", self.name));
+        }
+        if let Some(class_name) = self.ignore_classes.iter().find(|c| self.only_classes.iter().any(|o| o.eq_ignore_ascii_case(c))) {
+            return Err(format!("Zone '{}' both ignores and only keeps '{}'", self.name, class_name));
+        }
+        if let Some((class_name, _)) = self.min_confidence.iter().find(|(_, min)| !(**min > 0.0 && **min <= 1.0)) {
+            return Err(format!("Zone '{}': confidence for '{}' must be greater than 0 and at most 1", self.name, class_name));
+        }
+        if let Some(hours) = &self.hours {
+            if parse_time(&hours.start)? == parse_time(&hours.end)? {
+                return Err(format!("Zone '{}' needs different start and end times", self.name));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn applies_to(&self, camera_id: Option<&str>) -> bool {
+        self.camera_id.is_none() || self.camera_id.as_deref() == camera_id
+    }
+
+    // Even-odd test of the box's anchor
+    pub fn contains(&self, bbox: &BoundingBox) -> bool {
+        let [x, y] = anchor(bbox);
+        let edges = self.points.iter().zip(self.points.iter().cycle().skip(1));
+        edges
+            .filter(|([x1, y1], [x2, y2])| (*y1 <= y) != (*y2 <= y) && x < x1 + (y - y1) * (x2 - x1) / (y2 - y1))
+            .count()
+            % 2
+            == 1
+    }
+
+    fn keeps(&self, bbox: &BoundingBox, time: NaiveTime) -> bool {
+        if self.hours.as_ref().is_some_and(|h| !h.contains(time)) {
+            return true;
+        }
+        let is = |class_name: &String| class_name.eq_ignore_ascii_case(&bbox.class_name);
+        if self.ignore_classes.iter().any(is) {
+            return false;
+        }
+        if !self.only_classes.is_empty() && !self.only_classes.iter().any(is) {
+            return false;
+        }
+        let threshold = self.min_confidence.iter().find(|(class_name, _)| is(class_name));
+        threshold.is_none_or(|(_, min)| bbox.confidence >= *min)
+    }
+}
+
+pub fn validate(zones: &[Zone]) -> Result<(), String> {
+    for (index, zone) in zones.iter().enumerate() {
+        zone.validate()?;
+        if zones[..index].iter().any(|z| z.name == zone.name && z.camera_id == zone.camera_id) {
+            return Err(format!("Duplicate zone name '{}'", zone.name));
+        }
+    }
+    Ok(())
+}
+
+// First zone on this camera containing the box centre
+pub fn zone_for<'a>(zones: &'a [Zone], camera_id: Option<&str>, bbox: &BoundingBox) -> Option<&'a Zone> {
+    zones.iter().find(|z| z.applies_to(camera_id) && z.contains(bbox))
+}
+
+// Rectangles from before areas were shared zones: rule condition areas and PPE zones become zones, referenced by name.
+// Works on the raw settings JSON (or an export's settings) and the config profiles in it, before they are parsed
+pub fn adopt_legacy_areas(settings: &mut serde_json::Value) {
+    adopt_areas(settings);
+    if let Some(profiles) = settings.get_mut("config_profiles").and_then(|p| p.as_object_mut()) {
+        profiles.values_mut().for_each(adopt_areas);
+    }
+}
+
+fn rectangle(area: &serde_json::Value) -> Option<Vec<[f32; 2]>> {
+    let [x1, y1, x2, y2] = ["x1", "y1", "x2", "y2"].map(|k| area[k].as_f64().map(|v| v as f32));
+    let (x1, y1, x2, y2) = (x1?, y1?, x2?, y2?);
+    Some(vec![[x1, y1], [x2, y1], [x2, y2], [x1, y2]])
+}
+
+// Name of the zone with these points on this camera, added under a free name when there is none yet
+fn adopt(zones: &mut Vec<Zone>, name: &str, camera_id: Option<String>, points: Vec<[f32; 2]>) -> String {
+    if let Some(zone) = zones.iter().find(|z| z.camera_id == camera_id && z.points == points) {
+        return zone.name.clone();
+    }
+    let taken = |candidate: &str| zones.iter().any(|z| z.name == candidate && z.camera_id == camera_id);
+    let name = (1..).map(|n| if n == 1 { name.to_string() } else { format!("{} {}", name, n) }).find(|n| !taken(n)).unwrap_or_default();
+    zones.push(Zone {
+        name: name.clone(),
+        camera_id,
+        points,
+        ignore_classes: Vec::new(),
+        only_classes: Vec::new(),
+        min_confidence: BTreeMap::new(),
+        hours: None,
+    });
+    name
+}
+
+fn adopt_condition(condition: &mut serde_json::Value, rule: &str, camera_id: &Option<String>, zones: &mut Vec<Zone>) {
+    let Some(fields) = condition.as_object_mut() else {
+        return;
+    };
+    if let Some(points) = fields.remove("area").as_ref().and_then(rectangle) {
+        let name = adopt(zones, &format!("{} area", rule), camera_id.clone(), points);
+        fields.insert("zone".to_string(), serde_json::json!(name));
+    }
+    if let Some(children) = fields.get_mut("conditions").and_then(|c| c.as_array_mut()) {
+        children.iter_mut().for_each(|c| adopt_condition(c, rule, camera_id, zones));
+    }
+    if let Some(child) = fields.get_mut("condition") {
+        adopt_condition(child, rule, camera_id, zones);
+    }
+}
+
+fn adopt_areas(settings: &mut serde_json::Value) {
+    let Some(fields) = settings.as_object_mut() else {
+        return;
+    };
+    // Zones that don't parse are left for the settings parser to report
+    let mut zones: Vec<Zone> = match fields.get("zones").map(|z| serde_json::from_value(z.clone())) {
+        Some(Ok(zones)) => zones,
+        Some(Err(_)) => return,
+        None => Vec::new(),
+    };
+    let before = zones.clone();
+
+    let ppe_zones = fields.get_mut("ppe").and_then(|p| p.as_object_mut()).and_then(|p| p.remove("zones"));
+    for zone in ppe_zones.as_ref().and_then(|z| z.as_array()).into_iter().flatten() {
+        if let (Some(name), Some(points)) = (zone["name"].as_str(), rectangle(zone)) {
+            adopt(&mut zones, name, zone["camera_id"].as_str().map(str::to_string), points);
+        }
+    }
+    for rule in fields.get_mut("rules").and_then(|r| r.as_array_mut()).into_iter().flatten() {
+        let name = rule["name"].as_str().unwrap_or("Rule").to_string();
+        let camera_id = rule["camera_id"].as_str().map(str::to_string);
+        if let Some(condition) = rule.get_mut("condition") {
+            adopt_condition(condition, &name, &camera_id, &mut zones);
+        }
+    }
+    if zones != before {
+        fields.insert("zones".to_string(), serde_json::json!(zones));
+    }
+}
+
+// Drop boxes their zones filter out and return how many; overlapping zones must all keep a box
+pub fn apply(zones: &[Zone], camera_id: Option<&str>, detection: &mut DetectionData, time: NaiveTime) -> usize {
+    let zones: Vec<&Zone> = zones.iter().filter(|z| z.applies_to(camera_id)).collect();
+    if zones.is_empty() {
+        return 0;
+    }
+    let (kept, dropped): (Vec<BoundingBox>, Vec<BoundingBox>) = std::mem::take(&mut detection.boxes)
+        .into_iter()
+        .partition(|bbox| zones.iter().all(|z| !z.contains(bbox) || z.keeps(bbox, time)));
+    detection.boxes = kept;
+    if dropped.is_empty() {
+        return 0;
+    }
+    detection.crowd_density = yolo_detector::crowd_density(&detection.boxes);
+    detection.zone_occupancy = detection.crowd_density;
+
+    for bbox in &dropped {
+        if bbox.class_name == "person" {
+            detection.person_count = detection.person_count.saturating_sub(1);
+        }
+        if let Some(count) = detection.object_counts.get_mut(&bbox.class_name) {
+            *count = count.saturating_sub(1);
+            if *count == 0 {
+                detection.object_counts.remove(&bbox.class_name);
+            }
+        }
+    }
+    dropped.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bbox(class_name: &str, x: f32, confidence: f32) -> BoundingBox {
+        BoundingBox::new(class_name, x, 100.0, x + 40.0, 140.0, confidence)
+    }
+
+    fn zones() -> Vec<Zone> {
+        serde_json::from_value(serde_json::json!([
+            { "name": "lobby", "points": [[0, 0], [300, 0], [300, 300], [0, 300]], "ignore_classes": ["car"], "min_confidence": { "person": 0.6 } },
+            {
+                "name": "stockroom", "camera_id": "back", "points": [[400, 0], [700, 0], [550, 300]],
+                "only_classes": ["person"], "hours": { "start": "20:00", "end": "07:00" }
+            }
+        ]))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_zone_filters() {
+        let zones = zones();
+        assert!(validate(&zones).is_ok());
+        let mut detection: DetectionData = serde_json::from_value(serde_json::json!({
+            "person_count": 3, "object_counts": { "person": 3, "car": 1, "forklift": 1 },
+            "crowd_density": 0, "motion_intensity": 0, "zone_occupancy": 0
+        }))
+        .unwrap();
+        detection.boxes = vec![
+            bbox("car", 50.0, 0.9),
+            bbox("person", 100.0, 0.5),
+            bbox("person", 150.0, 0.8),
+            bbox("forklift", 530.0, 0.9),
+            bbox("person", 520.0, 0.9),
+        ];
+
+        let night = NaiveTime::from_hms_opt(23, 0, 0).unwrap();
+        let mut at_night = detection.clone();
+        assert_eq!(apply(&zones, Some("back"), &mut at_night, night), 3);
+        assert_eq!(at_night.boxes.iter().map(|b| b.x1).collect::<Vec<_>>(), vec![150.0, 520.0]);
+        assert_eq!((at_night.person_count, at_night.object_counts.len()), (2, 1));
+        // Two 40x40 boxes are left of the 640x480 frame
+        assert!((at_night.crowd_density - 3200.0 / 307200.0).abs() < 1e-6);
+        assert_eq!(at_night.zone_occupancy, at_night.crowd_density);
+
+        // During the day the stockroom lets everything through, and other cameras never see it
+        let noon = NaiveTime::from_hms_opt(12, 0, 0).unwrap();
+        assert_eq!(apply(&zones, Some("back"), &mut detection.clone(), noon), 2);
+        assert_eq!(apply(&zones, Some("front"), &mut detection.clone(), night), 2);
+        assert_eq!(zone_for(&zones, Some("back"), &bbox("forklift", 530.0, 0.9)).map(|z| z.name.as_str()), Some("stockroom"));
+    }
+
+    #[test]
+    fn test_adopts_legacy_areas() {
+        let mut settings = serde_json::json!({
+            "ppe": { "enabled": true, "zones": [{ "name": "loading bay", "camera_id": "dock", "x1": 0, "y1": 0, "x2": 320, "y2": 480 }] },
+            "rules": [{
+                "name": "Dock at night",
+                "camera_id": "dock",
+                "condition": { "type": "all", "conditions": [
+                    { "type": "present", "class_name": "person", "area": { "x1": 0, "y1": 0, "x2": 320, "y2": 480 } },
+                    { "type": "not", "condition": { "type": "present", "class_name": "car", "area": { "x1": 400, "y1": 0, "x2": 640, "y2": 200 } } }
+                ]}
+            }]
+        });
+        adopt_legacy_areas(&mut settings);
+
+        let zones: Vec<Zone> = serde_json::from_value(settings["zones"].clone()).unwrap();
+        assert_eq!(zones.iter().map(|z| z.name.as_str()).collect::<Vec<_>>(), vec!["loading bay", "Dock at night area"]);
+        assert!(validate(&zones).is_ok());
+        assert!(settings["ppe"].get("zones").is_none());
+        let conditions = &settings["rules"][0]["condition"]["conditions"];
+        assert_eq!(conditions[0]["zone"], "loading bay");
+        assert_eq!(conditions[1]["condition"]["zone"], "Dock at night area");
+        assert!(conditions[0].get("area").is_none());
+
+        // Already adopted settings are left as they are
+        let adopted = settings.clone();
+        adopt_legacy_areas(&mut settings);
+        assert_eq!(settings, adopted);
+    }
+
+    #[test]
+    fn test_zone_validation() {
+        let mut zones = zones();
+        zones[0].only_classes = vec!["Car".to_string()];
+        assert!(validate(&zones).is_err());
+        let mut zones = self::zones();
+        zones.push(zones[0].clone());
+        assert!(validate(&zones).is_err());
+        let mut zones = self::zones();
+        zones[1].points.pop();
+        assert!(validate(&zones).is_err());
+    }
+}
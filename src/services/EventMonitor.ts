@@ -55,6 +55,10 @@ class PriorityQueue<T> {
     return this.items.shift()?.element;
   }
 
+  clear(): void {
+    this.items = [];
+  }
+
   isEmpty(): boolean {
     return this.items.length === 0;
   }
@@ -72,9 +76,10 @@ class PriorityQueue<T> {
 export class EventMonitor {
   // Core components
   private contextEngine: ContextInferenceEngine;
-  private yoloInterval: NodeJS.Timer | null = null;
+  private yoloInterval: ReturnType<typeof setTimeout> | null = null;
   private eventQueue: PriorityQueue<QueuedAutonomousEvent> = new PriorityQueue();
   private isProcessing: boolean = false;
+  private pausedByBackend: boolean = false;  // Stopped by pause_analysis, restart on resume
 
   // Frame management
   private frameBuffer: string[] = [];  // Stores last N frames
@@ -112,6 +117,8 @@ export class EventMonitor {
     // Initialize default camera
     this.initializeDefaultCamera();
 
+    this.listenForAnalysisState();
+
     console.log('🧠 Autonomous EventMonitor initialized - Zero configuration mode');
   }
 
@@ -219,11 +226,17 @@ export class EventMonitor {
     console.log('🎯 Running first detection immediately...');
     await this.runAutonomousDetection();
 
-    // Then start the interval
-    this.yoloInterval = setInterval(async () => {
-      console.log('🔄 Interval tick - running detection');
-      await this.runAutonomousDetection();
-    }, intervalMs);
+    // Then keep going at the pace the backend recommends for the current activity and CPU load
+    const scheduleNext = async () => {
+      if (!this.monitoringState.active) return;
+      const delay = await this.nextFrameDelay(intervalMs);
+      if (!this.monitoringState.active) return;
+      this.yoloInterval = setTimeout(async () => {
+        await this.runAutonomousDetection();
+        scheduleNext();
+      }, delay);
+    };
+    scheduleNext();
 
     // Start processing queue
     this.processEventQueue();
@@ -234,8 +247,9 @@ export class EventMonitor {
 
   // Stop monitoring
   public stopMonitoring(): void {
+    this.pausedByBackend = false;
     if (this.yoloInterval) {
-      clearInterval(this.yoloInterval as unknown as number);
+      clearTimeout(this.yoloInterval);
       this.yoloInterval = null;
     }
 
@@ -255,6 +269,38 @@ export class EventMonitor {
     console.log(`🎯 Accuracy reached: ${intelligence.confidence_level * 100}%`);
   }
 
+  // Follow pause_analysis / resume_analysis from anywhere in the app
+  private listenForAnalysisState(): void {
+    const listen = (window as any).__TAURI__?.event?.listen;
+    if (!listen) return;
+    listen('analysis-state', (event: { payload: { paused: boolean; reason?: string } }) => {
+      if (event.payload.paused && this.monitoringState.active) {
+        console.log(`⏸️ Analysis paused: ${event.payload.reason}`);
+        this.eventQueue.clear();
+        this.monitoringState.analysis_queue = [];
+        this.stopMonitoring();
+        this.pausedByBackend = true;
+      } else if (!event.payload.paused && this.pausedByBackend) {
+        console.log('▶️ Analysis resumed');
+        this.pausedByBackend = false;
+        this.startMonitoring().catch((error) => console.error('EventMonitor: resume failed:', error));
+      }
+    }).catch((error: unknown) => console.error('EventMonitor: analysis state unavailable:', error));
+  }
+
+  // Adaptive frame interval from the backend, falling back to the fixed rate
+  private async nextFrameDelay(fallbackMs: number): Promise<number> {
+    try {
+      const invoke = (window as any).__TAURI__?.core?.invoke;
+      if (!invoke) return fallbackMs;
+      const pacing = await invoke('get_frame_interval', {});
+      return (pacing as { interval_ms: number }).interval_ms;
+    } catch (error) {
+      console.error('EventMonitor: frame pacing unavailable:', error);
+      return fallbackMs;
+    }
+  }
+
   // Run autonomous detection cycle (no zones, full frame)
   private async runAutonomousDetection(): Promise<void> {
     console.log('🎯 runAutonomousDetection called');
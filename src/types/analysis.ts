@@ -25,6 +25,9 @@ export type UrgencyLevel = 1 | 2 | 3 | 4 | 5;
 // Detection data from YOLO model
 export interface DetectionData {
   person_count: number;                    // Number of people detected
+  smoothed_person_count?: number;          // Person count smoothed over recent frames
+  visitors?: { new: number; returning: number; unique: number; new_staff: number };  // Re-identification counts
+  staff_count?: number;                    // People tagged as staff
   object_counts: Record<string, number>;   // Count of each object type
   crowd_density: number;                   // 0-1 percentage of area filled
   motion_intensity: number;                // 0-1 scale of movement